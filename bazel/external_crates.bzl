@@ -499,6 +499,7 @@ def external_crates_repository(name, annotations):
                 features = [
                     "bulk",
                     "multi_value",
+                    "simd",
                     "std",
                 ],
             ),
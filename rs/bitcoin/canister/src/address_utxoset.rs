@@ -110,7 +110,7 @@ impl<'a> AddressUtxoSet<'a> {
         let mut set: BTreeSet<_> = self
             .full_utxo_set
             .address_to_outpoints
-            .range(self.address.to_bytes(), offset.map(|x| x.to_bytes()))
+            .range_with_prefix(self.address.to_bytes(), offset.map(|x| x.to_bytes()))
             .map(|(k, _)| {
                 let (_, _, outpoint) = <(AddressStr, Height, OutPoint)>::from_bytes(k);
                 let (txout, height) = self
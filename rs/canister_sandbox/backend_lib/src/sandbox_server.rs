@@ -146,11 +146,12 @@ mod tests {
     };
     use ic_config::subnet_config::CyclesAccountManagerConfig;
     use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
-    use ic_cycles_account_manager::CyclesAccountManager;
+    use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
     use ic_interfaces::execution_environment::{AvailableMemory, ExecutionMode, HypervisorError};
     use ic_logger::replica_logger::no_op_logger;
+    use ic_registry_routing_table::CanisterIdRanges;
     use ic_registry_subnet_type::SubnetType;
-    use ic_replicated_state::{Global, NumWasmPages, PageIndex, PageMap};
+    use ic_replicated_state::{CanisterTimer, Global, NumWasmPages, PageIndex, PageMap};
     use ic_system_api::{
         sandbox_safe_system_state::{CanisterStatusView, SandboxSafeSystemState},
         ApiType, ExecutionParameters, InstructionLimits,
@@ -180,6 +181,7 @@ mod tests {
             compute_allocation: ComputeAllocation::default(),
             subnet_type: SubnetType::Application,
             execution_mode: ExecutionMode::Replicated,
+            subnet_memory_saturation: ResourceSaturation::default(),
         }
     }
 
@@ -189,10 +191,14 @@ mod tests {
         SandboxSafeSystemState::new_internal(
             canister_test_id(0),
             user_test_id(0).get(),
+            BTreeSet::from([user_test_id(0).get()]),
             CanisterStatusView::Running,
+            0,
             NumSeconds::from(3600),
             MemoryAllocation::BestEffort,
             Cycles::new(1_000_000),
+            Cycles::zero(),
+            None,
             BTreeMap::new(),
             CyclesAccountManager::new(
                 NumInstructions::from(1_000_000_000),
@@ -204,6 +210,9 @@ mod tests {
             BTreeMap::new(),
             0,
             ic00_aliases,
+            CanisterIdRanges::default(),
+            CanisterTimer::Inactive,
+            None,
         )
     }
 
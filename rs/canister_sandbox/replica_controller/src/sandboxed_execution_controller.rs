@@ -505,6 +505,7 @@ impl WasmExecutor for SandboxedExecutionController {
                                 accessed_pages: 0,
                                 dirty_pages: 0,
                             },
+                            instruction_profile: None,
                         },
                         SystemStateChanges::default(),
                     ),
@@ -25,6 +25,11 @@ pub enum CertificationVersion {
     V8 = 8,
     /// Producing non-empty `StreamHeader::reject_signals`.
     V9 = 9,
+    /// Added canister history.
+    V10 = 10,
+    /// Added optional `Request::deadline` and `Response::deadline` fields,
+    /// only encoded for best-effort messages.
+    V11 = 11,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -57,13 +62,13 @@ impl std::convert::TryFrom<u32> for CertificationVersion {
 
 /// The Canonical State certification version that should be used for newly
 /// computed states.
-pub const CURRENT_CERTIFICATION_VERSION: CertificationVersion = CertificationVersion::V9;
+pub const CURRENT_CERTIFICATION_VERSION: CertificationVersion = CertificationVersion::V10;
 
 /// Maximum supported certification version.
 ///
 /// The replica will panic if requested to certify using a version higher than
 /// this.
-pub const MAX_SUPPORTED_CERTIFICATION_VERSION: CertificationVersion = CertificationVersion::V9;
+pub const MAX_SUPPORTED_CERTIFICATION_VERSION: CertificationVersion = CertificationVersion::V11;
 
 /// Returns a list of all certification versions up to [MAX_SUPPORTED_CERTIFICATION_VERSION].
 pub fn all_supported_versions() -> impl std::iter::Iterator<Item = CertificationVersion> {
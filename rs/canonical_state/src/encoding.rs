@@ -11,6 +11,7 @@
 //! covered by compatibility tests.
 
 use crate::CertificationVersion;
+use ic_ic00_types::CanisterChange;
 use ic_protobuf::proxy::ProxyDecodeError;
 use ic_replicated_state::metadata_state::SystemMetadata;
 use ic_types::{messages::RequestOrResponse, xnet::StreamHeader, PrincipalId};
@@ -127,3 +128,15 @@ pub fn encode_controllers(controllers: &BTreeSet<PrincipalId>) -> Vec<u8> {
     controllers.serialize(&mut serializer).unwrap();
     serializer.into_inner()
 }
+
+/// Serializes a change from a canister's history as a CBOR map.
+///
+/// Like `encode_controllers`, the value is a CBOR data item with major type 6
+/// ("Semantic tag") and tag value `55799`, followed by the change using the
+/// field and variant names of the `canister_info` Candid interface.
+pub fn encode_canister_change(change: &CanisterChange) -> Vec<u8> {
+    let mut serializer = serde_cbor::ser::Serializer::new(vec![]);
+    serializer.self_describe().unwrap();
+    change.serialize(&mut serializer).unwrap();
+    serializer.into_inner()
+}
@@ -12,7 +12,7 @@ use crate::CertificationVersion;
 
 use super::types;
 use ic_protobuf::proxy::ProxyDecodeError;
-use ic_types::{messages::RequestOrResponse, xnet::StreamHeader};
+use ic_types::{messages::RequestOrResponse, time::NO_DEADLINE, xnet::StreamHeader};
 use serde::{Deserialize, Serialize};
 
 // Copy of `types::Request` at canonical version 3 (before the addition of `cycles_payment`).
@@ -60,6 +60,7 @@ impl TryFrom<RequestV3> for ic_types::messages::Request {
             payment: request.payment.cycles.try_into()?,
             method_name: request.method_name,
             method_payload: request.method_payload,
+            deadline: NO_DEADLINE,
         })
     }
 }
@@ -105,6 +106,7 @@ impl TryFrom<ResponseV3> for ic_types::messages::Response {
             originator_reply_callback: response.originator_reply_callback.into(),
             refund: response.refund.cycles.try_into()?,
             response_payload: response.response_payload.try_into()?,
+            deadline: NO_DEADLINE,
         })
     }
 }
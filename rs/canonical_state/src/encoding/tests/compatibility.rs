@@ -23,7 +23,7 @@ use ic_types::{
     crypto::CryptoHash,
     messages::{CallbackId, Payload, RejectContext, Request, RequestOrResponse, Response},
     xnet::StreamHeader,
-    CryptoHashOfPartialState, Cycles, Funds,
+    CoarseTime, CryptoHashOfPartialState, Cycles, Funds,
 };
 use serde_cbor::value::Value;
 use std::collections::{BTreeMap, VecDeque};
@@ -177,6 +177,76 @@ fn canonical_encoding_request() {
     );
 }
 
+/// Canonical CBOR encoding (with certification version V11) of:
+///
+/// ```no_run
+/// RequestOrResponse::Request(
+///     Request {
+///         receiver: canister_test_id(1),
+///         sender: canister_test_id(2),
+///         sender_reply_callback: CallbackId::from(3),
+///         payment: Funds::new(Cycles::new(4)),
+///         method_name: "test".to_string(),
+///         method_payload: vec![6],
+///         deadline: CoarseTime::from_secs_since_unix_epoch(7),
+///     }
+/// )
+/// ```
+///
+/// Expected:
+///
+/// ```text
+/// A1                            # map(1)
+///    00                         # field_index(RequestOrResponse::request)
+///    A7                         # map(7)
+///       00                      # field_index(Request::receiver)
+///       4A                      # bytes(10)
+///          00000000000000010101 # "\x00\x00\x00\x00\x00\x00\x00\x01\x01\x01"
+///       01                      # field_index(Request::sender)
+///       4A                      # bytes(10)
+///          00000000000000020101 # "\x00\x00\x00\x00\x00\x00\x00\x02\x01\x01"
+///       02                      # field_index(Request::sender_reply_callback)
+///       03                      # unsigned(3)
+///       03                      # field_index(Request::payment)
+///       A1                      # map(1)
+///          00                   # field_index(Funds::cycles)
+///          A1                   # map(1)
+///             00                # field_index(Cycles::raw)
+///             04                # unsigned(4)
+///       04                      # field_index(Request::method_name)
+///       64                      # text(4)
+///          74657374             # "test"
+///       05                      # field_index(Request::method_payload)
+///       41                      # bytes(1)
+///          06                   # "\x06"
+///       07                      # field_index(Request::deadline)
+///       07                      # unsigned(7)
+/// Used http://cbor.me/ for printing the human friendly output.
+/// ```
+#[test]
+fn canonical_encoding_best_effort_request() {
+    let request: RequestOrResponse = RequestBuilder::new()
+        .receiver(canister_test_id(1))
+        .sender(canister_test_id(2))
+        .sender_reply_callback(CallbackId::from(3))
+        .payment(Cycles::new(4))
+        .method_name("test".to_string())
+        .method_payload(vec![6])
+        .deadline(CoarseTime::from_secs_since_unix_epoch(7))
+        .build()
+        .into();
+
+    assert_eq!(
+        "A1 00 A7 00 4A 00 00 00 00 00 00 00 01 01 01 01 4A 00 00 00 00 00 00 00 02 01 01 02 03 03 A1 00 A1 00 04 04 64 74 65 73 74 05 41 06 07 07",
+        as_hex(&encode_message(&request, CertificationVersion::V11))
+    );
+    // The deadline is not encoded before certification version V11.
+    assert_eq!(
+        "A1 00 A6 00 4A 00 00 00 00 00 00 00 01 01 01 01 4A 00 00 00 00 00 00 00 02 01 01 02 03 03 A1 00 A1 00 04 04 64 74 65 73 74 05 41 06",
+        as_hex(&encode_message(&request, CertificationVersion::V10))
+    );
+}
+
 /// Canonical CBOR encoding of:
 ///
 /// ```no_run
@@ -13,7 +13,7 @@
 use crate::CertificationVersion;
 use ic_error_types::TryFromError;
 use ic_protobuf::proxy::ProxyDecodeError;
-use ic_types::xnet::StreamIndex;
+use ic_types::{time::NO_DEADLINE, xnet::StreamIndex};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::VecDeque,
@@ -65,6 +65,8 @@ pub struct Request {
     pub method_payload: Bytes,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cycles_payment: Option<Cycles>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<u32>,
 }
 
 /// Canonical representation of `ic_types::messages::Response`.
@@ -80,6 +82,8 @@ pub struct Response {
     pub response_payload: Payload,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cycles_refund: Option<Cycles>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<u32>,
 }
 
 /// Canonical representation of `ic_types::funds::Cycles`.
@@ -246,6 +250,7 @@ impl From<(&ic_types::messages::Request, CertificationVersion)> for Request {
             method_name: request.method_name.clone(),
             method_payload: request.method_payload.clone(),
             cycles_payment: None,
+            deadline: encode_deadline(request.deadline, certification_version),
         }
     }
 }
@@ -267,6 +272,7 @@ impl TryFrom<Request> for ic_types::messages::Request {
             payment,
             method_name: request.method_name,
             method_payload: request.method_payload,
+            deadline: decode_deadline(request.deadline),
         })
     }
 }
@@ -286,6 +292,7 @@ impl From<(&ic_types::messages::Response, CertificationVersion)> for Response {
             refund: funds,
             response_payload: (&response.response_payload, certification_version).into(),
             cycles_refund: None,
+            deadline: encode_deadline(response.deadline, certification_version),
         }
     }
 }
@@ -306,10 +313,30 @@ impl TryFrom<Response> for ic_types::messages::Response {
             originator_reply_callback: response.originator_reply_callback.into(),
             refund,
             response_payload: response.response_payload.try_into()?,
+            deadline: decode_deadline(response.deadline),
         })
     }
 }
 
+/// Encodes the deadline of a best-effort message. Messages without a deadline
+/// (and all messages at certification versions before V11) omit the field.
+fn encode_deadline(
+    deadline: ic_types::CoarseTime,
+    certification_version: CertificationVersion,
+) -> Option<u32> {
+    if certification_version >= CertificationVersion::V11 && deadline != NO_DEADLINE {
+        Some(deadline.as_secs_since_unix_epoch())
+    } else {
+        None
+    }
+}
+
+fn decode_deadline(deadline: Option<u32>) -> ic_types::CoarseTime {
+    deadline
+        .map(ic_types::CoarseTime::from_secs_since_unix_epoch)
+        .unwrap_or(NO_DEADLINE)
+}
+
 impl From<(&ic_types::funds::Cycles, CertificationVersion)> for Cycles {
     fn from(
         (cycles, _certification_version): (&ic_types::funds::Cycles, CertificationVersion),
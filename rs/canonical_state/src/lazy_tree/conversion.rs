@@ -3,8 +3,8 @@
 use super::{blob, fork, num, string, Lazy, LazyFork, LazyTree};
 use crate::{
     encoding::{
-        encode_controllers, encode_message, encode_metadata, encode_stream_header,
-        encode_subnet_canister_ranges,
+        encode_canister_change, encode_controllers, encode_message, encode_metadata,
+        encode_stream_header, encode_subnet_canister_ranges,
     },
     CertificationVersion, MAX_SUPPORTED_CERTIFICATION_VERSION,
 };
@@ -12,7 +12,7 @@ use ic_crypto_tree_hash::Label;
 use ic_error_types::RejectCode;
 use ic_registry_routing_table::RoutingTable;
 use ic_replicated_state::{
-    canister_state::CanisterState,
+    canister_state::{system_state::CanisterHistory, CanisterState},
     metadata_state::{IngressHistoryState, StreamMap, SubnetTopology, SystemMetadata},
     replicated_state::ReplicatedStateMessageRouting,
     ExecutionState, ReplicatedState,
@@ -192,6 +192,28 @@ impl<'a, T> LazyFork<'a> for StreamQueueFork<'a, T> {
     }
 }
 
+/// A fork that describes the history of a canister, with each change labeled
+/// by its index among all changes ever made to the canister.
+#[derive(Clone)]
+struct CanisterHistoryFork<'a>(&'a CanisterHistory);
+
+impl<'a> LazyFork<'a> for CanisterHistoryFork<'a> {
+    fn edge(&self, label: &Label) -> Option<LazyTree<'a>> {
+        let idx = u64::from_label(label.as_bytes())?;
+        let offset = idx.checked_sub(self.0.first_change_index())?;
+        self.0
+            .changes()
+            .get(offset as usize)
+            .map(|change| blob(move || encode_canister_change(change)))
+    }
+
+    fn labels(&self) -> Box<dyn Iterator<Item = Label> + '_> {
+        Box::new(
+            (self.0.first_change_index()..self.0.total_num_changes()).map(|idx| idx.to_label()),
+        )
+    }
+}
+
 impl<'a> From<&'a ReplicatedState> for LazyTree<'a> {
     fn from(state: &'a ReplicatedState) -> LazyTree<'a> {
         state_as_tree(state)
@@ -353,6 +375,13 @@ fn canisters_as_tree(
                         certification_version > CertificationVersion::V5,
                         "metadata",
                         canister_metadata_as_tree(execution_state, certification_version),
+                    )
+                    .with_tree_if(
+                        certification_version > CertificationVersion::V9,
+                        "history",
+                        fork(CanisterHistoryFork(
+                            canister.system_state.canister_history(),
+                        )),
                     ),
             ),
             None => fork(
@@ -366,6 +395,13 @@ fn canisters_as_tree(
                         certification_version > CertificationVersion::V1,
                         "controllers",
                         blob(move || encode_controllers(&canister.system_state.controllers)),
+                    )
+                    .with_tree_if(
+                        certification_version > CertificationVersion::V9,
+                        "history",
+                        fork(CanisterHistoryFork(
+                            canister.system_state.canister_history(),
+                        )),
                     ),
             ),
         },
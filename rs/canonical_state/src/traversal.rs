@@ -43,12 +43,15 @@ pub fn traverse<V: Visitor>(state: &ReplicatedState, mut v: V) -> V::Output {
 mod tests {
     use super::*;
     use crate::{
-        encoding::{encode_stream_header, types::SystemMetadata, CborProxyEncoder},
+        encoding::{
+            encode_canister_change, encode_stream_header, types::SystemMetadata, CborProxyEncoder,
+        },
         subtree_visitor::{Pattern, SubtreeVisitor},
         test_visitors::{NoopVisitor, TraceEntry as E, TracingVisitor},
         CertificationVersion,
     };
     use ic_base_types::{NumBytes, NumSeconds};
+    use ic_ic00_types::{CanisterChangeDetails, CanisterChangeOrigin};
     use ic_registry_routing_table::{CanisterIdRange, RoutingTable};
     use ic_registry_subnet_features::SubnetFeatures;
     use ic_registry_subnet_type::SubnetType;
@@ -220,6 +223,90 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_traverse_canister_history() {
+        let canister_id = canister_test_id(2);
+        let controller = user_test_id(24);
+        let controllers_cbor = {
+            let mut cbor = vec![217, 217, 247, 129, 74];
+            cbor.extend(controller.get().to_vec());
+            cbor
+        };
+        let mut canister_state = new_canister_state(
+            canister_id,
+            controller.get(),
+            INITIAL_CYCLES,
+            NumSeconds::from(100_000),
+        );
+        canister_state.system_state.add_canister_change(
+            mock_time(),
+            CanisterChangeOrigin::from_user(controller.get()),
+            CanisterChangeDetails::canister_creation(vec![controller.get()]),
+        );
+        canister_state.system_state.add_canister_change(
+            mock_time(),
+            CanisterChangeOrigin::from_user(controller.get()),
+            CanisterChangeDetails::code_uninstall(),
+        );
+        let changes: Vec<_> = canister_state
+            .system_state
+            .canister_history()
+            .changes()
+            .iter()
+            .map(encode_canister_change)
+            .collect();
+        let tmpdir = tempfile::Builder::new().prefix("test").tempdir().unwrap();
+        let mut state = ReplicatedState::new_rooted_at(
+            subnet_test_id(1),
+            SubnetType::Application,
+            tmpdir.path().into(),
+        );
+        state.put_canister_state(canister_state);
+
+        state.metadata.certification_version = CertificationVersion::V10;
+        let visitor = TracingVisitor::new(NoopVisitor);
+        assert_eq!(
+            vec![
+                E::StartSubtree, // global
+                edge("canister"),
+                E::StartSubtree,
+                E::EnterEdge(canister_id.get().into_vec()),
+                E::StartSubtree,
+                edge("controller"),
+                E::VisitBlob(controller.get().to_vec()),
+                edge("controllers"),
+                E::VisitBlob(controllers_cbor),
+                edge("history"),
+                E::StartSubtree,
+                edge(0u64.to_be_bytes()),
+                E::VisitBlob(changes[0].clone()),
+                edge(1u64.to_be_bytes()),
+                E::VisitBlob(changes[1].clone()),
+                E::EndSubtree, // history
+                E::EndSubtree, // canister
+                E::EndSubtree, // canisters
+                edge("metadata"),
+                E::VisitBlob(encode_metadata(SystemMetadata {
+                    id_counter: 0,
+                    prev_state_hash: None
+                })),
+                edge("request_status"),
+                E::StartSubtree,
+                E::EndSubtree, // request_status
+                edge("streams"),
+                E::StartSubtree,
+                E::EndSubtree, // streams
+                edge("subnet"),
+                E::StartSubtree,
+                E::EndSubtree, // subnets
+                edge("time"),
+                leb_num(0),
+                E::EndSubtree, // global
+            ],
+            traverse(&state, visitor).0
+        );
+    }
+
     #[test]
     fn test_traverse_canister_with_execution_state() {
         let canister_id = canister_test_id(2);
@@ -265,7 +352,6 @@ mod tests {
             exports: ExportedFunctions::new(BTreeSet::new()),
             metadata: WasmMetadata::new(metadata),
             last_executed_round: ExecutionRound::from(0),
-            task_queue: Default::default(),
         };
         canister_state.execution_state = Some(execution_state);
 
@@ -8,7 +8,11 @@ use ic_canonical_state::{
 };
 use ic_protobuf::proxy::ProxyDecodeError;
 use ic_test_utilities::{state::arb_stream_header, types::arbitrary};
-use ic_types::{messages::RequestOrResponse, xnet::StreamHeader};
+use ic_types::{
+    messages::{Request, RequestOrResponse, Response},
+    xnet::StreamHeader,
+    CoarseTime,
+};
 use lazy_static::lazy_static;
 use proptest::prelude::*;
 use std::ops::RangeInclusive;
@@ -59,7 +63,7 @@ pub(crate) fn arb_valid_versioned_stream_header(
         // 8.
         (
             arb_stream_header(/* sig_min_size */ 0, sig_max_size),
-            Just(CertificationVersion::V8..=CertificationVersion::V9)
+            Just(CertificationVersion::V8..=CertificationVersion::V11)
         ),
     ]
 }
@@ -97,7 +101,7 @@ lazy_static! {
         ),
         #[allow(clippy::redundant_closure)]
         VersionedEncoding::new(
-            CertificationVersion::V0..=CertificationVersion::V9,
+            CertificationVersion::V0..=CertificationVersion::V11,
             "StreamHeader",
             |v| StreamHeaderV8::proxy_encode(v),
             |v| StreamHeaderV8::proxy_decode(v),
@@ -174,10 +178,36 @@ proptest! {
 /// Produces a `RequestOrResponse` valid at all certification versions in the range.
 pub(crate) fn arb_valid_versioned_message(
 ) -> impl Strategy<Value = (RequestOrResponse, RangeInclusive<CertificationVersion>)> {
-    prop_oneof![(
-        arbitrary::request_or_response(),
-        Just(CertificationVersion::V0..=MAX_SUPPORTED_CERTIFICATION_VERSION)
-    ),]
+    prop_oneof![
+        (
+            arbitrary::request_or_response(),
+            Just(CertificationVersion::V0..=MAX_SUPPORTED_CERTIFICATION_VERSION)
+        ),
+        // Deadlines of best-effort messages are only encoded starting with
+        // certification version 11.
+        (
+            (arbitrary::request_or_response(), 1..=u32::MAX)
+                .prop_map(|(msg, secs)| with_deadline(msg, secs)),
+            Just(CertificationVersion::V11..=MAX_SUPPORTED_CERTIFICATION_VERSION)
+        ),
+    ]
+}
+
+/// Sets the deadline of `msg` to `secs` seconds since the UNIX epoch.
+fn with_deadline(msg: RequestOrResponse, secs: u32) -> RequestOrResponse {
+    let deadline = CoarseTime::from_secs_since_unix_epoch(secs);
+    match msg {
+        RequestOrResponse::Request(request) => Request {
+            deadline,
+            ..(*request).clone()
+        }
+        .into(),
+        RequestOrResponse::Response(response) => Response {
+            deadline,
+            ..(*response).clone()
+        }
+        .into(),
+    }
 }
 
 lazy_static! {
@@ -128,6 +128,15 @@ pub const SAMPLE_CONFIG: &str = r#"
         //   CspVault is an internal structure of the replica process.
         // - EXAMPLE: csp_vault_type: { unix_socket: "/some/path/to/socket" },
         //   CspVault is run as a separate process, which can be reached via a Unix socket.
+        // - EXAMPLE: csp_vault_type: { tcp: {
+        //       server_address: "10.0.0.2:4444",
+        //       client_certificate_pem_path: "/some/path/to/replica_cert.pem",
+        //       client_private_key_pem_path: "/some/path/to/replica_key.pem",
+        //       server_certificate_pem_path: "/some/path/to/csp_vault_cert.pem",
+        //   } },
+        //   CspVault is run as a separate process, possibly on another machine, which can be
+        //   reached via TCP. The connection is authenticated with mutual TLS using the given
+        //   (self-signed) certificates.
         csp_vault_type: { unix_socket: "/some/path/to/socket" },
     },
     // ========================================
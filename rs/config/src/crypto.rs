@@ -32,6 +32,38 @@ pub enum CspVaultType {
         )
     )]
     UnixSocket(PathBuf),
+    Tcp(TcpCspVaultConfig),
+}
+
+/// Configuration of a CspVault that runs as a separate process, possibly on a
+/// different machine, and is reached via TCP.
+///
+/// The connection is mutually authenticated with TLS: the replica and the
+/// CspVault each present a self-signed certificate, and each only accepts the
+/// certificate configured for the other side.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+#[cfg_attr(test, derive(Arbitrary))]
+pub struct TcpCspVaultConfig {
+    /// The address (`host:port`) at which the CspVault server listens.
+    pub server_address: String,
+    /// PEM file with the certificate the replica presents to the CspVault.
+    #[cfg_attr(
+        test,
+        proptest(strategy = "any::<String>().prop_map(|x| PathBuf::from(x))")
+    )]
+    pub client_certificate_pem_path: PathBuf,
+    /// PEM file with the PKCS#8 private key of the replica's certificate.
+    #[cfg_attr(
+        test,
+        proptest(strategy = "any::<String>().prop_map(|x| PathBuf::from(x))")
+    )]
+    pub client_private_key_pem_path: PathBuf,
+    /// PEM file with the certificate the CspVault server must present.
+    #[cfg_attr(
+        test,
+        proptest(strategy = "any::<String>().prop_map(|x| PathBuf::from(x))")
+    )]
+    pub server_certificate_pem_path: PathBuf,
 }
 
 impl Default for CspVaultType {
@@ -88,6 +120,15 @@ impl CryptoConfig {
         }
     }
 
+    /// Returns a new CryptoConfig with the given `crypto_root` path, with
+    /// CspVault reachable via TCP as specified by `tcp_config`.
+    pub fn new_with_tcp_vault(crypto_root: PathBuf, tcp_config: TcpCspVaultConfig) -> Self {
+        Self {
+            crypto_root,
+            csp_vault_type: CspVaultType::Tcp(tcp_config),
+        }
+    }
+
     /// Creates a new CryptoConfig in a temporary directory for testing.
     /// The directory has the permissions required for storing crypto state (see
     /// [`Self::check_dir_has_required_permissions`]) and will be automatically
@@ -192,6 +233,19 @@ mod tests {
         CryptoConfig::run_with_temp_config(|config| serde_test(config));
     }
 
+    #[test]
+    fn tcp_vault_config_serializes_and_deserializes() {
+        serde_test(CryptoConfig::new_with_tcp_vault(
+            PathBuf::from("/tmp/ic_crypto"),
+            TcpCspVaultConfig {
+                server_address: "10.0.0.2:4444".to_string(),
+                client_certificate_pem_path: PathBuf::from("/tmp/replica_cert.pem"),
+                client_private_key_pem_path: PathBuf::from("/tmp/replica_key.pem"),
+                server_certificate_pem_path: PathBuf::from("/tmp/csp_vault_cert.pem"),
+            },
+        ));
+    }
+
     proptest! {
         #[allow(dead_code)]
         // #[test]
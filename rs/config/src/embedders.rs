@@ -27,10 +27,25 @@ const DEFAULT_COST_TO_COMPILE_WASM_INSTRUCTION: NumInstructions = NumInstruction
 /// The number of rayon threads used by wasmtime to compile wasm binaries
 const DEFAULT_WASMTIME_RAYON_COMPILATION_THREADS: usize = 10;
 
+/// The maximum number of stable memory pages (of 4KiB) that a single message
+/// execution may modify. This corresponds to 2GiB.
+pub(crate) const STABLE_MEMORY_DIRTY_PAGE_LIMIT: u64 = 512 * 1024;
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 pub struct FeatureFlags {
     pub rate_limiting_of_debug_prints: FlagStatus,
     pub module_sharing: FlagStatus,
+    /// Instruments canisters to attribute executed instructions to Wasm
+    /// functions. Only meant for profiling canisters in test environments.
+    pub instruction_profiling: FlagStatus,
+    /// Maps the stable memory into the address space of the process and
+    /// tracks its modified pages with signal handlers like the Wasm memory
+    /// instead of copying the accessed bytes through the `PageMap`.
+    pub wasm_native_stable_memory: FlagStatus,
+    /// Accepts canisters that use the Wasm SIMD proposal. NaN results of
+    /// floating-point vector operations are canonicalized to keep the
+    /// execution deterministic.
+    pub wasm_simd: FlagStatus,
 }
 
 impl Default for FeatureFlags {
@@ -38,6 +53,9 @@ impl Default for FeatureFlags {
         Self {
             rate_limiting_of_debug_prints: FlagStatus::Enabled,
             module_sharing: FlagStatus::Enabled,
+            instruction_profiling: FlagStatus::Disabled,
+            wasm_native_stable_memory: FlagStatus::Disabled,
+            wasm_simd: FlagStatus::Disabled,
         }
     }
 }
@@ -68,6 +86,10 @@ pub struct Config {
 
     /// Flags to enable or disable features that are still experimental.
     pub feature_flags: FeatureFlags,
+
+    /// The maximum number of stable memory pages that a single message
+    /// execution may modify.
+    pub stable_memory_dirty_page_limit: u64,
 }
 
 impl Config {
@@ -82,6 +104,7 @@ impl Config {
             cost_to_compile_wasm_instruction: DEFAULT_COST_TO_COMPILE_WASM_INSTRUCTION,
             num_rayon_compilation_threads: DEFAULT_WASMTIME_RAYON_COMPILATION_THREADS,
             feature_flags: FeatureFlags::default(),
+            stable_memory_dirty_page_limit: STABLE_MEMORY_DIRTY_PAGE_LIMIT,
         }
     }
 }
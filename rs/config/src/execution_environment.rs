@@ -1,5 +1,6 @@
 use crate::{
-    embedders::QUERY_EXECUTION_THREADS, flag_status::FlagStatus,
+    embedders::{QUERY_EXECUTION_THREADS, STABLE_MEMORY_DIRTY_PAGE_LIMIT},
+    flag_status::FlagStatus,
     subnet_config::MAX_INSTRUCTIONS_PER_MESSAGE,
 };
 use ic_base_types::NumSeconds;
@@ -22,6 +23,10 @@ const GB: u64 = 1024 * 1024 * 1024;
 /// canister's data and the deltas.
 const SUBNET_MEMORY_CAPACITY: NumBytes = NumBytes::new(350 * GB);
 
+/// Once the memory usage of the subnet exceeds this threshold, canisters
+/// allocating new storage have to reserve cycles for it.
+const SUBNET_MEMORY_THRESHOLD: NumBytes = NumBytes::new(300 * GB);
+
 /// This is the upper limit on how much memory can be used by all canister
 /// messages on a given subnet.
 ///
@@ -50,6 +55,16 @@ const INGRESS_HISTORY_MEMORY_CAPACITY: NumBytes = NumBytes::new(10 * GB);
 /// memory can succeed.
 pub(crate) const SUBNET_HEAP_DELTA_CAPACITY: NumBytes = NumBytes::new(150 * GB);
 
+/// The maximum depth of a query call graph, i.e. the maximum length of a
+/// chain of composite queries calling each other. The canister that receives
+/// the user's query is at depth zero.
+const MAX_QUERY_CALL_GRAPH_DEPTH: usize = 6;
+
+/// The maximum number of instructions that all messages executed while
+/// handling a single user query can use in total. This bounds the cost of
+/// composite queries that fan out to many canisters.
+const MAX_QUERY_CALL_GRAPH_INSTRUCTIONS: NumInstructions = NumInstructions::new(5_000_000_000);
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 #[serde(default)]
 pub struct Config {
@@ -65,6 +80,10 @@ pub struct Config {
     /// the subnet.
     pub subnet_memory_capacity: NumBytes,
 
+    /// The subnet memory usage above which allocating new storage reserves
+    /// cycles, see `CyclesAccountManager::storage_reservation_cycles()`.
+    pub subnet_memory_threshold: NumBytes,
+
     /// The maximum amount of logical storage available to canister messages
     /// across the whole subnet.
     pub subnet_message_memory_capacity: NumBytes,
@@ -113,6 +132,30 @@ pub struct Config {
 
     /// Sharing of serialized modules between canisters.
     pub module_sharing: FlagStatus,
+
+    /// The maximum depth of a query call graph.
+    pub max_query_call_graph_depth: usize,
+
+    /// The maximum number of instructions that can be executed across all
+    /// messages of a query call graph.
+    pub max_query_call_graph_instructions: NumInstructions,
+
+    /// If this flag is enabled, then the instructions executed by canisters
+    /// are attributed to Wasm functions and collected as profiles. This is
+    /// only meant for test environments such as `drun` and `StateMachine`.
+    pub instruction_profiling: FlagStatus,
+
+    /// Indicates whether the stable memory is mapped into the address space
+    /// of the sandbox and tracked like the Wasm memory, instead of copying
+    /// the accessed bytes through the `PageMap`.
+    pub wasm_native_stable_memory: FlagStatus,
+
+    /// The maximum number of stable memory pages that a single message
+    /// execution may modify.
+    pub stable_memory_dirty_page_limit: u64,
+
+    /// Indicates whether canisters may use the Wasm SIMD instructions.
+    pub wasm_simd: FlagStatus,
 }
 
 impl Default for Config {
@@ -121,6 +164,7 @@ impl Default for Config {
             create_funds_whitelist: String::default(),
             max_instructions_for_message_acceptance_calls: MAX_INSTRUCTIONS_PER_MESSAGE,
             subnet_memory_capacity: SUBNET_MEMORY_CAPACITY,
+            subnet_memory_threshold: SUBNET_MEMORY_THRESHOLD,
             subnet_message_memory_capacity: SUBNET_MESSAGE_MEMORY_CAPACITY,
             ingress_history_memory_capacity: INGRESS_HISTORY_MEMORY_CAPACITY,
             max_canister_memory_size: NumBytes::new(
@@ -141,6 +185,12 @@ impl Default for Config {
             allocatable_compute_capacity_in_percent: 50,
             deterministic_time_slicing: FlagStatus::Disabled,
             module_sharing: FlagStatus::Enabled,
+            max_query_call_graph_depth: MAX_QUERY_CALL_GRAPH_DEPTH,
+            max_query_call_graph_instructions: MAX_QUERY_CALL_GRAPH_INSTRUCTIONS,
+            instruction_profiling: FlagStatus::Disabled,
+            wasm_native_stable_memory: FlagStatus::Disabled,
+            stable_memory_dirty_page_limit: STABLE_MEMORY_DIRTY_PAGE_LIMIT,
+            wasm_simd: FlagStatus::Disabled,
         }
     }
 }
@@ -263,6 +263,11 @@ pub struct CyclesAccountManagerConfig {
     /// How often to charge canisters for memory and compute allocations.
     pub duration_between_allocation_charges: Duration,
 
+    /// The period of storage that is reserved in cycles when a canister
+    /// allocates memory on a subnet whose memory usage is at its capacity.
+    /// Below full capacity a proportionally shorter period is reserved.
+    pub max_storage_reservation_period: Duration,
+
     /// Amount to charge for an ECDSA signature.
     pub ecdsa_signature_fee: Cycles,
 
@@ -295,6 +300,8 @@ impl CyclesAccountManagerConfig {
             // 4 SDR per GiB per year => 4e12 Cycles per year
             gib_storage_per_second_fee: Cycles::new(127_000),
             duration_between_allocation_charges: Duration::from_secs(10),
+            // Roughly ten years.
+            max_storage_reservation_period: Duration::from_secs(300_000_000),
             ecdsa_signature_fee: ECDSA_SIGNATURE_FEE,
             http_request_baseline_fee: Cycles::new(400_000_000),
             http_request_per_byte_fee: Cycles::new(100_000),
@@ -314,6 +321,8 @@ impl CyclesAccountManagerConfig {
             ingress_byte_reception_fee: Cycles::new(0),
             gib_storage_per_second_fee: Cycles::new(0),
             duration_between_allocation_charges: Duration::from_secs(10),
+            // Roughly ten years.
+            max_storage_reservation_period: Duration::from_secs(300_000_000),
             /// The ECDSA signature fee is the fee charged when creating a
             /// signature on this subnet. The request likely came from a
             /// different subnet which is not a system subnet. There is an
@@ -29,6 +29,7 @@ use ic_types::{
         NiDkgId, NiDkgTag, NiDkgTargetSubnet::Remote, NiDkgTranscript,
     },
     messages::{CallbackId, Response},
+    time::NO_DEADLINE,
     ReplicaVersion,
 };
 use std::collections::BTreeMap;
@@ -266,6 +267,7 @@ pub fn generate_execution_responses_for_canister_http_responses(
                         ic_types::messages::Payload::Reject((canister_http_reject).into())
                     }
                 },
+                deadline: NO_DEADLINE,
             }
         })
         // Deliver timeout responses
@@ -284,6 +286,7 @@ pub fn generate_execution_responses_for_canister_http_responses(
                             message: "Canister http request timed out".to_string(),
                         },
                     ),
+                    deadline: NO_DEADLINE,
                 }),
         )
         .collect()
@@ -374,6 +377,7 @@ pub fn generate_responses_to_setup_initial_dkg_calls(
                 originator_reply_callback: *callback_id,
                 refund: Cycles::zero(),
                 response_payload,
+                deadline: NO_DEADLINE,
             });
         }
     }
@@ -4,7 +4,7 @@
 
 use super::pre_signer::{EcdsaTranscriptBuilder, EcdsaTranscriptBuilderImpl};
 use super::signer::{EcdsaSignatureBuilder, EcdsaSignatureBuilderImpl};
-use super::utils::EcdsaBlockReaderImpl;
+use super::utils::{algorithm_for_key_id, EcdsaBlockReaderImpl};
 use crate::consensus::{
     crypto::ConsensusCrypto, metrics::EcdsaPayloadMetrics, pool_reader::PoolReader,
 };
@@ -39,6 +39,7 @@ use ic_types::{
     },
     messages::CallbackId,
     registry::RegistryClientError,
+    time::NO_DEADLINE,
     Height, NodeId, RegistryVersion, SubnetId,
 };
 use phantom_newtype::Id;
@@ -629,6 +630,7 @@ pub(crate) fn create_data_payload_helper(
         registry_client,
         next_interval_registry_version,
         subnet_id,
+        &ecdsa_payload.key_transcript.key_id,
         current_key_transcript.as_ref(),
         &mut ecdsa_payload.key_transcript.next_in_creation,
         &mut ecdsa_payload.uid_generator,
@@ -677,6 +679,7 @@ fn new_random_config(
     subnet_nodes: &[NodeId],
     summary_registry_version: RegistryVersion,
     uid_generator: &mut ecdsa::EcdsaUIDGenerator,
+    algorithm_id: AlgorithmId,
 ) -> Result<ecdsa::RandomTranscriptParams, EcdsaPayloadError> {
     let transcript_id = uid_generator.next_transcript_id();
     let dealers = subnet_nodes.iter().copied().collect::<BTreeSet<_>>();
@@ -686,7 +689,7 @@ fn new_random_config(
         dealers,
         receivers,
         summary_registry_version,
-        AlgorithmId::ThresholdEcdsaSecp256k1,
+        algorithm_id,
     ))
 }
 
@@ -703,6 +706,7 @@ fn make_new_quadruples_if_needed(
         make_new_quadruples_if_needed_helper(
             &node_ids,
             key_transcript.registry_version(),
+            key_transcript.algorithm_id(),
             ecdsa_config,
             ecdsa_payload,
         )
@@ -714,6 +718,7 @@ fn make_new_quadruples_if_needed(
 fn make_new_quadruples_if_needed_helper(
     subnet_nodes: &[NodeId],
     registry_version: RegistryVersion,
+    algorithm_id: AlgorithmId,
     ecdsa_config: &EcdsaConfig,
     ecdsa_payload: &mut ecdsa::EcdsaPayload,
 ) -> Result<(), EcdsaPayloadError> {
@@ -723,8 +728,10 @@ fn make_new_quadruples_if_needed_helper(
         let quadruples_in_creation = &mut ecdsa_payload.quadruples_in_creation;
         let uid_generator = &mut ecdsa_payload.uid_generator;
         for _ in 0..(quadruples_to_create - unassigned_quadruples) {
-            let kappa_config = new_random_config(subnet_nodes, registry_version, uid_generator)?;
-            let lambda_config = new_random_config(subnet_nodes, registry_version, uid_generator)?;
+            let kappa_config =
+                new_random_config(subnet_nodes, registry_version, uid_generator, algorithm_id)?;
+            let lambda_config =
+                new_random_config(subnet_nodes, registry_version, uid_generator, algorithm_id)?;
             quadruples_in_creation.insert(
                 uid_generator.next_quadruple_id(),
                 ecdsa::QuadrupleInCreation::new(kappa_config, lambda_config),
@@ -904,6 +911,7 @@ pub(crate) fn update_signature_agreements(
                     }
                     .encode(),
                 ),
+                deadline: NO_DEADLINE,
             };
             payload
                 .signature_agreements
@@ -948,6 +956,7 @@ fn update_next_key_transcript(
     registry_client: &dyn RegistryClient,
     next_interval_registry_version: RegistryVersion,
     subnet_id: SubnetId,
+    key_id: &EcdsaKeyId,
     current_key_transcript: Option<&ecdsa::UnmaskedTranscriptWithAttributes>,
     next_key_transcript_creation: &mut ecdsa::KeyTranscriptCreation,
     uid_generator: &mut ecdsa::EcdsaUIDGenerator,
@@ -959,6 +968,7 @@ fn update_next_key_transcript(
     update_next_key_transcript_helper(
         &receivers,
         next_interval_registry_version,
+        algorithm_for_key_id(key_id),
         current_key_transcript,
         next_key_transcript_creation,
         uid_generator,
@@ -968,9 +978,11 @@ fn update_next_key_transcript(
     )
 }
 
+#[allow(clippy::too_many_arguments)]
 fn update_next_key_transcript_helper(
     receivers: &[NodeId],
     registry_version: RegistryVersion,
+    algorithm_id: AlgorithmId,
     current_key_transcript: Option<&ecdsa::UnmaskedTranscriptWithAttributes>,
     next_key_transcript_creation: &mut ecdsa::KeyTranscriptCreation,
     uid_generator: &mut ecdsa::EcdsaUIDGenerator,
@@ -1035,7 +1047,7 @@ fn update_next_key_transcript_helper(
                     dealers_set,
                     receivers_set,
                     registry_version,
-                    AlgorithmId::ThresholdEcdsaSecp256k1,
+                    algorithm_id,
                 ),
             );
         }
@@ -1390,6 +1402,7 @@ fn make_reshare_dealings_response(
                             }
                             .encode(),
                         ),
+                        deadline: NO_DEADLINE,
                     });
                 }
             }
@@ -1646,10 +1659,20 @@ mod tests {
         uid_generator: &mut ecdsa::EcdsaUIDGenerator,
         quadruples_in_creation: &mut BTreeMap<ecdsa::QuadrupleId, ecdsa::QuadrupleInCreation>,
     ) -> (ecdsa::RandomTranscriptParams, ecdsa::RandomTranscriptParams) {
-        let kappa_config_ref =
-            new_random_config(subnet_nodes, registry_version, uid_generator).unwrap();
-        let lambda_config_ref =
-            new_random_config(subnet_nodes, registry_version, uid_generator).unwrap();
+        let kappa_config_ref = new_random_config(
+            subnet_nodes,
+            registry_version,
+            uid_generator,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
+        )
+        .unwrap();
+        let lambda_config_ref = new_random_config(
+            subnet_nodes,
+            registry_version,
+            uid_generator,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
+        )
+        .unwrap();
         quadruples_in_creation.insert(
             uid_generator.next_quadruple_id(),
             ecdsa::QuadrupleInCreation::new(kappa_config_ref.clone(), lambda_config_ref.clone()),
@@ -1677,6 +1700,7 @@ mod tests {
         let cur_height = Height::new(1);
         let subnet_nodes = (0..10).map(node_test_id).collect::<Vec<_>>();
         let summary_registry_version = RegistryVersion::new(10);
+        let algorithm = AlgorithmId::ThresholdEcdsaSecp256k1;
         let mut ecdsa_payload = empty_ecdsa_payload(subnet_id);
         let update_res = ecdsa_payload.uid_generator.update_height(cur_height);
         assert!(update_res.is_ok());
@@ -1689,6 +1713,7 @@ mod tests {
         let result = make_new_quadruples_if_needed_helper(
             &subnet_nodes,
             summary_registry_version,
+            algorithm,
             &ecdsa_config,
             &mut ecdsa_payload,
         );
@@ -1925,6 +1950,7 @@ mod tests {
         let subnet_id = subnet_test_id(1);
         let env = CanisterThresholdSigTestEnvironment::new(num_of_nodes);
         let registry_version = env.newest_registry_version;
+        let algorithm = AlgorithmId::ThresholdEcdsaSecp256k1;
         let subnet_nodes = env.receivers().into_iter().collect::<Vec<_>>();
         let mut block_reader = TestEcdsaBlockReader::new();
         let config_ids = |payload: &ecdsa::EcdsaPayload| {
@@ -1943,6 +1969,7 @@ mod tests {
         let result = update_next_key_transcript_helper(
             &subnet_nodes,
             registry_version,
+            algorithm,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -1976,6 +2003,7 @@ mod tests {
         let result = update_next_key_transcript_helper(
             &subnet_nodes,
             registry_version,
+            algorithm,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2015,6 +2043,7 @@ mod tests {
         let result = update_next_key_transcript_helper(
             &subnet_nodes,
             registry_version,
+            algorithm,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2052,6 +2081,7 @@ mod tests {
         let result = update_next_key_transcript_helper(
             &subnet_nodes,
             registry_version,
+            algorithm,
             Some(&current_key_transcript),
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2086,6 +2116,7 @@ mod tests {
         let result = update_next_key_transcript_helper(
             &subnet_nodes,
             registry_version,
+            algorithm,
             Some(&current_key_transcript),
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2117,6 +2148,7 @@ mod tests {
         let subnet_id = subnet_test_id(1);
         let env = CanisterThresholdSigTestEnvironment::new(num_of_nodes);
         let registry_version = env.newest_registry_version;
+        let algorithm = AlgorithmId::ThresholdEcdsaSecp256k1;
         let mut subnet_nodes = env.receivers().into_iter().collect::<Vec<_>>();
         let target_subnet_nodes = subnet_nodes.split_off(4);
         let mut block_reader = TestEcdsaBlockReader::new();
@@ -2136,6 +2168,7 @@ mod tests {
         let result = update_next_key_transcript_helper(
             &subnet_nodes,
             registry_version,
+            algorithm,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2169,6 +2202,7 @@ mod tests {
         let result = update_next_key_transcript_helper(
             &subnet_nodes,
             registry_version,
+            algorithm,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2208,6 +2242,7 @@ mod tests {
         let result = update_next_key_transcript_helper(
             &subnet_nodes,
             registry_version,
+            algorithm,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2257,6 +2292,7 @@ mod tests {
         let result = update_next_key_transcript_helper(
             &subnet_nodes,
             registry_version,
+            algorithm,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2295,6 +2331,7 @@ mod tests {
         let result = update_next_key_transcript_helper(
             &target_subnet_nodes,
             registry_version,
+            algorithm,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -1,7 +1,7 @@
 //! Common utils for the ECDSA implementation.
 
 use crate::ecdsa::complaints::{EcdsaTranscriptLoader, TranscriptLoadStatus};
-use ic_ic00_types::EcdsaKeyId;
+use ic_ic00_types::{EcdsaCurve, EcdsaKeyId};
 use ic_interfaces::consensus_pool::ConsensusBlockChain;
 use ic_interfaces::ecdsa::{EcdsaChangeAction, EcdsaChangeSet, EcdsaPool};
 use ic_protobuf::registry::subnet::v1 as pb;
@@ -14,6 +14,7 @@ use ic_types::consensus::{Block, BlockPayload, HasHeight};
 use ic_types::crypto::canister_threshold_sig::idkg::{
     IDkgTranscript, IDkgTranscriptOperation, InitialIDkgDealings,
 };
+use ic_types::crypto::AlgorithmId;
 use ic_types::Height;
 use std::collections::BTreeSet;
 use std::convert::TryInto;
@@ -208,6 +209,14 @@ pub(crate) fn inspect_ecdsa_initializations(
     }
 }
 
+/// Return the threshold ECDSA algorithm used for keys with the given key_id.
+pub(crate) fn algorithm_for_key_id(key_id: &EcdsaKeyId) -> AlgorithmId {
+    match key_id.curve {
+        EcdsaCurve::Secp256k1 => AlgorithmId::ThresholdEcdsaSecp256k1,
+        EcdsaCurve::Secp256r1 => AlgorithmId::ThresholdEcdsaSecp256r1,
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod test_utils {
     use crate::consensus::mocks::{dependencies, Dependencies};
@@ -262,6 +271,7 @@ pub(crate) mod test_utils {
             // be refunded to the canister.
             refund: ic_types::Cycles::new(0),
             response_payload: ic_types::messages::Payload::Data(vec![]),
+            deadline: ic_types::time::NO_DEADLINE,
         }
     }
 
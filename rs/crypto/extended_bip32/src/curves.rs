@@ -0,0 +1,65 @@
+use alloc::vec::Vec;
+
+/// The curve operations needed for public key derivation
+pub(crate) trait Point: Sized {
+    /// Deserialize a SEC1 encoded point, rejecting the identity
+    fn deserialize(bytes: &[u8]) -> Option<Self>;
+
+    /// Serialize the point in SEC1 compressed form
+    fn serialize(&self) -> Vec<u8>;
+
+    /// Return self + offset*G
+    ///
+    /// The offset is a big-endian integer. If it is not less than the
+    /// group order, or if the result is the identity, None is returned.
+    fn add_offset(&self, offset: &[u8]) -> Option<Self>;
+}
+
+macro_rules! define_point {
+    ($point:ident, $curve:ident) => {
+        pub(crate) struct $point($curve::ProjectivePoint);
+
+        impl Point for $point {
+            fn deserialize(bytes: &[u8]) -> Option<Self> {
+                use $curve::elliptic_curve::{sec1::FromEncodedPoint, Group};
+
+                let encoded = $curve::EncodedPoint::from_bytes(bytes).ok()?;
+                let affine: Option<$curve::AffinePoint> =
+                    $curve::AffinePoint::from_encoded_point(&encoded).into();
+                let point = $curve::ProjectivePoint::from(affine?);
+
+                if bool::from(point.is_identity()) {
+                    None
+                } else {
+                    Some(Self(point))
+                }
+            }
+
+            fn serialize(&self) -> Vec<u8> {
+                use $curve::elliptic_curve::sec1::ToEncodedPoint;
+
+                $curve::AffinePoint::from(self.0)
+                    .to_encoded_point(true)
+                    .as_bytes()
+                    .to_vec()
+            }
+
+            fn add_offset(&self, offset: &[u8]) -> Option<Self> {
+                use $curve::elliptic_curve::{group::ff::PrimeField, Group};
+
+                let offset: Option<$curve::Scalar> =
+                    $curve::Scalar::from_repr(*$curve::FieldBytes::from_slice(offset)).into();
+                let point = self.0 + $curve::ProjectivePoint::GENERATOR * offset?;
+
+                if bool::from(point.is_identity()) {
+                    None
+                } else {
+                    Some(Self(point))
+                }
+            }
+        }
+    };
+}
+
+define_point!(Secp256k1Point, k256);
+define_point!(Secp256r1Point, p256);
@@ -0,0 +1,237 @@
+//! Public key derivation for canister threshold ECDSA keys
+//!
+//! Canisters obtain their threshold ECDSA public keys from the
+//! management canister method `ecdsa_public_key`, which returns a
+//! public key and a chain code derived from the subnet's master key
+//! and the given derivation path. This crate implements the very same
+//! derivation as the replica, so that a public key can be derived
+//! further (or an `ecdsa_public_key` response checked) without
+//! contacting the Internet Computer.
+//!
+//! The derivation is BIP32's public child key derivation (CKDpub),
+//! extended in two ways:
+//!
+//! * A derivation index is an arbitrary byte string instead of a 32
+//!   bit integer. For 4 byte indexes below 2**31 this is identical to
+//!   BIP32. The replica uses the canister id as the first index, followed
+//!   by the derivation path provided by the canister.
+//! * Besides secp256k1, the same construction is used for secp256r1
+//!   (as done by SLIP-0010).
+//!
+//! Derivation from the master key starts with an all-zero chain code.
+//!
+//! The crate is `no_std` and only requires an allocator, so it can be
+//! used in canisters and hardware wallets alike.
+#![no_std]
+#![forbid(unsafe_code)]
+#![deny(clippy::unwrap_used)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+mod curves;
+
+use curves::{Point, Secp256k1Point, Secp256r1Point};
+
+/// The curve of a public key
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DerivationCurve {
+    Secp256k1,
+    Secp256r1,
+}
+
+/// Errors that can occur during public key derivation
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DerivationError {
+    /// The public key is not a valid SEC1 encoded point on the curve
+    InvalidPublicKey,
+    /// The chain code is not 32 bytes long
+    InvalidChainCodeLength(usize),
+}
+
+pub type DerivationResult<T> = Result<T, DerivationError>;
+
+/// A single index of a derivation path
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DerivationIndex(pub Vec<u8>);
+
+impl DerivationIndex {
+    /// Return the BIP32 "next" derivation index
+    ///
+    /// This is used in the (exceedingly unlikely) case that deriving
+    /// with an index results in an invalid key. The index is
+    /// interpreted as a big-endian integer and incremented by one.
+    pub fn next(&self) -> Self {
+        let mut n = self.0.clone();
+
+        n.reverse();
+
+        let mut carry = 1u8;
+        for w in &mut n {
+            let (v, c) = w.overflowing_add(carry);
+            *w = v;
+            carry = if c { 1 } else { 0 };
+        }
+
+        if carry != 0 {
+            n.push(carry);
+        }
+
+        n.reverse();
+
+        Self(n)
+    }
+}
+
+/// A derivation path of arbitrary depth
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DerivationPath {
+    path: Vec<DerivationIndex>,
+}
+
+/// The result of a public key derivation
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DerivationOutput {
+    /// The SEC1 compressed encoding of the derived public key
+    pub derived_public_key: Vec<u8>,
+    pub derived_chain_code: Vec<u8>,
+}
+
+impl DerivationPath {
+    /// Create a standard BIP32 derivation path
+    pub fn new_bip32(bip32: &[u32]) -> Self {
+        let mut path = Vec::with_capacity(bip32.len());
+        for n in bip32 {
+            path.push(DerivationIndex(n.to_be_bytes().to_vec()));
+        }
+        Self::new(path)
+    }
+
+    /// Create a free-form derivation path
+    pub fn new(path: Vec<DerivationIndex>) -> Self {
+        Self { path }
+    }
+
+    /// Create the path the replica uses for a canister's key
+    ///
+    /// This is the canister id followed by the derivation path the
+    /// canister provided to `ecdsa_public_key` or `sign_with_ecdsa`.
+    pub fn new_for_canister(canister_id: &[u8], derivation_path: &[Vec<u8>]) -> Self {
+        let mut path = Vec::with_capacity(1 + derivation_path.len());
+        path.push(DerivationIndex(canister_id.to_vec()));
+        for index in derivation_path {
+            path.push(DerivationIndex(index.clone()));
+        }
+        Self::new(path)
+    }
+
+    /// Return the indexes of this path
+    pub fn path(&self) -> &[DerivationIndex] {
+        &self.path
+    }
+
+    /// Derive a public key from a master public key
+    ///
+    /// This is the derivation the replica performs for
+    /// `ecdsa_public_key`, with the chain code of the master key being
+    /// all zeros.
+    pub fn derive_from_master_key(
+        &self,
+        curve: DerivationCurve,
+        master_public_key: &[u8],
+    ) -> DerivationResult<DerivationOutput> {
+        self.derive_public_key(curve, master_public_key, &[0; 32])
+    }
+
+    /// Derive a public key from an already derived public key
+    ///
+    /// The public key must be SEC1 encoded (compressed or not) and the
+    /// chain code 32 bytes long. Deriving with path `a` and then with
+    /// path `b` (using the chain code returned by the first
+    /// derivation) is the same as deriving with `a` followed by `b`.
+    pub fn derive_public_key(
+        &self,
+        curve: DerivationCurve,
+        public_key: &[u8],
+        chain_code: &[u8],
+    ) -> DerivationResult<DerivationOutput> {
+        match curve {
+            DerivationCurve::Secp256k1 => self.derive::<Secp256k1Point>(public_key, chain_code),
+            DerivationCurve::Secp256r1 => self.derive::<Secp256r1Point>(public_key, chain_code),
+        }
+    }
+
+    fn derive<P: Point>(
+        &self,
+        public_key: &[u8],
+        chain_code: &[u8],
+    ) -> DerivationResult<DerivationOutput> {
+        if chain_code.len() != 32 {
+            return Err(DerivationError::InvalidChainCodeLength(chain_code.len()));
+        }
+
+        let mut derived_key =
+            P::deserialize(public_key).ok_or(DerivationError::InvalidPublicKey)?;
+        let mut derived_chain_code = chain_code.to_vec();
+
+        for index in &self.path {
+            let (next_key, next_chain_code) = ckdpub(&derived_key, &derived_chain_code, index);
+            derived_key = next_key;
+            derived_chain_code = next_chain_code;
+        }
+
+        Ok(DerivationOutput {
+            derived_public_key: derived_key.serialize(),
+            derived_chain_code,
+        })
+    }
+}
+
+/// BIP32 Public parent key -> public child key (aka CKDpub)
+///
+/// See <https://en.bitcoin.it/wiki/BIP_0032#Child_key_derivation_.28CKD.29_functions>
+fn ckdpub<P: Point>(public_key: &P, chain_code: &[u8], index: &DerivationIndex) -> (P, Vec<u8>) {
+    let mut index = index.clone();
+
+    loop {
+        let hmac_output = hmac_sha512(chain_code, &[&public_key.serialize(), &index.0]);
+
+        // If iL >= order or the new key is infinity, try again with the "next" index
+        if let Some(new_key) = public_key.add_offset(&hmac_output[..32]) {
+            return (new_key, hmac_output[32..].to_vec());
+        }
+
+        index = index.next();
+    }
+}
+
+/// HMAC-SHA512 as specified in RFC 2104
+fn hmac_sha512(key: &[u8], inputs: &[&[u8]]) -> [u8; 64] {
+    use sha2::{Digest, Sha512};
+
+    const BLOCK_SIZE: usize = 128;
+
+    // Keys longer than the block size are hashed first. Chain codes
+    // are always 32 bytes, so this is just for completeness.
+    let mut padded_key = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        padded_key[..64].copy_from_slice(&Sha512::digest(key));
+    } else {
+        padded_key[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha512::new();
+    inner.update(padded_key.iter().map(|b| b ^ 0x36).collect::<Vec<u8>>());
+    for input in inputs {
+        inner.update(input);
+    }
+
+    let mut outer = Sha512::new();
+    outer.update(padded_key.iter().map(|b| b ^ 0x5c).collect::<Vec<u8>>());
+    outer.update(inner.finalize());
+
+    let mut output = [0u8; 64];
+    output.copy_from_slice(&outer.finalize());
+    output
+}
@@ -0,0 +1,125 @@
+use ic_crypto_extended_bip32::*;
+use ic_crypto_internal_test_vectors::extended_bip32 as test_vectors;
+use strum::IntoEnumIterator;
+
+fn curve_of(curve: test_vectors::DerivationCurve) -> DerivationCurve {
+    match curve {
+        test_vectors::DerivationCurve::Secp256k1 => DerivationCurve::Secp256k1,
+        test_vectors::DerivationCurve::Secp256r1 => DerivationCurve::Secp256r1,
+    }
+}
+
+#[test]
+fn should_derivation_match_test_vectors() {
+    for test_vec in test_vectors::PublicKeyDerivationTestVector::iter() {
+        let test_vec = test_vectors::public_key_derivation_testvec(test_vec);
+
+        let path = DerivationPath::new(test_vec.path.into_iter().map(DerivationIndex).collect());
+
+        let derived = path
+            .derive_public_key(
+                curve_of(test_vec.curve),
+                &test_vec.public_key,
+                &test_vec.chain_code,
+            )
+            .expect("Derivation failed");
+
+        assert_eq!(derived.derived_public_key, test_vec.derived_public_key);
+        assert_eq!(derived.derived_chain_code, test_vec.derived_chain_code);
+    }
+}
+
+#[test]
+fn should_derivation_be_composable() {
+    for test_vec in test_vectors::PublicKeyDerivationTestVector::iter() {
+        let test_vec = test_vectors::public_key_derivation_testvec(test_vec);
+        let curve = curve_of(test_vec.curve);
+
+        for split in 0..=test_vec.path.len() {
+            let (head, tail) = test_vec.path.split_at(split);
+
+            let head = DerivationPath::new(head.iter().cloned().map(DerivationIndex).collect())
+                .derive_public_key(curve, &test_vec.public_key, &test_vec.chain_code)
+                .expect("Derivation failed");
+            let tail = DerivationPath::new(tail.iter().cloned().map(DerivationIndex).collect())
+                .derive_public_key(curve, &head.derived_public_key, &head.derived_chain_code)
+                .expect("Derivation failed");
+
+            assert_eq!(tail.derived_public_key, test_vec.derived_public_key);
+            assert_eq!(tail.derived_chain_code, test_vec.derived_chain_code);
+        }
+    }
+}
+
+#[test]
+fn should_derive_from_master_key_with_zero_chain_code() {
+    let test_vec = test_vectors::public_key_derivation_testvec(
+        test_vectors::PublicKeyDerivationTestVector::SECP256K1_CANISTER_PATH,
+    );
+
+    let canister_id = &test_vec.path[0];
+    let path = DerivationPath::new_for_canister(canister_id, &test_vec.path[1..]);
+
+    let derived = path
+        .derive_from_master_key(DerivationCurve::Secp256k1, &test_vec.public_key)
+        .expect("Derivation failed");
+
+    assert_eq!(derived.derived_public_key, test_vec.derived_public_key);
+    assert_eq!(derived.derived_chain_code, test_vec.derived_chain_code);
+}
+
+#[test]
+fn should_accept_uncompressed_public_keys() {
+    // The secp256k1 generator, uncompressed and compressed
+    let uncompressed = hex::decode("0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8").unwrap();
+    let compressed =
+        hex::decode("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798").unwrap();
+
+    let path = DerivationPath::new_bip32(&[1, 2]);
+
+    assert_eq!(
+        path.derive_from_master_key(DerivationCurve::Secp256k1, &uncompressed),
+        path.derive_from_master_key(DerivationCurve::Secp256k1, &compressed)
+    );
+}
+
+#[test]
+fn should_reject_invalid_inputs() {
+    let test_vec = test_vectors::public_key_derivation_testvec(
+        test_vectors::PublicKeyDerivationTestVector::SECP256R1_BIP32_PATH,
+    );
+    let path = DerivationPath::new_bip32(&[1]);
+
+    assert_eq!(
+        path.derive_public_key(DerivationCurve::Secp256r1, &test_vec.public_key, &[0; 31]),
+        Err(DerivationError::InvalidChainCodeLength(31))
+    );
+
+    let mut bad_key = test_vec.public_key.clone();
+    bad_key[0] = 0x05;
+    assert_eq!(
+        path.derive_from_master_key(DerivationCurve::Secp256r1, &bad_key),
+        Err(DerivationError::InvalidPublicKey)
+    );
+
+    // The identity is not a valid public key
+    assert_eq!(
+        path.derive_from_master_key(DerivationCurve::Secp256r1, &[0x00]),
+        Err(DerivationError::InvalidPublicKey)
+    );
+}
+
+#[test]
+fn test_index_next_behavior() {
+    fn check_next(input: &[u8], output: &[u8]) {
+        let index = DerivationIndex(input.to_vec());
+        let next = index.next();
+        assert_eq!(next.0, output);
+    }
+
+    check_next(&[], &[1]);
+    check_next(&[1], &[2]);
+    check_next(&[0xFF], &[1, 0]);
+    check_next(&[0, 0, 0, 5], &[0, 0, 0, 6]);
+    check_next(&[0x7F, 0xFF, 0xFF, 0xFF], &[0x80, 0x00, 0x00, 0x00]);
+}
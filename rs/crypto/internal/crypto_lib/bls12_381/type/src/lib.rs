@@ -225,6 +225,28 @@ impl Scalar {
         Self::new(bls12_381::Scalar::from_bytes_wide(&le_bytes))
     }
 
+    /// Deterministically hash an input onto a BLS12-381 scalar
+    ///
+    /// This follows hash_to_field from draft-irtf-cfrg-hash-to-curve-16,
+    /// using expand_message_xmd with SHA-256 and L = 48, so the output
+    /// is statistically indistinguishable from a uniform scalar.
+    ///
+    /// # Arguments
+    /// * `domain_sep` - some protocol specific domain seperator
+    /// * `input` - the input which will be hashed
+    pub fn hash(domain_sep: &[u8], input: &[u8]) -> Self {
+        const L: usize = 48;
+
+        let digest = xmd(input, domain_sep, L);
+
+        let mut le_bytes = [0u8; 64];
+        for i in 0..L {
+            le_bytes[i] = digest[L - i - 1];
+        }
+        // le_bytes[48..64] left as zero
+        Self::new(bls12_381::Scalar::from_bytes_wide(&le_bytes))
+    }
+
     /// Deserialize a scalar from a big-endian byte string
     pub fn deserialize(bytes: &[u8]) -> Result<Self, PairingInvalidScalar> {
         let mut bytes: [u8; Self::BYTES] = bytes
@@ -745,6 +767,73 @@ impl From<G2Projective> for G2Prepared {
     }
 }
 
+/// Expand an input to an arbitrary length pseudo-random byte string
+///
+/// This is expand_message_xmd from draft-irtf-cfrg-hash-to-curve-16
+/// instantiated with SHA-256, including the handling of domain
+/// separators longer than 255 bytes.
+///
+/// # Panics
+/// Panics if `output_len` is larger than 8160 bytes, the maximum
+/// output length supported by expand_message_xmd with SHA-256.
+pub fn xmd(input: &[u8], domain_sep: &[u8], output_len: usize) -> Vec<u8> {
+    use sha2::{Digest, Sha256};
+
+    const B_IN_BYTES: usize = 32;
+    const S_IN_BYTES: usize = 64;
+
+    let ell = (output_len + B_IN_BYTES - 1) / B_IN_BYTES;
+    assert!(ell <= 255, "Requested xmd output is too long");
+
+    let oversize_dst;
+    let domain_sep = if domain_sep.len() > 255 {
+        let mut h = Sha256::new();
+        h.update(b"H2C-OVERSIZE-DST-");
+        h.update(domain_sep);
+        oversize_dst = h.finalize();
+        &oversize_dst[..]
+    } else {
+        domain_sep
+    };
+
+    let mut dst_prime = domain_sep.to_vec();
+    dst_prime.push(domain_sep.len() as u8);
+
+    let mut h = Sha256::new();
+    h.update([0u8; S_IN_BYTES]);
+    h.update(input);
+    h.update((output_len as u16).to_be_bytes());
+    h.update([0u8]);
+    h.update(&dst_prime);
+    let b_0 = h.finalize();
+
+    let mut output = Vec::with_capacity(ell * B_IN_BYTES);
+
+    let mut h = Sha256::new();
+    h.update(b_0);
+    h.update([1u8]);
+    h.update(&dst_prime);
+    let mut b_i = h.finalize();
+    output.extend_from_slice(&b_i);
+
+    for i in 2..=ell {
+        let mut h = Sha256::new();
+        h.update(
+            b_0.iter()
+                .zip(b_i.iter())
+                .map(|(x, y)| x ^ y)
+                .collect::<Vec<u8>>(),
+        );
+        h.update([i as u8]);
+        h.update(&dst_prime);
+        b_i = h.finalize();
+        output.extend_from_slice(&b_i);
+    }
+
+    output.truncate(output_len);
+    output
+}
+
 /// Perform BLS signature verification
 ///
 /// The naive version of this function requires two pairings, but it
@@ -726,6 +726,41 @@ fn test_hash_to_g1_matches_draft() {
     );
 }
 
+#[test]
+fn test_xmd_matches_draft() {
+    // These are the test vectors from draft-irtf-cfrg-hash-to-curve-16 section K.1
+
+    let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+
+    assert_eq!(
+        hex::encode(xmd(b"", &dst[..], 0x20)),
+        "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235"
+    );
+
+    assert_eq!(
+        hex::encode(xmd(b"abc", &dst[..], 0x20)),
+        "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615"
+    );
+
+    assert_eq!(
+        hex::encode(xmd(b"abc", &dst[..], 0x80)),
+        "abba86a6129e366fc877aab32fc4ffc70120d8996c88aee2fe4b32d6c7b6437a647e6c3163d40b76a73cf6a5674ef1d890f95b664ee0afa5359a5c4e07985635bbecbac65d747d3d2da7ec2b8221b17b0ca9dc8a1ac1c07ea6a1e60583e2cb00058e77b7b72a298425cd1b941ad4ec65e8afc50303a22c0f99b0509b4c895f40"
+    );
+}
+
+#[test]
+fn test_hash_to_scalar_generates_expected_values() {
+    scalar_test_encoding(
+        Scalar::hash(b"test-dst", b"abc"),
+        "6f40933ecbd959c3e170f36def95a05472c8b6fcf038bea834f397c5b5dfc1b3",
+    );
+
+    assert_ne!(
+        Scalar::hash(b"test-dst", b"abc"),
+        Scalar::hash(b"test-dst2", b"abc")
+    );
+}
+
 #[test]
 fn test_hash_to_g2_matches_draft() {
     /*
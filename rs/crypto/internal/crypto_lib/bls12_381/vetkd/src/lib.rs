@@ -0,0 +1,562 @@
+//! Verifiably Encrypted Threshold Key Derivation (vetKD)
+//!
+//! vetKD allows a canister to obtain a key derived from a subnet's
+//! threshold BLS12-381 key, encrypted to a transport key chosen by
+//! the user, without any node (or the canister) ever learning the
+//! derived key in plaintext.
+//!
+//! The master key is a threshold BLS12-381 key generated with the
+//! non-interactive DKG, with secret key `sk` and public key
+//! `pk = g2*sk`. For a canister id, a derivation path and a
+//! derivation id, the derived key is the BLS signature (using the
+//! message augmentation scheme) of the derivation id under the
+//! derived public key `dpk = pk + g2*t`, where `t` is a hash of the
+//! canister id and the derivation path.
+//!
+//! Given a transport public key `tpk = g1*tsk`, each node `i` holding
+//! a share `sk_i` of the master secret key creates an encrypted key
+//! share `(g1*r, g2*r, tpk*r + H*(sk_i + t))` for a random `r`, where
+//! `H` is the hash of the derivation id onto G1. Shares can be
+//! publicly verified against the node's public key share using
+//! pairings, and any threshold of valid shares can be combined into
+//! an encrypted key by Lagrange interpolation. The holder of the
+//! transport secret key can decrypt it, and verify the resulting key
+//! against the derived public key.
+#![forbid(unsafe_code)]
+#![forbid(missing_docs)]
+#![warn(rust_2018_idioms)]
+#![warn(future_incompatible)]
+
+use ic_crypto_internal_bls12_381_type::{
+    verify_bls_signature, xmd, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt,
+    Scalar,
+};
+use rand::{CryptoRng, RngCore};
+use std::collections::BTreeMap;
+use zeroize::Zeroize;
+
+/// The index of a node in the threshold key's committee
+pub type NodeIndex = u32;
+
+/// The derivation path of a vetKD key
+///
+/// This consists of the id of the canister requesting the key,
+/// followed by the derivation path provided by the canister.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DerivationPath {
+    delta: Scalar,
+}
+
+impl DerivationPath {
+    /// Create a derivation path for a canister
+    pub fn new(canister_id: &[u8], extra_path: &[Vec<u8>]) -> Self {
+        // Each element is length prefixed so that distinct paths can
+        // never result in the same hash input
+        let mut input = Vec::new();
+        input.extend_from_slice(&(canister_id.len() as u64).to_be_bytes());
+        input.extend_from_slice(canister_id);
+
+        for path in extra_path {
+            input.extend_from_slice(&(path.len() as u64).to_be_bytes());
+            input.extend_from_slice(path);
+        }
+
+        let delta = Scalar::hash(b"ic-crypto-vetkd-bls12-381-derivation-path", &input);
+
+        Self { delta }
+    }
+
+    fn delta(&self) -> &Scalar {
+        &self.delta
+    }
+}
+
+/// Error returned if a public key encoding is invalid
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PublicKeyDeserializationError {
+    /// The public key is not a valid encoding of a point of the group
+    InvalidPublicKey,
+}
+
+/// A public key derived from a master public key
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DerivedPublicKey {
+    point: G2Affine,
+}
+
+impl DerivedPublicKey {
+    /// The length of the serialized encoding of this type
+    pub const BYTES: usize = G2Affine::BYTES;
+
+    /// Derive a public key from a master public key and a derivation path
+    ///
+    /// Since derivation is linear, the same function can be used to
+    /// derive a node's share of the derived public key from the node's
+    /// share of the master public key.
+    pub fn compute_derived_key(master_pk: &G2Affine, derivation_path: &DerivationPath) -> Self {
+        let point = G2Projective::from(master_pk) + G2Affine::generator() * derivation_path.delta();
+        Self {
+            point: G2Affine::from(point),
+        }
+    }
+
+    /// Return the derived public key as a point of G2
+    pub fn point(&self) -> &G2Affine {
+        &self.point
+    }
+
+    /// Serialize the derived public key
+    pub fn serialize(&self) -> [u8; Self::BYTES] {
+        self.point.serialize()
+    }
+
+    /// Deserialize a derived public key
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, PublicKeyDeserializationError> {
+        let point = G2Affine::deserialize(bytes)
+            .map_err(|_| PublicKeyDeserializationError::InvalidPublicKey)?;
+        Ok(Self { point })
+    }
+}
+
+/// Hash the derivation id onto G1, following the message augmentation
+/// scheme of draft-irtf-cfrg-bls-signature-05
+fn augmented_hash_to_g1(derived_public_key: &G2Affine, derivation_id: &[u8]) -> G1Affine {
+    let domain_sep = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_AUG_";
+
+    let mut input = Vec::with_capacity(G2Affine::BYTES + derivation_id.len());
+    input.extend_from_slice(&derived_public_key.serialize());
+    input.extend_from_slice(derivation_id);
+
+    G1Affine::hash(domain_sep, &input)
+}
+
+/// Check that (c1, c2, c3) is a valid encryption of the BLS signature
+/// of `msg` under `public_key` for the transport key `transport_pk`
+fn is_valid_ciphertext(
+    c1: &G1Affine,
+    c2: &G2Affine,
+    c3: &G1Affine,
+    transport_pk: &G1Affine,
+    msg: &G1Affine,
+    public_key: &G2Affine,
+) -> bool {
+    let c2_prepared = G2Prepared::from(c2);
+
+    // e(c1, g2) == e(g1, c2)
+    let g1_neg = G1Affine::generator().neg();
+    let c1_c2 = Gt::multipairing(&[(c1, &G2Prepared::generator()), (&g1_neg, &c2_prepared)]);
+
+    // e(c3, g2) == e(tpk, c2) * e(msg, pk)
+    let c3_c2_msg = Gt::multipairing(&[
+        (c3, &G2Prepared::neg_generator()),
+        (transport_pk, &c2_prepared),
+        (msg, &G2Prepared::from(public_key)),
+    ]);
+
+    c1_c2.is_identity() && c3_c2_msg.is_identity()
+}
+
+const CIPHERTEXT_BYTES: usize = G1Affine::BYTES + G2Affine::BYTES + G1Affine::BYTES;
+
+fn serialize_ciphertext(c1: &G1Affine, c2: &G2Affine, c3: &G1Affine) -> [u8; CIPHERTEXT_BYTES] {
+    let mut output = [0u8; CIPHERTEXT_BYTES];
+    let (c1_bytes, rest) = output.split_at_mut(G1Affine::BYTES);
+    let (c2_bytes, c3_bytes) = rest.split_at_mut(G2Affine::BYTES);
+    c1_bytes.copy_from_slice(&c1.serialize());
+    c2_bytes.copy_from_slice(&c2.serialize());
+    c3_bytes.copy_from_slice(&c3.serialize());
+    output
+}
+
+fn deserialize_ciphertext(bytes: &[u8]) -> Option<(G1Affine, G2Affine, G1Affine)> {
+    if bytes.len() != CIPHERTEXT_BYTES {
+        return None;
+    }
+
+    let (c1_bytes, rest) = bytes.split_at(G1Affine::BYTES);
+    let (c2_bytes, c3_bytes) = rest.split_at(G2Affine::BYTES);
+
+    let c1 = G1Affine::deserialize(c1_bytes).ok()?;
+    let c2 = G2Affine::deserialize(c2_bytes).ok()?;
+    let c3 = G1Affine::deserialize(c3_bytes).ok()?;
+
+    Some((c1, c2, c3))
+}
+
+/// Error returned if a transport public key encoding is invalid
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TransportPublicKeyDeserializationError {
+    /// The key is not a valid encoding of a point of the group, or is the identity
+    InvalidPublicKey,
+}
+
+/// A transport public key, to which derived keys are encrypted
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransportPublicKey {
+    point: G1Affine,
+}
+
+impl TransportPublicKey {
+    /// The length of the serialized encoding of this type
+    pub const BYTES: usize = G1Affine::BYTES;
+
+    /// Serialize the transport public key
+    pub fn serialize(&self) -> [u8; Self::BYTES] {
+        self.point.serialize()
+    }
+
+    /// Deserialize a transport public key
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, TransportPublicKeyDeserializationError> {
+        let point = G1Affine::deserialize(bytes)
+            .map_err(|_| TransportPublicKeyDeserializationError::InvalidPublicKey)?;
+
+        // The identity would result in the derived key not being encrypted
+        if point.is_identity() {
+            return Err(TransportPublicKeyDeserializationError::InvalidPublicKey);
+        }
+
+        Ok(Self { point })
+    }
+
+    /// Return the transport public key as a point of G1
+    pub fn point(&self) -> &G1Affine {
+        &self.point
+    }
+}
+
+/// Error returned if a transport secret key encoding is invalid
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TransportSecretKeyDeserializationError {
+    /// The key is not a valid encoding of a scalar
+    InvalidSecretKey,
+}
+
+/// A transport secret key, held by the user requesting a derived key
+pub struct TransportSecretKey {
+    secret_key: Scalar,
+}
+
+impl Drop for TransportSecretKey {
+    fn drop(&mut self) {
+        self.secret_key.zeroize();
+    }
+}
+
+impl TransportSecretKey {
+    /// The length of the serialized encoding of this type
+    pub const BYTES: usize = Scalar::BYTES;
+
+    /// Generate a new random transport secret key
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Self {
+            secret_key: Scalar::random(rng),
+        }
+    }
+
+    /// Serialize the transport secret key
+    pub fn serialize(&self) -> [u8; Self::BYTES] {
+        self.secret_key.serialize()
+    }
+
+    /// Deserialize a transport secret key
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, TransportSecretKeyDeserializationError> {
+        let secret_key = Scalar::deserialize(bytes)
+            .map_err(|_| TransportSecretKeyDeserializationError::InvalidSecretKey)?;
+        Ok(Self { secret_key })
+    }
+
+    /// Return the public key associated with this secret key
+    pub fn public_key(&self) -> TransportPublicKey {
+        TransportPublicKey {
+            point: G1Affine::from(G1Affine::generator() * self.secret_key),
+        }
+    }
+
+    /// Decrypt an encrypted key
+    ///
+    /// Returns None if the decrypted key is not a valid BLS signature
+    /// of the derivation id under the derived public key.
+    pub fn decrypt(
+        &self,
+        encrypted_key: &EncryptedKey,
+        derived_public_key: &DerivedPublicKey,
+        derivation_id: &[u8],
+    ) -> Option<DerivedKey> {
+        let key = G1Affine::from(
+            G1Projective::from(&encrypted_key.c3) - encrypted_key.c1 * self.secret_key,
+        );
+
+        let msg = augmented_hash_to_g1(&derived_public_key.point, derivation_id);
+
+        if verify_bls_signature(&key, &derived_public_key.point, &msg) {
+            Some(DerivedKey { key })
+        } else {
+            None
+        }
+    }
+}
+
+/// A decrypted derived key
+///
+/// This is a BLS signature of the derivation id under the derived
+/// public key, and thus can be verified by anyone knowing the derived
+/// public key. Use `derive_symmetric_key` to obtain key material.
+pub struct DerivedKey {
+    key: G1Affine,
+}
+
+impl Drop for DerivedKey {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+impl DerivedKey {
+    /// The length of the serialized encoding of this type
+    pub const BYTES: usize = G1Affine::BYTES;
+
+    /// Serialize the derived key
+    pub fn serialize(&self) -> [u8; Self::BYTES] {
+        self.key.serialize()
+    }
+
+    /// Derive a symmetric key of the requested length from the derived key
+    ///
+    /// Different `domain_sep` values result in independent keys.
+    pub fn derive_symmetric_key(&self, domain_sep: &[u8], output_len: usize) -> Vec<u8> {
+        xmd(&self.key.serialize(), domain_sep, output_len)
+    }
+}
+
+/// Error returned if an encrypted key or encrypted key share encoding is invalid
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EncryptedKeyDeserializationError {
+    /// The encoding has the wrong length or contains an invalid point
+    InvalidEncryptedKey,
+}
+
+/// A node's share of an encrypted key
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EncryptedKeyShare {
+    c1: G1Affine,
+    c2: G2Affine,
+    c3: G1Affine,
+}
+
+impl EncryptedKeyShare {
+    /// The length of the serialized encoding of this type
+    pub const BYTES: usize = CIPHERTEXT_BYTES;
+
+    /// Create an encrypted key share
+    ///
+    /// # Arguments
+    /// * `master_pk` - the public key of the threshold key
+    /// * `node_sk` - this node's share of the threshold key
+    /// * `transport_pk` - the transport public key of the user
+    /// * `derivation_path` - the derivation path of the requested key
+    /// * `derivation_id` - the derivation id of the requested key
+    pub fn create<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        master_pk: &G2Affine,
+        node_sk: &Scalar,
+        transport_pk: &TransportPublicKey,
+        derivation_path: &DerivationPath,
+        derivation_id: &[u8],
+    ) -> Self {
+        let dpk = DerivedPublicKey::compute_derived_key(master_pk, derivation_path);
+        let msg = augmented_hash_to_g1(&dpk.point, derivation_id);
+
+        let r = Scalar::random(rng);
+        let mut derived_sk = *node_sk + *derivation_path.delta();
+
+        let c1 = G1Affine::from(G1Affine::generator() * r);
+        let c2 = G2Affine::from(G2Affine::generator() * r);
+        let c3 = G1Affine::from(transport_pk.point * r + msg * derived_sk);
+
+        derived_sk.zeroize();
+
+        Self { c1, c2, c3 }
+    }
+
+    /// Check the validity of an encrypted key share
+    ///
+    /// # Arguments
+    /// * `master_pk` - the public key of the threshold key
+    /// * `master_pk_share` - the public key share of the node which created the share
+    /// * `transport_pk` - the transport public key of the user
+    /// * `derivation_path` - the derivation path of the requested key
+    /// * `derivation_id` - the derivation id of the requested key
+    pub fn is_valid(
+        &self,
+        master_pk: &G2Affine,
+        master_pk_share: &G2Affine,
+        transport_pk: &TransportPublicKey,
+        derivation_path: &DerivationPath,
+        derivation_id: &[u8],
+    ) -> bool {
+        let dpk = DerivedPublicKey::compute_derived_key(master_pk, derivation_path);
+        let dpk_share = DerivedPublicKey::compute_derived_key(master_pk_share, derivation_path);
+        let msg = augmented_hash_to_g1(&dpk.point, derivation_id);
+
+        is_valid_ciphertext(
+            &self.c1,
+            &self.c2,
+            &self.c3,
+            &transport_pk.point,
+            &msg,
+            &dpk_share.point,
+        )
+    }
+
+    /// Serialize the encrypted key share
+    pub fn serialize(&self) -> [u8; Self::BYTES] {
+        serialize_ciphertext(&self.c1, &self.c2, &self.c3)
+    }
+
+    /// Deserialize an encrypted key share
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, EncryptedKeyDeserializationError> {
+        let (c1, c2, c3) = deserialize_ciphertext(bytes)
+            .ok_or(EncryptedKeyDeserializationError::InvalidEncryptedKey)?;
+        Ok(Self { c1, c2, c3 })
+    }
+}
+
+/// Error returned if combining encrypted key shares fails
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EncryptedKeyCombinationError {
+    /// Fewer shares than the reconstruction threshold were provided
+    InsufficientShares,
+    /// The combined encrypted key is invalid, because one of the shares was invalid
+    InvalidShares,
+}
+
+/// An encrypted derived key
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EncryptedKey {
+    c1: G1Affine,
+    c2: G2Affine,
+    c3: G1Affine,
+}
+
+impl EncryptedKey {
+    /// The length of the serialized encoding of this type
+    pub const BYTES: usize = CIPHERTEXT_BYTES;
+
+    /// Combine encrypted key shares into an encrypted key
+    ///
+    /// The first `reconstruction_threshold` shares are used. The
+    /// shares should have been checked with
+    /// `EncryptedKeyShare::is_valid` beforehand; if any of them is
+    /// invalid, combination fails with `InvalidShares`.
+    pub fn combine(
+        shares: &BTreeMap<NodeIndex, EncryptedKeyShare>,
+        reconstruction_threshold: usize,
+        master_pk: &G2Affine,
+        transport_pk: &TransportPublicKey,
+        derivation_path: &DerivationPath,
+        derivation_id: &[u8],
+    ) -> Result<Self, EncryptedKeyCombinationError> {
+        if reconstruction_threshold == 0 || shares.len() < reconstruction_threshold {
+            return Err(EncryptedKeyCombinationError::InsufficientShares);
+        }
+
+        let shares = shares
+            .iter()
+            .take(reconstruction_threshold)
+            .collect::<Vec<_>>();
+
+        let indices = shares.iter().map(|(i, _)| **i).collect::<Vec<_>>();
+        let coefficients = lagrange_coefficients_at_zero(&indices);
+
+        let mut c1 = G1Projective::identity();
+        let mut c2 = G2Projective::identity();
+        let mut c3 = G1Projective::identity();
+
+        for ((_, share), coefficient) in shares.iter().zip(coefficients.iter()) {
+            c1 += share.c1 * coefficient;
+            c2 += share.c2 * coefficient;
+            c3 += share.c3 * coefficient;
+        }
+
+        let encrypted_key = Self {
+            c1: G1Affine::from(c1),
+            c2: G2Affine::from(c2),
+            c3: G1Affine::from(c3),
+        };
+
+        if !encrypted_key.is_valid(master_pk, transport_pk, derivation_path, derivation_id) {
+            return Err(EncryptedKeyCombinationError::InvalidShares);
+        }
+
+        Ok(encrypted_key)
+    }
+
+    /// Check the validity of an encrypted key
+    ///
+    /// This allows anyone to check that the encrypted key decrypts to
+    /// the derived key, without knowledge of the transport secret key.
+    pub fn is_valid(
+        &self,
+        master_pk: &G2Affine,
+        transport_pk: &TransportPublicKey,
+        derivation_path: &DerivationPath,
+        derivation_id: &[u8],
+    ) -> bool {
+        let dpk = DerivedPublicKey::compute_derived_key(master_pk, derivation_path);
+        let msg = augmented_hash_to_g1(&dpk.point, derivation_id);
+
+        is_valid_ciphertext(
+            &self.c1,
+            &self.c2,
+            &self.c3,
+            &transport_pk.point,
+            &msg,
+            &dpk.point,
+        )
+    }
+
+    /// Serialize the encrypted key
+    pub fn serialize(&self) -> [u8; Self::BYTES] {
+        serialize_ciphertext(&self.c1, &self.c2, &self.c3)
+    }
+
+    /// Deserialize an encrypted key
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, EncryptedKeyDeserializationError> {
+        let (c1, c2, c3) = deserialize_ciphertext(bytes)
+            .ok_or(EncryptedKeyDeserializationError::InvalidEncryptedKey)?;
+        Ok(Self { c1, c2, c3 })
+    }
+}
+
+/// Compute the Lagrange coefficients at zero for the given node indices
+///
+/// As in the threshold BLS12-381 implementation, the share of node
+/// `i` is the evaluation of the polynomial at `x = i + 1`. The indices
+/// must be distinct, which is guaranteed when they are the keys of a map.
+fn lagrange_coefficients_at_zero(indices: &[NodeIndex]) -> Vec<Scalar> {
+    let x_values = indices
+        .iter()
+        .map(|i| Scalar::from_u64(*i as u64 + 1))
+        .collect::<Vec<_>>();
+
+    let mut coefficients = Vec::with_capacity(x_values.len());
+
+    for (i, x_i) in x_values.iter().enumerate() {
+        let mut numerator = Scalar::one();
+        let mut denominator = Scalar::one();
+
+        for (j, x_j) in x_values.iter().enumerate() {
+            if i != j {
+                numerator *= x_j;
+                denominator *= *x_j - *x_i;
+            }
+        }
+
+        // The x values are distinct and smaller than the group order,
+        // so the denominator is never zero
+        let inv = denominator.inverse().unwrap_or_else(Scalar::zero);
+        coefficients.push(numerator * inv);
+    }
+
+    coefficients
+}
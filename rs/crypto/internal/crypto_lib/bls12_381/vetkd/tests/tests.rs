@@ -0,0 +1,416 @@
+use ic_crypto_internal_bls12_381_type::{G1Affine, G2Affine, Scalar};
+use ic_crypto_internal_bls12_381_vetkd::*;
+use rand::{CryptoRng, Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::collections::BTreeMap;
+
+fn seeded_rng() -> ChaCha20Rng {
+    let mut thread_rng = rand::thread_rng();
+    let seed = thread_rng.gen::<u64>();
+    println!("RNG seed {}", seed);
+    ChaCha20Rng::seed_from_u64(seed)
+}
+
+/// A threshold key, shared among nodes using a random polynomial
+struct ThresholdKey {
+    master_pk: G2Affine,
+    node_sks: Vec<Scalar>,
+    node_pks: Vec<G2Affine>,
+    threshold: usize,
+}
+
+impl ThresholdKey {
+    fn new<R: RngCore + CryptoRng>(rng: &mut R, nodes: usize, threshold: usize) -> Self {
+        let poly = (0..threshold)
+            .map(|_| Scalar::random(rng))
+            .collect::<Vec<_>>();
+
+        let evaluate = |x: u64| {
+            let x = Scalar::from_u64(x);
+            let mut result = Scalar::zero();
+            for coefficient in poly.iter().rev() {
+                result = result * x + *coefficient;
+            }
+            result
+        };
+
+        let node_sks = (0..nodes)
+            .map(|i| evaluate(i as u64 + 1))
+            .collect::<Vec<_>>();
+        let node_pks = node_sks
+            .iter()
+            .map(|sk| G2Affine::from(G2Affine::generator() * sk))
+            .collect();
+
+        Self {
+            master_pk: G2Affine::from(G2Affine::generator() * poly[0]),
+            node_sks,
+            node_pks,
+            threshold,
+        }
+    }
+
+    fn create_shares<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        transport_pk: &TransportPublicKey,
+        derivation_path: &DerivationPath,
+        derivation_id: &[u8],
+    ) -> BTreeMap<NodeIndex, EncryptedKeyShare> {
+        self.node_sks
+            .iter()
+            .enumerate()
+            .map(|(i, sk)| {
+                let share = EncryptedKeyShare::create(
+                    rng,
+                    &self.master_pk,
+                    sk,
+                    transport_pk,
+                    derivation_path,
+                    derivation_id,
+                );
+                (i as NodeIndex, share)
+            })
+            .collect()
+    }
+}
+
+fn random_subset<R: RngCore, T: Clone>(
+    rng: &mut R,
+    shares: &BTreeMap<NodeIndex, T>,
+    include: usize,
+) -> BTreeMap<NodeIndex, T> {
+    assert!(include <= shares.len());
+
+    let keys = shares.keys().collect::<Vec<_>>();
+    let mut result = BTreeMap::new();
+
+    while result.len() != include {
+        let key_to_add = keys[rng.gen::<usize>() % keys.len()];
+        result.insert(*key_to_add, shares[key_to_add].clone());
+    }
+
+    result
+}
+
+#[test]
+fn should_vetkd_protocol_work() {
+    let rng = &mut seeded_rng();
+
+    let nodes = 10;
+    let threshold = 4;
+    let key = ThresholdKey::new(rng, nodes, threshold);
+
+    let derivation_path = DerivationPath::new(&[1, 2, 3, 4], &[b"path".to_vec()]);
+    let derivation_id = rng.gen::<[u8; 32]>();
+
+    let tsk = TransportSecretKey::generate(rng);
+    let tpk = tsk.public_key();
+
+    let shares = key.create_shares(rng, &tpk, &derivation_path, &derivation_id);
+
+    for (node_index, share) in &shares {
+        assert!(share.is_valid(
+            &key.master_pk,
+            &key.node_pks[*node_index as usize],
+            &tpk,
+            &derivation_path,
+            &derivation_id
+        ));
+    }
+
+    let dpk = DerivedPublicKey::compute_derived_key(&key.master_pk, &derivation_path);
+
+    let mut derived_keys = vec![];
+
+    for include in 0..=nodes {
+        let subset = random_subset(rng, &shares, include);
+
+        let combined = EncryptedKey::combine(
+            &subset,
+            key.threshold,
+            &key.master_pk,
+            &tpk,
+            &derivation_path,
+            &derivation_id,
+        );
+
+        if include < threshold {
+            assert_eq!(
+                combined,
+                Err(EncryptedKeyCombinationError::InsufficientShares)
+            );
+        } else {
+            let ek = combined.expect("Combination failed");
+            assert!(ek.is_valid(&key.master_pk, &tpk, &derivation_path, &derivation_id));
+
+            let derived_key = tsk
+                .decrypt(&ek, &dpk, &derivation_id)
+                .expect("Decryption failed");
+            derived_keys.push(derived_key.serialize());
+        }
+    }
+
+    // Every subset of shares results in the same derived key
+    assert!(derived_keys.windows(2).all(|w| w[0] == w[1]));
+}
+
+#[test]
+fn should_derived_key_not_depend_on_transport_key() {
+    let rng = &mut seeded_rng();
+
+    let key = ThresholdKey::new(rng, 4, 2);
+    let derivation_path = DerivationPath::new(b"canister", &[]);
+    let derivation_id = b"user";
+    let dpk = DerivedPublicKey::compute_derived_key(&key.master_pk, &derivation_path);
+
+    let mut derive_key = || {
+        let tsk = TransportSecretKey::generate(rng);
+        let tpk = tsk.public_key();
+        let shares = key.create_shares(rng, &tpk, &derivation_path, derivation_id);
+        let ek = EncryptedKey::combine(
+            &shares,
+            key.threshold,
+            &key.master_pk,
+            &tpk,
+            &derivation_path,
+            derivation_id,
+        )
+        .expect("Combination failed");
+        let derived_key = tsk
+            .decrypt(&ek, &dpk, derivation_id)
+            .expect("Decryption failed");
+        (ek.serialize(), derived_key.serialize())
+    };
+
+    let (ek1, k1) = derive_key();
+    let (ek2, k2) = derive_key();
+
+    assert_ne!(ek1, ek2);
+    assert_eq!(k1, k2);
+}
+
+#[test]
+fn should_derived_keys_differ_by_path_and_id() {
+    let rng = &mut seeded_rng();
+
+    let key = ThresholdKey::new(rng, 3, 2);
+    let tsk = TransportSecretKey::generate(rng);
+    let tpk = tsk.public_key();
+
+    let mut derive_key = |derivation_path: &DerivationPath, derivation_id: &[u8]| {
+        let shares = key.create_shares(rng, &tpk, derivation_path, derivation_id);
+        let ek = EncryptedKey::combine(
+            &shares,
+            key.threshold,
+            &key.master_pk,
+            &tpk,
+            derivation_path,
+            derivation_id,
+        )
+        .expect("Combination failed");
+        let dpk = DerivedPublicKey::compute_derived_key(&key.master_pk, derivation_path);
+        tsk.decrypt(&ek, &dpk, derivation_id)
+            .expect("Decryption failed")
+            .serialize()
+    };
+
+    let path = DerivationPath::new(b"canister", &[b"a".to_vec()]);
+
+    let k = derive_key(&path, b"id");
+    assert_eq!(k, derive_key(&path, b"id"));
+    assert_ne!(k, derive_key(&path, b"id2"));
+    assert_ne!(k, derive_key(&DerivationPath::new(b"canister", &[]), b"id"));
+    assert_ne!(
+        k,
+        derive_key(&DerivationPath::new(b"canister2", &[b"a".to_vec()]), b"id")
+    );
+
+    // Components are length prefixed, so moving bytes between them changes the key
+    assert_ne!(
+        k,
+        derive_key(&DerivationPath::new(b"canistera", &[]), b"id")
+    );
+}
+
+#[test]
+fn should_reject_shares_of_other_nodes_and_invalid_shares() {
+    let rng = &mut seeded_rng();
+
+    let key = ThresholdKey::new(rng, 4, 3);
+    let derivation_path = DerivationPath::new(b"canister", &[]);
+    let derivation_id = b"user";
+    let tsk = TransportSecretKey::generate(rng);
+    let tpk = tsk.public_key();
+
+    let mut shares = key.create_shares(rng, &tpk, &derivation_path, derivation_id);
+
+    // A share does not verify for another node's public key share
+    assert!(!shares[&0].is_valid(
+        &key.master_pk,
+        &key.node_pks[1],
+        &tpk,
+        &derivation_path,
+        derivation_id
+    ));
+
+    // A share does not verify for another derivation id or transport key
+    assert!(!shares[&0].is_valid(
+        &key.master_pk,
+        &key.node_pks[0],
+        &tpk,
+        &derivation_path,
+        b"other user"
+    ));
+    let other_tpk = TransportSecretKey::generate(rng).public_key();
+    assert!(!shares[&0].is_valid(
+        &key.master_pk,
+        &key.node_pks[0],
+        &other_tpk,
+        &derivation_path,
+        derivation_id
+    ));
+
+    // Replacing a share with one created for another derivation id
+    // makes the combination fail
+    let other_shares = key.create_shares(rng, &tpk, &derivation_path, b"other user");
+    shares.insert(0, other_shares[&0].clone());
+
+    assert_eq!(
+        EncryptedKey::combine(
+            &shares,
+            key.threshold,
+            &key.master_pk,
+            &tpk,
+            &derivation_path,
+            derivation_id,
+        ),
+        Err(EncryptedKeyCombinationError::InvalidShares)
+    );
+}
+
+#[test]
+fn should_decryption_fail_with_wrong_inputs() {
+    let rng = &mut seeded_rng();
+
+    let key = ThresholdKey::new(rng, 3, 2);
+    let derivation_path = DerivationPath::new(b"canister", &[]);
+    let derivation_id = b"user";
+    let tsk = TransportSecretKey::generate(rng);
+    let tpk = tsk.public_key();
+
+    let shares = key.create_shares(rng, &tpk, &derivation_path, derivation_id);
+    let ek = EncryptedKey::combine(
+        &shares,
+        key.threshold,
+        &key.master_pk,
+        &tpk,
+        &derivation_path,
+        derivation_id,
+    )
+    .expect("Combination failed");
+
+    let dpk = DerivedPublicKey::compute_derived_key(&key.master_pk, &derivation_path);
+    let other_dpk = DerivedPublicKey::compute_derived_key(
+        &key.master_pk,
+        &DerivationPath::new(b"other canister", &[]),
+    );
+
+    assert!(tsk.decrypt(&ek, &dpk, derivation_id).is_some());
+    assert!(tsk.decrypt(&ek, &dpk, b"other user").is_none());
+    assert!(tsk.decrypt(&ek, &other_dpk, derivation_id).is_none());
+
+    let other_tsk = TransportSecretKey::generate(rng);
+    assert!(other_tsk.decrypt(&ek, &dpk, derivation_id).is_none());
+}
+
+#[test]
+fn should_serialization_round_trip() {
+    let rng = &mut seeded_rng();
+
+    let key = ThresholdKey::new(rng, 3, 2);
+    let derivation_path = DerivationPath::new(b"canister", &[b"path".to_vec()]);
+    let derivation_id = b"user";
+
+    let tsk = TransportSecretKey::generate(rng);
+    let tsk2 = TransportSecretKey::deserialize(&tsk.serialize()).expect("Invalid secret key");
+    assert_eq!(tsk.public_key(), tsk2.public_key());
+
+    let tpk = tsk.public_key();
+    assert_eq!(
+        TransportPublicKey::deserialize(&tpk.serialize()),
+        Ok(tpk.clone())
+    );
+
+    let dpk = DerivedPublicKey::compute_derived_key(&key.master_pk, &derivation_path);
+    assert_eq!(
+        DerivedPublicKey::deserialize(&dpk.serialize()),
+        Ok(dpk.clone())
+    );
+
+    let shares = key.create_shares(rng, &tpk, &derivation_path, derivation_id);
+    for share in shares.values() {
+        assert_eq!(
+            EncryptedKeyShare::deserialize(&share.serialize()),
+            Ok(share.clone())
+        );
+    }
+
+    let ek = EncryptedKey::combine(
+        &shares,
+        key.threshold,
+        &key.master_pk,
+        &tpk,
+        &derivation_path,
+        derivation_id,
+    )
+    .expect("Combination failed");
+    let ek2 = EncryptedKey::deserialize(&ek.serialize()).expect("Invalid encrypted key");
+    assert_eq!(ek, ek2);
+
+    assert_eq!(
+        EncryptedKey::deserialize(&ek.serialize()[1..]),
+        Err(EncryptedKeyDeserializationError::InvalidEncryptedKey)
+    );
+}
+
+#[test]
+fn should_reject_identity_transport_public_key() {
+    assert_eq!(
+        TransportPublicKey::deserialize(&G1Affine::identity().serialize()),
+        Err(TransportPublicKeyDeserializationError::InvalidPublicKey)
+    );
+}
+
+#[test]
+fn should_derive_independent_symmetric_keys() {
+    let rng = &mut seeded_rng();
+
+    let key = ThresholdKey::new(rng, 3, 2);
+    let derivation_path = DerivationPath::new(b"canister", &[]);
+    let derivation_id = b"user";
+    let tsk = TransportSecretKey::generate(rng);
+    let tpk = tsk.public_key();
+
+    let shares = key.create_shares(rng, &tpk, &derivation_path, derivation_id);
+    let ek = EncryptedKey::combine(
+        &shares,
+        key.threshold,
+        &key.master_pk,
+        &tpk,
+        &derivation_path,
+        derivation_id,
+    )
+    .expect("Combination failed");
+    let dpk = DerivedPublicKey::compute_derived_key(&key.master_pk, &derivation_path);
+    let derived_key = tsk
+        .decrypt(&ek, &dpk, derivation_id)
+        .expect("Decryption failed");
+
+    let k1 = derived_key.derive_symmetric_key(b"aes-256-gcm", 32);
+    let k2 = derived_key.derive_symmetric_key(b"chacha20-poly1305", 32);
+
+    assert_eq!(k1.len(), 32);
+    assert_ne!(k1, k2);
+    assert_eq!(derived_key.derive_symmetric_key(b"aes-256-gcm", 32), k1);
+}
@@ -16,6 +16,7 @@ fn prime_for(curve: EccCurveType) -> BigUint {
             16,
         )
         .unwrap(),
+        EccCurveType::Ed25519 => panic!("Field arithmetic is not supported for Ed25519"),
     }
 }
 
@@ -38,7 +39,7 @@ fn fe_fuzz_run(curve_type: EccCurveType, data: &[u8]) -> Result<(), ThresholdEcd
 
     assert_eq!(hex::encode(few.as_bytes()), format_bn(&refw));
 
-    let one = EccFieldElement::one(curve_type);
+    let one = EccFieldElement::one(curve_type)?;
 
     let fe1_bits = &data[..32];
     let fe2_bits = &data[32..];
@@ -16,6 +16,11 @@ fn prime_for(curve: EccCurveType) -> BigUint {
             16,
         )
         .unwrap(),
+        EccCurveType::Ed25519 => BigUint::parse_bytes(
+            b"1000000000000000000000000000000014DEF9DEA2F79CD65812631A5CF5D3ED",
+            16,
+        )
+        .unwrap(),
     }
 }
 
@@ -46,4 +51,5 @@ fuzz_target!(|data: &[u8]| {
     }
     let _ = scalar_fuzz_run(EccCurveType::K256, data);
     let _ = scalar_fuzz_run(EccCurveType::P256, data);
+    let _ = scalar_fuzz_run(EccCurveType::Ed25519, data);
 });
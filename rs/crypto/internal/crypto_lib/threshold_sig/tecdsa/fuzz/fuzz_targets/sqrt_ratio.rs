@@ -24,7 +24,7 @@ fn sqrt_ratio_generic(
     let curve_type = u.curve_type();
 
     // Generic but slower codepath for other primes
-    let z = EccFieldElement::sswu_z(curve_type);
+    let z = EccFieldElement::sswu_z(curve_type)?;
     let vinv = v.invert();
     let uov = u.mul(&vinv)?;
     let sqrt_uov = uov.sqrt();
@@ -42,7 +42,7 @@ fn sqrt_ratio_p_3_mod_4(
 
     // Fast codepath for curves where p == 3 (mod 4)
     // See https://www.ietf.org/archive/id/draft-irtf-cfrg-hash-to-curve-14.html#appendix-F.2.1.2
-    let c2 = EccFieldElement::sswu_c2(curve_type);
+    let c2 = EccFieldElement::sswu_c2(curve_type)?;
 
     let tv1 = v.square()?;
     let tv2 = u.mul(v)?;
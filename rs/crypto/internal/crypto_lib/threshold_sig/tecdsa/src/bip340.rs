@@ -0,0 +1,386 @@
+use crate::*;
+use ic_crypto_sha::Sha256;
+
+/// Compute a BIP-340 tagged hash
+///
+/// See <https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki#design>
+fn bip340_tagged_hash(tag: &str, inputs: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = Sha256::hash(tag.as_bytes());
+
+    let mut sha256 = Sha256::new();
+    sha256.write(&tag_hash);
+    sha256.write(&tag_hash);
+    for input in inputs {
+        sha256.write(input);
+    }
+    sha256.finish()
+}
+
+/// Return the x coordinate of a secp256k1 point, along with a boolean
+/// which is true if the y coordinate of the point is even
+///
+/// The point at infinity is rejected since it has no x coordinate.
+fn bip340_x_and_parity(pt: &EccPoint) -> ThresholdEcdsaResult<(Vec<u8>, bool)> {
+    if pt.curve_type() != EccCurveType::K256 {
+        return Err(ThresholdEcdsaError::CurveMismatch);
+    }
+
+    if pt.is_infinity()? {
+        return Err(ThresholdEcdsaError::InvalidPoint);
+    }
+
+    // The SEC1 compressed encoding uses the header byte 0x02 for points
+    // with an even y coordinate, and 0x03 for points with an odd y
+    let compressed = pt.serialize();
+    Ok((compressed[1..].to_vec(), compressed[0] == 0x02))
+}
+
+/// Returns the point with the same x coordinate as `pt` but with an even y
+fn bip340_even_y(pt: &EccPoint) -> ThresholdEcdsaResult<EccPoint> {
+    let (_, is_even) = bip340_x_and_parity(pt)?;
+
+    if is_even {
+        Ok(*pt)
+    } else {
+        EccPoint::identity(pt.curve_type()).sub_points(pt)
+    }
+}
+
+/// Returns 1 if the y coordinate of the point is even, otherwise -1
+///
+/// BIP-340 implicitly negates the secret key and nonce whenever the
+/// associated public point has an odd y coordinate; multiplying by this
+/// value performs the same negation on shares of the secret.
+fn bip340_parity_multiplier(pt: &EccPoint) -> ThresholdEcdsaResult<EccScalar> {
+    let (_, is_even) = bip340_x_and_parity(pt)?;
+    let one = EccScalar::one(pt.curve_type());
+
+    if is_even {
+        Ok(one)
+    } else {
+        Ok(one.negate())
+    }
+}
+
+/// Compute the BIP-340 challenge e = H(R.x || P.x || m) mod n
+fn bip340_challenge(
+    presig: &EccPoint,
+    public_key: &EccPoint,
+    message: &[u8],
+) -> ThresholdEcdsaResult<EccScalar> {
+    let (presig_x, _) = bip340_x_and_parity(presig)?;
+    let (public_key_x, _) = bip340_x_and_parity(public_key)?;
+
+    let e = bip340_tagged_hash("BIP0340/challenge", &[&presig_x, &public_key_x, message]);
+    EccScalar::from_bytes_wide(EccCurveType::K256, &e)
+}
+
+/// Presignature rerandomization
+///
+/// Before use, the presignature transcript is rerandomized using a
+/// value derived from the randomness, message and key tweak, as is
+/// done for threshold ECDSA. The derived public key is also computed
+/// here, since it is needed by every subsequent step.
+struct RerandomizedPresignature {
+    derived_public_key: EccPoint,
+    randomized_pre_sig: EccPoint,
+    key_tweak: EccScalar,
+    randomizer: EccScalar,
+}
+
+impl RerandomizedPresignature {
+    fn compute(
+        message: &[u8],
+        randomness: &Randomness,
+        derivation_path: &DerivationPath,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<Self> {
+        let pre_sig = match &presig_transcript.combined_commitment {
+            CombinedCommitment::ByInterpolation(PolynomialCommitment::Simple(c)) => {
+                c.constant_term()
+            }
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        let curve_type = EccCurveType::K256;
+
+        if pre_sig.curve_type() != curve_type
+            || key_transcript.constant_term().curve_type() != curve_type
+        {
+            return Err(ThresholdEcdsaError::CurveMismatch);
+        }
+
+        let (key_tweak, _chain_key) =
+            derivation_path.derive_tweak(&key_transcript.constant_term())?;
+
+        let mut ro = ro::RandomOracle::new("ic-crypto-tschnorr-bip340-rerandomize-presig");
+        ro.add_bytestring("randomness", &randomness.get())?;
+        ro.add_bytestring("message", message)?;
+        ro.add_point("pre_sig", &pre_sig)?;
+        ro.add_scalar("key_tweak", &key_tweak)?;
+        let randomizer = ro.output_scalar(curve_type)?;
+
+        let randomized_pre_sig = pre_sig.add_points(&EccPoint::mul_by_g(&randomizer)?)?;
+
+        let derived_public_key = key_transcript
+            .constant_term()
+            .add_points(&EccPoint::mul_by_g(&key_tweak)?)?;
+
+        Ok(Self {
+            derived_public_key,
+            randomized_pre_sig,
+            key_tweak,
+            randomizer,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThresholdBip340SignatureShareInternal {
+    s: EccScalar,
+}
+
+impl ThresholdBip340SignatureShareInternal {
+    pub(crate) fn new(
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        key_transcript: &IDkgTranscriptInternal,
+        key_opening: &CommitmentOpening,
+        presig_transcript: &IDkgTranscriptInternal,
+        presig_opening: &CommitmentOpening,
+    ) -> ThresholdEcdsaResult<Self> {
+        let rerandomized = RerandomizedPresignature::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let key_share = match key_opening {
+            CommitmentOpening::Simple(s) => s,
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        let presig_share = match presig_opening {
+            CommitmentOpening::Simple(s) => s,
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        // Shares of the tweaked secret key and rerandomized nonce, negated
+        // if necessary so that the associated public points have even y
+        let tweaked_key_share = key_share
+            .add(&rerandomized.key_tweak)?
+            .mul(&bip340_parity_multiplier(&rerandomized.derived_public_key)?)?;
+
+        let nonce_share = presig_share
+            .add(&rerandomized.randomizer)?
+            .mul(&bip340_parity_multiplier(&rerandomized.randomized_pre_sig)?)?;
+
+        let e = bip340_challenge(
+            &rerandomized.randomized_pre_sig,
+            &rerandomized.derived_public_key,
+            message,
+        )?;
+
+        let s = nonce_share.add(&e.mul(&tweaked_key_share)?)?;
+
+        Ok(Self { s })
+    }
+
+    /// Verify a BIP-340 signature share
+    ///
+    /// The share is checked against the public commitments to the
+    /// signer's shares of the key and presignature transcripts.
+    pub fn verify(
+        &self,
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        signer_index: NodeIndex,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<()> {
+        let rerandomized = RerandomizedPresignature::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let e = bip340_challenge(
+            &rerandomized.randomized_pre_sig,
+            &rerandomized.derived_public_key,
+            message,
+        )?;
+
+        let tweak_g = EccPoint::mul_by_g(&rerandomized.key_tweak)?;
+        let randomizer_g = EccPoint::mul_by_g(&rerandomized.randomizer)?;
+
+        let key_j = key_transcript
+            .evaluate_at(signer_index)?
+            .add_points(&tweak_g)?;
+        let presig_j = presig_transcript
+            .evaluate_at(signer_index)?
+            .add_points(&randomizer_g)?;
+
+        let key_sign = bip340_parity_multiplier(&rerandomized.derived_public_key)?;
+        let presig_sign = bip340_parity_multiplier(&rerandomized.randomized_pre_sig)?;
+
+        let expected = EccPoint::mul_points(&presig_j, &presig_sign, &key_j, &e.mul(&key_sign)?)?;
+
+        if expected != EccPoint::mul_by_g(&self.s)? {
+            return Err(ThresholdEcdsaError::InvalidSignatureShare);
+        }
+
+        Ok(())
+    }
+
+    pub fn serialize(&self) -> ThresholdEcdsaResult<Vec<u8>> {
+        serde_cbor::to_vec(self)
+            .map_err(|e| ThresholdEcdsaError::SerializationError(format!("{}", e)))
+    }
+
+    pub fn deserialize(raw: &[u8]) -> ThresholdEcdsaResult<Self> {
+        serde_cbor::from_slice::<Self>(raw)
+            .map_err(|e| ThresholdEcdsaError::SerializationError(format!("{}", e)))
+    }
+}
+
+/// A BIP-340 Schnorr signature
+///
+/// The presignature point `r` is always stored with an even y coordinate
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdBip340CombinedSignatureInternal {
+    r: EccPoint,
+    s: EccScalar,
+}
+
+impl ThresholdBip340CombinedSignatureInternal {
+    /// Serialize in the format BIP-340 expects, which is x(r) || s
+    pub fn serialize(&self) -> ThresholdEcdsaResult<Vec<u8>> {
+        let (r_x, _) = bip340_x_and_parity(&self.r)?;
+        let s_bytes = self.s.serialize();
+
+        let mut sig = Vec::with_capacity(r_x.len() + s_bytes.len());
+        sig.extend_from_slice(&r_x);
+        sig.extend_from_slice(&s_bytes);
+        Ok(sig)
+    }
+
+    /// Deserialize a BIP-340 signature
+    pub fn deserialize(bytes: &[u8]) -> ThresholdEcdsaResult<Self> {
+        let curve_type = EccCurveType::K256;
+        let flen = curve_type.field_bytes();
+        let slen = curve_type.scalar_bytes();
+
+        if bytes.len() != flen + slen {
+            return Err(ThresholdEcdsaError::SerializationError(
+                "Bad signature length".to_string(),
+            ));
+        }
+
+        // BIP-340 r values are implicitly the point with even y
+        let mut encoded_r = Vec::with_capacity(1 + flen);
+        encoded_r.push(0x02);
+        encoded_r.extend_from_slice(&bytes[..flen]);
+
+        let r = EccPoint::deserialize(curve_type, &encoded_r)
+            .map_err(|e| ThresholdEcdsaError::SerializationError(format!("{:?}", e)))?;
+        let s = EccScalar::deserialize(curve_type, &bytes[flen..])
+            .map_err(|e| ThresholdEcdsaError::SerializationError(format!("{:?}", e)))?;
+
+        Ok(Self { r, s })
+    }
+
+    pub(crate) fn new(
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+        reconstruction_threshold: NumberOfNodes,
+        sig_shares: &BTreeMap<NodeIndex, ThresholdBip340SignatureShareInternal>,
+    ) -> ThresholdEcdsaResult<Self> {
+        let reconstruction_threshold = reconstruction_threshold.get() as usize;
+        if sig_shares.len() < reconstruction_threshold {
+            return Err(ThresholdEcdsaError::InsufficientDealings);
+        }
+
+        let rerandomized = RerandomizedPresignature::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let mut x_values = Vec::with_capacity(reconstruction_threshold);
+        let mut samples = Vec::with_capacity(reconstruction_threshold);
+
+        for (index, sig_share) in sig_shares.iter().take(reconstruction_threshold) {
+            x_values.push(*index);
+            samples.push(sig_share.s);
+        }
+
+        let coefficients = LagrangeCoefficients::at_zero(EccCurveType::K256, &x_values)?;
+        let s = coefficients.interpolate_scalar(&samples)?;
+
+        Ok(Self {
+            r: bip340_even_y(&rerandomized.randomized_pre_sig)?,
+            s,
+        })
+    }
+
+    /// Verify a threshold BIP-340 signature
+    ///
+    /// In addition to the BIP-340 verification equation, this checks
+    /// that the signature was generated using the rerandomized
+    /// presignature associated with the provided transcript and
+    /// randomness.
+    pub fn verify(
+        &self,
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        presig_transcript: &IDkgTranscriptInternal,
+        key_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<()> {
+        if self.s.is_zero() {
+            return Err(ThresholdEcdsaError::InvalidSignature);
+        }
+
+        let rerandomized = RerandomizedPresignature::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        if self.r != bip340_even_y(&rerandomized.randomized_pre_sig)? {
+            return Err(ThresholdEcdsaError::InvalidSignature);
+        }
+
+        let public_key = bip340_even_y(&rerandomized.derived_public_key)?;
+
+        let e = bip340_challenge(&self.r, &public_key, message)?;
+
+        // s*G - e*P == R
+        let rp = EccPoint::mul_points(
+            &EccPoint::generator_g(EccCurveType::K256)?,
+            &self.s,
+            &public_key,
+            &e.negate(),
+        )?;
+
+        if rp != self.r {
+            return Err(ThresholdEcdsaError::InvalidSignature);
+        }
+
+        Ok(())
+    }
+}
@@ -25,6 +25,10 @@ impl Debug for SecretShares {
                 f,
                 "SecretShares::ReshareOfUnmasked(EccScalar::P256) - REDACTED"
             ),
+            Self::ReshareOfUnmasked(EccScalar::Ed25519(_)) => write!(
+                f,
+                "SecretShares::ReshareOfUnmasked(EccScalar::Ed25519) - REDACTED"
+            ),
             Self::ReshareOfMasked(EccScalar::K256(_), EccScalar::K256(_)) => write!(
                 f,
                 "SecretShares::ReshareOfMasked(EccScalar::K256) - REDACTED"
@@ -33,6 +37,10 @@ impl Debug for SecretShares {
                 f,
                 "SecretShares::ReshareOfMasked(EccScalar::P256) - REDACTED"
             ),
+            Self::ReshareOfMasked(EccScalar::Ed25519(_), EccScalar::Ed25519(_)) => write!(
+                f,
+                "SecretShares::ReshareOfMasked(EccScalar::Ed25519) - REDACTED"
+            ),
             Self::ReshareOfMasked(_, _) => write!(
                 f,
                 "Unsupported curve combination in SecretShares::ReshareOfMasked!"
@@ -55,6 +63,15 @@ impl Debug for SecretShares {
                     "SecretShares::UnmaskedTimesMasked(EccScalar::P256) - REDACTED"
                 )
             }
+            Self::UnmaskedTimesMasked(
+                EccScalar::Ed25519(_),
+                (EccScalar::Ed25519(_), EccScalar::Ed25519(_)),
+            ) => {
+                write!(
+                    f,
+                    "SecretShares::UnmaskedTimesMasked(EccScalar::Ed25519) - REDACTED"
+                )
+            }
             Self::UnmaskedTimesMasked(_, (_, _)) => {
                 write!(
                     f,
@@ -190,7 +207,7 @@ impl IDkgDealingInternal {
         }
 
         for recipient in recipients {
-            if recipient.curve_type() != curve {
+            if recipient.curve_type() != MEGA_KEY_CURVE {
                 return Err(ThresholdEcdsaError::InvalidRecipients);
             }
         }
@@ -349,7 +366,7 @@ impl IDkgDealingInternal {
                 self.commitment
                     .verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
                 self.ciphertext
-                    .verify_is(MEGaCiphertextType::Pairs, curve_type)?;
+                    .verify_is(MEGaCiphertextType::Pairs, MEGA_KEY_CURVE, curve_type)?;
                 // no ZK proof for this transcript type
                 Ok(())
             }
@@ -360,8 +377,11 @@ impl IDkgDealingInternal {
                 self.commitment
                     .verify_is(PolynomialCommitmentType::Simple, curve_type)?;
                 previous_commitment.verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
-                self.ciphertext
-                    .verify_is(MEGaCiphertextType::Single, curve_type)?;
+                self.ciphertext.verify_is(
+                    MEGaCiphertextType::Single,
+                    MEGA_KEY_CURVE,
+                    curve_type,
+                )?;
 
                 proof.verify(
                     &previous_commitment.evaluate_at(dealer_index)?,
@@ -376,8 +396,11 @@ impl IDkgDealingInternal {
                 self.commitment
                     .verify_is(PolynomialCommitmentType::Simple, curve_type)?;
                 previous_commitment.verify_is(PolynomialCommitmentType::Simple, curve_type)?;
-                self.ciphertext
-                    .verify_is(MEGaCiphertextType::Single, curve_type)?;
+                self.ciphertext.verify_is(
+                    MEGaCiphertextType::Single,
+                    MEGA_KEY_CURVE,
+                    curve_type,
+                )?;
 
                 match previous_commitment {
                     PolynomialCommitment::Pedersen(_) => {
@@ -399,7 +422,7 @@ impl IDkgDealingInternal {
                 self.commitment
                     .verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
                 self.ciphertext
-                    .verify_is(MEGaCiphertextType::Pairs, curve_type)?;
+                    .verify_is(MEGaCiphertextType::Pairs, MEGA_KEY_CURVE, curve_type)?;
                 lhs.verify_is(PolynomialCommitmentType::Simple, curve_type)?;
                 rhs.verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
 
@@ -426,7 +449,7 @@ impl IDkgDealingInternal {
         dealer_index: NodeIndex,
         recipient_index: NodeIndex,
     ) -> ThresholdEcdsaResult<()> {
-        if private_key.curve_type() != curve_type || public_key.curve_type() != curve_type {
+        if private_key.curve_type() != MEGA_KEY_CURVE || public_key.curve_type() != MEGA_KEY_CURVE {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
 
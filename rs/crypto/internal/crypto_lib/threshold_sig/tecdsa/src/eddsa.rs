@@ -0,0 +1,317 @@
+use crate::*;
+use ic_crypto_sha::Sha512;
+
+/// Compute the Ed25519 challenge e = SHA-512(R || A || M) mod L
+///
+/// See RFC 8032 section 5.1.6. The hash output is interpreted as a
+/// little-endian integer, while EccScalar::from_bytes_wide expects a
+/// big-endian input, hence the reversal.
+fn ed25519_challenge(
+    presig: &EccPoint,
+    public_key: &EccPoint,
+    message: &[u8],
+) -> ThresholdEcdsaResult<EccScalar> {
+    let mut sha512 = Sha512::new();
+    sha512.write(&presig.serialize());
+    sha512.write(&public_key.serialize());
+    sha512.write(message);
+    let mut e = sha512.finish();
+    e.reverse();
+    EccScalar::from_bytes_wide(EccCurveType::Ed25519, &e)
+}
+
+/// Presignature rerandomization
+///
+/// Before use, the presignature transcript is rerandomized using a
+/// value derived from the randomness, message and key tweak, as is
+/// done for threshold ECDSA. The derived public key is also computed
+/// here, since it is needed by every subsequent step.
+struct RerandomizedPresignature {
+    derived_public_key: EccPoint,
+    randomized_pre_sig: EccPoint,
+    key_tweak: EccScalar,
+    randomizer: EccScalar,
+}
+
+impl RerandomizedPresignature {
+    fn compute(
+        message: &[u8],
+        randomness: &Randomness,
+        derivation_path: &DerivationPath,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<Self> {
+        let pre_sig = match &presig_transcript.combined_commitment {
+            CombinedCommitment::ByInterpolation(PolynomialCommitment::Simple(c)) => {
+                c.constant_term()
+            }
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        let curve_type = EccCurveType::Ed25519;
+
+        if pre_sig.curve_type() != curve_type
+            || key_transcript.constant_term().curve_type() != curve_type
+        {
+            return Err(ThresholdEcdsaError::CurveMismatch);
+        }
+
+        let (key_tweak, _chain_key) =
+            derivation_path.derive_tweak(&key_transcript.constant_term())?;
+
+        let mut ro = ro::RandomOracle::new("ic-crypto-tschnorr-ed25519-rerandomize-presig");
+        ro.add_bytestring("randomness", &randomness.get())?;
+        ro.add_bytestring("message", message)?;
+        ro.add_point("pre_sig", &pre_sig)?;
+        ro.add_scalar("key_tweak", &key_tweak)?;
+        let randomizer = ro.output_scalar(curve_type)?;
+
+        let randomized_pre_sig = pre_sig.add_points(&EccPoint::mul_by_g(&randomizer)?)?;
+
+        let derived_public_key = key_transcript
+            .constant_term()
+            .add_points(&EccPoint::mul_by_g(&key_tweak)?)?;
+
+        Ok(Self {
+            derived_public_key,
+            randomized_pre_sig,
+            key_tweak,
+            randomizer,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThresholdEd25519SignatureShareInternal {
+    s: EccScalar,
+}
+
+impl ThresholdEd25519SignatureShareInternal {
+    pub(crate) fn new(
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        key_transcript: &IDkgTranscriptInternal,
+        key_opening: &CommitmentOpening,
+        presig_transcript: &IDkgTranscriptInternal,
+        presig_opening: &CommitmentOpening,
+    ) -> ThresholdEcdsaResult<Self> {
+        let rerandomized = RerandomizedPresignature::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let key_share = match key_opening {
+            CommitmentOpening::Simple(s) => s,
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        let presig_share = match presig_opening {
+            CommitmentOpening::Simple(s) => s,
+            _ => return Err(ThresholdEcdsaError::UnexpectedCommitmentType),
+        };
+
+        let tweaked_key_share = key_share.add(&rerandomized.key_tweak)?;
+        let nonce_share = presig_share.add(&rerandomized.randomizer)?;
+
+        let e = ed25519_challenge(
+            &rerandomized.randomized_pre_sig,
+            &rerandomized.derived_public_key,
+            message,
+        )?;
+
+        let s = nonce_share.add(&e.mul(&tweaked_key_share)?)?;
+
+        Ok(Self { s })
+    }
+
+    /// Verify an Ed25519 signature share
+    ///
+    /// The share is checked against the public commitments to the
+    /// signer's shares of the key and presignature transcripts.
+    pub fn verify(
+        &self,
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        signer_index: NodeIndex,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<()> {
+        let rerandomized = RerandomizedPresignature::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let e = ed25519_challenge(
+            &rerandomized.randomized_pre_sig,
+            &rerandomized.derived_public_key,
+            message,
+        )?;
+
+        let tweak_g = EccPoint::mul_by_g(&rerandomized.key_tweak)?;
+        let randomizer_g = EccPoint::mul_by_g(&rerandomized.randomizer)?;
+
+        let key_j = key_transcript
+            .evaluate_at(signer_index)?
+            .add_points(&tweak_g)?;
+        let presig_j = presig_transcript
+            .evaluate_at(signer_index)?
+            .add_points(&randomizer_g)?;
+
+        let expected = presig_j.add_points(&key_j.scalar_mul(&e)?)?;
+
+        if expected != EccPoint::mul_by_g(&self.s)? {
+            return Err(ThresholdEcdsaError::InvalidSignatureShare);
+        }
+
+        Ok(())
+    }
+
+    pub fn serialize(&self) -> ThresholdEcdsaResult<Vec<u8>> {
+        serde_cbor::to_vec(self)
+            .map_err(|e| ThresholdEcdsaError::SerializationError(format!("{}", e)))
+    }
+
+    pub fn deserialize(raw: &[u8]) -> ThresholdEcdsaResult<Self> {
+        serde_cbor::from_slice::<Self>(raw)
+            .map_err(|e| ThresholdEcdsaError::SerializationError(format!("{}", e)))
+    }
+}
+
+/// An Ed25519 signature, as specified in RFC 8032
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdEd25519CombinedSignatureInternal {
+    r: EccPoint,
+    s: EccScalar,
+}
+
+impl ThresholdEd25519CombinedSignatureInternal {
+    /// Serialize in the format RFC 8032 expects, which is R || s
+    ///
+    /// Note that unlike the other curves in this library, Ed25519
+    /// encodes s in little-endian byte order.
+    pub fn serialize(&self) -> Vec<u8> {
+        let r_bytes = self.r.serialize();
+        let mut s_bytes = self.s.serialize();
+        s_bytes.reverse();
+
+        let mut sig = Vec::with_capacity(r_bytes.len() + s_bytes.len());
+        sig.extend_from_slice(&r_bytes);
+        sig.extend_from_slice(&s_bytes);
+        sig
+    }
+
+    /// Deserialize an Ed25519 signature
+    pub fn deserialize(bytes: &[u8]) -> ThresholdEcdsaResult<Self> {
+        let curve_type = EccCurveType::Ed25519;
+        let plen = curve_type.point_bytes();
+        let slen = curve_type.scalar_bytes();
+
+        if bytes.len() != plen + slen {
+            return Err(ThresholdEcdsaError::SerializationError(
+                "Bad signature length".to_string(),
+            ));
+        }
+
+        let mut s_bytes = bytes[plen..].to_vec();
+        s_bytes.reverse();
+
+        let r = EccPoint::deserialize(curve_type, &bytes[..plen])
+            .map_err(|e| ThresholdEcdsaError::SerializationError(format!("{:?}", e)))?;
+        let s = EccScalar::deserialize(curve_type, &s_bytes)
+            .map_err(|e| ThresholdEcdsaError::SerializationError(format!("{:?}", e)))?;
+
+        Ok(Self { r, s })
+    }
+
+    pub(crate) fn new(
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        key_transcript: &IDkgTranscriptInternal,
+        presig_transcript: &IDkgTranscriptInternal,
+        reconstruction_threshold: NumberOfNodes,
+        sig_shares: &BTreeMap<NodeIndex, ThresholdEd25519SignatureShareInternal>,
+    ) -> ThresholdEcdsaResult<Self> {
+        let reconstruction_threshold = reconstruction_threshold.get() as usize;
+        if sig_shares.len() < reconstruction_threshold {
+            return Err(ThresholdEcdsaError::InsufficientDealings);
+        }
+
+        let rerandomized = RerandomizedPresignature::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        let mut x_values = Vec::with_capacity(reconstruction_threshold);
+        let mut samples = Vec::with_capacity(reconstruction_threshold);
+
+        for (index, sig_share) in sig_shares.iter().take(reconstruction_threshold) {
+            x_values.push(*index);
+            samples.push(sig_share.s);
+        }
+
+        let coefficients = LagrangeCoefficients::at_zero(EccCurveType::Ed25519, &x_values)?;
+        let s = coefficients.interpolate_scalar(&samples)?;
+
+        Ok(Self {
+            r: rerandomized.randomized_pre_sig,
+            s,
+        })
+    }
+
+    /// Verify a threshold Ed25519 signature
+    ///
+    /// In addition to the Ed25519 verification equation, this checks
+    /// that the signature was generated using the rerandomized
+    /// presignature associated with the provided transcript and
+    /// randomness.
+    pub fn verify(
+        &self,
+        derivation_path: &DerivationPath,
+        message: &[u8],
+        randomness: Randomness,
+        presig_transcript: &IDkgTranscriptInternal,
+        key_transcript: &IDkgTranscriptInternal,
+    ) -> ThresholdEcdsaResult<()> {
+        let rerandomized = RerandomizedPresignature::compute(
+            message,
+            &randomness,
+            derivation_path,
+            key_transcript,
+            presig_transcript,
+        )?;
+
+        if self.r != rerandomized.randomized_pre_sig {
+            return Err(ThresholdEcdsaError::InvalidSignature);
+        }
+
+        let public_key = rerandomized.derived_public_key;
+
+        let e = ed25519_challenge(&self.r, &public_key, message)?;
+
+        // s*G - e*A == R
+        let rp = EccPoint::mul_points(
+            &EccPoint::generator_g(EccCurveType::Ed25519)?,
+            &self.s,
+            &public_key,
+            &e.negate(),
+        )?;
+
+        if rp != self.r {
+            return Err(ThresholdEcdsaError::InvalidSignature);
+        }
+
+        Ok(())
+    }
+}
@@ -33,6 +33,13 @@ fe_derive::derive_field_element!(
     SSWU_Z = "-11",
 );
 
+fn unsupported_curve(curve: EccCurveType) -> ThresholdEcdsaError {
+    ThresholdEcdsaError::InvalidArguments(format!(
+        "Field arithmetic is not supported for {}",
+        curve
+    ))
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum EccFieldElement {
     K256(Secp256k1FieldElement),
@@ -60,73 +67,81 @@ impl EccFieldElement {
     }
 
     /// Return the zero field element
-    pub fn zero(curve: EccCurveType) -> Self {
+    pub fn zero(curve: EccCurveType) -> ThresholdEcdsaResult<Self> {
         match curve {
-            EccCurveType::K256 => Self::K256(Secp256k1FieldElement::zero()),
-            EccCurveType::P256 => Self::P256(Secp256r1FieldElement::zero()),
+            EccCurveType::K256 => Ok(Self::K256(Secp256k1FieldElement::zero())),
+            EccCurveType::P256 => Ok(Self::P256(Secp256r1FieldElement::zero())),
+            EccCurveType::Ed25519 => Err(unsupported_curve(curve)),
         }
     }
 
     /// Return the one field element
-    pub fn one(curve: EccCurveType) -> Self {
+    pub fn one(curve: EccCurveType) -> ThresholdEcdsaResult<Self> {
         match curve {
-            EccCurveType::K256 => Self::K256(Secp256k1FieldElement::one()),
-            EccCurveType::P256 => Self::P256(Secp256r1FieldElement::one()),
+            EccCurveType::K256 => Ok(Self::K256(Secp256k1FieldElement::one())),
+            EccCurveType::P256 => Ok(Self::P256(Secp256r1FieldElement::one())),
+            EccCurveType::Ed25519 => Err(unsupported_curve(curve)),
         }
     }
 
     /// Return the field element "A" cooresponding to the curve equation
-    pub fn a(curve: EccCurveType) -> Self {
+    pub fn a(curve: EccCurveType) -> ThresholdEcdsaResult<Self> {
         match curve {
-            EccCurveType::K256 => Self::K256(Secp256k1FieldElement::a()),
-            EccCurveType::P256 => Self::P256(Secp256r1FieldElement::a()),
+            EccCurveType::K256 => Ok(Self::K256(Secp256k1FieldElement::a())),
+            EccCurveType::P256 => Ok(Self::P256(Secp256r1FieldElement::a())),
+            EccCurveType::Ed25519 => Err(unsupported_curve(curve)),
         }
     }
 
     /// Return the field element "B" cooresponding to the curve equation
-    pub fn b(curve: EccCurveType) -> Self {
+    pub fn b(curve: EccCurveType) -> ThresholdEcdsaResult<Self> {
         match curve {
-            EccCurveType::K256 => Self::K256(Secp256k1FieldElement::b()),
-            EccCurveType::P256 => Self::P256(Secp256r1FieldElement::b()),
+            EccCurveType::K256 => Ok(Self::K256(Secp256k1FieldElement::b())),
+            EccCurveType::P256 => Ok(Self::P256(Secp256r1FieldElement::b())),
+            EccCurveType::Ed25519 => Err(unsupported_curve(curve)),
         }
     }
 
     /// Return the field element "A" cooresponding to the curve equation
     /// for the curve used with SSWU hash2curve technique. This may or
     /// may not match the normal "A"
-    pub fn sswu_a(curve: EccCurveType) -> Self {
+    pub fn sswu_a(curve: EccCurveType) -> ThresholdEcdsaResult<Self> {
         match curve {
-            EccCurveType::K256 => Self::K256(Secp256k1FieldElement::sswu_a()),
-            EccCurveType::P256 => Self::P256(Secp256r1FieldElement::sswu_a()),
+            EccCurveType::K256 => Ok(Self::K256(Secp256k1FieldElement::sswu_a())),
+            EccCurveType::P256 => Ok(Self::P256(Secp256r1FieldElement::sswu_a())),
+            EccCurveType::Ed25519 => Err(unsupported_curve(curve)),
         }
     }
 
     /// Return the field element "B" cooresponding to the curve equation
     /// for the curve used with SSWU hash2curve technique. This may or
     /// may not match the normal "B"
-    pub fn sswu_b(curve: EccCurveType) -> Self {
+    pub fn sswu_b(curve: EccCurveType) -> ThresholdEcdsaResult<Self> {
         match curve {
-            EccCurveType::K256 => Self::K256(Secp256k1FieldElement::sswu_b()),
-            EccCurveType::P256 => Self::P256(Secp256r1FieldElement::sswu_b()),
+            EccCurveType::K256 => Ok(Self::K256(Secp256k1FieldElement::sswu_b())),
+            EccCurveType::P256 => Ok(Self::P256(Secp256r1FieldElement::sswu_b())),
+            EccCurveType::Ed25519 => Err(unsupported_curve(curve)),
         }
     }
 
     /// Return the field element "Z" as specified for the simplified
     /// SWU map in draft-irtf-cfrg-hash-to-curve-14
-    pub fn sswu_z(curve: EccCurveType) -> Self {
+    pub fn sswu_z(curve: EccCurveType) -> ThresholdEcdsaResult<Self> {
         match curve {
-            EccCurveType::K256 => Self::K256(Secp256k1FieldElement::sswu_z()),
-            EccCurveType::P256 => Self::P256(Secp256r1FieldElement::sswu_z()),
+            EccCurveType::K256 => Ok(Self::K256(Secp256k1FieldElement::sswu_z())),
+            EccCurveType::P256 => Ok(Self::P256(Secp256r1FieldElement::sswu_z())),
+            EccCurveType::Ed25519 => Err(unsupported_curve(curve)),
         }
     }
 
     /// Return the field element "C2" as specified for the simplified
     /// SWU map in draft-irtf-cfrg-hash-to-curve-14
     /// See section F.2.1.2
-    pub fn sswu_c2(curve: EccCurveType) -> Self {
+    pub fn sswu_c2(curve: EccCurveType) -> ThresholdEcdsaResult<Self> {
         match curve {
-            EccCurveType::K256 => Self::K256(Secp256k1FieldElement::sswu_c2()),
-            EccCurveType::P256 => Self::P256(Secp256r1FieldElement::sswu_c2()),
+            EccCurveType::K256 => Ok(Self::K256(Secp256k1FieldElement::sswu_c2())),
+            EccCurveType::P256 => Ok(Self::P256(Secp256r1FieldElement::sswu_c2())),
+            EccCurveType::Ed25519 => Err(unsupported_curve(curve)),
         }
     }
 
@@ -147,6 +162,7 @@ impl EccFieldElement {
                     .ok_or(ThresholdEcdsaError::InvalidFieldElement)?;
                 Ok(Self::P256(x))
             }
+            EccCurveType::Ed25519 => Err(unsupported_curve(curve)),
         }
     }
 
@@ -167,6 +183,7 @@ impl EccFieldElement {
                     .ok_or(ThresholdEcdsaError::InvalidFieldElement)?;
                 Ok(Self::P256(x))
             }
+            EccCurveType::Ed25519 => Err(unsupported_curve(curve)),
         }
     }
 
@@ -273,7 +290,7 @@ impl EccFieldElement {
     ///
     /// Returns zero if self is zero
     pub fn negate(&self) -> ThresholdEcdsaResult<Self> {
-        Self::zero(self.curve_type()).sub(self)
+        Self::zero(self.curve_type())?.sub(self)
     }
 
     /// Return the modular square root of self
@@ -8,17 +8,20 @@ use std::convert::{TryFrom, TryInto};
 use std::fmt;
 use zeroize::Zeroize;
 
+mod ed25519;
 mod secp256k1;
 mod secp256r1;
 
 /// Elliptic curve type enum
 ///
 /// Enumerates the curves supported by this library, currently K256 (aka
-/// secp256k1) and P256 (aka secp256r1)
+/// secp256k1), P256 (aka secp256r1) and Ed25519 (the prime order subgroup
+/// of edwards25519)
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum EccCurveType {
     K256,
     P256,
+    Ed25519,
 }
 
 impl EccCurveType {
@@ -30,6 +33,7 @@ impl EccCurveType {
         match self {
             EccCurveType::K256 => 256,
             EccCurveType::P256 => 256,
+            EccCurveType::Ed25519 => 253,
         }
     }
 
@@ -46,6 +50,7 @@ impl EccCurveType {
         match self {
             EccCurveType::K256 => 256,
             EccCurveType::P256 => 256,
+            EccCurveType::Ed25519 => 255,
         }
     }
 
@@ -65,13 +70,18 @@ impl EccCurveType {
         match self {
             EccCurveType::K256 => 128,
             EccCurveType::P256 => 128,
+            EccCurveType::Ed25519 => 128,
         }
     }
 
     /// Return the size of encoded points, in bytes
     pub fn point_bytes(&self) -> usize {
-        // 1 byte header with y parity plus an affine x field element
-        1 + self.field_bytes()
+        match self {
+            // 1 byte header with y parity plus an affine x field element
+            EccCurveType::K256 | EccCurveType::P256 => 1 + self.field_bytes(),
+            // The y coordinate with the sign of x packed into the top bit
+            EccCurveType::Ed25519 => self.field_bytes(),
+        }
     }
 
     /// Return a unique small integer for this curve type
@@ -81,6 +91,7 @@ impl EccCurveType {
         match self {
             EccCurveType::K256 => 1,
             EccCurveType::P256 => 2,
+            EccCurveType::Ed25519 => 3,
         }
     }
 
@@ -88,11 +99,16 @@ impl EccCurveType {
         match tag {
             1 => Some(EccCurveType::K256),
             2 => Some(EccCurveType::P256),
+            3 => Some(EccCurveType::Ed25519),
             _ => None,
         }
     }
 
-    /// Return a vector over all available curve types
+    /// Return a vector over all available short Weierstrass curve types
+    ///
+    /// Ed25519 is not included, since it does not support the field
+    /// arithmetic and hash2curve operations that are available for the
+    /// other curves.
     ///
     /// This is mostly useful for tests
     pub fn all() -> Vec<EccCurveType> {
@@ -105,6 +121,7 @@ impl fmt::Display for EccCurveType {
         let curve_name = match self {
             Self::K256 => "secp256k1",
             Self::P256 => "secp256r1",
+            Self::Ed25519 => "ed25519",
         };
 
         write!(f, "{}", curve_name)
@@ -115,6 +132,7 @@ impl fmt::Display for EccCurveType {
 pub enum EccScalar {
     K256(secp256k1::Scalar),
     P256(secp256r1::Scalar),
+    Ed25519(ed25519::Scalar),
 }
 
 impl fmt::Debug for EccScalar {
@@ -133,6 +151,7 @@ impl EccScalar {
         match self {
             Self::K256(_) => EccCurveType::K256,
             Self::P256(_) => EccCurveType::P256,
+            Self::Ed25519(_) => EccCurveType::Ed25519,
         }
     }
 
@@ -141,6 +160,7 @@ impl EccScalar {
         match (self, other) {
             (Self::K256(s1), Self::K256(s2)) => Ok(Self::K256(s1.add(s2))),
             (Self::P256(s1), Self::P256(s2)) => Ok(Self::P256(s1.add(s2))),
+            (Self::Ed25519(s1), Self::Ed25519(s2)) => Ok(Self::Ed25519(s1.add(s2))),
             (_, _) => Err(ThresholdEcdsaError::CurveMismatch),
         }
     }
@@ -150,6 +170,7 @@ impl EccScalar {
         match (self, other) {
             (Self::K256(s1), Self::K256(s2)) => Ok(Self::K256(s1.sub(s2))),
             (Self::P256(s1), Self::P256(s2)) => Ok(Self::P256(s1.sub(s2))),
+            (Self::Ed25519(s1), Self::Ed25519(s2)) => Ok(Self::Ed25519(s1.sub(s2))),
             (_, _) => Err(ThresholdEcdsaError::CurveMismatch),
         }
     }
@@ -159,6 +180,7 @@ impl EccScalar {
         match (self, other) {
             (Self::K256(s1), Self::K256(s2)) => Ok(Self::K256(s1.mul(s2))),
             (Self::P256(s1), Self::P256(s2)) => Ok(Self::P256(s1.mul(s2))),
+            (Self::Ed25519(s1), Self::Ed25519(s2)) => Ok(Self::Ed25519(s1.mul(s2))),
             (_, _) => Err(ThresholdEcdsaError::CurveMismatch),
         }
     }
@@ -176,6 +198,10 @@ impl EccScalar {
                 let s = s.invert().unwrap_or_else(secp256r1::Scalar::zero);
                 Ok(Self::P256(s))
             }
+            Self::Ed25519(s) => {
+                let s = s.invert().unwrap_or_else(ed25519::Scalar::zero);
+                Ok(Self::Ed25519(s))
+            }
         }
     }
 
@@ -187,6 +213,7 @@ impl EccScalar {
         match self {
             Self::K256(s) => s.as_bytes().to_vec(),
             Self::P256(s) => s.as_bytes().to_vec(),
+            Self::Ed25519(s) => s.as_bytes().to_vec(),
         }
     }
 
@@ -197,6 +224,7 @@ impl EccScalar {
         bytes.extend_from_slice(&match self {
             Self::K256(s) => s.as_bytes(),
             Self::P256(s) => s.as_bytes(),
+            Self::Ed25519(s) => s.as_bytes(),
         });
         bytes
     }
@@ -250,6 +278,11 @@ impl EccScalar {
                     .ok_or(ThresholdEcdsaError::InvalidScalar)?;
                 Ok(Self::P256(s))
             }
+            EccCurveType::Ed25519 => {
+                let s = ed25519::Scalar::deserialize(bytes)
+                    .ok_or(ThresholdEcdsaError::InvalidScalar)?;
+                Ok(Self::Ed25519(s))
+            }
         }
     }
 
@@ -270,6 +303,11 @@ impl EccScalar {
                     .ok_or(ThresholdEcdsaError::InvalidScalar)?;
                 Ok(Self::P256(s))
             }
+            EccCurveType::Ed25519 => {
+                let s = ed25519::Scalar::from_wide_bytes(bytes)
+                    .ok_or(ThresholdEcdsaError::InvalidScalar)?;
+                Ok(Self::Ed25519(s))
+            }
         }
     }
 
@@ -282,8 +320,14 @@ impl EccScalar {
 
         let mut buf = vec![0u8; curve.scalar_bytes()];
 
+        // For curves whose order is much smaller than a power of 2 (such as
+        // Ed25519) clear the excess high bits to avoid excessive rejections
+        let excess_bits = 8 * curve.scalar_bytes() - curve.scalar_bits();
+        let top_byte_mask = 0xFFu8 >> excess_bits;
+
         loop {
             rng.fill_bytes(&mut buf);
+            buf[0] &= top_byte_mask;
             if let Ok(scalar) = Self::deserialize(curve, &buf) {
                 buf.zeroize();
                 return Ok(scalar);
@@ -301,6 +345,7 @@ impl EccScalar {
         match self {
             Self::K256(s) => s.is_zero(),
             Self::P256(s) => s.is_zero(),
+            Self::Ed25519(s) => s.is_zero(),
         }
     }
 
@@ -309,6 +354,7 @@ impl EccScalar {
         match self {
             Self::K256(s) => s.is_high(),
             Self::P256(s) => s.is_high(),
+            Self::Ed25519(s) => s.is_high(),
         }
     }
 
@@ -321,6 +367,7 @@ impl EccScalar {
         match self {
             Self::K256(s) => Self::K256(s.negate()),
             Self::P256(s) => Self::P256(s.negate()),
+            Self::Ed25519(s) => Self::Ed25519(s.negate()),
         }
     }
 
@@ -332,6 +379,7 @@ impl EccScalar {
         match curve {
             EccCurveType::K256 => Self::K256(secp256k1::Scalar::zero()),
             EccCurveType::P256 => Self::P256(secp256r1::Scalar::zero()),
+            EccCurveType::Ed25519 => Self::Ed25519(ed25519::Scalar::zero()),
         }
     }
 
@@ -343,6 +391,7 @@ impl EccScalar {
         match curve {
             EccCurveType::K256 => Self::K256(secp256k1::Scalar::one()),
             EccCurveType::P256 => Self::P256(secp256r1::Scalar::one()),
+            EccCurveType::Ed25519 => Self::Ed25519(ed25519::Scalar::one()),
         }
     }
 
@@ -351,6 +400,7 @@ impl EccScalar {
         match curve {
             EccCurveType::K256 => Self::K256(secp256k1::Scalar::from(n)),
             EccCurveType::P256 => Self::P256(secp256r1::Scalar::from(n)),
+            EccCurveType::Ed25519 => Self::Ed25519(ed25519::Scalar::from(n)),
         }
     }
 
@@ -382,6 +432,8 @@ impl<'de> Deserialize<'de> for EccScalar {
 #[zeroize(drop)]
 pub enum EccScalarBytes {
     K256([u8; 32]),
+    P256([u8; 32]),
+    Ed25519([u8; 32]),
 }
 
 impl TryFrom<&EccScalarBytes> for EccScalar {
@@ -390,6 +442,8 @@ impl TryFrom<&EccScalarBytes> for EccScalar {
     fn try_from(bytes: &EccScalarBytes) -> ThresholdEcdsaResult<Self> {
         match bytes {
             EccScalarBytes::K256(raw) => EccScalar::deserialize(EccCurveType::K256, raw),
+            EccScalarBytes::P256(raw) => EccScalar::deserialize(EccCurveType::P256, raw),
+            EccScalarBytes::Ed25519(raw) => EccScalar::deserialize(EccCurveType::Ed25519, raw),
         }
     }
 }
@@ -404,8 +458,15 @@ impl TryFrom<&EccScalar> for EccScalarBytes {
                     ThresholdEcdsaError::SerializationError(format!("{:?}", e))
                 })?))
             }
-            _ => {
-                panic!("we don't support other curves yet at the higher layers");
+            EccCurveType::P256 => {
+                Ok(Self::P256(scalar.serialize().try_into().map_err(|e| {
+                    ThresholdEcdsaError::SerializationError(format!("{:?}", e))
+                })?))
+            }
+            EccCurveType::Ed25519 => {
+                Ok(Self::Ed25519(scalar.serialize().try_into().map_err(
+                    |e| ThresholdEcdsaError::SerializationError(format!("{:?}", e)),
+                )?))
             }
         }
     }
@@ -415,6 +476,7 @@ impl TryFrom<&EccScalar> for EccScalarBytes {
 pub enum EccPoint {
     K256(secp256k1::Point),
     P256(secp256r1::Point),
+    Ed25519(ed25519::Point),
 }
 
 impl fmt::Debug for EccPoint {
@@ -434,6 +496,7 @@ impl EccPoint {
         match curve {
             EccCurveType::K256 => Self::K256(secp256k1::Point::identity()),
             EccCurveType::P256 => Self::P256(secp256r1::Point::identity()),
+            EccCurveType::Ed25519 => Self::Ed25519(ed25519::Point::identity()),
         }
     }
 
@@ -442,6 +505,7 @@ impl EccPoint {
         match curve {
             EccCurveType::K256 => Ok(Self::K256(secp256k1::Point::generator())),
             EccCurveType::P256 => Ok(Self::P256(secp256r1::Point::generator())),
+            EccCurveType::Ed25519 => Ok(Self::Ed25519(ed25519::Point::generator())),
         }
     }
 
@@ -460,13 +524,20 @@ impl EccPoint {
         They are precomputed here to avoid invoking hash2curve many times. The
         test generator_h_has_expected_value compares these values to the output
         of hash2curve.
+
+        hash2curve is not implemented for Ed25519 in this library. Its value
+        was computed offline using the edwards25519_XMD:SHA-512_ELL2_RO_ suite
+        of RFC 9380 with the same input and domain separator.
         */
         let h = match curve {
             EccCurveType::K256 => {
-                hex!("037bdcfc024cf697a41fd3cda2436c843af5669e50042be3314a532d5b70572f59")
+                hex!("037bdcfc024cf697a41fd3cda2436c843af5669e50042be3314a532d5b70572f59").to_vec()
             }
             EccCurveType::P256 => {
-                hex!("036774e87305efcb97c0ce289d57cd721972845ca33eccb8026c6d7c1c4182e7c1")
+                hex!("036774e87305efcb97c0ce289d57cd721972845ca33eccb8026c6d7c1c4182e7c1").to_vec()
+            }
+            EccCurveType::Ed25519 => {
+                hex!("316c04e1d5898a0620751529953edb5c6eea3b1076f4571a18686a2cec809df9").to_vec()
             }
         };
 
@@ -477,6 +548,7 @@ impl EccPoint {
         match self {
             Self::K256(_) => EccCurveType::K256,
             Self::P256(_) => EccCurveType::P256,
+            Self::Ed25519(_) => EccCurveType::Ed25519,
         }
     }
 
@@ -522,6 +594,7 @@ impl EccPoint {
         match (self, other) {
             (Self::K256(pt1), Self::K256(pt2)) => Ok(Self::K256(pt1.add(pt2))),
             (Self::P256(pt1), Self::P256(pt2)) => Ok(Self::P256(pt1.add(pt2))),
+            (Self::Ed25519(pt1), Self::Ed25519(pt2)) => Ok(Self::Ed25519(pt1.add(pt2))),
             (_, _) => Err(ThresholdEcdsaError::CurveMismatch),
         }
     }
@@ -531,6 +604,7 @@ impl EccPoint {
         match (self, other) {
             (Self::K256(pt1), Self::K256(pt2)) => Ok(Self::K256(pt1.sub(pt2))),
             (Self::P256(pt1), Self::P256(pt2)) => Ok(Self::P256(pt1.sub(pt2))),
+            (Self::Ed25519(pt1), Self::Ed25519(pt2)) => Ok(Self::Ed25519(pt1.sub(pt2))),
             (_, _) => Err(ThresholdEcdsaError::CurveMismatch),
         }
     }
@@ -540,6 +614,7 @@ impl EccPoint {
         match (self, scalar) {
             (Self::K256(pt), EccScalar::K256(s)) => Ok(Self::K256(pt.mul(s))),
             (Self::P256(pt), EccScalar::P256(s)) => Ok(Self::P256(pt.mul(s))),
+            (Self::Ed25519(pt), EccScalar::Ed25519(s)) => Ok(Self::Ed25519(pt.mul(s))),
             (_, _) => Err(ThresholdEcdsaError::CurveMismatch),
         }
     }
@@ -549,6 +624,7 @@ impl EccPoint {
         match self {
             Self::K256(pt) => Self::K256(pt.double()),
             Self::P256(pt) => Self::P256(pt.double()),
+            Self::Ed25519(pt) => Self::Ed25519(pt.double()),
         }
     }
 
@@ -596,6 +672,13 @@ impl EccPoint {
                 Ok(Self::P256(secp256r1::Point::lincomb(pt1, s1, pt2, s2)))
             }
 
+            (
+                Self::Ed25519(pt1),
+                EccScalar::Ed25519(s1),
+                Self::Ed25519(pt2),
+                EccScalar::Ed25519(s2),
+            ) => Ok(Self::Ed25519(ed25519::Point::lincomb(pt1, s1, pt2, s2))),
+
             (_, _, _, _) => Err(ThresholdEcdsaError::CurveMismatch),
         }
     }
@@ -615,13 +698,15 @@ impl EccPoint {
 
     /// Serialize a point in compressed form
     ///
-    /// The output is in SEC1 format, and will be 1 header byte
-    /// followed by a single field element, which for K256 and P256 is
-    /// 32 bytes long.
+    /// For K256 and P256 the output is in SEC1 format, and will be 1 header
+    /// byte followed by a single field element, which is 32 bytes long.
+    ///
+    /// For Ed25519 the output is the 32 byte encoding of RFC 8032.
     pub fn serialize(&self) -> Vec<u8> {
         match self {
             Self::K256(pt) => pt.serialize(),
             Self::P256(pt) => pt.serialize(),
+            Self::Ed25519(pt) => pt.serialize(),
         }
     }
 
@@ -636,6 +721,7 @@ impl EccPoint {
         bytes.extend_from_slice(&match self {
             Self::K256(pt) => pt.serialize(),
             Self::P256(pt) => pt.serialize(),
+            Self::Ed25519(pt) => pt.serialize(),
         });
 
         bytes
@@ -646,10 +732,15 @@ impl EccPoint {
     /// The output is in SEC1 format, and will be 1 header byte
     /// followed by a two field elements, which for K256 and P256 is
     /// 32 bytes long each.
-    fn serialize_uncompressed(&self) -> Vec<u8> {
+    ///
+    /// SEC1 encoding is not defined for Ed25519 points.
+    fn serialize_uncompressed(&self) -> ThresholdEcdsaResult<Vec<u8>> {
         match self {
-            Self::K256(pt) => pt.serialize_uncompressed(),
-            Self::P256(pt) => pt.serialize_uncompressed(),
+            Self::K256(pt) => Ok(pt.serialize_uncompressed()),
+            Self::P256(pt) => Ok(pt.serialize_uncompressed()),
+            Self::Ed25519(_) => Err(ThresholdEcdsaError::InvalidArguments(
+                "Uncompressed encoding is not supported for Ed25519 points".to_string(),
+            )),
         }
     }
 
@@ -657,7 +748,7 @@ impl EccPoint {
     pub fn affine_x(&self) -> ThresholdEcdsaResult<EccFieldElement> {
         let curve_type = self.curve_type();
         let field_bytes = curve_type.field_bytes();
-        let z = self.serialize_uncompressed();
+        let z = self.serialize_uncompressed()?;
         EccFieldElement::from_bytes(curve_type, &z[1..field_bytes + 1])
     }
 
@@ -665,7 +756,7 @@ impl EccPoint {
     pub fn affine_y(&self) -> ThresholdEcdsaResult<EccFieldElement> {
         let curve_type = self.curve_type();
         let field_bytes = curve_type.field_bytes();
-        let z = self.serialize_uncompressed();
+        let z = self.serialize_uncompressed()?;
         EccFieldElement::from_bytes(curve_type, &z[1 + field_bytes..])
     }
 
@@ -674,6 +765,7 @@ impl EccPoint {
         match self {
             Self::K256(p) => Ok(p.is_infinity()),
             Self::P256(p) => Ok(p.is_infinity()),
+            Self::Ed25519(p) => Ok(p.is_infinity()),
         }
     }
 
@@ -695,6 +787,12 @@ impl EccPoint {
             return Err(ThresholdEcdsaError::InvalidPoint);
         }
 
+        // Ed25519 has a single (compressed) point format, which also
+        // encodes the identity element
+        if curve == EccCurveType::Ed25519 {
+            return Self::deserialize_any_format(curve, bytes);
+        }
+
         // We encode the point at infinity as all-zero byte string of the same
         // length as a compressed point. This is non-standard (per SEC1) but a
         // fixed length point format is easier to reason about.
@@ -735,6 +833,11 @@ impl EccPoint {
                     .ok_or(ThresholdEcdsaError::InvalidPoint)?;
                 Ok(Self::P256(pt))
             }
+            EccCurveType::Ed25519 => {
+                let pt =
+                    ed25519::Point::deserialize(bytes).ok_or(ThresholdEcdsaError::InvalidPoint)?;
+                Ok(Self::Ed25519(pt))
+            }
         }
     }
 }
@@ -0,0 +1,222 @@
+use curve25519_dalek::{
+    constants::ED25519_BASEPOINT_POINT,
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    traits::{Identity, IsIdentity},
+};
+use zeroize::Zeroize;
+
+#[derive(Copy, Clone, Eq, PartialEq, Zeroize)]
+pub struct Scalar {
+    s: curve25519_dalek::scalar::Scalar,
+}
+
+impl Scalar {
+    pub const BYTES: usize = 32;
+
+    /// Internal constructor (private)
+    fn new(s: curve25519_dalek::scalar::Scalar) -> Self {
+        Self { s }
+    }
+
+    /// Deserialize a scalar
+    ///
+    /// The input is the big-endian encoding of the scalar, in order to
+    /// be consistent with the other curves supported by this library.
+    ///
+    /// If the input is not the correct length or is out of range
+    /// then None is returned
+    pub fn deserialize(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::BYTES {
+            return None;
+        }
+
+        let mut le_bytes = [0u8; Self::BYTES];
+        le_bytes.copy_from_slice(bytes);
+        le_bytes.reverse();
+
+        let s = curve25519_dalek::scalar::Scalar::from_canonical_bytes(le_bytes);
+        le_bytes.zeroize();
+        s.map(Self::new)
+    }
+
+    /// Compute the scalar from a larger value
+    ///
+    /// The input is allowed to be up to twice the length of a scalar. It is
+    /// interpreted as a big-endian encoded integer, and reduced modulo the
+    /// group order.
+    pub fn from_wide_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() > Self::BYTES * 2 {
+            return None;
+        }
+
+        let mut le_bytes = [0u8; Self::BYTES * 2];
+        for (i, b) in bytes.iter().rev().enumerate() {
+            le_bytes[i] = *b;
+        }
+
+        let s = curve25519_dalek::scalar::Scalar::from_bytes_mod_order_wide(&le_bytes);
+        le_bytes.zeroize();
+        Some(Self::new(s))
+    }
+
+    /// Return constant zero
+    pub fn zero() -> Self {
+        Self::new(curve25519_dalek::scalar::Scalar::zero())
+    }
+
+    /// Return constant one
+    pub fn one() -> Self {
+        Self::new(curve25519_dalek::scalar::Scalar::one())
+    }
+
+    /// Create a scalar from a small integer
+    pub fn from(v: u64) -> Self {
+        Self::new(curve25519_dalek::scalar::Scalar::from(v))
+    }
+
+    /// Add two scalars
+    pub fn add(&self, other: &Self) -> Self {
+        Self::new(self.s + other.s)
+    }
+
+    /// Subtract two scalars
+    pub fn sub(&self, other: &Self) -> Self {
+        Self::new(self.s - other.s)
+    }
+
+    /// Multiply two scalars
+    pub fn mul(&self, other: &Self) -> Self {
+        Self::new(self.s * other.s)
+    }
+
+    /// Perform modular inversion
+    ///
+    /// Returns None if no modular inverse exists (ie because the
+    /// scalar is zero)
+    pub fn invert(&self) -> Option<Self> {
+        if self.is_zero() {
+            None
+        } else {
+            Some(Self::new(self.s.invert()))
+        }
+    }
+
+    /// Check if the scalar is zero
+    pub fn is_zero(&self) -> bool {
+        self.s == curve25519_dalek::scalar::Scalar::zero()
+    }
+
+    /// Return if the scalar is "high"
+    ///
+    /// This is false if s*2 would not overflow
+    ///
+    /// This comparison is not constant time; it is only used for
+    /// normalizing public values.
+    pub fn is_high(&self) -> bool {
+        self.as_bytes() > self.negate().as_bytes()
+    }
+
+    /// Return the negation of the scalar
+    pub fn negate(&self) -> Self {
+        Self::new(-self.s)
+    }
+
+    /// Return the encoding of the scalar as bytes
+    ///
+    /// The return value is fixed length big endian encoding, with
+    /// zero padding if required. Note that this is the reverse of the
+    /// byte order used for scalars in Ed25519 signatures.
+    pub fn as_bytes(&self) -> [u8; Self::BYTES] {
+        let mut bytes = self.s.to_bytes();
+        bytes.reverse();
+        bytes
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct Point {
+    p: EdwardsPoint,
+}
+
+impl Point {
+    pub const BYTES: usize = 32;
+
+    /// Internal constructor (private)
+    fn new(p: EdwardsPoint) -> Self {
+        Self { p }
+    }
+
+    /// Deserialize a point
+    ///
+    /// Only the standard 32 byte compressed encoding is accepted. The
+    /// encoding must be canonical, and the point must be within the
+    /// prime order subgroup.
+    ///
+    /// If the value encoded is not a valid point, then None is returned
+    pub fn deserialize(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::BYTES {
+            return None;
+        }
+
+        let pt = CompressedEdwardsY::from_slice(bytes).decompress()?;
+
+        // Reject non-canonical encodings, so that each point has
+        // exactly one valid encoding
+        if pt.compress().as_bytes() != bytes {
+            return None;
+        }
+
+        if !pt.is_torsion_free() {
+            return None;
+        }
+
+        Some(Self::new(pt))
+    }
+
+    /// Return the identity element
+    pub fn identity() -> Self {
+        Self::new(EdwardsPoint::identity())
+    }
+
+    /// Return the standard generator of the group
+    pub fn generator() -> Self {
+        Self::new(ED25519_BASEPOINT_POINT)
+    }
+
+    /// Perform multi-exponentiation
+    ///
+    /// Equivalent to p1*s1 + p2*s2
+    pub fn lincomb(p1: &Point, s1: &Scalar, p2: &Point, s2: &Scalar) -> Self {
+        Self::new(p1.p * s1.s + p2.p * s2.s)
+    }
+
+    /// Add two points
+    pub fn add(&self, other: &Self) -> Self {
+        Self::new(self.p + other.p)
+    }
+
+    /// Subtract two points
+    pub fn sub(&self, other: &Self) -> Self {
+        Self::new(self.p - other.p)
+    }
+
+    /// Perform point doubling
+    pub fn double(&self) -> Self {
+        Self::new(self.p + self.p)
+    }
+
+    /// Scalar multiplication
+    pub fn mul(&self, scalar: &Scalar) -> Self {
+        Self::new(self.p * scalar.s)
+    }
+
+    /// Serialize the point to bytes in compressed format
+    pub fn serialize(&self) -> Vec<u8> {
+        self.p.compress().to_bytes().to_vec()
+    }
+
+    /// Check if the point is the identity element
+    pub fn is_infinity(&self) -> bool {
+        self.p.is_identity()
+    }
+}
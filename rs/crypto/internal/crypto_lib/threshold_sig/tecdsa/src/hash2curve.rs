@@ -38,7 +38,7 @@ fn sqrt_ratio(
     if curve_type == EccCurveType::P256 || curve_type == EccCurveType::K256 {
         // Fast codepath for curves where p == 3 (mod 4)
         // See https://www.ietf.org/archive/id/draft-irtf-cfrg-hash-to-curve-14.html#appendix-F.2.1.2
-        let c2 = EccFieldElement::sswu_c2(curve_type);
+        let c2 = EccFieldElement::sswu_c2(curve_type)?;
 
         let tv1 = v.square()?;
         let tv2 = u.mul(v)?;
@@ -59,7 +59,7 @@ fn sqrt_ratio(
         // that we may want to consider using in the future, should we require
         // hash2curve support for curves with p == 1 (mod 4)
 
-        let z = EccFieldElement::sswu_z(curve_type);
+        let z = EccFieldElement::sswu_z(curve_type)?;
         let vinv = v.invert();
         let uov = u.mul(&vinv)?;
         let (uov_is_qr, sqrt_uov) = uov.sqrt();
@@ -76,10 +76,10 @@ fn sqrt_ratio(
 fn sswu(u: &EccFieldElement) -> ThresholdEcdsaResult<(EccFieldElement, EccFieldElement)> {
     let curve = u.curve_type();
 
-    let a = EccFieldElement::sswu_a(curve);
-    let b = EccFieldElement::sswu_b(curve);
-    let z = EccFieldElement::sswu_z(curve);
-    let one = EccFieldElement::one(curve);
+    let a = EccFieldElement::sswu_a(curve)?;
+    let b = EccFieldElement::sswu_b(curve)?;
+    let z = EccFieldElement::sswu_z(curve)?;
+    let one = EccFieldElement::one(curve)?;
 
     let tv1 = z.mul(&u.square()?)?;
     let mut tv2 = tv1.square()?;
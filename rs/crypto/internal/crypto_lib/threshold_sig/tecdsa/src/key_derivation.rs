@@ -63,16 +63,19 @@ impl DerivationPath {
     ///
     /// Extended to support larger inputs, which is needed for
     /// deriving the canister public key
+    ///
+    /// BIP32 itself is only defined for secp256k1. For secp256r1 the
+    /// same construction is used, as is done by SLIP-0010.
+    ///
+    /// For Ed25519 the group order is only slightly larger than 2**252,
+    /// so rejecting out of range values of iL (as BIP32 does) would
+    /// cause a retry for nearly every index. Instead iL is reduced
+    /// modulo the group order, which introduces only a negligible bias.
     fn bip32_ckdpub(
         public_key: &EccPoint,
         chain_key: &[u8],
         index: &DerivationIndex,
     ) -> ThresholdEcdsaResult<(EccPoint, Vec<u8>, EccScalar)> {
-        // BIP32 is only defined for secp256k1
-        if public_key.curve_type() != EccCurveType::K256 {
-            return Err(ThresholdEcdsaError::CurveMismatch);
-        }
-
         let mut hmac = Hmac::<Sha512>::new(chain_key);
 
         hmac.write(&public_key.serialize());
@@ -80,47 +83,66 @@ impl DerivationPath {
 
         let hmac_output = hmac.finish();
 
-        let key_offset = EccScalar::from_bytes_wide(public_key.curve_type(), &hmac_output[..32])?;
+        let curve_type = public_key.curve_type();
+
+        let key_offset = EccScalar::from_bytes_wide(curve_type, &hmac_output[..32])?;
 
         let new_chain_key = hmac_output[32..].to_vec();
 
         let new_key = public_key.add_points(&EccPoint::mul_by_g(&key_offset)?)?;
 
+        let out_of_range =
+            curve_type != EccCurveType::Ed25519 && key_offset.serialize() != hmac_output[..32];
+
         // If iL >= order or new_key=inf, try again with the "next" index
-        if key_offset.serialize() != hmac_output[..32] || new_key.is_infinity()? {
+        if out_of_range || new_key.is_infinity()? {
             Self::bip32_ckdpub(public_key, chain_key, &index.next())
         } else {
             Ok((new_key, new_chain_key, key_offset))
         }
     }
 
+    /// Derive the tweak and chain key from a master public key
+    ///
+    /// Derivation from the master key starts with an all-zero chain key.
     pub fn derive_tweak(
         &self,
         master_public_key: &EccPoint,
     ) -> ThresholdEcdsaResult<(EccScalar, Vec<u8>)> {
-        let curve_type = master_public_key.curve_type();
+        self.derive_tweak_with_chain_key(master_public_key, &[0; 32])
+    }
 
-        if curve_type == EccCurveType::K256 {
-            let mut derived_key = *master_public_key;
-            let mut derived_chain_key = vec![0; 32];
-            let mut derived_offset = EccScalar::zero(curve_type);
+    /// Derive the tweak and chain key from an arbitrary public key
+    ///
+    /// This allows continuing a derivation from a previously derived
+    /// public key and chain key, as is possible with BIP32.
+    pub fn derive_tweak_with_chain_key(
+        &self,
+        public_key: &EccPoint,
+        chain_key: &[u8],
+    ) -> ThresholdEcdsaResult<(EccScalar, Vec<u8>)> {
+        if chain_key.len() != 32 {
+            return Err(ThresholdEcdsaError::InvalidArguments(format!(
+                "Invalid chain key length {}",
+                chain_key.len()
+            )));
+        }
 
-            for idx in &self.path {
-                let (next_derived_key, next_chain_key, next_offset) =
-                    Self::bip32_ckdpub(&derived_key, &derived_chain_key, idx)?;
+        let curve_type = public_key.curve_type();
 
-                derived_key = next_derived_key;
-                derived_chain_key = next_chain_key;
-                derived_offset = derived_offset.add(&next_offset)?;
-            }
+        let mut derived_key = *public_key;
+        let mut derived_chain_key = chain_key.to_vec();
+        let mut derived_offset = EccScalar::zero(curve_type);
 
-            Ok((derived_offset, derived_chain_key))
-        } else {
-            // Key derivation is not currently defined for curves other than secp256k1
-            Err(ThresholdEcdsaError::InvalidArguments(format!(
-                "Currently key derivation not defined for {}",
-                curve_type
-            )))
+        for idx in &self.path {
+            let (next_derived_key, next_chain_key, next_offset) =
+                Self::bip32_ckdpub(&derived_key, &derived_chain_key, idx)?;
+
+            derived_key = next_derived_key;
+            derived_chain_key = next_chain_key;
+            derived_offset = derived_offset.add(&next_offset)?;
         }
+
+        Ok((derived_offset, derived_chain_key))
     }
 }
@@ -69,6 +69,21 @@
 //! * Generation and verification of signature shares
 //! * Generation and verification of combined signatures
 //!
+//! ## Protocol: Threshold Schnorr Signatures
+//!
+//! Files: `bip340.rs` and `eddsa.rs`
+//!
+//! Threshold Schnorr signatures reuse the IDKG transcripts, but are
+//! much simpler than ECDSA since no multiplication of shared values is
+//! required. Both the key and the presignature are unmasked transcripts,
+//! and each signature share is simply the opening of `k + e*x` where `e`
+//! is the Schnorr challenge. As with ECDSA, the presignature is first
+//! rerandomized using the provided randomness.
+//!
+//! Two variants are supported:
+//!  * BIP-340 Schnorr signatures over secp256k1, as used by Taproot
+//!  * Ed25519 signatures as specified in RFC 8032
+//!
 //! ## Protocol: MEGa Encryption
 //!
 //! File: `mega.rs`
@@ -130,8 +145,9 @@
 //! notable performance improvements.
 //!
 //! Currently, curve arithmetic is implemented using the `k256` and `p256`
-//! crates from the RustCrypto project. Wrappers for these types are
-//! included in the `group` subdirectory.
+//! crates from the RustCrypto project, and `curve25519-dalek` for
+//! Ed25519. Wrappers for these types are included in the `group`
+//! subdirectory.
 //!
 //! ## Utility Functions: H2C and XMD
 //!
@@ -246,8 +262,10 @@ pub enum ThresholdEcdsaError {
 
 pub type ThresholdEcdsaResult<T> = std::result::Result<T, ThresholdEcdsaError>;
 
+pub mod bip340;
 mod complaints;
 mod dealings;
+pub mod eddsa;
 mod fe;
 mod group;
 mod hash2curve;
@@ -269,6 +287,10 @@ pub use crate::poly::*;
 pub use crate::transcript::*;
 
 pub use crate::key_derivation::{DerivationIndex, DerivationPath};
+pub use bip340::{ThresholdBip340CombinedSignatureInternal, ThresholdBip340SignatureShareInternal};
+pub use eddsa::{
+    ThresholdEd25519CombinedSignatureInternal, ThresholdEd25519SignatureShareInternal,
+};
 pub use sign::{ThresholdEcdsaCombinedSigInternal, ThresholdEcdsaSigShareInternal};
 
 /// Create MEGa encryption keypair
@@ -317,6 +339,9 @@ pub fn create_dealing(
 ) -> Result<IDkgDealingInternal, IdkgCreateDealingInternalError> {
     let curve = match algorithm_id {
         AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEcdsaSecp256r1 => Ok(EccCurveType::P256),
+        AlgorithmId::ThresholdSchnorrBip340 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEd25519 => Ok(EccCurveType::Ed25519),
         _ => Err(IdkgCreateDealingInternalError::UnsupportedAlgorithm),
     }?;
 
@@ -368,6 +393,9 @@ pub fn create_transcript(
 ) -> Result<IDkgTranscriptInternal, IDkgCreateTranscriptInternalError> {
     let curve = match algorithm_id {
         AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEcdsaSecp256r1 => Ok(EccCurveType::P256),
+        AlgorithmId::ThresholdSchnorrBip340 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEd25519 => Ok(EccCurveType::Ed25519),
         _ => Err(IDkgCreateTranscriptInternalError::UnsupportedAlgorithm),
     }?;
 
@@ -565,6 +593,9 @@ pub fn publicly_verify_dealing(
 ) -> Result<(), IDkgVerifyDealingInternalError> {
     let curve = match algorithm_id {
         AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEcdsaSecp256r1 => Ok(EccCurveType::P256),
+        AlgorithmId::ThresholdSchnorrBip340 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEd25519 => Ok(EccCurveType::Ed25519),
         _ => Err(IDkgVerifyDealingInternalError::UnsupportedAlgorithm),
     }?;
 
@@ -597,6 +628,9 @@ pub fn privately_verify_dealing(
 ) -> Result<(), IDkgVerifyDealingInternalError> {
     let curve = match algorithm_id {
         AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEcdsaSecp256r1 => Ok(EccCurveType::P256),
+        AlgorithmId::ThresholdSchnorrBip340 => Ok(EccCurveType::K256),
+        AlgorithmId::ThresholdEd25519 => Ok(EccCurveType::Ed25519),
         _ => Err(IDkgVerifyDealingInternalError::UnsupportedAlgorithm),
     }?;
 
@@ -660,6 +694,9 @@ fn signature_parameters(algorithm_id: AlgorithmId) -> Option<(EccCurveType, usiz
         AlgorithmId::ThresholdEcdsaSecp256k1 => {
             Some((EccCurveType::K256, EccCurveType::K256.scalar_bytes()))
         }
+        AlgorithmId::ThresholdEcdsaSecp256r1 => {
+            Some((EccCurveType::P256, EccCurveType::P256.scalar_bytes()))
+        }
         _ => None,
     }
 }
@@ -808,6 +845,7 @@ pub fn combine_sig_shares(
 ) -> Result<ThresholdEcdsaCombinedSigInternal, ThresholdEcdsaCombineSigSharesInternalError> {
     let curve_type = match algorithm_id {
         AlgorithmId::ThresholdEcdsaSecp256k1 => EccCurveType::K256,
+        AlgorithmId::ThresholdEcdsaSecp256r1 => EccCurveType::P256,
         _ => return Err(ThresholdEcdsaCombineSigSharesInternalError::UnsupportedAlgorithm),
     };
 
@@ -922,6 +960,294 @@ pub fn derive_public_key(
     )?)
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdSchnorrCreateSigShareInternalError {
+    InconsistentCommitments,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdSchnorrCreateSigShareInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            ThresholdEcdsaError::UnexpectedCommitmentType => Self::InconsistentCommitments,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdSchnorrVerifySigShareInternalError {
+    InconsistentCommitments,
+    InvalidSignatureShare,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdSchnorrVerifySigShareInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            ThresholdEcdsaError::UnexpectedCommitmentType => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidSignatureShare => Self::InvalidSignatureShare,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdSchnorrCombineSigSharesInternalError {
+    InconsistentCommitments,
+    InsufficientShares,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdSchnorrCombineSigSharesInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            ThresholdEcdsaError::UnexpectedCommitmentType => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InsufficientDealings => Self::InsufficientShares,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ThresholdSchnorrVerifySignatureInternalError {
+    InvalidSignature,
+    InconsistentCommitments,
+    InternalError(String),
+}
+
+impl From<ThresholdEcdsaError> for ThresholdSchnorrVerifySignatureInternalError {
+    fn from(e: ThresholdEcdsaError) -> Self {
+        match e {
+            ThresholdEcdsaError::CurveMismatch => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidCommitment => Self::InconsistentCommitments,
+            ThresholdEcdsaError::UnexpectedCommitmentType => Self::InconsistentCommitments,
+            ThresholdEcdsaError::InvalidSignature => Self::InvalidSignature,
+            x => Self::InternalError(format!("{:?}", x)),
+        }
+    }
+}
+
+/// Create a new threshold BIP-340 Schnorr signature share
+///
+/// The key transcript and presignature transcript must both be
+/// unmasked transcripts over secp256k1, and key_opening and
+/// presig_opening are our openings of the commitments in those
+/// transcripts.
+///
+/// Unlike ECDSA, the message is not hashed by the caller; BIP-340
+/// allows messages of any length.
+pub fn create_bip340_signature_share(
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    nonce: Randomness,
+    key_transcript: &IDkgTranscriptInternal,
+    key_opening: &CommitmentOpening,
+    presig_transcript: &IDkgTranscriptInternal,
+    presig_opening: &CommitmentOpening,
+) -> Result<ThresholdBip340SignatureShareInternal, ThresholdSchnorrCreateSigShareInternalError> {
+    ThresholdBip340SignatureShareInternal::new(
+        derivation_path,
+        message,
+        nonce,
+        key_transcript,
+        key_opening,
+        presig_transcript,
+        presig_opening,
+    )
+    .map_err(|e| e.into())
+}
+
+/// Verify a BIP-340 signature share
+///
+/// The values provided must be consistent with when the signature share
+/// was created
+pub fn verify_bip340_signature_share(
+    sig_share: &ThresholdBip340SignatureShareInternal,
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    signer_index: NodeIndex,
+    key_transcript: &IDkgTranscriptInternal,
+    presig_transcript: &IDkgTranscriptInternal,
+) -> Result<(), ThresholdSchnorrVerifySigShareInternalError> {
+    sig_share
+        .verify(
+            derivation_path,
+            message,
+            randomness,
+            signer_index,
+            key_transcript,
+            presig_transcript,
+        )
+        .map_err(|e| e.into())
+}
+
+/// Combine sufficient signature shares into a BIP-340 signature
+///
+/// The signature shares must be verified prior to use, and there must
+/// be at least reconstruction_threshold many of them.
+pub fn combine_bip340_signature_shares(
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    key_transcript: &IDkgTranscriptInternal,
+    presig_transcript: &IDkgTranscriptInternal,
+    reconstruction_threshold: NumberOfNodes,
+    sig_shares: &BTreeMap<NodeIndex, ThresholdBip340SignatureShareInternal>,
+) -> Result<ThresholdBip340CombinedSignatureInternal, ThresholdSchnorrCombineSigSharesInternalError>
+{
+    ThresholdBip340CombinedSignatureInternal::new(
+        derivation_path,
+        message,
+        randomness,
+        key_transcript,
+        presig_transcript,
+        reconstruction_threshold,
+        sig_shares,
+    )
+    .map_err(|e| e.into())
+}
+
+/// Verify a threshold BIP-340 signature
+///
+/// In addition to checking that the signature itself is consistent
+/// with the provided message and the public key associated with
+/// `derivation_path`, this function also verifies that the signature
+/// was generated correctly with regards to the provided presignature
+/// transcript and randomness.
+pub fn verify_threshold_bip340_signature(
+    signature: &ThresholdBip340CombinedSignatureInternal,
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    presig_transcript: &IDkgTranscriptInternal,
+    key_transcript: &IDkgTranscriptInternal,
+) -> Result<(), ThresholdSchnorrVerifySignatureInternalError> {
+    signature
+        .verify(
+            derivation_path,
+            message,
+            randomness,
+            presig_transcript,
+            key_transcript,
+        )
+        .map_err(|e| e.into())
+}
+
+/// Create a new threshold Ed25519 signature share
+///
+/// The key transcript and presignature transcript must both be
+/// unmasked transcripts over Ed25519, and key_opening and
+/// presig_opening are our openings of the commitments in those
+/// transcripts.
+///
+/// The message is signed as is (that is, "pure" Ed25519 as specified
+/// in RFC 8032, not the prehashed Ed25519ph variant).
+pub fn create_ed25519_signature_share(
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    nonce: Randomness,
+    key_transcript: &IDkgTranscriptInternal,
+    key_opening: &CommitmentOpening,
+    presig_transcript: &IDkgTranscriptInternal,
+    presig_opening: &CommitmentOpening,
+) -> Result<ThresholdEd25519SignatureShareInternal, ThresholdSchnorrCreateSigShareInternalError> {
+    ThresholdEd25519SignatureShareInternal::new(
+        derivation_path,
+        message,
+        nonce,
+        key_transcript,
+        key_opening,
+        presig_transcript,
+        presig_opening,
+    )
+    .map_err(|e| e.into())
+}
+
+/// Verify an Ed25519 signature share
+///
+/// The values provided must be consistent with when the signature share
+/// was created
+pub fn verify_ed25519_signature_share(
+    sig_share: &ThresholdEd25519SignatureShareInternal,
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    signer_index: NodeIndex,
+    key_transcript: &IDkgTranscriptInternal,
+    presig_transcript: &IDkgTranscriptInternal,
+) -> Result<(), ThresholdSchnorrVerifySigShareInternalError> {
+    sig_share
+        .verify(
+            derivation_path,
+            message,
+            randomness,
+            signer_index,
+            key_transcript,
+            presig_transcript,
+        )
+        .map_err(|e| e.into())
+}
+
+/// Combine sufficient signature shares into an Ed25519 signature
+///
+/// The signature shares must be verified prior to use, and there must
+/// be at least reconstruction_threshold many of them.
+pub fn combine_ed25519_signature_shares(
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    key_transcript: &IDkgTranscriptInternal,
+    presig_transcript: &IDkgTranscriptInternal,
+    reconstruction_threshold: NumberOfNodes,
+    sig_shares: &BTreeMap<NodeIndex, ThresholdEd25519SignatureShareInternal>,
+) -> Result<ThresholdEd25519CombinedSignatureInternal, ThresholdSchnorrCombineSigSharesInternalError>
+{
+    ThresholdEd25519CombinedSignatureInternal::new(
+        derivation_path,
+        message,
+        randomness,
+        key_transcript,
+        presig_transcript,
+        reconstruction_threshold,
+        sig_shares,
+    )
+    .map_err(|e| e.into())
+}
+
+/// Verify a threshold Ed25519 signature
+///
+/// In addition to checking that the signature itself is consistent
+/// with the provided message and the public key associated with
+/// `derivation_path`, this function also verifies that the signature
+/// was generated correctly with regards to the provided presignature
+/// transcript and randomness.
+pub fn verify_threshold_ed25519_signature(
+    signature: &ThresholdEd25519CombinedSignatureInternal,
+    derivation_path: &DerivationPath,
+    message: &[u8],
+    randomness: Randomness,
+    presig_transcript: &IDkgTranscriptInternal,
+    key_transcript: &IDkgTranscriptInternal,
+) -> Result<(), ThresholdSchnorrVerifySignatureInternalError> {
+    signature
+        .verify(
+            derivation_path,
+            message,
+            randomness,
+            presig_transcript,
+            key_transcript,
+        )
+        .map_err(|e| e.into())
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum IDkgGenerateComplaintsInternalError {
     InvalidArguments(String),
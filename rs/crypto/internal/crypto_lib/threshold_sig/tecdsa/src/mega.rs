@@ -7,6 +7,12 @@ use serde::{Deserialize, Serialize};
 use std::convert::{TryFrom, TryInto};
 use zeroize::Zeroize;
 
+/// The curve of the MEGa encryption keys of the receivers
+///
+/// Nodes register a single secp256k1 MEGa key, which is used to encrypt
+/// shares of threshold keys on any of the supported curves.
+pub const MEGA_KEY_CURVE: EccCurveType = EccCurveType::K256;
+
 /// The type of MEGa ciphertext
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MEGaCiphertextType {
@@ -120,6 +126,7 @@ impl Debug for MEGaPrivateKey {
         match &self.secret {
             EccScalar::K256(_) => write!(f, "MEGaPrivateKey(EccScalar::K256) - REDACTED"),
             EccScalar::P256(_) => write!(f, "MEGaPrivateKey(EccScalar::P256) - REDACTED"),
+            EccScalar::Ed25519(_) => write!(f, "MEGaPrivateKey(EccScalar::Ed25519) - REDACTED"),
         }
     }
 }
@@ -206,30 +213,32 @@ impl MEGaCiphertext {
 
     /// Simple type verification for MEGa ciphertexts
     ///
-    /// Verifies that the ciphertext is of the expected type (single or pairs)
-    /// and is for the expected curve.
+    /// Verifies that the ciphertext is of the expected type (single or pairs),
+    /// that the ephemeral key and proof of possession are on the curve of
+    /// the recipients' MEGa keys, and that the encrypted plaintexts are
+    /// scalars of the expected curve.
     pub fn verify_is(
         &self,
         ctype: MEGaCiphertextType,
-        curve: EccCurveType,
+        key_curve: EccCurveType,
+        plaintext_curve: EccCurveType,
     ) -> ThresholdEcdsaResult<()> {
-        if self.ephemeral_key().curve_type() != curve {
+        if self.ephemeral_key().curve_type() != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
 
-        if self.pop_public_key().curve_type() != curve {
+        if self.pop_public_key().curve_type() != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
-        if self.pop_proof().curve_type()? != curve {
+        if self.pop_proof().curve_type()? != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
 
         let curves_ok = match self {
-            MEGaCiphertext::Single(c) => c.ctexts.iter().all(|x| x.curve_type() == curve),
-            MEGaCiphertext::Pairs(c) => c
-                .ctexts
-                .iter()
-                .all(|(x, y)| x.curve_type() == curve && y.curve_type() == curve),
+            MEGaCiphertext::Single(c) => c.ctexts.iter().all(|x| x.curve_type() == plaintext_curve),
+            MEGaCiphertext::Pairs(c) => c.ctexts.iter().all(|(x, y)| {
+                x.curve_type() == plaintext_curve && y.curve_type() == plaintext_curve
+            }),
         };
 
         if !curves_ok {
@@ -309,10 +318,15 @@ impl From<MEGaCiphertextPair> for MEGaCiphertext {
     }
 }
 
+/// Checks the arguments of a MEGa encryption
+///
+/// Returns the curve of the recipients' public keys and the curve of the
+/// plaintexts. These may differ, for instance when shares of a P-256 key
+/// are encrypted to recipients holding secp256k1 MEGa keys.
 fn check_plaintexts(
     plaintexts: &[EccScalar],
     recipients: &[MEGaPublicKey],
-) -> ThresholdEcdsaResult<EccCurveType> {
+) -> ThresholdEcdsaResult<(EccCurveType, EccCurveType)> {
     if plaintexts.len() != recipients.len() {
         return Err(ThresholdEcdsaError::InvalidArguments(
             "Must be as many plaintexts as recipients".to_string(),
@@ -325,27 +339,29 @@ fn check_plaintexts(
         ));
     }
 
-    let curve_type = plaintexts[0].curve_type();
+    let plaintext_curve = plaintexts[0].curve_type();
 
     for pt in plaintexts {
-        if pt.curve_type() != curve_type {
+        if pt.curve_type() != plaintext_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
     }
 
+    let key_curve = recipients[0].curve_type();
+
     for recipient in recipients {
-        if recipient.curve_type() != curve_type {
+        if recipient.curve_type() != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
     }
 
-    Ok(curve_type)
+    Ok((key_curve, plaintext_curve))
 }
 
 fn check_plaintexts_pair(
     plaintexts: &[(EccScalar, EccScalar)],
     recipients: &[MEGaPublicKey],
-) -> ThresholdEcdsaResult<EccCurveType> {
+) -> ThresholdEcdsaResult<(EccCurveType, EccCurveType)> {
     if plaintexts.len() != recipients.len() {
         return Err(ThresholdEcdsaError::InvalidArguments(
             "Must be as many plaintexts as recipients".to_string(),
@@ -358,25 +374,29 @@ fn check_plaintexts_pair(
         ));
     }
 
-    let curve_type = plaintexts[0].0.curve_type();
+    let plaintext_curve = plaintexts[0].0.curve_type();
 
     for pt in plaintexts {
-        if pt.0.curve_type() != curve_type || pt.1.curve_type() != curve_type {
+        if pt.0.curve_type() != plaintext_curve || pt.1.curve_type() != plaintext_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
     }
 
+    let key_curve = recipients[0].curve_type();
+
     for recipient in recipients {
-        if recipient.curve_type() != curve_type {
+        if recipient.curve_type() != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
     }
 
-    Ok(curve_type)
+    Ok((key_curve, plaintext_curve))
 }
 
+#[allow(clippy::too_many_arguments)]
 fn mega_hash_to_scalars(
     ctype: MEGaCiphertextType,
+    plaintext_curve: EccCurveType,
     dealer_index: NodeIndex,
     recipient_index: NodeIndex,
     associated_data: &[u8],
@@ -384,8 +404,6 @@ fn mega_hash_to_scalars(
     ephemeral_key: &EccPoint,
     shared_secret: &EccPoint,
 ) -> ThresholdEcdsaResult<Vec<EccScalar>> {
-    let curve_type = public_key.curve_type();
-
     let count = match ctype {
         MEGaCiphertextType::Single => 1,
         MEGaCiphertextType::Pairs => 2,
@@ -398,7 +416,7 @@ fn mega_hash_to_scalars(
     ro.add_point("public_key", public_key)?;
     ro.add_point("ephemeral_key", ephemeral_key)?;
     ro.add_point("shared_secret", shared_secret)?;
-    ro.output_scalars(curve_type, count)
+    ro.output_scalars(plaintext_curve, count)
 }
 
 /// Compute the Proof Of Possession (PoP) base element
@@ -490,12 +508,12 @@ impl MEGaCiphertextSingle {
         dealer_index: NodeIndex,
         associated_data: &[u8],
     ) -> ThresholdEcdsaResult<Self> {
-        let curve_type = check_plaintexts(plaintexts, recipients)?;
+        let (key_curve, plaintext_curve) = check_plaintexts(plaintexts, recipients)?;
 
         let ctype = MEGaCiphertextType::Single;
 
         let (beta, v, pop_public_key, pop_proof) =
-            compute_eph_key_and_pop(ctype, curve_type, seed, associated_data, dealer_index)?;
+            compute_eph_key_and_pop(ctype, key_curve, seed, associated_data, dealer_index)?;
 
         let mut ctexts = Vec::with_capacity(recipients.len());
 
@@ -504,6 +522,7 @@ impl MEGaCiphertextSingle {
 
             let hm = mega_hash_to_scalars(
                 ctype,
+                plaintext_curve,
                 dealer_index,
                 index as NodeIndex,
                 associated_data,
@@ -554,8 +573,11 @@ impl MEGaCiphertextSingle {
             ));
         }
 
+        let plaintext_curve = self.ctexts[recipient_index as usize].curve_type();
+
         let hm = mega_hash_to_scalars(
             MEGaCiphertextType::Single,
+            plaintext_curve,
             dealer_index,
             recipient_index,
             associated_data,
@@ -601,12 +623,12 @@ impl MEGaCiphertextPair {
         dealer_index: NodeIndex,
         associated_data: &[u8],
     ) -> ThresholdEcdsaResult<Self> {
-        let curve_type = check_plaintexts_pair(plaintexts, recipients)?;
+        let (key_curve, plaintext_curve) = check_plaintexts_pair(plaintexts, recipients)?;
 
         let ctype = MEGaCiphertextType::Pairs;
 
         let (beta, v, pop_public_key, pop_proof) =
-            compute_eph_key_and_pop(ctype, curve_type, seed, associated_data, dealer_index)?;
+            compute_eph_key_and_pop(ctype, key_curve, seed, associated_data, dealer_index)?;
 
         let mut ctexts = Vec::with_capacity(recipients.len());
 
@@ -615,6 +637,7 @@ impl MEGaCiphertextPair {
 
             let hm = mega_hash_to_scalars(
                 ctype,
+                plaintext_curve,
                 dealer_index,
                 index as NodeIndex,
                 associated_data,
@@ -666,8 +689,11 @@ impl MEGaCiphertextPair {
             ));
         }
 
+        let plaintext_curve = self.ctexts[recipient_index as usize].0.curve_type();
+
         let hm = mega_hash_to_scalars(
             MEGaCiphertextType::Pairs,
+            plaintext_curve,
             dealer_index,
             recipient_index,
             associated_data,
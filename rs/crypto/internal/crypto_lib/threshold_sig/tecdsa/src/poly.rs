@@ -21,6 +21,9 @@ impl Debug for Polynomial {
         match &self.curve {
             EccCurveType::K256 => write!(f, "Polynomial {{curve: K256, coefficients: REDACTED}}"),
             EccCurveType::P256 => write!(f, "Polynomial {{curve: P256, coefficients: REDACTED}}"),
+            EccCurveType::Ed25519 => {
+                write!(f, "Polynomial {{curve: Ed25519, coefficients: REDACTED}}")
+            }
         }
     }
 }
@@ -317,6 +320,9 @@ impl Debug for CommitmentOpening {
             Self::Simple(EccScalar::P256(_)) => {
                 write!(f, "CommitmentOpening::Simple(P256(REDACTED))")
             }
+            Self::Simple(EccScalar::Ed25519(_)) => {
+                write!(f, "CommitmentOpening::Simple(Ed25519(REDACTED))")
+            }
             Self::Pedersen(EccScalar::K256(_), EccScalar::K256(_)) => write!(
                 f,
                 "CommitmentOpening::Pedersen(K256(REDACTED), K256(REDACTED))"
@@ -325,6 +331,10 @@ impl Debug for CommitmentOpening {
                 f,
                 "CommitmentOpening::Pedersen(P256(REDACTED), P256(REDACTED))"
             ),
+            Self::Pedersen(EccScalar::Ed25519(_), EccScalar::Ed25519(_)) => write!(
+                f,
+                "CommitmentOpening::Pedersen(Ed25519(REDACTED), Ed25519(REDACTED))"
+            ),
             Self::Pedersen(_, _) => write!(
                 f,
                 "ERROR: Unsupported curve combination in CommitmentOpening!"
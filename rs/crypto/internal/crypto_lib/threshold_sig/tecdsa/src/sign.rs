@@ -225,6 +225,7 @@ impl ThresholdEcdsaCombinedSigInternal {
     ) -> ThresholdEcdsaResult<Self> {
         let curve_type = match algorithm_id {
             AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
+            AlgorithmId::ThresholdEcdsaSecp256r1 => Ok(EccCurveType::P256),
             x => Err(ThresholdEcdsaError::SerializationError(format!(
                 "Invalid algorithm {:?} for threshold ECDSA",
                 x
@@ -410,6 +411,15 @@ pub fn derive_public_key(
         AlgorithmId::EcdsaSecp256k1 => {
             EccPoint::deserialize(EccCurveType::K256, &master_public_key.public_key)?
         }
+        AlgorithmId::EcdsaP256 => {
+            EccPoint::deserialize(EccCurveType::P256, &master_public_key.public_key)?
+        }
+        AlgorithmId::SchnorrSecp256k1 => {
+            EccPoint::deserialize(EccCurveType::K256, &master_public_key.public_key)?
+        }
+        AlgorithmId::Ed25519 => {
+            EccPoint::deserialize(EccCurveType::Ed25519, &master_public_key.public_key)?
+        }
         _ => return Err(ThresholdEcdsaError::CurveMismatch),
     };
     // Compute tweak
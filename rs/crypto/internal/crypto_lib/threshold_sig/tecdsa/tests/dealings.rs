@@ -284,6 +284,7 @@ fn flip_curve(s: &EccScalar) -> EccScalar {
     let wrong_curve = match s.curve_type() {
         EccCurveType::K256 => EccCurveType::P256,
         EccCurveType::P256 => EccCurveType::K256,
+        EccCurveType::Ed25519 => EccCurveType::K256,
     };
 
     let s_bytes = s.serialize();
@@ -17,7 +17,7 @@ fn random_field_element(curve_type: EccCurveType) -> EccFieldElement {
 #[test]
 fn test_one_minus_one_is_zero() -> Result<(), ThresholdEcdsaError> {
     for curve_type in EccCurveType::all() {
-        let one = EccFieldElement::one(curve_type);
+        let one = EccFieldElement::one(curve_type)?;
         let neg_one = one.negate()?;
         let zero = one.add(&neg_one).unwrap();
         assert!(bool::from(zero.is_zero()));
@@ -32,7 +32,7 @@ fn test_one_from_bytes_eq_one() -> Result<(), ThresholdEcdsaError> {
         0, 1,
     ];
     for curve_type in EccCurveType::all() {
-        let one = EccFieldElement::one(curve_type);
+        let one = EccFieldElement::one(curve_type)?;
         let one_from_bytes = EccFieldElement::from_bytes(curve_type, &ones)?;
         let one_from_bytes_wide = EccFieldElement::from_bytes_wide(curve_type, &ones)?;
 
@@ -64,7 +64,7 @@ fn test_x_minus_x_is_zero() -> Result<(), ThresholdEcdsaError> {
 #[test]
 fn test_neg_one_x_neg_one_is_one() -> Result<(), ThresholdEcdsaError> {
     for curve_type in EccCurveType::all() {
-        let one = EccFieldElement::one(curve_type);
+        let one = EccFieldElement::one(curve_type)?;
         let neg_one = one.negate()?;
         let should_be_one = neg_one.mul(&neg_one).unwrap();
         assert_eq!(one, should_be_one);
@@ -106,7 +106,7 @@ fn test_from_bytes_is_inverse_of_as_bytes() {
 #[test]
 fn test_inverse_is_correct() -> Result<(), ThresholdEcdsaError> {
     for curve_type in EccCurveType::all() {
-        let one = EccFieldElement::one(curve_type);
+        let one = EccFieldElement::one(curve_type)?;
 
         for _trial in 0..100 {
             let fe = random_field_element(curve_type);
@@ -132,7 +132,7 @@ fn test_inverse_is_correct() -> Result<(), ThresholdEcdsaError> {
 #[test]
 fn test_inverse_of_zero_is_zero() -> Result<(), ThresholdEcdsaError> {
     for curve_type in EccCurveType::all() {
-        let zero = EccFieldElement::zero(curve_type);
+        let zero = EccFieldElement::zero(curve_type)?;
         assert!(bool::from(zero.invert().is_zero()));
     }
 
@@ -142,7 +142,7 @@ fn test_inverse_of_zero_is_zero() -> Result<(), ThresholdEcdsaError> {
 #[test]
 fn test_inverse_of_one_is_one() -> Result<(), ThresholdEcdsaError> {
     for curve_type in EccCurveType::all() {
-        let one = EccFieldElement::one(curve_type);
+        let one = EccFieldElement::one(curve_type)?;
         assert_eq!(one.invert(), one);
     }
 
@@ -188,8 +188,8 @@ fn test_ab_values_are_correct() -> Result<(), ThresholdEcdsaError> {
         and checking that the point decodes successfully.
          */
 
-        let a = EccFieldElement::a(curve_type);
-        let b = EccFieldElement::b(curve_type);
+        let a = EccFieldElement::a(curve_type)?;
+        let b = EccFieldElement::b(curve_type)?;
 
         loop {
             let x = random_field_element(curve_type);
@@ -218,8 +218,8 @@ fn test_ab_values_are_correct() -> Result<(), ThresholdEcdsaError> {
 #[test]
 fn test_sswu_z_values_are_correct() -> Result<(), ThresholdEcdsaError> {
     fn sswu_z_value(curve_type: EccCurveType) -> i32 {
-        let one = EccFieldElement::one(curve_type);
-        let mut z = EccFieldElement::sswu_z(curve_type);
+        let one = EccFieldElement::one(curve_type).expect("Unsupported curve");
+        let mut z = EccFieldElement::sswu_z(curve_type).expect("Unsupported curve");
 
         /*
         SSWU z value is always chosen to be the smallest acceptable value.
@@ -247,8 +247,8 @@ fn test_sswu_z_values_are_correct() -> Result<(), ThresholdEcdsaError> {
 #[test]
 fn test_sswu_c2_values_are_correct() -> Result<(), ThresholdEcdsaError> {
     for curve_type in EccCurveType::all() {
-        let z = EccFieldElement::sswu_z(curve_type);
-        let c2 = EccFieldElement::sswu_c2(curve_type);
+        let z = EccFieldElement::sswu_z(curve_type)?;
+        let c2 = EccFieldElement::sswu_c2(curve_type)?;
         let neg_z = z.negate()?;
         let (_, sqrt_neg_z) = neg_z.sqrt();
         assert_eq!(c2, sqrt_neg_z);
@@ -1,4 +1,6 @@
 use ic_crypto_internal_threshold_sig_ecdsa::*;
+use ic_types::crypto::canister_threshold_sig::MasterEcdsaPublicKey;
+use ic_types::crypto::AlgorithmId;
 use rand::Rng;
 use std::convert::{TryFrom, TryInto};
 
@@ -23,7 +25,8 @@ fn test_index_next_behavior() {
 }
 
 #[test]
-fn test_that_key_derivation_on_secp256r1_currently_fails() -> Result<(), ThresholdEcdsaError> {
+fn test_that_key_derivation_on_secp256r1_is_consistent_with_tweak(
+) -> Result<(), ThresholdEcdsaError> {
     let mut rng = rand::thread_rng();
     let path = DerivationPath::new_bip32(&[1, 2, 3]);
     let master_key = EccPoint::hash_to_point(
@@ -32,12 +35,72 @@ fn test_that_key_derivation_on_secp256r1_currently_fails() -> Result<(), Thresho
         "public_key".as_bytes(),
     )?;
 
-    assert_eq!(
-        path.derive_tweak(&master_key),
-        Err(ThresholdEcdsaError::InvalidArguments(
-            "Currently key derivation not defined for secp256r1".to_string()
-        ))
-    );
+    let (tweak, chain_key) = path.derive_tweak(&master_key)?;
+    assert_eq!(tweak.curve_type(), EccCurveType::P256);
+    assert_eq!(chain_key.len(), 32);
+
+    let master_public_key = MasterEcdsaPublicKey {
+        algorithm_id: AlgorithmId::EcdsaP256,
+        public_key: master_key.serialize(),
+    };
+    let derived_key = sign::derive_public_key(&master_public_key, &path)?;
+
+    let expected_key = master_key.add_points(&EccPoint::mul_by_g(&tweak)?)?;
+    assert_eq!(derived_key.algorithm_id, AlgorithmId::EcdsaP256);
+    assert_eq!(derived_key.public_key, expected_key.serialize());
+    assert_eq!(derived_key.chain_key, chain_key);
+
+    Ok(())
+}
+
+#[test]
+fn test_that_key_derivation_on_ed25519_is_consistent_with_tweak() -> Result<(), ThresholdEcdsaError>
+{
+    let mut rng = rand::thread_rng();
+    let path = DerivationPath::new_bip32(&[1, 2, 3]);
+    let master_key = EccPoint::mul_by_g(&EccScalar::random(EccCurveType::Ed25519, &mut rng)?)?;
+
+    let (tweak, chain_key) = path.derive_tweak(&master_key)?;
+    assert_eq!(tweak.curve_type(), EccCurveType::Ed25519);
+    assert_eq!(chain_key.len(), 32);
+
+    let master_public_key = MasterEcdsaPublicKey {
+        algorithm_id: AlgorithmId::Ed25519,
+        public_key: master_key.serialize(),
+    };
+    let derived_key = sign::derive_public_key(&master_public_key, &path)?;
+
+    let expected_key = master_key.add_points(&EccPoint::mul_by_g(&tweak)?)?;
+    assert_eq!(derived_key.algorithm_id, AlgorithmId::Ed25519);
+    assert_eq!(derived_key.public_key, expected_key.serialize());
+    assert_eq!(derived_key.chain_key, chain_key);
+
+    Ok(())
+}
+
+#[test]
+fn should_key_derivation_match_test_vectors() -> Result<(), ThresholdEcdsaError> {
+    use ic_crypto_internal_test_vectors::extended_bip32::*;
+    use strum::IntoEnumIterator;
+
+    for test_vec in PublicKeyDerivationTestVector::iter() {
+        let test_vec = public_key_derivation_testvec(test_vec);
+
+        let curve_type = match test_vec.curve {
+            DerivationCurve::Secp256k1 => EccCurveType::K256,
+            DerivationCurve::Secp256r1 => EccCurveType::P256,
+        };
+
+        let public_key = EccPoint::deserialize(curve_type, &test_vec.public_key)?;
+        let path = DerivationPath::new(test_vec.path.into_iter().map(DerivationIndex).collect());
+
+        let (tweak, chain_key) =
+            path.derive_tweak_with_chain_key(&public_key, &test_vec.chain_code)?;
+        let derived_key = public_key.add_points(&EccPoint::mul_by_g(&tweak)?)?;
+
+        assert_eq!(derived_key.serialize(), test_vec.derived_public_key);
+        assert_eq!(chain_key, test_vec.derived_chain_code);
+    }
 
     Ok(())
 }
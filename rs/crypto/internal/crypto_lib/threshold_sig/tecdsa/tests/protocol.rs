@@ -268,3 +268,38 @@ fn should_basic_signing_protocol_work() -> Result<(), ThresholdEcdsaError> {
 
     Ok(())
 }
+
+#[test]
+fn should_basic_signing_protocol_work_with_secp256r1() -> Result<(), ThresholdEcdsaError> {
+    let nodes = 7;
+    let threshold = nodes / 3;
+    let number_of_dealings_corrupted = threshold;
+    let setup = SignatureProtocolSetup::new(
+        EccCurveType::P256,
+        nodes,
+        threshold,
+        number_of_dealings_corrupted,
+        random_seed(),
+    )?;
+
+    let alg = setup.alg();
+    assert_eq!(alg, ic_types::crypto::AlgorithmId::ThresholdEcdsaSecp256r1);
+
+    let mut rng = rand::thread_rng();
+    let signed_message = rng.gen::<[u8; 32]>().to_vec();
+    let random_beacon = Randomness::from(rng.gen::<[u8; 32]>());
+
+    let derivation_path = DerivationPath::new_bip32(&[1, 2, 3]);
+    let proto =
+        SignatureProtocolExecution::new(setup, signed_message, random_beacon, derivation_path);
+
+    let shares = proto.generate_shares()?;
+    let sig = proto.generate_signature(&shares).unwrap();
+
+    let sig2 = ThresholdEcdsaCombinedSigInternal::deserialize(alg, &sig.serialize())?;
+    assert_eq!(sig, sig2);
+
+    assert!(proto.verify_signature(&sig).is_ok());
+
+    Ok(())
+}
@@ -0,0 +1,220 @@
+use ic_crypto_internal_threshold_sig_ecdsa::*;
+use ic_types::*;
+use rand::Rng;
+use std::collections::BTreeMap;
+
+mod test_utils;
+
+use crate::test_utils::*;
+
+fn random_subset<T: Clone>(
+    shares: &BTreeMap<NodeIndex, T>,
+    include: usize,
+) -> BTreeMap<NodeIndex, T> {
+    assert!(include <= shares.len());
+
+    let mut rng = rand::thread_rng();
+    let mut result = BTreeMap::new();
+
+    let keys = shares.keys().collect::<Vec<_>>();
+
+    while result.len() != include {
+        let key_to_add = keys[rng.gen::<usize>() % keys.len()];
+
+        if !result.contains_key(key_to_add) {
+            result.insert(*key_to_add, shares[key_to_add].clone());
+        }
+    }
+
+    result
+}
+
+#[test]
+fn should_basic_bip340_signing_protocol_work() -> Result<(), ThresholdEcdsaError> {
+    let nodes = 10;
+    let threshold = nodes / 3;
+    let number_of_dealings_corrupted = threshold;
+    let setup = SchnorrSignatureProtocolSetup::new(
+        EccCurveType::K256,
+        nodes,
+        threshold,
+        number_of_dealings_corrupted,
+        random_seed(),
+    )?;
+
+    let mut rng = rand::thread_rng();
+    let signed_message = rng.gen::<[u8; 32]>().to_vec();
+    let random_beacon = Randomness::from(rng.gen::<[u8; 32]>());
+
+    let derivation_path = DerivationPath::new_bip32(&[1, 2, 3]);
+    let proto = SchnorrSignatureProtocolExecution::new(
+        setup.clone(),
+        signed_message.clone(),
+        random_beacon,
+        derivation_path.clone(),
+    );
+
+    let shares = proto.generate_bip340_shares()?;
+
+    for i in 0..=nodes {
+        let shares = random_subset(&shares, i);
+
+        if shares.len() < threshold {
+            assert!(proto.generate_bip340_signature(&shares).is_err());
+        } else {
+            let sig = proto.generate_bip340_signature(&shares).unwrap();
+            let sig2 = ThresholdBip340CombinedSignatureInternal::deserialize(&sig.serialize()?)?;
+            assert_eq!(sig, sig2);
+            assert!(proto.verify_bip340_signature(&sig).is_ok());
+        }
+    }
+
+    // Test that another run of the protocol generates signatures
+    // which are not verifiable in the earlier one (due to different randomness)
+    let random_beacon2 = Randomness::from(rng.gen::<[u8; 32]>());
+    let proto2 = SchnorrSignatureProtocolExecution::new(
+        setup,
+        signed_message,
+        random_beacon2,
+        derivation_path,
+    );
+
+    let shares = proto2.generate_bip340_shares()?;
+    let sig = proto2.generate_bip340_signature(&shares).unwrap();
+
+    assert!(proto.verify_bip340_signature(&sig).is_err());
+    assert!(proto2.verify_bip340_signature(&sig).is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn should_bip340_signing_protocol_work_with_arbitrary_message_lengths(
+) -> Result<(), ThresholdEcdsaError> {
+    let nodes = 4;
+    let threshold = 2;
+    let setup =
+        SchnorrSignatureProtocolSetup::new(EccCurveType::K256, nodes, threshold, 0, random_seed())?;
+
+    let mut rng = rand::thread_rng();
+
+    for message_len in [0, 1, 32, 100] {
+        let signed_message = (0..message_len)
+            .map(|_| rng.gen::<u8>())
+            .collect::<Vec<_>>();
+        let random_beacon = Randomness::from(rng.gen::<[u8; 32]>());
+
+        let proto = SchnorrSignatureProtocolExecution::new(
+            setup.clone(),
+            signed_message,
+            random_beacon,
+            DerivationPath::new_bip32(&[]),
+        );
+
+        let shares = proto.generate_bip340_shares()?;
+        let sig = proto.generate_bip340_signature(&shares).unwrap();
+        assert!(proto.verify_bip340_signature(&sig).is_ok());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn should_basic_ed25519_signing_protocol_work() -> Result<(), ThresholdEcdsaError> {
+    let nodes = 10;
+    let threshold = nodes / 3;
+    let number_of_dealings_corrupted = threshold;
+    let setup = SchnorrSignatureProtocolSetup::new(
+        EccCurveType::Ed25519,
+        nodes,
+        threshold,
+        number_of_dealings_corrupted,
+        random_seed(),
+    )?;
+
+    let mut rng = rand::thread_rng();
+    let signed_message = rng.gen::<[u8; 32]>().to_vec();
+    let random_beacon = Randomness::from(rng.gen::<[u8; 32]>());
+
+    let derivation_path = DerivationPath::new_bip32(&[1, 2, 3]);
+    let proto = SchnorrSignatureProtocolExecution::new(
+        setup.clone(),
+        signed_message.clone(),
+        random_beacon,
+        derivation_path.clone(),
+    );
+
+    let shares = proto.generate_ed25519_shares()?;
+
+    for i in 0..=nodes {
+        let shares = random_subset(&shares, i);
+
+        if shares.len() < threshold {
+            assert!(proto.generate_ed25519_signature(&shares).is_err());
+        } else {
+            let sig = proto.generate_ed25519_signature(&shares).unwrap();
+            let sig2 = ThresholdEd25519CombinedSignatureInternal::deserialize(&sig.serialize())?;
+            assert_eq!(sig, sig2);
+            assert!(proto.verify_ed25519_signature(&sig).is_ok());
+        }
+    }
+
+    // Test that another run of the protocol generates signatures
+    // which are not verifiable in the earlier one (due to different randomness)
+    let random_beacon2 = Randomness::from(rng.gen::<[u8; 32]>());
+    let proto2 = SchnorrSignatureProtocolExecution::new(
+        setup,
+        signed_message,
+        random_beacon2,
+        derivation_path,
+    );
+
+    let shares = proto2.generate_ed25519_shares()?;
+    let sig = proto2.generate_ed25519_signature(&shares).unwrap();
+
+    assert!(proto.verify_ed25519_signature(&sig).is_err());
+    assert!(proto2.verify_ed25519_signature(&sig).is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn should_ed25519_signature_shares_not_verify_for_other_signers() -> Result<(), ThresholdEcdsaError>
+{
+    let nodes = 4;
+    let threshold = 2;
+    let setup = SchnorrSignatureProtocolSetup::new(
+        EccCurveType::Ed25519,
+        nodes,
+        threshold,
+        0,
+        random_seed(),
+    )?;
+
+    let mut rng = rand::thread_rng();
+    let signed_message = rng.gen::<[u8; 32]>().to_vec();
+    let random_beacon = Randomness::from(rng.gen::<[u8; 32]>());
+    let derivation_path = DerivationPath::new_bip32(&[7]);
+
+    let proto = SchnorrSignatureProtocolExecution::new(
+        setup.clone(),
+        signed_message.clone(),
+        random_beacon,
+        derivation_path.clone(),
+    );
+
+    let shares = proto.generate_ed25519_shares()?;
+
+    assert!(verify_ed25519_signature_share(
+        &shares[&0],
+        &derivation_path,
+        &signed_message,
+        random_beacon,
+        1,
+        &setup.key.transcript,
+        &setup.presig.transcript,
+    )
+    .is_err());
+
+    Ok(())
+}
@@ -28,11 +28,8 @@ impl ProtocolSetup {
     ) -> Result<Self, ThresholdEcdsaError> {
         let alg = match curve {
             EccCurveType::K256 => AlgorithmId::ThresholdEcdsaSecp256k1,
-            _ => {
-                return Err(ThresholdEcdsaError::InvalidArguments(
-                    "Unsupported curve".to_string(),
-                ))
-            }
+            EccCurveType::P256 => AlgorithmId::ThresholdEcdsaSecp256r1,
+            EccCurveType::Ed25519 => AlgorithmId::ThresholdEd25519,
         };
 
         let mut rng = seed.into_rng();
@@ -42,7 +39,7 @@ impl ProtocolSetup {
         let mut pk = Vec::with_capacity(receivers);
 
         for _i in 0..receivers {
-            let k = MEGaPrivateKey::generate(curve, &mut rng)?;
+            let k = MEGaPrivateKey::generate(MEGA_KEY_CURVE, &mut rng)?;
             pk.push(k.public_key()?);
             sk.push(k);
         }
@@ -717,8 +714,12 @@ impl SignatureProtocolSetup {
     }
 
     pub fn public_key(&self, path: &DerivationPath) -> Result<EcdsaPublicKey, ThresholdEcdsaError> {
+        let algorithm_id = match self.setup.alg {
+            AlgorithmId::ThresholdEcdsaSecp256r1 => AlgorithmId::EcdsaP256,
+            _ => AlgorithmId::EcdsaSecp256k1,
+        };
         let master_public_key = MasterEcdsaPublicKey {
-            algorithm_id: AlgorithmId::EcdsaSecp256k1,
+            algorithm_id,
             public_key: self.key.transcript.constant_term().serialize(),
         };
         ic_crypto_internal_threshold_sig_ecdsa::sign::derive_public_key(&master_public_key, path)
@@ -832,10 +833,300 @@ impl SignatureProtocolExecution {
 
         use k256::ecdsa::signature::{Signature, Verifier};
 
-        let vk = k256::ecdsa::VerifyingKey::from_sec1_bytes(&pk.public_key)
+        match self.setup.alg() {
+            AlgorithmId::ThresholdEcdsaSecp256r1 => {
+                let vk = p256::ecdsa::VerifyingKey::from_sec1_bytes(&pk.public_key)
+                    .expect("Failed to parse public key");
+
+                let sig = p256::ecdsa::Signature::from_bytes(&sig.serialize())
+                    .expect("Failed to parse signature");
+
+                assert!(vk.verify(&self.signed_message, &sig).is_ok());
+            }
+            _ => {
+                let vk = k256::ecdsa::VerifyingKey::from_sec1_bytes(&pk.public_key)
+                    .expect("Failed to parse public key");
+
+                let sig = k256::ecdsa::Signature::from_bytes(&sig.serialize())
+                    .expect("Failed to parse signature");
+
+                assert!(vk.verify(&self.signed_message, &sig).is_ok());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SchnorrSignatureProtocolSetup {
+    setup: ProtocolSetup,
+    pub key: ProtocolRound,
+    pub presig: ProtocolRound,
+}
+
+impl SchnorrSignatureProtocolSetup {
+    pub fn new(
+        curve: EccCurveType,
+        number_of_dealers: usize,
+        threshold: usize,
+        number_of_dealings_corrupted: usize,
+        seed: Seed,
+    ) -> ThresholdEcdsaResult<Self> {
+        let setup = ProtocolSetup::new(curve, number_of_dealers, threshold, seed)?;
+
+        let key = ProtocolRound::random(&setup, number_of_dealers, number_of_dealings_corrupted)?;
+        let presig =
+            ProtocolRound::random(&setup, number_of_dealers, number_of_dealings_corrupted)?;
+
+        let key = ProtocolRound::reshare_of_masked(
+            &setup,
+            &key,
+            number_of_dealers,
+            number_of_dealings_corrupted,
+        )?;
+        let presig = ProtocolRound::reshare_of_masked(
+            &setup,
+            &presig,
+            number_of_dealers,
+            number_of_dealings_corrupted,
+        )?;
+
+        Ok(Self { setup, key, presig })
+    }
+
+    pub fn public_key(&self, path: &DerivationPath) -> Result<EcdsaPublicKey, ThresholdEcdsaError> {
+        let algorithm_id = match self.key.transcript.constant_term().curve_type() {
+            EccCurveType::Ed25519 => AlgorithmId::Ed25519,
+            _ => AlgorithmId::SchnorrSecp256k1,
+        };
+        let master_public_key = MasterEcdsaPublicKey {
+            algorithm_id,
+            public_key: self.key.transcript.constant_term().serialize(),
+        };
+        ic_crypto_internal_threshold_sig_ecdsa::sign::derive_public_key(&master_public_key, path)
+    }
+}
+
+/// Verify a BIP-340 signature without using the bip340 module
+///
+/// The public key is given in SEC1 compressed form; BIP-340 only
+/// uses the x coordinate of it.
+fn verify_bip340_signature_independently(
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> ThresholdEcdsaResult<bool> {
+    fn tagged_hash(tag: &str, inputs: &[&[u8]]) -> [u8; 32] {
+        let tag_hash = ic_crypto_sha::Sha256::hash(tag.as_bytes());
+        let mut sha256 = ic_crypto_sha::Sha256::new();
+        sha256.write(&tag_hash);
+        sha256.write(&tag_hash);
+        for input in inputs {
+            sha256.write(input);
+        }
+        sha256.finish()
+    }
+
+    let curve_type = EccCurveType::K256;
+    let x = &public_key[1..];
+    let r = &signature[..32];
+
+    let mut even_y_public_key = vec![0x02];
+    even_y_public_key.extend_from_slice(x);
+    let p = EccPoint::deserialize(curve_type, &even_y_public_key)?;
+    let s = EccScalar::deserialize(curve_type, &signature[32..])?;
+    let e = EccScalar::from_bytes_wide(
+        curve_type,
+        &tagged_hash("BIP0340/challenge", &[r, x, message]),
+    )?;
+
+    // R = s*G - e*P
+    let big_r = EccPoint::mul_points(&EccPoint::generator_g(curve_type)?, &s, &p, &e.negate())?;
+
+    if big_r.is_infinity()? {
+        return Ok(false);
+    }
+
+    let big_r = big_r.serialize();
+    Ok(big_r[0] == 0x02 && &big_r[1..] == r)
+}
+
+#[derive(Clone, Debug)]
+pub struct SchnorrSignatureProtocolExecution {
+    setup: SchnorrSignatureProtocolSetup,
+    signed_message: Vec<u8>,
+    random_beacon: Randomness,
+    derivation_path: DerivationPath,
+}
+
+impl SchnorrSignatureProtocolExecution {
+    pub fn new(
+        setup: SchnorrSignatureProtocolSetup,
+        signed_message: Vec<u8>,
+        random_beacon: Randomness,
+        derivation_path: DerivationPath,
+    ) -> Self {
+        Self {
+            setup,
+            signed_message,
+            random_beacon,
+            derivation_path,
+        }
+    }
+
+    pub fn generate_bip340_shares(
+        &self,
+    ) -> ThresholdEcdsaResult<BTreeMap<NodeIndex, ThresholdBip340SignatureShareInternal>> {
+        let mut shares = BTreeMap::new();
+
+        for node_index in 0..self.setup.setup.receivers {
+            let share = create_bip340_signature_share(
+                &self.derivation_path,
+                &self.signed_message,
+                self.random_beacon,
+                &self.setup.key.transcript,
+                &self.setup.key.openings[node_index],
+                &self.setup.presig.transcript,
+                &self.setup.presig.openings[node_index],
+            )
+            .expect("Failed to create sig share");
+
+            verify_bip340_signature_share(
+                &share,
+                &self.derivation_path,
+                &self.signed_message,
+                self.random_beacon,
+                node_index as NodeIndex,
+                &self.setup.key.transcript,
+                &self.setup.presig.transcript,
+            )
+            .expect("Signature share verification failed");
+
+            shares.insert(node_index as NodeIndex, share);
+        }
+
+        Ok(shares)
+    }
+
+    pub fn generate_bip340_signature(
+        &self,
+        shares: &BTreeMap<NodeIndex, ThresholdBip340SignatureShareInternal>,
+    ) -> Result<
+        ThresholdBip340CombinedSignatureInternal,
+        ThresholdSchnorrCombineSigSharesInternalError,
+    > {
+        combine_bip340_signature_shares(
+            &self.derivation_path,
+            &self.signed_message,
+            self.random_beacon,
+            &self.setup.key.transcript,
+            &self.setup.presig.transcript,
+            self.setup.setup.threshold,
+            shares,
+        )
+    }
+
+    pub fn verify_bip340_signature(
+        &self,
+        sig: &ThresholdBip340CombinedSignatureInternal,
+    ) -> Result<(), ThresholdSchnorrVerifySignatureInternalError> {
+        verify_threshold_bip340_signature(
+            sig,
+            &self.derivation_path,
+            &self.signed_message,
+            self.random_beacon,
+            &self.setup.presig.transcript,
+            &self.setup.key.transcript,
+        )?;
+
+        // If verification succeeded, check with a standalone BIP-340 verifier also
+        let pk = self.setup.public_key(&self.derivation_path)?;
+
+        assert!(verify_bip340_signature_independently(
+            &pk.public_key,
+            &self.signed_message,
+            &sig.serialize()?
+        )?);
+
+        Ok(())
+    }
+
+    pub fn generate_ed25519_shares(
+        &self,
+    ) -> ThresholdEcdsaResult<BTreeMap<NodeIndex, ThresholdEd25519SignatureShareInternal>> {
+        let mut shares = BTreeMap::new();
+
+        for node_index in 0..self.setup.setup.receivers {
+            let share = create_ed25519_signature_share(
+                &self.derivation_path,
+                &self.signed_message,
+                self.random_beacon,
+                &self.setup.key.transcript,
+                &self.setup.key.openings[node_index],
+                &self.setup.presig.transcript,
+                &self.setup.presig.openings[node_index],
+            )
+            .expect("Failed to create sig share");
+
+            verify_ed25519_signature_share(
+                &share,
+                &self.derivation_path,
+                &self.signed_message,
+                self.random_beacon,
+                node_index as NodeIndex,
+                &self.setup.key.transcript,
+                &self.setup.presig.transcript,
+            )
+            .expect("Signature share verification failed");
+
+            shares.insert(node_index as NodeIndex, share);
+        }
+
+        Ok(shares)
+    }
+
+    pub fn generate_ed25519_signature(
+        &self,
+        shares: &BTreeMap<NodeIndex, ThresholdEd25519SignatureShareInternal>,
+    ) -> Result<
+        ThresholdEd25519CombinedSignatureInternal,
+        ThresholdSchnorrCombineSigSharesInternalError,
+    > {
+        combine_ed25519_signature_shares(
+            &self.derivation_path,
+            &self.signed_message,
+            self.random_beacon,
+            &self.setup.key.transcript,
+            &self.setup.presig.transcript,
+            self.setup.setup.threshold,
+            shares,
+        )
+    }
+
+    pub fn verify_ed25519_signature(
+        &self,
+        sig: &ThresholdEd25519CombinedSignatureInternal,
+    ) -> Result<(), ThresholdSchnorrVerifySignatureInternalError> {
+        verify_threshold_ed25519_signature(
+            sig,
+            &self.derivation_path,
+            &self.signed_message,
+            self.random_beacon,
+            &self.setup.presig.transcript,
+            &self.setup.key.transcript,
+        )?;
+
+        // If verification succeeded, check with ed25519-dalek also
+        let pk = self.setup.public_key(&self.derivation_path)?;
+
+        use ed25519_dalek::Verifier;
+        use std::convert::TryFrom;
+
+        let vk = ed25519_dalek::PublicKey::from_bytes(&pk.public_key)
             .expect("Failed to parse public key");
 
-        let sig = k256::ecdsa::Signature::from_bytes(&sig.serialize())
+        let sig = ed25519_dalek::Signature::try_from(&sig.serialize()[..])
             .expect("Failed to parse signature");
 
         assert!(vk.verify(&self.signed_message, &sig).is_ok());
@@ -1,6 +1,7 @@
 use super::super::types::{CspPop, CspPublicKey};
 use ic_crypto_tls_interfaces::TlsPublicKeyCert;
 use ic_protobuf::crypto::v1::NodePublicKeys;
+use ic_protobuf::registry::crypto::v1::{PublicKey as PublicKeyProto, X509PublicKeyCert};
 use ic_types::crypto::{AlgorithmId, CryptoError, KeyId};
 use ic_types::NodeId;
 
@@ -57,6 +58,48 @@ pub trait CspKeyGenerator {
     ) -> Result<TlsPublicKeyCert, CryptoError>;
 }
 
+/// A trait that can be used to rotate the node signing key and the TLS key of
+/// a node.
+///
+/// Rotating a key generates new key material, stores the new secret key in the
+/// secret key store, and replaces the corresponding public key in the public
+/// key store. The secret key of the previous key pair is kept in the secret key
+/// store, so that the node can keep using the previous key pair for as long as
+/// it is the one in the registry.
+pub trait CspNodeKeyRotation {
+    /// Rotates the node signing key of the node with ID `node_id`.
+    ///
+    /// Since the node ID can no longer be derived from the new node signing
+    /// public key, `node_id` is stored in the public key store (unless a node
+    /// ID was already stored by a previous rotation).
+    ///
+    /// Returns the new node signing public key.
+    ///
+    /// # Errors
+    /// * `CryptoError::InternalError` if the key generation fails or if the
+    ///   public key store cannot be updated.
+    fn rotate_node_signing_key(&self, node_id: NodeId) -> Result<PublicKeyProto, CryptoError>;
+
+    /// Rotates the TLS key of the node with ID `node_id`.
+    ///
+    /// The new certificate is generated in the same way as in
+    /// `CspKeyGenerator::gen_tls_key_pair`, with validity ending at
+    /// `not_after`.
+    ///
+    /// Returns the new TLS certificate.
+    ///
+    /// # Errors
+    /// * `CryptoError::InvalidNotAfterDate` if `not_after` is not specified
+    ///   according to RFC 5280 or if `not_after` is in the past.
+    /// * `CryptoError::InternalError` if the key generation fails or if the
+    ///   public key store cannot be updated.
+    fn rotate_tls_key(
+        &self,
+        node_id: NodeId,
+        not_after: &str,
+    ) -> Result<X509PublicKeyCert, CryptoError>;
+}
+
 /// A trait that allows checking the secret key store for the availability of a
 /// key.
 pub trait CspSecretKeyStoreChecker {
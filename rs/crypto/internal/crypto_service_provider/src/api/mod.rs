@@ -9,7 +9,9 @@ mod tls;
 pub use canister_threshold::{
     CspCreateMEGaKeyError, CspIDkgProtocol, CspThresholdEcdsaSigVerifier, CspThresholdEcdsaSigner,
 };
-pub use keygen::{CspKeyGenerator, CspSecretKeyStoreChecker, NodePublicKeyData};
+pub use keygen::{
+    CspKeyGenerator, CspNodeKeyRotation, CspSecretKeyStoreChecker, NodePublicKeyData,
+};
 pub use sign::CspSigner;
 pub use threshold::{
     threshold_sign_error::CspThresholdSignError, DistributedKeyGenerationCspClient, NiDkgCspClient,
@@ -1,15 +1,18 @@
 //! Utilities for key generation and key identifier generation
 
-use crate::api::{CspKeyGenerator, CspSecretKeyStoreChecker};
+use crate::api::{CspKeyGenerator, CspNodeKeyRotation, CspSecretKeyStoreChecker};
+use crate::public_key_store::{self, PublicKeyStoreError};
 use crate::secret_key_store::SecretKeyStore;
 use crate::types::{CspPop, CspPublicKey};
 use crate::vault::api::CspTlsKeygenError;
-use crate::Csp;
+use crate::{Csp, PublicKeyData};
 use ic_crypto_internal_threshold_sig_ecdsa::{EccCurveType, MEGaPublicKey, PolynomialCommitment};
 use ic_crypto_internal_types::encrypt::forward_secure::CspFsEncryptionPublicKey;
 use ic_crypto_sha::Sha256;
 use ic_crypto_sha::{Context, DomainSeparationContext};
 use ic_crypto_tls_interfaces::TlsPublicKeyCert;
+use ic_protobuf::crypto::v1::NodePublicKeys;
+use ic_protobuf::registry::crypto::v1::{PublicKey as PublicKeyProto, X509PublicKeyCert};
 use ic_types::crypto::{AlgorithmId, CryptoError, KeyId};
 use ic_types::NodeId;
 use rand::{CryptoRng, Rng};
@@ -67,6 +70,65 @@ impl<R: Rng + CryptoRng + Send + Sync, S: SecretKeyStore, C: SecretKeyStore> Csp
     }
 }
 
+impl<R: Rng + CryptoRng + Send + Sync, S: SecretKeyStore, C: SecretKeyStore> CspNodeKeyRotation
+    for Csp<R, S, C>
+{
+    fn rotate_node_signing_key(&self, node_id: NodeId) -> Result<PublicKeyProto, CryptoError> {
+        let (_key_id, csp_pk) = self.gen_key_pair(AlgorithmId::Ed25519)?;
+        let node_signing_pk = utils::node_signing_pk_to_proto(csp_pk);
+        if let Some(pk_path) = &self.public_key_store_path {
+            if public_key_store::read_node_id(pk_path)
+                .map_err(public_key_store_error)?
+                .is_none()
+            {
+                public_key_store::store_node_id(pk_path, node_id)
+                    .map_err(public_key_store_error)?;
+            }
+        }
+        self.update_node_public_keys(|node_pks| {
+            node_pks.node_signing_pk = Some(node_signing_pk.clone())
+        })?;
+        Ok(node_signing_pk)
+    }
+
+    fn rotate_tls_key(
+        &self,
+        node_id: NodeId,
+        not_after: &str,
+    ) -> Result<X509PublicKeyCert, CryptoError> {
+        let tls_certificate = self.gen_tls_key_pair(node_id, not_after)?.to_proto();
+        self.update_node_public_keys(|node_pks| {
+            node_pks.tls_certificate = Some(tls_certificate.clone())
+        })?;
+        Ok(tls_certificate)
+    }
+}
+
+impl<R: Rng + CryptoRng + Send + Sync, S: SecretKeyStore, C: SecretKeyStore> Csp<R, S, C> {
+    /// Applies `update` to the node public keys and persists the result in the
+    /// public key store (if any).
+    fn update_node_public_keys<F: FnOnce(&mut NodePublicKeys)>(
+        &self,
+        update: F,
+    ) -> Result<(), CryptoError> {
+        let mut public_key_data = self.public_key_data.write();
+        let mut node_public_keys = public_key_data.node_public_keys.clone();
+        update(&mut node_public_keys);
+        if let Some(pk_path) = &self.public_key_store_path {
+            public_key_store::store_node_public_keys(pk_path, &node_public_keys)
+                .map_err(public_key_store_error)?;
+        }
+        *public_key_data = PublicKeyData::new(node_public_keys);
+        Ok(())
+    }
+}
+
+fn public_key_store_error(error: PublicKeyStoreError) -> CryptoError {
+    CryptoError::InternalError {
+        internal_error: format!("Failed to update the public key store: {:?}", error),
+    }
+}
+
 impl<R: Rng + CryptoRng + Send + Sync, S: SecretKeyStore, C: SecretKeyStore>
     CspSecretKeyStoreChecker for Csp<R, S, C>
 {
@@ -148,12 +210,29 @@ mod tls_keygen {
 
 /// Some key related utils
 pub mod utils {
+    use crate::types::CspPublicKey;
     use ic_crypto_internal_types::encrypt::forward_secure::{
         CspFsEncryptionPop, CspFsEncryptionPublicKey,
     };
     use ic_protobuf::registry::crypto::v1::AlgorithmId as AlgorithmIdProto;
     use ic_protobuf::registry::crypto::v1::PublicKey as PublicKeyProto;
 
+    /// Form a protobuf structure of the node signing public key
+    ///
+    /// # Panics
+    /// If the public key is not an Ed25519 public key.
+    pub fn node_signing_pk_to_proto(pk: CspPublicKey) -> PublicKeyProto {
+        match pk {
+            CspPublicKey::Ed25519(pk) => PublicKeyProto {
+                algorithm: AlgorithmIdProto::Ed25519 as i32,
+                key_value: pk.0.to_vec(),
+                version: 0,
+                proof_data: None,
+            },
+            _ => panic!("Unsupported types"),
+        }
+    }
+
     /// Form a protobuf structure of the public key and proof of possession
     pub fn dkg_dealing_encryption_pk_to_proto(
         pk: CspFsEncryptionPublicKey,
@@ -359,3 +359,105 @@ mod tls {
         cert.as_x509().serial_number().to_bn().unwrap()
     }
 }
+
+mod rotation {
+    use super::*;
+    use crate::api::NodePublicKeyData;
+    use ic_config::crypto::CryptoConfig;
+
+    const NODE_1: u64 = 4241;
+    const NOT_AFTER: &str = "25670102030405Z";
+
+    #[test]
+    fn should_rotate_node_signing_key_and_keep_previous_secret_key() {
+        CryptoConfig::run_with_temp_config(|config| {
+            let csp = Csp::new_with_rng(csprng_seeded_with(42), &config);
+            let first_pk = csp.rotate_node_signing_key(node_test_id(NODE_1)).unwrap();
+
+            let second_pk = csp.rotate_node_signing_key(node_test_id(NODE_1)).unwrap();
+
+            assert_ne!(first_pk, second_pk);
+            assert_eq!(
+                csp.node_public_keys().node_signing_pk,
+                Some(second_pk.clone())
+            );
+            assert_eq!(
+                public_key_store::read_node_public_keys(&config.crypto_root)
+                    .unwrap()
+                    .node_signing_pk,
+                Some(second_pk.clone())
+            );
+            for pk in [first_pk, second_pk] {
+                let csp_pk = CspPublicKey::try_from(pk).unwrap();
+                assert!(csp
+                    .sks_contains(&public_key_hash_as_key_id(&csp_pk))
+                    .unwrap());
+            }
+        });
+    }
+
+    #[test]
+    fn should_store_node_id_when_rotating_node_signing_key() {
+        CryptoConfig::run_with_temp_config(|config| {
+            let csp = Csp::new_with_rng(csprng_seeded_with(42), &config);
+            assert_eq!(
+                public_key_store::read_node_id(&config.crypto_root).unwrap(),
+                None
+            );
+
+            csp.rotate_node_signing_key(node_test_id(NODE_1)).unwrap();
+            csp.rotate_node_signing_key(node_test_id(NODE_1 + 1))
+                .unwrap();
+
+            assert_eq!(
+                public_key_store::read_node_id(&config.crypto_root).unwrap(),
+                Some(node_test_id(NODE_1))
+            );
+        });
+    }
+
+    #[test]
+    fn should_rotate_tls_key_and_keep_previous_secret_key() {
+        CryptoConfig::run_with_temp_config(|config| {
+            let csp = Csp::new_with_rng(csprng_seeded_with(42), &config);
+            let first_cert = csp.rotate_tls_key(node_test_id(NODE_1), NOT_AFTER).unwrap();
+
+            let second_cert = csp.rotate_tls_key(node_test_id(NODE_1), NOT_AFTER).unwrap();
+
+            assert_ne!(first_cert, second_cert);
+            assert_eq!(
+                csp.node_public_keys().tls_certificate,
+                Some(second_cert.clone())
+            );
+            assert_eq!(
+                public_key_store::read_node_public_keys(&config.crypto_root)
+                    .unwrap()
+                    .tls_certificate,
+                Some(second_cert.clone())
+            );
+            for cert in [first_cert, second_cert] {
+                let cert = TlsPublicKeyCert::new_from_der(cert.certificate_der).unwrap();
+                assert!(csp.sks_contains_tls_key(&cert).unwrap());
+            }
+            assert_eq!(
+                public_key_store::read_node_id(&config.crypto_root).unwrap(),
+                None
+            );
+        });
+    }
+
+    #[test]
+    fn should_fail_to_rotate_tls_key_with_invalid_not_after_date() {
+        CryptoConfig::run_with_temp_config(|config| {
+            let csp = Csp::new_with_rng(csprng_seeded_with(42), &config);
+
+            let result = csp.rotate_tls_key(node_test_id(NODE_1), "invalid");
+
+            assert!(matches!(
+                result,
+                Err(CryptoError::InvalidNotAfterDate { .. })
+            ));
+            assert_eq!(csp.node_public_keys().tls_certificate, None);
+        });
+    }
+}
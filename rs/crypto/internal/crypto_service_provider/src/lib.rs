@@ -18,11 +18,13 @@ pub mod vault;
 
 pub use crate::vault::api::TlsHandshakeCspVault;
 pub use crate::vault::local_csp_vault::LocalCspVault;
-pub use crate::vault::remote_csp_vault::run_csp_vault_server;
 use crate::vault::remote_csp_vault::RemoteCspVault;
+pub use crate::vault::remote_csp_vault::{
+    run_csp_vault_server, run_csp_vault_server_with_tcp_tls, CspVaultTlsConfig,
+};
 
 use crate::api::{
-    CspIDkgProtocol, CspKeyGenerator, CspSecretKeyStoreChecker, CspSigner,
+    CspIDkgProtocol, CspKeyGenerator, CspNodeKeyRotation, CspSecretKeyStoreChecker, CspSigner,
     CspThresholdEcdsaSigVerifier, CspThresholdEcdsaSigner, CspTlsClientHandshake,
     CspTlsHandshakeSignerProvider, CspTlsServerHandshake, NiDkgCspClient, NodePublicKeyData,
     ThresholdSignatureCspClient,
@@ -33,7 +35,7 @@ use crate::secret_key_store::volatile_store::VolatileSecretKeyStore;
 use crate::secret_key_store::SecretKeyStore;
 use crate::types::CspPublicKey;
 use crate::vault::api::CspVault;
-use ic_config::crypto::{CryptoConfig, CspVaultType};
+use ic_config::crypto::{CryptoConfig, CspVaultType, TcpCspVaultConfig};
 use ic_crypto_internal_logmon::metrics::CryptoMetrics;
 use ic_crypto_internal_types::encrypt::forward_secure::CspFsEncryptionPublicKey;
 use ic_logger::{info, new_logger, replica_logger::no_op_logger, ReplicaLogger};
@@ -44,7 +46,7 @@ use rand::rngs::OsRng;
 use rand::{CryptoRng, Rng};
 use secret_key_store::proto_store::ProtoSecretKeyStore;
 use std::convert::TryFrom;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -56,6 +58,7 @@ const CANISTER_SKS_DATA_FILENAME: &str = "canister_sks_data.pb";
 pub trait CryptoServiceProvider:
     CspSigner
     + CspKeyGenerator
+    + CspNodeKeyRotation
     + ThresholdSignatureCspClient
     + NiDkgCspClient
     + CspIDkgProtocol
@@ -72,6 +75,7 @@ pub trait CryptoServiceProvider:
 impl<T> CryptoServiceProvider for T where
     T: CspSigner
         + CspKeyGenerator
+        + CspNodeKeyRotation
         + ThresholdSignatureCspClient
         + CspIDkgProtocol
         + CspThresholdEcdsaSigner
@@ -131,7 +135,10 @@ impl PublicKeyData {
 /// Implements the CryptoServiceProvider for an RNG and a SecretKeyStore.
 pub struct Csp<R: Rng + CryptoRng + Send + Sync, S: SecretKeyStore, C: SecretKeyStore> {
     csp_vault: Arc<dyn CspVault>,
-    public_key_data: PublicKeyData,
+    public_key_data: RwLock<PublicKeyData>,
+    // The directory of the public key store, if any. Updated when keys are
+    // rotated.
+    public_key_store_path: Option<PathBuf>,
     logger: ReplicaLogger,
     // TODO(CRP-1325): remove R, S, C generics.
     _marker: std::marker::PhantomData<(R, S, C)>,
@@ -193,13 +200,14 @@ impl<T> CspRwLock<T> {
 impl Csp<OsRng, ProtoSecretKeyStore, ProtoSecretKeyStore> {
     /// Creates a production-grade crypto service provider.
     ///
-    /// If the `config`'s vault type is `UnixSocket`, a `tokio_runtime_handle`
-    /// must be provided, which is then used for the `async`hronous
-    /// communication with the vault via RPC.
+    /// If the `config`'s vault type is `UnixSocket` or `Tcp`, a
+    /// `tokio_runtime_handle` must be provided, which is then used for the
+    /// `async`hronous communication with the vault via RPC.
     ///
     /// # Panics
-    /// Panics if the `config`'s vault type is `UnixSocket` and
-    /// `tokio_runtime_handle` is `None`.
+    /// Panics if the `config`'s vault type is `UnixSocket` or `Tcp` and
+    /// `tokio_runtime_handle` is `None`, or if the connection to the remote
+    /// vault cannot be established.
     pub fn new(
         config: &CryptoConfig,
         tokio_runtime_handle: Option<tokio::runtime::Handle>,
@@ -214,6 +222,12 @@ impl Csp<OsRng, ProtoSecretKeyStore, ProtoSecretKeyStore> {
                 config,
                 logger,
             ),
+            CspVaultType::Tcp(tcp_config) => Self::new_with_tcp_vault(
+                tcp_config,
+                tokio_runtime_handle.expect("missing tokio runtime handle"),
+                config,
+                logger,
+            ),
         }
     }
 
@@ -266,11 +280,40 @@ impl Csp<OsRng, ProtoSecretKeyStore, ProtoSecretKeyStore> {
         Self::csp_with(&config.crypto_root, logger, Arc::new(csp_vault))
     }
 
+    fn new_with_tcp_vault(
+        tcp_config: &TcpCspVaultConfig,
+        rt_handle: tokio::runtime::Handle,
+        config: &CryptoConfig,
+        logger: Option<ReplicaLogger>,
+    ) -> Self {
+        let logger = logger.unwrap_or_else(no_op_logger);
+        info!(
+            logger,
+            "Proceeding with a remote csp_vault via TCP, CryptoConfig: {:?}", config
+        );
+        let tls_config = CspVaultTlsConfig::from_pem_files(
+            &tcp_config.client_certificate_pem_path,
+            &tcp_config.client_private_key_pem_path,
+            &tcp_config.server_certificate_pem_path,
+        )
+        .unwrap_or_else(|e| panic!("Invalid TLS config for CspVault: {}", e));
+        let csp_vault =
+            RemoteCspVault::new_with_tcp_tls(&tcp_config.server_address, &tls_config, rt_handle)
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "Could not connect to CspVault at address {}: {:?}",
+                        tcp_config.server_address, e
+                    )
+                });
+        Self::csp_with(&config.crypto_root, logger, Arc::new(csp_vault))
+    }
+
     fn csp_with(pk_path: &Path, logger: ReplicaLogger, csp_vault: Arc<dyn CspVault>) -> Self {
         let node_public_keys = read_node_public_keys(pk_path).unwrap_or_default();
         let public_key_data = PublicKeyData::new(node_public_keys);
         Csp {
-            public_key_data,
+            public_key_data: RwLock::new(public_key_data),
+            public_key_store_path: Some(pk_path.to_path_buf()),
             csp_vault,
             logger,
             _marker: std::marker::PhantomData,
@@ -289,7 +332,8 @@ impl<R: 'static + Rng + CryptoRng + Send + Sync + Clone>
         let node_public_keys = read_node_public_keys(&config.crypto_root).unwrap_or_default();
         let public_key_data = PublicKeyData::new(node_public_keys);
         Csp {
-            public_key_data,
+            public_key_data: RwLock::new(public_key_data),
+            public_key_store_path: Some(config.crypto_root.clone()),
             csp_vault: Arc::new(LocalCspVault::new_for_test(
                 csprng,
                 ProtoSecretKeyStore::open(&config.crypto_root, SKS_DATA_FILENAME, None),
@@ -305,7 +349,7 @@ impl<R: Rng + CryptoRng + Send + Sync> Csp<R, VolatileSecretKeyStore, VolatileSe
     ///
     /// Note: This is for testing only and MUST NOT be used in production.
     pub fn reset_public_key_data(&mut self, node_public_keys: NodePublicKeys) {
-        self.public_key_data = PublicKeyData::new(node_public_keys);
+        *self.public_key_data.get_mut() = PublicKeyData::new(node_public_keys);
     }
 }
 
@@ -313,11 +357,12 @@ impl<R: Rng + CryptoRng + Send + Sync, S: SecretKeyStore, C: SecretKeyStore> Nod
     for Csp<R, S, C>
 {
     fn node_public_keys(&self) -> NodePublicKeys {
-        self.public_key_data.node_public_keys.clone()
+        self.public_key_data.read().node_public_keys.clone()
     }
 
     fn node_signing_key_id(&self) -> KeyId {
         self.public_key_data
+            .read()
             .sks_key_ids
             .node_signing_key_id
             .to_owned()
@@ -326,6 +371,7 @@ impl<R: Rng + CryptoRng + Send + Sync, S: SecretKeyStore, C: SecretKeyStore> Nod
 
     fn dkg_dealing_encryption_key_id(&self) -> KeyId {
         self.public_key_data
+            .read()
             .sks_key_ids
             .dkg_dealing_encryption_key_id
             .to_owned()
@@ -344,7 +390,8 @@ impl<R: 'static + Rng + CryptoRng + Send + Sync + Clone, S: 'static + SecretKeyS
         let node_public_keys = Default::default();
         let public_key_data = PublicKeyData::new(node_public_keys);
         Csp {
-            public_key_data,
+            public_key_data: RwLock::new(public_key_data),
+            public_key_store_path: None,
             csp_vault: Arc::new(LocalCspVault::new_for_test(csprng, secret_key_store)),
             logger: no_op_logger(),
             _marker: std::marker::PhantomData,
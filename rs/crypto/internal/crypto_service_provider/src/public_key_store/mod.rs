@@ -4,8 +4,11 @@ use std::fs;
 use std::path::Path;
 
 use ic_protobuf::crypto::v1::NodePublicKeys;
+use ic_protobuf::types::v1::NodeId as NodeIdProto;
+use ic_types::{node_id_into_protobuf, node_id_try_from_protobuf, NodeId};
 
 const PK_DATA_FILENAME: &str = "public_keys.pb";
+const NODE_ID_FILENAME: &str = "node_id.pb";
 
 /// Error while reading or writing public keys
 #[derive(Clone, Debug)]
@@ -35,3 +38,34 @@ pub fn read_node_public_keys(crypto_root: &Path) -> Result<NodePublicKeys, Publi
         Err(err) => Err(PublicKeyStoreError::IOError(err.to_string())),
     }
 }
+
+/// Write the node ID to local storage
+///
+/// The node ID is derived from the node signing public key the node was
+/// registered with. Once this key is rotated, the node ID can no longer be
+/// derived from the node signing public key in the public key store and is
+/// therefore stored explicitly.
+pub fn store_node_id(crypto_root: &Path, node_id: NodeId) -> Result<(), PublicKeyStoreError> {
+    let node_id_file = crypto_root.join(NODE_ID_FILENAME);
+
+    ic_utils::fs::write_protobuf_using_tmp_file(node_id_file, &node_id_into_protobuf(node_id))
+        .map_err(|err| PublicKeyStoreError::IOError(err.to_string()))
+}
+
+/// Read the node ID from local storage
+///
+/// Returns `Ok(None)` if no node ID was stored, i.e., if the node ID is
+/// derived from the node signing public key.
+pub fn read_node_id(crypto_root: &Path) -> Result<Option<NodeId>, PublicKeyStoreError> {
+    let node_id_file = crypto_root.join(NODE_ID_FILENAME);
+    if !node_id_file.exists() {
+        return Ok(None);
+    }
+    let data =
+        fs::read(node_id_file).map_err(|err| PublicKeyStoreError::IOError(err.to_string()))?;
+    let node_id_proto = NodeIdProto::decode(&*data)
+        .map_err(|err| PublicKeyStoreError::ParsingError(err.to_string()))?;
+    node_id_try_from_protobuf(node_id_proto)
+        .map(Some)
+        .map_err(|err| PublicKeyStoreError::ParsingError(format!("{:?}", err)))
+}
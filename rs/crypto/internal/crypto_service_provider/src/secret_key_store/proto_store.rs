@@ -11,6 +11,7 @@ use ic_types::crypto::KeyId;
 use parking_lot::RwLock;
 use prost::Message;
 use std::borrow::{Borrow, BorrowMut};
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::convert::TryInto;
 use std::fs;
@@ -221,6 +222,25 @@ impl ProtoSecretKeyStore {
         sks_proto
     }
 
+    /// Adds the keys that were written to `sks_data_file` by another instance
+    /// of the store (e.g., node keys rotated by the orchestrator while the
+    /// replica is running) and that are missing in `secret_keys`.
+    ///
+    /// Keys already present in `secret_keys` are left untouched. Returns
+    /// whether any key was added.
+    fn merge_secret_keys_from_disk(sks_data_file: &Path, secret_keys: &mut SecretKeys) -> bool {
+        let mut keys_added = false;
+        if let Some(keys_on_disk) = ProtoSecretKeyStore::read_sks_data_from_disk(sks_data_file) {
+            for (key_id, entry) in keys_on_disk {
+                if let Entry::Vacant(vacant) = secret_keys.entry(key_id) {
+                    vacant.insert(entry);
+                    keys_added = true;
+                }
+            }
+        }
+        keys_added
+    }
+
     fn write_secret_keys_to_disk(sks_data_file: &Path, secret_keys: &SecretKeys) {
         let sks_proto = ProtoSecretKeyStore::secret_keys_to_sks_proto(secret_keys);
         ic_utils::fs::write_protobuf_using_tmp_file(sks_data_file, &sks_proto).unwrap();
@@ -234,20 +254,33 @@ impl SecretKeyStore for ProtoSecretKeyStore {
         key: CspSecretKey,
         scope: Option<Scope>,
     ) -> Result<(), SecretKeyStoreError> {
-        with_write_lock(&self.keys, |keys| match keys.get(&id) {
-            Some(_) => Err(SecretKeyStoreError::DuplicateKeyId(id)),
-            None => {
-                keys.insert(id, (key, scope));
-                ProtoSecretKeyStore::write_secret_keys_to_disk(&self.proto_file, keys);
-                Ok(())
+        with_write_lock(&self.keys, |keys| {
+            ProtoSecretKeyStore::merge_secret_keys_from_disk(&self.proto_file, keys);
+            match keys.get(&id) {
+                Some(_) => Err(SecretKeyStoreError::DuplicateKeyId(id)),
+                None => {
+                    keys.insert(id, (key, scope));
+                    ProtoSecretKeyStore::write_secret_keys_to_disk(&self.proto_file, keys);
+                    Ok(())
+                }
             }
         })
     }
 
     fn get(&self, id: &KeyId) -> Option<CspSecretKey> {
-        with_read_lock(&self.keys, |keys| {
+        let maybe_key = with_read_lock(&self.keys, |keys| {
             keys.get(id).map(|(csp_key, _)| csp_key.to_owned())
+        });
+        if maybe_key.is_some() {
+            return maybe_key;
+        }
+        // The key may have been added by another instance of the store, so
+        // pick up any new keys from disk before giving up.
+        with_write_lock(&self.keys, |keys| {
+            ProtoSecretKeyStore::merge_secret_keys_from_disk(&self.proto_file, keys);
+            Ok(keys.get(id).map(|(csp_key, _)| csp_key.to_owned()))
         })
+        .expect("lambda unexpectedly returned Err")
     }
 
     fn contains(&self, id: &KeyId) -> bool {
@@ -255,13 +288,16 @@ impl SecretKeyStore for ProtoSecretKeyStore {
     }
 
     fn remove(&mut self, id: &KeyId) -> bool {
-        let result = with_write_lock(&self.keys, |keys| match keys.get(id) {
-            Some(_) => {
-                keys.remove(id);
-                ProtoSecretKeyStore::write_secret_keys_to_disk(&self.proto_file, keys);
-                Ok(true)
+        let result = with_write_lock(&self.keys, |keys| {
+            ProtoSecretKeyStore::merge_secret_keys_from_disk(&self.proto_file, keys);
+            match keys.get(id) {
+                Some(_) => {
+                    keys.remove(id);
+                    ProtoSecretKeyStore::write_secret_keys_to_disk(&self.proto_file, keys);
+                    Ok(true)
+                }
+                None => Ok(false),
             }
-            None => Ok(false),
         });
         result.expect("lambda unexpectedly returned Err")
     }
@@ -271,6 +307,7 @@ impl SecretKeyStore for ProtoSecretKeyStore {
         F: Fn(&KeyId, &CspSecretKey) -> bool,
     {
         with_write_lock(&self.keys, |keys| {
+            ProtoSecretKeyStore::merge_secret_keys_from_disk(&self.proto_file, keys);
             let mut all_keys = SecretKeys::new();
             core::mem::swap(&mut all_keys, keys);
             let orig_keys_count = all_keys.len();
@@ -384,6 +421,39 @@ pub mod tests {
         test_utils::should_retain_expected_keys(proto_key_store());
     }
 
+    #[test]
+    fn should_retrieve_key_inserted_by_another_store_instance() {
+        let dir = mk_temp_dir_with_permissions(0o700);
+        let mut writer = ProtoSecretKeyStore::open(dir.path(), "sks_data.pb", None);
+        let reader = ProtoSecretKeyStore::open(dir.path(), "sks_data.pb", None);
+        let key_id = test_utils::make_key_id(42);
+        let secret_key = test_utils::make_secret_key(43);
+
+        assert!(reader.get(&key_id).is_none());
+        assert!(writer.insert(key_id, secret_key.clone(), None).is_ok());
+
+        assert_eq!(reader.get(&key_id), Some(secret_key));
+        assert!(reader.contains(&key_id));
+    }
+
+    #[test]
+    fn should_not_drop_key_inserted_by_another_store_instance_when_writing() {
+        let dir = mk_temp_dir_with_permissions(0o700);
+        let mut store_1 = ProtoSecretKeyStore::open(dir.path(), "sks_data.pb", None);
+        let mut store_2 = ProtoSecretKeyStore::open(dir.path(), "sks_data.pb", None);
+        let (key_id_1, secret_key_1) =
+            (test_utils::make_key_id(42), test_utils::make_secret_key(43));
+        let (key_id_2, secret_key_2) =
+            (test_utils::make_key_id(44), test_utils::make_secret_key(45));
+
+        assert!(store_1.insert(key_id_1, secret_key_1.clone(), None).is_ok());
+        assert!(store_2.insert(key_id_2, secret_key_2.clone(), None).is_ok());
+
+        let reopened = ProtoSecretKeyStore::open(dir.path(), "sks_data.pb", None);
+        assert_eq!(reopened.get(&key_id_1), Some(secret_key_1));
+        assert_eq!(reopened.get(&key_id_2), Some(secret_key_2));
+    }
+
     fn proto_key_store() -> TempSecretKeyStore {
         TempSecretKeyStore::new()
     }
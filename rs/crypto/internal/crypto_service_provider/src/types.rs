@@ -94,8 +94,22 @@ impl CspSecretKey {
             ))) => AlgorithmId::ThresholdEcdsaSecp256k1,
             Self::IDkgCommitmentOpening(CommitmentOpeningBytes::Pedersen(
                 EccScalarBytes::K256(_),
-                EccScalarBytes::K256(_),
+                _,
             )) => AlgorithmId::ThresholdEcdsaSecp256k1,
+            Self::IDkgCommitmentOpening(CommitmentOpeningBytes::Simple(EccScalarBytes::P256(
+                _,
+            ))) => AlgorithmId::ThresholdEcdsaSecp256r1,
+            Self::IDkgCommitmentOpening(CommitmentOpeningBytes::Pedersen(
+                EccScalarBytes::P256(_),
+                _,
+            )) => AlgorithmId::ThresholdEcdsaSecp256r1,
+            Self::IDkgCommitmentOpening(CommitmentOpeningBytes::Simple(
+                EccScalarBytes::Ed25519(_),
+            )) => AlgorithmId::ThresholdEd25519,
+            Self::IDkgCommitmentOpening(CommitmentOpeningBytes::Pedersen(
+                EccScalarBytes::Ed25519(_),
+                _,
+            )) => AlgorithmId::ThresholdEd25519,
         }
     }
 }
@@ -128,13 +142,47 @@ impl std::fmt::Debug for CspSecretKey {
             }
             Self::IDkgCommitmentOpening(CommitmentOpeningBytes::Pedersen(
                 EccScalarBytes::K256(_),
-                EccScalarBytes::K256(_),
+                _,
             )) => {
                 write!(
                     f,
                     "CspSecretKey::IDkgCommitmentOpening::Pedersen::K256 - REDACTED"
                 )
             }
+            Self::IDkgCommitmentOpening(CommitmentOpeningBytes::Simple(EccScalarBytes::P256(
+                _,
+            ))) => {
+                write!(
+                    f,
+                    "CspSecretKey::IDkgCommitmentOpening::Simple::P256 - REDACTED"
+                )
+            }
+            Self::IDkgCommitmentOpening(CommitmentOpeningBytes::Pedersen(
+                EccScalarBytes::P256(_),
+                _,
+            )) => {
+                write!(
+                    f,
+                    "CspSecretKey::IDkgCommitmentOpening::Pedersen::P256 - REDACTED"
+                )
+            }
+            Self::IDkgCommitmentOpening(CommitmentOpeningBytes::Simple(
+                EccScalarBytes::Ed25519(_),
+            )) => {
+                write!(
+                    f,
+                    "CspSecretKey::IDkgCommitmentOpening::Simple::Ed25519 - REDACTED"
+                )
+            }
+            Self::IDkgCommitmentOpening(CommitmentOpeningBytes::Pedersen(
+                EccScalarBytes::Ed25519(_),
+                _,
+            )) => {
+                write!(
+                    f,
+                    "CspSecretKey::IDkgCommitmentOpening::Pedersen::Ed25519 - REDACTED"
+                )
+            }
         }
     }
 }
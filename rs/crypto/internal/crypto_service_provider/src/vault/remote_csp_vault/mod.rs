@@ -27,15 +27,17 @@ use ic_types::crypto::{AlgorithmId, KeyId};
 use ic_types::{NodeId, NodeIndex, NumberOfNodes, Randomness};
 use std::collections::{BTreeMap, BTreeSet};
 use std::path::Path;
-use tokio::net::UnixListener;
+use tokio::net::{TcpListener, UnixListener};
 
 mod tarpc_csp_vault_client;
 mod tarpc_csp_vault_server;
+mod tls;
 
 use ic_crypto_internal_logmon::metrics::CryptoMetrics;
 use std::sync::Arc;
 pub use tarpc_csp_vault_client::RemoteCspVault;
 pub use tarpc_csp_vault_server::TarpcCspVaultServerImpl;
+pub use tls::{CspVaultTlsConfig, CspVaultTlsConfigError};
 
 #[cfg(test)]
 mod tests;
@@ -224,3 +226,23 @@ pub async fn run_csp_vault_server(
     );
     server.run().await
 }
+
+/// Runs a CSP vault server that accepts connections on the TCP `listener`,
+/// authenticating clients with TLS as specified by `tls_config`.
+pub async fn run_csp_vault_server_with_tcp_tls(
+    sks_dir: &Path,
+    listener: TcpListener,
+    tls_config: &CspVaultTlsConfig,
+    logger: ReplicaLogger,
+    metrics: CryptoMetrics,
+) -> Result<(), CspVaultTlsConfigError> {
+    let server = tarpc_csp_vault_server::TarpcCspVaultServerImpl::new_with_tcp_tls(
+        sks_dir,
+        listener,
+        tls_config,
+        logger,
+        Arc::new(metrics),
+    )?;
+    server.run().await;
+    Ok(())
+}
@@ -8,6 +8,7 @@ use crate::vault::api::{
     MultiSignatureCspVault, NiDkgCspVault, SecretKeyStoreCspVault, ThresholdEcdsaSignerCspVault,
     ThresholdSignatureCspVault,
 };
+use crate::vault::remote_csp_vault::tls::{irrelevant_server_name, CspVaultTlsConfig};
 use crate::vault::remote_csp_vault::TarpcCspVaultClient;
 use crate::TlsHandshakeCspVault;
 use core::future::Future;
@@ -38,10 +39,13 @@ use ic_types::{NodeId, NumberOfNodes, Randomness};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 use std::path::Path;
+use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use tarpc::serde_transport;
 use tarpc::tokio_serde::formats::Bincode;
-use tokio::net::UnixStream;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpStream, UnixStream};
+use tokio_rustls::TlsConnector;
 use tokio_util::codec::length_delimited::LengthDelimitedCodec;
 
 /// An implementation of `CspVault`-trait that talks to a remote CSP vault.
@@ -89,18 +93,53 @@ impl RemoteCspVault {
                 server_address: socket_path.to_string_lossy().to_string(),
                 message: e.to_string(),
             })?;
+        Ok(Self::new_with_connection(conn, rt_handle))
+    }
+
+    /// Creates a new `RemoteCspVault`-object that communicates
+    /// with a server listening on TCP at `server_address` (`host:port`).
+    /// The connection is mutually authenticated with TLS as specified by
+    /// `tls_config`, whose peer certificate is the one of the server.
+    pub fn new_with_tcp_tls(
+        server_address: &str,
+        tls_config: &CspVaultTlsConfig,
+        rt_handle: tokio::runtime::Handle,
+    ) -> Result<Self, RemoteCspVaultError> {
+        let transport_error = |message: String| RemoteCspVaultError::TransportError {
+            server_address: server_address.to_string(),
+            message,
+        };
+        let client_config = tls_config
+            .client_config()
+            .map_err(|e| transport_error(e.to_string()))?;
+        let tls_connector = TlsConnector::from(Arc::new(client_config));
+        let conn = rt_handle
+            .block_on(async {
+                let tcp_stream = TcpStream::connect(server_address).await?;
+                tls_connector
+                    .connect(irrelevant_server_name(), tcp_stream)
+                    .await
+            })
+            .map_err(|e| transport_error(e.to_string()))?;
+        Ok(Self::new_with_connection(conn, rt_handle))
+    }
+
+    fn new_with_connection<T>(conn: T, rt_handle: tokio::runtime::Handle) -> Self
+    where
+        T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
         let codec_builder = LengthDelimitedCodec::builder();
         let transport = serde_transport::new(codec_builder.new_framed(conn), Bincode::default());
         let client = {
             let _enter_guard = rt_handle.enter();
             TarpcCspVaultClient::new(Default::default(), transport).spawn()
         };
-        Ok(RemoteCspVault {
+        RemoteCspVault {
             tarpc_csp_client: client,
             rpc_timeout: DEFAULT_RPC_TIMEOUT,
             long_rpc_timeout: LONG_RPC_TIMEOUT,
             tokio_runtime_handle: rt_handle,
-        })
+        }
     }
 
     #[cfg(test)]
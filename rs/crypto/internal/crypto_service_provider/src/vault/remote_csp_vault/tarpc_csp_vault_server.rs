@@ -9,6 +9,7 @@ use crate::vault::api::{
     ThresholdSignatureCspVault,
 };
 use crate::vault::local_csp_vault::LocalCspVault;
+use crate::vault::remote_csp_vault::tls::{CspVaultTlsConfig, CspVaultTlsConfigError};
 use crate::vault::remote_csp_vault::TarpcCspVault;
 use crate::{TlsHandshakeCspVault, CANISTER_SKS_DATA_FILENAME, SKS_DATA_FILENAME};
 use ic_crypto_internal_logmon::metrics::CryptoMetrics;
@@ -28,7 +29,7 @@ use ic_crypto_internal_types::sign::threshold_sig::ni_dkg::{
 };
 use ic_crypto_internal_types::NodeIndex;
 use ic_crypto_tls_interfaces::TlsPublicKeyCert;
-use ic_logger::{new_logger, ReplicaLogger};
+use ic_logger::{new_logger, warn, ReplicaLogger};
 use ic_types::crypto::canister_threshold_sig::error::{
     IDkgCreateDealingError, IDkgLoadTranscriptError, IDkgOpenTranscriptError,
     IDkgRetainThresholdKeysError, IDkgVerifyDealingPrivateError, ThresholdEcdsaSignShareError,
@@ -46,18 +47,30 @@ use tarpc::server::Serve;
 use tarpc::tokio_serde::formats::Bincode;
 use tarpc::{context, serde_transport, server::Channel};
 use threadpool::ThreadPool;
-use tokio::net::UnixListener;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, UnixListener};
+use tokio_rustls::TlsAcceptor;
 use tokio_util::codec::length_delimited::LengthDelimitedCodec;
 
 /// Crypto service provider (CSP) vault server based on the tarpc RPC framework.
 pub struct TarpcCspVaultServerImpl {
     local_csp_vault: Arc<LocalCspVault<OsRng, ProtoSecretKeyStore, ProtoSecretKeyStore>>,
-    listener: UnixListener,
+    listener: VaultListener,
     thread_pool: ThreadPool,
-    #[allow(unused)]
     logger: ReplicaLogger,
 }
 
+/// The listener on which the tarpc CSP vault server accepts client connections.
+enum VaultListener {
+    UnixSocket(UnixListener),
+    /// Connections are accepted over TCP and must complete a TLS handshake
+    /// with client authentication before any request is served.
+    TcpWithTls {
+        listener: TcpListener,
+        tls_acceptor: TlsAcceptor,
+    },
+}
+
 /// A worker of the tarpc CSP vault server responsible for a single service request.
 ///
 /// For each service request (i.e., remote procedure call), a new worker is
@@ -453,11 +466,50 @@ impl TarpcCspVault for TarpcCspVaultServerWorker {
 }
 
 impl TarpcCspVaultServerImpl {
+    /// Creates a server that accepts connections on the Unix domain socket
+    /// `listener`.
     pub fn new(
         sks_dir: &Path,
         listener: UnixListener,
         logger: ReplicaLogger,
         metrics: Arc<CryptoMetrics>,
+    ) -> Self {
+        Self::new_with_listener(
+            sks_dir,
+            VaultListener::UnixSocket(listener),
+            logger,
+            metrics,
+        )
+    }
+
+    /// Creates a server that accepts connections on the TCP `listener`.
+    ///
+    /// Clients must authenticate with TLS, using the certificate pinned in
+    /// `tls_config`.
+    pub fn new_with_tcp_tls(
+        sks_dir: &Path,
+        listener: TcpListener,
+        tls_config: &CspVaultTlsConfig,
+        logger: ReplicaLogger,
+        metrics: Arc<CryptoMetrics>,
+    ) -> Result<Self, CspVaultTlsConfigError> {
+        let tls_acceptor = TlsAcceptor::from(Arc::new(tls_config.server_config()?));
+        Ok(Self::new_with_listener(
+            sks_dir,
+            VaultListener::TcpWithTls {
+                listener,
+                tls_acceptor,
+            },
+            logger,
+            metrics,
+        ))
+    }
+
+    fn new_with_listener(
+        sks_dir: &Path,
+        listener: VaultListener,
+        logger: ReplicaLogger,
+        metrics: Arc<CryptoMetrics>,
     ) -> Self {
         let node_secret_key_store =
             ProtoSecretKeyStore::open(sks_dir, SKS_DATA_FILENAME, Some(new_logger!(&logger)));
@@ -484,31 +536,67 @@ impl TarpcCspVaultServerImpl {
     }
 
     pub async fn run(self) {
-        // Wrap data in telegrams with a length header.
-        let codec_builder = LengthDelimitedCodec::builder();
-
         // Listen for connections; spawns one `tokio` task per client.
         loop {
-            let (conn, _addr) = self.listener.accept().await.unwrap_or_else(|e| {
-                panic!(
-                    "Error listening at socket {:?}: {}",
-                    &self.listener.local_addr(),
-                    e
-                )
-            });
-            let local_csp_vault = Arc::clone(&self.local_csp_vault);
-            let thread_pool_handle = self.thread_pool.clone(); // creates a pool handle similar to Arc
-            tokio::spawn(async move {
-                let framed = codec_builder.new_framed(conn);
-                let transport = serde_transport::new(framed, Bincode::default());
-                let worker = TarpcCspVaultServerWorker {
-                    local_csp_vault,
-                    thread_pool_handle,
-                };
-                let channel_executor =
-                    BaseChannel::with_defaults(transport).execute(worker.serve());
-                channel_executor.await;
-            });
+            let worker = TarpcCspVaultServerWorker {
+                local_csp_vault: Arc::clone(&self.local_csp_vault),
+                thread_pool_handle: self.thread_pool.clone(), // creates a pool handle similar to Arc
+            };
+            match &self.listener {
+                VaultListener::UnixSocket(listener) => {
+                    let (conn, _addr) = listener.accept().await.unwrap_or_else(|e| {
+                        panic!(
+                            "Error listening at socket {:?}: {}",
+                            listener.local_addr(),
+                            e
+                        )
+                    });
+                    tokio::spawn(serve_connection(conn, worker));
+                }
+                VaultListener::TcpWithTls {
+                    listener,
+                    tls_acceptor,
+                } => {
+                    // Unlike for the Unix socket, errors accepting a TCP
+                    // connection may be caused by the network and are not fatal.
+                    let (conn, addr) = match listener.accept().await {
+                        Ok(conn_and_addr) => conn_and_addr,
+                        Err(e) => {
+                            warn!(
+                                self.logger,
+                                "Error accepting connection at address {:?}: {}",
+                                listener.local_addr(),
+                                e
+                            );
+                            continue;
+                        }
+                    };
+                    // The handshake is performed in the spawned task so that a
+                    // misbehaving client cannot block accepting other clients.
+                    let tls_acceptor = tls_acceptor.clone();
+                    let logger = self.logger.clone();
+                    tokio::spawn(async move {
+                        match tls_acceptor.accept(conn).await {
+                            Ok(tls_stream) => serve_connection(tls_stream, worker).await,
+                            Err(e) => warn!(
+                                logger,
+                                "TLS handshake with CspVault client at {} failed: {}", addr, e
+                            ),
+                        }
+                    });
+                }
+            }
         }
     }
 }
+
+async fn serve_connection<T>(conn: T, worker: TarpcCspVaultServerWorker)
+where
+    T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    // Wrap data in telegrams with a length header.
+    let framed = LengthDelimitedCodec::builder().new_framed(conn);
+    let transport = serde_transport::new(framed, Bincode::default());
+    let channel_executor = BaseChannel::with_defaults(transport).execute(worker.serve());
+    channel_executor.await;
+}
@@ -390,3 +390,226 @@ mod tls_sign {
         test_utils::tls::should_fail_to_sign_if_secret_key_in_store_has_wrong_type(csp_vault);
     }
 }
+
+mod tcp_with_tls {
+    use super::*;
+    use crate::vault::remote_csp_vault::tarpc_csp_vault_client::RemoteCspVaultError;
+    use crate::vault::remote_csp_vault::{CspVaultTlsConfig, CspVaultTlsConfigError};
+    use ic_crypto_internal_csp_test_utils::remote_csp_vault::start_new_remote_csp_vault_server_with_tcp_tls_for_test;
+    use ic_crypto_internal_tls::keygen::generate_tls_key_pair_der;
+    use openssl::asn1::Asn1Time;
+    use openssl::pkey::PKey;
+    use openssl::x509::X509;
+    use rand::thread_rng;
+    use std::path::{Path, PathBuf};
+
+    struct TlsKeyPair {
+        certificate_der: Vec<u8>,
+        private_key_der: Vec<u8>,
+    }
+
+    fn new_tls_key_pair(common_name: &str) -> TlsKeyPair {
+        let not_after = Asn1Time::days_from_now(31).expect("failed to create Asn1Time");
+        let (certificate, private_key) =
+            generate_tls_key_pair_der(&mut thread_rng(), common_name, &not_after)
+                .expect("failed to generate TLS key pair");
+        TlsKeyPair {
+            certificate_der: certificate.bytes,
+            private_key_der: private_key.bytes.clone(),
+        }
+    }
+
+    fn tls_config(own: &TlsKeyPair, peer: &TlsKeyPair) -> CspVaultTlsConfig {
+        CspVaultTlsConfig::new(
+            own.certificate_der.clone(),
+            own.private_key_der.clone(),
+            peer.certificate_der.clone(),
+        )
+    }
+
+    fn connect(
+        server_address: std::net::SocketAddr,
+        client_tls_config: &CspVaultTlsConfig,
+        rt_handle: &tokio::runtime::Handle,
+    ) -> Result<RemoteCspVault, RemoteCspVaultError> {
+        RemoteCspVault::new_with_tcp_tls(
+            &server_address.to_string(),
+            client_tls_config,
+            rt_handle.clone(),
+        )
+    }
+
+    #[test]
+    fn should_generate_and_sign_with_key_over_tls() {
+        let tokio_rt = new_tokio_runtime();
+        let (server, client) = (new_tls_key_pair("server"), new_tls_key_pair("client"));
+        let server_address = start_new_remote_csp_vault_server_with_tcp_tls_for_test(
+            tokio_rt.handle(),
+            &tls_config(&server, &client),
+        );
+
+        let csp_vault = connect(
+            server_address,
+            &tls_config(&client, &server),
+            tokio_rt.handle(),
+        )
+        .expect("Could not create RemoteCspVault");
+
+        test_utils::basic_sig::should_sign_and_verify_with_generated_ed25519_key_pair(Arc::new(
+            csp_vault,
+        ));
+    }
+
+    #[test]
+    fn should_fail_to_connect_if_server_certificate_is_not_pinned_by_client() {
+        let tokio_rt = new_tokio_runtime();
+        let (server, client) = (new_tls_key_pair("server"), new_tls_key_pair("client"));
+        let other_server = new_tls_key_pair("other_server");
+        let server_address = start_new_remote_csp_vault_server_with_tcp_tls_for_test(
+            tokio_rt.handle(),
+            &tls_config(&server, &client),
+        );
+
+        let result = connect(
+            server_address,
+            &tls_config(&client, &other_server),
+            tokio_rt.handle(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(RemoteCspVaultError::TransportError { message, .. })
+            if message.contains("differs from the pinned certificate")
+        ));
+    }
+
+    #[test]
+    fn should_not_serve_client_whose_certificate_is_not_pinned_by_server() {
+        let tokio_rt = new_tokio_runtime();
+        let (server, client) = (new_tls_key_pair("server"), new_tls_key_pair("client"));
+        let other_client = new_tls_key_pair("other_client");
+        let server_address = start_new_remote_csp_vault_server_with_tcp_tls_for_test(
+            tokio_rt.handle(),
+            &tls_config(&server, &client),
+        );
+
+        // In TLS 1.3, the client considers the handshake complete before the
+        // server has verified the client certificate, so connecting may
+        // succeed, but no request is served.
+        if let Ok(csp_vault) = connect(
+            server_address,
+            &tls_config(&other_client, &server),
+            tokio_rt.handle(),
+        ) {
+            let result = csp_vault.gen_key_pair(ic_types::crypto::AlgorithmId::Ed25519);
+            assert!(result.is_err(), "Unexpected result: {:?}", result);
+        }
+    }
+
+    #[test]
+    fn should_fail_to_connect_if_no_server_is_listening() {
+        let tokio_rt = new_tokio_runtime();
+        let (server, client) = (new_tls_key_pair("server"), new_tls_key_pair("client"));
+        let unused_address = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("failed to bind");
+            listener.local_addr().expect("failed to get local address")
+        };
+
+        let result = connect(
+            unused_address,
+            &tls_config(&client, &server),
+            tokio_rt.handle(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(RemoteCspVaultError::TransportError { server_address, .. })
+            if server_address == unused_address.to_string()
+        ));
+    }
+
+    fn write_pem_files(dir: &Path, own: &TlsKeyPair, peer: &TlsKeyPair) -> [PathBuf; 3] {
+        let certificate_pem = X509::from_der(&own.certificate_der)
+            .and_then(|cert| cert.to_pem())
+            .expect("failed to encode certificate");
+        let private_key_pem = PKey::private_key_from_der(&own.private_key_der)
+            .and_then(|key| key.private_key_to_pem_pkcs8())
+            .expect("failed to encode private key");
+        let peer_certificate_pem = X509::from_der(&peer.certificate_der)
+            .and_then(|cert| cert.to_pem())
+            .expect("failed to encode certificate");
+        let paths = [
+            dir.join("cert.pem"),
+            dir.join("key.pem"),
+            dir.join("peer_cert.pem"),
+        ];
+        std::fs::write(&paths[0], certificate_pem).expect("failed to write file");
+        std::fs::write(&paths[1], private_key_pem).expect("failed to write file");
+        std::fs::write(&paths[2], peer_certificate_pem).expect("failed to write file");
+        paths
+    }
+
+    #[test]
+    fn should_connect_with_configs_loaded_from_pem_files() {
+        let tokio_rt = new_tokio_runtime();
+        let (server, client) = (new_tls_key_pair("server"), new_tls_key_pair("client"));
+        let (server_dir, client_dir) = (
+            tempfile::tempdir().expect("failed to create temp dir"),
+            tempfile::tempdir().expect("failed to create temp dir"),
+        );
+        let [cert, key, peer_cert] = write_pem_files(server_dir.path(), &server, &client);
+        let server_tls_config = CspVaultTlsConfig::from_pem_files(&cert, &key, &peer_cert)
+            .expect("failed to load server config");
+        let [cert, key, peer_cert] = write_pem_files(client_dir.path(), &client, &server);
+        let client_tls_config = CspVaultTlsConfig::from_pem_files(&cert, &key, &peer_cert)
+            .expect("failed to load client config");
+        let server_address = start_new_remote_csp_vault_server_with_tcp_tls_for_test(
+            tokio_rt.handle(),
+            &server_tls_config,
+        );
+
+        let csp_vault = connect(server_address, &client_tls_config, tokio_rt.handle())
+            .expect("Could not create RemoteCspVault");
+
+        test_utils::basic_sig::should_generate_ed25519_key_pair(Arc::new(csp_vault));
+    }
+
+    #[test]
+    fn should_fail_to_load_config_if_private_key_file_contains_certificate() {
+        let (own, peer) = (new_tls_key_pair("own"), new_tls_key_pair("peer"));
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let [cert, _key, peer_cert] = write_pem_files(dir.path(), &own, &peer);
+
+        let result = CspVaultTlsConfig::from_pem_files(&cert, &cert, &peer_cert);
+
+        assert!(matches!(
+            result,
+            Err(CspVaultTlsConfigError::PemParsingError { path, .. }) if path == cert
+        ));
+    }
+
+    #[test]
+    fn should_fail_to_load_config_if_file_does_not_exist() {
+        let (own, peer) = (new_tls_key_pair("own"), new_tls_key_pair("peer"));
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let [cert, key, _peer_cert] = write_pem_files(dir.path(), &own, &peer);
+        let missing = dir.path().join("missing.pem");
+
+        let result = CspVaultTlsConfig::from_pem_files(&cert, &key, &missing);
+
+        assert!(matches!(
+            result,
+            Err(CspVaultTlsConfigError::PemParsingError { path, .. }) if path == missing
+        ));
+    }
+
+    #[test]
+    fn should_not_print_private_key() {
+        let (own, peer) = (new_tls_key_pair("own"), new_tls_key_pair("peer"));
+
+        let debug_string = format!("{:?}", tls_config(&own, &peer));
+
+        assert!(debug_string.contains("REDACTED"));
+        assert!(!debug_string.contains(&hex::encode(&own.private_key_der)));
+    }
+}
@@ -0,0 +1,271 @@
+//! Mutual TLS for connections to a remote CSP vault over TCP.
+//!
+//! Both endpoints authenticate with a self-signed certificate and accept only
+//! a peer presenting exactly the certificate they were configured with
+//! (certificate pinning). There is no certificate authority and no hostname
+//! verification involved.
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio_rustls::rustls::ciphersuite::{TLS13_AES_128_GCM_SHA256, TLS13_AES_256_GCM_SHA384};
+use tokio_rustls::rustls::internal::pemfile;
+use tokio_rustls::rustls::{
+    Certificate, ClientCertVerified, ClientCertVerifier, ClientConfig, DistinguishedNames,
+    PrivateKey, ProtocolVersion, RootCertStore, ServerCertVerified, ServerCertVerifier,
+    ServerConfig, TLSError,
+};
+use tokio_rustls::webpki;
+use tokio_rustls::webpki::DNSNameRef;
+use zeroize::Zeroizing;
+
+/// The key material of one endpoint of a TLS connection between the replica
+/// and a remote CSP vault.
+#[derive(Clone)]
+pub struct CspVaultTlsConfig {
+    certificate_der: Vec<u8>,
+    private_key_der: Zeroizing<Vec<u8>>,
+    peer_certificate_der: Vec<u8>,
+}
+
+/// The TLS key material of a remote CSP vault endpoint is invalid.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CspVaultTlsConfigError {
+    PemParsingError { path: PathBuf, message: String },
+    InvalidKeyMaterial { message: String },
+}
+
+impl fmt::Display for CspVaultTlsConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CspVaultTlsConfigError::PemParsingError { path, message } => {
+                write!(
+                    f,
+                    "Failed to parse PEM file {}: {}",
+                    path.display(),
+                    message
+                )
+            }
+            CspVaultTlsConfigError::InvalidKeyMaterial { message } => {
+                write!(f, "Invalid TLS key material: {}", message)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for CspVaultTlsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CspVaultTlsConfig")
+            .field("certificate_der", &hex::encode(&self.certificate_der))
+            .field("private_key_der", &"REDACTED")
+            .field(
+                "peer_certificate_der",
+                &hex::encode(&self.peer_certificate_der),
+            )
+            .finish()
+    }
+}
+
+impl CspVaultTlsConfig {
+    /// Creates a config from a DER-encoded X.509 certificate and the
+    /// corresponding PKCS#8 DER-encoded private key that this endpoint
+    /// authenticates with, and the DER-encoded X.509 certificate that the peer
+    /// must present.
+    pub fn new(
+        certificate_der: Vec<u8>,
+        private_key_der: Vec<u8>,
+        peer_certificate_der: Vec<u8>,
+    ) -> Self {
+        Self {
+            certificate_der,
+            private_key_der: Zeroizing::new(private_key_der),
+            peer_certificate_der,
+        }
+    }
+
+    /// Creates a config from PEM files, see [`Self::new`].
+    ///
+    /// The certificate files must contain a single certificate, and the private
+    /// key file a single PKCS#8 private key.
+    pub fn from_pem_files(
+        certificate_pem_path: &Path,
+        private_key_pem_path: &Path,
+        peer_certificate_pem_path: &Path,
+    ) -> Result<Self, CspVaultTlsConfigError> {
+        Ok(Self::new(
+            single_certificate_from_pem_file(certificate_pem_path)?,
+            single_private_key_from_pem_file(private_key_pem_path)?,
+            single_certificate_from_pem_file(peer_certificate_pem_path)?,
+        ))
+    }
+
+    /// Returns the rustls config for the vault server, which requires the
+    /// client to authenticate.
+    pub(super) fn server_config(&self) -> Result<ServerConfig, CspVaultTlsConfigError> {
+        let mut config = ServerConfig::new(Arc::new(PinnedCertVerifier::new(
+            self.peer_certificate_der.clone(),
+        )));
+        config.versions = vec![ProtocolVersion::TLSv1_3];
+        config.ciphersuites = vec![&TLS13_AES_256_GCM_SHA384, &TLS13_AES_128_GCM_SHA256];
+        config
+            .set_single_cert(
+                vec![Certificate(self.certificate_der.clone())],
+                PrivateKey(self.private_key_der.to_vec()),
+            )
+            .map_err(invalid_key_material)?;
+        Ok(config)
+    }
+
+    /// Returns the rustls config for the vault client.
+    pub(super) fn client_config(&self) -> Result<ClientConfig, CspVaultTlsConfigError> {
+        let mut config = ClientConfig::new();
+        config.versions = vec![ProtocolVersion::TLSv1_3];
+        config.ciphersuites = vec![&TLS13_AES_256_GCM_SHA384, &TLS13_AES_128_GCM_SHA256];
+        config
+            .set_single_client_cert(
+                vec![Certificate(self.certificate_der.clone())],
+                PrivateKey(self.private_key_der.to_vec()),
+            )
+            .map_err(invalid_key_material)?;
+        config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(PinnedCertVerifier::new(
+                self.peer_certificate_der.clone(),
+            )));
+        Ok(config)
+    }
+}
+
+/// The server name sent by the client. It is irrelevant because the server
+/// certificate is pinned.
+pub(super) fn irrelevant_server_name() -> DNSNameRef<'static> {
+    DNSNameRef::try_from_ascii_str("domain.is-irrelevant-as-hostname-verification-is.disabled")
+        .expect("failed to create domain")
+}
+
+fn invalid_key_material(error: TLSError) -> CspVaultTlsConfigError {
+    CspVaultTlsConfigError::InvalidKeyMaterial {
+        message: format!("{}", error),
+    }
+}
+
+fn pem_file_reader(path: &Path) -> Result<BufReader<File>, CspVaultTlsConfigError> {
+    let file = File::open(path).map_err(|e| CspVaultTlsConfigError::PemParsingError {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })?;
+    Ok(BufReader::new(file))
+}
+
+fn single_certificate_from_pem_file(path: &Path) -> Result<Vec<u8>, CspVaultTlsConfigError> {
+    let mut certificates = pemfile::certs(&mut pem_file_reader(path)?).map_err(|_| {
+        CspVaultTlsConfigError::PemParsingError {
+            path: path.to_path_buf(),
+            message: "malformed certificate".to_string(),
+        }
+    })?;
+    if certificates.len() != 1 {
+        return Err(CspVaultTlsConfigError::PemParsingError {
+            path: path.to_path_buf(),
+            message: format!(
+                "expected exactly one certificate, but found {}",
+                certificates.len()
+            ),
+        });
+    }
+    Ok(certificates.remove(0).0)
+}
+
+fn single_private_key_from_pem_file(path: &Path) -> Result<Vec<u8>, CspVaultTlsConfigError> {
+    // nb. Make sure we don't leak sensitive info in the error message.
+    let mut private_keys =
+        pemfile::pkcs8_private_keys(&mut pem_file_reader(path)?).map_err(|_| {
+            CspVaultTlsConfigError::PemParsingError {
+                path: path.to_path_buf(),
+                message: "malformed PKCS#8 private key".to_string(),
+            }
+        })?;
+    if private_keys.len() != 1 {
+        return Err(CspVaultTlsConfigError::PemParsingError {
+            path: path.to_path_buf(),
+            message: format!(
+                "expected exactly one PKCS#8 private key, but found {}",
+                private_keys.len()
+            ),
+        });
+    }
+    Ok(private_keys.remove(0).0)
+}
+
+/// Implements `ServerCertVerifier` and `ClientCertVerifier`. The peer
+/// certificate is considered trusted if the peer presents exactly one
+/// certificate and this certificate equals the pinned one.
+///
+/// As a `ClientCertVerifier`, client authentication is mandatory.
+struct PinnedCertVerifier {
+    pinned_certificate_der: Vec<u8>,
+}
+
+impl PinnedCertVerifier {
+    fn new(pinned_certificate_der: Vec<u8>) -> Self {
+        Self {
+            pinned_certificate_der,
+        }
+    }
+
+    fn verify(&self, presented_certs: &[Certificate]) -> Result<(), TLSError> {
+        match presented_certs {
+            [presented_cert] if presented_cert.0 == self.pinned_certificate_der => Ok(()),
+            [_] => Err(TLSError::General(
+                "The peer certificate is not trusted since it differs from the pinned certificate."
+                    .to_string(),
+            )),
+            _ => Err(TLSError::General(format!(
+                "The peer must send exactly one self signed certificate, but it sent {} certificates.",
+                presented_certs.len()
+            ))),
+        }
+    }
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        _roots: &RootCertStore,
+        presented_certs: &[Certificate],
+        _dns_name: DNSNameRef,
+        _ocsp_response: &[u8],
+    ) -> Result<ServerCertVerified, TLSError> {
+        self.verify(presented_certs)
+            .map(|_| ServerCertVerified::assertion())
+    }
+}
+
+impl ClientCertVerifier for PinnedCertVerifier {
+    fn offer_client_auth(&self) -> bool {
+        true
+    }
+
+    fn client_auth_mandatory(&self, _sni: Option<&webpki::DNSName>) -> Option<bool> {
+        Some(true)
+    }
+
+    fn client_auth_root_subjects(
+        &self,
+        _sni: Option<&webpki::DNSName>,
+    ) -> Option<DistinguishedNames> {
+        // If `None` is returned, the connection would be aborted, see the rust doc of
+        // `client_auth_root_subjects`.
+        Some(DistinguishedNames::new())
+    }
+
+    fn verify_client_cert(
+        &self,
+        presented_certs: &[Certificate],
+        _sni: Option<&webpki::DNSName>,
+    ) -> Result<ClientCertVerified, TLSError> {
+        self.verify(presented_certs)
+            .map(|_| ClientCertVerified::assertion())
+    }
+}
@@ -1,9 +1,11 @@
 use crate::files::mk_temp_dir_with_permissions;
+use ic_crypto_internal_csp::CspVaultTlsConfig;
 use ic_crypto_internal_logmon::metrics::CryptoMetrics;
 use ic_logger::replica_logger::no_op_logger;
+use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::net::UnixListener;
+use tokio::net::{TcpListener, UnixListener};
 
 /// Creates a temporary file; it is the caller's responsibility to delete it
 /// after use.
@@ -50,3 +52,33 @@ pub fn start_new_remote_csp_vault_server_for_test(rt_handle: &tokio::runtime::Ha
     });
     return_socket_path
 }
+
+/// Starts a fresh CSP Vault server instance for testing that listens via TCP
+/// on the loopback interface and authenticates clients with TLS as specified
+/// by `tls_config`. Returns the address at which the server is listening.
+pub fn start_new_remote_csp_vault_server_with_tcp_tls_for_test(
+    rt_handle: &tokio::runtime::Handle,
+    tls_config: &CspVaultTlsConfig,
+) -> SocketAddr {
+    let sks_dir = mk_temp_dir_with_permissions(0o700);
+    let listener = rt_handle
+        .block_on(TcpListener::bind("127.0.0.1:0"))
+        .unwrap_or_else(|e| panic!("Error binding to loopback interface: {}", e));
+    let server_address = listener
+        .local_addr()
+        .expect("failed to get local socket address");
+    let server =
+        ic_crypto_internal_csp::vault::remote_csp_vault::TarpcCspVaultServerImpl::new_with_tcp_tls(
+            sks_dir.path(),
+            listener,
+            tls_config,
+            no_op_logger(),
+            Arc::new(CryptoMetrics::none()),
+        )
+        .expect("invalid TLS config");
+    rt_handle.spawn(async move {
+        let _move_temp_dir_here_to_ensure_it_is_not_cleaned_up = sks_dir;
+        server.run().await;
+    });
+    server_address
+}
@@ -0,0 +1,246 @@
+//! Test vectors for the public key derivation used for canister threshold
+//! ECDSA keys.
+//!
+//! The derivation is an extension of BIP32's CKDpub, see
+//! `DerivationPath` in the `tecdsa` crate. Unless otherwise noted the
+//! vectors derive from a master key, with an all-zero chain code, as
+//! the replica does for `ecdsa_public_key`. The master keys are
+//! `SHA-256("ic-crypto-extended-bip32-master-key-<curve>") * G`.
+use crate::unhex::*;
+use strum_macros::EnumIter;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DerivationCurve {
+    Secp256k1,
+    Secp256r1,
+}
+
+pub struct PublicKeyDerivationTestVectorData {
+    pub curve: DerivationCurve,
+    /// SEC1 compressed encoding of the key to derive from
+    pub public_key: Vec<u8>,
+    pub chain_code: Vec<u8>,
+    pub path: Vec<Vec<u8>>,
+    /// SEC1 compressed derived public key
+    pub derived_public_key: Vec<u8>,
+    pub derived_chain_code: Vec<u8>,
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, EnumIter)]
+pub enum PublicKeyDerivationTestVector {
+    SECP256K1_EMPTY_PATH,
+    SECP256K1_BIP32_PATH,
+    SECP256K1_CANISTER_PATH,
+    SECP256K1_DEEP_PATH,
+    SECP256R1_EMPTY_PATH,
+    SECP256R1_BIP32_PATH,
+    SECP256R1_CANISTER_PATH,
+    SECP256R1_DEEP_PATH,
+    SECP256K1_BIP32_TEST_VECTOR_1,
+}
+
+pub fn public_key_derivation_testvec(
+    test_vec: PublicKeyDerivationTestVector,
+) -> PublicKeyDerivationTestVectorData {
+    match test_vec {
+        PublicKeyDerivationTestVector::SECP256K1_EMPTY_PATH => PublicKeyDerivationTestVectorData {
+            curve: DerivationCurve::Secp256k1,
+            public_key: hex_to_byte_vec(
+                "03597641464a0f80c93807cb25e470658316fd2de60cb430f3e43847b0ce505a40",
+            ),
+            chain_code: hex_to_byte_vec(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            ),
+            path: vec![],
+            derived_public_key: hex_to_byte_vec(
+                "03597641464a0f80c93807cb25e470658316fd2de60cb430f3e43847b0ce505a40",
+            ),
+            derived_chain_code: hex_to_byte_vec(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            ),
+        },
+        PublicKeyDerivationTestVector::SECP256K1_BIP32_PATH => PublicKeyDerivationTestVectorData {
+            curve: DerivationCurve::Secp256k1,
+            public_key: hex_to_byte_vec(
+                "03597641464a0f80c93807cb25e470658316fd2de60cb430f3e43847b0ce505a40",
+            ),
+            chain_code: hex_to_byte_vec(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            ),
+            path: vec![
+                hex_to_byte_vec("00000001"),
+                hex_to_byte_vec("00000002"),
+                hex_to_byte_vec("00000003"),
+            ],
+            derived_public_key: hex_to_byte_vec(
+                "03e757428f3065acf4e5ef8572e39317b0998c37099b616a92e1fb304c19c43951",
+            ),
+            derived_chain_code: hex_to_byte_vec(
+                "186d9a411afd83c1a275dc81736c628cb6d565b13caa42ec2ed0b9734192d827",
+            ),
+        },
+        PublicKeyDerivationTestVector::SECP256K1_CANISTER_PATH => PublicKeyDerivationTestVectorData {
+            curve: DerivationCurve::Secp256k1,
+            public_key: hex_to_byte_vec(
+                "03597641464a0f80c93807cb25e470658316fd2de60cb430f3e43847b0ce505a40",
+            ),
+            chain_code: hex_to_byte_vec(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            ),
+            path: vec![
+                hex_to_byte_vec("0000000000e000be0101"),
+                hex_to_byte_vec(""),
+                hex_to_byte_vec("612064657269766174696f6e20696e6465782074686174206973206c6f6e676572207468616e2061207363616c61722c2061732063616e69737465727320617265206672656520746f207069636b20616e7920696e646578"),
+            ],
+            derived_public_key: hex_to_byte_vec(
+                "023fe64ec2645127f586991a126dbb3f7a910f09fe3518081e1b754fc68c2fff66",
+            ),
+            derived_chain_code: hex_to_byte_vec(
+                "f13f1b84c97342ccc7ac267576ce90a3ca6576d79b48edce75c69d862128b45c",
+            ),
+        },
+        PublicKeyDerivationTestVector::SECP256K1_DEEP_PATH => PublicKeyDerivationTestVectorData {
+            curve: DerivationCurve::Secp256k1,
+            public_key: hex_to_byte_vec(
+                "03597641464a0f80c93807cb25e470658316fd2de60cb430f3e43847b0ce505a40",
+            ),
+            chain_code: hex_to_byte_vec(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            ),
+            path: vec![
+                hex_to_byte_vec("00"),
+                hex_to_byte_vec("01"),
+                hex_to_byte_vec("02"),
+                hex_to_byte_vec("03"),
+                hex_to_byte_vec("04"),
+                hex_to_byte_vec("05"),
+                hex_to_byte_vec("06"),
+                hex_to_byte_vec("07"),
+                hex_to_byte_vec("08"),
+                hex_to_byte_vec("09"),
+                hex_to_byte_vec("0a"),
+                hex_to_byte_vec("0b"),
+                hex_to_byte_vec("0c"),
+                hex_to_byte_vec("0d"),
+                hex_to_byte_vec("0e"),
+                hex_to_byte_vec("0f"),
+            ],
+            derived_public_key: hex_to_byte_vec(
+                "030f0a7ce2914c043717f267d1b15098b3df78e00c6fb914dd1a01ffae78866102",
+            ),
+            derived_chain_code: hex_to_byte_vec(
+                "08e4e7f8d1ef01123eadf32d4932b4fd1f24562daf823221fd8da5d34541f3f2",
+            ),
+        },
+        PublicKeyDerivationTestVector::SECP256R1_EMPTY_PATH => PublicKeyDerivationTestVectorData {
+            curve: DerivationCurve::Secp256r1,
+            public_key: hex_to_byte_vec(
+                "02fb35faa0be68208c521416a9b63b1efe4be8405b5fbe17c84bb556137ee8e130",
+            ),
+            chain_code: hex_to_byte_vec(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            ),
+            path: vec![],
+            derived_public_key: hex_to_byte_vec(
+                "02fb35faa0be68208c521416a9b63b1efe4be8405b5fbe17c84bb556137ee8e130",
+            ),
+            derived_chain_code: hex_to_byte_vec(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            ),
+        },
+        PublicKeyDerivationTestVector::SECP256R1_BIP32_PATH => PublicKeyDerivationTestVectorData {
+            curve: DerivationCurve::Secp256r1,
+            public_key: hex_to_byte_vec(
+                "02fb35faa0be68208c521416a9b63b1efe4be8405b5fbe17c84bb556137ee8e130",
+            ),
+            chain_code: hex_to_byte_vec(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            ),
+            path: vec![
+                hex_to_byte_vec("00000001"),
+                hex_to_byte_vec("00000002"),
+                hex_to_byte_vec("00000003"),
+            ],
+            derived_public_key: hex_to_byte_vec(
+                "0221e57e4988525f836c76badd18feb4593887e36d82a902259b90075abd7b211c",
+            ),
+            derived_chain_code: hex_to_byte_vec(
+                "05565e743c6e4363c2161345524f94e2c85c2e7b8942480ca9f2635772d7b6e0",
+            ),
+        },
+        PublicKeyDerivationTestVector::SECP256R1_CANISTER_PATH => PublicKeyDerivationTestVectorData {
+            curve: DerivationCurve::Secp256r1,
+            public_key: hex_to_byte_vec(
+                "02fb35faa0be68208c521416a9b63b1efe4be8405b5fbe17c84bb556137ee8e130",
+            ),
+            chain_code: hex_to_byte_vec(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            ),
+            path: vec![
+                hex_to_byte_vec("0000000000e000be0101"),
+                hex_to_byte_vec(""),
+                hex_to_byte_vec("612064657269766174696f6e20696e6465782074686174206973206c6f6e676572207468616e2061207363616c61722c2061732063616e69737465727320617265206672656520746f207069636b20616e7920696e646578"),
+            ],
+            derived_public_key: hex_to_byte_vec(
+                "0380afec75c81f93fae81bb9d647e70c15d08d8b9c8b5ec9d6342e770dcad3f6ce",
+            ),
+            derived_chain_code: hex_to_byte_vec(
+                "7276ae509efc3748cf04102347eb0be7dc86ab1e0fd0dab813f358ada9b359bc",
+            ),
+        },
+        PublicKeyDerivationTestVector::SECP256R1_DEEP_PATH => PublicKeyDerivationTestVectorData {
+            curve: DerivationCurve::Secp256r1,
+            public_key: hex_to_byte_vec(
+                "02fb35faa0be68208c521416a9b63b1efe4be8405b5fbe17c84bb556137ee8e130",
+            ),
+            chain_code: hex_to_byte_vec(
+                "0000000000000000000000000000000000000000000000000000000000000000",
+            ),
+            path: vec![
+                hex_to_byte_vec("00"),
+                hex_to_byte_vec("01"),
+                hex_to_byte_vec("02"),
+                hex_to_byte_vec("03"),
+                hex_to_byte_vec("04"),
+                hex_to_byte_vec("05"),
+                hex_to_byte_vec("06"),
+                hex_to_byte_vec("07"),
+                hex_to_byte_vec("08"),
+                hex_to_byte_vec("09"),
+                hex_to_byte_vec("0a"),
+                hex_to_byte_vec("0b"),
+                hex_to_byte_vec("0c"),
+                hex_to_byte_vec("0d"),
+                hex_to_byte_vec("0e"),
+                hex_to_byte_vec("0f"),
+            ],
+            derived_public_key: hex_to_byte_vec(
+                "03fa441eb5c6dea5bf548ab885a6d940fcd72185530b63f7b3024f27bb840d147b",
+            ),
+            derived_chain_code: hex_to_byte_vec(
+                "ff170ae01e00c6f4650ccd39c05430faa91d250d199710cddb5eee894d245e02",
+            ),
+        },
+        // https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki#test-vector-1
+        // from m/0H to m/0H/1
+        PublicKeyDerivationTestVector::SECP256K1_BIP32_TEST_VECTOR_1 => PublicKeyDerivationTestVectorData {
+            curve: DerivationCurve::Secp256k1,
+            public_key: hex_to_byte_vec(
+                "035a784662a4a20a65bf6aab9ae98a6c068a81c52e4b032c0fb5400c706cfccc56",
+            ),
+            chain_code: hex_to_byte_vec(
+                "47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141",
+            ),
+            path: vec![
+                hex_to_byte_vec("00000001"),
+            ],
+            derived_public_key: hex_to_byte_vec(
+                "03501e454bf00751f24b1b489aa925215d66af2234e3891c3b21a52bedb3cd711c",
+            ),
+            derived_chain_code: hex_to_byte_vec(
+                "2a7857631386ba23dacac34180dd1983734e444fdbf774041578e9b6adb37c19",
+            ),
+        },
+    }
+}
@@ -2,6 +2,7 @@
 pub mod ecdsa_p256;
 pub mod ecdsa_secp256k1;
 pub mod ed25519;
+pub mod extended_bip32;
 pub mod iccsa;
 pub mod multi_bls12_381;
 pub mod test_data;
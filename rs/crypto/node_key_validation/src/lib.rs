@@ -12,6 +12,13 @@
 //! * the public key is valid, which includes checking that the key is a point
 //!   on the curve and in the right subgroup
 //!
+//! A node's ID is derived from the node signing key the node was registered
+//! with. When a node rotates its signing key, the new key is validated with
+//! `ValidNodeSigningPublicKey::try_from(key)`, which performs the same checks
+//! except for the one on the node ID. Likewise, the key material of a node
+//! that may have rotated its signing key is validated with
+//! `ValidNodePublicKeys::try_from_with_rotated_node_signing_key(keys, node_id)`.
+//!
 //! Validation of a *node's committee signing key* includes verifying that
 //! * the key is present and well-formed
 //! * the public key's proof of possession (PoP) is valid
@@ -88,6 +95,18 @@ pub struct ValidIDkgDealingEncryptionPublicKey {
     idkg_dealing_encryption_pubkey: PublicKey,
 }
 
+/// Validated node signing public key of a node that rotates its node signing
+/// key.
+///
+/// Instances have successfully passed the validity check and are immutable,
+/// i.e., the contained public key material is guaranteed to be valid.
+///
+/// Use `try_from` to create an instance from an unvalidated public key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidNodeSigningPublicKey {
+    node_signing_pubkey: PublicKey,
+}
+
 impl ValidNodePublicKeys {
     /// Determines if the given node public key material is valid.
     ///
@@ -98,6 +117,31 @@ impl ValidNodePublicKeys {
     /// whether key material is validated or not.
     pub fn try_from(keys: NodePublicKeys, node_id: NodeId) -> Result<Self, KeyValidationError> {
         validate_node_signing_key(keys.node_signing_pk.as_ref(), node_id)?;
+        Self::try_from_with_validated_node_signing_key(keys, node_id)
+    }
+
+    /// Determines if the given node public key material is valid, allowing
+    /// for a rotated node signing key.
+    ///
+    /// In contrast to `try_from`, the `node_id` is not required to be derived
+    /// from the node signing key, since the ID of a node does not change when
+    /// the node rotates its signing key. All other checks are the same.
+    pub fn try_from_with_rotated_node_signing_key(
+        keys: NodePublicKeys,
+        node_id: NodeId,
+    ) -> Result<Self, KeyValidationError> {
+        let node_signing_key = keys
+            .node_signing_pk
+            .as_ref()
+            .ok_or_else(|| invalid_node_signing_key_error("key is missing"))?;
+        validate_node_signing_key_bytes(node_signing_key)?;
+        Self::try_from_with_validated_node_signing_key(keys, node_id)
+    }
+
+    fn try_from_with_validated_node_signing_key(
+        keys: NodePublicKeys,
+        node_id: NodeId,
+    ) -> Result<Self, KeyValidationError> {
         validate_committee_signing_key(keys.committee_signing_pk.as_ref())?;
         validate_dkg_dealing_encryption_key(keys.dkg_dealing_encryption_pk.as_ref(), node_id)?;
         validate_tls_certificate(keys.tls_certificate.as_ref(), node_id)?;
@@ -180,6 +224,26 @@ impl ValidIDkgDealingEncryptionPublicKey {
     }
 }
 
+impl ValidNodeSigningPublicKey {
+    /// Determines if the given node signing public key is valid.
+    ///
+    /// Returns a `ValidNodeSigningPublicKey` iff the `key` is valid. In
+    /// contrast to `ValidNodePublicKeys::try_from`, the node ID is not
+    /// required to be derived from the `key`, since the ID of a node does not
+    /// change when the node rotates its signing key.
+    pub fn try_from(key: PublicKey) -> Result<Self, KeyValidationError> {
+        validate_node_signing_key_bytes(&key)?;
+        Ok(Self {
+            node_signing_pubkey: key,
+        })
+    }
+
+    /// Returns the validated node signing key.
+    pub fn get(&self) -> &PublicKey {
+        &self.node_signing_pubkey
+    }
+}
+
 /// A key validation error.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct KeyValidationError {
@@ -211,6 +275,15 @@ fn validate_node_signing_key(
             node_id
         )));
     }
+    validate_node_signing_key_bytes(pubkey_proto)
+}
+
+/// Validates a node's signing key without checking that the node ID is
+/// derived from it.
+fn validate_node_signing_key_bytes(pubkey_proto: &PublicKey) -> Result<(), KeyValidationError> {
+    let pubkey_bytes = BasicSigEd25519PublicKeyBytes::try_from(pubkey_proto)
+        .map_err(|e| invalid_node_signing_key_error(format!("{}", e)))?;
+
     if !ic_crypto_internal_basic_sig_ed25519::verify_public_key(&pubkey_bytes) {
         return Err(invalid_node_signing_key_error("verification failed"));
     }
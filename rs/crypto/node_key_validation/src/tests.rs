@@ -501,6 +501,117 @@ mod idkg_dealing_encryption_public_key_validation {
     }
 }
 
+mod node_signing_public_key_validation {
+    use super::*;
+
+    #[test]
+    fn should_succeed_on_valid_node_signing_key() {
+        let node_signing_key = valid_node_keys()
+            .node_signing_pk
+            .expect("missing node signing key");
+
+        let result = ValidNodeSigningPublicKey::try_from(node_signing_key.clone());
+
+        assert!(matches!(result, Ok(key) if key.get() == &node_signing_key));
+    }
+
+    #[test]
+    fn should_succeed_on_node_signing_key_of_another_node() {
+        let (keys, node_id) = valid_node_keys_and_node_id();
+        let (other_keys, _other_node_id) = valid_node_keys_and_node_id();
+        let rotated_node_signing_key = other_keys
+            .node_signing_pk
+            .expect("missing node signing key");
+        let rotated_keys = NodePublicKeys {
+            node_signing_pk: Some(rotated_node_signing_key.clone()),
+            ..keys
+        };
+        assert!(ValidNodePublicKeys::try_from(rotated_keys, node_id).is_err());
+
+        let result = ValidNodeSigningPublicKey::try_from(rotated_node_signing_key);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn should_fail_if_node_signing_key_pubkey_conversion_fails() {
+        let mut node_signing_key = valid_node_keys()
+            .node_signing_pk
+            .expect("missing node signing key");
+        node_signing_key.key_value.push(42);
+
+        let result = ValidNodeSigningPublicKey::try_from(node_signing_key);
+
+        assert!(matches!(result, Err(KeyValidationError { error })
+            if error.contains("invalid node signing key: PublicKeyBytesFromProtoError")
+            && error.contains("Wrong data length")
+        ));
+    }
+
+    #[test]
+    fn should_fail_if_node_signing_key_verification_fails() {
+        let mut node_signing_key = valid_node_keys()
+            .node_signing_pk
+            .expect("missing node signing key");
+        let invalid_pubkey = {
+            let nspk_bytes = BasicSigEd25519PublicKeyBytes::try_from(&node_signing_key).unwrap();
+            invalidate_valid_ed25519_pubkey(nspk_bytes)
+        };
+        node_signing_key.key_value = invalid_pubkey.0.to_vec();
+
+        let result = ValidNodeSigningPublicKey::try_from(node_signing_key);
+
+        assert!(matches!(result, Err(KeyValidationError { error })
+            if error == "invalid node signing key: verification failed"
+        ));
+    }
+
+    #[test]
+    fn should_succeed_on_node_keys_with_rotated_node_signing_key() {
+        let (keys, node_id) = valid_node_keys_and_node_id();
+        let (other_keys, _other_node_id) = valid_node_keys_and_node_id();
+        let rotated_node_signing_key = other_keys
+            .node_signing_pk
+            .expect("missing node signing key");
+        let rotated_keys = NodePublicKeys {
+            node_signing_pk: Some(rotated_node_signing_key.clone()),
+            ..keys
+        };
+
+        let result =
+            ValidNodePublicKeys::try_from_with_rotated_node_signing_key(rotated_keys, node_id);
+
+        assert!(matches!(result, Ok(valid_keys)
+            if valid_keys.node_id() == node_id
+            && valid_keys.node_signing_key() == &rotated_node_signing_key
+        ));
+    }
+
+    #[test]
+    fn should_fail_on_node_keys_with_missing_rotated_node_signing_key() {
+        let (mut keys, node_id) = valid_node_keys_and_node_id();
+        keys.node_signing_pk = None;
+
+        let result = ValidNodePublicKeys::try_from_with_rotated_node_signing_key(keys, node_id);
+
+        assert!(matches!(result, Err(KeyValidationError { error })
+            if error == "invalid node signing key: key is missing"
+        ));
+    }
+
+    #[test]
+    fn should_fail_on_node_keys_with_rotated_node_signing_key_for_another_node_id() {
+        let (keys, _node_id) = valid_node_keys_and_node_id();
+        let (_other_keys, other_node_id) = valid_node_keys_and_node_id();
+
+        // The TLS certificate is still bound to the node ID.
+        let result =
+            ValidNodePublicKeys::try_from_with_rotated_node_signing_key(keys, other_node_id);
+
+        assert!(result.is_err());
+    }
+}
+
 fn invalidate_valid_ed25519_pubkey(
     valid_pubkey: BasicSigEd25519PublicKeyBytes,
 ) -> BasicSigEd25519PublicKeyBytes {
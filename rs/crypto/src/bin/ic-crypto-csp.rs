@@ -1,5 +1,6 @@
 use clap::Parser;
 use ic_config::{Config, ConfigSource};
+use ic_crypto_internal_csp::CspVaultTlsConfig;
 use ic_crypto_internal_logmon::metrics::CryptoMetrics;
 use ic_logger::{info, new_replica_logger_from_config};
 use ic_metrics::MetricsRegistry;
@@ -13,43 +14,109 @@ const IC_CRYPTO_CSP_SOCKET_NAME: &str = "ic-crypto-csp.socket";
     name = "Remote CspVault server",
     version = "0.1",
     author = "Internet Computer Developers",
-    about = "NOTE: This binary is intended to be started as socket-activated \
-               systemd service with a single socket named ic-crypto-csp.socket"
+    about = "NOTE: Unless --tcp-listen-address is given, this binary is intended \
+               to be started as socket-activated systemd service with a single socket \
+               named ic-crypto-csp.socket"
 )]
 struct Opts {
     /// Sets the replica configuration file
     #[clap(long = "replica-config-file", parse(from_os_str))]
     config: PathBuf,
+
+    /// Listens for replica connections on this TCP address (`host:port`)
+    /// instead of the systemd socket. Connections are authenticated with
+    /// mutual TLS, which requires the --tls-* options.
+    #[clap(long = "tcp-listen-address")]
+    tcp_listen_address: Option<String>,
+
+    /// PEM file with the certificate the server presents to the replica
+    #[clap(long = "tls-certificate-pem-file", parse(from_os_str))]
+    tls_certificate_pem_file: Option<PathBuf>,
+
+    /// PEM file with the PKCS#8 private key of the server's certificate
+    #[clap(long = "tls-private-key-pem-file", parse(from_os_str))]
+    tls_private_key_pem_file: Option<PathBuf>,
+
+    /// PEM file with the certificate the replica must present
+    #[clap(long = "tls-client-certificate-pem-file", parse(from_os_str))]
+    tls_client_certificate_pem_file: Option<PathBuf>,
 }
 
 #[tokio::main]
 async fn main() {
     let opts = Opts::parse();
-    let ic_config = get_ic_config(opts.config);
+    let ic_config = get_ic_config(opts.config.clone());
 
     let sks_dir = ic_config.crypto.crypto_root.as_path();
 
-    ensure_single_named_systemd_socket(IC_CRYPTO_CSP_SOCKET_NAME);
-    let systemd_socket_listener = listener_from_first_systemd_socket();
-
     // The `AsyncGuard` must be kept in scope for asynchronously logged messages to appear in the logs.
     let (logger, _async_log_guard) = new_replica_logger_from_config(&ic_config.csp_vault_logger);
 
-    info!(logger;
-        crypto.method_name => "main",
-        crypto.description => format!(
-            "Starting CspVault server listening at systemd socket '{:?}', with SKS-data in '{}' ...",
-            systemd_socket_listener.local_addr().expect("failed to get local socket address"),
-            sks_dir.display()
-        )
-    );
-
     // We abort the whole program with a core dump if a single thread panics.
     // This way we can capture all the context if a critical error happens.
     abort_on_panic();
     let metrics = CryptoMetrics::new(Some(&MetricsRegistry::global()));
-    ic_crypto_internal_csp::run_csp_vault_server(sks_dir, systemd_socket_listener, logger, metrics)
-        .await;
+
+    match &opts.tcp_listen_address {
+        None => {
+            ensure_single_named_systemd_socket(IC_CRYPTO_CSP_SOCKET_NAME);
+            let systemd_socket_listener = listener_from_first_systemd_socket();
+            info!(logger;
+                crypto.method_name => "main",
+                crypto.description => format!(
+                    "Starting CspVault server listening at systemd socket '{:?}', with SKS-data in '{}' ...",
+                    systemd_socket_listener.local_addr().expect("failed to get local socket address"),
+                    sks_dir.display()
+                )
+            );
+            ic_crypto_internal_csp::run_csp_vault_server(
+                sks_dir,
+                systemd_socket_listener,
+                logger,
+                metrics,
+            )
+            .await;
+        }
+        Some(tcp_listen_address) => {
+            let tls_config = tls_config_from_opts(&opts);
+            let tcp_listener = tokio::net::TcpListener::bind(tcp_listen_address)
+                .await
+                .unwrap_or_else(|e| panic!("Failed to bind to {}: {}", tcp_listen_address, e));
+            info!(logger;
+                crypto.method_name => "main",
+                crypto.description => format!(
+                    "Starting CspVault server listening via TCP with TLS at '{:?}', with SKS-data in '{}' ...",
+                    tcp_listener.local_addr().expect("failed to get local socket address"),
+                    sks_dir.display()
+                )
+            );
+            ic_crypto_internal_csp::run_csp_vault_server_with_tcp_tls(
+                sks_dir,
+                tcp_listener,
+                &tls_config,
+                logger,
+                metrics,
+            )
+            .await
+            .unwrap_or_else(|e| panic!("Invalid TLS config: {}", e));
+        }
+    }
+}
+
+fn tls_config_from_opts(opts: &Opts) -> CspVaultTlsConfig {
+    let required = |path: &Option<PathBuf>, option: &str| -> PathBuf {
+        path.clone()
+            .unwrap_or_else(|| panic!("--{} is required with --tcp-listen-address", option))
+    };
+    CspVaultTlsConfig::from_pem_files(
+        &required(&opts.tls_certificate_pem_file, "tls-certificate-pem-file"),
+        &required(&opts.tls_private_key_pem_file, "tls-private-key-pem-file"),
+        &required(
+            &opts.tls_client_certificate_pem_file,
+            "tls-client-certificate-pem-file",
+        ),
+    )
+    .unwrap_or_else(|e| panic!("Invalid TLS config: {}", e))
 }
 
 /// Aborts the whole program with a core dump if a single thread panics.
@@ -9,10 +9,10 @@ use ic_crypto_internal_csp::api::tls_errors::{
     CspTlsClientHandshakeError, CspTlsServerHandshakeError,
 };
 use ic_crypto_internal_csp::api::{
-    CspCreateMEGaKeyError, CspIDkgProtocol, CspKeyGenerator, CspSecretKeyStoreChecker, CspSigner,
-    CspThresholdEcdsaSigVerifier, CspThresholdEcdsaSigner, CspThresholdSignError,
-    CspTlsClientHandshake, CspTlsHandshakeSignerProvider, CspTlsServerHandshake,
-    DistributedKeyGenerationCspClient, NiDkgCspClient, NodePublicKeyData,
+    CspCreateMEGaKeyError, CspIDkgProtocol, CspKeyGenerator, CspNodeKeyRotation,
+    CspSecretKeyStoreChecker, CspSigner, CspThresholdEcdsaSigVerifier, CspThresholdEcdsaSigner,
+    CspThresholdSignError, CspTlsClientHandshake, CspTlsHandshakeSignerProvider,
+    CspTlsServerHandshake, DistributedKeyGenerationCspClient, NiDkgCspClient, NodePublicKeyData,
     ThresholdSignatureCspClient,
 };
 use ic_crypto_internal_csp::tls::cert_chain::CspCertificateChain;
@@ -40,6 +40,7 @@ use ic_crypto_internal_types::sign::threshold_sig::ni_dkg::{
 use ic_crypto_internal_types::sign::threshold_sig::public_key::CspThresholdSigPublicKey;
 use ic_crypto_tls_interfaces::{TlsPublicKeyCert, TlsStream};
 use ic_protobuf::crypto::v1::NodePublicKeys;
+use ic_protobuf::registry::crypto::v1::{PublicKey as PublicKeyProto, X509PublicKeyCert};
 use ic_types::crypto::canister_threshold_sig::error::{
     IDkgCreateDealingError, IDkgCreateTranscriptError, IDkgLoadTranscriptError,
     IDkgOpenTranscriptError, IDkgRetainThresholdKeysError, IDkgVerifyComplaintError,
@@ -115,6 +116,16 @@ mock! {
         ) -> Result<TlsPublicKeyCert, CryptoError>;
     }
 
+    pub trait CspNodeKeyRotation {
+        fn rotate_node_signing_key(&self, node_id: NodeId) -> Result<PublicKeyProto, CryptoError>;
+
+        fn rotate_tls_key(
+            &self,
+            node_id: NodeId,
+            not_after: &str,
+        ) -> Result<X509PublicKeyCert, CryptoError>;
+    }
+
     pub trait ThresholdSignatureCspClient {
 
         fn threshold_sign(
@@ -25,7 +25,7 @@ use crate::keygen::{
     ensure_dkg_dealing_encryption_key_material_is_set_up_correctly,
     ensure_idkg_dealing_encryption_key_material_is_set_up_correctly,
     ensure_node_signing_key_material_is_set_up_correctly,
-    ensure_tls_key_material_is_set_up_correctly,
+    ensure_tls_key_material_is_set_up_correctly, TLS_CERT_NOT_AFTER,
 };
 use ic_crypto_internal_logmon::metrics::CryptoMetrics;
 pub use temp_crypto::{NodeKeysToGenerate, TempCryptoComponent};
@@ -144,11 +144,7 @@ pub fn get_node_keys_or_generate_if_missing(
                     panic!("Generated keys differ from the stored ones.");
                 }
             }
-            let node_signing_pk = node_pks
-                .node_signing_pk
-                .as_ref()
-                .expect("Missing node signing public key");
-            let node_id = derive_node_id(node_signing_pk);
+            let node_id = node_id_from_public_key_store(crypto_root, &node_pks);
             (node_pks, node_id)
         }
         Err(e) => panic!("Node contains inconsistent key material: {}", e),
@@ -160,6 +156,33 @@ pub fn derive_node_id(node_signing_pk: &PublicKeyProto) -> NodeId {
         .expect("Corrupted node signing public key")
 }
 
+/// Returns the ID of the node whose public keys are in the public key store at
+/// `crypto_root`.
+///
+/// The node ID is derived from the node signing public key, unless the node
+/// signing key was rotated: in that case the node ID is read from the public
+/// key store, where it was stored when the key was rotated.
+///
+/// # Panics
+///  * if the stored node ID cannot be read.
+///  * if no node ID is stored and the node signing public key is missing or
+///    corrupted.
+pub(crate) fn node_id_from_public_key_store(
+    crypto_root: &Path,
+    node_pks: &NodePublicKeys,
+) -> NodeId {
+    match public_key_store::read_node_id(crypto_root) {
+        Ok(Some(node_id)) => node_id,
+        Ok(None) => derive_node_id(
+            node_pks
+                .node_signing_pk
+                .as_ref()
+                .expect("Missing node signing public key"),
+        ),
+        Err(e) => panic!("Failed to read the node ID: {:?}", e),
+    }
+}
+
 fn generate_node_signing_keys(csp: &dyn CryptoServiceProvider) -> PublicKeyProto {
     let generated = csp
         .gen_key_pair(AlgorithmId::Ed25519)
@@ -318,7 +341,7 @@ fn generate_committee_signing_keys(csp: &dyn CryptoServiceProvider) -> PublicKey
 /// 4.1.2.5; see https://tools.ietf.org/html/rfc5280#section-4.1.2.5) that the
 /// certificate has no well-defined expiration date.
 fn generate_tls_keys(csp: &mut dyn CryptoServiceProvider, node: NodeId) -> TlsPublicKeyCert {
-    csp.gen_tls_key_pair(node, "99991231235959Z")
+    csp.gen_tls_key_pair(node, TLS_CERT_NOT_AFTER)
         .expect("error generating TLS key pair")
 }
 
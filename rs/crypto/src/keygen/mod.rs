@@ -18,7 +18,7 @@ use ic_crypto_internal_types::encrypt::forward_secure::{
 };
 use ic_crypto_tls_interfaces::TlsPublicKeyCert;
 use ic_interfaces::crypto::{KeyManager, PublicKeyRegistrationStatus};
-use ic_logger::warn;
+use ic_logger::{info, warn};
 use ic_protobuf::crypto::v1::NodePublicKeys;
 use ic_protobuf::registry::crypto::v1::{PublicKey as PublicKeyProto, X509PublicKeyCert};
 use ic_registry_client_helpers::crypto::CryptoRegistry;
@@ -27,6 +27,10 @@ use ic_types::RegistryVersion;
 use std::convert::TryFrom;
 use std::sync::Arc;
 
+/// The 'not after' date of the node's TLS certificates. RFC 5280 specifies
+/// this value for certificates that have no well-defined expiration date.
+pub(crate) const TLS_CERT_NOT_AFTER: &str = "99991231235959Z";
+
 impl<C: CryptoServiceProvider> KeyManager for CryptoComponentFatClient<C> {
     fn node_public_keys(&self) -> NodePublicKeys {
         self.csp.node_public_keys()
@@ -45,6 +49,14 @@ impl<C: CryptoServiceProvider> KeyManager for CryptoComponentFatClient<C> {
         if let Some(pubkey) = self.unregistered_idkg_dealing_encryption_key(registry_version) {
             return Ok(PublicKeyRegistrationStatus::IDkgDealingEncPubkeyNeedsRegistration(pubkey));
         }
+        let node_signing_pk = self.unregistered_rotated_node_signing_key(registry_version)?;
+        let tls_certificate = self.unregistered_rotated_tls_certificate(registry_version)?;
+        if node_signing_pk.is_some() || tls_certificate.is_some() {
+            return Ok(PublicKeyRegistrationStatus::RotatedKeysNeedRegistration {
+                node_signing_pk,
+                tls_certificate,
+            });
+        }
         if self.node_public_keys().idkg_dealing_encryption_pk.is_none() {
             warn!(
                 self.logger,
@@ -65,6 +77,21 @@ impl<C: CryptoServiceProvider> KeyManager for CryptoComponentFatClient<C> {
         }
         Ok(PublicKeyRegistrationStatus::AllKeysRegistered)
     }
+
+    fn rotate_node_signing_key(&self) -> CryptoResult<PublicKeyProto> {
+        let node_signing_pk = self.csp.rotate_node_signing_key(self.node_id)?;
+        info!(
+            self.logger,
+            "Rotated the node signing key of node {}", self.node_id
+        );
+        Ok(node_signing_pk)
+    }
+
+    fn rotate_tls_key(&self) -> CryptoResult<X509PublicKeyCert> {
+        let tls_certificate = self.csp.rotate_tls_key(self.node_id, TLS_CERT_NOT_AFTER)?;
+        info!(self.logger, "Rotated the TLS key of node {}", self.node_id);
+        Ok(tls_certificate)
+    }
 }
 
 // Helpers for implementing `KeyManager`-trait.
@@ -185,6 +212,51 @@ impl<C: CryptoServiceProvider> CryptoComponentFatClient<C> {
         None
     }
 
+    /// Returns the node signing public key from the public key store if it
+    /// was rotated, i.e., if it differs from the one in the registry, and if
+    /// it is backed by a secret key.
+    fn unregistered_rotated_node_signing_key(
+        &self,
+        registry_version: RegistryVersion,
+    ) -> CryptoResult<Option<PublicKeyProto>> {
+        let registry_pk = key_from_registry(
+            Arc::clone(&self.registry_client),
+            self.node_id,
+            KeyPurpose::NodeSigning,
+            registry_version,
+        )?;
+        match self.node_public_keys().node_signing_pk {
+            Some(local_pk) if local_pk != registry_pk => {
+                ensure_node_signing_key_material_is_set_up_correctly(local_pk.clone(), &self.csp)?;
+                Ok(Some(local_pk))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the TLS certificate from the public key store if it was
+    /// rotated, i.e., if it differs from the one in the registry, and if it is
+    /// backed by a secret key.
+    fn unregistered_rotated_tls_certificate(
+        &self,
+        registry_version: RegistryVersion,
+    ) -> CryptoResult<Option<X509PublicKeyCert>> {
+        let registry_cert = self
+            .registry_client
+            .get_tls_certificate(self.node_id, registry_version)?
+            .ok_or(CryptoError::TlsCertNotFound {
+                node_id: self.node_id,
+                registry_version,
+            })?;
+        match self.node_public_keys().tls_certificate {
+            Some(local_cert) if local_cert != registry_cert => {
+                ensure_tls_key_material_is_set_up_correctly(local_cert.clone(), &self.csp)?;
+                Ok(Some(local_cert))
+            }
+            _ => Ok(None),
+        }
+    }
+
     fn ensure_idkg_dealing_encryption_key_material_is_set_up(
         &self,
         registry_version: RegistryVersion,
@@ -24,7 +24,7 @@ pub use sign::utils::{
 };
 pub use sign::{derive_tecdsa_public_key, get_tecdsa_master_public_key};
 
-use crate::common::utils::{derive_node_id, TempCryptoComponent};
+use crate::common::utils::{derive_node_id, node_id_from_public_key_store, TempCryptoComponent};
 use crate::sign::ThresholdSigDataStoreImpl;
 use crate::utils::get_node_keys_or_generate_if_missing;
 use ic_config::crypto::CryptoConfig;
@@ -236,7 +236,7 @@ impl CryptoComponentFatClient<Csp<OsRng, ProtoSecretKeyStore, ProtoSecretKeyStor
     /// as this will lead to concurrency issues e.g. when the components
     /// access the secret key store simultaneously.
     ///
-    /// If the `config`'s vault type is `UnixSocket`, a `tokio_runtime_handle`
+    /// If the `config`'s vault type is `UnixSocket` or `Tcp`, a `tokio_runtime_handle`
     /// must be provided, which is then used for the `async`hronous
     /// communication with the vault via RPC for secret key operations. In most
     /// cases, this is done by calling `tokio::runtime::Handle::block_on` and
@@ -248,7 +248,7 @@ impl CryptoComponentFatClient<Csp<OsRng, ProtoSecretKeyStore, ProtoSecretKeyStor
     /// and accepting the performance implications.
     ///
     /// # Panics
-    /// Panics if the `config`'s vault type is `UnixSocket` and
+    /// Panics if the `config`'s vault type is `UnixSocket` or `Tcp` and
     /// `tokio_runtime_handle` is `None`.
     ///
     /// ```
@@ -289,12 +289,7 @@ impl CryptoComponentFatClient<Csp<OsRng, ProtoSecretKeyStore, ProtoSecretKeyStor
             Some(new_logger!(&logger)),
             Arc::clone(&metrics),
         );
-        let node_pks = csp.node_public_keys();
-        let node_signing_pk = node_pks
-            .node_signing_pk
-            .as_ref()
-            .expect("Missing node signing public key");
-        let node_id = derive_node_id(node_signing_pk);
+        let node_id = node_id_from_public_key_store(&config.crypto_root, &csp.node_public_keys());
         CryptoComponentFatClient {
             lockable_threshold_sig_data_store: LockableThresholdSigDataStore::new(),
             csp,
@@ -308,7 +303,7 @@ impl CryptoComponentFatClient<Csp<OsRng, ProtoSecretKeyStore, ProtoSecretKeyStor
     /// Creates a crypto component using a fake `node_id`.
     ///
     /// # Panics
-    /// Panics if the `config`'s vault type is `UnixSocket` and
+    /// Panics if the `config`'s vault type is `UnixSocket` or `Tcp` and
     /// `tokio_runtime_handle` is `None`.
     pub fn new_with_fake_node_id(
         config: &CryptoConfig,
@@ -355,7 +350,7 @@ impl CryptoComponentFatClient<Csp<OsRng, ProtoSecretKeyStore, ProtoSecretKeyStor
     /// Please refer to the trait documentation of
     /// `CryptoComponentForNonReplicaProcess` for more details.
     ///
-    /// If the `config`'s vault type is `UnixSocket`, a `tokio_runtime_handle`
+    /// If the `config`'s vault type is `UnixSocket` or `Tcp`, a `tokio_runtime_handle`
     /// must be provided, which is then used for the `async`hronous
     /// communication with the vault via RPC for secret key operations. In most
     /// cases, this is done by calling `tokio::runtime::Handle::block_on` and
@@ -374,7 +369,7 @@ impl CryptoComponentFatClient<Csp<OsRng, ProtoSecretKeyStore, ProtoSecretKeyStor
     /// The methods of the `TlsHandshake` trait are unaffected by this.
     ///
     /// # Panics
-    /// Panics if the `config`'s vault type is `UnixSocket` and
+    /// Panics if the `config`'s vault type is `UnixSocket` or `Tcp` and
     /// `tokio_runtime_handle` is `None`.
     pub fn new_for_non_replica_process(
         config: &CryptoConfig,
@@ -215,6 +215,7 @@ pub fn get_tecdsa_master_public_key(
             let pub_key = internal_transcript.constant_term();
             let algorithm_id = match idkg_transcript.algorithm_id {
                 AlgorithmId::ThresholdEcdsaSecp256k1 => AlgorithmId::EcdsaSecp256k1,
+                AlgorithmId::ThresholdEcdsaSecp256r1 => AlgorithmId::EcdsaP256,
                 _ => {
                     return Err(MasterPublicKeyExtractionError::UnsupportedAlgorithm(
                         format!("{:?}", idkg_transcript.algorithm_id),
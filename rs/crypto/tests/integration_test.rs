@@ -777,11 +777,65 @@ fn should_succeed_check_keys_with_registry_if_idkg_key_requires_registration() {
     ));
 }
 
+#[test]
+fn should_succeed_check_keys_with_registry_if_rotated_node_signing_key_requires_registration() {
+    let crypto = TestKeygenCrypto::builder()
+        .with_node_keys_to_generate(NodeKeysToGenerate::all())
+        .add_generated_node_signing_key_to_registry()
+        .add_generated_committee_signing_key_to_registry()
+        .add_generated_dkg_dealing_enc_key_to_registry()
+        .add_generated_idkg_dealing_enc_key_to_registry()
+        .add_generated_tls_cert_to_registry()
+        .build(NODE_ID, REG_V1);
+
+    let rotated_node_signing_pk = crypto.get().rotate_node_signing_key().unwrap();
+    let result = crypto.get().check_keys_with_registry(REG_V1);
+
+    assert_eq!(
+        crypto.get().node_public_keys().node_signing_pk,
+        Some(rotated_node_signing_pk.clone())
+    );
+    assert!(matches!(
+        result,
+        Ok(PublicKeyRegistrationStatus::RotatedKeysNeedRegistration {
+            node_signing_pk: Some(key),
+            tls_certificate: None,
+        }) if key == rotated_node_signing_pk
+    ));
+}
+
+#[test]
+fn should_succeed_check_keys_with_registry_if_rotated_tls_certificate_requires_registration() {
+    let crypto = TestKeygenCrypto::builder()
+        .with_node_keys_to_generate(NodeKeysToGenerate::all())
+        .add_generated_node_signing_key_to_registry()
+        .add_generated_committee_signing_key_to_registry()
+        .add_generated_dkg_dealing_enc_key_to_registry()
+        .add_generated_idkg_dealing_enc_key_to_registry()
+        .add_generated_tls_cert_to_registry()
+        .build(NODE_ID, REG_V1);
+
+    let rotated_tls_certificate = crypto.get().rotate_tls_key().unwrap();
+    let result = crypto.get().check_keys_with_registry(REG_V1);
+
+    assert_eq!(
+        crypto.get().node_public_keys().tls_certificate,
+        Some(rotated_tls_certificate.clone())
+    );
+    assert!(matches!(
+        result,
+        Ok(PublicKeyRegistrationStatus::RotatedKeysNeedRegistration {
+            node_signing_pk: None,
+            tls_certificate: Some(cert),
+        }) if cert == rotated_tls_certificate
+    ));
+}
+
 /// If this test fails it means that one of AlgorithmId and AlgorithmIdProto structs was updated but not the other.
 /// Ensure the structs are consistent and then update the test below.
 #[test]
 fn algorithm_id_should_match_algorithm_id_proto() {
-    let algorithm_id_variants = 17;
+    let algorithm_id_variants = 20;
     assert_eq!(AlgorithmId::iter().count(), algorithm_id_variants);
 
     for i in 0..algorithm_id_variants {
@@ -854,6 +908,18 @@ fn algorithm_id_should_match_algorithm_id_proto() {
         AlgorithmId::MegaSecp256k1 as i32,
         AlgorithmIdProto::MegaSecp256k1 as i32
     );
+    assert_eq!(
+        AlgorithmId::ThresholdEcdsaSecp256r1 as i32,
+        AlgorithmIdProto::ThresholdEcdsaSecp256r1 as i32
+    );
+    assert_eq!(
+        AlgorithmId::ThresholdSchnorrBip340 as i32,
+        AlgorithmIdProto::ThresholdSchnorrBip340 as i32
+    );
+    assert_eq!(
+        AlgorithmId::ThresholdEd25519 as i32,
+        AlgorithmIdProto::ThresholdEd25519 as i32
+    );
 }
 
 fn get_logger(log_filename: &std::path::Path) -> LoggerImpl {
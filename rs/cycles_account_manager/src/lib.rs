@@ -16,7 +16,8 @@
 use ic_base_types::NumSeconds;
 use ic_config::subnet_config::CyclesAccountManagerConfig;
 use ic_ic00_types::{
-    CanisterIdRecord, InstallCodeArgs, Method, Payload, SetControllerArgs, UpdateSettingsArgs,
+    CanisterIdRecord, CanisterSnapshotArgs, InstallCodeArgs, Method, Payload, SetControllerArgs,
+    TakeCanisterSnapshotArgs, UpdateSettingsArgs,
 };
 use ic_interfaces::execution_environment::CanisterOutOfCyclesError;
 use ic_logger::{error, info, ReplicaLogger};
@@ -370,7 +371,9 @@ impl CyclesAccountManager {
                 | Ok(Method::CanisterStatus)
                 | Ok(Method::DeleteCanister)
                 | Ok(Method::UninstallCode)
-                | Ok(Method::StopCanister) => match CanisterIdRecord::decode(ingress.arg()) {
+                | Ok(Method::StopCanister)
+                | Ok(Method::ListCanisterSnapshots)
+                | Ok(Method::FetchCanisterLogs) => match CanisterIdRecord::decode(ingress.arg()) {
                     Ok(record) => Some(record.get_canister_id()),
                     Err(err) => {
                         return Err(IngressInductionCostError::InvalidSubnetPayload(
@@ -399,6 +402,26 @@ impl CyclesAccountManager {
                         ))
                     }
                 },
+                Ok(Method::TakeCanisterSnapshot) => {
+                    match TakeCanisterSnapshotArgs::decode(ingress.arg()) {
+                        Ok(args) => Some(args.get_canister_id()),
+                        Err(err) => {
+                            return Err(IngressInductionCostError::InvalidSubnetPayload(
+                                err.to_string(),
+                            ))
+                        }
+                    }
+                }
+                Ok(Method::LoadCanisterSnapshot) | Ok(Method::DeleteCanisterSnapshot) => {
+                    match CanisterSnapshotArgs::decode(ingress.arg()) {
+                        Ok(args) => Some(args.get_canister_id()),
+                        Err(err) => {
+                            return Err(IngressInductionCostError::InvalidSubnetPayload(
+                                err.to_string(),
+                            ))
+                        }
+                    }
+                }
                 Ok(Method::InstallCode) => match InstallCodeArgs::decode(ingress.arg()) {
                     Ok(record) => Some(record.get_canister_id()),
                     Err(err) => {
@@ -408,6 +431,7 @@ impl CyclesAccountManager {
                     }
                 },
                 Ok(Method::CreateCanister)
+                | Ok(Method::CanisterInfo)
                 | Ok(Method::SetupInitialDKG)
                 | Ok(Method::DepositCycles)
                 | Ok(Method::HttpRequest)
@@ -488,8 +512,42 @@ impl CyclesAccountManager {
     ) -> Result<(), CanisterOutOfCyclesError> {
         let cycles_amount = self.memory_cost(bytes, duration, subnet_size);
 
+        // Cycles reserved for storage are used up first and the rest is
+        // charged from the main balance.
+        let from_reserved_balance = std::cmp::min(cycles_amount, system_state.reserved_balance());
+
         // Can charge all the way to the empty account (zero cycles)
-        self.consume_with_threshold(system_state, cycles_amount, Cycles::zero())
+        self.consume_with_threshold(
+            system_state,
+            cycles_amount - from_reserved_balance,
+            Cycles::zero(),
+        )?;
+        system_state.remove_cycles_from_reserved_balance(from_reserved_balance);
+        self.observe_consumed_cycles(system_state, from_reserved_balance);
+        Ok(())
+    }
+
+    /// Returns the number of cycles that a canister has to move to its
+    /// reserved balance when it allocates `allocated_bytes` of new storage on
+    /// a subnet with the given memory saturation.
+    ///
+    /// Nothing is reserved while the subnet memory usage is below the
+    /// threshold. Above it, the reserved cycles pay for the new storage for up
+    /// to `max_storage_reservation_period`, scaled linearly from zero at the
+    /// threshold to the full period at the subnet capacity.
+    ///
+    /// Note: like `memory_cost()` without the subnet size scaling.
+    pub fn storage_reservation_cycles(
+        &self,
+        allocated_bytes: NumBytes,
+        subnet_memory_saturation: &ResourceSaturation,
+    ) -> Cycles {
+        let reserved_bytes = subnet_memory_saturation.reservation_factor(allocated_bytes.get());
+        self.memory_cost(
+            NumBytes::new(reserved_bytes),
+            self.config.max_storage_reservation_period,
+            1,
+        )
     }
 
     /// The cost of using `bytes` worth of memory.
@@ -753,10 +811,14 @@ impl CyclesAccountManager {
     /// Charges a canister for its resource allocation and usage for the
     /// duration specified. If fees were successfully charged, then returns
     /// Ok(CanisterState) else returns Err(CanisterState).
+    ///
+    /// `snapshots_memory_usage` is the total size of the canister's snapshots,
+    /// which is charged on top of the canister's own memory.
     pub fn charge_canister_for_resource_allocation_and_usage(
         &self,
         log: &ReplicaLogger,
         canister: &mut CanisterState,
+        snapshots_memory_usage: NumBytes,
         duration_between_blocks: Duration,
         subnet_size: usize,
     ) -> Result<(), CanisterOutOfCyclesError> {
@@ -766,7 +828,7 @@ impl CyclesAccountManager {
             MemoryAllocation::Reserved(bytes) => bytes,
             // The canister uses best-effort memory allocation, so charge based on current usage.
             MemoryAllocation::BestEffort => canister.memory_usage(self.own_subnet_type),
-        };
+        } + snapshots_memory_usage;
         if let Err(err) = self.charge_for_memory(
             &mut canister.system_state,
             bytes_to_charge,
@@ -815,6 +877,62 @@ impl CyclesAccountManager {
     }
 }
 
+/// The usage of a subnet resource relative to its threshold and capacity.
+///
+/// Allocating the resource above the threshold requires reserving cycles, see
+/// [`CyclesAccountManager::storage_reservation_cycles`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceSaturation {
+    usage: u64,
+    threshold: u64,
+    capacity: u64,
+}
+
+impl ResourceSaturation {
+    /// Creates a new `ResourceSaturation`. The threshold is capped at the
+    /// capacity and the usage is not capped.
+    pub fn new(usage: u64, threshold: u64, capacity: u64) -> Self {
+        let threshold = threshold.min(capacity);
+        Self {
+            usage,
+            threshold,
+            capacity,
+        }
+    }
+
+    /// Returns the part of the usage that is above the threshold.
+    pub fn usage_above_threshold(&self) -> u64 {
+        self.usage.saturating_sub(self.threshold)
+    }
+
+    /// Returns a copy with `delta` added to the usage.
+    pub fn add(&self, delta: u64) -> Self {
+        Self {
+            usage: self.usage.saturating_add(delta),
+            threshold: self.threshold,
+            capacity: self.capacity,
+        }
+    }
+
+    /// Returns the amount of `requested` that needs to be paid for in full:
+    /// the part of `requested` above the threshold weighted by the average
+    /// saturation between the threshold (0) and the capacity (1) over the
+    /// allocation.
+    pub fn reservation_factor(&self, requested: u64) -> u64 {
+        let before = self.usage_above_threshold();
+        let after = self.add(requested).usage_above_threshold();
+        debug_assert!(before <= after);
+        let range = self.capacity - self.threshold;
+        if range == 0 {
+            return 0;
+        }
+        // `(after - before) * (before + after) / 2` is the area under the
+        // linear saturation curve over the allocation.
+        let weighted = (after - before) as u128 * (before as u128 + after as u128) / 2;
+        (weighted / range as u128).min(u64::MAX as u128) as u64
+    }
+}
+
 /// Encapsulates the payer and cost of inducting an ingress messages.
 #[derive(Debug, Eq, PartialEq)]
 pub enum IngressInductionCost {
@@ -1,7 +1,9 @@
 use ic_base_types::NumSeconds;
 use ic_config::subnet_config::SubnetConfigs;
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
-use ic_cycles_account_manager::{IngressInductionCost, IngressInductionCostError};
+use ic_cycles_account_manager::{
+    IngressInductionCost, IngressInductionCostError, ResourceSaturation,
+};
 use ic_ic00_types::{CanisterIdRecord, Payload, IC_00};
 use ic_interfaces::execution_environment::CanisterOutOfCyclesError;
 use ic_registry_subnet_type::SubnetType;
@@ -67,6 +69,7 @@ fn test_can_charge_application_subnets() {
                         .charge_canister_for_resource_allocation_and_usage(
                             &log,
                             &mut canister,
+                            NumBytes::from(0),
                             duration,
                             subnet_size,
                         )
@@ -319,6 +322,63 @@ fn canister_charge_for_memory_until_zero_works() {
         .is_err());
 }
 
+#[test]
+fn canister_charge_for_memory_uses_reserved_balance_first() {
+    let subnet_size = SMALL_APP_SUBNET_MAX_SIZE;
+    let mut system_state = SystemStateBuilder::new().build();
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let bytes = NumBytes::from(1 << 30);
+    let fee = cycles_account_manager.memory_cost(bytes, Duration::from_secs(1), subnet_size);
+
+    system_state.reserve_cycles(fee * 2u64).unwrap();
+    let balance = system_state.balance();
+    cycles_account_manager
+        .charge_for_memory(
+            &mut system_state,
+            bytes,
+            Duration::from_secs(1),
+            subnet_size,
+        )
+        .unwrap();
+    assert_eq!(system_state.balance(), balance);
+    assert_eq!(system_state.reserved_balance(), fee);
+
+    // Only half of the fee can be paid from the reserved balance now.
+    system_state.remove_cycles_from_reserved_balance(Cycles::new(fee.get() / 2));
+    let remaining = system_state.reserved_balance();
+    cycles_account_manager
+        .charge_for_memory(
+            &mut system_state,
+            bytes,
+            Duration::from_secs(1),
+            subnet_size,
+        )
+        .unwrap();
+    assert_eq!(system_state.balance(), balance - (fee - remaining));
+    assert_eq!(system_state.reserved_balance(), Cycles::zero());
+}
+
+#[test]
+fn resource_saturation_reservation_factor() {
+    // Below the threshold nothing needs to be reserved.
+    let saturation = ResourceSaturation::new(0, 100, 200);
+    assert_eq!(saturation.reservation_factor(100), 0);
+
+    // The reservation grows quadratically above the threshold.
+    let saturation = ResourceSaturation::new(0, 0, 100);
+    assert_eq!(saturation.reservation_factor(100), 50);
+    assert_eq!(saturation.reservation_factor(50), 12);
+    assert_eq!(saturation.add(50).reservation_factor(50), 37);
+
+    // Only the part above the threshold counts.
+    let saturation = ResourceSaturation::new(50, 100, 200);
+    assert_eq!(saturation.reservation_factor(100), 12);
+
+    // No reservation if the threshold equals the capacity.
+    let saturation = ResourceSaturation::new(500, 100, 100);
+    assert_eq!(saturation.reservation_factor(100), 0);
+}
+
 #[test]
 fn ingress_induction_cost_subnet_message_with_invalid_payload() {
     let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
@@ -407,6 +467,7 @@ fn charging_removes_canisters_with_insufficient_balance() {
             .charge_canister_for_resource_allocation_and_usage(
                 &log,
                 &mut canister,
+                NumBytes::from(0),
                 Duration::from_secs(1),
                 subnet_size,
             )
@@ -425,6 +486,7 @@ fn charging_removes_canisters_with_insufficient_balance() {
             .charge_canister_for_resource_allocation_and_usage(
                 &log,
                 &mut canister,
+                NumBytes::from(0),
                 Duration::from_secs(1),
                 subnet_size,
             )
@@ -443,6 +505,7 @@ fn charging_removes_canisters_with_insufficient_balance() {
             .charge_canister_for_resource_allocation_and_usage(
                 &log,
                 &mut canister,
+                NumBytes::from(0),
                 Duration::from_secs(1),
                 subnet_size,
             )
@@ -450,6 +513,44 @@ fn charging_removes_canisters_with_insufficient_balance() {
     })
 }
 
+#[test]
+fn charging_for_resources_includes_snapshots_memory() {
+    with_test_replica_logger(|log| {
+        let subnet_size = SMALL_APP_SUBNET_MAX_SIZE;
+        let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+        let duration = Duration::from_secs(1);
+        let snapshots_memory_usage = NumBytes::from(1 << 30);
+        let snapshots_cost =
+            cycles_account_manager.memory_cost(snapshots_memory_usage, duration, subnet_size);
+
+        // The canister can pay for its own memory, but not for its snapshots.
+        let mut canister = new_canister_state(
+            canister_test_id(1),
+            canister_test_id(11).get(),
+            Cycles::new(snapshots_cost.get() / 2),
+            NumSeconds::from(0),
+        );
+        cycles_account_manager
+            .charge_canister_for_resource_allocation_and_usage(
+                &log,
+                &mut canister.clone(),
+                NumBytes::from(0),
+                duration,
+                subnet_size,
+            )
+            .unwrap();
+        cycles_account_manager
+            .charge_canister_for_resource_allocation_and_usage(
+                &log,
+                &mut canister,
+                snapshots_memory_usage,
+                duration,
+                subnet_size,
+            )
+            .unwrap_err();
+    })
+}
+
 #[test]
 fn cycles_withdraw_no_threshold() {
     let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
@@ -2,10 +2,10 @@
 
 use crate::message::{msg_stream_from_file, Message};
 use hex::encode;
-use ic_config::{subnet_config::SubnetConfigs, Config};
+use ic_config::{flag_status::FlagStatus, subnet_config::SubnetConfigs, Config};
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, UserError};
-use ic_execution_environment::ExecutionServices;
+use ic_execution_environment::{ExecutionServices, InstructionProfiles};
 use ic_interfaces::{execution_environment::IngressHistoryReader, messaging::MessageRouting};
 use ic_interfaces_state_manager::StateReader;
 use ic_messaging::MessageRoutingImpl;
@@ -40,8 +40,8 @@ use ic_types::{
 };
 use slog::{Drain, Logger};
 use std::collections::BTreeMap;
-use std::fs::OpenOptions;
-use std::path::PathBuf;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::{thread::sleep, time::Duration};
 
@@ -58,6 +58,9 @@ pub struct DrunOptions {
     pub cfg: Config,
     pub extra_batches: u64,
     pub log_file: Option<PathBuf>,
+    /// If set, instruction profiles of all executed messages are written to
+    /// this file in the folded stacks format.
+    pub instruction_profile: Option<PathBuf>,
 }
 
 /// Deliver a single message to the Message Routing layer
@@ -152,10 +155,14 @@ fn get_registry(
 pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
     let DrunOptions {
         msg_filename,
-        cfg,
+        mut cfg,
         extra_batches,
         log_file,
+        instruction_profile,
     } = uo;
+    if instruction_profile.is_some() {
+        cfg.hypervisor.instruction_profiling = FlagStatus::Enabled;
+    }
     // Hardcoded magic values to create a ReplicaConfig that parses.
     let subnet_type = SubnetType::System;
     let subnet_config = SubnetConfigs::default().own_subnet_config(subnet_type);
@@ -198,18 +205,19 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
         None,
         ic_types::malicious_flags::MaliciousFlags::default(),
     ));
+    let execution_services = ExecutionServices::setup_execution(
+        log.clone().into(),
+        &metrics_registry,
+        replica_config.subnet_id,
+        subnet_type,
+        subnet_config.scheduler_config,
+        cfg.hypervisor.clone(),
+        Arc::clone(&cycles_account_manager),
+        Arc::clone(&state_manager) as Arc<_>,
+    );
+    let instruction_profiles = execution_services.instruction_profiles.clone();
     let (_, ingress_history_writer, ingress_hist_reader, query_handler, _, _, scheduler) =
-        ExecutionServices::setup_execution(
-            log.clone().into(),
-            &metrics_registry,
-            replica_config.subnet_id,
-            subnet_type,
-            subnet_config.scheduler_config,
-            cfg.hypervisor.clone(),
-            Arc::clone(&cycles_account_manager),
-            Arc::clone(&state_manager) as Arc<_>,
-        )
-        .into_parts();
+        execution_services.into_parts();
 
     let _metrics_runtime = MetricsRuntimeImpl::new_insecure(
         tokio::runtime::Handle::current(),
@@ -270,7 +278,23 @@ pub fn run_drun(uo: DrunOptions) -> Result<(), String> {
                 );
             }
         })
-    })
+    })?;
+
+    if let Some(path) = instruction_profile {
+        write_instruction_profile(&path, &instruction_profiles)?;
+    }
+    Ok(())
+}
+
+fn write_instruction_profile(
+    path: &Path,
+    instruction_profiles: &InstructionProfiles,
+) -> Result<(), String> {
+    let mut file = File::create(path)
+        .map_err(|err| format!("Failed to create {}: {}", path.display(), err))?;
+    instruction_profiles
+        .write_folded(&mut file)
+        .map_err(|err| format!("Failed to write {}: {}", path.display(), err))
 }
 
 fn print_query_result(res: Result<WasmResult, UserError>) {
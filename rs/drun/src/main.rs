@@ -13,6 +13,7 @@ const ARG_CONF: &str = "config";
 const ARG_LOG_FILE: &str = "log-file";
 const ARG_MESSAGES: &str = "messages";
 const ARG_EXTRA_BATCHES: &str = "extra-batches";
+const ARG_INSTRUCTION_PROFILE: &str = "instruction-profile";
 
 fn main() -> Result<(), String> {
     // Check if `drun` is running in the canister sandbox mode where it waits
@@ -55,11 +56,14 @@ async fn drun_main() -> Result<(), String> {
             })
             .unwrap_or(DEFAULT_EXTRA_BATCHES);
 
+        let instruction_profile = matches.value_of(ARG_INSTRUCTION_PROFILE).map(PathBuf::from);
+
         let uo = DrunOptions {
             msg_filename: matches.value_of(ARG_MESSAGES).unwrap().to_string(),
             cfg,
             extra_batches,
             log_file,
+            instruction_profile,
         };
         run_drun(uo)
     })
@@ -108,5 +112,15 @@ fn get_arg_matches() -> ArgMatches {
                 .help("Log file for the run (default: None).")
                 .takes_value(true),
         )
+        .arg(
+            Arg::new(ARG_INSTRUCTION_PROFILE)
+                .long(ARG_INSTRUCTION_PROFILE)
+                .value_name("instruction_profile")
+                .help(
+                    "Write the instructions executed by each Wasm function to this file \
+                    in the folded stacks format (default: None).",
+                )
+                .takes_value(true),
+        )
         .get_matches()
 }
@@ -10,7 +10,6 @@ use std::{sync::Arc, time::Duration};
 pub use compilation_cache::CompilationCache;
 use ic_interfaces::execution_environment::AvailableMemory;
 use ic_replicated_state::{ExecutionState, Global, NumWasmPages, PageIndex};
-use ic_sys::PageBytes;
 use ic_system_api::{
     sandbox_safe_system_state::SandboxSafeSystemState, ApiType, ExecutionParameters,
 };
@@ -34,7 +33,7 @@ pub struct WasmExecutionInput {
 pub struct InstanceRunResult {
     pub dirty_pages: Vec<PageIndex>,
     pub stable_memory_size: NumWasmPages,
-    pub stable_memory_dirty_pages: Vec<(PageIndex, PageBytes)>,
+    pub stable_memory_dirty_pages: Vec<PageIndex>,
     pub exported_globals: Vec<Global>,
 }
 
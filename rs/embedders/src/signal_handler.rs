@@ -2,12 +2,26 @@ use crate::wasmtime_embedder::host_memory::MemoryPageSize;
 
 use memory_tracker::{AccessKind, SigsegvMemoryTracker};
 use std::convert::TryFrom;
-use std::sync::{atomic::Ordering, Arc, Mutex};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+/// A memory tracked by the SIGSEGV handler, e.g. the Wasm heap or the natively
+/// mapped stable memory.
+pub(crate) struct TrackedMemory {
+    pub(crate) tracker: Arc<Mutex<SigsegvMemoryTracker>>,
+    /// The current size of the memory in Wasm pages.
+    pub(crate) current_size_in_pages: MemoryPageSize,
+    /// If present, the handler stores the number of dirty pages of the memory
+    /// here after every fault, so that a limit on dirty pages can be enforced
+    /// while the execution is still running.
+    pub(crate) dirty_page_count: Option<Arc<AtomicUsize>>,
+}
 
 /// Helper function to create a memory tracking SIGSEGV handler function.
 pub(crate) fn sigsegv_memory_tracker_handler(
-    sigsegv_memory_tracker: Arc<Mutex<SigsegvMemoryTracker>>,
-    current_memory_size_in_pages: MemoryPageSize,
+    tracked_memories: Vec<TrackedMemory>,
 ) -> impl Fn(i32, *const libc::siginfo_t, *const libc::c_void) -> bool + Send + Sync {
     move |signum: i32, siginfo_ptr: *const libc::siginfo_t, ucontext_ptr: *const libc::c_void| {
         use nix::sys::signal::Signal;
@@ -50,31 +64,39 @@ pub(crate) fn sigsegv_memory_tracker_handler(
             return false;
         }
 
-        let sigsegv_memory_tracker = sigsegv_memory_tracker.lock().unwrap();
+        for tracked_memory in tracked_memories.iter() {
+            let sigsegv_memory_tracker = tracked_memory.tracker.lock().unwrap();
 
-        let check_if_expanded = || unsafe {
-            let page_count = current_memory_size_in_pages.load(Ordering::SeqCst) as usize;
-            let heap_size = page_count * (wasmtime_environ::WASM_PAGE_SIZE as usize);
-            let heap_start = sigsegv_memory_tracker.area().addr() as *mut libc::c_void;
-            if (heap_start <= si_addr) && (si_addr < { heap_start.add(heap_size) }) {
-                Some(heap_size)
-            } else {
-                None
-            }
-        };
+            let check_if_expanded = || unsafe {
+                let page_count =
+                    tracked_memory.current_size_in_pages.load(Ordering::SeqCst) as usize;
+                let heap_size = page_count * (wasmtime_environ::WASM_PAGE_SIZE as usize);
+                let heap_start = sigsegv_memory_tracker.area().addr() as *mut libc::c_void;
+                if (heap_start <= si_addr) && (si_addr < { heap_start.add(heap_size) }) {
+                    Some(heap_size)
+                } else {
+                    None
+                }
+            };
 
-        // We handle SIGSEGV from the Wasm module heap ourselves.
-        if sigsegv_memory_tracker.area().is_within(si_addr) {
-            // Returns true if the signal has been handled by our handler which indicates
-            // that the instance should continue.
-            sigsegv_memory_tracker.handle_sigsegv(access_kind, si_addr)
-        // The heap has expanded. Update tracked memory area.
-        } else if let Some(heap_size) = check_if_expanded() {
-            let delta = heap_size - sigsegv_memory_tracker.area().size();
-            sigsegv_memory_tracker.expand(delta);
-            true
-        } else {
-            false
+            // We handle SIGSEGV from the tracked memories ourselves.
+            if sigsegv_memory_tracker.area().is_within(si_addr) {
+                // Returns true if the signal has been handled by our handler which
+                // indicates that the instance should continue.
+                let handled = sigsegv_memory_tracker.handle_sigsegv(access_kind, si_addr);
+                if let Some(dirty_page_count) = &tracked_memory.dirty_page_count {
+                    dirty_page_count
+                        .store(sigsegv_memory_tracker.num_dirty_pages(), Ordering::SeqCst);
+                }
+                return handled;
+            }
+            // The memory has expanded. Update tracked memory area.
+            if let Some(heap_size) = check_if_expanded() {
+                let delta = heap_size - sigsegv_memory_tracker.area().size();
+                sigsegv_memory_tracker.expand(delta);
+                return true;
+            }
         }
+        false
     }
 }
@@ -12,8 +12,10 @@ use serde::{Deserialize, Serialize};
 use crate::wasm_utils::compile;
 use crate::wasm_utils::instrumentation::Segments;
 use crate::{
-    wasm_utils::decoding::decode_wasm, wasm_utils::validation::WasmImportsDetails,
-    wasmtime_embedder::WasmtimeInstance, WasmExecutionInput, WasmtimeEmbedder,
+    wasm_utils::decoding::decode_wasm,
+    wasm_utils::validation::WasmImportsDetails,
+    wasmtime_embedder::{host_memory::NativeStableMemory, WasmtimeInstance},
+    WasmExecutionInput, WasmtimeEmbedder,
 };
 use crate::{CompilationCache, CompilationResult, SerializedModule};
 use ic_config::flag_status::FlagStatus;
@@ -26,7 +28,8 @@ use ic_metrics::MetricsRegistry;
 use ic_replicated_state::{EmbedderCache, ExecutionState};
 use ic_sys::{page_bytes_from_ptr, PageBytes, PageIndex, PAGE_SIZE};
 use ic_system_api::{
-    system_api_empty::SystemApiEmpty, ExecutionParameters, ModificationTracking, SystemApiImpl,
+    stable_memory_dirty_page_limit_exceeded, system_api_empty::SystemApiEmpty, ExecutionParameters,
+    ModificationTracking, SystemApiImpl,
 };
 use ic_types::{CanisterId, NumBytes, NumInstructions};
 use ic_wasm_types::{BinaryEncodedWasm, CanisterModule};
@@ -213,6 +216,7 @@ impl WasmExecutor for WasmExecutorImpl {
                                 accessed_pages: 0,
                                 dirty_pages: 0,
                             },
+                            instruction_profile: None,
                         },
                         sandbox_safe_system_state.changes(),
                     ),
@@ -550,7 +554,7 @@ pub fn process(
 ) {
     let canister_id = sandbox_safe_system_state.canister_id();
     let modification_tracking = api_type.modification_tracking();
-    let system_api = SystemApiImpl::new(
+    let mut system_api = SystemApiImpl::new(
         api_type,
         sandbox_safe_system_state,
         canister_current_memory_usage,
@@ -561,6 +565,23 @@ pub fn process(
         logger,
     );
 
+    let stable_memory_dirty_page_limit = embedder.config().stable_memory_dirty_page_limit;
+    if modification_tracking == ModificationTracking::Track {
+        system_api.set_stable_memory_dirty_page_limit(stable_memory_dirty_page_limit);
+    }
+
+    // With native stable memory the stable memory is mapped into the address
+    // space of the process and its pages are tracked like the Wasm memory.
+    let stable_memory_region = match embedder.config().feature_flags.wasm_native_stable_memory {
+        FlagStatus::Enabled => {
+            let native_stable_memory = NativeStableMemory::new(stable_memory.size);
+            let region = native_stable_memory.region(stable_memory.page_map.clone());
+            system_api.set_mapped_stable_memory(Box::new(native_stable_memory));
+            Some(region)
+        }
+        FlagStatus::Disabled => None,
+    };
+
     let first_slice_instruction_limit = system_api.slice_instruction_limit();
 
     let mut instance = match embedder.new_instance(
@@ -569,11 +590,13 @@ pub fn process(
         globals,
         wasm_memory.size,
         wasm_memory.page_map.clone(),
+        stable_memory_region,
         modification_tracking,
         system_api,
     ) {
         Ok(instance) => instance,
-        Err((err, system_api)) => {
+        Err((err, mut system_api)) => {
+            system_api.append_trap_to_canister_log(&err);
             // TODO(RUN-269): The `num_instructions_left` should be set to
             // the limit, not zero here.
             return (
@@ -589,6 +612,7 @@ pub fn process(
                         accessed_pages: 0,
                         dirty_pages: 0,
                     },
+                    instruction_profile: None,
                 },
                 None,
                 Err(system_api),
@@ -623,6 +647,11 @@ pub fn process(
     let message_instructions_left = message_instruction_limit - message_instructions_executed;
 
     let instance_stats = instance.get_stats();
+    let instruction_profile = instance
+        .store_data_mut()
+        .instruction_profiler
+        .take()
+        .map(|profiler| profiler.finish(message_instructions_executed.get()));
 
     // Has the side effect up deallocating memory if message failed and
     // returning cycles from a request that wasn't sent.
@@ -642,7 +671,20 @@ pub fn process(
     let mut allocated_bytes = NumBytes::from(0);
     let mut allocated_message_bytes = NumBytes::from(0);
 
+    // Writes to the stable memory trap as soon as they exceed the limit on
+    // dirty pages, so this check should never fail. It is kept as a safety
+    // net in case a write path bypasses the check during execution.
     let wasm_state_changes = match run_result {
+        Ok(run_result)
+            if modification_tracking == ModificationTracking::Track
+                && run_result.stable_memory_dirty_pages.len() as u64
+                    > stable_memory_dirty_page_limit =>
+        {
+            wasm_result = Err(stable_memory_dirty_page_limit_exceeded(
+                stable_memory_dirty_page_limit,
+            ));
+            None
+        }
         Ok(run_result) => {
             match modification_tracking {
                 ModificationTracking::Track => {
@@ -654,10 +696,7 @@ pub fn process(
 
                     // Update the stable memory and serialize the delta.
                     let stable_memory_delta = stable_memory.page_map.update(
-                        &instance
-                            .store_data_mut()
-                            .system_api
-                            .stable_memory_dirty_pages(),
+                        &instance.stable_memory_page_delta(&run_result.stable_memory_dirty_pages),
                     );
                     stable_memory.size = run_result.stable_memory_size;
                     allocated_bytes = instance.store_data().system_api.get_allocated_bytes();
@@ -678,6 +717,13 @@ pub fn process(
         Err(_) => None,
     };
 
+    if let Err(err) = &wasm_result {
+        instance
+            .store_data_mut()
+            .system_api
+            .append_trap_to_canister_log(err);
+    }
+
     (
         SliceExecutionOutput {
             executed_instructions: slice_instructions_executed,
@@ -688,6 +734,7 @@ pub fn process(
             allocated_bytes,
             allocated_message_bytes,
             instance_stats,
+            instruction_profile,
         },
         wasm_state_changes,
         Ok(instance),
@@ -735,6 +782,7 @@ pub fn get_initial_globals_and_memory(
         &[],
         NumWasmPages::from(0),
         wasm_page_map.clone(),
+        None,
         ModificationTracking::Ignore,
         system_api,
     ) {
@@ -28,6 +28,7 @@ fn validate_and_instrument(
         wasm,
         instruction_cost_table,
         config.cost_to_compile_wasm_instruction,
+        config.feature_flags.instruction_profiling,
     )?;
     Ok((wasm_validation_details, instrumentation_output))
 }
@@ -93,8 +93,32 @@
 //! blocks to optimize for performance. The maximal overflow in that case is
 //! bound by the length of the longest execution path consisting of
 //! non-reentrant basic blocks.
+//!
+//! If instruction profiling is enabled, two more System API functions are
+//! inserted:
+//!
+//! ```wasm
+//! (import "__" "profile_enter" (func (;2;) (param i32)))
+//! (import "__" "profile_exit" (func (;3;) (param i32)))
+//! ```
+//!
+//! and the body of every function is wrapped into a block that calls them
+//! with the index of the function in the original module:
+//!
+//! ```wasm
+//! i32.const 5
+//! call 2
+//! block  ;; with the result type of the function
+//!   ...  ;; the original body with `profile_exit` before every `return`
+//! end
+//! i32.const 5
+//! call 3
+//! ```
+//!
+//! This allows to attribute the executed instructions to call stacks.
 
 use super::{errors::into_parity_wasm_error, wasm_module_builder::WasmModuleBuilder};
+use ic_config::flag_status::FlagStatus;
 use ic_replicated_state::canister_state::WASM_PAGE_SIZE_IN_BYTES;
 use ic_replicated_state::NumWasmPages;
 use ic_sys::{PageBytes, PageIndex, PAGE_SIZE};
@@ -105,10 +129,11 @@ use ic_wasm_types::{BinaryEncodedWasm, WasmInstrumentationError};
 use parity_wasm::builder;
 use parity_wasm::elements::{
     BlockType, BulkInstruction, ExportEntry, FuncBody, FunctionType, GlobalEntry, GlobalType,
-    InitExpr, Instruction, Instructions, Internal, Local, Module, Section, Type, ValueType,
+    ImportCountType, InitExpr, Instruction, Instructions, Internal, Local, Module, Section,
+    SimdInstruction, Type, ValueType,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryFrom;
 use std::iter::FromIterator;
 
@@ -116,7 +141,29 @@ use std::iter::FromIterator;
 enum InjectedImports {
     OutOfInstructionsFn = 0,
     UpdateAvailableMemoryFn = 1,
-    Count = 2,
+    // The profiling functions are injected only if instruction profiling is
+    // enabled.
+    ProfileEnterFn = 2,
+    ProfileExitFn = 3,
+}
+
+// Returns the number of injected functions.
+fn injected_imports_count(instruction_profiling: FlagStatus) -> u32 {
+    match instruction_profiling {
+        FlagStatus::Enabled => 4,
+        FlagStatus::Disabled => 2,
+    }
+}
+
+/// The costs of the SIMD instructions that are more expensive than the default
+/// cost of a Wasm instruction. Vector floating-point division and square root
+/// are not pipelined as well as the other vector operations, so they are
+/// charged per lane. All other SIMD instructions have the default cost.
+pub mod simd_cost {
+    pub const F32X4_DIV: u64 = 4;
+    pub const F32X4_SQRT: u64 = 4;
+    pub const F64X2_DIV: u64 = 2;
+    pub const F64X2_SQRT: u64 = 2;
 }
 
 // Converts a Wasm instruction to a string mnemonic.
@@ -169,6 +216,18 @@ impl Default for InstructionCostTable {
             0,
         );
 
+        for (instruction, cost) in [
+            (SimdInstruction::F32x4Div, simd_cost::F32X4_DIV),
+            (SimdInstruction::F32x4Sqrt, simd_cost::F32X4_SQRT),
+            (SimdInstruction::F64x2Div, simd_cost::F64X2_DIV),
+            (SimdInstruction::F64x2Sqrt, simd_cost::F64X2_SQRT),
+        ] {
+            instruction_cost.insert(
+                instruction_to_mnemonic(&Instruction::Simd(instruction)),
+                cost,
+            );
+        }
+
         Self {
             default_cost: 1,
             instruction_cost,
@@ -182,13 +241,16 @@ impl Default for InstructionCostTable {
 //   * `update_available_memory` which is called after a native `memory.grow` to
 //     check whether the canister has enough available memory according to its
 //     memory allocation.
+// If instruction profiling is enabled, it additionally injects
+// `profile_enter` and `profile_exit` which are called when a function starts
+// and finishes respectively.
 //
-// Note that these functions are injected as the first two imports, so that we
-// can increment all function indices unconditionally by two. (If they would be
-// added as the last two imports, we'd need to increment only non imported
+// Note that these functions are injected as the first imports, so that we
+// can increment all function indices unconditionally by their number. (If they
+// would be added as the last imports, we'd need to increment only non imported
 // functions, since imported functions precede all others in the function index
 // space, but this would be error-prone).
-fn inject_helper_functions(module: Module) -> Module {
+fn inject_helper_functions(module: Module, instruction_profiling: FlagStatus) -> Module {
     let mut builder = builder::from_module(module);
     let import_sig = builder.push_signature(builder::signature().build_sig());
 
@@ -217,17 +279,29 @@ fn inject_helper_functions(module: Module) -> Module {
             .build(),
     );
 
+    if instruction_profiling == FlagStatus::Enabled {
+        let import_sig =
+            builder.push_signature(builder::signature().with_param(ValueType::I32).build_sig());
+        for field in ["profile_enter", "profile_exit"] {
+            builder.push_import(
+                builder::import()
+                    .module("__")
+                    .field(field)
+                    .external()
+                    .func(import_sig)
+                    .build(),
+            );
+        }
+    }
+
+    let count = injected_imports_count(instruction_profiling);
     let mut module = builder.build();
-    // We know, we have at least two imports, because we pushed them above, now
-    // let's move them to the first two positions respectively, so that we can
-    // increase all other function indices unconditionally.
+    // We know, we have at least `count` imports, because we pushed them above,
+    // now let's move them to the first positions preserving their order, so
+    // that we can increase all other function indices unconditionally.
     let entries = module.import_section_mut().unwrap().entries_mut();
-    let last = entries.pop().unwrap();
-    debug_assert!(last.module() == "__" && last.field() == "update_available_memory");
-    entries.insert(0, last);
-    let last = entries.pop().unwrap();
-    debug_assert!(last.module() == "__" && last.field() == "out_of_instructions");
-    entries.insert(0, last);
+    let injected: Vec<_> = entries.drain(entries.len() - count as usize..).collect();
+    entries.splice(0..0, injected);
 
     debug_assert!(
         entries[InjectedImports::OutOfInstructionsFn as usize].field() == "out_of_instructions"
@@ -236,8 +310,13 @@ fn inject_helper_functions(module: Module) -> Module {
         entries[InjectedImports::UpdateAvailableMemoryFn as usize].field()
             == "update_available_memory"
     );
+    debug_assert!(
+        instruction_profiling == FlagStatus::Disabled
+            || (entries[InjectedImports::ProfileEnterFn as usize].field() == "profile_enter"
+                && entries[InjectedImports::ProfileExitFn as usize].field() == "profile_exit")
+    );
 
-    // We lift all call references by 2
+    // We lift all call references by the number of injected functions.
     for section in module.sections_mut() {
         match section {
             Section::Code(ref mut code_section) => {
@@ -245,7 +324,7 @@ fn inject_helper_functions(module: Module) -> Module {
                     let code = func_body.code_mut();
                     code.elements_mut().iter_mut().for_each(|instr| {
                         if let Instruction::Call(ref mut call_index) = instr {
-                            *call_index += InjectedImports::Count as u32;
+                            *call_index += count;
                         }
                     });
                 }
@@ -253,18 +332,18 @@ fn inject_helper_functions(module: Module) -> Module {
             Section::Export(ref mut export_section) => {
                 for export in export_section.entries_mut() {
                     if let Internal::Function(ref mut func_index) = export.internal_mut() {
-                        *func_index += InjectedImports::Count as u32;
+                        *func_index += count;
                     }
                 }
             }
             Section::Element(ref mut elements_section) => {
                 for segment in elements_section.entries_mut() {
                     for func_index in segment.members_mut() {
-                        *func_index += InjectedImports::Count as u32;
+                        *func_index += count;
                     }
                 }
             }
-            Section::Start(ref mut func_index) => *func_index += InjectedImports::Count as u32,
+            Section::Start(ref mut func_index) => *func_index += count,
             _ => {}
         }
     }
@@ -406,11 +485,13 @@ pub(super) fn instrument(
     wasm: &BinaryEncodedWasm,
     instruction_cost_table: &InstructionCostTable,
     cost_to_compile_wasm_instruction: NumInstructions,
+    instruction_profiling: FlagStatus,
 ) -> Result<InstrumentationOutput, WasmInstrumentationError> {
     let module = parity_wasm::deserialize_buffer::<Module>(wasm.as_slice()).map_err(|err| {
         WasmInstrumentationError::ParityDeserializeError(into_parity_wasm_error(err))
     })?;
-    let mut module = inject_helper_functions(module);
+    let num_imported_functions = module.import_count(ImportCountType::Function) as u32;
+    let mut module = inject_helper_functions(module, instruction_profiling);
     module = export_table(module);
     module = export_memory(module);
     module = export_mutable_globals(module);
@@ -463,6 +544,10 @@ pub(super) fn instrument(
         }
     }
 
+    if instruction_profiling == FlagStatus::Enabled {
+        inject_profiling(&mut module, num_imported_functions);
+    }
+
     let mut module = export_additional_symbols(module, &export_module_data)?;
     let exported_functions = module
         .export_section()
@@ -523,6 +608,32 @@ pub(super) fn instrument(
     })
 }
 
+/// Returns the function names from the name section of the given Wasm module.
+/// The names are used to symbolize instruction profiles, so the keys are the
+/// indices of the functions in the original module. Returns an empty map if
+/// the module does not have a valid name section.
+pub fn function_names(wasm: &BinaryEncodedWasm) -> BTreeMap<u32, String> {
+    let module = match parity_wasm::deserialize_buffer::<Module>(wasm.as_slice()) {
+        Ok(module) => module,
+        Err(_) => return BTreeMap::new(),
+    };
+    let module = match module.parse_names() {
+        Ok(module) => module,
+        Err((_, module)) => module,
+    };
+    module
+        .names_section()
+        .and_then(|names| names.functions())
+        .map(|functions| {
+            functions
+                .names()
+                .iter()
+                .map(|(index, name)| (index, name.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 // Helper function used by instrumentation to export additional symbols.
 //
 // Returns the new module or an error if a symbol is not reserved.
@@ -745,6 +856,88 @@ fn inject_update_available_memory(func_body: &mut FuncBody, func_type: &Function
     }
 }
 
+// Wraps the body of every function defined in the module into a block that is
+// preceded by a call to `profile_enter` and followed by a call to
+// `profile_exit`. Since `return` leaves the function without reaching the end
+// of the block, `profile_exit` is called before every `return` too. The
+// functions are identified by their index in the original module, which is
+// the index used by the name section.
+//
+// Branches to the outermost label of the function keep working because they
+// now target the wrapping block, which has the same result type.
+fn inject_profiling(module: &mut Module, num_imported_functions: u32) {
+    let results: Vec<Vec<ValueType>> = match (module.function_section(), module.type_section()) {
+        (Some(functions), Some(types)) => functions
+            .entries()
+            .iter()
+            .map(|func| {
+                let Type::Function(t) = &types.types()[func.type_ref() as usize];
+                t.results().to_vec()
+            })
+            .collect(),
+        _ => return,
+    };
+    // A block with multiple results needs a type without parameters, because
+    // the block does not consume any values from the stack. Such types are
+    // appended to the type section unless they already exist.
+    let types = module.type_section_mut().unwrap().types_mut();
+    let block_types: Vec<BlockType> = results
+        .into_iter()
+        .map(|results| match results.as_slice() {
+            [] => BlockType::NoResult,
+            [result] => BlockType::Value(*result),
+            _ => {
+                let block_type = Type::Function(FunctionType::new(vec![], results.clone()));
+                let index = match types.iter().position(|t| *t == block_type) {
+                    Some(index) => index,
+                    None => {
+                        types.push(block_type);
+                        types.len() - 1
+                    }
+                };
+                BlockType::TypeIndex(index as u32)
+            }
+        })
+        .collect();
+    let code_section = match module.code_section_mut() {
+        Some(code_section) => code_section,
+        None => return,
+    };
+    for (func_ix, (func_body, block_type)) in code_section
+        .bodies_mut()
+        .iter_mut()
+        .zip(block_types.into_iter())
+        .enumerate()
+    {
+        let func_index = Instruction::I32Const((num_imported_functions + func_ix as u32) as i32);
+        let mut orig_elems = std::mem::take(func_body.code_mut().elements_mut());
+        // Drop the `end` of the function, it is added back after the block.
+        orig_elems.pop();
+        let mut elems: Vec<Instruction> = Vec::with_capacity(orig_elems.len() + 8);
+        elems.extend_from_slice(&[
+            func_index.clone(),
+            Instruction::Call(InjectedImports::ProfileEnterFn as u32),
+            Instruction::Block(block_type),
+        ]);
+        for instr in orig_elems {
+            if let Instruction::Return = instr {
+                elems.extend_from_slice(&[
+                    func_index.clone(),
+                    Instruction::Call(InjectedImports::ProfileExitFn as u32),
+                ]);
+            }
+            elems.push(instr);
+        }
+        elems.extend_from_slice(&[
+            Instruction::End,
+            func_index,
+            Instruction::Call(InjectedImports::ProfileExitFn as u32),
+            Instruction::End,
+        ]);
+        *func_body.code_mut().elements_mut() = elems;
+    }
+}
+
 // This function scans through the Wasm code and creates an injection point
 // at the beginning of every basic block (straight-line sequence of instructions
 // with no branches) and before each bulk memory instruction. An injection point
@@ -804,17 +997,26 @@ fn injections(
     res
 }
 
-// Looks for the data section and if it is present, converts it to a vector of
-// tuples (heap offset, bytes) and then deletes the section.
+// Looks for the data section and if it is present, converts its active segments
+// to a vector of tuples (heap offset, bytes). The passive segments of the bulk
+// memory proposal are kept in the section because they are referenced by the
+// `memory.init` and `data.drop` instructions. The section is deleted if it
+// contains no passive segments.
 fn get_data(sections: &mut Vec<Section>) -> Segments {
     let mut res = Segments::default();
     let mut data_section_idx = sections.len();
     for (i, section) in sections.iter_mut().enumerate() {
         if let Section::Data(section) = section {
-            data_section_idx = i;
+            // Active segments are replaced by empty segments instead of being
+            // removed to preserve the indices of the passive segments.
+            let has_passive_segments = section.entries().iter().any(|s| s.passive());
+            if !has_passive_segments {
+                data_section_idx = i;
+            }
             res = section
                 .entries_mut()
                 .iter_mut()
+                .filter(|segment| !segment.passive())
                 .map(|segment| {
                     let offset = match segment.offset() {
                         None => panic!("no offset found for the data segment"),
@@ -3,7 +3,7 @@
 
 use super::errors::into_parity_wasm_error;
 
-use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_replicated_state::canister_state::execution_state::{
     CustomSection, CustomSectionType, WasmMetadata,
 };
@@ -289,6 +289,16 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 },
             )],
         ),
+        (
+            "call_with_best_effort_response",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I32],
+                    return_type: vec![],
+                },
+            )],
+        ),
         (
             "call_cycles_add",
             vec![(
@@ -520,6 +530,36 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 },
             )],
         ),
+        (
+            "is_controller",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I32, ValueType::I32],
+                    return_type: vec![ValueType::I32],
+                },
+            )],
+        ),
+        (
+            "canister_version",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![],
+                    return_type: vec![ValueType::I64],
+                },
+            )],
+        ),
+        (
+            "in_replicated_execution",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![],
+                    return_type: vec![ValueType::I32],
+                },
+            )],
+        ),
         (
             "mint_cycles",
             vec![(
@@ -530,6 +570,16 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 },
             )],
         ),
+        (
+            "global_timer_set",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I64],
+                    return_type: vec![ValueType::I64],
+                },
+            )],
+        ),
         (
             "call_cycles_add128",
             vec![(
@@ -621,6 +671,13 @@ fn get_valid_exported_functions() -> HashMap<String, FunctionSignature> {
                 return_type: vec![],
             },
         ),
+        (
+            "canister_composite_query",
+            FunctionSignature {
+                param_types: vec![],
+                return_type: vec![],
+            },
+        ),
         (
             "canister_pre_upgrade",
             FunctionSignature {
@@ -649,6 +706,20 @@ fn get_valid_exported_functions() -> HashMap<String, FunctionSignature> {
                 return_type: vec![],
             },
         ),
+        (
+            "canister_global_timer",
+            FunctionSignature {
+                param_types: vec![],
+                return_type: vec![],
+            },
+        ),
+        (
+            "canister_on_low_wasm_memory",
+            FunctionSignature {
+                param_types: vec![],
+                return_type: vec![],
+            },
+        ),
     ];
 
     valid_exported_functions
@@ -770,8 +841,8 @@ fn validate_import_section(module: &Module) -> Result<WasmImportsDetails, WasmVa
 }
 
 // Performs the following checks:
-// * Validates signatures of exported canister_update and canister_query
-//   methods.
+// * Validates signatures of exported canister_update, canister_query and
+//   canister_composite_query methods.
 // * Validates the signatures of other allowed exported functions (like
 //   `canister_init` or `canister_pre_upgrade`) if present.
 // * Validates that the canister doesn't export any reserved symbols
@@ -795,15 +866,17 @@ fn validate_export_section(module: &Module) -> Result<usize, WasmValidationError
                 let mut func_name = export.field();
                 // func_name holds either:
                 // - the entire exported non-IC function names, or
-                // - canister_query or canister_update part in case of the IC functions.
+                // - canister_query, canister_composite_query or canister_update part in
+                //   case of the IC functions.
                 if func_name.starts_with("canister_query ")
+                    || func_name.starts_with("canister_composite_query ")
                     || func_name.starts_with("canister_update ")
                 {
                     let parts: Vec<&str> = func_name.splitn(2, ' ').collect();
                     let unmangled_func_name = parts[1];
                     if seen_funcs.contains(unmangled_func_name) {
                         return Err(WasmValidationError::InvalidExportSection(format!(
-                            "Duplicate function '{}' exported for both update calls and queries.",
+                            "Duplicate function '{}' exported multiple times with different call types: update, query, or composite_query.",
                             unmangled_func_name
                         )));
                     }
@@ -851,9 +924,9 @@ fn validate_export_section(module: &Module) -> Result<usize, WasmValidationError
 fn validate_data_section(module: &Module) -> Result<(), WasmValidationError> {
     fn validate_segment(s: &DataSegment) -> Result<(), WasmValidationError> {
         match s.offset() {
-            None => Err(WasmValidationError::InvalidDataSection(
-                "Empty offset in data segment.".to_string(),
-            )),
+            // Passive segments of the bulk memory proposal have no offset. They
+            // are copied into the memory explicitly using `memory.init`.
+            None => Ok(()),
             Some(expr) => match expr.code() {
                 [Instruction::I32Const(_), Instruction::End] => Ok(()),
                 _ => Err(WasmValidationError::InvalidDataSection(
@@ -874,7 +947,9 @@ fn validate_data_section(module: &Module) -> Result<(), WasmValidationError> {
         .try_for_each(validate_segment)
 }
 
-// Checks that no more than `max_globals` are defined in the module.
+// Checks that no more than `max_globals` are defined in the module and that
+// none of the mutable globals has the `v128` type. Mutable globals are
+// persisted between messages and only scalar values are supported there.
 fn validate_global_section(module: &Module, max_globals: usize) -> Result<(), WasmValidationError> {
     if let Some(section) = module.global_section() {
         let globals_defined = section.entries().len();
@@ -884,6 +959,15 @@ fn validate_global_section(module: &Module, max_globals: usize) -> Result<(), Wa
                 allowed: max_globals,
             });
         }
+        for (index, global) in section.entries().iter().enumerate() {
+            let global_type = global.global_type();
+            if global_type.is_mutable() && global_type.content_type() == ValueType::V128 {
+                return Err(WasmValidationError::InvalidGlobalSection(format!(
+                    "Mutable global {} has type v128 which is not supported.",
+                    index
+                )));
+            }
+        }
     }
     Ok(())
 }
@@ -1048,16 +1132,25 @@ fn validate_code_section(module: &Module) -> Result<NumInstructions, WasmValidat
 }
 
 /// Sets Wasmtime flags to ensure deterministic execution.
-pub fn ensure_determinism(config: &mut Config) {
+///
+/// The NaN canonicalization also applies to the lanes of the floating-point
+/// SIMD instructions, so enabling SIMD does not introduce non-determinism.
+/// The relaxed SIMD instructions are not supported by the Wasmtime version
+/// we use and therefore remain rejected.
+pub fn ensure_determinism(config: &mut Config, embedders_config: &EmbeddersConfig) {
     config
         .wasm_threads(false)
-        .wasm_simd(false)
+        .wasm_simd(embedders_config.feature_flags.wasm_simd == FlagStatus::Enabled)
+        .wasm_bulk_memory(true)
         .cranelift_nan_canonicalization(true);
 }
 
-fn can_compile(wasm: &BinaryEncodedWasm) -> Result<(), WasmValidationError> {
+fn can_compile(
+    wasm: &BinaryEncodedWasm,
+    embedders_config: &EmbeddersConfig,
+) -> Result<(), WasmValidationError> {
     let mut config = wasmtime::Config::default();
-    ensure_determinism(&mut config);
+    ensure_determinism(&mut config, embedders_config);
     let engine = wasmtime::Engine::new(&config).map_err(|_| {
         WasmValidationError::WasmtimeValidation(String::from("Failed to initialize Wasm engine"))
     })?;
@@ -1113,7 +1206,7 @@ pub(super) fn validate_wasm_binary(
     wasm: &BinaryEncodedWasm,
     config: &EmbeddersConfig,
 ) -> Result<WasmValidationDetails, WasmValidationError> {
-    can_compile(wasm)?;
+    can_compile(wasm, config)?;
     let module = parity_wasm::deserialize_buffer::<Module>(wasm.as_slice())
         .map_err(|err| WasmValidationError::ParityDeserializeError(into_parity_wasm_error(err)))?;
     let imports_details = validate_import_section(&module)?;
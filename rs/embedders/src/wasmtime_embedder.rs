@@ -6,7 +6,7 @@ pub mod system_api_complexity;
 use std::{
     collections::HashMap,
     convert::TryFrom,
-    sync::{Arc, Mutex},
+    sync::{atomic::Ordering, Arc, Mutex},
 };
 
 use ic_system_api::ModificationTracking;
@@ -15,12 +15,13 @@ use wasmtime::{unix::StoreExt, Engine, Memory, Module, Mutability, OptLevel, Sto
 use host_memory::MmapMemoryCreator;
 pub use host_memory::WasmtimeMemoryCreator;
 use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::flag_status::FlagStatus;
 use ic_interfaces::execution_environment::{
-    HypervisorError, HypervisorResult, InstanceStats, SystemApi, TrapCode,
+    HypervisorError, HypervisorResult, InstanceStats, InstructionProfile, SystemApi, TrapCode,
 };
 use ic_logger::{debug, error, fatal, ReplicaLogger};
 use ic_replicated_state::{EmbedderCache, Global, NumWasmPages, PageIndex, PageMap};
-use ic_sys::PAGE_SIZE;
+use ic_sys::{page_bytes_from_ptr, PageBytes, PAGE_SIZE};
 use ic_types::{
     methods::{FuncRef, WasmMethod},
     CanisterId,
@@ -29,11 +30,14 @@ use ic_wasm_types::{BinaryEncodedWasm, WasmEngineError};
 use memory_tracker::{DirtyPageTracking, SigsegvMemoryTracker};
 use signal_stack::WasmtimeSignalStack;
 
-use crate::{serialized_module::SerializedModuleBytes, wasm_utils::validation::ensure_determinism};
+use crate::{
+    serialized_module::SerializedModuleBytes, signal_handler::TrackedMemory,
+    wasm_utils::validation::ensure_determinism,
+};
 
 use super::InstanceRunResult;
 
-use self::host_memory::{MemoryPageSize, MemoryStart};
+use self::host_memory::{MemoryPageSize, MemoryStart, StableMemoryRegion};
 
 #[cfg(test)]
 mod wasmtime_embedder_tests;
@@ -126,7 +130,7 @@ impl WasmtimeEmbedder {
     fn create_engine(&self) -> HypervisorResult<Engine> {
         let mut config = wasmtime::Config::default();
         config.cranelift_opt_level(OptLevel::None);
-        ensure_determinism(&mut config);
+        ensure_determinism(&mut config, &self.config);
         let raw_creator = MmapMemoryCreator {};
         let mem_creator = Arc::new(WasmtimeMemoryCreator::new(
             raw_creator,
@@ -187,6 +191,7 @@ impl WasmtimeEmbedder {
         exported_globals: &[Global],
         heap_size: NumWasmPages,
         page_map: PageMap,
+        stable_memory: Option<StableMemoryRegion>,
         modification_tracking: ModificationTracking,
         system_api: S,
     ) -> Result<WasmtimeInstance<S>, (HypervisorError, S)> {
@@ -199,6 +204,10 @@ impl WasmtimeEmbedder {
             StoreData {
                 system_api,
                 num_instructions_global: None,
+                instruction_profiler: match self.config.feature_flags.instruction_profiling {
+                    FlagStatus::Enabled => Some(InstructionProfiler::default()),
+                    FlagStatus::Disabled => None,
+                },
             },
         );
 
@@ -306,6 +315,8 @@ impl WasmtimeEmbedder {
             ModificationTracking::Track => DirtyPageTracking::Track,
         };
 
+        let mut tracked_memories = vec![];
+
         // if `wasmtime::Instance` does not have memory we don't need a memory tracker
         let memory_tracker = match instance_memory {
             None => None,
@@ -329,22 +340,63 @@ impl WasmtimeEmbedder {
                             store.into_data().system_api,
                         ));
                     }
-                    Some(current_memory_size_in_pages) => Some(sigsegv_memory_tracker(
-                        &instance_memory,
-                        current_memory_size_in_pages,
-                        &mut store,
-                        page_map,
-                        self.log.clone(),
-                        dirty_page_tracking,
-                    )),
+                    Some(current_memory_size_in_pages) => {
+                        let memory_tracker = sigsegv_memory_tracker(
+                            instance_memory.data_ptr(&store),
+                            instance_memory.data_size(&store),
+                            page_map,
+                            self.log.clone(),
+                            dirty_page_tracking,
+                            true,
+                        );
+                        tracked_memories.push(TrackedMemory {
+                            tracker: Arc::clone(&memory_tracker),
+                            current_size_in_pages: current_memory_size_in_pages,
+                            dirty_page_count: None,
+                        });
+                        Some(memory_tracker)
+                    }
                 }
             }
         };
+
+        // The mapped stable memory is tracked in the same way as the Wasm memory.
+        let stable_memory_tracker = stable_memory.map(|region| {
+            let size =
+                region.used.load(Ordering::SeqCst) * wasmtime_environ::WASM_PAGE_SIZE as usize;
+            let stable_memory_tracker = sigsegv_memory_tracker(
+                region.start.0 as *mut u8,
+                size,
+                region.page_map,
+                self.log.clone(),
+                dirty_page_tracking,
+                // The limit on dirty pages is enforced based on the pages that
+                // fault on write. Without prefetching this number does not
+                // depend on how the memory is backed, which may differ
+                // between replicas.
+                false,
+            );
+            tracked_memories.push(TrackedMemory {
+                tracker: Arc::clone(&stable_memory_tracker),
+                current_size_in_pages: region.used,
+                dirty_page_count: Some(region.dirty_pages),
+            });
+            stable_memory_tracker
+        });
+
+        if !tracked_memories.is_empty() {
+            let handler = crate::signal_handler::sigsegv_memory_tracker_handler(tracked_memories);
+            // http://man7.org/linux/man-pages/man7/signal-safety.7.html
+            unsafe {
+                store.set_signal_handler(handler);
+            };
+        }
         let signal_stack = WasmtimeSignalStack::new();
 
         Ok(WasmtimeInstance {
             instance,
             memory_tracker,
+            stable_memory_tracker,
             signal_stack,
             log: self.log.clone(),
             instance_stats: InstanceStats {
@@ -370,58 +422,104 @@ struct StoreRef(*mut wasmtime::Store<()>);
 unsafe impl Sync for StoreRef {}
 unsafe impl Send for StoreRef {}
 
-fn sigsegv_memory_tracker<S>(
-    instance_memory: &wasmtime::Memory,
-    current_memory_size_in_pages: MemoryPageSize,
-    store: &mut wasmtime::Store<S>,
+fn sigsegv_memory_tracker(
+    base: *mut u8,
+    size: usize,
     page_map: PageMap,
     log: ReplicaLogger,
     dirty_page_tracking: DirtyPageTracking,
+    prefetching: bool,
 ) -> Arc<Mutex<SigsegvMemoryTracker>> {
-    let base = instance_memory.data_ptr(&store);
-    let size = instance_memory.data_size(&store);
-
-    let sigsegv_memory_tracker = {
-        // For both SIGSEGV and in the future UFFD memory tracking we need
-        // the base address of the heap and its size
-        let base = base as *mut libc::c_void;
-        if base as usize % PAGE_SIZE != 0 {
-            fatal!(log, "[EXC-BUG] Memory tracker - Heap must be page aligned.");
-        }
-        if size % PAGE_SIZE != 0 {
-            fatal!(
-                log,
-                "[EXC-BUG] Memory tracker - Heap size must be a multiple of page size."
-            );
-        }
+    // For both SIGSEGV and in the future UFFD memory tracking we need
+    // the base address of the heap and its size
+    let base = base as *mut libc::c_void;
+    if base as usize % PAGE_SIZE != 0 {
+        fatal!(log, "[EXC-BUG] Memory tracker - Heap must be page aligned.");
+    }
+    if size % PAGE_SIZE != 0 {
+        fatal!(
+            log,
+            "[EXC-BUG] Memory tracker - Heap size must be a multiple of page size."
+        );
+    }
 
-        Arc::new(Mutex::new(
-            SigsegvMemoryTracker::new(base, size, log, dirty_page_tracking, page_map)
-                .expect("failed to instantiate SIGSEGV memory tracker"),
-        ))
+    let tracker = SigsegvMemoryTracker::new(base, size, log, dirty_page_tracking, page_map)
+        .expect("failed to instantiate SIGSEGV memory tracker");
+    let tracker = if prefetching {
+        tracker
+    } else {
+        tracker.without_prefetching()
     };
+    Arc::new(Mutex::new(tracker))
+}
 
-    let handler = crate::signal_handler::sigsegv_memory_tracker_handler(
-        Arc::clone(&sigsegv_memory_tracker),
-        current_memory_size_in_pages,
-    );
-    // http://man7.org/linux/man-pages/man7/signal-safety.7.html
-    unsafe {
-        store.set_signal_handler(handler);
-    };
-    sigsegv_memory_tracker
+/// Returns the pages modified since the last call, excluding the speculatively
+/// dirty pages that turned out to be unchanged.
+fn tracked_dirty_pages(memory_tracker: &Arc<Mutex<SigsegvMemoryTracker>>) -> Vec<PageIndex> {
+    let memory_tracker = memory_tracker.lock().unwrap();
+    let speculatively_dirty_pages = memory_tracker.take_speculatively_dirty_pages();
+    let dirty_pages = memory_tracker.take_dirty_pages();
+    dirty_pages
+        .into_iter()
+        .chain(speculatively_dirty_pages.into_iter())
+        .filter_map(|p| memory_tracker.validate_speculatively_dirty_page(p))
+        .collect::<Vec<PageIndex>>()
 }
 
 /// Additional types that need to be owned by the `wasmtime::Store`.
 pub struct StoreData<S> {
     pub system_api: S,
     pub num_instructions_global: Option<wasmtime::Global>,
+    /// Present only if instruction profiling is enabled.
+    pub instruction_profiler: Option<InstructionProfiler>,
+}
+
+/// Attributes the instructions executed by a message to the Wasm call stack.
+/// The instrumented code notifies the profiler whenever a function starts or
+/// finishes together with the number of instructions executed so far.
+#[derive(Default)]
+pub struct InstructionProfiler {
+    stack: Vec<u32>,
+    // The number of instructions executed at the last change of the stack.
+    last_instructions_executed: u64,
+    profile: InstructionProfile,
+}
+
+impl InstructionProfiler {
+    /// Records that the function with the given index has started.
+    pub fn enter(&mut self, func_index: u32, instructions_executed: u64) {
+        self.attribute(instructions_executed);
+        self.stack.push(func_index);
+    }
+
+    /// Records that the function with the given index has finished.
+    pub fn exit(&mut self, func_index: u32, instructions_executed: u64) {
+        self.attribute(instructions_executed);
+        debug_assert_eq!(self.stack.last(), Some(&func_index));
+        self.stack.pop();
+    }
+
+    /// Attributes the remaining instructions to the current stack, which is
+    /// not empty if the execution trapped, and returns the profile.
+    pub fn finish(mut self, instructions_executed: u64) -> InstructionProfile {
+        self.attribute(instructions_executed);
+        self.profile
+    }
+
+    fn attribute(&mut self, instructions_executed: u64) {
+        let instructions = instructions_executed.saturating_sub(self.last_instructions_executed);
+        self.profile.add(&self.stack, instructions);
+        self.last_instructions_executed =
+            self.last_instructions_executed.max(instructions_executed);
+    }
 }
 
 /// Encapsulates a Wasmtime instance on the Internet Computer.
 pub struct WasmtimeInstance<S: SystemApi> {
     instance: wasmtime::Instance,
     memory_tracker: Option<Arc<Mutex<SigsegvMemoryTracker>>>,
+    /// Present only if the stable memory is mapped natively.
+    stable_memory_tracker: Option<Arc<Mutex<SigsegvMemoryTracker>>>,
     signal_stack: WasmtimeSignalStack,
     log: ReplicaLogger,
     instance_stats: InstanceStats,
@@ -457,14 +555,7 @@ impl<S: SystemApi> WasmtimeInstance<S> {
 
     fn dirty_pages(&self) -> Vec<PageIndex> {
         if let Some(memory_tracker) = self.memory_tracker.as_ref() {
-            let memory_tracker = memory_tracker.lock().unwrap();
-            let speculatively_dirty_pages = memory_tracker.take_speculatively_dirty_pages();
-            let dirty_pages = memory_tracker.take_dirty_pages();
-            dirty_pages
-                .into_iter()
-                .chain(speculatively_dirty_pages.into_iter())
-                .filter_map(|p| memory_tracker.validate_speculatively_dirty_page(p))
-                .collect::<Vec<PageIndex>>()
+            tracked_dirty_pages(memory_tracker)
         } else {
             debug!(
                 self.log,
@@ -531,14 +622,21 @@ impl<S: SystemApi> WasmtimeInstance<S> {
         self.instance_stats.accessed_pages += num_accessed_pages;
         self.instance_stats.dirty_pages += dirty_pages.len();
 
-        let stable_memory_dirty_pages: Vec<_> = self
-            .store
-            .data()
-            .system_api
-            .stable_memory_dirty_pages()
-            .into_iter()
-            .map(|(i, p)| (i, *p))
-            .collect();
+        let stable_memory_dirty_pages: Vec<_> = match self.stable_memory_tracker.as_ref() {
+            Some(stable_memory_tracker) => {
+                self.instance_stats.accessed_pages +=
+                    stable_memory_tracker.lock().unwrap().num_accessed_pages();
+                tracked_dirty_pages(stable_memory_tracker)
+            }
+            None => self
+                .store
+                .data()
+                .system_api
+                .stable_memory_dirty_pages()
+                .into_iter()
+                .map(|(i, _)| i)
+                .collect(),
+        };
         let stable_memory_size =
             NumWasmPages::from(self.store.data().system_api.stable_memory_size());
         self.instance_stats.dirty_pages += stable_memory_dirty_pages.len();
@@ -615,6 +713,32 @@ impl<S: SystemApi> WasmtimeInstance<S> {
             .unwrap_or_else(|_| std::ptr::null())
     }
 
+    /// Returns the contents of the given dirty pages of the stable memory.
+    pub fn stable_memory_page_delta(
+        &self,
+        dirty_pages: &[PageIndex],
+    ) -> Vec<(PageIndex, &PageBytes)> {
+        match self.stable_memory_tracker.as_ref() {
+            Some(stable_memory_tracker) => {
+                let stable_memory_addr = stable_memory_tracker.lock().unwrap().area().addr();
+                dirty_pages
+                    .iter()
+                    .map(|page_index| {
+                        // SAFETY: All dirty pages are mapped and remain valid for the
+                        // lifetime of `self`. Since this function is called after Wasm
+                        // execution, the dirty pages are not borrowed as mutable.
+                        let page_ref = unsafe {
+                            let offset = page_index.get() as usize * PAGE_SIZE;
+                            page_bytes_from_ptr(self, (stable_memory_addr + offset) as *const u8)
+                        };
+                        (*page_index, page_ref)
+                    })
+                    .collect()
+            }
+            None => self.store.data().system_api.stable_memory_dirty_pages(),
+        }
+    }
+
     /// Returns execution statistics for this instance.
     ///
     /// Note that stats must be available even if this instance trapped.
@@ -5,7 +5,10 @@ use wasmtime_environ::{WASM32_MAX_PAGES, WASM_PAGE_SIZE};
 use crate::ICMemoryCreator;
 use crate::LinearMemory;
 
+use ic_replicated_state::{NumWasmPages, PageMap};
 use ic_sys::PAGE_SIZE;
+use ic_system_api::MappedStableMemory;
+use ic_types::MAX_STABLE_MEMORY_IN_BYTES;
 
 use libc::c_void;
 use libc::MAP_FAILED;
@@ -36,7 +39,7 @@ fn wasm_max_mem_size_in_bytes() -> usize {
 #[derive(Hash, PartialEq, Eq)]
 pub(crate) struct MemoryStart(pub(crate) usize);
 
-pub(crate) struct MemoryPageSize(Arc<AtomicUsize>);
+pub(crate) struct MemoryPageSize(pub(crate) Arc<AtomicUsize>);
 
 impl Deref for MemoryPageSize {
     type Target = Arc<AtomicUsize>;
@@ -243,3 +246,80 @@ unsafe impl<M: LinearMemory + Send + Sync + 'static> wasmtime::LinearMemory for
         self.mem.as_ptr() as *mut _
     }
 }
+
+/// The stable memory of a canister mapped into the address space of the
+/// process next to the Wasm memory. It is not a linear memory of the Wasm
+/// module: the canister still accesses it through the `ic0.stable*` system
+/// calls, which copy directly between the two mappings instead of going
+/// through the `PageMap`. The maximum size of the stable memory is reserved
+/// upfront, so the mapping never moves when the stable memory grows. The
+/// embedder tracks the accessed and modified pages of the mapping with a
+/// `SigsegvMemoryTracker` in the same way as it does for the Wasm memory.
+pub struct NativeStableMemory {
+    mem: MmapMemory,
+    used: MemoryPageSize,
+    dirty_pages: Arc<AtomicUsize>,
+}
+
+impl NativeStableMemory {
+    pub fn new(size: NumWasmPages) -> Self {
+        Self {
+            mem: MmapMemory::new(MAX_STABLE_MEMORY_IN_BYTES as usize, 0),
+            used: MemoryPageSize(Arc::new(AtomicUsize::new(size.get()))),
+            dirty_pages: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Returns the description of the mapping that the embedder needs to
+    /// track the pages backed by the given `PageMap`.
+    pub fn region(&self, page_map: PageMap) -> StableMemoryRegion {
+        StableMemoryRegion {
+            start: MemoryStart(self.mem.as_ptr() as usize),
+            used: MemoryPageSize(Arc::clone(&self.used)),
+            dirty_pages: Arc::clone(&self.dirty_pages),
+            page_map,
+        }
+    }
+}
+
+impl MappedStableMemory for NativeStableMemory {
+    fn as_slice(&self) -> &[u8] {
+        // SAFETY: The mapping reserves `MAX_STABLE_MEMORY_IN_BYTES` and lives as
+        // long as `self`. Pages that are not accessible yet are made accessible by
+        // the signal handler of the memory tracker on the first access.
+        unsafe {
+            std::slice::from_raw_parts(
+                self.mem.as_ptr() as *const u8,
+                MAX_STABLE_MEMORY_IN_BYTES as usize,
+            )
+        }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: See `as_slice()`.
+        unsafe {
+            std::slice::from_raw_parts_mut(
+                self.mem.as_ptr() as *mut u8,
+                MAX_STABLE_MEMORY_IN_BYTES as usize,
+            )
+        }
+    }
+
+    fn grow_to(&mut self, size: NumWasmPages) {
+        self.used.store(size.get(), Ordering::SeqCst);
+    }
+
+    fn num_dirty_pages(&self) -> usize {
+        self.dirty_pages.load(Ordering::SeqCst)
+    }
+}
+
+/// Describes the mapped stable memory to the embedder, which sets up the
+/// tracking of its pages when instantiating a module.
+pub struct StableMemoryRegion {
+    pub(crate) start: MemoryStart,
+    pub(crate) used: MemoryPageSize,
+    /// Updated by the signal handler of the memory tracker.
+    pub(crate) dirty_pages: Arc<AtomicUsize>,
+    pub(crate) page_map: PageMap,
+}
@@ -6,7 +6,7 @@ use ic_interfaces::execution_environment::{
 };
 use ic_logger::{error, ReplicaLogger};
 use ic_registry_subnet_type::SubnetType;
-use ic_types::{CanisterId, Cycles, NumBytes, NumInstructions};
+use ic_types::{CanisterId, Cycles, NumBytes, NumInstructions, Time};
 
 use wasmtime::{AsContextMut, Caller, Global, Linker, Store, Trap, Val};
 
@@ -608,6 +608,28 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "is_controller", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, src: i32, size: i32| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::IS_CONTROLLER,
+                        memory: (size as u64).into(),
+                        disk: 0.into(),
+                        network: 0.into(),
+                    },
+                )?;
+                with_memory_and_system_api(caller, |system_api, memory| {
+                    system_api.ic0_is_controller(src as u32, size as u32, memory)
+                })
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("ic0", "debug_print", {
             let log = log.clone();
@@ -625,21 +647,20 @@ pub(crate) fn syscalls<S: SystemApi>(
                         network: (length as u64).into(),
                     },
                 )?;
-                match (
-                    caller.data().system_api.subnet_type(),
-                    rate_limiting_of_debug_prints,
-                ) {
-                    // Debug print is a no-op on non-system subnets with rate limiting.
-                    (SubnetType::Application, FlagStatus::Enabled) => Ok(()),
-                    (SubnetType::VerifiedApplication, FlagStatus::Enabled) => Ok(()),
-                    // If rate limiting is disabled or the subnet is a system subnet, then
-                    // debug print produces output.
-                    (_, FlagStatus::Disabled) | (SubnetType::System, FlagStatus::Enabled) => {
-                        with_memory_and_system_api(caller, |system_api, memory| {
+                with_memory_and_system_api(caller, |system_api, memory| {
+                    // The message always ends up in the canister's log.
+                    system_api.save_log_message(offset as u32, length as u32, memory);
+                    match (system_api.subnet_type(), rate_limiting_of_debug_prints) {
+                        // Debug print is a no-op on non-system subnets with rate limiting.
+                        (SubnetType::Application, FlagStatus::Enabled) => Ok(()),
+                        (SubnetType::VerifiedApplication, FlagStatus::Enabled) => Ok(()),
+                        // If rate limiting is disabled or the subnet is a system subnet, then
+                        // debug print produces output.
+                        (_, FlagStatus::Disabled) | (SubnetType::System, FlagStatus::Enabled) => {
                             system_api.ic0_debug_print(offset as u32, length as u32, memory)
-                        })
+                        }
                     }
-                }
+                })
             }
         })
         .unwrap();
@@ -662,6 +683,7 @@ pub(crate) fn syscalls<S: SystemApi>(
                     },
                 )?;
                 with_memory_and_system_api(caller, |system_api, memory| {
+                    system_api.save_log_message(offset as u32, length as u32, memory);
                     system_api.ic0_trap(offset as u32, length as u32, memory)
                 })
             }
@@ -791,6 +813,17 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "call_with_best_effort_response", {
+            move |mut caller: Caller<'_, StoreData<S>>, timeout_seconds: i32| {
+                with_system_api(&mut caller, |s| {
+                    s.ic0_call_with_best_effort_response(timeout_seconds as u32)
+                })
+                .map_err(|e| process_err(caller, e))
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("ic0", "call_cycles_add", {
             move |mut caller: Caller<'_, StoreData<S>>, amount: i64| {
@@ -1195,6 +1228,38 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
+    // The profiling functions are imported only by modules instrumented with
+    // instruction profiling enabled.
+    linker
+        .func_wrap("__", "profile_enter", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, func_index: u32| -> Result<(), _> {
+                // Counter type 0 is the number of instructions executed by the
+                // message so far.
+                let instructions =
+                    ic0_performance_counter_helper(&log, canister_id, &mut caller, 0)?;
+                if let Some(profiler) = caller.data_mut().instruction_profiler.as_mut() {
+                    profiler.enter(func_index, instructions);
+                }
+                Ok(())
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("__", "profile_exit", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, func_index: u32| -> Result<(), _> {
+                let instructions =
+                    ic0_performance_counter_helper(&log, canister_id, &mut caller, 0)?;
+                if let Some(profiler) = caller.data_mut().instruction_profiler.as_mut() {
+                    profiler.exit(func_index, instructions);
+                }
+                Ok(())
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("__", "update_available_memory", {
             move |mut caller: Caller<'_, StoreData<S>>,
@@ -1217,6 +1282,29 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "canister_version", {
+            move |mut caller: Caller<'_, StoreData<S>>| {
+                with_system_api(&mut caller, |s| s.ic0_canister_version())
+                    .map_err(|e| process_err(caller, e))
+                    .and_then(|s| {
+                        i64::try_from(s).map_err(|e| {
+                            wasmtime::Trap::new(format!("ic0_canister_version failed: {}", e))
+                        })
+                    })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "in_replicated_execution", {
+            move |mut caller: Caller<'_, StoreData<S>>| {
+                with_system_api(&mut caller, |s| s.ic0_in_replicated_execution())
+                    .map_err(|e| process_err(caller, e))
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("ic0", "certified_data_set", {
             let log = log.clone();
@@ -1292,5 +1380,17 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "global_timer_set", {
+            move |mut caller: Caller<'_, StoreData<S>>, time: u64| {
+                with_system_api(&mut caller, |s| {
+                    s.ic0_global_timer_set(Time::from_nanos_since_unix_epoch(time))
+                })
+                .map_err(|e| process_err(caller, e))
+                .map(|s| s.as_nanos_since_unix_epoch())
+            }
+        })
+        .unwrap();
+
     linker
 }
@@ -84,6 +84,7 @@ pub mod cpu {
     pub const MSG_REJECT: NumInstructions = from_nanos(20);
     pub const CANISTER_SELF_COPY: NumInstructions = from_nanos(60);
     pub const CONTROLLER_COPY: NumInstructions = from_nanos(60);
+    pub const IS_CONTROLLER: NumInstructions = from_nanos(60);
     pub const DEBUG_PRINT: NumInstructions = from_nanos(30);
     pub const TRAP: NumInstructions = from_nanos(1_000);
     pub const CALL_SIMPLE: NumInstructions = from_nanos(1_000);
@@ -4,6 +4,7 @@ use super::{system_api, StoreData, NUM_INSTRUCTION_GLOBAL_NAME};
 use crate::{wasm_utils::validate_and_instrument_for_testing, WasmtimeEmbedder};
 use ic_config::embedders::Config as EmbeddersConfig;
 use ic_config::flag_status::FlagStatus;
+use ic_cycles_account_manager::ResourceSaturation;
 use ic_interfaces::execution_environment::{AvailableMemory, ExecutionMode};
 use ic_logger::replica_logger::no_op_logger;
 use ic_registry_subnet_type::SubnetType;
@@ -54,6 +55,7 @@ fn test_wasmtime_system_api() {
             compute_allocation: ComputeAllocation::default(),
             subnet_type: SubnetType::Application,
             execution_mode: ExecutionMode::Replicated,
+            subnet_memory_saturation: ResourceSaturation::default(),
         },
         *MAX_SUBNET_AVAILABLE_MEMORY,
         Memory::default(),
@@ -65,6 +67,7 @@ fn test_wasmtime_system_api() {
         StoreData {
             system_api,
             num_instructions_global: None,
+            instruction_profiler: None,
         },
     );
 
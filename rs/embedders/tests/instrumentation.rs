@@ -1,4 +1,5 @@
 use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::flag_status::FlagStatus;
 use ic_embedders::{
     wasm_utils::{
         instrumentation::{export_additional_symbols, ExportModuleData, Segments},
@@ -11,7 +12,7 @@ use ic_logger::replica_logger::no_op_logger;
 use ic_sys::{PageIndex, PAGE_SIZE};
 use ic_wasm_types::BinaryEncodedWasm;
 use insta::assert_snapshot;
-use parity_wasm::elements::{self, Module, Section};
+use parity_wasm::elements::{self, BlockType, Instruction, Module, Section};
 use pretty_assertions::assert_eq;
 use std::fs;
 use wabt::{wat2wasm, Features};
@@ -217,3 +218,117 @@ fn test_exports_only_reserved_symbols() {
         assert!(RESERVED_SYMBOLS.contains(&export.field()))
     }
 }
+
+#[test]
+fn instruction_profiling_wraps_function_bodies() {
+    let wasm = wat2wasm(
+        r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func $test
+                (call $msg_reply)
+                (return)
+            )
+            (export "canister_update test" (func $test))
+        )"#,
+    )
+    .map(BinaryEncodedWasm::new)
+    .unwrap();
+    let mut config = EmbeddersConfig::default();
+    config.feature_flags.instruction_profiling = FlagStatus::Enabled;
+    let output =
+        validate_and_instrument_for_testing(&WasmtimeEmbedder::new(config, no_op_logger()), &wasm)
+            .expect("couldn't instrument Wasm code")
+            .1;
+    let module: Module = parity_wasm::elements::deserialize_buffer(output.binary.as_slice())
+        .expect("couldn't deserialize module");
+
+    let imports: Vec<_> = module
+        .import_section()
+        .unwrap()
+        .entries()
+        .iter()
+        .map(|entry| (entry.module(), entry.field()))
+        .collect();
+    assert_eq!(
+        imports,
+        vec![
+            ("__", "out_of_instructions"),
+            ("__", "update_available_memory"),
+            ("__", "profile_enter"),
+            ("__", "profile_exit"),
+            ("ic0", "msg_reply"),
+        ]
+    );
+
+    // The function is reported with its index in the original module.
+    let code = module.code_section().unwrap().bodies()[0].code().elements();
+    assert_eq!(
+        &code[0..3],
+        &[
+            Instruction::I32Const(1),
+            Instruction::Call(2),
+            Instruction::Block(BlockType::NoResult),
+        ]
+    );
+    let return_position = code
+        .iter()
+        .position(|instruction| *instruction == Instruction::Return)
+        .unwrap();
+    assert_eq!(
+        &code[return_position - 2..return_position],
+        &[Instruction::I32Const(1), Instruction::Call(3)]
+    );
+    assert_eq!(
+        &code[code.len() - 4..],
+        &[
+            Instruction::End,
+            Instruction::I32Const(1),
+            Instruction::Call(3),
+            Instruction::End,
+        ]
+    );
+}
+
+#[test]
+fn instruction_profiling_uses_block_type_without_params_for_multiple_results() {
+    let mut features = Features::new();
+    features.enable_multi_value();
+    let wasm = wabt::wat2wasm_with_features(
+        r#"
+        (module
+            (func $pair (param i32) (result i32 i32)
+                (local.get 0)
+                (local.get 0)
+            )
+            (func $test
+                (drop (drop (call $pair (i32.const 1))))
+            )
+            (export "canister_update test" (func $test))
+        )"#,
+        features,
+    )
+    .map(BinaryEncodedWasm::new)
+    .unwrap();
+    let mut config = EmbeddersConfig::default();
+    config.feature_flags.instruction_profiling = FlagStatus::Enabled;
+    let output =
+        validate_and_instrument_for_testing(&WasmtimeEmbedder::new(config, no_op_logger()), &wasm)
+            .expect("couldn't instrument Wasm code")
+            .1;
+    let module: Module = parity_wasm::elements::deserialize_buffer(output.binary.as_slice())
+        .expect("couldn't deserialize module");
+
+    let code = module.code_section().unwrap().bodies()[0].code().elements();
+    let type_index = match code[2] {
+        Instruction::Block(BlockType::TypeIndex(type_index)) => type_index,
+        ref instruction => panic!("Unexpected instruction {:?}", instruction),
+    };
+    let elements::Type::Function(block_type) =
+        &module.type_section().unwrap().types()[type_index as usize];
+    assert!(block_type.params().is_empty());
+    assert_eq!(
+        block_type.results(),
+        &[elements::ValueType::I32, elements::ValueType::I32]
+    );
+}
@@ -1,5 +1,5 @@
 use assert_matches::assert_matches;
-use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_embedders::{
     wasm_utils::{
         validate_and_instrument_for_testing,
@@ -19,6 +19,12 @@ fn wat2wasm(wat: &str) -> Result<BinaryEncodedWasm, wabt::Error> {
     features.enable_multi_value();
     wabt::wat2wasm_with_features(wat, features).map(BinaryEncodedWasm::new)
 }
+
+fn wat2wasm_with_simd(wat: &str) -> Result<BinaryEncodedWasm, wabt::Error> {
+    let mut features = wabt::Features::new();
+    features.enable_simd();
+    wabt::wat2wasm_with_features(wat, features).map(BinaryEncodedWasm::new)
+}
 use ic_replicated_state::canister_state::execution_state::{
     CustomSection, CustomSectionType, WasmMetadata,
 };
@@ -88,6 +94,8 @@ fn can_validate_valid_export_section() {
                   (func $x)
                   (export "canister_init" (func $x))
                   (export "canister_heartbeat" (func $x))
+                  (export "canister_global_timer" (func $x))
+                  (export "canister_on_low_wasm_memory" (func $x))
                   (export "canister_pre_upgrade" (func $x))
                   (export "canister_post_upgrade" (func $x))
                   (export "canister_query read" (func $x)))"#,
@@ -184,6 +192,34 @@ fn can_validate_canister_heartbeat_with_invalid_params() {
     );
 }
 
+#[test]
+fn can_validate_canister_global_timer_with_invalid_params() {
+    let wasm = wat2wasm(
+        r#"(module
+                  (func $x (param $y i32))
+                  (export "canister_global_timer" (func $x)))"#,
+    )
+    .unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::InvalidFunctionSignature(_))
+    );
+}
+
+#[test]
+fn can_validate_canister_on_low_wasm_memory_with_invalid_params() {
+    let wasm = wat2wasm(
+        r#"(module
+                  (func $x (param $y i32))
+                  (export "canister_on_low_wasm_memory" (func $x)))"#,
+    )
+    .unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::InvalidFunctionSignature(_))
+    );
+}
+
 #[test]
 fn can_validate_canister_pre_upgrade_with_invalid_return() {
     let wasm = wat2wasm(
@@ -269,6 +305,52 @@ fn can_validate_duplicate_method_for_canister_query_and_canister_update() {
     );
 }
 
+#[test]
+fn can_validate_valid_canister_composite_query() {
+    let wasm = wat2wasm(
+        r#"(module
+                    (func $read)
+                    (export "canister_composite_query read" (func $read)))"#,
+    )
+    .unwrap();
+    assert_eq!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Ok(WasmValidationDetails {
+            largest_function_instruction_count: NumInstructions::new(1),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn can_validate_invalid_canister_composite_query() {
+    let wasm = wat2wasm(
+        r#"(module
+                    (func $read (param i64 i32) (result i32) (local.get 1))
+                    (export "canister_composite_query read" (func $read)))"#,
+    )
+    .unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::InvalidFunctionSignature(_))
+    );
+}
+
+#[test]
+fn can_validate_duplicate_method_for_canister_query_and_canister_composite_query() {
+    let wasm = wat2wasm(
+        r#"(module
+                    (func $read)
+                    (export "canister_query read" (func $read))
+                    (export "canister_composite_query read" (func $read)))"#,
+    )
+    .unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::InvalidExportSection(_))
+    );
+}
+
 #[test]
 fn can_validate_canister_query_update_method_name_with_whitespace() {
     let wasm = wat2wasm(
@@ -325,6 +407,74 @@ fn can_validate_invalid_offset_expression_in_data_section() {
     );
 }
 
+#[test]
+fn can_validate_passive_data_segment() {
+    let mut features = wabt::Features::new();
+    features.enable_bulk_memory();
+    let wasm = wabt::wat2wasm_with_features(
+        r#"
+                (module
+                    (memory (;0;) 1)
+                    (func (export "canister_update test")
+                        (memory.init 0 (i32.const 0) (i32.const 0) (i32.const 4))
+                        (data.drop 0)
+                    )
+                    (data "abcd")
+                )
+            "#,
+        features,
+    )
+    .map(BinaryEncodedWasm::new)
+    .unwrap();
+    assert!(validate_wasm_binary(&wasm, &EmbeddersConfig::default()).is_ok());
+}
+
+const SIMD_WAT: &str = r#"
+        (module
+            (func (export "canister_update test") (result v128)
+                (f32x4.div
+                    (v128.const f32x4 1.0 2.0 3.0 4.0)
+                    (v128.const f32x4 0.0 0.0 0.0 0.0))
+                (f32x4.sqrt)
+            )
+        )
+    "#;
+
+#[test]
+fn can_reject_simd_if_disabled() {
+    let wasm = wat2wasm_with_simd(SIMD_WAT).unwrap();
+    assert_matches!(
+        validate_wasm_binary(&wasm, &EmbeddersConfig::default()),
+        Err(WasmValidationError::WasmtimeValidation(_))
+    );
+}
+
+#[test]
+fn can_validate_simd_if_enabled() {
+    let wasm = wat2wasm_with_simd(SIMD_WAT).unwrap();
+    let mut config = EmbeddersConfig::default();
+    config.feature_flags.wasm_simd = FlagStatus::Enabled;
+    assert!(validate_wasm_binary(&wasm, &config).is_ok());
+}
+
+#[test]
+fn can_reject_mutable_v128_global() {
+    let wasm = wat2wasm_with_simd(
+        r#"
+                (module
+                    (global (mut v128) (v128.const i32x4 0 0 0 0))
+                )
+            "#,
+    )
+    .unwrap();
+    let mut config = EmbeddersConfig::default();
+    config.feature_flags.wasm_simd = FlagStatus::Enabled;
+    assert_matches!(
+        validate_wasm_binary(&wasm, &config),
+        Err(WasmValidationError::InvalidGlobalSection(_))
+    );
+}
+
 #[test]
 fn can_validate_module_with_import_func() {
     // Accepts `msg_reply` from ic0 module.
@@ -1,5 +1,6 @@
 use ic_config::embedders::Config;
 use ic_config::flag_status::FlagStatus;
+use ic_cycles_account_manager::ResourceSaturation;
 use ic_embedders::wasm_utils::compile;
 use ic_embedders::WasmtimeEmbedder;
 use ic_interfaces::execution_environment::{AvailableMemory, ExecutionMode};
@@ -76,6 +77,7 @@ fn test_api_for_update(
             compute_allocation: ComputeAllocation::default(),
             subnet_type: SubnetType::Application,
             execution_mode: ExecutionMode::Replicated,
+            subnet_memory_saturation: ResourceSaturation::default(),
         },
         *MAX_SUBNET_AVAILABLE_MEMORY,
         Memory::default(),
@@ -230,6 +232,7 @@ mod tests {
                         &[],
                         NumWasmPages::from(0),
                         page_map.clone(),
+                        None,
                         modification_tracking,
                         api,
                     )
@@ -378,6 +381,7 @@ mod tests {
                     &[],
                     NumWasmPages::from(0),
                     PageMap::default(),
+                    None,
                     ModificationTracking::Ignore,
                     api,
                 )
@@ -598,6 +602,7 @@ mod tests {
                 &[],
                 NumWasmPages::from(0),
                 PageMap::default(),
+                None,
                 ModificationTracking::Track,
                 api,
             )
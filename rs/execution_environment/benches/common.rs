@@ -3,6 +3,7 @@
 ///
 use criterion::{BatchSize, Criterion};
 use ic_config::flag_status::FlagStatus;
+use ic_cycles_account_manager::ResourceSaturation;
 use ic_error_types::RejectCode;
 use ic_execution_environment::{as_round_instructions, CompilationCostHandling, RoundLimits};
 use ic_interfaces::execution_environment::{AvailableMemory, ExecutionMode, SubnetAvailableMemory};
@@ -21,6 +22,7 @@ use ic_test_utilities::{
 use ic_types::{
     messages::{CallbackId, Payload, RejectContext},
     methods::{Callback, WasmClosure},
+    time::NO_DEADLINE,
     Cycles, MemoryAllocation, NumBytes, NumInstructions, Time,
 };
 use ic_wasm_types::CanisterModule;
@@ -84,8 +86,11 @@ where
         MemoryAllocation::try_from(NumBytes::from(0)).unwrap();
 
     // Create call context and callback
-    let call_origin =
-        CallOrigin::CanisterUpdate(canister_test_id(REMOTE_CANISTER_ID), CallbackId::new(0));
+    let call_origin = CallOrigin::CanisterUpdate(
+        canister_test_id(REMOTE_CANISTER_ID),
+        CallbackId::new(0),
+        NO_DEADLINE,
+    );
     let call_context_id = canister_state
         .system_state
         .call_context_manager_mut()
@@ -99,6 +104,7 @@ where
         WasmClosure::new(0, 1),
         WasmClosure::new(0, 1),
         None,
+        NO_DEADLINE,
     );
 
     // Create an Ingress message
@@ -129,6 +135,7 @@ where
         compute_allocation: canister_state.scheduler_state.compute_allocation,
         subnet_type: SubnetType::Application,
         execution_mode: ExecutionMode::Replicated,
+        subnet_memory_saturation: ResourceSaturation::default(),
     };
 
     BenchmarkArgs {
@@ -1,6 +1,10 @@
-use crate::execution::install_code::{InstallCodeRoutineResult, PausedInstallCodeRoutine};
+use crate::execution::install_code::{
+    InstallCodeRoutineError, InstallCodeRoutineResult, PausedInstallCodeRoutine,
+};
 use crate::execution::{install::execute_install, upgrade::execute_upgrade};
-use crate::execution_environment::{CompilationCostHandling, RoundContext, RoundLimits};
+use crate::execution_environment::{
+    as_round_instructions, CompilationCostHandling, RoundContext, RoundLimits,
+};
 use crate::{
     canister_settings::CanisterSettings,
     hypervisor::Hypervisor,
@@ -10,23 +14,28 @@ use crate::{
 use candid::Decode;
 use ic_base_types::NumSeconds;
 use ic_config::flag_status::FlagStatus;
-use ic_cycles_account_manager::CyclesAccountManager;
+use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CanisterIdRecord, CanisterInstallMode, CanisterStatusResultV2, CanisterStatusType,
-    InstallCodeArgs, Method as Ic00Method, SetControllerArgs, UpdateSettingsArgs,
+    CanisterIdRecord, CanisterInfoResponse, CanisterInstallMode, CanisterSnapshotArgs,
+    CanisterSnapshotResponse, CanisterStatusResultV2, CanisterStatusType,
+    FetchCanisterLogsResponse, InstallCodeArgs, ListCanisterSnapshotsResponse, LogVisibility,
+    Method as Ic00Method, SetControllerArgs, TakeCanisterSnapshotArgs, UpdateSettingsArgs,
+    MAX_CANISTER_SNAPSHOTS,
 };
 use ic_interfaces::execution_environment::{
     CanisterOutOfCyclesError, HypervisorError, IngressHistoryWriter,
 };
+use ic_interfaces::messages::CanisterInputMessage;
 use ic_logger::{error, fatal, info, warn, ReplicaLogger};
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    CallOrigin, CanisterState, CanisterStatus, NetworkTopology, ReplicatedState, SchedulerState,
-    SystemState,
+    canister_state::execution_state::WasmBinary, CallOrigin, CanisterSnapshot, CanisterState,
+    CanisterStatus, CanisterTimer, ExecutionState, ExecutionTask, NetworkTopology, ReplicatedState,
+    ReservationError, SchedulerState, SnapshotId, SystemState,
 };
-use ic_state_layout::{CanisterLayout, CheckpointLayout, RwPolicy};
+use ic_state_layout::{CanisterLayout, CheckpointLayout, RwPolicy, SnapshotLayout};
 use ic_system_api::ExecutionParameters;
 use ic_types::nominal_cycles::NominalCycles;
 use ic_types::{
@@ -42,11 +51,16 @@ use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::{collections::BTreeSet, convert::TryFrom, str::FromStr, sync::Arc};
 
+/// The number of instructions charged for every 4 KiB page of a new canister
+/// snapshot, i.e. one instruction per byte that has to be persisted.
+const INSTRUCTIONS_PER_SNAPSHOT_PAGE: NumInstructions = NumInstructions::new(4_096);
+
 #[derive(Debug, PartialEq, Eq)]
 pub(crate) struct InstallCodeResult {
     pub heap_delta: NumBytes,
     pub old_wasm_hash: Option<[u8; 32]>,
     pub new_wasm_hash: Option<[u8; 32]>,
+    pub mode: CanisterInstallMode,
 }
 
 /// The result of executing a single slice of `install_code` message (i.e
@@ -57,7 +71,6 @@ pub(crate) struct InstallCodeResult {
 ///   with some changes such charging of execution cycles.
 /// * If exection did not complete, then the result contains the old canister state,
 ///   with some changes such reservation of execution cycles and a continuation.
-#[allow(dead_code)]
 #[derive(Debug)]
 pub(crate) enum DtsInstallCodeResult {
     Finished {
@@ -88,6 +101,7 @@ pub(crate) enum StopCanisterResult {
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 pub(crate) struct CanisterMgrConfig {
     pub(crate) subnet_memory_capacity: NumBytes,
+    pub(crate) subnet_memory_threshold: NumBytes,
     pub(crate) default_provisional_cycles_balance: Cycles,
     pub(crate) default_freeze_threshold: NumSeconds,
     pub(crate) compute_capacity: u64,
@@ -101,6 +115,7 @@ impl CanisterMgrConfig {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         subnet_memory_capacity: NumBytes,
+        subnet_memory_threshold: NumBytes,
         default_provisional_cycles_balance: Cycles,
         default_freeze_threshold: NumSeconds,
         own_subnet_id: SubnetId,
@@ -112,6 +127,7 @@ impl CanisterMgrConfig {
     ) -> Self {
         Self {
             subnet_memory_capacity,
+            subnet_memory_threshold,
             default_provisional_cycles_balance,
             default_freeze_threshold,
             own_subnet_id,
@@ -334,7 +350,8 @@ impl CanisterManager {
             // "DepositCycles" can be called by anyone however as ingress message
             // cannot carry cycles, it does not make sense to allow them from users.
             | Ok(Ic00Method::DepositCycles)
-            | Ok(Ic00Method::HttpRequest) => only_canisters_allowed(),
+            | Ok(Ic00Method::HttpRequest)
+            | Ok(Ic00Method::CanisterInfo) => only_canisters_allowed(),
 
             // These methods are only valid if they are sent by the controller
             // of the canister. We assume that the canister always wants to
@@ -343,7 +360,8 @@ impl CanisterManager {
             | Ok(Ic00Method::StartCanister)
             | Ok(Ic00Method::UninstallCode)
             | Ok(Ic00Method::StopCanister)
-            | Ok(Ic00Method::DeleteCanister) => match Decode!(payload, CanisterIdRecord) {
+            | Ok(Ic00Method::DeleteCanister)
+            | Ok(Ic00Method::ListCanisterSnapshots) => match Decode!(payload, CanisterIdRecord) {
                 Err(e) => failed_to_decode(&e),
                 Ok(args) => is_sender_controller(args.get_canister_id()),
             },
@@ -359,6 +377,30 @@ impl CanisterManager {
                 Err(e) => failed_to_decode(&e),
                 Ok(args) => is_sender_controller(args.get_canister_id()),
             },
+            Ok(Ic00Method::TakeCanisterSnapshot) => match Decode!(payload, TakeCanisterSnapshotArgs) {
+                Err(e) => failed_to_decode(&e),
+                Ok(args) => is_sender_controller(args.get_canister_id()),
+            },
+            Ok(Ic00Method::LoadCanisterSnapshot)
+            | Ok(Ic00Method::DeleteCanisterSnapshot) => match Decode!(payload, CanisterSnapshotArgs) {
+                Err(e) => failed_to_decode(&e),
+                Ok(args) => is_sender_controller(args.get_canister_id()),
+            },
+            // Logs can be read by anyone if the canister made them public.
+            Ok(Ic00Method::FetchCanisterLogs) => match Decode!(payload, CanisterIdRecord) {
+                Err(e) => failed_to_decode(&e),
+                Ok(args) => {
+                    let canister_id = args.get_canister_id();
+                    match state.canister_state(&canister_id) {
+                        Some(canister)
+                            if canister.system_state.log_visibility == LogVisibility::Public =>
+                        {
+                            Ok(())
+                        }
+                        _ => is_sender_controller(canister_id),
+                    }
+                }
+            },
 
             // Nobody pays for `raw_rand`, so this cannot be used via ingress messages
             Ok(Ic00Method::RawRand) => only_canisters_allowed(),
@@ -465,6 +507,23 @@ impl CanisterManager {
         if let Some(freezing_threshold) = settings.freezing_threshold {
             canister.system_state.freeze_threshold = freezing_threshold;
         }
+        if let Some(log_visibility) = settings.log_visibility {
+            canister.system_state.log_visibility = log_visibility;
+        }
+        if let Some(wasm_memory_limit) = settings.wasm_memory_limit {
+            canister.system_state.wasm_memory_limit = match wasm_memory_limit.get() {
+                0 => None,
+                _ => Some(wasm_memory_limit),
+            };
+        }
+        if let Some(wasm_memory_threshold) = settings.wasm_memory_threshold {
+            canister.system_state.wasm_memory_threshold = wasm_memory_threshold;
+        }
+        if let Some(reserved_cycles_limit) = settings.reserved_cycles_limit {
+            canister
+                .system_state
+                .set_reserved_balance_limit(Some(reserved_cycles_limit));
+        }
     }
 
     /// Tries to apply the requested settings on the canister identified by
@@ -489,10 +548,26 @@ impl CanisterManager {
             canister,
             settings.memory_allocation(),
         )?;
+        self.validate_wasm_memory_limit(canister, settings.wasm_memory_limit())?;
+        let reservation_cycles = self.memory_allocation_reservation_cycles(
+            canister,
+            settings.memory_allocation(),
+            total_subnet_memory_taken,
+        );
+        self.validate_reserved_cycles(
+            canister,
+            reservation_cycles,
+            settings.reserved_cycles_limit(),
+        )?;
 
         let validated_settings =
             ValidatedCanisterSettings::try_from((settings, self.config.max_controllers))?;
         self.do_update_settings(validated_settings, canister);
+        canister
+            .system_state
+            .reserve_cycles(reservation_cycles)
+            .expect("the reservation has been validated");
+        canister.system_state.canister_version += 1;
 
         Ok(())
     }
@@ -603,7 +678,7 @@ impl CanisterManager {
                 paused_execution,
             } => {
                 unreachable!(
-                    "Unexpected paused execution: {:?}. DTS is not enabled yet",
+                    "Unexpected paused execution: {:?}. Tests using this helper run without DTS",
                     paused_execution
                 );
             }
@@ -712,6 +787,8 @@ impl CanisterManager {
 
         // Copy bits out of context as the calls below are going to consume it.
         let mode = context.mode;
+        let initial_balance = canister.system_state.balance();
+        let initial_reserved_balance = canister.system_state.reserved_balance();
 
         let round = RoundContext {
             network_topology,
@@ -750,6 +827,8 @@ impl CanisterManager {
                 result,
             } => finish_install_code(
                 canister,
+                initial_balance,
+                initial_reserved_balance,
                 message_instruction_limit,
                 instructions_left,
                 result,
@@ -761,6 +840,8 @@ impl CanisterManager {
             InstallCodeRoutineResult::Paused { paused_execution } => {
                 let paused_execution = PausedInstallCodeExecution {
                     paused_routine: paused_execution,
+                    initial_balance,
+                    initial_reserved_balance,
                     message_instruction_limit,
                     mode,
                     canister_layout_path,
@@ -960,6 +1041,7 @@ impl CanisterManager {
                     compute_allocation,
                 )
                 .get(),
+            canister.system_state.reserved_balance().get(),
         ))
     }
 
@@ -978,7 +1060,17 @@ impl CanisterManager {
             .canister_state_mut(&canister_id)
             .ok_or(CanisterManagerError::CanisterNotFound(canister_id))?;
 
-        let settings = CanisterSettings::new(Some(new_controller), None, None, None, None);
+        let settings = CanisterSettings::new(
+            Some(new_controller),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
         self.update_settings(
             sender,
             settings,
@@ -1074,11 +1166,299 @@ impl CanisterManager {
             .mark_deleted()
             .expect("failed to mark canister as deleted on the filesystem");
 
+        // Snapshots of the canister are deleted together with it.
+        remove_canister_snapshots(state, canister_id_to_delete);
+
         // The canister has now been removed from `ReplicatedState` and is dropped
         // once the function is out of scope.
         Ok(())
     }
 
+    /// Takes a snapshot of the Wasm module, memories and certified data of a
+    /// canister, so that they can be restored later with
+    /// `load_canister_snapshot`. Only the controllers of the canister can take
+    /// snapshots of it.
+    ///
+    /// If `replace_snapshot` is set, the given snapshot of the canister is
+    /// deleted once the new one has been taken and does not count towards the
+    /// limit of snapshots per canister.
+    ///
+    /// The canister must be stopped. Taking the snapshot costs
+    /// `INSTRUCTIONS_PER_SNAPSHOT_PAGE` instructions per page of its memories,
+    /// which are charged to the canister and to the round.
+    pub(crate) fn take_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        args: TakeCanisterSnapshotArgs,
+        state: &mut ReplicatedState,
+        max_instructions: NumInstructions,
+        round_limits: &mut RoundLimits,
+    ) -> Result<CanisterSnapshotResponse, CanisterManagerError> {
+        let canister_id = args.get_canister_id();
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        self.validate_controller(canister, &sender)?;
+        if canister.status() != CanisterStatusType::Stopped {
+            return Err(CanisterManagerError::TakeCanisterSnapshotNotStopped(
+                canister_id,
+            ));
+        }
+        // A paused or aborted execution may still change the memories that
+        // are about to be captured.
+        if canister.has_paused_or_aborted_execution() {
+            return Err(CanisterManagerError::TakeCanisterSnapshotExecutionInProgress(canister_id));
+        }
+
+        let replace_snapshot = match args.replace_snapshot() {
+            Some(snapshot_id) => {
+                Some(self.validate_snapshot_id(state, canister_id, snapshot_id)?)
+            }
+            None => None,
+        };
+
+        let mut snapshot_count = state.canister_snapshots.count_snapshots(canister_id);
+        let mut memory_taken = state.total_memory_taken();
+        let mut snapshots_memory_taken = state.canister_snapshots.memory_taken(canister_id);
+        if let Some(snapshot_id) = &replace_snapshot {
+            // The snapshot that is replaced is deleted, so it doesn't count
+            // towards the limits.
+            let replaced_size = state.canister_snapshots.get(snapshot_id).unwrap().size();
+            snapshot_count -= 1;
+            memory_taken -= replaced_size;
+            snapshots_memory_taken -= replaced_size;
+        }
+        if snapshot_count >= MAX_CANISTER_SNAPSHOTS {
+            return Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
+                canister_id,
+                limit: MAX_CANISTER_SNAPSHOTS,
+            });
+        }
+
+        let snapshot = CanisterSnapshot::from_canister(canister, state.time()).ok_or(
+            CanisterManagerError::Hypervisor(canister_id, HypervisorError::WasmModuleNotFound),
+        )?;
+        let snapshot_size = snapshot.size();
+        let instructions = INSTRUCTIONS_PER_SNAPSHOT_PAGE
+            * (snapshot.wasm_memory.page_map.num_host_pages()
+                + snapshot.stable_memory.page_map.num_host_pages()) as u64;
+        if instructions > max_instructions {
+            return Err(
+                CanisterManagerError::TakeCanisterSnapshotInstructionLimitExceeded {
+                    canister_id,
+                    instructions,
+                    limit: max_instructions,
+                },
+            );
+        }
+        if snapshot_size + memory_taken > self.config.subnet_memory_capacity {
+            return Err(CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                requested: snapshot_size,
+                available: NumBytes::from(
+                    self.config
+                        .subnet_memory_capacity
+                        .get()
+                        .saturating_sub(memory_taken.get()),
+                ),
+            });
+        }
+
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        let execution_cycles = self.cycles_account_manager.execution_cost(instructions);
+        self.reserve_cycles_for_snapshot(
+            canister,
+            snapshot_size,
+            snapshots_memory_taken,
+            memory_taken,
+            execution_cycles,
+        )?;
+        let memory_usage = canister.memory_usage(self.config.own_subnet_type);
+        let compute_allocation = canister.compute_allocation();
+        self.cycles_account_manager
+            .consume_cycles(
+                &mut canister.system_state,
+                memory_usage,
+                compute_allocation,
+                execution_cycles,
+            )
+            .map_err(|err| CanisterManagerError::InsufficientCyclesForSnapshot {
+                canister_id,
+                available: err.available,
+                required: err.requested,
+            })?;
+        round_limits.instructions -= as_round_instructions(instructions);
+
+        if let Some(snapshot_id) = replace_snapshot {
+            remove_canister_snapshot(state, snapshot_id);
+        }
+
+        let snapshot_id = SnapshotId::new(canister_id, state.metadata.next_snapshot_id);
+        state.metadata.next_snapshot_id += 1;
+        let response = CanisterSnapshotResponse::new(
+            snapshot_id.to_bytes(),
+            snapshot.taken_at_timestamp.as_nanos_since_unix_epoch(),
+            snapshot_size,
+        );
+        state
+            .canister_snapshots
+            .push(snapshot_id, Arc::new(snapshot));
+        // The snapshot's files are written at the next checkpoint.
+        state.metadata.heap_delta_estimate += snapshot_size;
+
+        Ok(response)
+    }
+
+    /// Replaces the Wasm module, memories and certified data of a canister
+    /// with the ones stored in the given snapshot of the canister. Only the
+    /// controllers of the canister can load its snapshots and the canister
+    /// must be stopped.
+    pub(crate) fn load_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        args: CanisterSnapshotArgs,
+        state: &mut ReplicatedState,
+    ) -> Result<(), CanisterManagerError> {
+        let canister_id = args.get_canister_id();
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        self.validate_controller(canister, &sender)?;
+        let snapshot_id = self.validate_snapshot_id(state, canister_id, args.snapshot_id())?;
+        let snapshot = Arc::clone(state.canister_snapshots.get(&snapshot_id).unwrap());
+        if canister.status() != CanisterStatusType::Stopped {
+            return Err(CanisterManagerError::LoadCanisterSnapshotNotStopped(
+                canister_id,
+            ));
+        }
+        // A paused or aborted execution refers to the execution state that is
+        // about to be replaced.
+        if canister.has_paused_or_aborted_execution() {
+            return Err(CanisterManagerError::LoadCanisterSnapshotExecutionInProgress(canister_id));
+        }
+
+        let path = state.path().to_owned();
+        // The snapshot's Wasm module is copied into memory, so that it is
+        // written to the canister's directory at the next checkpoint rather
+        // than shared with the snapshot's directory.
+        let execution_state = ExecutionState::new(
+            canister_layout(&path, &canister_id).raw_path(),
+            WasmBinary::new(CanisterModule::new(snapshot.binary.as_slice().to_vec())),
+            snapshot.exports.clone(),
+            snapshot.restore_wasm_memory(),
+            snapshot.restore_stable_memory(),
+            snapshot.exported_globals.clone(),
+            snapshot.metadata.clone(),
+        );
+        self.validate_memory_usage_after_load(
+            canister,
+            execution_state.memory_usage(),
+            state.total_memory_taken(),
+        )?;
+
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        canister.execution_state = Some(execution_state);
+        canister.system_state.certified_data = snapshot.certified_data.clone();
+        // The global timer was set by the code that is replaced.
+        canister.system_state.global_timer = CanisterTimer::Inactive;
+        canister.system_state.canister_version += 1;
+
+        // The restored memories contain all pages in their deltas, so the files
+        // backing the canister's old memories have to be truncated.
+        truncate_canister_heap(&self.log, &path, canister_id);
+        truncate_canister_stable_memory(&self.log, &path, canister_id);
+
+        state.metadata.heap_delta_estimate += snapshot.size();
+        Ok(())
+    }
+
+    /// Lists the snapshots of a canister. Only the controllers of the canister
+    /// can list its snapshots.
+    pub(crate) fn list_canister_snapshots(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &ReplicatedState,
+    ) -> Result<ListCanisterSnapshotsResponse, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        self.validate_controller(canister, &sender)?;
+
+        Ok(ListCanisterSnapshotsResponse(
+            state
+                .canister_snapshots
+                .list_snapshots(canister_id)
+                .map(|(snapshot_id, snapshot)| {
+                    CanisterSnapshotResponse::new(
+                        snapshot_id.to_bytes(),
+                        snapshot.taken_at_timestamp.as_nanos_since_unix_epoch(),
+                        snapshot.size(),
+                    )
+                })
+                .collect(),
+        ))
+    }
+
+    /// Deletes the given snapshot of a canister. Only the controllers of the
+    /// canister can delete its snapshots.
+    pub(crate) fn delete_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        args: CanisterSnapshotArgs,
+        state: &mut ReplicatedState,
+    ) -> Result<(), CanisterManagerError> {
+        let canister_id = args.get_canister_id();
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        self.validate_controller(canister, &sender)?;
+        let snapshot_id = self.validate_snapshot_id(state, canister_id, args.snapshot_id())?;
+
+        remove_canister_snapshot(state, snapshot_id);
+        Ok(())
+    }
+
+    /// Returns the log of a canister. Depending on the canister's log
+    /// visibility, either only its controllers or everyone can read the log.
+    pub(crate) fn fetch_canister_logs(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &ReplicatedState,
+    ) -> Result<FetchCanisterLogsResponse, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        match canister.system_state.log_visibility {
+            LogVisibility::Public => {}
+            LogVisibility::Controllers => self.validate_controller(canister, &sender)?,
+        }
+
+        Ok(FetchCanisterLogsResponse {
+            canister_log_records: canister
+                .system_state
+                .canister_log
+                .records()
+                .iter()
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// Returns the most recent changes to a canister together with its current
+    /// module hash and controllers. Any canister can request the information
+    /// of any other canister.
+    pub(crate) fn get_canister_info(
+        &self,
+        canister_id: CanisterId,
+        num_requested_changes: Option<u64>,
+        state: &ReplicatedState,
+    ) -> Result<CanisterInfoResponse, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        let canister_history = canister.system_state.canister_history();
+        let num_requested_changes = num_requested_changes.unwrap_or(0) as usize;
+
+        Ok(CanisterInfoResponse {
+            total_num_changes: canister_history.total_num_changes(),
+            recent_changes: canister_history
+                .get_changes(num_requested_changes)
+                .cloned()
+                .collect(),
+            module_hash: get_wasm_hash(canister).map(|hash| hash.to_vec()),
+            controllers: canister.controllers().iter().copied().collect(),
+        })
+    }
+
     /// Creates a new canister with the cycles amount specified and inserts it
     /// into `ReplicatedState`.
     ///
@@ -1296,6 +1676,189 @@ impl CanisterManager {
         Ok(())
     }
 
+    fn validate_wasm_memory_limit(
+        &self,
+        canister: &CanisterState,
+        wasm_memory_limit: Option<NumBytes>,
+    ) -> Result<(), CanisterManagerError> {
+        if let Some(wasm_memory_limit) = wasm_memory_limit {
+            let wasm_memory_usage = canister.wasm_memory_usage();
+            if wasm_memory_limit.get() != 0 && wasm_memory_limit < wasm_memory_usage {
+                return Err(CanisterManagerError::InvalidSettings {
+                    message: format!(
+                        "Invalid settings: 'wasm_memory_limit' ({} bytes) is below the current Wasm memory usage of the canister ({} bytes)",
+                        wasm_memory_limit, wasm_memory_usage
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the cycles to reserve for growing the memory allocation of the
+    /// canister to `memory_allocation`, see
+    /// `CyclesAccountManager::storage_reservation_cycles()`.
+    fn memory_allocation_reservation_cycles(
+        &self,
+        canister: &CanisterState,
+        memory_allocation: Option<MemoryAllocation>,
+        total_subnet_memory_taken: NumBytes,
+    ) -> Cycles {
+        let new_bytes = match memory_allocation {
+            Some(MemoryAllocation::Reserved(bytes)) => bytes,
+            Some(MemoryAllocation::BestEffort) | None => return Cycles::zero(),
+        };
+        // Memory that is already allocated or in use is already included in
+        // the subnet memory usage.
+        let old_bytes = std::cmp::max(
+            canister.memory_allocation().bytes(),
+            canister.memory_usage(self.config.own_subnet_type),
+        );
+        let allocated_bytes = new_bytes.get().saturating_sub(old_bytes.get());
+        let subnet_memory_saturation = ResourceSaturation::new(
+            total_subnet_memory_taken.get(),
+            self.config.subnet_memory_threshold.get(),
+            self.config.subnet_memory_capacity.get(),
+        );
+        self.cycles_account_manager
+            .storage_reservation_cycles(NumBytes::new(allocated_bytes), &subnet_memory_saturation)
+    }
+
+    /// Checks that the canister can reserve `reservation_cycles` without
+    /// exceeding the (possibly updated) reserved cycles limit and that the
+    /// limit is not below the cycles reserved so far.
+    fn validate_reserved_cycles(
+        &self,
+        canister: &CanisterState,
+        reservation_cycles: Cycles,
+        reserved_cycles_limit: Option<Cycles>,
+    ) -> Result<(), CanisterManagerError> {
+        if reservation_cycles > canister.system_state.balance() {
+            return Err(CanisterManagerError::InsufficientCyclesInMemoryAllocation {
+                canister_id: canister.canister_id(),
+                available: canister.system_state.balance(),
+                required: reservation_cycles,
+            });
+        }
+        let limit =
+            reserved_cycles_limit.or_else(|| canister.system_state.reserved_balance_limit());
+        if let Some(limit) = limit {
+            let requested = canister.system_state.reserved_balance() + reservation_cycles;
+            if requested > limit {
+                return Err(CanisterManagerError::ReservedCyclesLimitIsTooLow {
+                    cycles: requested,
+                    limit,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the canister stays above its freezing threshold once its
+    /// memory usage grows by a new snapshot of `snapshot_size` bytes and
+    /// `execution_cycles` are paid for taking it, and reserves the cycles for
+    /// the new storage, in the same way as growing the canister's memory does.
+    fn reserve_cycles_for_snapshot(
+        &self,
+        canister: &mut CanisterState,
+        snapshot_size: NumBytes,
+        snapshots_memory_taken: NumBytes,
+        total_subnet_memory_taken: NumBytes,
+        execution_cycles: Cycles,
+    ) -> Result<(), CanisterManagerError> {
+        let subnet_memory_saturation = ResourceSaturation::new(
+            total_subnet_memory_taken.get(),
+            self.config.subnet_memory_threshold.get(),
+            self.config.subnet_memory_capacity.get(),
+        );
+        let reservation_cycles = self
+            .cycles_account_manager
+            .storage_reservation_cycles(snapshot_size, &subnet_memory_saturation);
+        let threshold = self.cycles_account_manager.freeze_threshold_cycles(
+            canister.system_state.freeze_threshold,
+            canister.memory_allocation(),
+            canister.memory_usage(self.config.own_subnet_type)
+                + snapshots_memory_taken
+                + snapshot_size,
+            canister.compute_allocation(),
+        );
+        let canister_id = canister.canister_id();
+        let required = threshold + reservation_cycles + execution_cycles;
+        if canister.system_state.balance() < required {
+            return Err(CanisterManagerError::InsufficientCyclesForSnapshot {
+                canister_id,
+                available: canister.system_state.balance(),
+                required,
+            });
+        }
+        canister
+            .system_state
+            .reserve_cycles(reservation_cycles)
+            .map_err(|err| match err {
+                ReservationError::ReservedLimitExceed { requested, limit } => {
+                    CanisterManagerError::ReservedCyclesLimitExceededForSnapshot {
+                        canister_id,
+                        requested,
+                        limit,
+                    }
+                }
+                ReservationError::InsufficientCycles {
+                    requested,
+                    available,
+                } => CanisterManagerError::InsufficientCyclesForSnapshot {
+                    canister_id,
+                    available,
+                    required: requested,
+                },
+            })
+    }
+
+    /// Checks that the canister's memory usage after replacing its execution
+    /// state with one that uses `new_execution_memory_usage` bytes fits into
+    /// its memory allocation and into the subnet's available memory.
+    fn validate_memory_usage_after_load(
+        &self,
+        canister: &CanisterState,
+        new_execution_memory_usage: NumBytes,
+        total_subnet_memory_taken: NumBytes,
+    ) -> Result<(), CanisterManagerError> {
+        let old_execution_memory_usage = canister
+            .execution_state
+            .as_ref()
+            .map_or(NumBytes::from(0), |es| es.memory_usage());
+        match canister.memory_allocation() {
+            MemoryAllocation::Reserved(allocation) => {
+                let memory_usage_needed = canister.memory_usage(self.config.own_subnet_type)
+                    - old_execution_memory_usage
+                    + new_execution_memory_usage;
+                if memory_usage_needed > allocation {
+                    return Err(CanisterManagerError::NotEnoughMemoryAllocationGiven {
+                        canister_id: canister.canister_id(),
+                        memory_allocation_given: canister.memory_allocation(),
+                        memory_usage_needed,
+                    });
+                }
+            }
+            MemoryAllocation::BestEffort => {
+                let requested = new_execution_memory_usage
+                    .get()
+                    .saturating_sub(old_execution_memory_usage.get());
+                let available = self
+                    .config
+                    .subnet_memory_capacity
+                    .get()
+                    .saturating_sub(total_subnet_memory_taken.get());
+                if requested > available {
+                    return Err(CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                        requested: NumBytes::from(requested),
+                        available: NumBytes::from(available),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn validate_canister_is_stopped(
         &self,
         canister: &CanisterState,
@@ -1336,6 +1899,27 @@ impl CanisterManager {
         Ok(canister_id)
     }
 
+    /// Parses the given snapshot id and checks that it refers to an existing
+    /// snapshot of the given canister.
+    fn validate_snapshot_id(
+        &self,
+        state: &ReplicatedState,
+        canister_id: CanisterId,
+        snapshot_id: &[u8],
+    ) -> Result<SnapshotId, CanisterManagerError> {
+        let snapshot_id =
+            SnapshotId::try_from(snapshot_id).map_err(CanisterManagerError::InvalidSnapshotId)?;
+        if snapshot_id.canister_id() != canister_id
+            || state.canister_snapshots.get(&snapshot_id).is_none()
+        {
+            return Err(CanisterManagerError::CanisterSnapshotNotFound {
+                canister_id,
+                snapshot_id,
+            });
+        }
+        Ok(snapshot_id)
+    }
+
     fn validate_canister_exists<'a>(
         &self,
         state: &'a ReplicatedState,
@@ -1364,6 +1948,34 @@ pub(crate) fn canister_layout(
         .expect("failed to obtain canister layout")
 }
 
+pub(crate) fn snapshot_layout(
+    state_path: &Path,
+    snapshot_id: &SnapshotId,
+) -> SnapshotLayout<RwPolicy> {
+    CheckpointLayout::<RwPolicy>::new(state_path.into(), Height::from(0))
+        .and_then(|layout| layout.snapshot(snapshot_id))
+        .expect("failed to obtain snapshot layout")
+}
+
+/// Removes the given snapshot from `state` and marks it as deleted on the
+/// filesystem.
+pub(crate) fn remove_canister_snapshot(state: &mut ReplicatedState, snapshot_id: SnapshotId) {
+    state.canister_snapshots.remove(&snapshot_id);
+    snapshot_layout(state.path(), &snapshot_id)
+        .mark_deleted()
+        .expect("failed to mark snapshot as deleted on the filesystem");
+}
+
+/// Removes all snapshots of the given canister from `state` and marks them as
+/// deleted on the filesystem.
+pub(crate) fn remove_canister_snapshots(state: &mut ReplicatedState, canister_id: CanisterId) {
+    for snapshot_id in state.canister_snapshots.remove_snapshots(canister_id) {
+        snapshot_layout(state.path(), &snapshot_id)
+            .mark_deleted()
+            .expect("failed to mark snapshot as deleted on the filesystem");
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub(crate) enum CanisterManagerError {
     CanisterInvalidController {
@@ -1409,6 +2021,43 @@ pub(crate) enum CanisterManagerError {
         subnet_id: SubnetId,
         max_number_of_canisters: u64,
     },
+    CanisterSnapshotNotFound {
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+    },
+    CanisterSnapshotLimitExceeded {
+        canister_id: CanisterId,
+        limit: usize,
+    },
+    InvalidSnapshotId(String),
+    InsufficientCyclesInMemoryAllocation {
+        canister_id: CanisterId,
+        available: Cycles,
+        required: Cycles,
+    },
+    ReservedCyclesLimitIsTooLow {
+        cycles: Cycles,
+        limit: Cycles,
+    },
+    LoadCanisterSnapshotNotStopped(CanisterId),
+    LoadCanisterSnapshotExecutionInProgress(CanisterId),
+    TakeCanisterSnapshotNotStopped(CanisterId),
+    TakeCanisterSnapshotExecutionInProgress(CanisterId),
+    TakeCanisterSnapshotInstructionLimitExceeded {
+        canister_id: CanisterId,
+        instructions: NumInstructions,
+        limit: NumInstructions,
+    },
+    InsufficientCyclesForSnapshot {
+        canister_id: CanisterId,
+        available: Cycles,
+        required: Cycles,
+    },
+    ReservedCyclesLimitExceededForSnapshot {
+        canister_id: CanisterId,
+        requested: Cycles,
+        limit: Cycles,
+    },
 }
 
 impl From<CanisterManagerError> for UserError {
@@ -1552,6 +2201,105 @@ impl From<CanisterManagerError> for UserError {
                     format!("Subnet {} has reached the allowed canister limit of {} canisters. Retry creating the canister.", subnet_id, max_number_of_canisters),
                 )
             }
+            CanisterSnapshotNotFound { canister_id, snapshot_id } => {
+                Self::new(
+                    ErrorCode::CanisterSnapshotNotFound,
+                    format!("Could not find the snapshot {} of canister {}.", snapshot_id, canister_id),
+                )
+            }
+            CanisterSnapshotLimitExceeded { canister_id, limit } => {
+                Self::new(
+                    ErrorCode::CanisterSnapshotLimitExceeded,
+                    format!("Canister {} has reached the limit of {} snapshots. Delete a snapshot or replace it when taking a new one.", canister_id, limit),
+                )
+            }
+            InvalidSnapshotId(message) => {
+                Self::new(
+                    ErrorCode::InvalidManagementPayload,
+                    format!("Invalid snapshot id: {}", message),
+                )
+            }
+            InsufficientCyclesInMemoryAllocation { canister_id, available, required } => {
+                Self::new(
+                    ErrorCode::CanisterOutOfCycles,
+                    format!(
+                        "Cannot increase the memory allocation of canister {} due to insufficient cycles. At least {} cycles must be reserved, but only {} are available.",
+                        canister_id, required, available
+                    ),
+                )
+            }
+            ReservedCyclesLimitIsTooLow { cycles, limit } => {
+                Self::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!(
+                        "Could not validate the settings: the reserved cycles limit {} is below the {} cycles that the canister needs to reserve",
+                        limit, cycles
+                    ),
+                )
+            }
+            LoadCanisterSnapshotNotStopped(canister_id) => {
+                Self::new(
+                    ErrorCode::CanisterNotStopped,
+                    format!(
+                        "Canister {} must be stopped before a snapshot is loaded.",
+                        canister_id,
+                    )
+                )
+            }
+            LoadCanisterSnapshotExecutionInProgress(canister_id) => {
+                Self::new(
+                    ErrorCode::CanisterNotStopped,
+                    format!(
+                        "Canister {} has a paused or aborted execution. Retry loading the snapshot once it has finished.",
+                        canister_id,
+                    )
+                )
+            }
+            TakeCanisterSnapshotNotStopped(canister_id) => {
+                Self::new(
+                    ErrorCode::CanisterNotStopped,
+                    format!(
+                        "Canister {} must be stopped before a snapshot is taken.",
+                        canister_id,
+                    )
+                )
+            }
+            TakeCanisterSnapshotExecutionInProgress(canister_id) => {
+                Self::new(
+                    ErrorCode::CanisterNotStopped,
+                    format!(
+                        "Canister {} has a paused or aborted execution. Retry taking the snapshot once it has finished.",
+                        canister_id,
+                    )
+                )
+            }
+            TakeCanisterSnapshotInstructionLimitExceeded { canister_id, instructions, limit } => {
+                Self::new(
+                    ErrorCode::CanisterInstructionLimitExceeded,
+                    format!(
+                        "Taking a snapshot of canister {} requires {} instructions, which exceeds the limit of {} instructions.",
+                        canister_id, instructions, limit
+                    )
+                )
+            }
+            InsufficientCyclesForSnapshot { canister_id, available, required } => {
+                Self::new(
+                    ErrorCode::CanisterOutOfCycles,
+                    format!(
+                        "Cannot take a snapshot of canister {} due to insufficient cycles. At least {} cycles are required, but only {} are available.",
+                        canister_id, required, available
+                    ),
+                )
+            }
+            ReservedCyclesLimitExceededForSnapshot { canister_id, requested, limit } => {
+                Self::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!(
+                        "Cannot take a snapshot of canister {} because the reserved cycles {} would exceed the reserved cycles limit {}.",
+                        canister_id, requested, limit
+                    ),
+                )
+            }
         }
     }
 }
@@ -1629,6 +2377,11 @@ pub fn uninstall_canister(
     // Drop its certified data.
     canister.system_state.certified_data = Vec::new();
 
+    // Deactivate its global timer.
+    canister.system_state.global_timer = CanisterTimer::Inactive;
+
+    canister.system_state.canister_version += 1;
+
     truncate_canister_heap(log, state_path, canister.canister_id());
     truncate_canister_stable_memory(log, state_path, canister.canister_id());
 
@@ -1663,7 +2416,7 @@ pub fn uninstall_canister(
                         },
                     }));
                 }
-                CallOrigin::CanisterUpdate(caller_canister_id, callback_id) => {
+                CallOrigin::CanisterUpdate(caller_canister_id, callback_id, deadline) => {
                     rejects.push(Response::Canister(CanisterResponse {
                         originator: *caller_canister_id,
                         respondent: canister_id,
@@ -1673,14 +2426,15 @@ pub fn uninstall_canister(
                             code: RejectCode::CanisterReject,
                             message: String::from("Canister has been uninstalled."),
                         }),
+                        deadline: *deadline,
                     }));
                 }
                 CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => fatal!(
                     log,
                     "No callbacks with a query origin should be found when uninstalling"
                 ),
-                CallOrigin::Heartbeat => {
-                    // Cannot respond to heartbeat messages. Nothing to do.
+                CallOrigin::SystemTask => {
+                    // Cannot respond to system tasks. Nothing to do.
                 }
             }
 
@@ -1689,6 +2443,47 @@ pub fn uninstall_canister(
         }
     }
 
+    // Aborted calls have not created call contexts yet and must not be
+    // executed from scratch against the uninstalled canister, so reject them
+    // here. Aborted responses stay in the task queue: their call contexts are
+    // deleted above, so executing them only unregisters their callbacks.
+    let task_queue = std::mem::take(&mut canister.system_state.task_queue);
+    canister.system_state.task_queue = task_queue
+        .into_iter()
+        .filter_map(|task| match task {
+            ExecutionTask::AbortedExecution(CanisterInputMessage::Request(request)) => {
+                rejects.push(Response::Canister(CanisterResponse {
+                    originator: request.sender,
+                    respondent: canister_id,
+                    originator_reply_callback: request.sender_reply_callback,
+                    refund: request.payment,
+                    response_payload: Payload::Reject(RejectContext {
+                        code: RejectCode::CanisterReject,
+                        message: String::from("Canister has been uninstalled."),
+                    }),
+                    deadline: request.deadline,
+                }));
+                None
+            }
+            ExecutionTask::AbortedExecution(CanisterInputMessage::Ingress(ingress)) => {
+                rejects.push(Response::Ingress(IngressResponse {
+                    message_id: ingress.message_id.clone(),
+                    status: IngressStatus::Known {
+                        receiver: canister_id.get(),
+                        user_id: ingress.source,
+                        time,
+                        state: IngressState::Failed(UserError::new(
+                            ErrorCode::CanisterRejectedMessage,
+                            "Canister has been uninstalled.",
+                        )),
+                    },
+                }));
+                None
+            }
+            task => Some(task),
+        })
+        .collect();
+
     rejects
 }
 
@@ -1698,6 +2493,10 @@ struct ValidatedCanisterSettings {
     pub compute_allocation: Option<ComputeAllocation>,
     pub memory_allocation: Option<MemoryAllocation>,
     pub freezing_threshold: Option<NumSeconds>,
+    pub log_visibility: Option<LogVisibility>,
+    pub wasm_memory_limit: Option<NumBytes>,
+    pub wasm_memory_threshold: Option<NumBytes>,
+    pub reserved_cycles_limit: Option<Cycles>,
 }
 
 impl TryFrom<(CanisterSettings, usize)> for ValidatedCanisterSettings {
@@ -1733,18 +2532,29 @@ impl TryFrom<(CanisterSettings, usize)> for ValidatedCanisterSettings {
             compute_allocation: settings.compute_allocation(),
             memory_allocation: settings.memory_allocation(),
             freezing_threshold: settings.freezing_threshold(),
+            log_visibility: settings.log_visibility(),
+            wasm_memory_limit: settings.wasm_memory_limit(),
+            wasm_memory_threshold: settings.wasm_memory_threshold(),
+            reserved_cycles_limit: settings.reserved_cycles_limit(),
         })
     }
 }
 
 // Finalizes execution of the `install_code` message that could have run
 // multiple rounds due to determnistic time slicing.
+//
+// `initial_balance` and `initial_reserved_balance` are the balances of the old
+// canister when the execution started. The old canister stays in the
+// replicated state while the execution is paused, so its balances can change
+// in the meantime, e.g. due to storage charges or deposited cycles.
 #[allow(clippy::too_many_arguments)]
 fn finish_install_code(
     mut old_canister: CanisterState,
+    initial_balance: Cycles,
+    initial_reserved_balance: Cycles,
     instruction_limit: NumInstructions,
     instructions_left: NumInstructions,
-    result: Result<(CanisterState, NumBytes), CanisterManagerError>,
+    result: Result<(CanisterState, NumBytes), InstallCodeRoutineError>,
     mode: CanisterInstallMode,
     canister_layout_path: PathBuf,
     config: &CanisterMgrConfig,
@@ -1754,27 +2564,34 @@ fn finish_install_code(
     let instructions_consumed = instruction_limit - instructions_left;
     match result {
         Ok((mut new_canister, heap_delta)) => {
-            if mode == CanisterInstallMode::Upgrade
-                && old_canister.system_state.queues().input_queues_stats()
-                    != new_canister.system_state.queues().input_queues_stats()
-            {
-                error!(
-                    round.log,
-                    "Input queues changed after upgrade. Before: {:?}. After: {:?}",
-                    old_canister.system_state.queues().input_queues_stats(),
-                    new_canister.system_state.queues().input_queues_stats()
-                );
-                let err = CanisterManagerError::Hypervisor(
-                    old_canister.canister_id(),
-                    HypervisorError::ContractViolation(
-                        "Input queues changed after upgrade".to_string(),
-                    ),
+            // The old canister stays in the replicated state while the
+            // execution is paused, so new messages can be inducted into its
+            // input queues and its output messages can be routed. Take its
+            // current queues and tasks to make sure no message is lost or
+            // delivered twice.
+            new_canister
+                .system_state
+                .put_queues(old_canister.system_state.take_queues());
+            new_canister.system_state.task_queue =
+                std::mem::take(&mut old_canister.system_state.task_queue);
+            new_canister.system_state.canister_version += 1;
+
+            // Carry over the balance changes of the old canister that
+            // happened while the execution was paused on top of the changes
+            // made by the installation itself. The reserved balance can only
+            // decrease outside of execution.
+            let old_balance = old_canister.system_state.balance();
+            let new_balance = new_canister.system_state.balance();
+            *new_canister.system_state.balance_mut() = if new_balance >= initial_balance {
+                old_balance + (new_balance - initial_balance)
+            } else {
+                old_balance - (initial_balance - new_balance)
+            };
+            new_canister
+                .system_state
+                .remove_cycles_from_reserved_balance(
+                    initial_reserved_balance - old_canister.system_state.reserved_balance(),
                 );
-                return DtsInstallCodeResult::Finished {
-                    canister: old_canister,
-                    result: Err(err),
-                };
-            }
 
             // Refund the left over execution cycles to the new canister and
             // replace the old canister with the new one.
@@ -1802,20 +2619,25 @@ fn finish_install_code(
                 );
             }
 
-            // TODO(RUN-221): Copy parts of `old_canister_state` that could have changed
-            // externally into the new canister state in `result`.
             DtsInstallCodeResult::Finished {
                 canister: new_canister,
                 result: Ok(InstallCodeResult {
                     heap_delta,
                     old_wasm_hash,
                     new_wasm_hash,
+                    mode,
                 }),
             }
         }
-        Err(err) => {
+        Err(InstallCodeRoutineError { err, canister_log }) => {
             // the install / upgrade failed. Refund the left over cycles to
             // the old canister and leave it in the state.
+            if let Some(canister_log) = canister_log {
+                // The log of the failed executions is based on the log of the
+                // old canister, which cannot change while the installation is
+                // in progress.
+                old_canister.system_state.canister_log = canister_log;
+            }
             if config.rate_limiting_of_instructions == FlagStatus::Enabled {
                 old_canister.scheduler_state.install_code_debit += instructions_consumed;
             }
@@ -1841,6 +2663,8 @@ fn finish_install_code(
 #[derive(Debug)]
 pub(crate) struct PausedInstallCodeExecution {
     paused_routine: Box<dyn PausedInstallCodeRoutine>,
+    initial_balance: Cycles,
+    initial_reserved_balance: Cycles,
     message_instruction_limit: NumInstructions,
     mode: CanisterInstallMode,
     canister_layout_path: PathBuf,
@@ -1848,8 +2672,9 @@ pub(crate) struct PausedInstallCodeExecution {
 }
 
 impl PausedInstallCodeExecution {
-    #[allow(dead_code)]
-    fn resume(
+    /// Resumes the paused execution with the old canister state, which stays
+    /// in the replicated state while the execution is paused.
+    pub(crate) fn resume(
         self,
         canister: CanisterState,
         round: RoundContext,
@@ -1862,6 +2687,8 @@ impl PausedInstallCodeExecution {
                 result,
             } => finish_install_code(
                 canister,
+                self.initial_balance,
+                self.initial_reserved_balance,
                 self.message_instruction_limit,
                 instructions_left,
                 result,
@@ -1882,6 +2709,22 @@ impl PausedInstallCodeExecution {
             }
         }
     }
+
+    /// Aborts the paused execution. The old canister state remains unchanged
+    /// and the execution cycles that were reserved at the start are refunded
+    /// because `install_code` is executed from scratch later.
+    pub(crate) fn abort(
+        self,
+        canister: &mut CanisterState,
+        cycles_account_manager: &CyclesAccountManager,
+    ) {
+        self.paused_routine.abort();
+        cycles_account_manager.refund_execution_cycles(
+            &mut canister.system_state,
+            self.message_instruction_limit,
+            self.message_instruction_limit,
+        );
+    }
 }
 
 #[cfg(test)]
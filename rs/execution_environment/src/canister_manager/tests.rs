@@ -14,7 +14,7 @@ use assert_matches::assert_matches;
 use candid::Decode;
 use ic_base_types::{NumSeconds, PrincipalId};
 use ic_config::{execution_environment::Config, flag_status::FlagStatus};
-use ic_cycles_account_manager::CyclesAccountManager;
+use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::{ErrorCode, UserError};
 use ic_ic00_types::{
     CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgs, CanisterStatusType,
@@ -92,6 +92,7 @@ lazy_static! {
         compute_allocation: ComputeAllocation::default(),
         subnet_type: SubnetType::Application,
         execution_mode: ExecutionMode::Replicated,
+        subnet_memory_saturation: ResourceSaturation::default(),
     };
 }
 
@@ -229,6 +230,7 @@ fn canister_manager_config(
     rate_limiting_of_instructions: FlagStatus,
 ) -> CanisterMgrConfig {
     CanisterMgrConfig::new(
+        MEMORY_CAPACITY,
         MEMORY_CAPACITY,
         DEFAULT_PROVISIONAL_BALANCE,
         NumSeconds::from(100_000),
@@ -2619,6 +2621,10 @@ fn lower_memory_allocation_than_usage_fails() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(2)).unwrap()),
             None,
+            None,
+            None,
+            None,
+            None,
         );
 
         let compute_allocation_used = state.total_compute_allocation();
@@ -2650,6 +2656,10 @@ fn test_install_when_updating_memory_allocation_via_canister_settings() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(2)).unwrap()),
             None,
+            None,
+            None,
+            None,
+            None,
         );
         let canister_id = canister_manager
             .create_canister(
@@ -2692,6 +2702,10 @@ fn test_install_when_updating_memory_allocation_via_canister_settings() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(MEMORY_CAPACITY.get() / 2)).unwrap()),
             None,
+            None,
+            None,
+            None,
+            None,
         );
 
         let compute_allocation_used = state.total_compute_allocation();
@@ -2739,6 +2753,10 @@ fn test_upgrade_when_updating_memory_allocation_via_canister_settings() {
                 MemoryAllocation::try_from(NumBytes::from(WASM_PAGE_SIZE_IN_BYTES + 100)).unwrap(),
             ),
             None,
+            None,
+            None,
+            None,
+            None,
         );
         let wat = r#"
         (module
@@ -2813,6 +2831,10 @@ fn test_upgrade_when_updating_memory_allocation_via_canister_settings() {
                     .unwrap(),
             ),
             None,
+            None,
+            None,
+            None,
+            None,
         );
 
         let compute_allocation_used = state.total_compute_allocation();
@@ -2894,7 +2916,7 @@ fn test_install_when_setting_memory_allocation_to_zero() {
         let wasm = ic_test_utilities::universal_canister::UNIVERSAL_CANISTER_WASM.to_vec();
 
         let sender = canister_test_id(100).get();
-        let settings = CanisterSettings::new(None, None, None, None, None);
+        let settings = CanisterSettings::new(None, None, None, None, None, None, None, None, None);
         let canister_id = canister_manager
             .create_canister(
                 sender,
@@ -2914,6 +2936,10 @@ fn test_install_when_setting_memory_allocation_to_zero() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(0)).unwrap()),
             None,
+            None,
+            None,
+            None,
+            None,
         );
 
         let compute_allocation_used = state.total_compute_allocation();
@@ -2961,6 +2987,10 @@ fn test_upgrade_when_setting_memory_allocation_to_zero() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(MEMORY_CAPACITY.get() / 2)).unwrap()),
             None,
+            None,
+            None,
+            None,
+            None,
         );
         let canister_id = canister_manager
             .create_canister(
@@ -2998,6 +3028,10 @@ fn test_upgrade_when_setting_memory_allocation_to_zero() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(0)).unwrap()),
             None,
+            None,
+            None,
+            None,
+            None,
         );
 
         let compute_allocation_used = state.total_compute_allocation();
@@ -1,13 +1,16 @@
 use ic_base_types::{NumBytes, NumSeconds};
 use ic_error_types::{ErrorCode, UserError};
-use ic_ic00_types::CanisterSettingsArgs;
+use ic_ic00_types::{CanisterSettingsArgs, LogVisibility};
 use ic_types::{
-    ComputeAllocation, InvalidComputeAllocationError, InvalidMemoryAllocationError,
+    ComputeAllocation, Cycles, InvalidComputeAllocationError, InvalidMemoryAllocationError,
     MemoryAllocation, PrincipalId,
 };
 use num_traits::cast::ToPrimitive;
 use std::convert::TryFrom;
 
+/// The largest accepted `wasm_memory_limit`: 2^48 bytes (256 TiB).
+pub(crate) const MAX_WASM_MEMORY_LIMIT: u64 = 1 << 48;
+
 /// Struct used for decoding CanisterSettingsArgs
 #[derive(Default)]
 pub(crate) struct CanisterSettings {
@@ -16,6 +19,10 @@ pub(crate) struct CanisterSettings {
     compute_allocation: Option<ComputeAllocation>,
     memory_allocation: Option<MemoryAllocation>,
     freezing_threshold: Option<NumSeconds>,
+    log_visibility: Option<LogVisibility>,
+    wasm_memory_limit: Option<NumBytes>,
+    wasm_memory_threshold: Option<NumBytes>,
+    reserved_cycles_limit: Option<Cycles>,
 }
 
 impl CanisterSettings {
@@ -25,6 +32,10 @@ impl CanisterSettings {
         compute_allocation: Option<ComputeAllocation>,
         memory_allocation: Option<MemoryAllocation>,
         freezing_threshold: Option<NumSeconds>,
+        log_visibility: Option<LogVisibility>,
+        wasm_memory_limit: Option<NumBytes>,
+        wasm_memory_threshold: Option<NumBytes>,
+        reserved_cycles_limit: Option<Cycles>,
     ) -> Self {
         Self {
             controller,
@@ -32,6 +43,10 @@ impl CanisterSettings {
             compute_allocation,
             memory_allocation,
             freezing_threshold,
+            log_visibility,
+            wasm_memory_limit,
+            wasm_memory_threshold,
+            reserved_cycles_limit,
         }
     }
 
@@ -54,6 +69,23 @@ impl CanisterSettings {
     pub fn freezing_threshold(&self) -> Option<NumSeconds> {
         self.freezing_threshold
     }
+
+    pub fn log_visibility(&self) -> Option<LogVisibility> {
+        self.log_visibility
+    }
+
+    /// The requested Wasm memory limit, where `0` means unlimited.
+    pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
+        self.wasm_memory_limit
+    }
+
+    pub fn wasm_memory_threshold(&self) -> Option<NumBytes> {
+        self.wasm_memory_threshold
+    }
+
+    pub fn reserved_cycles_limit(&self) -> Option<Cycles> {
+        self.reserved_cycles_limit
+    }
 }
 
 impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
@@ -83,12 +115,42 @@ impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
             None => None,
         };
 
+        let wasm_memory_limit = match input.wasm_memory_limit {
+            Some(limit) => match limit.0.to_u64() {
+                Some(bytes) if bytes <= MAX_WASM_MEMORY_LIMIT => Some(NumBytes::from(bytes)),
+                _ => {
+                    return Err(UpdateSettingsError::WasmMemoryLimitOutOfRange { provided: limit })
+                }
+            },
+            None => None,
+        };
+
+        let wasm_memory_threshold = match input.wasm_memory_threshold {
+            Some(threshold) => Some(NumBytes::from(threshold.0.to_u64().ok_or(
+                UpdateSettingsError::WasmMemoryThresholdOutOfRange {
+                    provided: threshold,
+                },
+            )?)),
+            None => None,
+        };
+
+        let reserved_cycles_limit = match input.reserved_cycles_limit {
+            Some(limit) => Some(Cycles::from(limit.0.to_u128().ok_or(
+                UpdateSettingsError::ReservedCyclesLimitOutOfRange { provided: limit },
+            )?)),
+            None => None,
+        };
+
         Ok(CanisterSettings::new(
             input.controller,
             input.controllers,
             compute_allocation,
             memory_allocation,
             freezing_threshold,
+            input.log_visibility,
+            wasm_memory_limit,
+            wasm_memory_threshold,
+            reserved_cycles_limit,
         ))
     }
 }
@@ -108,6 +170,9 @@ pub enum UpdateSettingsError {
     ComputeAllocation(InvalidComputeAllocationError),
     MemoryAllocation(InvalidMemoryAllocationError),
     FreezingThresholdOutOfRange { provided: candid::Nat },
+    WasmMemoryLimitOutOfRange { provided: candid::Nat },
+    WasmMemoryThresholdOutOfRange { provided: candid::Nat },
+    ReservedCyclesLimitOutOfRange { provided: candid::Nat },
 }
 
 impl From<UpdateSettingsError> for UserError {
@@ -136,6 +201,27 @@ impl From<UpdateSettingsError> for UserError {
                     provided
                 ),
             ),
+            UpdateSettingsError::WasmMemoryLimitOutOfRange { provided } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Wasm memory limit expected to be in the range of [0..2^48], got {}",
+                    provided
+                ),
+            ),
+            UpdateSettingsError::WasmMemoryThresholdOutOfRange { provided } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Wasm memory threshold expected to be in the range of [0..2^64-1], got {}",
+                    provided
+                ),
+            ),
+            UpdateSettingsError::ReservedCyclesLimitOutOfRange { provided } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Reserved cycles limit expected to be in the range of [0..2^128-1], got {}",
+                    provided
+                ),
+            ),
         }
     }
 }
@@ -1,7 +1,7 @@
 // Replicated messages.
 pub(crate) mod call;
-pub mod heartbeat;
 pub mod response;
+pub mod system_task;
 
 // Non-replicated messages.
 pub mod nonreplicated_query;
@@ -11,6 +11,7 @@ use crate::execution_environment::{
     ExecuteMessageResult, ExecutionResponse, PausedExecution, RoundContext, RoundLimits,
 };
 use ic_config::flag_status::FlagStatus;
+use ic_cycles_account_manager::CyclesAccountManager;
 use ic_embedders::wasm_executor::{PausedWasmExecution, WasmExecutionResult};
 use ic_error_types::{ErrorCode, UserError};
 use ic_interfaces::messages::CanisterInputMessage;
@@ -43,6 +44,7 @@ fn early_error_to_result(
                 originator_reply_callback: request.sender_reply_callback,
                 refund: request.payment,
                 response_payload: Payload::from(Err(user_error)),
+                deadline: request.deadline,
             };
             ExecutionResponse::Request(response)
         }
@@ -81,6 +83,18 @@ fn validate_message(
         }
     }
 
+    // Composite queries can call other canisters only in non-replicated mode.
+    if canister.exports_composite_query_method(req.method_name().to_string()) {
+        return Err(UserError::new(
+            ErrorCode::CompositeQueryCalledInReplicatedMode,
+            format!(
+                "Composite query {} of canister {} cannot be called in replicated mode",
+                req.method_name(),
+                canister.canister_id()
+            ),
+        ));
+    }
+
     let query = WasmMethod::Query(req.method_name().to_string());
     if validate_method(&query, canister).is_err() {
         let update = WasmMethod::Update(req.method_name().to_string());
@@ -244,6 +258,8 @@ fn process_update_result(
                 );
                 NumBytes::from((output.instance_stats.dirty_pages * ic_sys::PAGE_SIZE) as u64)
             } else {
+                system_state_changes
+                    .apply_canister_log_changes(round.time, &mut canister.system_state);
                 NumBytes::from(0)
             };
 
@@ -304,12 +320,23 @@ impl PausedExecution for PausedCallExecution {
         process_update_result(canister, result, self.original, round, round_limits)
     }
 
-    fn abort(self: Box<Self>) -> CanisterInputMessage {
+    fn abort(
+        self: Box<Self>,
+        canister: &mut CanisterState,
+        cycles_account_manager: &CyclesAccountManager,
+    ) -> Option<CanisterInputMessage> {
         self.paused_wasm_execution.abort();
-        match self.original.message {
+        // The message is executed from scratch later and is charged again.
+        cycles_account_manager.refund_execution_cycles(
+            &mut canister.system_state,
+            self.original.message_instruction_limit,
+            self.original.message_instruction_limit,
+        );
+        let message = match self.original.message {
             RequestOrIngress::Request(r) => CanisterInputMessage::Request(r),
             RequestOrIngress::Ingress(i) => CanisterInputMessage::Ingress(i),
-        }
+        };
+        Some(message)
     }
 }
 
@@ -337,7 +364,7 @@ fn execute_query_method(
     // unmodified version of the canister. Hence, execute on clones
     // of system and execution states so that we have the original
     // versions.
-    let (output, _output_execution_state, _output_system_state) = round.hypervisor.execute(
+    let (output, _output_execution_state, output_system_state) = round.hypervisor.execute(
         api_type,
         time,
         canister.system_state.clone(),
@@ -348,6 +375,9 @@ fn execute_query_method(
         round.network_topology,
         round_limits,
     );
+    // The canister log is the only change that is kept, so that debug prints
+    // and traps of the query are readable regardless of its outcome.
+    canister.system_state.canister_log = output_system_state.canister_log;
 
     let result = output.wasm_result;
     let log = round.log;
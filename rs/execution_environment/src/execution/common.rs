@@ -11,7 +11,7 @@ use ic_replicated_state::{CallContext, CallContextAction, CallOrigin, CanisterSt
 use ic_types::ingress::{IngressState, IngressStatus, WasmResult};
 use ic_types::messages::{CallbackId, MessageId, Payload, RejectContext, Response};
 use ic_types::methods::{Callback, WasmMethod};
-use ic_types::{Cycles, Time, UserId};
+use ic_types::{time::CoarseTime, Cycles, Time, UserId};
 
 use crate::execution_environment::ExecutionResponse;
 use crate::{as_round_instructions, RoundLimits};
@@ -46,15 +46,15 @@ pub(crate) fn action_to_response(
             time,
             log,
         ),
-        CallOrigin::CanisterUpdate(caller_canister_id, callback_id) => {
-            action_to_request_response(canister, action, caller_canister_id, callback_id)
+        CallOrigin::CanisterUpdate(caller_canister_id, callback_id, deadline) => {
+            action_to_request_response(canister, action, caller_canister_id, callback_id, deadline)
         }
         CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => fatal!(
             log,
             "The update path should not have created a callback with a query origin",
         ),
-        CallOrigin::Heartbeat => {
-            // Since heartbeat messages are invoked by the system as opposed
+        CallOrigin::SystemTask => {
+            // Since system tasks are invoked by the system as opposed
             // to a principal, they cannot respond since there's no one to
             // respond to. Do nothing.
             ExecutionResponse::Empty
@@ -67,6 +67,7 @@ pub(crate) fn action_to_request_response(
     action: CallContextAction,
     originator: CanisterId,
     reply_callback_id: CallbackId,
+    deadline: CoarseTime,
 ) -> ExecutionResponse {
     let response_payload_and_refund = match action {
         CallContextAction::NotYetResponded | CallContextAction::AlreadyResponded => None,
@@ -107,6 +108,7 @@ pub(crate) fn action_to_request_response(
             originator_reply_callback: reply_callback_id,
             refund,
             response_payload,
+            deadline,
         })
     } else {
         ExecutionResponse::Empty
@@ -195,21 +197,22 @@ pub(crate) fn wasm_result_to_query_response(
         CallOrigin::Ingress(user_id, message_id) => {
             wasm_result_to_ingress_response(result, canister, user_id, message_id, time)
         }
-        CallOrigin::CanisterUpdate(caller_canister_id, callback_id) => {
+        CallOrigin::CanisterUpdate(caller_canister_id, callback_id, deadline) => {
             let response = Response {
                 originator: caller_canister_id,
                 respondent: canister.canister_id(),
                 originator_reply_callback: callback_id,
                 refund: Cycles::zero(),
                 response_payload: Payload::from(result),
+                deadline,
             };
             ExecutionResponse::Request(response)
         }
         CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => {
             fatal!(log, "The update path should not have a query origin",)
         }
-        CallOrigin::Heartbeat => {
-            // Since heartbeat messages are invoked by the system as opposed
+        CallOrigin::SystemTask => {
+            // Since system tasks are invoked by the system as opposed
             // to a principal, they cannot respond since there's no one to
             // respond to. Do nothing.
             ExecutionResponse::Empty
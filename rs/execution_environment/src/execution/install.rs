@@ -3,13 +3,16 @@
 // See https://internetcomputer.org/docs/current/references/ic-interface-spec/#ic-install_code
 use crate::canister_manager::{canister_layout, CanisterManagerError, InstallCodeContext};
 use crate::execution::common::update_round_limits;
-use crate::execution::install_code::{InstallCodeRoutineResult, PausedInstallCodeRoutine};
+use crate::execution::install_code::{
+    finish_failed_execution, validate_wasm_memory_limit, InstallCodeRoutineResult,
+    PausedInstallCodeRoutine,
+};
 use crate::execution_environment::{CompilationCostHandling, RoundContext, RoundLimits};
 use ic_base_types::{NumBytes, PrincipalId};
 use ic_embedders::wasm_executor::{PausedWasmExecution, WasmExecutionResult};
 use ic_interfaces::execution_environment::WasmExecutionOutput;
 use ic_logger::{fatal, info};
-use ic_replicated_state::{CanisterState, SystemState};
+use ic_replicated_state::{CanisterState, CanisterTimer, SystemState};
 use ic_sys::PAGE_SIZE;
 use ic_system_api::sandbox_safe_system_state::SystemStateChanges;
 use ic_system_api::{ApiType, ExecutionParameters};
@@ -81,7 +84,7 @@ pub(crate) fn execute_install(
             Err(err) => {
                 return InstallCodeRoutineResult::Finished {
                     instructions_left: execution_parameters.instruction_limits.message(),
-                    result: Err((canister_id, err).into()),
+                    result: Err(CanisterManagerError::from((canister_id, err)).into()),
                 };
             }
         };
@@ -97,6 +100,9 @@ pub(crate) fn execute_install(
     let scheduler_state = old_canister.scheduler_state.clone();
     let mut new_canister = CanisterState::new(system_state, Some(execution_state), scheduler_state);
 
+    // The global timer of the previous code (if any) must not fire for the new code.
+    new_canister.system_state.global_timer = CanisterTimer::Inactive;
+
     // Update allocations.  This must happen after we have created the new
     // execution state so that we fairly account for the memory requirements
     // of the new wasm module.
@@ -116,13 +122,21 @@ pub(crate) fn execute_install(
                     canister_id,
                     memory_allocation_given: desired_memory_allocation,
                     memory_usage_needed: new_canister.memory_usage(subnet_type),
-                }),
+                }
+                .into()),
             };
         }
         execution_parameters.canister_memory_limit = bytes;
     }
     new_canister.system_state.memory_allocation = desired_memory_allocation;
 
+    if let Err(err) = validate_wasm_memory_limit(&new_canister) {
+        return InstallCodeRoutineResult::Finished {
+            instructions_left: execution_parameters.instruction_limits.message(),
+            result: Err(err.into()),
+        };
+    }
+
     let total_heap_delta = NumBytes::from(0);
 
     // Stage 2: invoke the `start()` method of the Wasm module (if present).
@@ -171,10 +185,11 @@ pub(crate) fn execute_install(
         new_canister.execution_state = Some(output_execution_state);
 
         match wasm_execution_result {
-            WasmExecutionResult::Finished(slice, output, _system_state_changes) => {
+            WasmExecutionResult::Finished(slice, output, system_state_changes) => {
                 update_round_limits(round_limits, &slice);
                 install_stage_2a_process_start_result(
                     output,
+                    system_state_changes,
                     context.sender,
                     context.arg,
                     new_canister,
@@ -205,9 +220,10 @@ pub(crate) fn execute_install(
 #[allow(clippy::too_many_arguments)]
 fn install_stage_2a_process_start_result(
     output: WasmExecutionOutput,
+    system_state_changes: SystemStateChanges,
     context_sender: PrincipalId,
     context_arg: Vec<u8>,
-    new_canister: CanisterState,
+    mut new_canister: CanisterState,
     execution_parameters: ExecutionParameters,
     mut total_heap_delta: NumBytes,
     time: Time,
@@ -228,14 +244,21 @@ fn install_stage_2a_process_start_result(
                 .subnet_available_memory
                 .try_decrement(output.allocated_bytes, output.allocated_message_bytes)
                 .unwrap();
+            // `start()` cannot change the system state, but its debug
+            // prints belong in the canister log.
+            system_state_changes
+                .apply_canister_log_changes(round.time, &mut new_canister.system_state);
             total_heap_delta +=
                 NumBytes::from((output.instance_stats.dirty_pages * PAGE_SIZE) as u64);
         }
         Err(err) => {
-            return InstallCodeRoutineResult::Finished {
+            return finish_failed_execution(
+                new_canister,
+                system_state_changes,
                 instructions_left,
-                result: Err((canister_id, err).into()),
-            }
+                (canister_id, err).into(),
+                round.time,
+            );
         }
     };
 
@@ -386,10 +409,13 @@ fn install_stage_3_process_init_result(
                 result: Ok((new_canister, total_heap_delta)),
             }
         }
-        Err(err) => InstallCodeRoutineResult::Finished {
-            instructions_left: output.num_instructions_left,
-            result: Err((canister_id, err).into()),
-        },
+        Err(err) => finish_failed_execution(
+            new_canister,
+            system_state_changes,
+            output.num_instructions_left,
+            (canister_id, err).into(),
+            round.time,
+        ),
     }
 }
 
@@ -440,7 +466,7 @@ impl PausedInstallCodeRoutine for PausedInitExecution {
     }
 
     fn abort(self: Box<Self>) {
-        todo!()
+        self.paused_wasm_execution.abort();
     }
 }
 
@@ -469,10 +495,11 @@ impl PausedInstallCodeRoutine for PausedStartExecutionDuringInstall {
             self.paused_wasm_execution.resume(execution_state);
         new_canister.execution_state = Some(execution_state);
         match wasm_execution_result {
-            WasmExecutionResult::Finished(slice, output, _system_state_changes) => {
+            WasmExecutionResult::Finished(slice, output, system_state_changes) => {
                 update_round_limits(round_limits, &slice);
                 install_stage_2a_process_start_result(
                     output,
+                    system_state_changes,
                     self.context_sender,
                     self.context_arg,
                     new_canister,
@@ -496,6 +523,6 @@ impl PausedInstallCodeRoutine for PausedStartExecutionDuringInstall {
     }
 
     fn abort(self: Box<Self>) {
-        todo!()
+        self.paused_wasm_execution.abort();
     }
 }
@@ -2,8 +2,10 @@
 // and upgrades.
 
 use ic_base_types::NumBytes;
-use ic_replicated_state::CanisterState;
-use ic_types::NumInstructions;
+use ic_interfaces::execution_environment::HypervisorError;
+use ic_replicated_state::{CanisterLog, CanisterState};
+use ic_system_api::sandbox_safe_system_state::SystemStateChanges;
+use ic_types::{NumInstructions, Time};
 
 use crate::{
     canister_manager::CanisterManagerError, execution_environment::RoundContext, RoundLimits,
@@ -19,16 +21,74 @@ use crate::{
 pub(crate) enum InstallCodeRoutineResult {
     Finished {
         instructions_left: NumInstructions,
-        result: Result<(CanisterState, NumBytes), CanisterManagerError>,
+        result: Result<(CanisterState, NumBytes), InstallCodeRoutineError>,
     },
     Paused {
         paused_execution: Box<dyn PausedInstallCodeRoutine>,
     },
 }
 
+/// The error of a failed installation or upgrade routine.
+#[derive(Debug)]
+pub(crate) struct InstallCodeRoutineError {
+    pub err: CanisterManagerError,
+    /// The canister log including the records of all executions of the
+    /// routine, if any Wasm code was executed. The caller should keep it in
+    /// the old state, so that debug prints and traps of a failed installation
+    /// or upgrade remain readable.
+    pub canister_log: Option<CanisterLog>,
+}
+
+impl From<CanisterManagerError> for InstallCodeRoutineError {
+    fn from(err: CanisterManagerError) -> Self {
+        Self {
+            err,
+            canister_log: None,
+        }
+    }
+}
+
+/// Finishes a routine whose Wasm execution has failed. The log records of the
+/// failed execution are appended to the log of the new canister state, which
+/// is then returned with the error.
+pub(crate) fn finish_failed_execution(
+    mut new_canister: CanisterState,
+    system_state_changes: SystemStateChanges,
+    instructions_left: NumInstructions,
+    err: CanisterManagerError,
+    time: Time,
+) -> InstallCodeRoutineResult {
+    system_state_changes.apply_canister_log_changes(time, &mut new_canister.system_state);
+    InstallCodeRoutineResult::Finished {
+        instructions_left,
+        result: Err(InstallCodeRoutineError {
+            err,
+            canister_log: Some(new_canister.system_state.canister_log),
+        }),
+    }
+}
+
+/// Checks that the Wasm memory of the new canister state, e.g. the initial
+/// memory declared by the new module, does not exceed the canister's
+/// `wasm_memory_limit`.
+pub(crate) fn validate_wasm_memory_limit(
+    new_canister: &CanisterState,
+) -> Result<(), CanisterManagerError> {
+    if let Some(limit) = new_canister.system_state.wasm_memory_limit {
+        let bytes = new_canister.wasm_memory_usage();
+        if bytes > limit {
+            return Err(CanisterManagerError::from((
+                new_canister.canister_id(),
+                HypervisorError::WasmMemoryLimitExceeded { bytes, limit },
+            )));
+        }
+    }
+    Ok(())
+}
+
 /// Represents a paused execution of install code routine,
 /// that can be resumed or aborted.
-pub(crate) trait PausedInstallCodeRoutine: std::fmt::Debug {
+pub(crate) trait PausedInstallCodeRoutine: std::fmt::Debug + Send {
     /// Resumes a paused install code execution.
     fn resume(
         self: Box<Self>,
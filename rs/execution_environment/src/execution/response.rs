@@ -4,6 +4,7 @@
 use crate::execution_environment::{
     ExecuteMessageResult, ExecutionResponse, PausedExecution, RoundContext, RoundLimits,
 };
+use ic_cycles_account_manager::CyclesAccountManager;
 use ic_embedders::wasm_executor::{PausedWasmExecution, WasmExecutionResult};
 use ic_interfaces::messages::CanisterInputMessage;
 use ic_replicated_state::{CallOrigin, CanisterState};
@@ -63,9 +64,13 @@ impl PausedExecution for PausedResponseExecution {
         )
     }
 
-    fn abort(self: Box<Self>) -> CanisterInputMessage {
+    fn abort(
+        self: Box<Self>,
+        _canister: &mut CanisterState,
+        _cycles_account_manager: &CyclesAccountManager,
+    ) -> Option<CanisterInputMessage> {
         self.paused_wasm_execution.abort();
-        CanisterInputMessage::Response(self.original.message)
+        Some(CanisterInputMessage::Response(self.original.message))
     }
 }
 
@@ -98,9 +103,13 @@ impl PausedExecution for PausedCleanupExecution {
         )
     }
 
-    fn abort(self: Box<Self>) -> CanisterInputMessage {
+    fn abort(
+        self: Box<Self>,
+        _canister: &mut CanisterState,
+        _cycles_account_manager: &CyclesAccountManager,
+    ) -> Option<CanisterInputMessage> {
         self.paused_wasm_execution.abort();
-        CanisterInputMessage::Response(self.original.message)
+        Some(CanisterInputMessage::Response(self.original.message))
     }
 }
 
@@ -231,9 +240,9 @@ pub fn execute_response(
     };
 
     let func_ref = match call_origin {
-        CallOrigin::Ingress(_, _) | CallOrigin::CanisterUpdate(_, _) | CallOrigin::Heartbeat => {
-            FuncRef::UpdateClosure(closure)
-        }
+        CallOrigin::Ingress(_, _)
+        | CallOrigin::CanisterUpdate(_, _, _)
+        | CallOrigin::SystemTask => FuncRef::UpdateClosure(closure),
         CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => FuncRef::QueryClosure(closure),
     };
 
@@ -304,9 +313,9 @@ fn execute_response_cleanup(
         .instruction_limits
         .update(instructions_left);
     let func_ref = match original.call_origin {
-        CallOrigin::Ingress(_, _) | CallOrigin::CanisterUpdate(_, _) | CallOrigin::Heartbeat => {
-            FuncRef::UpdateClosure(cleanup_closure)
-        }
+        CallOrigin::Ingress(_, _)
+        | CallOrigin::CanisterUpdate(_, _, _)
+        | CallOrigin::SystemTask => FuncRef::UpdateClosure(cleanup_closure),
         CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => {
             FuncRef::QueryClosure(cleanup_closure)
         }
@@ -392,6 +401,8 @@ fn process_response_result(
                 }
                 Err(callback_err) => {
                     // A trap has occurred when executing the reply/reject closure.
+                    system_state_changes
+                        .apply_canister_log_changes(round.time, &mut canister.system_state);
                     // Execute the cleanup if it exists.
                     match original.callback.on_cleanup.clone() {
                         Some(cleanup_closure) => {
@@ -503,6 +514,8 @@ fn process_cleanup_result(
                 }
                 Err(cleanup_err) => {
                     // Executing the cleanup call back failed.
+                    system_state_changes
+                        .apply_canister_log_changes(round.time, &mut canister.system_state);
                     (
                         cleanup_output.num_instructions_left,
                         NumBytes::from(0),
@@ -0,0 +1,361 @@
+// This module defines how system tasks, i.e. the `canister_heartbeat`,
+// `canister_global_timer` and `canister_on_low_wasm_memory` methods, are
+// executed.
+// See https://smartcontracts.org/docs/interface-spec/index.html#_heartbeat.
+use crate::execution::common::update_round_limits;
+use crate::execution_environment::{
+    ExecuteMessageResult, ExecutionResponse, PausedExecution, RoundContext, RoundLimits,
+};
+use crate::CanisterHeartbeatError;
+use ic_cycles_account_manager::CyclesAccountManager;
+use ic_embedders::wasm_executor::{PausedWasmExecution, WasmExecutionResult};
+use ic_ic00_types::CanisterStatusType;
+use ic_interfaces::execution_environment::HypervisorError;
+use ic_interfaces::messages::CanisterInputMessage;
+use ic_logger::{fatal, info};
+use ic_replicated_state::{
+    CallOrigin, CanisterState, CanisterTimer, ExecutionState, OnLowWasmMemoryHookStatus,
+    SchedulerState, SystemState,
+};
+use ic_system_api::{ApiType, ExecutionParameters};
+use ic_types::messages::CallContextId;
+use ic_types::methods::{FuncRef, SystemMethod, WasmMethod};
+use ic_types::{Cycles, NumBytes, NumInstructions};
+
+/// Holds the result of a system task execution.
+pub enum SystemTaskResult {
+    Finished {
+        /// The canister state resulted from the system task execution.
+        canister_state: CanisterState,
+        /// The size of the heap delta change, if execution is successful
+        /// or the relevant error in case of failure.
+        heap_delta_result: Result<NumBytes, CanisterHeartbeatError>,
+    },
+    Paused {
+        /// The canister state with the changes necessary for DTS such as the
+        /// reserved execution cycles.
+        canister_state: CanisterState,
+        /// The paused execution that the caller can either resume or abort.
+        paused_execution: Box<dyn PausedExecution>,
+    },
+}
+
+impl SystemTaskResult {
+    pub fn new(
+        canister_state: CanisterState,
+        heap_delta_result: Result<NumBytes, CanisterHeartbeatError>,
+    ) -> Self {
+        Self::Finished {
+            canister_state,
+            heap_delta_result,
+        }
+    }
+}
+
+// Validates a canister before executing a system task.
+//
+// Returns the canister split in parts if successful,
+// otherwise `SystemTaskResult` which contains the error.
+fn validate_canister(
+    canister: CanisterState,
+    method: WasmMethod,
+) -> Result<(ExecutionState, SystemState, SchedulerState), SystemTaskResult> {
+    // Check that the status of the canister is Running.
+    if canister.status() != CanisterStatusType::Running {
+        let status = canister.status();
+        return Err(SystemTaskResult::new(
+            canister,
+            Err(CanisterHeartbeatError::CanisterNotRunning { status }),
+        ));
+    }
+
+    let (execution_state, old_system_state, scheduler_state) = canister.into_parts();
+
+    // Validate that the Wasm module is present.
+    let execution_state = match execution_state {
+        Some(es) => es,
+        None => {
+            return Err(SystemTaskResult::new(
+                CanisterState::from_parts(None, old_system_state, scheduler_state),
+                Err(CanisterHeartbeatError::CanisterExecutionFailed(
+                    HypervisorError::WasmModuleNotFound,
+                )),
+            ))
+        }
+    };
+
+    if !execution_state.exports_method(&method) {
+        return Err(SystemTaskResult::new(
+            CanisterState::from_parts(Some(execution_state), old_system_state, scheduler_state),
+            // If the Wasm module does not export the method, then this execution
+            // succeeds as a no-op.
+            Ok(NumBytes::from(0)),
+        ));
+    }
+
+    Ok((execution_state, old_system_state, scheduler_state))
+}
+
+/// Executes a system task, i.e. `canister_heartbeat` or `canister_global_timer`,
+/// of a given canister.
+///
+/// Before executing the system task, the canister is validated to meet the following
+/// conditions:
+///     - The status of the canister is Running.
+///     Otherwise, `CanisterHeartbeatError::CanisterNotRunning` error is returned.
+///     - Wasm module is present.
+///     Otherwise, `CanisterHeartbeatError::CanisterExecutionFailed` error is returned.
+///     - Wasm module exports the system task method.
+///    
+/// When the system task method is not exported, the execution succeeds as a no-op operation.
+/// No changes are applied to the canister state if the canister cannot be validated.
+///
+/// The global timer of the canister is deactivated right before `canister_global_timer`
+/// is executed, so the canister has to set it again to be invoked another time.
+/// In contrast, `canister_on_low_wasm_memory` is marked as executed only once
+/// its execution has finished, so an aborted execution is retried in a later
+/// round. It is then invoked again only after the free Wasm memory has been
+/// above the threshold. The execution succeeds as a no-op if the hook is not
+/// ready, e.g. because it has already been executed in the meantime.
+///
+/// With deterministic time slicing the execution may be paused, in which case
+/// `SystemTaskResult::Paused` is returned with a paused execution that can be
+/// resumed in the following rounds.
+///
+/// Otherwise, returns `SystemTaskResult::Finished` with:
+///
+/// - The updated `CanisterState` if the execution succeeded, otherwise
+/// the old `CanisterState`.
+///
+/// - A result containing the size of the heap delta change if
+/// execution was successful or the relevant `CanisterHeartbeatError` error if execution fails.
+pub fn execute_system_task(
+    canister: CanisterState,
+    system_task: SystemMethod,
+    execution_parameters: ExecutionParameters,
+    round: RoundContext,
+    round_limits: &mut RoundLimits,
+) -> SystemTaskResult {
+    let method = WasmMethod::System(system_task.clone());
+    let memory_usage = canister.memory_usage(round.hypervisor.subnet_type());
+    let compute_allocation = canister.scheduler_state.compute_allocation;
+    let message_instruction_limit = execution_parameters.instruction_limits.message();
+
+    // Validate and extract execution state.
+    let (execution_state, mut system_state, scheduler_state) =
+        match validate_canister(canister, method.clone()) {
+            Ok((execution_state, system_state, scheduler_state)) => {
+                (execution_state, system_state, scheduler_state)
+            }
+            Err(err) => return err,
+        };
+
+    if system_task == SystemMethod::CanisterOnLowWasmMemory
+        && !system_state.on_low_wasm_memory_hook_status.is_ready()
+    {
+        return SystemTaskResult::new(
+            CanisterState::from_parts(Some(execution_state), system_state, scheduler_state),
+            Ok(NumBytes::from(0)),
+        );
+    }
+
+    // Charge for system task execution.
+    if let Err(err) = round.cycles_account_manager.withdraw_execution_cycles(
+        &mut system_state,
+        memory_usage,
+        compute_allocation,
+        message_instruction_limit,
+    ) {
+        return SystemTaskResult::new(
+            CanisterState::from_parts(Some(execution_state), system_state, scheduler_state),
+            Err(CanisterHeartbeatError::OutOfCycles(err)),
+        );
+    }
+
+    if system_task == SystemMethod::CanisterGlobalTimer {
+        system_state.global_timer = CanisterTimer::Inactive;
+    }
+
+    // Execute the system task.
+    let call_context_id = system_state
+        .call_context_manager_mut()
+        .unwrap()
+        .new_call_context(CallOrigin::SystemTask, Cycles::new(0), round.time);
+    let api_type = ApiType::system_task(system_task.clone(), round.time, call_context_id);
+    let (output_execution_state, result) = round.hypervisor.execute_dts(
+        api_type,
+        &system_state,
+        memory_usage,
+        execution_parameters,
+        FuncRef::Method(method),
+        execution_state,
+        round_limits,
+        round.network_topology,
+    );
+    let canister =
+        CanisterState::from_parts(Some(output_execution_state), system_state, scheduler_state);
+    let original = OriginalContext {
+        system_task,
+        call_context_id,
+        message_instruction_limit,
+    };
+    process_system_task_result(canister, result, original, round, round_limits)
+}
+
+fn process_system_task_result(
+    mut canister: CanisterState,
+    result: WasmExecutionResult,
+    original: OriginalContext,
+    round: RoundContext,
+    round_limits: &mut RoundLimits,
+) -> SystemTaskResult {
+    match result {
+        WasmExecutionResult::Paused(slice, paused_wasm_execution) => {
+            update_round_limits(round_limits, &slice);
+            let paused_execution = Box::new(PausedSystemTaskExecution {
+                paused_wasm_execution,
+                original,
+            });
+            SystemTaskResult::Paused {
+                canister_state: canister,
+                paused_execution,
+            }
+        }
+        WasmExecutionResult::Finished(slice, output, system_state_changes) => {
+            update_round_limits(round_limits, &slice);
+            let heap_delta = match output.wasm_result {
+                Ok(ref opt_result) => {
+                    if opt_result.is_some() {
+                        fatal!(round.log, "[EXC-BUG] System methods cannot use msg_reply.");
+                    }
+                    // TODO(RUN-265): Replace `unwrap` with a proper execution error
+                    // here because subnet available memory may have changed since
+                    // the start of execution.
+                    round_limits
+                        .subnet_available_memory
+                        .try_decrement(output.allocated_bytes, output.allocated_message_bytes)
+                        .unwrap();
+                    system_state_changes.apply_changes(
+                        round.time,
+                        &mut canister.system_state,
+                        round.network_topology,
+                        round.hypervisor.subnet_id(),
+                        round.log,
+                    );
+                    Ok(NumBytes::from(
+                        (output.instance_stats.dirty_pages * ic_sys::PAGE_SIZE) as u64,
+                    ))
+                }
+                Err(ref err) => {
+                    system_state_changes
+                        .apply_canister_log_changes(round.time, &mut canister.system_state);
+                    Err(CanisterHeartbeatError::CanisterExecutionFailed(err.clone()))
+                }
+            };
+            let _action = canister
+                .system_state
+                .call_context_manager_mut()
+                .unwrap()
+                .on_canister_result(original.call_context_id, output.wasm_result);
+
+            // Refund the canister with any cycles left after message execution.
+            round.cycles_account_manager.refund_execution_cycles(
+                &mut canister.system_state,
+                output.num_instructions_left,
+                original.message_instruction_limit,
+            );
+
+            if original.system_task == SystemMethod::CanisterOnLowWasmMemory {
+                canister.system_state.on_low_wasm_memory_hook_status =
+                    OnLowWasmMemoryHookStatus::Executed;
+            }
+
+            SystemTaskResult::new(canister, heap_delta)
+        }
+    }
+}
+
+/// Context variables that remain the same throughout the entire deterministic
+/// time slicing execution of a system task.
+#[derive(Debug)]
+struct OriginalContext {
+    system_task: SystemMethod,
+    call_context_id: CallContextId,
+    message_instruction_limit: NumInstructions,
+}
+
+#[derive(Debug)]
+struct PausedSystemTaskExecution {
+    paused_wasm_execution: Box<dyn PausedWasmExecution>,
+    original: OriginalContext,
+}
+
+impl PausedExecution for PausedSystemTaskExecution {
+    fn resume(
+        self: Box<Self>,
+        mut canister: CanisterState,
+        round: RoundContext,
+        round_limits: &mut RoundLimits,
+    ) -> ExecuteMessageResult {
+        let execution_state = canister.execution_state.take().unwrap();
+        let (execution_state, result) = self.paused_wasm_execution.resume(execution_state);
+        canister.execution_state = Some(execution_state);
+        let system_task = self.original.system_task.clone();
+        let log = round.log;
+        let result =
+            process_system_task_result(canister, result, self.original, round, round_limits);
+        match result {
+            SystemTaskResult::Finished {
+                canister_state,
+                heap_delta_result,
+            } => {
+                let heap_delta = match heap_delta_result {
+                    Ok(heap_delta) => heap_delta,
+                    Err(err) => {
+                        info!(
+                            log,
+                            "Error executing {} on canister {} with failure `{}`",
+                            system_task,
+                            canister_state.canister_id(),
+                            err
+                        );
+                        NumBytes::from(0)
+                    }
+                };
+                ExecuteMessageResult::Finished {
+                    canister: canister_state,
+                    response: ExecutionResponse::Empty,
+                    heap_delta,
+                }
+            }
+            SystemTaskResult::Paused {
+                canister_state,
+                paused_execution,
+            } => ExecuteMessageResult::Paused {
+                canister: canister_state,
+                paused_execution,
+            },
+        }
+    }
+
+    fn abort(
+        self: Box<Self>,
+        canister: &mut CanisterState,
+        cycles_account_manager: &CyclesAccountManager,
+    ) -> Option<CanisterInputMessage> {
+        self.paused_wasm_execution.abort();
+        // None of the instructions executed so far are committed.
+        cycles_account_manager.refund_execution_cycles(
+            &mut canister.system_state,
+            self.original.message_instruction_limit,
+            self.original.message_instruction_limit,
+        );
+        // The system task doesn't have a message to retry. Close its call
+        // context instead. The scheduler enqueues a new system task in the
+        // next round if necessary.
+        if let Some(call_context_manager) = canister.system_state.call_context_manager_mut() {
+            call_context_manager.on_canister_result(self.original.call_context_id, Ok(None));
+        }
+        None
+    }
+}
@@ -4,13 +4,16 @@
 // and https://internetcomputer.org/docs/current/references/ic-interface-spec/#system-api-upgrades
 use crate::canister_manager::{canister_layout, CanisterManagerError, InstallCodeContext};
 use crate::execution::common::update_round_limits;
-use crate::execution::install_code::{InstallCodeRoutineResult, PausedInstallCodeRoutine};
+use crate::execution::install_code::{
+    finish_failed_execution, validate_wasm_memory_limit, InstallCodeRoutineResult,
+    PausedInstallCodeRoutine,
+};
 use crate::execution_environment::{CompilationCostHandling, RoundContext, RoundLimits};
 use ic_base_types::{NumBytes, PrincipalId};
 use ic_embedders::wasm_executor::{PausedWasmExecution, WasmExecutionResult};
 use ic_interfaces::execution_environment::{HypervisorError, WasmExecutionOutput};
 use ic_logger::{fatal, info};
-use ic_replicated_state::{CanisterState, Memory, SystemState};
+use ic_replicated_state::{CanisterState, CanisterTimer, Memory, SystemState};
 use ic_sys::PAGE_SIZE;
 use ic_system_api::sandbox_safe_system_state::SystemStateChanges;
 use ic_system_api::{ApiType, ExecutionParameters};
@@ -89,7 +92,11 @@ pub(crate) fn execute_upgrade(
         None => {
             return InstallCodeRoutineResult::Finished {
                 instructions_left: execution_parameters.instruction_limits.message(),
-                result: Err((canister_id, HypervisorError::WasmModuleNotFound).into()),
+                result: Err(CanisterManagerError::from((
+                    canister_id,
+                    HypervisorError::WasmModuleNotFound,
+                ))
+                .into()),
             }
         }
         Some(es) => es,
@@ -210,10 +217,13 @@ fn upgrade_stage_1_process_pre_upgrade_result(
                 compilation_cost_handling,
             )
         }
-        Err(err) => InstallCodeRoutineResult::Finished {
+        Err(err) => finish_failed_execution(
+            new_canister,
+            system_state_changes,
             instructions_left,
-            result: Err((canister_id, err).into()),
-        },
+            (canister_id, err).into(),
+            time,
+        ),
     }
 }
 
@@ -243,6 +253,10 @@ fn upgrade_stage_2_and_3a_create_execution_state_and_call_start(
         .instruction_limits
         .update(instructions_left);
 
+    // The global timer is deactivated on upgrade. This happens after
+    // `canister_pre_upgrade`, so that the new code starts without a timer.
+    new_canister.system_state.global_timer = CanisterTimer::Inactive;
+
     // Stage 2: create a new execution state based on the new Wasm code.
     // Replace the execution state of the canister with a new execution state, but
     // persist the stable memory (if it exists).
@@ -256,10 +270,14 @@ fn upgrade_stage_2_and_3a_create_execution_state_and_call_start(
             compilation_cost_handling,
         ) {
             Err(err) => {
-                return InstallCodeRoutineResult::Finished {
+                // Keep the log records of `canister_pre_upgrade()`.
+                return finish_failed_execution(
+                    new_canister,
+                    SystemStateChanges::default(),
                     instructions_left,
-                    result: Err((canister_id, err).into()),
-                };
+                    (canister_id, err).into(),
+                    time,
+                );
             }
             Ok((instructions_from_compilation, mut execution_state)) => {
                 let stable_memory = match new_canister.execution_state {
@@ -293,19 +311,35 @@ fn upgrade_stage_2_and_3a_create_execution_state_and_call_start(
     let subnet_type = round.hypervisor.subnet_type();
     if let MemoryAllocation::Reserved(bytes) = desired_memory_allocation {
         if bytes < new_canister.memory_usage(subnet_type) {
-            return InstallCodeRoutineResult::Finished {
-                instructions_left,
-                result: Err(CanisterManagerError::NotEnoughMemoryAllocationGiven {
-                    canister_id,
-                    memory_allocation_given: desired_memory_allocation,
-                    memory_usage_needed: new_canister.memory_usage(subnet_type),
-                }),
+            let err = CanisterManagerError::NotEnoughMemoryAllocationGiven {
+                canister_id,
+                memory_allocation_given: desired_memory_allocation,
+                memory_usage_needed: new_canister.memory_usage(subnet_type),
             };
+            // Keep the log records of `canister_pre_upgrade()`.
+            return finish_failed_execution(
+                new_canister,
+                SystemStateChanges::default(),
+                instructions_left,
+                err,
+                time,
+            );
         }
         execution_parameters.canister_memory_limit = bytes;
     }
     new_canister.system_state.memory_allocation = desired_memory_allocation;
 
+    if let Err(err) = validate_wasm_memory_limit(&new_canister) {
+        // Keep the log records of `canister_pre_upgrade()`.
+        return finish_failed_execution(
+            new_canister,
+            SystemStateChanges::default(),
+            instructions_left,
+            err,
+            time,
+        );
+    }
+
     // Stage 3: invoke the `start()` method (if present).
 
     let method = WasmMethod::System(SystemMethod::CanisterStart);
@@ -344,10 +378,11 @@ fn upgrade_stage_2_and_3a_create_execution_state_and_call_start(
         new_canister.execution_state = Some(output_execution_state);
 
         match wasm_execution_result {
-            WasmExecutionResult::Finished(slice, output, _system_state_changes) => {
+            WasmExecutionResult::Finished(slice, output, system_state_changes) => {
                 update_round_limits(round_limits, &slice);
                 upgrade_stage_3b_process_start_result(
                     output,
+                    system_state_changes,
                     context.sender,
                     context.arg,
                     new_canister,
@@ -378,9 +413,10 @@ fn upgrade_stage_2_and_3a_create_execution_state_and_call_start(
 #[allow(clippy::too_many_arguments)]
 fn upgrade_stage_3b_process_start_result(
     output: WasmExecutionOutput,
+    system_state_changes: SystemStateChanges,
     context_sender: PrincipalId,
     context_arg: Vec<u8>,
-    new_canister: CanisterState,
+    mut new_canister: CanisterState,
     execution_parameters: ExecutionParameters,
     mut total_heap_delta: NumBytes,
     time: Time,
@@ -402,6 +438,10 @@ fn upgrade_stage_3b_process_start_result(
                 .subnet_available_memory
                 .try_decrement(output.allocated_bytes, output.allocated_message_bytes)
                 .unwrap();
+            // `start()` cannot change the system state, but its debug
+            // prints belong in the canister log.
+            system_state_changes
+                .apply_canister_log_changes(round.time, &mut new_canister.system_state);
             total_heap_delta +=
                 NumBytes::from((output.instance_stats.dirty_pages * PAGE_SIZE) as u64);
             upgrade_stage_4a_call_post_upgrade(
@@ -416,10 +456,13 @@ fn upgrade_stage_3b_process_start_result(
                 round_limits,
             )
         }
-        Err(err) => InstallCodeRoutineResult::Finished {
+        Err(err) => finish_failed_execution(
+            new_canister,
+            system_state_changes,
             instructions_left,
-            result: Err((canister_id, err).into()),
-        },
+            (canister_id, err).into(),
+            round.time,
+        ),
     }
 }
 
@@ -549,10 +592,13 @@ fn upgrade_stage_4b_process_post_upgrade_result(
                 round,
             )
         }
-        Err(err) => InstallCodeRoutineResult::Finished {
+        Err(err) => finish_failed_execution(
+            new_canister,
+            system_state_changes,
             instructions_left,
-            result: Err((canister_id, err).into()),
-        },
+            (canister_id, err).into(),
+            round.time,
+        ),
     }
 }
 
@@ -635,7 +681,7 @@ impl PausedInstallCodeRoutine for PausedPreUpgradeExecution {
     }
 
     fn abort(self: Box<Self>) {
-        todo!()
+        self.paused_wasm_execution.abort();
     }
 }
 
@@ -665,10 +711,11 @@ impl PausedInstallCodeRoutine for PausedStartExecutionDuringUpgrade {
             self.paused_wasm_execution.resume(execution_state);
         new_canister.execution_state = Some(execution_state);
         match wasm_execution_result {
-            WasmExecutionResult::Finished(slice, output, _system_state_changes) => {
+            WasmExecutionResult::Finished(slice, output, system_state_changes) => {
                 update_round_limits(round_limits, &slice);
                 upgrade_stage_3b_process_start_result(
                     output,
+                    system_state_changes,
                     self.context_sender,
                     self.context_arg,
                     new_canister,
@@ -692,7 +739,7 @@ impl PausedInstallCodeRoutine for PausedStartExecutionDuringUpgrade {
     }
 
     fn abort(self: Box<Self>) {
-        todo!()
+        self.paused_wasm_execution.abort();
     }
 }
 
@@ -744,6 +791,6 @@ impl PausedInstallCodeRoutine for PausedPostUpgradeExecution {
     }
 
     fn abort(self: Box<Self>) {
-        todo!()
+        self.paused_wasm_execution.abort();
     }
 }
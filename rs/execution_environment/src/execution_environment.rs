@@ -1,7 +1,10 @@
-use crate::canister_manager::{CanisterManagerError, DtsInstallCodeResult};
+use crate::canister_manager::{
+    CanisterManagerError, DtsInstallCodeResult, PausedInstallCodeExecution,
+};
 use crate::execution::{
-    heartbeat::execute_heartbeat, nonreplicated_query::execute_non_replicated_query,
+    nonreplicated_query::execute_non_replicated_query,
     response::execute_response,
+    system_task::{execute_system_task, SystemTaskResult},
 };
 use crate::{
     canister_manager::{
@@ -20,15 +23,17 @@ use ic_config::execution_environment::Config as ExecutionConfig;
 use ic_config::flag_status::FlagStatus;
 use ic_crypto::derive_tecdsa_public_key;
 use ic_cycles_account_manager::{
-    CyclesAccountManager, IngressInductionCost, IngressInductionCostError,
+    CyclesAccountManager, IngressInductionCost, IngressInductionCostError, ResourceSaturation,
 };
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CanisterHttpRequestArgs, CanisterIdRecord, CanisterSettingsArgs, CanisterStatusType,
+    CanisterChangeDetails, CanisterChangeOrigin, CanisterHttpRequestArgs, CanisterIdRecord,
+    CanisterInfoRequest, CanisterSettingsArgs, CanisterSnapshotArgs, CanisterStatusType,
     ComputeInitialEcdsaDealingsArgs, CreateCanisterArgs, ECDSAPublicKeyArgs,
     ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob, InstallCodeArgs, Method as Ic00Method,
     Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs,
-    SetControllerArgs, SetupInitialDKGArgs, SignWithECDSAArgs, UpdateSettingsArgs, IC_00,
+    SetControllerArgs, SetupInitialDKGArgs, SignWithECDSAArgs, TakeCanisterSnapshotArgs,
+    UpdateSettingsArgs, IC_00,
 };
 use ic_interfaces::execution_environment::{
     AvailableMemory, CanisterOutOfCyclesError, RegistryExecutionSettings,
@@ -62,6 +67,8 @@ use ic_types::{
         is_subnet_message, AnonymousQuery, Payload, RejectContext, Request, Response,
         SignedIngressContent, StopCanisterContext,
     },
+    methods::SystemMethod,
+    time::NO_DEADLINE,
     CanisterId, ComputeAllocation, Cycles, NumBytes, NumInstructions, SubnetId, Time,
 };
 use ic_wasm_types::WasmHash;
@@ -211,8 +218,26 @@ pub trait PausedExecution: std::fmt::Debug + Send {
         round_limits: &mut RoundLimits,
     ) -> ExecuteMessageResult;
 
-    /// Aborts the paused execution and returns the original message.
-    fn abort(self: Box<Self>) -> CanisterInputMessage;
+    /// Aborts the paused execution and returns the original message, if any.
+    /// System tasks don't have a message: an aborted system task is dropped
+    /// because the scheduler enqueues system tasks in each round.
+    ///
+    /// The execution cycles reserved at the start of the execution are
+    /// refunded because the message is executed from scratch later.
+    fn abort(
+        self: Box<Self>,
+        canister: &mut CanisterState,
+        cycles_account_manager: &CyclesAccountManager,
+    ) -> Option<CanisterInputMessage>;
+}
+
+/// A paused `install_code` execution together with the original message and
+/// the data necessary to process its result.
+#[derive(Debug)]
+struct PausedInstallCode {
+    paused_execution: PausedInstallCodeExecution,
+    message: RequestOrIngress,
+    new_wasm_hash: WasmHash,
 }
 
 /// Stores all paused executions keyed by their ids.
@@ -226,6 +251,10 @@ struct PausedExecutionRegistry {
 
     // All paused executions.
     registry: HashMap<PausedExecutionId, Box<dyn PausedExecution>>,
+
+    // All paused `install_code` executions. They share the ids with the
+    // other paused executions.
+    install_code_registry: HashMap<PausedExecutionId, PausedInstallCode>,
 }
 
 /// ExecutionEnvironment is the component responsible for executing messages
@@ -243,7 +272,8 @@ pub struct ExecutionEnvironment {
     paused_execution_registry: Arc<Mutex<PausedExecutionRegistry>>,
 }
 
-/// Errors when executing `canister_heartbeat`.
+/// Errors when executing a system task, i.e. `canister_heartbeat` or
+/// `canister_global_timer`.
 #[derive(Debug, Eq, PartialEq)]
 pub enum CanisterHeartbeatError {
     /// The canister isn't running.
@@ -253,7 +283,7 @@ pub enum CanisterHeartbeatError {
 
     OutOfCycles(CanisterOutOfCyclesError),
 
-    /// Execution failed while executing the `canister_heartbeat`.
+    /// Execution failed while executing the system task.
     CanisterExecutionFailed(HypervisorError),
 }
 
@@ -301,6 +331,7 @@ impl ExecutionEnvironment {
     ) -> Self {
         let canister_manager_config: CanisterMgrConfig = CanisterMgrConfig::new(
             config.subnet_memory_capacity,
+            config.subnet_memory_threshold,
             config.default_provisional_cycles_balance,
             config.default_freeze_threshold,
             own_subnet_id,
@@ -373,6 +404,7 @@ impl ExecutionEnvironment {
                                 originator_reply_callback: request.sender_reply_callback,
                                 refund: request.payment,
                                 response_payload: response.response_payload.clone(),
+                                deadline: request.deadline,
                             }
                             .into(),
                         );
@@ -436,11 +468,25 @@ impl ExecutionEnvironment {
             Ok(Ic00Method::UninstallCode) => {
                 let res = match CanisterIdRecord::decode(payload) {
                     Err(err) => Err(candid_error_to_user_error(err)),
-                    Ok(args) => self
+                    Ok(args) => {
+                        // Paused executions cannot continue without the code.
+                        if let Some(canister) = state.canister_state_mut(&args.get_canister_id()) {
+                            self.abort_canister(canister);
+                        }
+                        self
                         .canister_manager
                         .uninstall_code(args.get_canister_id(), *msg.sender(), &mut state)
-                        .map(|()| EmptyBlob::encode())
-                        .map_err(|err| err.into()),
+                        .map(|()| {
+                            add_canister_change(
+                                &mut state,
+                                args.get_canister_id(),
+                                canister_change_origin(&msg),
+                                CanisterChangeDetails::code_uninstall(),
+                            );
+                            EmptyBlob::encode()
+                        })
+                        .map_err(|err| err.into())
+                    }
                 };
                 Some((res, msg.take_cycles()))
             }
@@ -455,12 +501,24 @@ impl ExecutionEnvironment {
                         let canister_id = args.get_canister_id();
                         let result = match CanisterSettings::try_from(args.settings) {
                             Err(err) => Err(err.into()),
-                            Ok(settings) => self.update_settings(
-                                *msg.sender(),
-                                settings,
-                                canister_id,
-                                &mut state,
-                            ),
+                            Ok(settings) => {
+                                let changes_controllers = settings.controller().is_some()
+                                    || settings.controllers().is_some();
+                                let result = self.update_settings(
+                                    *msg.sender(),
+                                    settings,
+                                    canister_id,
+                                    &mut state,
+                                );
+                                if result.is_ok() && changes_controllers {
+                                    record_controllers_change(
+                                        &mut state,
+                                        canister_id,
+                                        canister_change_origin(&msg),
+                                    );
+                                }
+                                result
+                            }
                         };
                         // The induction cost of `UpdateSettings` is charged
                         // after applying the new settings to allow users to
@@ -510,7 +568,14 @@ impl ExecutionEnvironment {
                             args.get_new_controller(),
                             &mut state,
                         )
-                        .map(|()| EmptyBlob::encode())
+                        .map(|()| {
+                            record_controllers_change(
+                                &mut state,
+                                args.get_canister_id(),
+                                canister_change_origin(&msg),
+                            );
+                            EmptyBlob::encode()
+                        })
                         .map_err(|err| err.into()),
                 };
                 Some((res, msg.take_cycles()))
@@ -567,6 +632,97 @@ impl ExecutionEnvironment {
                 Some((res, msg.take_cycles()))
             }
 
+            Ok(Ic00Method::TakeCanisterSnapshot) => {
+                let res = match TakeCanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => self
+                        .canister_manager
+                        .take_canister_snapshot(
+                            *msg.sender(),
+                            args,
+                            &mut state,
+                            instruction_limits.message(),
+                            round_limits,
+                        )
+                        .map(|response| response.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::LoadCanisterSnapshot) => {
+                let res = match CanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => self
+                        .canister_manager
+                        .load_canister_snapshot(*msg.sender(), args, &mut state)
+                        .map(|()| EmptyBlob::encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::ListCanisterSnapshots) => {
+                let res = match CanisterIdRecord::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => self
+                        .canister_manager
+                        .list_canister_snapshots(*msg.sender(), args.get_canister_id(), &state)
+                        .map(|response| response.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::DeleteCanisterSnapshot) => {
+                let res = match CanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => self
+                        .canister_manager
+                        .delete_canister_snapshot(*msg.sender(), args, &mut state)
+                        .map(|()| EmptyBlob::encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::FetchCanisterLogs) => {
+                let res = match CanisterIdRecord::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => self
+                        .canister_manager
+                        .fetch_canister_logs(*msg.sender(), args.get_canister_id(), &state)
+                        .map(|response| response.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::CanisterInfo) => match &msg {
+                RequestOrIngress::Ingress(_) => Some((
+                    Err(UserError::new(
+                        ErrorCode::CanisterMethodNotFound,
+                        "canister_info can only be called by other canisters, not via ingress messages.",
+                    )),
+                    msg.take_cycles(),
+                )),
+                RequestOrIngress::Request(_) => {
+                    let res = match CanisterInfoRequest::decode(payload) {
+                        Err(err) => Err(candid_error_to_user_error(err)),
+                        Ok(args) => self
+                            .canister_manager
+                            .get_canister_info(
+                                args.canister_id(),
+                                args.num_requested_changes(),
+                                &state,
+                            )
+                            .map(|response| response.encode())
+                            .map_err(|err| err.into()),
+                    };
+                    Some((res, msg.take_cycles()))
+                }
+            },
+
             Ok(Ic00Method::RawRand) => {
                 let res = match EmptyBlob::decode(payload) {
                     Err(err) => Err(candid_error_to_user_error(err)),
@@ -685,6 +841,7 @@ impl ExecutionEnvironment {
                                         message: reject_message,
                                     },
                                 ),
+                                deadline: request.deadline,
                             }
                             .into(),
                         );
@@ -810,7 +967,14 @@ impl ExecutionEnvironment {
                                     &registry_settings.provisional_whitelist,
                                     registry_settings.max_number_of_canisters,
                                 )
-                                .map(|canister_id| CanisterIdRecord::from(canister_id).encode())
+                                .map(|canister_id| {
+                                    record_canister_creation(
+                                        &mut state,
+                                        canister_id,
+                                        canister_change_origin(&msg),
+                                    );
+                                    CanisterIdRecord::from(canister_id).encode()
+                                })
                                 .map_err(|err| err.into()),
                             Err(err) => Err(err.into()),
                         }
@@ -947,8 +1111,12 @@ impl ExecutionEnvironment {
             time,
         };
 
-        let execution_parameters =
-            self.execution_parameters(&canister, instruction_limits, ExecutionMode::Replicated);
+        let execution_parameters = self.execution_parameters(
+            &canister,
+            instruction_limits,
+            ExecutionMode::Replicated,
+            self.subnet_memory_saturation(&round_limits.subnet_available_memory),
+        );
 
         execute_call(
             canister,
@@ -960,34 +1128,67 @@ impl ExecutionEnvironment {
         )
     }
 
-    /// Executes a heartbeat of a given canister.
-    pub fn execute_canister_heartbeat(
+    /// Executes a system task, i.e. a heartbeat or a global timer, of a given canister.
+    ///
+    /// If the execution of a heartbeat is paused, then the paused execution is
+    /// added to the task queue of the canister and the result is the heap
+    /// delta of zero.
+    pub fn execute_canister_system_task(
         &self,
         canister: CanisterState,
+        system_task: SystemMethod,
         instruction_limits: InstructionLimits,
         network_topology: Arc<NetworkTopology>,
         time: Time,
         round_limits: &mut RoundLimits,
     ) -> (CanisterState, Result<NumBytes, CanisterHeartbeatError>) {
-        // A heartbeat is expected to finish quickly, so DTS is not supported for it.
-        let instruction_limits = InstructionLimits::new(
-            FlagStatus::Disabled,
-            instruction_limits.slice(),
-            instruction_limits.slice(),
+        let instruction_limits = match system_task {
+            SystemMethod::CanisterHeartbeat => instruction_limits,
+            // The global timer is deactivated and the low Wasm memory hook is
+            // marked as executed before the execution, so neither would run
+            // again if a paused execution was aborted. DTS is not supported for
+            // them.
+            _ => InstructionLimits::new(
+                FlagStatus::Disabled,
+                instruction_limits.slice(),
+                instruction_limits.slice(),
+            ),
+        };
+        let execution_parameters = self.execution_parameters(
+            &canister,
+            instruction_limits,
+            ExecutionMode::Replicated,
+            self.subnet_memory_saturation(&round_limits.subnet_available_memory),
         );
-        let execution_parameters =
-            self.execution_parameters(&canister, instruction_limits, ExecutionMode::Replicated);
-        let (canister, result) = execute_heartbeat(
+        let round = RoundContext {
+            network_topology: &*network_topology,
+            hypervisor: &self.hypervisor,
+            cycles_account_manager: &self.cycles_account_manager,
+            log: &self.log,
+            time,
+        };
+        let (canister, result) = match execute_system_task(
             canister,
-            network_topology,
+            system_task.clone(),
             execution_parameters,
-            self.own_subnet_type,
-            time,
-            &self.hypervisor,
-            &self.cycles_account_manager,
+            round,
             round_limits,
-        )
-        .into_parts();
+        ) {
+            SystemTaskResult::Finished {
+                canister_state,
+                heap_delta_result,
+            } => (canister_state, heap_delta_result),
+            SystemTaskResult::Paused {
+                canister_state,
+                paused_execution,
+            } => {
+                let (canister, heap_delta, _) = self.process_result(ExecuteMessageResult::Paused {
+                    canister: canister_state,
+                    paused_execution,
+                });
+                (canister, Ok(heap_delta))
+            }
+        };
         if let Err(err) = &result {
             // We should monitor all errors in the system subnets and only
             // system errors on other subnets.
@@ -997,7 +1198,8 @@ impl ExecutionEnvironment {
                 if log_count < LOG_FIRST_N_HEARTBEAT || log_count % LOG_ONE_HEARTBEAT_OUT_OF == 0 {
                     warn!(
                         self.log,
-                        "Error executing heartbeat on canister {} with failure `{}`",
+                        "Error executing {} on canister {} with failure `{}`",
+                        system_task,
                         canister.canister_id(),
                         err;
                         messaging.canister_id => canister.canister_id().to_string(),
@@ -1029,6 +1231,7 @@ impl ExecutionEnvironment {
         canister: &CanisterState,
         instruction_limits: InstructionLimits,
         execution_mode: ExecutionMode,
+        subnet_memory_saturation: ResourceSaturation,
     ) -> ExecutionParameters {
         ExecutionParameters {
             instruction_limits,
@@ -1036,9 +1239,21 @@ impl ExecutionEnvironment {
             compute_allocation: canister.scheduler_state.compute_allocation,
             subnet_type: self.own_subnet_type,
             execution_mode,
+            subnet_memory_saturation,
         }
     }
 
+    /// Returns the memory usage of the subnet relative to the threshold above
+    /// which allocating storage reserves cycles.
+    pub(crate) fn subnet_memory_saturation(
+        &self,
+        subnet_available_memory: &SubnetAvailableMemory,
+    ) -> ResourceSaturation {
+        let capacity = self.config.subnet_memory_capacity.get();
+        let usage = (capacity as i64 - subnet_available_memory.get_total_memory()).max(0) as u64;
+        ResourceSaturation::new(usage, self.config.subnet_memory_threshold.get(), capacity)
+    }
+
     fn create_canister(
         &self,
         sender: PrincipalId,
@@ -1058,8 +1273,16 @@ impl ExecutionEnvironment {
                     state,
                 );
                 (
-                    res.map(|new_canister_id| CanisterIdRecord::from(new_canister_id).encode())
-                        .map_err(|err| err.into()),
+                    res.map(|new_canister_id| {
+                        // Only canisters can call `create_canister`.
+                        record_canister_creation(
+                            state,
+                            new_canister_id,
+                            CanisterChangeOrigin::from_canister(sender),
+                        );
+                        CanisterIdRecord::from(new_canister_id).encode()
+                    })
+                    .map_err(|err| err.into()),
                     cycles,
                 )
             }
@@ -1198,8 +1421,12 @@ impl ExecutionEnvironment {
         network_topology: Arc<NetworkTopology>,
         round_limits: &mut RoundLimits,
     ) -> ExecuteMessageResult {
-        let execution_parameters =
-            self.execution_parameters(&canister, instruction_limits, ExecutionMode::Replicated);
+        let execution_parameters = self.execution_parameters(
+            &canister,
+            instruction_limits,
+            ExecutionMode::Replicated,
+            self.subnet_memory_saturation(&round_limits.subnet_available_memory),
+        );
         let round = RoundContext {
             network_topology: &*network_topology,
             hypervisor: &self.hypervisor,
@@ -1307,8 +1534,12 @@ impl ExecutionEnvironment {
                 self.config.max_instructions_for_message_acceptance_calls,
                 self.config.max_instructions_for_message_acceptance_calls,
             );
-            let execution_parameters =
-                self.execution_parameters(canister, instruction_limits, execution_mode);
+            let execution_parameters = self.execution_parameters(
+                canister,
+                instruction_limits,
+                execution_mode,
+                ResourceSaturation::default(),
+            );
             inspect_message::execute_inspect_message(
                 state.time(),
                 canister.clone(),
@@ -1342,8 +1573,12 @@ impl ExecutionEnvironment {
             max_instructions_per_message,
             max_instructions_per_message,
         );
-        let execution_parameters =
-            self.execution_parameters(&canister, instruction_limits, ExecutionMode::NonReplicated);
+        let execution_parameters = self.execution_parameters(
+            &canister,
+            instruction_limits,
+            ExecutionMode::NonReplicated,
+            ResourceSaturation::default(),
+        );
         let subnet_available_memory = subnet_memory_capacity(&self.config);
         let mut round_limits = RoundLimits {
             instructions: as_round_instructions(max_instructions_per_message),
@@ -1403,6 +1638,7 @@ impl ExecutionEnvironment {
                     originator_reply_callback: req.sender_reply_callback,
                     refund,
                     response_payload: payload,
+                    deadline: req.deadline,
                 };
 
                 state.push_subnet_output_response(response.into());
@@ -1484,6 +1720,7 @@ impl ExecutionEnvironment {
                             code: RejectCode::CanisterReject,
                             message: format!("Canister {}'s stop request cancelled", canister_id),
                         }),
+                        deadline: NO_DEADLINE,
                     };
                     state.push_subnet_output_response(response.into());
                 }
@@ -1688,14 +1925,24 @@ impl ExecutionEnvironment {
         // Start logging execution time for `install_code`.
         let timer = Timer::start();
 
-        let (install_context, old_canister) = match decode_input_and_take_canister(&msg, &mut state)
-        {
-            Ok(result) => result,
-            Err(err) => {
-                let refund = msg.take_cycles();
-                return self.finish_subnet_message_execution(state, msg, Err(err), refund, timer);
-            }
-        };
+        let (install_context, mut old_canister) =
+            match decode_input_and_take_canister(&msg, &mut state) {
+                Ok(result) => result,
+                Err(err) => {
+                    let refund = msg.take_cycles();
+                    return self.finish_subnet_message_execution(
+                        state,
+                        msg,
+                        Err(err),
+                        refund,
+                        timer,
+                    );
+                }
+            };
+
+        // Paused executions cannot continue after the code changes. The
+        // aborted messages are executed again after `install_code` finishes.
+        self.abort_canister(&mut old_canister);
 
         let canister_id = old_canister.canister_id();
         let new_wasm_hash = WasmHash::from(&install_context.wasm_module);
@@ -1723,6 +1970,8 @@ impl ExecutionEnvironment {
             compute_allocation: ComputeAllocation::default(),
             subnet_type: state.metadata.own_subnet_type,
             execution_mode: ExecutionMode::Replicated,
+            subnet_memory_saturation: self
+                .subnet_memory_saturation(&round_limits.subnet_available_memory),
         };
 
         let dts_result = self.canister_manager.install_code_dts(
@@ -1756,11 +2005,21 @@ impl ExecutionEnvironment {
     ) -> ReplicatedState {
         let execution_duration = timer.elapsed();
         match dts_result {
-            DtsInstallCodeResult::Finished { canister, result } => {
+            DtsInstallCodeResult::Finished {
+                mut canister,
+                result,
+            } => {
                 let canister_id = canister.canister_id();
                 let result = match result {
                     Ok(result) => {
                         state.metadata.heap_delta_estimate += result.heap_delta;
+                        if let Some(module_hash) = result.new_wasm_hash {
+                            canister.system_state.add_canister_change(
+                                state.time(),
+                                canister_change_origin(&message),
+                                CanisterChangeDetails::code_deployment(result.mode, module_hash),
+                            );
+                        }
                         if self.config.module_sharing == FlagStatus::Enabled {
                             state.metadata.expected_compiled_wasms.insert(new_wasm_hash);
                         }
@@ -1789,11 +2048,80 @@ impl ExecutionEnvironment {
                 self.finish_subnet_message_execution(state, message, result, refund, timer)
             }
             DtsInstallCodeResult::Paused {
-                canister,
-                paused_execution: _,
+                mut canister,
+                paused_execution,
             } => {
+                let id = self.register_paused_install_code(PausedInstallCode {
+                    paused_execution,
+                    message,
+                    new_wasm_hash,
+                });
+                canister
+                    .system_state
+                    .task_queue
+                    .push_front(ExecutionTask::PausedInstallCode(id));
                 state.put_canister_state(canister);
-                unimplemented!("Unexpected paused install_code: DTS is not enabled yet.")
+                state
+            }
+        }
+    }
+
+    /// Resumes a paused `install_code` execution of the given canister or
+    /// executes an aborted one from scratch. Does nothing if the canister
+    /// doesn't have such a task.
+    ///
+    /// Note that the scheduler doesn't execute other subnet messages while
+    /// an `install_code` execution is in progress.
+    pub fn resume_install_code(
+        &self,
+        mut state: ReplicatedState,
+        canister_id: &CanisterId,
+        instruction_limits: InstructionLimits,
+        round_limits: &mut RoundLimits,
+    ) -> ReplicatedState {
+        let task = match state.canister_state_mut(canister_id) {
+            Some(canister)
+                if canister.has_paused_install_code() || canister.has_aborted_install_code() =>
+            {
+                canister.pop_task().unwrap()
+            }
+            _ => return state,
+        };
+        match task {
+            ExecutionTask::PausedInstallCode(id) => {
+                let paused = self.take_paused_install_code(id).unwrap();
+                let canister = state.take_canister_state(canister_id).unwrap();
+                let dts_result = {
+                    let round = RoundContext {
+                        network_topology: &state.metadata.network_topology,
+                        hypervisor: &self.hypervisor,
+                        cycles_account_manager: &self.cycles_account_manager,
+                        log: &self.log,
+                        time: state.time(),
+                    };
+                    paused
+                        .paused_execution
+                        .resume(canister, round, round_limits)
+                };
+                // The execution time is measured only for the last slice.
+                let timer = Timer::start();
+                self.process_install_code_result(
+                    state,
+                    paused.message,
+                    dts_result,
+                    timer,
+                    paused.new_wasm_hash,
+                )
+            }
+            ExecutionTask::AbortedInstallCode(message) => {
+                self.execute_install_code(message, state, instruction_limits, round_limits)
+            }
+            ExecutionTask::Heartbeat
+            | ExecutionTask::GlobalTimer
+            | ExecutionTask::OnLowWasmMemory
+            | ExecutionTask::PausedExecution(..)
+            | ExecutionTask::AbortedExecution(..) => {
+                unreachable!("Expected an install_code task, got {:?}", task)
             }
         }
     }
@@ -1813,28 +2141,60 @@ impl ExecutionEnvironment {
         id
     }
 
+    /// Returns the paused `install_code` execution by its id.
+    fn take_paused_install_code(&self, id: PausedExecutionId) -> Option<PausedInstallCode> {
+        let mut guard = self.paused_execution_registry.lock().unwrap();
+        guard.install_code_registry.remove(&id)
+    }
+
+    /// Registers the given paused `install_code` execution and returns its id.
+    fn register_paused_install_code(&self, paused: PausedInstallCode) -> PausedExecutionId {
+        let mut guard = self.paused_execution_registry.lock().unwrap();
+        let id = PausedExecutionId(guard.next_id);
+        guard.next_id += 1;
+        guard.install_code_registry.insert(id, paused);
+        id
+    }
+
     /// Aborts all paused execution in the given state.
     pub fn abort_paused_executions(&self, state: &mut ReplicatedState) {
         for canister in state.canisters_iter_mut() {
-            if let Some(execution_state) = canister.execution_state.as_mut() {
-                if !execution_state.task_queue.is_empty() {
-                    let task_queue = std::mem::take(&mut execution_state.task_queue);
-                    execution_state.task_queue = task_queue
-                        .into_iter()
-                        .map(|task| match task {
-                            ExecutionTask::AbortedExecution(..) | ExecutionTask::Heartbeat => task,
-                            ExecutionTask::PausedExecution(id) => {
-                                let paused = self.take_paused_execution(id).unwrap();
-                                let message = paused.abort();
-                                ExecutionTask::AbortedExecution(message)
-                            }
-                        })
-                        .collect();
-                };
-            }
+            self.abort_canister(canister);
         }
     }
 
+    /// Aborts all paused executions of the given canister. The aborted
+    /// messages stay in the task queue and are executed from scratch later.
+    fn abort_canister(&self, canister: &mut CanisterState) {
+        if canister.system_state.task_queue.is_empty() {
+            return;
+        }
+        let task_queue = std::mem::take(&mut canister.system_state.task_queue);
+        canister.system_state.task_queue = task_queue
+            .into_iter()
+            .filter_map(|task| match task {
+                ExecutionTask::AbortedExecution(..)
+                | ExecutionTask::AbortedInstallCode(..)
+                | ExecutionTask::Heartbeat
+                | ExecutionTask::GlobalTimer
+                | ExecutionTask::OnLowWasmMemory => Some(task),
+                ExecutionTask::PausedExecution(id) => {
+                    let paused = self.take_paused_execution(id).unwrap();
+                    paused
+                        .abort(canister, &self.cycles_account_manager)
+                        .map(ExecutionTask::AbortedExecution)
+                }
+                ExecutionTask::PausedInstallCode(id) => {
+                    let paused = self.take_paused_install_code(id).unwrap();
+                    paused
+                        .paused_execution
+                        .abort(canister, &self.cycles_account_manager);
+                    Some(ExecutionTask::AbortedInstallCode(paused.message))
+                }
+            })
+            .collect();
+    }
+
     /// If the given result corresponds to a finished execution, then it processes
     /// the response and return the ingress status (if any). Otherwise, it registers
     /// the paused execution and adds it to the task queue.
@@ -1868,11 +2228,8 @@ impl ExecutionEnvironment {
                 paused_execution,
             } => {
                 let id = self.register_paused_execution(paused_execution);
-                // The execution state is guaranteed to exist because we have a paused execution.
                 canister
-                    .execution_state
-                    .as_mut()
-                    .unwrap()
+                    .system_state
                     .task_queue
                     .push_front(ExecutionTask::PausedExecution(id));
                 (canister, NumBytes::from(0), None)
@@ -1910,6 +2267,69 @@ pub(crate) fn subnet_memory_capacity(config: &ExecutionConfig) -> SubnetAvailabl
     .into()
 }
 
+/// Returns the origin of the changes made to a canister by the given message.
+fn canister_change_origin(msg: &RequestOrIngress) -> CanisterChangeOrigin {
+    match msg {
+        RequestOrIngress::Request(request) => {
+            CanisterChangeOrigin::from_canister(request.sender.get())
+        }
+        RequestOrIngress::Ingress(ingress) => CanisterChangeOrigin::from_user(ingress.source.get()),
+    }
+}
+
+/// Records a change in the history of the given canister. Does nothing if the
+/// canister doesn't exist.
+fn add_canister_change(
+    state: &mut ReplicatedState,
+    canister_id: CanisterId,
+    origin: CanisterChangeOrigin,
+    details: CanisterChangeDetails,
+) {
+    let time = state.time();
+    if let Some(canister) = state.canister_state_mut(&canister_id) {
+        canister
+            .system_state
+            .add_canister_change(time, origin, details);
+    }
+}
+
+/// Records the creation of the given canister with its initial controllers.
+fn record_canister_creation(
+    state: &mut ReplicatedState,
+    canister_id: CanisterId,
+    origin: CanisterChangeOrigin,
+) {
+    let controllers = match state.canister_state(&canister_id) {
+        Some(canister) => canister.controllers().iter().copied().collect(),
+        None => return,
+    };
+    add_canister_change(
+        state,
+        canister_id,
+        origin,
+        CanisterChangeDetails::canister_creation(controllers),
+    );
+}
+
+/// Records that the controllers of the given canister were replaced by its
+/// current controllers.
+fn record_controllers_change(
+    state: &mut ReplicatedState,
+    canister_id: CanisterId,
+    origin: CanisterChangeOrigin,
+) {
+    let controllers = match state.canister_state(&canister_id) {
+        Some(canister) => canister.controllers().iter().copied().collect(),
+        None => return,
+    };
+    add_canister_change(
+        state,
+        canister_id,
+        origin,
+        CanisterChangeDetails::controllers_change(controllers),
+    );
+}
+
 fn get_canister_mut(
     canister_id: CanisterId,
     state: &mut ReplicatedState,
@@ -1971,7 +2391,12 @@ pub fn execute_canister(
     time: Time,
     round_limits: &mut RoundLimits,
 ) -> ExecuteCanisterResult {
-    if !canister.is_active() {
+    // A canister with an `install_code` execution in progress doesn't execute
+    // anything else. The scheduler resumes `install_code` separately.
+    if !canister.is_active()
+        || canister.has_paused_install_code()
+        || canister.has_aborted_install_code()
+    {
         return ExecuteCanisterResult {
             canister,
             heap_delta: NumBytes::from(0),
@@ -1982,8 +2407,9 @@ pub fn execute_canister(
     match canister.pop_task() {
         Some(task) => match task {
             ExecutionTask::Heartbeat => {
-                let (canister, result) = exec_env.execute_canister_heartbeat(
+                let (canister, result) = exec_env.execute_canister_system_task(
                     canister,
+                    SystemMethod::CanisterHeartbeat,
                     instruction_limits,
                     network_topology,
                     time,
@@ -1997,6 +2423,40 @@ pub fn execute_canister(
                     description: Some("heartbeat".to_string()),
                 }
             }
+            ExecutionTask::GlobalTimer => {
+                let (canister, result) = exec_env.execute_canister_system_task(
+                    canister,
+                    SystemMethod::CanisterGlobalTimer,
+                    instruction_limits,
+                    network_topology,
+                    time,
+                    round_limits,
+                );
+                let heap_delta = result.unwrap_or_else(|_| NumBytes::from(0));
+                ExecuteCanisterResult {
+                    canister,
+                    heap_delta,
+                    ingress_status: None,
+                    description: Some("global timer".to_string()),
+                }
+            }
+            ExecutionTask::OnLowWasmMemory => {
+                let (canister, result) = exec_env.execute_canister_system_task(
+                    canister,
+                    SystemMethod::CanisterOnLowWasmMemory,
+                    instruction_limits,
+                    network_topology,
+                    time,
+                    round_limits,
+                );
+                let heap_delta = result.unwrap_or_else(|_| NumBytes::from(0));
+                ExecuteCanisterResult {
+                    canister,
+                    heap_delta,
+                    ingress_status: None,
+                    description: Some("on low wasm memory".to_string()),
+                }
+            }
             ExecutionTask::PausedExecution(id) => {
                 let paused = exec_env.take_paused_execution(id).unwrap();
                 let round_context = RoundContext {
@@ -2024,6 +2484,9 @@ pub fn execute_canister(
                 time,
                 round_limits,
             ),
+            ExecutionTask::PausedInstallCode(..) | ExecutionTask::AbortedInstallCode(..) => {
+                unreachable!("The install_code tasks are not executed here.")
+            }
         },
         None => {
             let message = canister.pop_input().unwrap();
@@ -288,5 +288,22 @@ fn dashboard_label_value_from(code: ErrorCode) -> &'static str {
         CanisterInstallCodeRateLimited => {
             "Canister is rate limited because it executed too many instructions in the previous install_code messages"
         }
+        CanisterSnapshotNotFound => "Canister snapshot not found",
+        CanisterSnapshotLimitExceeded => "Canister snapshot limit exceeded",
+        QueryCallGraphTooDeep => "Query call graph contains too many nested calls",
+        QueryCallGraphTotalInstructionLimitExceeded => {
+            "Query call graph exceeded the total instruction limit"
+        }
+        CompositeQueryCalledInReplicatedMode => "Composite query called in replicated mode",
+        CanisterMemoryAccessLimitExceeded => {
+            "Canister exceeded the limit for the number of modified stable memory pages"
+        }
+        CanisterWasmMemoryLimitExceeded => "Canister exceeded its Wasm memory limit",
+        InsufficientCyclesInMemoryGrow => {
+            "Canister does not have enough cycles to reserve for growing its memory"
+        }
+        ReservedCyclesLimitExceededInMemoryGrow => {
+            "Canister exceeded its reserved cycles limit when growing its memory"
+        }
     }
 }
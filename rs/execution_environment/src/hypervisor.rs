@@ -3,19 +3,19 @@ use ic_config::embedders::FeatureFlags;
 use ic_config::flag_status::FlagStatus;
 use ic_config::{embedders::Config as EmbeddersConfig, execution_environment::Config};
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_embedders::wasm_executor::{WasmExecutionResult, WasmExecutor};
+use ic_embedders::wasm_executor::{PausedWasmExecution, WasmExecutionResult, WasmExecutor};
+use ic_embedders::wasm_utils::{decoding::decode_wasm, instrumentation::function_names};
 use ic_embedders::{wasm_executor::WasmExecutorImpl, WasmExecutionInput, WasmtimeEmbedder};
 use ic_embedders::{CompilationCache, CompilationResult};
-use ic_interfaces::execution_environment::{HypervisorResult, WasmExecutionOutput};
-use ic_logger::{fatal, ReplicaLogger};
+use ic_interfaces::execution_environment::{
+    HypervisorResult, InstructionProfile, WasmExecutionOutput,
+};
+use ic_logger::ReplicaLogger;
 use ic_metrics::buckets::decimal_buckets_with_zero;
 use ic_metrics::{buckets::exponential_buckets, MetricsRegistry};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::NetworkTopology;
-use ic_replicated_state::{
-    page_map::allocated_pages_count, CanisterState, ExecutionState, SchedulerState, SystemState,
-};
-use ic_sys::PAGE_SIZE;
+use ic_replicated_state::{page_map::allocated_pages_count, ExecutionState, SystemState};
 use ic_system_api::ExecutionParameters;
 use ic_system_api::{sandbox_safe_system_state::SandboxSafeSystemState, ApiType};
 use ic_types::{
@@ -23,7 +23,12 @@ use ic_types::{
 };
 use ic_wasm_types::CanisterModule;
 use prometheus::{Histogram, IntCounterVec, IntGauge};
-use std::{path::PathBuf, sync::Arc};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
 
 use crate::execution::common::update_round_limits;
 use crate::execution_environment::{as_round_instructions, CompilationCostHandling, RoundLimits};
@@ -110,6 +115,117 @@ impl HypervisorMetrics {
     }
 }
 
+/// The instruction profile of a single message execution together with the
+/// information needed to render it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageInstructionProfile {
+    pub canister_id: CanisterId,
+    /// The exported method or the system task that was executed.
+    pub function: String,
+    pub profile: InstructionProfile,
+    /// The names of the Wasm functions from the name section of the module.
+    pub function_names: Arc<BTreeMap<u32, String>>,
+}
+
+impl MessageInstructionProfile {
+    /// Returns the profile in the folded stacks format where the root frame
+    /// is `<canister_id>:<function>`.
+    pub fn to_folded(&self) -> String {
+        self.profile.to_folded(
+            &format!("{}:{}", self.canister_id, self.function),
+            &self.function_names,
+        )
+    }
+}
+
+/// A shared collection of instruction profiles recorded by the hypervisor
+/// when instruction profiling is enabled.
+#[derive(Clone, Debug, Default)]
+pub struct InstructionProfiles(Arc<Mutex<Vec<MessageInstructionProfile>>>);
+
+impl InstructionProfiles {
+    fn push(&self, profile: MessageInstructionProfile) {
+        self.0.lock().unwrap().push(profile);
+    }
+
+    /// Returns all recorded profiles and clears the collection.
+    pub fn take(&self) -> Vec<MessageInstructionProfile> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+
+    /// Writes all recorded profiles in the folded stacks format that is
+    /// understood by `inferno-flamegraph` and `flamegraph.pl`.
+    pub fn write_folded<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for profile in self.0.lock().unwrap().iter() {
+            writer.write_all(profile.to_folded().as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// Identifies the message whose instruction profile is being recorded.
+#[derive(Debug)]
+struct ProfilingContext {
+    canister_id: CanisterId,
+    function: String,
+    profiles: InstructionProfiles,
+}
+
+impl ProfilingContext {
+    fn record(
+        self,
+        execution_state: &ExecutionState,
+        result: WasmExecutionResult,
+    ) -> WasmExecutionResult {
+        match result {
+            WasmExecutionResult::Finished(slice, mut output, system_state_changes) => {
+                if let Some(profile) = output.instruction_profile.take() {
+                    let function_names =
+                        decode_wasm(execution_state.wasm_binary.binary.to_shared_vec())
+                            .map(|wasm| function_names(&wasm))
+                            .unwrap_or_default();
+                    self.profiles.push(MessageInstructionProfile {
+                        canister_id: self.canister_id,
+                        function: self.function,
+                        profile,
+                        function_names: Arc::new(function_names),
+                    });
+                }
+                WasmExecutionResult::Finished(slice, output, system_state_changes)
+            }
+            WasmExecutionResult::Paused(slice, paused) => WasmExecutionResult::Paused(
+                slice,
+                Box::new(ProfiledPausedWasmExecution {
+                    paused,
+                    context: self,
+                }),
+            ),
+        }
+    }
+}
+
+/// Records the instruction profile once the paused execution finishes.
+#[derive(Debug)]
+struct ProfiledPausedWasmExecution {
+    paused: Box<dyn PausedWasmExecution>,
+    context: ProfilingContext,
+}
+
+impl PausedWasmExecution for ProfiledPausedWasmExecution {
+    fn resume(
+        self: Box<Self>,
+        execution_state: ExecutionState,
+    ) -> (ExecutionState, WasmExecutionResult) {
+        let (execution_state, result) = self.paused.resume(execution_state);
+        let result = self.context.record(&execution_state, result);
+        (execution_state, result)
+    }
+
+    fn abort(self: Box<Self>) {
+        self.paused.abort();
+    }
+}
+
 #[doc(hidden)]
 pub struct Hypervisor {
     wasm_executor: Arc<dyn WasmExecutor>,
@@ -120,6 +236,8 @@ pub struct Hypervisor {
     cycles_account_manager: Arc<CyclesAccountManager>,
     compilation_cache: Arc<CompilationCache>,
     deterministic_time_slicing: FlagStatus,
+    instruction_profiling: FlagStatus,
+    instruction_profiles: InstructionProfiles,
 }
 
 impl Hypervisor {
@@ -131,38 +249,6 @@ impl Hypervisor {
         self.own_subnet_type
     }
 
-    // A helper that converts a Wasm execution output to an execution
-    // result of `execution_canister_*` functions.
-    //
-    // The components of the resulting `CanisterState` are computed
-    // as follows:
-    // - `execution_state` is taken from the Wasm output.
-    // - `scheduler_state` is taken from the corresponding argument.
-    // - `system_state` is taken from the system_state_accessor if the execution
-    //   succeeded; otherwise, it is taken from the corresponding argument.
-    pub fn system_execution_result(
-        &self,
-        output: WasmExecutionOutput,
-        execution_state: ExecutionState,
-        old_system_state: SystemState,
-        scheduler_state: SchedulerState,
-        output_system_state: SystemState,
-    ) -> (CanisterState, NumInstructions, HypervisorResult<NumBytes>) {
-        let (system_state, heap_delta) = match output.wasm_result {
-            Ok(opt_result) => {
-                if opt_result.is_some() {
-                    fatal!(self.log, "[EXC-BUG] System methods cannot use msg_reply.");
-                }
-                let bytes = NumBytes::from((output.instance_stats.dirty_pages * PAGE_SIZE) as u64);
-                (output_system_state, Ok(bytes))
-            }
-            Err(err) => (old_system_state, Err(err)),
-        };
-        let canister =
-            CanisterState::from_parts(Some(execution_state), system_state, scheduler_state);
-        (canister, output.num_instructions_left, heap_delta)
-    }
-
     pub fn create_execution_state(
         &self,
         canister_module: CanisterModule,
@@ -202,6 +288,10 @@ impl Hypervisor {
         embedder_config.feature_flags.rate_limiting_of_debug_prints =
             config.rate_limiting_of_debug_prints;
         embedder_config.feature_flags.module_sharing = config.module_sharing;
+        embedder_config.feature_flags.instruction_profiling = config.instruction_profiling;
+        embedder_config.feature_flags.wasm_native_stable_memory = config.wasm_native_stable_memory;
+        embedder_config.stable_memory_dirty_page_limit = config.stable_memory_dirty_page_limit;
+        embedder_config.feature_flags.wasm_simd = config.wasm_simd;
 
         let wasm_executor: Arc<dyn WasmExecutor> = match config.canister_sandboxing_flag {
             FlagStatus::Enabled => {
@@ -232,6 +322,8 @@ impl Hypervisor {
             cycles_account_manager,
             compilation_cache: Arc::new(CompilationCache::new(config.module_sharing)),
             deterministic_time_slicing: config.deterministic_time_slicing,
+            instruction_profiling: config.instruction_profiling,
+            instruction_profiles: InstructionProfiles::default(),
         }
     }
 
@@ -256,9 +348,16 @@ impl Hypervisor {
                 FeatureFlags::default().module_sharing,
             )),
             deterministic_time_slicing,
+            instruction_profiling: FlagStatus::Disabled,
+            instruction_profiles: InstructionProfiles::default(),
         }
     }
 
+    /// Returns the profiles recorded when instruction profiling is enabled.
+    pub fn instruction_profiles(&self) -> InstructionProfiles {
+        self.instruction_profiles.clone()
+    }
+
     #[cfg(test)]
     pub fn compile_count(&self) -> u64 {
         self.metrics.compile.get_sample_count()
@@ -346,6 +445,19 @@ impl Hypervisor {
             ),
         }
         let api_type_str = api_type.as_str();
+        let profiling_context = match self.instruction_profiling {
+            FlagStatus::Enabled => Some(ProfilingContext {
+                canister_id: system_state.canister_id,
+                function: match &func_ref {
+                    FuncRef::Method(method) => method.to_string(),
+                    FuncRef::UpdateClosure(_) | FuncRef::QueryClosure(_) => {
+                        api_type_str.to_string()
+                    }
+                },
+                profiles: self.instruction_profiles.clone(),
+            }),
+            FlagStatus::Disabled => None,
+        };
         let static_system_state = SandboxSafeSystemState::new(
             system_state,
             *self.cycles_account_manager,
@@ -368,6 +480,10 @@ impl Hypervisor {
                 .observe_compilation_metrics(&compilation_result);
         }
         self.metrics.observe(api_type_str, &execution_result);
+        let execution_result = match profiling_context {
+            Some(context) => context.record(&execution_state, execution_result),
+            None => execution_result,
+        };
         (execution_state, execution_result)
     }
 
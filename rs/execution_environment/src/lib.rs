@@ -21,7 +21,9 @@ pub use execution_environment::{
     RoundInstructions, RoundLimits,
 };
 pub use history::{IngressHistoryReaderImpl, IngressHistoryWriterImpl};
-pub use hypervisor::{Hypervisor, HypervisorMetrics};
+pub use hypervisor::{
+    Hypervisor, HypervisorMetrics, InstructionProfiles, MessageInstructionProfile,
+};
 use ic_base_types::PrincipalId;
 use ic_btc_canister::BitcoinCanister;
 use ic_config::{execution_environment::Config, subnet_config::SchedulerConfig};
@@ -86,6 +88,9 @@ pub struct ExecutionServices {
     pub async_query_handler: QueryExecutionService,
     pub anonymous_query_handler: AnonymousQueryService,
     pub scheduler: Box<dyn Scheduler<State = ReplicatedState>>,
+    /// The instruction profiles recorded when instruction profiling is
+    /// enabled in the execution config.
+    pub instruction_profiles: InstructionProfiles,
 }
 
 impl ExecutionServices {
@@ -110,6 +115,7 @@ impl ExecutionServices {
             logger.clone(),
             Arc::clone(&cycles_account_manager),
         ));
+        let instruction_profiles = hypervisor.instruction_profiles();
 
         let ingress_history_writer = Arc::new(IngressHistoryWriterImpl::new(
             config.clone(),
@@ -196,6 +202,7 @@ impl ExecutionServices {
             async_query_handler,
             anonymous_query_handler,
             scheduler,
+            instruction_profiles,
         }
     }
 
@@ -163,6 +163,8 @@ impl QueryHandler for InternalHttpQueryHandler {
             subnet_available_memory,
             max_canister_memory_size,
             self.max_instructions_per_message,
+            self.config.max_query_call_graph_depth,
+            self.config.max_query_call_graph_instructions,
         );
         context.run(
             query,
@@ -1,15 +1,27 @@
-//! This module implements an MVP version of inter-canister queries. This
-//! implementation has the following restrictions:
+//! This module implements composite queries, i.e. queries exported as
+//! `canister_composite_query` that can call query and composite query methods
+//! of other canisters. The implementation has the following restrictions:
 //!
 //! - A canister can only query other canisters on the same subnet.
 //!
 //! - A canister can only query other canisters when it is doing non-replicated
 //! execution, i.e. the originator of the processing is a Query from an end-user
-//! and not an Ingress message.
+//! and not an Ingress message. Calling a composite query in replicated mode
+//! fails with `CompositeQueryCalledInReplicatedMode`.
 //!
 //! - Loops are not allowed. E.g. call graphs like A -> B -> C -> A are not
 //! supported.
 //!
+//! - The depth of the call graph and the total number of instructions executed
+//! by all its messages are bounded by `max_query_call_graph_depth` and
+//! `max_query_call_graph_instructions` of the execution environment config.
+//!
+//! - Only composite queries can call other canisters. Regular queries, whether
+//! sent by an end-user or by another canister, are executed as pure queries and
+//! their outgoing calls fail. The exception are subnets where the legacy
+//! inter-canister queries are enabled (system and verified application
+//! subnets).
+//!
 //! Some interesting factoids about inter-canister query execution to keep in
 //! mind:
 //!
@@ -46,7 +58,7 @@ use crate::{
 };
 use ic_base_types::NumBytes;
 use ic_config::flag_status::FlagStatus;
-use ic_cycles_account_manager::CyclesAccountManager;
+use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_interfaces::execution_environment::{ExecutionMode, HypervisorError, SubnetAvailableMemory};
 use ic_logger::{debug, error, fatal, warn, ReplicaLogger};
@@ -61,6 +73,7 @@ use ic_types::{
     messages::{
         CallbackId, Payload, RejectContext, Request, RequestOrResponse, Response, UserQuery,
     },
+    time::NO_DEADLINE,
     CanisterId, Cycles, NumInstructions, NumMessages, QueryAllocation, Time,
 };
 use std::{
@@ -71,7 +84,7 @@ use std::{
 const ENABLE_QUERY_OPTIMIZATION: bool = true;
 
 const LOOP_DETECTED_ERROR_MSG: &str =
-    "Loop detected.  MVP inter-canister queries do not support loops.";
+    "Loop detected.  Inter-canister queries do not support loops.";
 
 /// A simple enum representing the different things that
 /// QueryContext::enqueue_requests() can return.
@@ -93,6 +106,7 @@ fn generate_response(request: Arc<Request>, payload: Payload) -> Response {
         originator_reply_callback: request.sender_reply_callback,
         response_payload: payload,
         refund: Cycles::zero(),
+        deadline: request.deadline,
     }
 }
 
@@ -116,6 +130,14 @@ pub(super) struct QueryContext<'a> {
     max_canister_memory_size: NumBytes,
     max_instructions_per_message: NumInstructions,
     round_limits: RoundLimits,
+    max_query_call_graph_depth: usize,
+    max_query_call_graph_instructions: NumInstructions,
+    // The number of instructions executed by all messages of the call graph so
+    // far.
+    query_call_graph_instructions_executed: NumInstructions,
+    // The depth in the call graph of every canister that executed a message,
+    // where the canister receiving the user's query is at depth zero.
+    call_graph_depths: BTreeMap<CanisterId, usize>,
 }
 
 impl<'a> QueryContext<'a> {
@@ -130,6 +152,8 @@ impl<'a> QueryContext<'a> {
         subnet_available_memory: SubnetAvailableMemory,
         max_canister_memory_size: NumBytes,
         max_instructions_per_message: NumInstructions,
+        max_query_call_graph_depth: usize,
+        max_query_call_graph_instructions: NumInstructions,
     ) -> Self {
         let network_topology = Arc::new(state.metadata.network_topology.clone());
         let round_limits = RoundLimits {
@@ -150,6 +174,10 @@ impl<'a> QueryContext<'a> {
             max_canister_memory_size,
             max_instructions_per_message,
             round_limits,
+            max_query_call_graph_depth,
+            max_query_call_graph_instructions,
+            query_call_graph_instructions_executed: NumInstructions::from(0),
+            call_graph_depths: BTreeMap::new(),
         }
     }
 
@@ -188,12 +216,15 @@ impl<'a> QueryContext<'a> {
         }
 
         let call_origin = CallOrigin::Query(query.source);
-        // EXC-500: Contain the usage of inter-canister query calls to the subnets
-        // that currently use it until we decide on the future of this feature and
-        // get a proper spec for it.
-        let cross_canister_query_calls_enabled = self.own_subnet_type == SubnetType::System
-            || self.own_subnet_type == SubnetType::VerifiedApplication;
-        let try_pure_query = ENABLE_QUERY_OPTIMIZATION || !cross_canister_query_calls_enabled;
+        self.call_graph_depths.insert(canister_id, 0);
+        // Composite queries can always call other canisters, so they are
+        // executed as `Stateful` right away.
+        let is_composite_query =
+            old_canister.exports_composite_query_method(query.method_name.clone());
+        let cross_canister_query_calls_enabled =
+            is_composite_query || self.legacy_query_calls_enabled();
+        let try_pure_query = !is_composite_query
+            && (ENABLE_QUERY_OPTIMIZATION || !cross_canister_query_calls_enabled);
         let query_kind = if try_pure_query {
             NonReplicatedQueryKind::Pure {
                 caller: query.source.get(),
@@ -283,6 +314,17 @@ impl<'a> QueryContext<'a> {
         let measurement_scope =
             MeasurementScope::nested(&metrics.query_spawned_calls, measurement_scope);
         loop {
+            if self.query_call_graph_instructions_executed >= self.max_query_call_graph_instructions
+            {
+                return Err(UserError::new(
+                    ErrorCode::QueryCallGraphTotalInstructionLimitExceeded,
+                    format!(
+                        "Query call graph of canister {} exceeded the limit of {} instructions",
+                        starting_canister_id, self.max_query_call_graph_instructions
+                    ),
+                ));
+            }
+
             if let Some(response) = self.outstanding_response.take() {
                 debug!(self.log, "Executing response for {}", response.originator);
                 // Any result returned by `handle_response` is a query context
@@ -357,8 +399,8 @@ impl<'a> QueryContext<'a> {
                         // Messages of these types are not produced by this
                         // module so must have existed on the canister's output
                         // queue from before.
-                        CallOrigin::CanisterUpdate(_, _)
-                        | CallOrigin::Heartbeat
+                        CallOrigin::CanisterUpdate(_, _, _)
+                        | CallOrigin::SystemTask
                         | CallOrigin::Ingress(_, _) => continue,
 
                         // We never serialize messages of such types in the
@@ -401,13 +443,7 @@ impl<'a> QueryContext<'a> {
         query_kind: NonReplicatedQueryKind,
         measurement_scope: &MeasurementScope,
     ) -> (CanisterState, Result<Option<WasmResult>, UserError>) {
-        let instruction_limit = self.max_instructions_per_message.min(
-            self.query_allocations_used
-                .write()
-                .unwrap()
-                .allocation_before_execution(&canister.canister_id())
-                .into(),
-        );
+        let instruction_limit = self.instruction_limit(&canister.canister_id());
         let instruction_limits =
             InstructionLimits::new(FlagStatus::Disabled, instruction_limit, instruction_limit);
         let execution_parameters = self.execution_parameters(&canister, instruction_limits);
@@ -425,6 +461,7 @@ impl<'a> QueryContext<'a> {
             &mut self.round_limits,
         );
         let instructions_executed = instruction_limit - instructions_left;
+        self.query_call_graph_instructions_executed += instructions_executed;
         measurement_scope.add(instructions_executed, NumMessages::from(1));
         self.query_allocations_used
             .write()
@@ -474,8 +511,8 @@ impl<'a> QueryContext<'a> {
         };
         let func_ref = match call_origin {
             CallOrigin::Ingress(_, _)
-            | CallOrigin::CanisterUpdate(_, _)
-            | CallOrigin::Heartbeat => unreachable!("Unreachable in the QueryContext."),
+            | CallOrigin::CanisterUpdate(_, _, _)
+            | CallOrigin::SystemTask => unreachable!("Unreachable in the QueryContext."),
             CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => {
                 FuncRef::QueryClosure(closure)
             }
@@ -501,13 +538,7 @@ impl<'a> QueryContext<'a> {
             ),
         };
 
-        let instruction_limit = self.max_instructions_per_message.min(
-            self.query_allocations_used
-                .write()
-                .unwrap()
-                .allocation_before_execution(&canister_id)
-                .into(),
-        );
+        let instruction_limit = self.instruction_limit(&canister_id);
         let instruction_limits =
             InstructionLimits::new(FlagStatus::Disabled, instruction_limit, instruction_limit);
         let mut execution_parameters = self.execution_parameters(&canister, instruction_limits);
@@ -563,6 +594,7 @@ impl<'a> QueryContext<'a> {
             .on_canister_result(call_context_id, result);
 
         let instructions_executed = instruction_limit - instructions_left;
+        self.query_call_graph_instructions_executed += instructions_executed;
         measurement_scope.add(instructions_executed, NumMessages::from(1));
         self.query_allocations_used
             .write()
@@ -593,8 +625,8 @@ impl<'a> QueryContext<'a> {
     ) -> (NumInstructions, Result<Option<WasmResult>, HypervisorError>) {
         let func_ref = match call_origin {
             CallOrigin::Ingress(_, _)
-            | CallOrigin::CanisterUpdate(_, _)
-            | CallOrigin::Heartbeat => unreachable!("Unreachable in the QueryContext."),
+            | CallOrigin::CanisterUpdate(_, _, _)
+            | CallOrigin::SystemTask => unreachable!("Unreachable in the QueryContext."),
             CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => {
                 FuncRef::QueryClosure(cleanup_closure)
             }
@@ -635,6 +667,13 @@ impl<'a> QueryContext<'a> {
         }
     }
 
+    // EXC-500: Contain the usage of inter-canister query calls from regular
+    // queries to the subnets that currently use it.
+    fn legacy_query_calls_enabled(&self) -> bool {
+        self.own_subnet_type == SubnetType::System
+            || self.own_subnet_type == SubnetType::VerifiedApplication
+    }
+
     // Executes a query sent from one canister to another. If a loop in the call
     // graph is detected, then an error is returned.
     fn handle_request(
@@ -658,6 +697,20 @@ impl<'a> QueryContext<'a> {
             error!(self.log, "[EXC-BUG] The canister that we want to execute a request on should not already be loaded.");
         }
 
+        let depth = self.call_graph_depth(&request.sender) + 1;
+        if depth > self.max_query_call_graph_depth {
+            let error = UserError::new(
+                ErrorCode::QueryCallGraphTooDeep,
+                format!(
+                    "Query call graph exceeded the maximum depth of {}",
+                    self.max_query_call_graph_depth
+                ),
+            );
+            let payload = Payload::Reject(RejectContext::from(error));
+            self.outstanding_response = Some(generate_response(request, payload));
+            return None;
+        }
+
         let canister = match self.state.get_active_canister(&request.receiver) {
             Ok(canister) => canister,
             Err(err) => {
@@ -667,12 +720,26 @@ impl<'a> QueryContext<'a> {
             }
         };
 
-        let call_origin = CallOrigin::CanisterQuery(request.sender, request.sender_reply_callback);
+        self.call_graph_depths.insert(canister_id, depth);
+        // Only composite queries can call other canisters. Unless the legacy
+        // inter-canister query calls are enabled, a regular query is executed
+        // as a pure query, so that its attempts to make outgoing calls fail.
+        let query_kind = if canister.exports_composite_query_method(request.method_name.clone())
+            || self.legacy_query_calls_enabled()
+        {
+            let call_origin =
+                CallOrigin::CanisterQuery(request.sender, request.sender_reply_callback);
+            NonReplicatedQueryKind::Stateful { call_origin }
+        } else {
+            NonReplicatedQueryKind::Pure {
+                caller: request.sender.get(),
+            }
+        };
         let (mut canister, result) = self.execute_query(
             canister,
             request.method_name.as_str(),
             request.method_payload.as_slice(),
-            NonReplicatedQueryKind::Stateful { call_origin },
+            query_kind,
             measurement_scope,
         );
 
@@ -833,6 +900,7 @@ impl<'a> QueryContext<'a> {
                 originator_reply_callback: callback_id,
                 response_payload: payload,
                 refund: Cycles::zero(),
+                deadline: NO_DEADLINE,
             };
             self.outstanding_response = Some(response);
         };
@@ -961,9 +1029,9 @@ impl<'a> QueryContext<'a> {
         match call_origin {
             CallOrigin::Query(_) => self.handle_response_with_query_origin(canister, action),
 
-            CallOrigin::CanisterUpdate(_, _)
+            CallOrigin::CanisterUpdate(_, _, _)
             | CallOrigin::Ingress(_, _)
-            | CallOrigin::Heartbeat => fatal!(
+            | CallOrigin::SystemTask => fatal!(
                 self.log,
                 "Canister {}: query path should not have created a callback with an update origin",
                 canister_id
@@ -975,6 +1043,35 @@ impl<'a> QueryContext<'a> {
         }
     }
 
+    // Returns the instruction limit of the next message executed on the given
+    // canister, taking into account the instructions left in the call graph.
+    fn instruction_limit(&self, canister_id: &CanisterId) -> NumInstructions {
+        let call_graph_instructions_left = self.max_query_call_graph_instructions
+            - self
+                .query_call_graph_instructions_executed
+                .min(self.max_query_call_graph_instructions);
+        self.max_instructions_per_message
+            .min(
+                self.query_allocations_used
+                    .write()
+                    .unwrap()
+                    .allocation_before_execution(canister_id)
+                    .into(),
+            )
+            .min(call_graph_instructions_left)
+    }
+
+    // Returns the depth of the given canister in the call graph.
+    fn call_graph_depth(&self, canister_id: &CanisterId) -> usize {
+        *self.call_graph_depths.get(canister_id).unwrap_or_else(|| {
+            fatal!(
+                self.log,
+                "Canister {}: Expected to find the depth in the call graph",
+                canister_id
+            )
+        })
+    }
+
     fn execution_parameters(
         &self,
         canister: &CanisterState,
@@ -986,6 +1083,7 @@ impl<'a> QueryContext<'a> {
             compute_allocation: canister.scheduler_state.compute_allocation,
             subnet_type: self.own_subnet_type,
             execution_mode: ExecutionMode::NonReplicated,
+            subnet_memory_saturation: ResourceSaturation::default(),
         }
     }
 }
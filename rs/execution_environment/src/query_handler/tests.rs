@@ -3,11 +3,11 @@ use ic_base_types::NumSeconds;
 use ic_error_types::{ErrorCode, UserError};
 use ic_registry_subnet_type::SubnetType;
 use ic_test_utilities::{
-    execution_environment::ExecutionTestBuilder,
+    execution_environment::{ExecutionTest, ExecutionTestBuilder},
     types::ids::user_test_id,
     universal_canister::{call_args, wasm},
 };
-use ic_types::{ingress::WasmResult, messages::UserQuery, Cycles};
+use ic_types::{ingress::WasmResult, messages::UserQuery, CanisterId, Cycles, NumInstructions};
 use std::sync::Arc;
 
 const CYCLES_BALANCE: Cycles = Cycles::new(100_000_000_000_000);
@@ -390,3 +390,172 @@ fn queries_to_frozen_canisters_are_rejected() {
     );
     assert!(result.is_ok());
 }
+
+// A canister with a composite query `forward` that replies with "pong" if its
+// argument is empty. Otherwise, it calls `forward` on the canister whose id is
+// in the first 10 bytes of the argument, passes on the rest of the argument
+// and relays the reply or reject.
+const FORWARDING_WAT: &str = r#"
+    (module
+        (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+        (import "ic0" "msg_arg_data_copy" (func $msg_arg_data_copy (param i32 i32 i32)))
+        (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (import "ic0" "msg_reject" (func $msg_reject (param i32 i32)))
+        (import "ic0" "msg_reject_msg_size" (func $msg_reject_msg_size (result i32)))
+        (import "ic0" "msg_reject_msg_copy" (func $msg_reject_msg_copy (param i32 i32 i32)))
+        (import "ic0" "call_new"
+            (func $call_new (param i32 i32 i32 i32 i32 i32 i32 i32)))
+        (import "ic0" "call_data_append" (func $call_data_append (param i32 i32)))
+        (import "ic0" "call_perform" (func $call_perform (result i32)))
+        (func $forward
+            (if (i32.eqz (call $msg_arg_data_size))
+                (then
+                    (call $msg_reply_data_append (i32.const 10) (i32.const 4))
+                    (call $msg_reply)
+                    (return)))
+            (call $msg_arg_data_copy (i32.const 100) (i32.const 0) (call $msg_arg_data_size))
+            (call $call_new
+                (i32.const 100) (i32.const 10)  ;; callee canister id
+                (i32.const 0) (i32.const 7)     ;; refers to "forward" on the heap
+                (i32.const 0) (i32.const 0)     ;; on_reply closure
+                (i32.const 1) (i32.const 0))    ;; on_reject closure
+            (call $call_data_append
+                (i32.const 110) (i32.sub (call $msg_arg_data_size) (i32.const 10)))
+            (drop (call $call_perform)))
+        (func $on_reply (param $env i32)
+            (call $msg_arg_data_copy (i32.const 100) (i32.const 0) (call $msg_arg_data_size))
+            (call $msg_reply_data_append (i32.const 100) (call $msg_arg_data_size))
+            (call $msg_reply))
+        (func $on_reject (param $env i32)
+            (call $msg_reject_msg_copy (i32.const 100) (i32.const 0) (call $msg_reject_msg_size))
+            (call $msg_reject (i32.const 100) (call $msg_reject_msg_size)))
+        (table funcref (elem $on_reply $on_reject))
+        (memory 1)
+        (data (i32.const 0) "forward")
+        (data (i32.const 10) "pong")
+        (export "canister_composite_query forward" (func $forward))
+    )"#;
+
+// A canister with a regular query `forward` that always replies with "pong".
+const PONG_WAT: &str = r#"
+    (module
+        (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (func (export "canister_query forward")
+            (call $msg_reply_data_append (i32.const 0) (i32.const 4))
+            (call $msg_reply))
+        (memory 1)
+        (data (i32.const 0) "pong")
+    )"#;
+
+// A canister with a regular query `forward` that never terminates.
+const LOOP_WAT: &str = r#"
+    (module
+        (func (export "canister_query forward")
+            (loop $loop (br $loop)))
+    )"#;
+
+fn forward_query(
+    test: &ExecutionTest,
+    receiver: CanisterId,
+    callees: &[CanisterId],
+) -> Result<WasmResult, UserError> {
+    test.query(
+        UserQuery {
+            source: user_test_id(2),
+            receiver,
+            method_name: "forward".to_string(),
+            method_payload: callees
+                .iter()
+                .flat_map(|callee| callee.get_ref().as_slice().to_vec())
+                .collect(),
+            ingress_expiry: 0,
+            nonce: None,
+        },
+        Arc::new(test.state().clone()),
+        vec![],
+    )
+}
+
+#[test]
+fn composite_query_calls_other_canisters_on_application_subnet() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_a = test.canister_from_wat(FORWARDING_WAT).unwrap();
+    let canister_b = test.canister_from_wat(FORWARDING_WAT).unwrap();
+    let canister_c = test.canister_from_wat(PONG_WAT).unwrap();
+
+    let output = forward_query(&test, canister_a, &[canister_b, canister_c]);
+    assert_eq!(output, Ok(WasmResult::Reply(b"pong".to_vec())));
+}
+
+#[test]
+fn regular_query_called_by_composite_query_cannot_call_other_canisters() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_a = test.canister_from_wat(FORWARDING_WAT).unwrap();
+    let canister_b = test
+        .canister_from_wat(
+            FORWARDING_WAT.replace("canister_composite_query forward", "canister_query forward"),
+        )
+        .unwrap();
+    let canister_c = test.canister_from_wat(PONG_WAT).unwrap();
+
+    // Without outgoing calls the regular query of canister B replies.
+    let output = forward_query(&test, canister_a, &[canister_b]);
+    assert_eq!(output, Ok(WasmResult::Reply(b"pong".to_vec())));
+
+    let output = forward_query(&test, canister_a, &[canister_b, canister_c]);
+    match output {
+        Ok(WasmResult::Reject(msg)) => assert!(
+            msg.contains("\"ic0_call_new\" cannot be executed"),
+            "{}",
+            msg
+        ),
+        _ => panic!("Unexpected result: {:?}", output),
+    }
+}
+
+#[test]
+fn composite_query_call_graph_depth_is_limited() {
+    let mut test = ExecutionTestBuilder::new().build();
+    downcast_query_handler_mut(test.query_handler_mut())
+        .config
+        .max_query_call_graph_depth = 2;
+    let canister_a = test.canister_from_wat(FORWARDING_WAT).unwrap();
+    let canister_b = test.canister_from_wat(FORWARDING_WAT).unwrap();
+    let canister_c = test.canister_from_wat(FORWARDING_WAT).unwrap();
+    let canister_d = test.canister_from_wat(FORWARDING_WAT).unwrap();
+
+    let output = forward_query(&test, canister_a, &[canister_b, canister_c]);
+    assert_eq!(output, Ok(WasmResult::Reply(b"pong".to_vec())));
+
+    let output = forward_query(&test, canister_a, &[canister_b, canister_c, canister_d]);
+    match output {
+        Ok(WasmResult::Reject(msg)) => assert!(msg.contains("maximum depth of 2"), "{}", msg),
+        _ => panic!("Unexpected result: {:?}", output),
+    }
+}
+
+#[test]
+fn composite_query_call_graph_instructions_are_limited() {
+    let mut test = ExecutionTestBuilder::new().build();
+    downcast_query_handler_mut(test.query_handler_mut())
+        .config
+        .max_query_call_graph_instructions = NumInstructions::from(1_000_000);
+    let canister_a = test.canister_from_wat(FORWARDING_WAT).unwrap();
+    let canister_b = test.canister_from_wat(LOOP_WAT).unwrap();
+
+    let err = forward_query(&test, canister_a, &[canister_b]).unwrap_err();
+    assert_eq!(
+        err.code(),
+        ErrorCode::QueryCallGraphTotalInstructionLimitExceeded
+    );
+}
+
+#[test]
+fn composite_query_cannot_be_called_in_replicated_mode() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(FORWARDING_WAT).unwrap();
+    let err = test.ingress(canister_id, "forward", vec![]).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CompositeQueryCalledInReplicatedMode);
+}
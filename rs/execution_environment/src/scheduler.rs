@@ -1,5 +1,5 @@
 use crate::{
-    canister_manager::{uninstall_canister, InstallCodeContext},
+    canister_manager::{remove_canister_snapshots, uninstall_canister, InstallCodeContext},
     execution_environment::{
         as_num_instructions, as_round_instructions, execute_canister, ExecuteCanisterResult,
         ExecutionEnvironment, RoundInstructions, RoundLimits,
@@ -308,19 +308,41 @@ impl SchedulerImpl {
 
         let mut total_heap_delta = NumBytes::from(0);
 
-        // Add `Heartbeat` tasks to be executed before input messages.
+        // Add `Heartbeat`, `GlobalTimer` and `OnLowWasmMemory` tasks to be
+        // executed before input messages.
         {
             let _timer = self
                 .metrics
                 .round_inner_heartbeat_overhead_duration
                 .start_timer();
+            let now = state.time();
             for canister in state.canisters_iter_mut() {
-                if canister.exports_heartbeat_method() {
-                    if let Some(execution_state) = canister.execution_state.as_mut() {
-                        execution_state
-                            .task_queue
-                            .push_back(ExecutionTask::Heartbeat);
-                    }
+                let exports_heartbeat = canister.exports_heartbeat_method();
+                let global_timer_fired = canister.exports_global_timer_method()
+                    && canister.system_state.global_timer.has_reached_deadline(now);
+                if exports_heartbeat {
+                    canister
+                        .system_state
+                        .task_queue
+                        .push_back(ExecutionTask::Heartbeat);
+                }
+                if global_timer_fired {
+                    canister
+                        .system_state
+                        .task_queue
+                        .push_back(ExecutionTask::GlobalTimer);
+                }
+                canister.update_on_low_wasm_memory_hook_status();
+                if canister.exports_on_low_wasm_memory_method()
+                    && canister
+                        .system_state
+                        .on_low_wasm_memory_hook_status
+                        .is_ready()
+                {
+                    canister
+                        .system_state
+                        .task_queue
+                        .push_back(ExecutionTask::OnLowWasmMemory);
                 }
             }
         }
@@ -422,16 +444,21 @@ impl SchedulerImpl {
                 .metrics
                 .round_inner_heartbeat_overhead_duration
                 .start_timer();
-            // Remove all remaining `Heartbeat` tasks because they will be added
-            // again in the next round.
+            // Remove all remaining `Heartbeat`, `GlobalTimer` and
+            // `OnLowWasmMemory` tasks because they will be added again in the
+            // next round. A global timer that didn't get to run is still
+            // active, so it fires in the next round, and likewise a hook that
+            // didn't get to run is still ready.
             for canister in state.canisters_iter_mut() {
-                if let Some(execution_state) = canister.execution_state.as_mut() {
-                    execution_state.task_queue.retain(|task| match task {
-                        ExecutionTask::Heartbeat => false,
-                        ExecutionTask::PausedExecution(..)
-                        | ExecutionTask::AbortedExecution(..) => true,
-                    });
-                }
+                canister.system_state.task_queue.retain(|task| match task {
+                    ExecutionTask::Heartbeat
+                    | ExecutionTask::GlobalTimer
+                    | ExecutionTask::OnLowWasmMemory => false,
+                    ExecutionTask::PausedExecution(..)
+                    | ExecutionTask::AbortedExecution(..)
+                    | ExecutionTask::PausedInstallCode(..)
+                    | ExecutionTask::AbortedInstallCode(..) => true,
+                });
             }
         }
 
@@ -700,7 +727,14 @@ impl SchedulerImpl {
 
         let state_path = state.root.clone();
         let state_time = state.time();
+        let mut snapshots_memory_usage = BTreeMap::<CanisterId, NumBytes>::new();
+        for (snapshot_id, snapshot) in state.canister_snapshots.iter() {
+            *snapshots_memory_usage
+                .entry(snapshot_id.canister_id())
+                .or_default() += snapshot.size();
+        }
         let mut all_rejects = Vec::new();
+        let mut uninstalled_canisters = Vec::new();
         for canister in state.canisters_iter_mut() {
             self.observe_canister_metrics(canister);
             if self
@@ -708,6 +742,10 @@ impl SchedulerImpl {
                 .charge_canister_for_resource_allocation_and_usage(
                     &self.log,
                     canister,
+                    snapshots_memory_usage
+                        .get(&canister.canister_id())
+                        .cloned()
+                        .unwrap_or_default(),
                     duration_since_last_charge,
                     subnet_size,
                 )
@@ -719,6 +757,7 @@ impl SchedulerImpl {
                     &state_path,
                     state_time,
                 ));
+                uninstalled_canisters.push(canister.canister_id());
                 canister.scheduler_state.compute_allocation = ComputeAllocation::zero();
                 canister.system_state.memory_allocation = MemoryAllocation::BestEffort;
 
@@ -731,6 +770,12 @@ impl SchedulerImpl {
             }
         }
 
+        // Snapshots of uninstalled canisters are deleted as well, since the
+        // canisters cannot pay for them anymore.
+        for canister_id in uninstalled_canisters {
+            remove_canister_snapshots(state, canister_id);
+        }
+
         // Send rejects to any requests that were forcibly closed while uninstalling.
         for rejects in all_rejects.into_iter() {
             process_responses(
@@ -881,6 +926,10 @@ impl Scheduler for SchedulerImpl {
             {
                 let _timer = self.metrics.round_preparation_ingress.start_timer();
                 self.purge_expired_ingress_messages(&mut state);
+                let timed_out_callbacks = state.time_out_callbacks();
+                self.metrics
+                    .timed_out_callbacks_count
+                    .inc_by(timed_out_callbacks as u64);
             }
 
             // See documentation around definition of `heap_delta_estimate` for an
@@ -990,9 +1039,47 @@ impl Scheduler for SchedulerImpl {
             let measurement_scope =
                 MeasurementScope::nested(&self.metrics.round_subnet_queue, &measurement_scope);
 
+            // Advance the `install_code` executions that were paused or
+            // aborted in the previous rounds.
+            let long_install_code_canister_ids: Vec<CanisterId> = state
+                .canisters_iter()
+                .filter(|canister| {
+                    canister.has_paused_install_code() || canister.has_aborted_install_code()
+                })
+                .map(|canister| canister.canister_id())
+                .collect();
+            for canister_id in long_install_code_canister_ids {
+                if round_limits.instructions <= RoundInstructions::from(0) {
+                    break;
+                }
+                let instruction_limits = InstructionLimits::new(
+                    self.deterministic_time_slicing,
+                    self.config.max_instructions_per_install_code,
+                    self.config.max_instructions_per_slice,
+                );
+                let instructions_before = round_limits.instructions;
+                state = self.exec_env.resume_install_code(
+                    state,
+                    &canister_id,
+                    instruction_limits,
+                    &mut round_limits,
+                );
+                let instructions_executed =
+                    as_num_instructions(instructions_before - round_limits.instructions);
+                measurement_scope.add(instructions_executed, NumMessages::from(1));
+            }
+
             let mut total_bitcoin_requests = 0;
 
-            while let Some(msg) = state.pop_subnet_input() {
+            // Subnet messages are executed in order, so no other subnet message
+            // is executed while an `install_code` execution is in progress.
+            let mut ongoing_long_install_code = has_long_install_code(&state);
+            while !ongoing_long_install_code {
+                let msg = match state.pop_subnet_input() {
+                    Some(msg) => msg,
+                    None => break,
+                };
+                let is_install_code = is_install_code(&msg);
                 let max_instructions_per_message =
                     get_instructions_limit_for_subnet_message(&self.config, &msg);
                 let instruction_limits = InstructionLimits::new(
@@ -1021,6 +1108,9 @@ impl Scheduler for SchedulerImpl {
                 if round_limits.instructions <= RoundInstructions::from(0) {
                     break;
                 }
+                if is_install_code {
+                    ongoing_long_install_code = has_long_install_code(&state);
+                }
 
                 // Stop after executing at most `MAX_BITCOIN_REQUESTS_PER_ROUND`.
                 //
@@ -1531,6 +1621,12 @@ fn get_instructions_limit_for_subnet_message(
             | SetupInitialDKG
             | SignWithECDSA
             | ComputeInitialEcdsaDealings
+            | TakeCanisterSnapshot
+            | LoadCanisterSnapshot
+            | ListCanisterSnapshots
+            | DeleteCanisterSnapshot
+            | FetchCanisterLogs
+            | CanisterInfo
             | StartCanister
             | StopCanister
             | UninstallCode
@@ -1553,6 +1649,27 @@ fn get_instructions_limit_for_subnet_message(
     }
 }
 
+/// Returns true if the given subnet message is an `install_code` message.
+fn is_install_code(msg: &CanisterInputMessage) -> bool {
+    let method_name = match msg {
+        CanisterInputMessage::Response(_) => return false,
+        CanisterInputMessage::Ingress(ingress) => &ingress.method_name,
+        CanisterInputMessage::Request(request) => &request.method_name,
+    };
+    matches!(
+        Ic00Method::from_str(method_name),
+        Ok(Ic00Method::InstallCode)
+    )
+}
+
+/// Returns true if some canister has a paused or aborted `install_code`
+/// execution.
+fn has_long_install_code(state: &ReplicatedState) -> bool {
+    state
+        .canisters_iter()
+        .any(|canister| canister.has_paused_install_code() || canister.has_aborted_install_code())
+}
+
 fn is_bitcoin_request(msg: &CanisterInputMessage) -> bool {
     use Ic00Method::*;
 
@@ -1575,6 +1692,12 @@ fn is_bitcoin_request(msg: &CanisterInputMessage) -> bool {
                 | SetupInitialDKG
                 | SignWithECDSA
                 | ComputeInitialEcdsaDealings
+                | TakeCanisterSnapshot
+                | LoadCanisterSnapshot
+                | ListCanisterSnapshots
+                | DeleteCanisterSnapshot
+                | FetchCanisterLogs
+                | CanisterInfo
                 | StartCanister
                 | StopCanister
                 | UninstallCode
@@ -49,6 +49,7 @@ use ic_test_utilities::{
 use ic_types::{
     messages::{CallContextId, Request, RequestOrResponse, Response},
     methods::{Callback, FuncRef, SystemMethod, WasmClosure, WasmMethod},
+    time::NO_DEADLINE,
     ComputeAllocation, Cycles, ExecutionRound, MemoryAllocation, NumInstructions, Randomness, Time,
     UserId,
 };
@@ -163,7 +164,9 @@ impl SchedulerTest {
     /// The `system_method` parameter can be used to optionally enable the
     /// heartbeat by passing `Some(SystemMethod::CanisterHeartbeat)`.
     /// In that case the heartbeat execution must be specified before each
-    /// round using `expect_heartbeat()`.
+    /// round using `expect_heartbeat()`. Similarly, the global timer can be
+    /// enabled by passing `Some(SystemMethod::CanisterGlobalTimer)` and its
+    /// execution must be specified using `expect_global_timer()`.
     pub fn create_canister_with(
         &mut self,
         cycles: Cycles,
@@ -339,7 +342,22 @@ impl SchedulerTest {
              `create_canister_with(.., Some(SystemMethod::Heartbeat))`"
         );
         let mut wasm_executor = self.wasm_executor.core.lock().unwrap();
-        wasm_executor.push_heartbeat(canister_id, heartbeat);
+        wasm_executor.push_system_task(canister_id, heartbeat);
+    }
+
+    /// Specifies global timer execution for the next round.
+    pub fn expect_global_timer(&mut self, canister_id: CanisterId, global_timer: TestMessage) {
+        assert!(
+            self.canister_state(canister_id)
+                .execution_state
+                .as_ref()
+                .unwrap()
+                .exports_method(&WasmMethod::System(SystemMethod::CanisterGlobalTimer)),
+            "The canister should be created with \
+             `create_canister_with(.., Some(SystemMethod::CanisterGlobalTimer))`"
+        );
+        let mut wasm_executor = self.wasm_executor.core.lock().unwrap();
+        wasm_executor.push_system_task(canister_id, global_timer);
     }
 
     pub fn execute_round(&mut self, round_type: ExecutionRoundType) {
@@ -740,7 +758,7 @@ struct TestWasmExecutorCore {
     messages: HashMap<u32, TestMessage>,
     install_code: HashMap<CanisterId, VecDeque<TestInstallCode>>,
     current_install_code: Option<TestInstallCode>,
-    heartbeat: HashMap<CanisterId, VecDeque<TestMessage>>,
+    system_task: HashMap<CanisterId, VecDeque<TestMessage>>,
     schedule: Vec<(ThreadId, ExecutionRound, CanisterId, NumInstructions)>,
     next_message_id: u32,
     round: ExecutionRound,
@@ -752,7 +770,7 @@ impl TestWasmExecutorCore {
             messages: HashMap::new(),
             install_code: HashMap::new(),
             current_install_code: None,
-            heartbeat: HashMap::new(),
+            system_task: HashMap::new(),
             schedule: vec![],
             next_message_id: 0,
             round: ExecutionRound::new(0),
@@ -790,6 +808,7 @@ impl TestWasmExecutorCore {
                     accessed_pages: 0,
                     dirty_pages: 0,
                 },
+                instruction_profile: None,
             };
             let system_state_changes = SystemStateChanges::default();
             self.schedule
@@ -824,6 +843,7 @@ impl TestWasmExecutorCore {
             allocated_message_bytes: NumBytes::from(0),
             num_instructions_left: instructions_left,
             instance_stats,
+            instruction_profile: None,
         };
         self.schedule
             .push((thread_id, self.round, canister_id, message.instructions));
@@ -916,6 +936,7 @@ impl TestWasmExecutorCore {
                 on_reply: closure.clone(),
                 on_reject: closure,
                 on_cleanup: None,
+                deadline: NO_DEADLINE,
             })
             .map_err(|err| err.to_string())?;
         let request = Request {
@@ -925,6 +946,7 @@ impl TestWasmExecutorCore {
             payment: Cycles::zero(),
             method_name: "update".into(),
             method_payload: encode_message_id_as_payload(call_message_id),
+            deadline: NO_DEADLINE,
         };
         system_state
             .push_output_request(canister_current_memory_usage, compute_allocation, request)
@@ -963,12 +985,12 @@ impl TestWasmExecutorCore {
                 let message = self.messages.remove(&message_id).unwrap();
                 (message_id, message, Some(*call_context_id))
             }
-            ApiType::Heartbeat {
+            ApiType::SystemTask {
                 call_context_id, ..
             } => {
                 let message_id = self.next_message_id();
                 let message = self
-                    .heartbeat
+                    .system_task
                     .get_mut(&canister_id)
                     .unwrap()
                     .pop_front()
@@ -1066,11 +1088,11 @@ impl TestWasmExecutorCore {
             .push_back(install_code);
     }
 
-    fn push_heartbeat(&mut self, canister_id: CanisterId, heartbeat: TestMessage) {
-        self.heartbeat
+    fn push_system_task(&mut self, canister_id: CanisterId, system_task: TestMessage) {
+        self.system_task
             .entry(canister_id)
             .or_default()
-            .push_back(heartbeat);
+            .push_back(system_task);
     }
 
     fn next_message_id(&mut self) -> u32 {
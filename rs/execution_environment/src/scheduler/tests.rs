@@ -13,7 +13,7 @@ use ic_logger::replica_logger::no_op_logger;
 use ic_registry_routing_table::CanisterIdRange;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::testing::CanisterQueuesTesting;
-use ic_replicated_state::CanisterStatus;
+use ic_replicated_state::{CanisterStatus, CanisterTimer};
 
 use ic_test_utilities::{
     mock_time,
@@ -1141,6 +1141,42 @@ fn execute_heartbeat_before_messages() {
     assert_eq!(test.ingress_queue_size(canister), 3);
 }
 
+#[test]
+fn execute_global_timer_once_deadline_is_reached() {
+    let mut test = SchedulerTestBuilder::new()
+        .with_scheduler_config(SchedulerConfig {
+            scheduler_cores: 1,
+            ..SchedulerConfig::application_subnet()
+        })
+        .build();
+    let canister = test.create_canister_with(
+        Cycles::new(1_000_000_000_000),
+        ComputeAllocation::zero(),
+        MemoryAllocation::BestEffort,
+        Some(SystemMethod::CanisterGlobalTimer),
+    );
+    let now = test.state().time();
+    test.canister_state_mut(canister).system_state.global_timer =
+        CanisterTimer::Active(now + Duration::from_secs(1));
+
+    // The deadline is in the future, so the global timer doesn't run.
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    let metrics = &test.scheduler().metrics;
+    assert_eq!(metrics.round_inner.messages.get_sample_sum(), 0.0);
+
+    // Once the deadline is reached, the global timer runs exactly once.
+    test.state_mut().metadata.batch_time = now + Duration::from_secs(1);
+    test.expect_global_timer(canister, instructions(1));
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    let metrics = &test.scheduler().metrics;
+    assert_eq!(metrics.round_inner.messages.get_sample_sum(), 1.0);
+    assert_eq!(
+        test.canister_state(canister).system_state.global_timer,
+        CanisterTimer::Inactive
+    );
+}
+
 #[test]
 fn execute_multiple_heartbeats() {
     // This tests multiple canisters with heartbeat methods running over multiple
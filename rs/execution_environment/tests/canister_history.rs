@@ -0,0 +1,197 @@
+use ic_error_types::ErrorCode;
+use ic_ic00_types::{
+    CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterInfoRequest,
+    CanisterInfoResponse, CanisterInstallMode, Method, Payload as Ic00Payload,
+};
+use ic_test_utilities::execution_environment::{ExecutionTest, ExecutionTestBuilder};
+use ic_test_utilities::types::ids::{canister_test_id, subnet_test_id, user_test_id};
+use ic_types::{
+    messages::{Payload, RequestOrResponse},
+    CanisterId, Cycles,
+};
+use ic_wasm_types::CanisterModule;
+
+const EMPTY_WAT: &str = "(module)";
+
+const OTHER_WAT: &str = r#"(module (func (export "canister_query read")))"#;
+
+fn module_hash(wat: &str) -> [u8; 32] {
+    CanisterModule::new(wabt::wat2wasm(wat).unwrap()).module_hash()
+}
+
+fn changes(test: &ExecutionTest, canister_id: CanisterId) -> Vec<CanisterChange> {
+    test.canister_state(canister_id)
+        .system_state
+        .canister_history()
+        .changes()
+        .iter()
+        .cloned()
+        .collect()
+}
+
+fn canister_info(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    num_requested_changes: Option<u64>,
+) -> CanisterInfoResponse {
+    test.inject_call_to_ic00(
+        Method::CanisterInfo,
+        CanisterInfoRequest::new(canister_id, num_requested_changes).encode(),
+        Cycles::zero(),
+    );
+    test.execute_all();
+    match test.xnet_messages().last().unwrap().clone() {
+        RequestOrResponse::Response(response) => match &response.response_payload {
+            Payload::Data(bytes) => CanisterInfoResponse::decode(bytes).unwrap(),
+            Payload::Reject(reject) => panic!("Unexpected reject: {}", reject.message),
+        },
+        RequestOrResponse::Request(_) => panic!("Expected a response"),
+    }
+}
+
+#[test]
+fn canister_history_records_code_and_controller_changes() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let user = test.user_id().get();
+    let timestamp_nanos = test.time().as_nanos_since_unix_epoch();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    test.install_canister(canister_id, wabt::wat2wasm(EMPTY_WAT).unwrap())
+        .unwrap();
+    test.upgrade_canister(canister_id, wabt::wat2wasm(OTHER_WAT).unwrap())
+        .unwrap();
+    test.uninstall_code(canister_id).unwrap();
+    let new_controller = user_test_id(42).get();
+    test.set_controller(canister_id, new_controller).unwrap();
+
+    let origin = CanisterChangeOrigin::from_user(user);
+    assert_eq!(
+        changes(&test, canister_id),
+        vec![
+            CanisterChange::new(
+                timestamp_nanos,
+                origin.clone(),
+                CanisterChangeDetails::canister_creation(vec![user]),
+            ),
+            CanisterChange::new(
+                timestamp_nanos,
+                origin.clone(),
+                CanisterChangeDetails::code_deployment(
+                    CanisterInstallMode::Install,
+                    module_hash(EMPTY_WAT),
+                ),
+            ),
+            CanisterChange::new(
+                timestamp_nanos,
+                origin.clone(),
+                CanisterChangeDetails::code_deployment(
+                    CanisterInstallMode::Upgrade,
+                    module_hash(OTHER_WAT),
+                ),
+            ),
+            CanisterChange::new(
+                timestamp_nanos,
+                origin.clone(),
+                CanisterChangeDetails::code_uninstall(),
+            ),
+            CanisterChange::new(
+                timestamp_nanos,
+                origin,
+                CanisterChangeDetails::controllers_change(vec![new_controller]),
+            ),
+        ]
+    );
+}
+
+#[test]
+fn failed_changes_are_not_recorded() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(EMPTY_WAT).unwrap();
+    let num_changes = changes(&test, canister_id).len();
+
+    // Installing into a non-empty canister fails.
+    let err = test
+        .install_canister(canister_id, wabt::wat2wasm(EMPTY_WAT).unwrap())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterNonEmpty);
+
+    // Only controllers can change the settings.
+    test.set_user_id(user_test_id(42));
+    let err = test
+        .set_controller(canister_id, user_test_id(42).get())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterInvalidController);
+
+    assert_eq!(changes(&test, canister_id).len(), num_changes);
+}
+
+#[test]
+fn updating_other_settings_is_not_recorded() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(EMPTY_WAT).unwrap();
+    let num_changes = changes(&test, canister_id).len();
+    test.update_freezing_threshold(canister_id, 1_000.into())
+        .unwrap();
+    assert_eq!(changes(&test, canister_id).len(), num_changes);
+}
+
+#[test]
+fn canister_info_returns_requested_changes() {
+    let caller = canister_test_id(10);
+    let mut test = ExecutionTestBuilder::new()
+        .with_caller(subnet_test_id(2), caller)
+        .build();
+    let canister_id = test.canister_from_wat(EMPTY_WAT).unwrap();
+    let user = test.user_id().get();
+
+    let info = canister_info(&mut test, canister_id, None);
+    assert_eq!(info.total_num_changes, 2);
+    assert_eq!(info.recent_changes, vec![]);
+    assert_eq!(info.module_hash, Some(module_hash(EMPTY_WAT).to_vec()));
+    assert_eq!(info.controllers, vec![user]);
+
+    let info = canister_info(&mut test, canister_id, Some(1));
+    assert_eq!(
+        info.recent_changes,
+        changes(&test, canister_id)[1..].to_vec()
+    );
+
+    let info = canister_info(&mut test, canister_id, Some(10));
+    assert_eq!(info.recent_changes, changes(&test, canister_id));
+}
+
+#[test]
+fn changes_made_by_canisters_record_the_canister() {
+    let caller = canister_test_id(10);
+    let mut test = ExecutionTestBuilder::new()
+        .with_caller(subnet_test_id(2), caller)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    test.set_controller(canister_id, caller.get()).unwrap();
+
+    test.inject_call_to_ic00(
+        Method::UninstallCode,
+        ic_ic00_types::CanisterIdRecord::from(canister_id).encode(),
+        Cycles::zero(),
+    );
+    test.execute_all();
+
+    let last_change = changes(&test, canister_id).pop().unwrap();
+    assert_eq!(
+        last_change.origin,
+        CanisterChangeOrigin::from_canister(caller.get())
+    );
+    assert_eq!(last_change.details, CanisterChangeDetails::code_uninstall());
+}
+
+#[test]
+fn canister_info_cannot_be_called_via_ingress() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(EMPTY_WAT).unwrap();
+    let err = test
+        .subnet_message(
+            Method::CanisterInfo,
+            CanisterInfoRequest::new(canister_id, None).encode(),
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterMethodNotFound);
+}
@@ -0,0 +1,143 @@
+use ic_error_types::ErrorCode;
+use ic_ic00_types::{CanisterLogRecord, FetchCanisterLogsResponse, LogVisibility, Payload};
+use ic_test_utilities::execution_environment::{ExecutionTest, ExecutionTestBuilder};
+use ic_test_utilities::types::ids::user_test_id;
+use ic_types::{ingress::WasmResult, CanisterId, Cycles};
+
+// A canister that prints "hello" in `print` and additionally traps with
+// "oops" in `trap` or hits `unreachable` in `unreachable`.
+const LOGGING_WAT: &str = r#"
+    (module
+        (import "ic0" "debug_print" (func $debug_print (param i32 i32)))
+        (import "ic0" "trap" (func $trap (param i32 i32)))
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (func (export "canister_update print")
+            (call $debug_print (i32.const 0) (i32.const 5))
+            (call $msg_reply)
+        )
+        (func (export "canister_update trap")
+            (call $debug_print (i32.const 0) (i32.const 5))
+            (call $trap (i32.const 5) (i32.const 4))
+        )
+        (func (export "canister_update unreachable")
+            (call $debug_print (i32.const 0) (i32.const 5))
+            unreachable
+        )
+        (memory (export "memory") 1)
+        (data (i32.const 0) "hellooops")
+    )"#;
+
+fn fetch_logs(test: &mut ExecutionTest, canister_id: CanisterId) -> Vec<CanisterLogRecord> {
+    match test.fetch_canister_logs(canister_id).unwrap() {
+        WasmResult::Reply(bytes) => {
+            FetchCanisterLogsResponse::decode(&bytes)
+                .unwrap()
+                .canister_log_records
+        }
+        WasmResult::Reject(msg) => panic!("Unexpected reject: {}", msg),
+    }
+}
+
+#[test]
+fn debug_prints_are_recorded_in_canister_log() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(LOGGING_WAT).unwrap();
+    test.ingress(canister_id, "print", vec![]).unwrap();
+    test.ingress(canister_id, "print", vec![]).unwrap();
+    let timestamp_nanos = test.time().as_nanos_since_unix_epoch();
+    assert_eq!(
+        fetch_logs(&mut test, canister_id),
+        vec![
+            CanisterLogRecord {
+                idx: 0,
+                timestamp_nanos,
+                content: b"hello".to_vec(),
+            },
+            CanisterLogRecord {
+                idx: 1,
+                timestamp_nanos,
+                content: b"hello".to_vec(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn trap_messages_are_recorded_in_canister_log() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(LOGGING_WAT).unwrap();
+    let err = test.ingress(canister_id, "trap", vec![]).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterCalledTrap);
+    // The messages printed before the trap are kept as well.
+    let contents: Vec<Vec<u8>> = fetch_logs(&mut test, canister_id)
+        .into_iter()
+        .map(|record| record.content)
+        .collect();
+    assert_eq!(contents, vec![b"hello".to_vec(), b"oops".to_vec()]);
+}
+
+#[test]
+fn all_traps_are_recorded_in_canister_log() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(LOGGING_WAT).unwrap();
+    let err = test
+        .ingress(canister_id, "unreachable", vec![])
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterTrapped);
+    let records = fetch_logs(&mut test, canister_id);
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].content, b"hello".to_vec());
+    assert!(records[1].content.starts_with(b"[TRAP]: "));
+}
+
+#[test]
+fn logs_of_failed_installation_are_kept() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let wasm = wabt::wat2wasm(
+        r#"
+        (module
+            (import "ic0" "debug_print" (func $debug_print (param i32 i32)))
+            (import "ic0" "trap" (func $trap (param i32 i32)))
+            (func (export "canister_init")
+                (call $debug_print (i32.const 0) (i32.const 5))
+                (call $trap (i32.const 5) (i32.const 4))
+            )
+            (memory 1)
+            (data (i32.const 0) "hellooops")
+        )"#,
+    )
+    .unwrap();
+    let err = test.install_canister(canister_id, wasm).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterCalledTrap);
+    let contents: Vec<Vec<u8>> = fetch_logs(&mut test, canister_id)
+        .into_iter()
+        .map(|record| record.content)
+        .collect();
+    assert_eq!(contents, vec![b"hello".to_vec(), b"oops".to_vec()]);
+}
+
+#[test]
+fn only_controllers_can_fetch_logs_by_default() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(LOGGING_WAT).unwrap();
+    test.ingress(canister_id, "print", vec![]).unwrap();
+    test.set_user_id(user_test_id(42));
+    let err = test.fetch_canister_logs(canister_id).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterInvalidController);
+}
+
+#[test]
+fn anyone_can_fetch_public_logs() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(LOGGING_WAT).unwrap();
+    test.ingress(canister_id, "print", vec![]).unwrap();
+    test.update_log_visibility(canister_id, LogVisibility::Public)
+        .unwrap();
+    assert_eq!(
+        test.canister_state(canister_id).system_state.log_visibility,
+        LogVisibility::Public
+    );
+    test.set_user_id(user_test_id(42));
+    assert_eq!(fetch_logs(&mut test, canister_id).len(), 1);
+}
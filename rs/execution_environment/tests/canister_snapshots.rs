@@ -0,0 +1,310 @@
+use ic_error_types::ErrorCode;
+use ic_ic00_types::{
+    CanisterIdRecord, CanisterSnapshotResponse, ListCanisterSnapshotsResponse, Method, Payload,
+};
+use ic_test_utilities::execution_environment::{ExecutionTest, ExecutionTestBuilder};
+use ic_test_utilities::types::ids::user_test_id;
+use ic_types::{
+    ingress::WasmResult, CanisterId, Cycles, MemoryAllocation, NumBytes, NumInstructions,
+    NumSeconds,
+};
+
+// A canister that keeps a counter in the first byte of its memory.
+const COUNTER_WAT: &str = r#"
+    (module
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (import "ic0" "msg_reply_data_append"
+            (func $msg_reply_data_append (param i32 i32)))
+        (func (export "canister_update inc")
+            (i32.store8 (i32.const 0)
+                (i32.add (i32.load8_u (i32.const 0)) (i32.const 1)))
+            (call $msg_reply)
+        )
+        (func (export "canister_update read")
+            (call $msg_reply_data_append (i32.const 0) (i32.const 1))
+            (call $msg_reply)
+        )
+        (memory (export "memory") 1)
+    )"#;
+
+fn take_snapshot(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    replace_snapshot: Option<Vec<u8>>,
+) -> CanisterSnapshotResponse {
+    match test
+        .take_canister_snapshot(canister_id, replace_snapshot)
+        .unwrap()
+    {
+        WasmResult::Reply(bytes) => CanisterSnapshotResponse::decode(&bytes).unwrap(),
+        WasmResult::Reject(msg) => panic!("Unexpected reject: {}", msg),
+    }
+}
+
+fn list_snapshots(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+) -> Vec<CanisterSnapshotResponse> {
+    match test.list_canister_snapshots(canister_id).unwrap() {
+        WasmResult::Reply(bytes) => ListCanisterSnapshotsResponse::decode(&bytes).unwrap().0,
+        WasmResult::Reject(msg) => panic!("Unexpected reject: {}", msg),
+    }
+}
+
+fn stop(test: &mut ExecutionTest, canister_id: CanisterId) {
+    test.stop_canister(canister_id);
+    test.process_stopping_canisters();
+}
+
+#[test]
+fn take_canister_snapshot_and_list_it() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_WAT).unwrap();
+    stop(&mut test, canister_id);
+    let snapshot = take_snapshot(&mut test, canister_id, None);
+    assert_eq!(
+        snapshot.taken_at_timestamp,
+        test.time().as_nanos_since_unix_epoch()
+    );
+    assert_eq!(
+        list_snapshots(&mut test, canister_id),
+        vec![snapshot.clone()]
+    );
+    assert_eq!(
+        test.state().canister_snapshots.memory_taken(canister_id),
+        NumBytes::from(snapshot.total_size)
+    );
+}
+
+#[test]
+fn load_canister_snapshot_restores_memory() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_WAT).unwrap();
+    test.ingress(canister_id, "inc", vec![]).unwrap();
+    stop(&mut test, canister_id);
+    let snapshot = take_snapshot(&mut test, canister_id, None);
+    test.start_canister(canister_id).unwrap();
+
+    test.ingress(canister_id, "inc", vec![]).unwrap();
+    let result = test.ingress(canister_id, "read", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![2]));
+
+    stop(&mut test, canister_id);
+    test.load_canister_snapshot(canister_id, snapshot.id.clone())
+        .unwrap();
+    test.start_canister(canister_id).unwrap();
+    let result = test.ingress(canister_id, "read", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![1]));
+
+    // The snapshot can be loaded again after the canister changed.
+    test.ingress(canister_id, "inc", vec![]).unwrap();
+    stop(&mut test, canister_id);
+    test.load_canister_snapshot(canister_id, snapshot.id)
+        .unwrap();
+    test.start_canister(canister_id).unwrap();
+    let result = test.ingress(canister_id, "read", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![1]));
+}
+
+#[test]
+fn load_canister_snapshot_after_uninstall() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_WAT).unwrap();
+    test.ingress(canister_id, "inc", vec![]).unwrap();
+    stop(&mut test, canister_id);
+    let snapshot = take_snapshot(&mut test, canister_id, None);
+    test.uninstall_code(canister_id).unwrap();
+    assert!(test.canister_state(canister_id).execution_state.is_none());
+
+    test.load_canister_snapshot(canister_id, snapshot.id)
+        .unwrap();
+    test.start_canister(canister_id).unwrap();
+    let result = test.ingress(canister_id, "read", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![1]));
+}
+
+#[test]
+fn take_canister_snapshot_fails_when_limit_is_reached() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_WAT).unwrap();
+    stop(&mut test, canister_id);
+    let snapshot = take_snapshot(&mut test, canister_id, None);
+
+    let err = test.take_canister_snapshot(canister_id, None).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterSnapshotLimitExceeded);
+
+    // Replacing the existing snapshot is allowed.
+    let new_snapshot = take_snapshot(&mut test, canister_id, Some(snapshot.id.clone()));
+    assert_ne!(new_snapshot.id, snapshot.id);
+    assert_eq!(list_snapshots(&mut test, canister_id), vec![new_snapshot]);
+}
+
+#[test]
+fn take_canister_snapshot_of_empty_canister_fails() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    stop(&mut test, canister_id);
+    let err = test.take_canister_snapshot(canister_id, None).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterWasmModuleNotFound);
+}
+
+#[test]
+fn delete_canister_snapshot_removes_it() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_WAT).unwrap();
+    stop(&mut test, canister_id);
+    let snapshot = take_snapshot(&mut test, canister_id, None);
+
+    test.delete_canister_snapshot(canister_id, snapshot.id.clone())
+        .unwrap();
+    assert_eq!(list_snapshots(&mut test, canister_id), vec![]);
+    assert_eq!(
+        test.state().canister_snapshots.memory_taken(canister_id),
+        NumBytes::from(0)
+    );
+
+    let err = test
+        .load_canister_snapshot(canister_id, snapshot.id)
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterSnapshotNotFound);
+}
+
+#[test]
+fn snapshots_of_other_canisters_cannot_be_loaded() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_WAT).unwrap();
+    let other_canister_id = test.canister_from_wat(COUNTER_WAT).unwrap();
+    stop(&mut test, canister_id);
+    stop(&mut test, other_canister_id);
+    let snapshot = take_snapshot(&mut test, canister_id, None);
+
+    let err = test
+        .load_canister_snapshot(other_canister_id, snapshot.id)
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterSnapshotNotFound);
+}
+
+#[test]
+fn invalid_snapshot_id_is_rejected() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_WAT).unwrap();
+    let err = test
+        .delete_canister_snapshot(canister_id, vec![1, 2, 3])
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::InvalidManagementPayload);
+}
+
+#[test]
+fn only_controllers_can_take_snapshots() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_WAT).unwrap();
+    test.set_user_id(user_test_id(42));
+    let err = test.take_canister_snapshot(canister_id, None).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterInvalidController);
+    let err = test.list_canister_snapshots(canister_id).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterInvalidController);
+}
+
+#[test]
+fn deleting_canister_deletes_its_snapshots() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_WAT).unwrap();
+    stop(&mut test, canister_id);
+    take_snapshot(&mut test, canister_id, None);
+    test.subnet_message(
+        Method::DeleteCanister,
+        CanisterIdRecord::from(canister_id).encode(),
+    )
+    .unwrap();
+    assert!(test.state().canister_snapshots.is_empty());
+}
+
+#[test]
+fn load_canister_snapshot_requires_stopped_canister() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_WAT).unwrap();
+    stop(&mut test, canister_id);
+    let snapshot = take_snapshot(&mut test, canister_id, None);
+    test.start_canister(canister_id).unwrap();
+
+    let err = test
+        .load_canister_snapshot(canister_id, snapshot.id.clone())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterNotStopped);
+
+    stop(&mut test, canister_id);
+    test.load_canister_snapshot(canister_id, snapshot.id)
+        .unwrap();
+}
+
+#[test]
+fn load_canister_snapshot_respects_memory_allocation() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_WAT).unwrap();
+    stop(&mut test, canister_id);
+    let snapshot = take_snapshot(&mut test, canister_id, None);
+    test.uninstall_code(canister_id).unwrap();
+    test.canister_state_mut(canister_id)
+        .system_state
+        .memory_allocation = MemoryAllocation::Reserved(NumBytes::from(100));
+
+    let err = test
+        .load_canister_snapshot(canister_id, snapshot.id)
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::InsufficientMemoryAllocation);
+    assert!(test.canister_state(canister_id).execution_state.is_none());
+}
+
+#[test]
+fn take_canister_snapshot_fails_below_freezing_threshold() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_WAT).unwrap();
+    test.canister_state_mut(canister_id)
+        .system_state
+        .freeze_threshold = NumSeconds::from(1 << 40);
+    stop(&mut test, canister_id);
+
+    let err = test.take_canister_snapshot(canister_id, None).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterOutOfCycles);
+    assert!(test.state().canister_snapshots.is_empty());
+}
+
+#[test]
+fn take_canister_snapshot_requires_stopped_canister() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_WAT).unwrap();
+
+    let err = test.take_canister_snapshot(canister_id, None).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterNotStopped);
+    assert!(test.state().canister_snapshots.is_empty());
+
+    stop(&mut test, canister_id);
+    take_snapshot(&mut test, canister_id, None);
+}
+
+#[test]
+fn take_canister_snapshot_charges_instructions_per_page() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(COUNTER_WAT).unwrap();
+    // Touches the first page of the Wasm memory.
+    test.ingress(canister_id, "inc", vec![]).unwrap();
+    stop(&mut test, canister_id);
+
+    let balance_before = test.canister_state(canister_id).system_state.balance();
+    let reserved_before = test
+        .canister_state(canister_id)
+        .system_state
+        .reserved_balance();
+    take_snapshot(&mut test, canister_id, None);
+    let balance_after = test.canister_state(canister_id).system_state.balance();
+    let reserved_after = test
+        .canister_state(canister_id)
+        .system_state
+        .reserved_balance();
+
+    assert_eq!(
+        balance_before - balance_after - (reserved_after - reserved_before),
+        test.cycles_account_manager()
+            .execution_cost(NumInstructions::new(4_096))
+    );
+}
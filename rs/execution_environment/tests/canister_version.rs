@@ -0,0 +1,175 @@
+use ic_test_utilities::{
+    execution_environment::ExecutionTestBuilder, types::ids::user_test_id, universal_canister::wasm,
+};
+use ic_types::{ingress::WasmResult, messages::UserQuery, Cycles, NumSeconds};
+use std::{convert::TryInto, sync::Arc};
+
+// A canister that exposes the results of `ic0.canister_version`,
+// `ic0.is_controller` and `ic0.in_replicated_execution`.
+const WAT: &str = r#"
+    (module
+        (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+        (import "ic0" "msg_arg_data_copy" (func $msg_arg_data_copy (param i32 i32 i32)))
+        (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (import "ic0" "canister_version" (func $canister_version (result i64)))
+        (import "ic0" "is_controller" (func $is_controller (param i32 i32) (result i32)))
+        (import "ic0" "in_replicated_execution" (func $in_replicated_execution (result i32)))
+        (func (export "canister_query version")
+            (i64.store (i32.const 0) (call $canister_version))
+            (call $msg_reply_data_append (i32.const 0) (i32.const 8))
+            (call $msg_reply))
+        (func (export "canister_query is_controller")
+            (call $msg_arg_data_copy (i32.const 100) (i32.const 0) (call $msg_arg_data_size))
+            (i32.store (i32.const 0)
+                (call $is_controller (i32.const 100) (call $msg_arg_data_size)))
+            (call $msg_reply_data_append (i32.const 0) (i32.const 4))
+            (call $msg_reply))
+        (func (export "canister_query replicated")
+            (i32.store (i32.const 0) (call $in_replicated_execution))
+            (call $msg_reply_data_append (i32.const 0) (i32.const 4))
+            (call $msg_reply))
+        (memory 1)
+    )"#;
+
+fn reply_u64(result: WasmResult) -> u64 {
+    match result {
+        WasmResult::Reply(bytes) => u64::from_le_bytes(bytes.try_into().unwrap()),
+        WasmResult::Reject(reason) => panic!("unexpected reject: {}", reason),
+    }
+}
+
+fn reply_u32(result: WasmResult) -> u32 {
+    match result {
+        WasmResult::Reply(bytes) => u32::from_le_bytes(bytes.try_into().unwrap()),
+        WasmResult::Reject(reason) => panic!("unexpected reject: {}", reason),
+    }
+}
+
+#[test]
+fn canister_version_is_incremented_on_install_upgrade_and_settings_change() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wasm = wabt::wat2wasm(WAT).unwrap();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .canister_version,
+        0
+    );
+
+    test.install_canister(canister_id, wasm.clone()).unwrap();
+    let result = test.ingress(canister_id, "version", vec![]).unwrap();
+    assert_eq!(reply_u64(result), 1);
+
+    test.upgrade_canister(canister_id, wasm).unwrap();
+    let result = test.ingress(canister_id, "version", vec![]).unwrap();
+    assert_eq!(reply_u64(result), 2);
+
+    test.update_freezing_threshold(canister_id, NumSeconds::from(1))
+        .unwrap();
+    let result = test.ingress(canister_id, "version", vec![]).unwrap();
+    assert_eq!(reply_u64(result), 3);
+}
+
+#[test]
+fn is_controller_checks_the_canister_controllers() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(WAT).unwrap();
+
+    let controller = test.user_id().get().to_vec();
+    let result = test
+        .ingress(canister_id, "is_controller", controller)
+        .unwrap();
+    assert_eq!(reply_u32(result), 1);
+
+    let other = user_test_id(1234).get().to_vec();
+    let result = test.ingress(canister_id, "is_controller", other).unwrap();
+    assert_eq!(reply_u32(result), 0);
+}
+
+#[test]
+fn in_replicated_execution_depends_on_execution_mode() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(WAT).unwrap();
+
+    // A query method called via ingress runs in replicated mode.
+    let result = test.ingress(canister_id, "replicated", vec![]).unwrap();
+    assert_eq!(reply_u32(result), 1);
+
+    let result = test
+        .query(
+            UserQuery {
+                source: user_test_id(2),
+                receiver: canister_id,
+                method_name: "replicated".to_string(),
+                method_payload: vec![],
+                ingress_expiry: 0,
+                nonce: None,
+            },
+            Arc::new(test.state().clone()),
+            vec![],
+        )
+        .unwrap();
+    assert_eq!(reply_u32(result), 0);
+}
+
+#[test]
+fn universal_canister_can_use_canister_version_is_controller_and_in_replicated_execution() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+
+    let result = test
+        .ingress(
+            canister_id,
+            "update",
+            wasm().canister_version().reply_int64().build(),
+        )
+        .unwrap();
+    assert_eq!(reply_u64(result), 1);
+
+    let controller = test.user_id().get().to_vec();
+    let result = test
+        .ingress(
+            canister_id,
+            "update",
+            wasm().is_controller(&controller).reply_int().build(),
+        )
+        .unwrap();
+    assert_eq!(reply_u32(result), 1);
+
+    let other = user_test_id(1234).get().to_vec();
+    let result = test
+        .ingress(
+            canister_id,
+            "update",
+            wasm().is_controller(&other).reply_int().build(),
+        )
+        .unwrap();
+    assert_eq!(reply_u32(result), 0);
+
+    let result = test
+        .ingress(
+            canister_id,
+            "update",
+            wasm().in_replicated_execution().reply_int().build(),
+        )
+        .unwrap();
+    assert_eq!(reply_u32(result), 1);
+
+    let result = test
+        .query(
+            UserQuery {
+                source: user_test_id(2),
+                receiver: canister_id,
+                method_name: "query".to_string(),
+                method_payload: wasm().in_replicated_execution().reply_int().build(),
+                ingress_expiry: 0,
+                nonce: None,
+            },
+            Arc::new(test.state().clone()),
+            vec![],
+        )
+        .unwrap();
+    assert_eq!(reply_u32(result), 0);
+}
@@ -36,6 +36,7 @@ use ic_types::{
     messages::{
         CallbackId, Payload, RejectContext, RequestOrResponse, Response, MAX_RESPONSE_COUNT_BYTES,
     },
+    time::NO_DEADLINE,
     CanisterId, Cycles, RegistryVersion, Time,
 };
 use ic_types::{messages::MAX_INTER_CANISTER_PAYLOAD_IN_BYTES, NumInstructions};
@@ -1094,7 +1095,8 @@ fn setup_initial_dkg_sender_not_on_nns() {
                     ic00::Method::SetupInitialDKG,
                     other_canister,
                 )
-            })
+            }),
+            deadline: NO_DEADLINE,
         }
         .into()
     );
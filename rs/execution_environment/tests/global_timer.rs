@@ -0,0 +1,129 @@
+use assert_matches::assert_matches;
+use ic_execution_environment::CanisterHeartbeatError;
+use ic_ic00_types::CanisterStatusType;
+use ic_interfaces::execution_environment::{HypervisorError, TrapCode};
+use ic_replicated_state::{page_map::PAGE_SIZE, CanisterTimer};
+use ic_test_utilities::execution_environment::ExecutionTestBuilder;
+use ic_types::{ingress::WasmResult, NumBytes, Time};
+
+// A canister that sets its global timer to the given time in `set` and
+// records the number of timer invocations in the first byte of its memory.
+const TIMER_WAT: &str = r#"
+    (module
+        (import "ic0" "global_timer_set"
+            (func $global_timer_set (param i64) (result i64)))
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (func (export "canister_update set")
+            (drop (call $global_timer_set (i64.const 1000)))
+            (call $msg_reply)
+        )
+        (func (export "canister_global_timer")
+            (i32.store8 (i32.const 0)
+                (i32.add (i32.load8_u (i32.const 0)) (i32.const 1)))
+        )
+        (memory (export "memory") 1)
+    )"#;
+
+#[test]
+fn global_timer_is_executed() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (func (export "canister_global_timer") unreachable)
+            (memory (export "memory") 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let err = test.global_timer(canister_id).unwrap_err();
+    assert_eq!(
+        err,
+        CanisterHeartbeatError::CanisterExecutionFailed(HypervisorError::Trapped(
+            TrapCode::Unreachable
+        ))
+    );
+}
+
+#[test]
+fn global_timer_produces_heap_delta() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(TIMER_WAT).unwrap();
+    assert_eq!(NumBytes::from(0), test.state().metadata.heap_delta_estimate);
+    test.global_timer(canister_id).unwrap();
+    assert_eq!(
+        NumBytes::from((PAGE_SIZE) as u64),
+        test.state().metadata.heap_delta_estimate
+    );
+}
+
+#[test]
+fn global_timer_can_be_set() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(TIMER_WAT).unwrap();
+    assert_eq!(
+        test.canister_state(canister_id).system_state.global_timer,
+        CanisterTimer::Inactive
+    );
+    let result = test.ingress(canister_id, "set", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![]));
+    assert_eq!(
+        test.canister_state(canister_id).system_state.global_timer,
+        CanisterTimer::Active(Time::from_nanos_since_unix_epoch(1000))
+    );
+}
+
+#[test]
+fn global_timer_is_deactivated_before_execution() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(TIMER_WAT).unwrap();
+    test.ingress(canister_id, "set", vec![]).unwrap();
+    test.global_timer(canister_id).unwrap();
+    assert_eq!(
+        test.canister_state(canister_id).system_state.global_timer,
+        CanisterTimer::Inactive
+    );
+}
+
+#[test]
+fn global_timer_is_deactivated_on_upgrade() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(TIMER_WAT).unwrap();
+    test.ingress(canister_id, "set", vec![]).unwrap();
+    test.upgrade_canister(canister_id, wabt::wat2wasm(TIMER_WAT).unwrap())
+        .unwrap();
+    assert_eq!(
+        test.canister_state(canister_id).system_state.global_timer,
+        CanisterTimer::Inactive
+    );
+}
+
+#[test]
+fn global_timer_is_deactivated_on_uninstall() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(TIMER_WAT).unwrap();
+    test.ingress(canister_id, "set", vec![]).unwrap();
+    test.uninstall_code(canister_id).unwrap();
+    assert_eq!(
+        test.canister_state(canister_id).system_state.global_timer,
+        CanisterTimer::Inactive
+    );
+}
+
+#[test]
+fn global_timer_doesnt_run_if_canister_is_stopped() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(TIMER_WAT).unwrap();
+    test.ingress(canister_id, "set", vec![]).unwrap();
+    test.stop_canister(canister_id);
+    test.process_stopping_canisters();
+    let err = test.global_timer(canister_id).unwrap_err();
+    assert_matches!(
+        err,
+        CanisterHeartbeatError::CanisterNotRunning {
+            status: CanisterStatusType::Stopped,
+        }
+    );
+    // The timer stays active, so it fires once the canister runs again.
+    assert_eq!(
+        test.canister_state(canister_id).system_state.global_timer,
+        CanisterTimer::Active(Time::from_nanos_since_unix_epoch(1000))
+    );
+}
@@ -102,3 +102,79 @@ fn heartbeat_doesnt_run_if_canister_is_stopping() {
         }
     );
 }
+
+// A heartbeat that runs a loop with the given number of iterations and then
+// writes to the Wasm memory.
+fn long_heartbeat_wat(iterations: u32) -> String {
+    format!(
+        r#"
+        (module
+            (func (export "canister_heartbeat")
+                (local $i i32)
+                (local.set $i (i32.const {}))
+                (loop $loop
+                    (local.set $i (i32.sub (local.get $i) (i32.const 1)))
+                    (br_if $loop (i32.ne (local.get $i) (i32.const 0)))
+                )
+                (i32.store (i32.const 10) (i32.const 10))
+            )
+            (memory (export "memory") 1)
+        )"#,
+        iterations
+    )
+}
+
+#[test]
+fn dts_pause_resume_works_in_heartbeat() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_instruction_limit(100_000_000)
+        .with_slice_instruction_limit(10_000)
+        .with_deterministic_time_slicing()
+        .with_manual_execution()
+        .build();
+    let canister_id = test.canister_from_wat(long_heartbeat_wat(10_000)).unwrap();
+    test.heartbeat(canister_id).unwrap();
+    assert!(test.canister_state(canister_id).has_task());
+    assert_eq!(NumBytes::from(0), test.state().metadata.heap_delta_estimate);
+
+    while test.canister_state(canister_id).has_task() {
+        test.execute_slice(canister_id);
+    }
+    assert_eq!(
+        NumBytes::from((PAGE_SIZE) as u64),
+        test.state().metadata.heap_delta_estimate
+    );
+}
+
+#[test]
+fn dts_abort_drops_heartbeat() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_instruction_limit(100_000_000)
+        .with_slice_instruction_limit(10_000)
+        .with_deterministic_time_slicing()
+        .with_manual_execution()
+        .build();
+    let canister_id = test.canister_from_wat(long_heartbeat_wat(10_000)).unwrap();
+    let initial_balance = test.canister_state(canister_id).system_state.balance();
+    test.heartbeat(canister_id).unwrap();
+    test.execute_slice(canister_id);
+    assert!(test.canister_state(canister_id).has_task());
+    assert!(test.canister_state(canister_id).system_state.balance() < initial_balance);
+
+    // An aborted heartbeat is not restarted and its changes are discarded.
+    // The execution cycles reserved for it are refunded.
+    test.abort_paused_executions();
+    assert!(!test.canister_state(canister_id).has_task());
+    assert_eq!(
+        test.canister_state(canister_id).system_state.balance(),
+        initial_balance
+    );
+    assert_eq!(NumBytes::from(0), test.state().metadata.heap_delta_estimate);
+    assert!(test
+        .canister_state(canister_id)
+        .system_state
+        .call_context_manager()
+        .unwrap()
+        .call_contexts()
+        .is_empty());
+}
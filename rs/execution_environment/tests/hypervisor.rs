@@ -2,7 +2,7 @@ use assert_matches::assert_matches;
 use candid::{Decode, Encode};
 use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_error_types::{ErrorCode, RejectCode};
-use ic_ic00_types::CanisterHttpResponsePayload;
+use ic_ic00_types::{CanisterHttpResponsePayload, CanisterInstallMode, EmptyBlob, InstallCodeArgs};
 use ic_interfaces::execution_environment::HypervisorError;
 use ic_nns_constants::CYCLES_MINTING_CANISTER_ID;
 use ic_registry_subnet_type::SubnetType;
@@ -380,6 +380,120 @@ fn ic0_stable_read_and_write_work() {
     assert_eq!(WasmResult::Reply(b"efghabcd".to_vec()), result);
 }
 
+#[test]
+fn ic0_stable_read_and_write_work_with_native_stable_memory() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_wasm_native_stable_memory()
+        .build();
+    let wat = r#"
+        (module
+            (import "ic0" "stable_grow" (func $stable_grow (param i32) (result i32)))
+            (import "ic0" "stable_size" (func $stable_size (result i32)))
+            (import "ic0" "stable_read"
+                (func $stable_read (param $dst i32) (param $offset i32) (param $size i32))
+            )
+            (import "ic0" "stable_write"
+                (func $stable_write (param $offset i32) (param $src i32) (param $size i32))
+            )
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32))
+            )
+            (func (export "canister_update write")
+                (drop (call $stable_grow (i32.const 2)))
+                ;; Write across the boundary of the first two Wasm pages.
+                (call $stable_write (i32.const 65532) (i32.const 0) (i32.const 8))
+                (call $msg_reply)
+            )
+            (func (export "canister_update read")
+                (if (i32.ne (call $stable_size) (i32.const 2))
+                    (then (unreachable))
+                )
+                (call $stable_read (i32.const 100) (i32.const 65532) (i32.const 8))
+                (call $msg_reply_data_append (i32.const 100) (i32.const 8))
+                (call $msg_reply)
+            )
+            (memory 1)
+            (data (i32.const 0) "abcdefgh")
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let result = test.ingress(canister_id, "write", vec![]).unwrap();
+    assert_eq!(WasmResult::Reply(vec![]), result);
+    // The second message observes the stable memory persisted by the first one.
+    let result = test.ingress(canister_id, "read", vec![]).unwrap();
+    assert_eq!(WasmResult::Reply(b"abcdefgh".to_vec()), result);
+}
+
+#[test]
+fn stable_memory_dirty_page_limit_is_enforced() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_wasm_native_stable_memory()
+        .with_stable_memory_dirty_page_limit(2)
+        .build();
+    fn wat(bytes: usize) -> String {
+        format!(
+            r#"(module
+                (import "ic0" "stable_grow" (func $stable_grow (param i32) (result i32)))
+                (import "ic0" "stable_write"
+                    (func $stable_write (param $offset i32) (param $src i32) (param $size i32))
+                )
+                (func (export "canister_update test")
+                    (drop (call $stable_grow (i32.const 1)))
+                    (call $stable_write (i32.const 0) (i32.const 0) (i32.const {}))
+                )
+                (memory 1)
+            )"#,
+            bytes
+        )
+    }
+    let canister_id = test.canister_from_wat(wat(2 * PAGE_SIZE)).unwrap();
+    let result = test.ingress(canister_id, "test", vec![]);
+    assert_empty_reply(result);
+
+    let canister_id = test.canister_from_wat(wat(2 * PAGE_SIZE + 1)).unwrap();
+    let heap_delta_estimate_before = test.state().metadata.heap_delta_estimate;
+    let err = test.ingress(canister_id, "test", vec![]).unwrap_err();
+    assert_eq!(ErrorCode::CanisterMemoryAccessLimitExceeded, err.code());
+    assert_eq!(
+        heap_delta_estimate_before,
+        test.state().metadata.heap_delta_estimate
+    );
+}
+
+#[test]
+fn stable_memory_dirty_page_limit_is_enforced_during_execution() {
+    // The write above the limit traps right away, so the endless loop after
+    // it is never reached.
+    let wat = format!(
+        r#"(module
+            (import "ic0" "stable_grow" (func $stable_grow (param i32) (result i32)))
+            (import "ic0" "stable_write"
+                (func $stable_write (param $offset i32) (param $src i32) (param $size i32))
+            )
+            (func (export "canister_update test")
+                (drop (call $stable_grow (i32.const 1)))
+                (call $stable_write (i32.const 0) (i32.const 0) (i32.const {}))
+                (loop $loop (br $loop))
+            )
+            (memory 1)
+        )"#,
+        2 * PAGE_SIZE + 1
+    );
+    for native_stable_memory in [false, true] {
+        let builder = ExecutionTestBuilder::new()
+            .with_instruction_limit(1_000_000)
+            .with_stable_memory_dirty_page_limit(2);
+        let mut test = if native_stable_memory {
+            builder.with_wasm_native_stable_memory().build()
+        } else {
+            builder.build()
+        };
+        let canister_id = test.canister_from_wat(wat.clone()).unwrap();
+        let err = test.ingress(canister_id, "test", vec![]).unwrap_err();
+        assert_eq!(ErrorCode::CanisterMemoryAccessLimitExceeded, err.code());
+    }
+}
+
 #[test]
 fn ic0_stable_read_traps_if_out_of_bounds() {
     let mut test = ExecutionTestBuilder::new().build();
@@ -2917,6 +3031,32 @@ fn account_for_size_of_memory_fill_instruction() {
     assert!(test.executed_instructions() > NumInstructions::from(1000));
 }
 
+#[test]
+fn passive_data_segment_can_be_copied_with_memory_init() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32))
+            )
+            (func (export "canister_update test")
+                (memory.init 1 (i32.const 10) (i32.const 2) (i32.const 3))
+                (call $msg_reply_data_append (i32.const 0) (i32.const 16))
+                (call $msg_reply)
+            )
+            (memory 1)
+            (data (i32.const 0) "abcd")
+            (data "xyz12")
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let result = test.ingress(canister_id, "test", vec![]).unwrap();
+    assert_eq!(
+        WasmResult::Reply(b"abcd\0\0\0\0\0\0z12\0\0\0".to_vec()),
+        result
+    );
+}
+
 // Verify that the `memory.fill` with max u32 bytes triggers the out of
 // instructions trap.
 #[test]
@@ -4006,3 +4146,220 @@ fn dts_abort_works_in_update_call() {
     let result = check_ingress_status(ingress_status).unwrap();
     assert_eq!(result, WasmResult::Reply(vec![1, 2, 3, 4, 5]));
 }
+
+#[test]
+fn dts_pause_resume_works_in_install_code() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_install_code_instruction_limit(
+            1_000_000_000 + wasm_compilation_cost(UNIVERSAL_CANISTER_WASM).get(),
+        )
+        .with_slice_instruction_limit(10_000)
+        .with_deterministic_time_slicing()
+        .with_manual_execution()
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let work = wasm().stable_grow(1).stable_fill(0, 42, 65_536).build();
+    let (ingress_id, _) = test.ingress_raw(
+        canister_id,
+        "update",
+        wasm().set_pre_upgrade(work).reply().build(),
+    );
+    test.execute_message(canister_id);
+    check_ingress_status(test.ingress_status(ingress_id)).unwrap();
+
+    let args = InstallCodeArgs::new(
+        CanisterInstallMode::Upgrade,
+        canister_id,
+        UNIVERSAL_CANISTER_WASM.to_vec(),
+        vec![],
+        None,
+        None,
+        None,
+    );
+    let ingress_id = test.dts_install_code(args);
+    assert!(test.canister_state(canister_id).has_paused_install_code());
+
+    let mut slices = 1;
+    while test.canister_state(canister_id).has_paused_install_code() {
+        test.execute_slice(canister_id);
+        slices += 1;
+    }
+    assert!(slices > 2);
+    assert!(!test.canister_state(canister_id).has_task());
+    let ingress_status = test.ingress_status(ingress_id);
+    let result = check_ingress_status(ingress_status).unwrap();
+    assert_eq!(result, WasmResult::Reply(EmptyBlob::encode()));
+}
+
+#[test]
+fn dts_abort_works_in_install_code() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_install_code_instruction_limit(
+            1_000_000_000 + wasm_compilation_cost(UNIVERSAL_CANISTER_WASM).get(),
+        )
+        .with_slice_instruction_limit(10_000)
+        .with_deterministic_time_slicing()
+        .with_manual_execution()
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let work = wasm().stable_grow(1).stable_fill(0, 42, 65_536).build();
+    let (ingress_id, _) = test.ingress_raw(
+        canister_id,
+        "update",
+        wasm().set_pre_upgrade(work).reply().build(),
+    );
+    test.execute_message(canister_id);
+    check_ingress_status(test.ingress_status(ingress_id)).unwrap();
+
+    let args = InstallCodeArgs::new(
+        CanisterInstallMode::Upgrade,
+        canister_id,
+        UNIVERSAL_CANISTER_WASM.to_vec(),
+        vec![],
+        None,
+        None,
+        None,
+    );
+    let ingress_id = test.dts_install_code(args);
+    test.execute_slice(canister_id);
+    assert!(test.canister_state(canister_id).has_paused_install_code());
+
+    // Abort before finishing the execution.
+    test.abort_paused_executions();
+    assert!(!test.canister_state(canister_id).has_paused_install_code());
+    assert!(test.canister_state(canister_id).has_aborted_install_code());
+
+    // Now execute from scratch.
+    test.execute_slice(canister_id);
+    assert!(test.canister_state(canister_id).has_paused_install_code());
+    while test.canister_state(canister_id).has_paused_install_code() {
+        test.execute_slice(canister_id);
+    }
+    assert!(!test.canister_state(canister_id).has_task());
+    let ingress_status = test.ingress_status(ingress_id);
+    let result = check_ingress_status(ingress_status).unwrap();
+    assert_eq!(result, WasmResult::Reply(EmptyBlob::encode()));
+}
+
+#[test]
+fn dts_install_code_blocks_other_messages_of_canister() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_install_code_instruction_limit(
+            1_000_000_000 + wasm_compilation_cost(UNIVERSAL_CANISTER_WASM).get(),
+        )
+        .with_slice_instruction_limit(10_000)
+        .with_deterministic_time_slicing()
+        .with_manual_execution()
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let work = wasm().stable_grow(1).stable_fill(0, 42, 65_536).build();
+    let (ingress_id, _) = test.ingress_raw(
+        canister_id,
+        "update",
+        wasm().set_pre_upgrade(work).reply().build(),
+    );
+    test.execute_message(canister_id);
+    check_ingress_status(test.ingress_status(ingress_id)).unwrap();
+
+    let args = InstallCodeArgs::new(
+        CanisterInstallMode::Upgrade,
+        canister_id,
+        UNIVERSAL_CANISTER_WASM.to_vec(),
+        vec![],
+        None,
+        None,
+        None,
+    );
+    let install_id = test.dts_install_code(args);
+    assert!(test.canister_state(canister_id).has_paused_install_code());
+
+    // The update call is queued until `install_code` finishes.
+    let (update_id, _) = test.ingress_raw(canister_id, "update", wasm().reply().build());
+    test.execute_all();
+    assert_eq!(test.ingress_status(update_id), IngressStatus::Unknown);
+
+    while test.canister_state(canister_id).has_paused_install_code() {
+        test.execute_slice(canister_id);
+    }
+    let result = check_ingress_status(test.ingress_status(install_id)).unwrap();
+    assert_eq!(result, WasmResult::Reply(EmptyBlob::encode()));
+
+    test.execute_message(canister_id);
+    let result = check_ingress_status(test.ingress_status(update_id)).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![]));
+}
+
+#[test]
+fn dts_install_code_keeps_balance_changes_made_while_paused() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_install_code_instruction_limit(
+            1_000_000_000 + wasm_compilation_cost(UNIVERSAL_CANISTER_WASM).get(),
+        )
+        .with_slice_instruction_limit(10_000)
+        .with_deterministic_time_slicing()
+        .with_manual_execution()
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let work = wasm().stable_grow(1).stable_fill(0, 42, 65_536).build();
+    let (ingress_id, _) = test.ingress_raw(
+        canister_id,
+        "update",
+        wasm().set_pre_upgrade(work).reply().build(),
+    );
+    test.execute_message(canister_id);
+    check_ingress_status(test.ingress_status(ingress_id)).unwrap();
+
+    let args = InstallCodeArgs::new(
+        CanisterInstallMode::Upgrade,
+        canister_id,
+        UNIVERSAL_CANISTER_WASM.to_vec(),
+        vec![],
+        None,
+        None,
+        None,
+    );
+    let install_id = test.dts_install_code(args);
+    assert!(test.canister_state(canister_id).has_paused_install_code());
+
+    // Cycles deposited while the upgrade is paused must not be lost.
+    let deposit = Cycles::new(1_000_000_000_000_000);
+    *test
+        .canister_state_mut(canister_id)
+        .system_state
+        .balance_mut() += deposit;
+
+    while test.canister_state(canister_id).has_paused_install_code() {
+        test.execute_slice(canister_id);
+    }
+    let result = check_ingress_status(test.ingress_status(install_id)).unwrap();
+    assert_eq!(result, WasmResult::Reply(EmptyBlob::encode()));
+    assert!(test.canister_state(canister_id).system_state.balance() > deposit);
+}
+
+#[test]
+fn uninstall_code_rejects_aborted_calls() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_instruction_limit(1_000_000)
+        .with_slice_instruction_limit(1_000)
+        .with_deterministic_time_slicing()
+        .with_manual_execution()
+        .build();
+    let canister_id = test.universal_canister().unwrap();
+    let work = wasm()
+        .push_bytes(&[1, 2, 3, 4, 5])
+        .append_and_reply()
+        .build();
+
+    // The workload above finishes in 5 slices.
+    let (ingress_id, _) = test.ingress_raw(canister_id, "update", work);
+    test.execute_slice(canister_id);
+    assert!(test.canister_state(canister_id).has_task());
+
+    // Uninstalling aborts the paused execution and rejects the call instead
+    // of executing it again.
+    test.uninstall_code(canister_id).unwrap();
+    assert!(!test.canister_state(canister_id).has_task());
+    let err = check_ingress_status(test.ingress_status(ingress_id)).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterRejectedMessage);
+    assert_eq!(err.description(), "Canister has been uninstalled.");
+}
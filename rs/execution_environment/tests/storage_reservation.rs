@@ -0,0 +1,152 @@
+use ic_error_types::ErrorCode;
+use ic_ic00_types::{
+    CanisterSettingsArgs, CanisterStatusResultV2, Method, Payload, UpdateSettingsArgs,
+};
+use ic_test_utilities::execution_environment::{get_reply, ExecutionTestBuilder};
+use ic_types::{ingress::WasmResult, Cycles, NumBytes};
+
+const GIB: u64 = 1 << 30;
+const WASM_PAGE_SIZE: u64 = 64 * 1024;
+
+// A canister that grows its Wasm memory by 10 pages in `grow`.
+const GROW_WAT: &str = r#"
+    (module
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (func (export "canister_update grow")
+            (if (i32.eq (memory.grow (i32.const 10)) (i32.const -1))
+                (then unreachable))
+            (call $msg_reply)
+        )
+        (memory (export "memory") 1)
+    )"#;
+
+// Builds a test where 250 GiB of the 350 GiB subnet memory capacity are in use
+// and cycles are reserved above 200 GiB.
+fn busy_subnet_test() -> ExecutionTestBuilder {
+    ExecutionTestBuilder::new()
+        .with_subnet_total_memory(100 * GIB as i64)
+        .with_subnet_memory_threshold(200 * GIB)
+}
+
+#[test]
+fn memory_grow_does_not_reserve_cycles_below_subnet_memory_threshold() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(GROW_WAT).unwrap();
+    let result = test.ingress(canister_id, "grow", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![]));
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .reserved_balance(),
+        Cycles::zero()
+    );
+}
+
+#[test]
+fn memory_grow_reserves_cycles_above_subnet_memory_threshold() {
+    let mut test = busy_subnet_test().build();
+    let canister_id = test.canister_from_wat(GROW_WAT).unwrap();
+    let balance_before = test.canister_state(canister_id).system_state.balance();
+
+    let result = test.ingress(canister_id, "grow", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![]));
+
+    let system_state = &test.canister_state(canister_id).system_state;
+    let reserved = system_state.reserved_balance();
+    assert!(reserved > Cycles::zero());
+    assert!(balance_before - system_state.balance() >= reserved);
+    assert_eq!(
+        test.canister_state(canister_id).wasm_memory_usage(),
+        NumBytes::from(11 * WASM_PAGE_SIZE)
+    );
+}
+
+#[test]
+fn memory_grow_fails_above_reserved_cycles_limit() {
+    let mut test = busy_subnet_test().build();
+    let canister_id = test.canister_from_wat(GROW_WAT).unwrap();
+    test.update_reserved_cycles_limit(canister_id, Cycles::new(1))
+        .unwrap();
+
+    let err = test.ingress(canister_id, "grow", vec![]).unwrap_err();
+    assert_eq!(
+        err.code(),
+        ErrorCode::ReservedCyclesLimitExceededInMemoryGrow
+    );
+
+    let canister = test.canister_state(canister_id);
+    assert_eq!(canister.system_state.reserved_balance(), Cycles::zero());
+    assert_eq!(canister.wasm_memory_usage(), NumBytes::from(WASM_PAGE_SIZE));
+}
+
+#[test]
+fn reserved_cycles_limit_cannot_be_below_reserved_balance() {
+    let mut test = busy_subnet_test().build();
+    let canister_id = test.canister_from_wat(GROW_WAT).unwrap();
+    test.ingress(canister_id, "grow", vec![]).unwrap();
+    let reserved = test
+        .canister_state(canister_id)
+        .system_state
+        .reserved_balance();
+
+    let err = test
+        .update_reserved_cycles_limit(canister_id, reserved - Cycles::new(1))
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .reserved_balance_limit(),
+        None
+    );
+
+    test.update_reserved_cycles_limit(canister_id, reserved)
+        .unwrap();
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .reserved_balance_limit(),
+        Some(reserved)
+    );
+}
+
+#[test]
+fn increasing_memory_allocation_reserves_cycles() {
+    // Unlike executions, `update_settings` takes the subnet memory usage from
+    // the replicated state, which is empty here.
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_memory_threshold(0)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let payload = UpdateSettingsArgs {
+        canister_id: canister_id.into(),
+        settings: CanisterSettingsArgs {
+            memory_allocation: Some(candid::Nat::from(GIB)),
+            ..Default::default()
+        },
+    }
+    .encode();
+    test.subnet_message(Method::UpdateSettings, payload)
+        .unwrap();
+    assert!(
+        test.canister_state(canister_id)
+            .system_state
+            .reserved_balance()
+            > Cycles::zero()
+    );
+}
+
+#[test]
+fn canister_status_shows_reserved_cycles() {
+    let mut test = busy_subnet_test().build();
+    let canister_id = test.canister_from_wat(GROW_WAT).unwrap();
+    test.ingress(canister_id, "grow", vec![]).unwrap();
+    let reserved = test
+        .canister_state(canister_id)
+        .system_state
+        .reserved_balance();
+
+    let result = test.canister_status(canister_id);
+    let status = CanisterStatusResultV2::decode(&get_reply(result)).unwrap();
+    assert_eq!(status.reserved_cycles(), reserved.get());
+}
@@ -0,0 +1,242 @@
+use ic_error_types::ErrorCode;
+use ic_replicated_state::OnLowWasmMemoryHookStatus;
+use ic_test_utilities::execution_environment::ExecutionTestBuilder;
+use ic_types::{ingress::WasmResult, Cycles, NumBytes};
+
+const WASM_PAGE_SIZE: u64 = 64 * 1024;
+
+// A canister that grows its Wasm memory by 10 pages in `grow` and records the
+// number of `canister_on_low_wasm_memory` invocations in the first byte of its
+// memory.
+const GROW_WAT: &str = r#"
+    (module
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (func (export "canister_update grow")
+            (if (i32.eq (memory.grow (i32.const 10)) (i32.const -1))
+                (then unreachable))
+            (call $msg_reply)
+        )
+        (func (export "canister_on_low_wasm_memory")
+            (i32.store8 (i32.const 0)
+                (i32.add (i32.load8_u (i32.const 0)) (i32.const 1)))
+        )
+        (memory (export "memory") 1)
+    )"#;
+
+#[test]
+fn memory_grow_succeeds_below_wasm_memory_limit() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(GROW_WAT).unwrap();
+    test.update_wasm_memory_limit(
+        canister_id,
+        NumBytes::from(11 * WASM_PAGE_SIZE),
+        NumBytes::from(0),
+    )
+    .unwrap();
+    let result = test.ingress(canister_id, "grow", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![]));
+    assert_eq!(
+        test.canister_state(canister_id).wasm_memory_usage(),
+        NumBytes::from(11 * WASM_PAGE_SIZE)
+    );
+}
+
+#[test]
+fn memory_grow_fails_above_wasm_memory_limit() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(GROW_WAT).unwrap();
+    test.update_wasm_memory_limit(
+        canister_id,
+        NumBytes::from(10 * WASM_PAGE_SIZE),
+        NumBytes::from(0),
+    )
+    .unwrap();
+    let err = test.ingress(canister_id, "grow", vec![]).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterWasmMemoryLimitExceeded);
+    assert_eq!(
+        test.canister_state(canister_id).wasm_memory_usage(),
+        NumBytes::from(WASM_PAGE_SIZE)
+    );
+}
+
+#[test]
+fn zero_wasm_memory_limit_means_unlimited() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(GROW_WAT).unwrap();
+    test.update_wasm_memory_limit(canister_id, NumBytes::from(0), NumBytes::from(0))
+        .unwrap();
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .wasm_memory_limit,
+        None
+    );
+    let result = test.ingress(canister_id, "grow", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![]));
+}
+
+#[test]
+fn wasm_memory_limit_cannot_be_below_wasm_memory_usage() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(GROW_WAT).unwrap();
+    test.ingress(canister_id, "grow", vec![]).unwrap();
+    let err = test
+        .update_wasm_memory_limit(
+            canister_id,
+            NumBytes::from(10 * WASM_PAGE_SIZE),
+            NumBytes::from(0),
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .wasm_memory_limit,
+        None
+    );
+}
+
+// A canister that declares an initial Wasm memory of 10 pages.
+const LARGE_MEMORY_WAT: &str = r#"
+    (module
+        (memory (export "memory") 10)
+    )"#;
+
+#[test]
+fn install_fails_if_initial_memory_exceeds_wasm_memory_limit() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    test.update_wasm_memory_limit(
+        canister_id,
+        NumBytes::from(5 * WASM_PAGE_SIZE),
+        NumBytes::from(0),
+    )
+    .unwrap();
+    let wasm = wabt::wat2wasm(LARGE_MEMORY_WAT).unwrap();
+    let err = test.install_canister(canister_id, wasm).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterWasmMemoryLimitExceeded);
+    assert!(test.canister_state(canister_id).execution_state.is_none());
+}
+
+#[test]
+fn upgrade_fails_if_initial_memory_exceeds_wasm_memory_limit() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(GROW_WAT).unwrap();
+    test.update_wasm_memory_limit(
+        canister_id,
+        NumBytes::from(5 * WASM_PAGE_SIZE),
+        NumBytes::from(0),
+    )
+    .unwrap();
+    let wasm = wabt::wat2wasm(LARGE_MEMORY_WAT).unwrap();
+    let err = test.upgrade_canister(canister_id, wasm).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterWasmMemoryLimitExceeded);
+    assert_eq!(
+        test.canister_state(canister_id).wasm_memory_usage(),
+        NumBytes::from(WASM_PAGE_SIZE)
+    );
+}
+
+#[test]
+fn on_low_wasm_memory_hook_becomes_ready_below_threshold() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(GROW_WAT).unwrap();
+    test.update_wasm_memory_limit(
+        canister_id,
+        NumBytes::from(20 * WASM_PAGE_SIZE),
+        NumBytes::from(10 * WASM_PAGE_SIZE),
+    )
+    .unwrap();
+
+    // 19 pages are left below the limit.
+    test.canister_state_mut(canister_id)
+        .update_on_low_wasm_memory_hook_status();
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .on_low_wasm_memory_hook_status,
+        OnLowWasmMemoryHookStatus::ConditionNotSatisfied
+    );
+
+    // 9 pages are left below the limit.
+    test.ingress(canister_id, "grow", vec![]).unwrap();
+    test.canister_state_mut(canister_id)
+        .update_on_low_wasm_memory_hook_status();
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .on_low_wasm_memory_hook_status,
+        OnLowWasmMemoryHookStatus::Ready
+    );
+}
+
+#[test]
+fn on_low_wasm_memory_hook_is_marked_as_executed() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(GROW_WAT).unwrap();
+    test.update_wasm_memory_limit(
+        canister_id,
+        NumBytes::from(20 * WASM_PAGE_SIZE),
+        NumBytes::from(10 * WASM_PAGE_SIZE),
+    )
+    .unwrap();
+    test.ingress(canister_id, "grow", vec![]).unwrap();
+    test.canister_state_mut(canister_id)
+        .update_on_low_wasm_memory_hook_status();
+
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .on_low_wasm_memory_hook_status,
+        OnLowWasmMemoryHookStatus::Ready
+    );
+
+    test.on_low_wasm_memory(canister_id).unwrap();
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .on_low_wasm_memory_hook_status,
+        OnLowWasmMemoryHookStatus::Executed
+    );
+
+    // The hook stays executed while the condition holds.
+    test.canister_state_mut(canister_id)
+        .update_on_low_wasm_memory_hook_status();
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .on_low_wasm_memory_hook_status,
+        OnLowWasmMemoryHookStatus::Executed
+    );
+
+    // Raising the limit resets the hook.
+    test.update_wasm_memory_limit(
+        canister_id,
+        NumBytes::from(40 * WASM_PAGE_SIZE),
+        NumBytes::from(10 * WASM_PAGE_SIZE),
+    )
+    .unwrap();
+    test.canister_state_mut(canister_id)
+        .update_on_low_wasm_memory_hook_status();
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .on_low_wasm_memory_hook_status,
+        OnLowWasmMemoryHookStatus::ConditionNotSatisfied
+    );
+}
+
+#[test]
+fn on_low_wasm_memory_hook_is_not_executed_if_not_ready() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(GROW_WAT).unwrap();
+    let balance = test.canister_state(canister_id).system_state.balance();
+    test.on_low_wasm_memory(canister_id).unwrap();
+    let canister = test.canister_state(canister_id);
+    assert_eq!(
+        canister.system_state.on_low_wasm_memory_hook_status,
+        OnLowWasmMemoryHookStatus::ConditionNotSatisfied
+    );
+    // The hook is skipped before any cycles are charged for its execution.
+    assert_eq!(canister.system_state.balance(), balance);
+}
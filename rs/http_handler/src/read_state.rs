@@ -227,6 +227,8 @@ async fn verify_paths(
             [b"canister", _canister_id, b"controller"] => {}
             [b"canister", _canister_id, b"controllers"] => {}
             [b"canister", _canister_id, b"module_hash"] => {}
+            [b"canister", _canister_id, b"history"] => {}
+            [b"canister", _canister_id, b"history", _change_index] => {}
             [b"canister", canister_id, b"metadata", name] => {
                 let name = String::from_utf8(Vec::from(*name)).map_err(|err| HttpError {
                     status: StatusCode::BAD_REQUEST,
@@ -359,7 +361,9 @@ mod test {
     use ic_crypto_tree_hash::{Digest, Label, MixedHashTree, Path};
     use ic_interfaces_state_manager::Labeled;
     use ic_registry_subnet_type::SubnetType;
-    use ic_replicated_state::{BitcoinState, CanisterQueues, ReplicatedState, SystemMetadata};
+    use ic_replicated_state::{
+        BitcoinState, CanisterQueues, CanisterSnapshots, ReplicatedState, SystemMetadata,
+    };
     use ic_test_utilities::{
         mock_time,
         state::insert_dummy_canister,
@@ -506,6 +510,7 @@ mod test {
                         CanisterQueues::default(),
                         Vec::new(),
                         BitcoinState::default(),
+                        CanisterSnapshots::default(),
                         std::path::PathBuf::new(),
                     )),
                 )
@@ -29,7 +29,9 @@ use ic_registry_client::client::RegistryClientImpl;
 use ic_registry_keys::make_subnet_record_key;
 use ic_registry_proto_data_provider::ProtoRegistryDataProvider;
 use ic_registry_subnet_type::SubnetType;
-use ic_replicated_state::{BitcoinState, CanisterQueues, ReplicatedState, SystemMetadata};
+use ic_replicated_state::{
+    BitcoinState, CanisterQueues, CanisterSnapshots, ReplicatedState, SystemMetadata,
+};
 use ic_test_utilities::{
     consensus::MockConsensusCache,
     crypto::temp_crypto_component_with_fake_registry,
@@ -189,6 +191,7 @@ where
                         CanisterQueues::default(),
                         Vec::new(),
                         BitcoinState::default(),
+                        CanisterSnapshots::default(),
                         std::path::PathBuf::new(),
                     )),
                 )
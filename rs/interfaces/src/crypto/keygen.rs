@@ -1,5 +1,5 @@
 use ic_protobuf::crypto::v1::NodePublicKeys;
-use ic_protobuf::registry::crypto::v1::PublicKey as PublicKeyProto;
+use ic_protobuf::registry::crypto::v1::{PublicKey as PublicKeyProto, X509PublicKeyCert};
 use ic_types::crypto::CryptoResult;
 use ic_types::RegistryVersion;
 
@@ -16,6 +16,12 @@ pub trait KeyManager {
     /// available locally in the public key store, then
     /// `Ok(PublicKeyRegistrationStatus::IDkgDealingEncPubkeyNeedsRegistration)`
     /// is returned.
+    ///
+    /// If all keys are properly set up but the node signing key or the TLS
+    /// key was rotated and the new public key or certificate in the public key
+    /// store still needs to be registered, then
+    /// `Ok(PublicKeyRegistrationStatus::RotatedKeysNeedRegistration)` is
+    /// returned.
     fn check_keys_with_registry(
         &self,
         registry_version: RegistryVersion,
@@ -23,12 +29,41 @@ pub trait KeyManager {
 
     /// Returns node public keys that were read when this crypto component was
     /// created. Node public keys stay the same throughout the lifetime of
-    /// the component.
+    /// the component, unless a key is rotated with this component.
     fn node_public_keys(&self) -> NodePublicKeys;
+
+    /// Rotates the node signing key.
+    ///
+    /// A new node signing key pair is generated and its public key replaces
+    /// the node signing public key in the public key store. The ID of the node
+    /// does not change. The previous secret key is kept, so that the node
+    /// keeps signing with the previous key pair until the new public key is
+    /// registered: signing always uses the node signing key that is in the
+    /// registry at the given registry version.
+    ///
+    /// Returns the new node signing public key, which must then be registered.
+    fn rotate_node_signing_key(&self) -> CryptoResult<PublicKeyProto>;
+
+    /// Rotates the TLS key.
+    ///
+    /// New TLS key material is generated and the new certificate replaces the
+    /// TLS certificate in the public key store. The previous secret key is
+    /// kept, so that TLS handshakes keep using the previous certificate until
+    /// the new certificate is registered: handshakes always use the
+    /// certificate that is in the registry at the given registry version.
+    ///
+    /// Returns the new TLS certificate, which must then be registered.
+    fn rotate_tls_key(&self) -> CryptoResult<X509PublicKeyCert>;
 }
 
 #[derive(Clone, Debug)]
 pub enum PublicKeyRegistrationStatus {
     AllKeysRegistered,
     IDkgDealingEncPubkeyNeedsRegistration(PublicKeyProto),
+    /// The node signing public key and/or the TLS certificate in the public
+    /// key store were rotated and differ from the ones in the registry.
+    RotatedKeysNeedRegistration {
+        node_signing_pk: Option<PublicKeyProto>,
+        tls_certificate: Option<X509PublicKeyCert>,
+    },
 }
@@ -38,6 +38,53 @@ pub struct InstanceStats {
     pub dirty_pages: usize,
 }
 
+/// Instructions executed by a single message attributed to the Wasm call
+/// stacks that executed them. It is collected only if instruction profiling
+/// is enabled.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct InstructionProfile {
+    /// Maps a call stack given as Wasm function indices from the outermost to
+    /// the innermost function to the number of instructions executed by the
+    /// innermost function itself.
+    pub stacks: BTreeMap<Vec<u32>, u64>,
+}
+
+impl InstructionProfile {
+    /// Attributes the given number of instructions to the given call stack.
+    pub fn add(&mut self, stack: &[u32], instructions: u64) {
+        if instructions > 0 {
+            *self.stacks.entry(stack.to_vec()).or_insert(0) += instructions;
+        }
+    }
+
+    /// Returns the total number of instructions in the profile.
+    pub fn total_instructions(&self) -> u64 {
+        self.stacks.values().sum()
+    }
+
+    /// Returns the profile in the folded-stack format understood by
+    /// flamegraph tools: one line per call stack with `;`-separated frames
+    /// followed by the number of instructions. The given `root` frame is
+    /// prepended to every stack. Functions missing in `function_names` are
+    /// shown as `func[<index>]`.
+    pub fn to_folded(&self, root: &str, function_names: &BTreeMap<u32, String>) -> String {
+        let mut folded = String::new();
+        for (stack, instructions) in self.stacks.iter() {
+            folded.push_str(root);
+            for func_index in stack {
+                folded.push(';');
+                match function_names.get(func_index) {
+                    // The semicolon separates frames, so it cannot appear in names.
+                    Some(name) => folded.push_str(&name.replace(';', ":")),
+                    None => folded.push_str(&format!("func[{}]", func_index)),
+                }
+            }
+            folded.push_str(&format!(" {}\n", instructions));
+        }
+        folded
+    }
+}
+
 /// Errors that can be returned when fetching the available memory on a subnet.
 #[derive(Debug)]
 pub enum SubnetAvailableMemoryError {
@@ -491,6 +538,11 @@ pub trait SystemApi {
     /// Outputs the specified bytes on the heap as a string on STDOUT.
     fn ic0_debug_print(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
 
+    /// Appends the specified bytes on the heap to the canister's log. This is
+    /// done for the messages of both `ic0.debug_print` and `ic0.trap`, and
+    /// regardless of whether debug prints are rate limited.
+    fn save_log_message(&mut self, src: u32, size: u32, heap: &[u8]);
+
     /// Traps, with a possibly helpful message
     fn ic0_trap(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
 
@@ -547,6 +599,16 @@ pub trait SystemApi {
     /// See https://sdk.dfinity.org/docs/interface-spec/index.html#system-api-call
     fn ic0_call_on_cleanup(&mut self, fun: u32, env: u32) -> HypervisorResult<()>;
 
+    /// Makes the call under construction a best-effort call: if no response
+    /// was received `timeout_seconds` (capped at `MAX_CALL_TIMEOUT_SECONDS`)
+    /// after `ic0.call_perform`, the call is rejected with `SYS_UNKNOWN`. Can
+    /// be called at most once between `ic0.call_new` and `ic0.call_perform`.
+    ///
+    /// Best-effort calls are currently limited to canisters on the same
+    /// subnet: `ic0.call_perform` fails for calls to other subnets or to the
+    /// management canister.
+    fn ic0_call_with_best_effort_response(&mut self, timeout_seconds: u32) -> HypervisorResult<()>;
+
     /// (deprecated) Please use `ic0_call_cycles_add128` instead, as this API
     /// can only add a 64-bit value.
     ///
@@ -824,6 +886,20 @@ pub trait SystemApi {
     /// running, `2` indicates stopping, and `3` indicates stopped.
     fn ic0_canister_status(&self) -> HypervisorResult<u32>;
 
+    /// Returns `1` if the principal whose bytes are at `src` with length
+    /// `size` in the heap is a controller of the canister and `0` otherwise.
+    /// Traps if the bytes are not a valid principal.
+    fn ic0_is_controller(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<u32>;
+
+    /// Returns the version of the canister, which is incremented every time
+    /// the canister's code or settings change.
+    fn ic0_canister_version(&self) -> HypervisorResult<u64>;
+
+    /// Returns `1` if the canister is being run in replicated execution
+    /// (e.g. an update call or a heartbeat) and `0` otherwise (e.g. a query
+    /// call answered by a single replica).
+    fn ic0_in_replicated_execution(&self) -> HypervisorResult<i32>;
+
     /// Mints the `amount` cycles
     /// Adds cycles to the canister's balance.
     ///
@@ -835,6 +911,13 @@ pub trait SystemApi {
     ///
     /// Returns the amount of cycles added to the canister's balance.
     fn ic0_mint_cycles(&mut self, amount: u64) -> HypervisorResult<u64>;
+
+    /// Sets the canister's global timer to the given time and returns the
+    /// previous value of the timer.
+    ///
+    /// The time is given in nanoseconds since the Unix epoch. The value `0`
+    /// deactivates the timer and is also returned if the timer was not set.
+    fn ic0_global_timer_set(&mut self, time: Time) -> HypervisorResult<Time>;
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -916,6 +999,9 @@ pub struct WasmExecutionOutput {
     pub allocated_bytes: NumBytes,
     pub allocated_message_bytes: NumBytes,
     pub instance_stats: InstanceStats,
+    /// The instruction profile of the execution if instruction profiling is
+    /// enabled.
+    pub instruction_profile: Option<InstructionProfile>,
 }
 
 impl fmt::Display for WasmExecutionOutput {
@@ -953,6 +1039,24 @@ mod tests {
         assert_eq!(available.max_available_message_memory(), 5);
     }
 
+    #[test]
+    fn test_instruction_profile_to_folded() {
+        let mut profile = InstructionProfile::default();
+        profile.add(&[3], 10);
+        profile.add(&[3, 5], 20);
+        profile.add(&[3, 5], 5);
+        profile.add(&[3, 7], 0);
+        assert_eq!(profile.total_instructions(), 35);
+
+        let function_names = vec![(3, "main".to_string()), (5, "a;b".to_string())]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            profile.to_folded("root", &function_names),
+            "root;main 10\nroot;main;a:b 25\n"
+        );
+    }
+
     #[test]
     fn test_subnet_available_memory() {
         let mut available: SubnetAvailableMemory =
@@ -1,6 +1,6 @@
 use ic_base_types::{CanisterIdError, PrincipalIdBlobParseError};
 use ic_error_types::UserError;
-use ic_types::{methods::WasmMethod, CanisterId, Cycles};
+use ic_types::{methods::WasmMethod, CanisterId, Cycles, NumBytes};
 use ic_wasm_types::{WasmEngineError, WasmInstrumentationError, WasmValidationError};
 use serde::{Deserialize, Serialize};
 
@@ -121,6 +121,30 @@ pub enum HypervisorError {
     /// The canister is close to running out of Wasm memory and
     /// attempted to allocate reserved Wasm pages.
     WasmReservedPages,
+    /// The message modified more stable memory pages than allowed for a
+    /// single message execution. The payload contains a description of the
+    /// exceeded limit.
+    MemoryAccessLimitExceeded(String),
+    /// An attempt was made to grow the canister's Wasm memory above its
+    /// `wasm_memory_limit` setting.
+    WasmMemoryLimitExceeded {
+        bytes: NumBytes,
+        limit: NumBytes,
+    },
+    /// Growing the memory by `bytes` requires reserving more cycles than the
+    /// canister has, see the `reserved_cycles_limit` setting.
+    InsufficientCyclesInMemoryGrow {
+        bytes: NumBytes,
+        available: Cycles,
+        required: Cycles,
+    },
+    /// Growing the memory by `bytes` would bring the reserved cycles above the
+    /// canister's `reserved_cycles_limit` setting.
+    ReservedCyclesLimitExceededInMemoryGrow {
+        bytes: NumBytes,
+        requested: Cycles,
+        limit: Cycles,
+    },
     /// The execution was aborted by deterministic time slicing. This error is
     /// not observable by the user and should be processed before leaving Wasm
     /// execution.
@@ -178,6 +202,7 @@ impl HypervisorError {
                 let kind = match wasm_method {
                     WasmMethod::Update(_) => "update",
                     WasmMethod::Query(_) => "query",
+                    WasmMethod::CompositeQuery(_) => "composite query",
                     WasmMethod::System(_) => "system",
                 };
 
@@ -238,6 +263,42 @@ impl HypervisorError {
                     canister_id
                 ),
             ),
+            Self::MemoryAccessLimitExceeded(description) => UserError::new(
+                E::CanisterMemoryAccessLimitExceeded,
+                format!(
+                    "Canister {} exceeded memory access limits: {}",
+                    canister_id, description
+                ),
+            ),
+            Self::WasmMemoryLimitExceeded { bytes, limit } => UserError::new(
+                E::CanisterWasmMemoryLimitExceeded,
+                format!(
+                    "Canister {} exceeded its Wasm memory limit of {} bytes: its Wasm memory would grow to {} bytes",
+                    canister_id, limit, bytes
+                ),
+            ),
+            Self::InsufficientCyclesInMemoryGrow {
+                bytes,
+                available,
+                required,
+            } => UserError::new(
+                E::InsufficientCyclesInMemoryGrow,
+                format!(
+                    "Canister {} cannot grow its memory by {} bytes due to insufficient cycles. At least {} additional cycles are required, but only {} are available.",
+                    canister_id, bytes, required, available
+                ),
+            ),
+            Self::ReservedCyclesLimitExceededInMemoryGrow {
+                bytes,
+                requested,
+                limit,
+            } => UserError::new(
+                E::ReservedCyclesLimitExceededInMemoryGrow,
+                format!(
+                    "Canister {} cannot grow its memory by {} bytes due to its reserved cycles limit. The current limit ({}) would be exceeded by {} cycles.",
+                    canister_id, bytes, limit, requested - limit
+                ),
+            ),
             Self::CanisterStopped => UserError::new(
                 E::CanisterStopped,
                 format!("Canister {} is stopped", canister_id,),
@@ -314,6 +375,14 @@ impl HypervisorError {
             HypervisorError::Cleanup { .. } => "Cleanup",
             HypervisorError::WasmEngineError(_) => "WasmEngineError",
             HypervisorError::WasmReservedPages => "WasmReservedPages",
+            HypervisorError::MemoryAccessLimitExceeded(_) => "MemoryAccessLimitExceeded",
+            HypervisorError::WasmMemoryLimitExceeded { .. } => "WasmMemoryLimitExceeded",
+            HypervisorError::InsufficientCyclesInMemoryGrow { .. } => {
+                "InsufficientCyclesInMemoryGrow"
+            }
+            HypervisorError::ReservedCyclesLimitExceededInMemoryGrow { .. } => {
+                "ReservedCyclesLimitExceededInMemoryGrow"
+            }
             HypervisorError::Aborted => "Aborted",
         }
     }
@@ -347,7 +416,11 @@ impl HypervisorError {
             | HypervisorError::InvalidCanisterId(_)
             | HypervisorError::MessageRejected
             | HypervisorError::InsufficientCyclesBalance(_)
-            | HypervisorError::WasmReservedPages => false,
+            | HypervisorError::WasmReservedPages
+            | HypervisorError::MemoryAccessLimitExceeded(_)
+            | HypervisorError::WasmMemoryLimitExceeded { .. }
+            | HypervisorError::InsufficientCyclesInMemoryGrow { .. }
+            | HypervisorError::ReservedCyclesLimitExceededInMemoryGrow { .. } => false,
         }
     }
 }
@@ -199,6 +199,7 @@ pub struct SigsegvMemoryTracker {
     dirty_page_tracking: DirtyPageTracking,
     page_map: PageMap,
     use_new_signal_handler: bool,
+    max_pages_to_map: usize,
     #[cfg(feature = "sigsegv_handler_checksum")]
     checksum: RefCell<checksum::SigsegChecksum>,
 }
@@ -234,6 +235,7 @@ impl SigsegvMemoryTracker {
             dirty_page_tracking,
             page_map,
             use_new_signal_handler,
+            max_pages_to_map: MAX_PAGES_TO_MAP,
             #[cfg(feature = "sigsegv_handler_checksum")]
             checksum: RefCell::new(checksum::SigsegChecksum::default()),
         };
@@ -273,6 +275,14 @@ impl SigsegvMemoryTracker {
         Ok(tracker)
     }
 
+    /// Disables prefetching, so that every page faults on its first read and
+    /// on its first write access. Then `num_dirty_pages()` is the exact number
+    /// of pages written so far, independent of how the memory is backed.
+    pub fn without_prefetching(mut self) -> Self {
+        self.max_pages_to_map = 1;
+        self
+    }
+
     pub fn handle_sigsegv(
         &self,
         access_kind: Option<AccessKind>,
@@ -317,6 +327,13 @@ impl SigsegvMemoryTracker {
         self.accessed_bitmap.borrow().marked_count()
     }
 
+    /// Returns the number of pages that are known to be dirty. Speculatively
+    /// dirty pages are not included, because they may turn out unchanged.
+    /// See `without_prefetching()`.
+    pub fn num_dirty_pages(&self) -> usize {
+        self.dirty_pages.borrow().len()
+    }
+
     fn page_index_from(&self, addr: *mut libc::c_void) -> PageIndex {
         let page_start_mask = !(PAGE_SIZE as usize - 1);
         let page_start_addr = (addr as usize) & page_start_mask;
@@ -521,7 +538,7 @@ pub fn sigsegv_fault_handler_new(
         (_, DirtyPageTracking::Ignore) => {
             // We don't care about dirty pages here, so we can set up the page mapping for
             // for multiple pages as read/write right away.
-            let prefetch_range = range_from_count(faulting_page, MAX_PAGES_TO_MAP);
+            let prefetch_range = range_from_count(faulting_page, tracker.max_pages_to_map);
             let max_prefetch_range = accessed_bitmap.restrict_range_to_unmarked(prefetch_range);
             let min_prefetch_range =
                 accessed_bitmap.restrict_range_to_predicted(max_prefetch_range.clone());
@@ -538,7 +555,7 @@ pub fn sigsegv_fault_handler_new(
         (AccessKind::Read, DirtyPageTracking::Track) => {
             // Set up the page mapping as read-only in order to get a signal on subsequent
             // write accesses to track dirty pages. We can do this for multiple pages.
-            let prefetch_range = range_from_count(faulting_page, MAX_PAGES_TO_MAP);
+            let prefetch_range = range_from_count(faulting_page, tracker.max_pages_to_map);
             let max_prefetch_range = accessed_bitmap.restrict_range_to_unmarked(prefetch_range);
             let min_prefetch_range =
                 accessed_bitmap.restrict_range_to_predicted(max_prefetch_range.clone());
@@ -555,7 +572,7 @@ pub fn sigsegv_fault_handler_new(
         (AccessKind::Write, DirtyPageTracking::Track) => {
             let mut dirty_bitmap = tracker.dirty_bitmap.borrow_mut();
             assert!(!dirty_bitmap.is_marked(faulting_page));
-            let prefetch_range = range_from_count(faulting_page, MAX_PAGES_TO_MAP);
+            let prefetch_range = range_from_count(faulting_page, tracker.max_pages_to_map);
             // Ensure that we don't overwrite an already dirty page.
             let prefetch_range = dirty_bitmap.restrict_range_to_unmarked(prefetch_range);
             if accessed_bitmap.is_marked(faulting_page) {
@@ -196,6 +196,7 @@ impl StreamBuilderImpl {
                         code: reject_code,
                         message: reject_message,
                     }),
+                    deadline: req.deadline,
                 }
                 .into(),
                 // Arbitrary large amounts, pushing a response always returns memory.
@@ -27,6 +27,7 @@ use ic_types::{
         CallbackId, Payload, RejectContext, Request, RequestOrResponse, Response,
         MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64,
     },
+    time::NO_DEADLINE,
     xnet::{StreamIndex, StreamIndexedQueue},
     CanisterId, Cycles, SubnetId, Time,
 };
@@ -116,6 +117,7 @@ fn reject_local_request() {
                         code: RejectCode::SysFatal,
                         message: reject_message.to_string(),
                     }),
+                    deadline: msg.deadline,
                 }
                 .into(),
                 (u64::MAX / 2).into(),
@@ -181,6 +183,7 @@ fn reject_local_request_for_subnet() {
                         code: RejectCode::SysFatal,
                         message: reject_message.to_string(),
                     }),
+                    deadline: msg.deadline,
                 }
                 .into(),
                 (u64::MAX / 2).into(),
@@ -707,6 +710,7 @@ fn build_streams_with_oversized_payloads() {
             payment: Cycles::new(1),
             method_name: method_name.clone(),
             method_payload: oversized_request_payload.clone(),
+            deadline: NO_DEADLINE,
         };
         assert!(local_request.payload_size_bytes() > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES);
 
@@ -718,6 +722,7 @@ fn build_streams_with_oversized_payloads() {
             payment: Cycles::new(2),
             method_name,
             method_payload: oversized_request_payload,
+            deadline: NO_DEADLINE,
         };
         assert!(remote_request.payload_size_bytes() > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES);
         let remote_request_reject = Response {
@@ -732,6 +737,7 @@ fn build_streams_with_oversized_payloads() {
                     local_canister
                 ),
             )),
+            deadline: NO_DEADLINE,
         };
 
         // Oversized response: will be replaced with a reject response.
@@ -741,6 +747,7 @@ fn build_streams_with_oversized_payloads() {
             originator_reply_callback: CallbackId::from(3),
             refund: Cycles::new(3),
             response_payload: Payload::Data(oversized_response_payload),
+            deadline: NO_DEADLINE,
         };
         assert!(data_response.payload_size_bytes() > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES);
         let data_response_reject = Response {
@@ -755,6 +762,7 @@ fn build_streams_with_oversized_payloads() {
                     local_canister
                 ),
             )),
+            deadline: NO_DEADLINE,
         };
 
         // Oversized reject response: will be replaced with a reject response.
@@ -767,6 +775,7 @@ fn build_streams_with_oversized_payloads() {
                 RejectCode::SysTransient,
                 oversized_error_message,
             )),
+            deadline: NO_DEADLINE,
         };
         assert!(reject_response.payload_size_bytes() > MAX_INTER_CANISTER_PAYLOAD_IN_BYTES);
         let reject_response_reject = Response {
@@ -778,6 +787,7 @@ fn build_streams_with_oversized_payloads() {
                 RejectCode::SysTransient,
                 "x".repeat(5 * 1024) + "..." + &"x".repeat(2 * 1024),
             )),
+            deadline: NO_DEADLINE,
         };
 
         let (stream_builder, mut provided_state, metrics_registry) = new_fixture(&log);
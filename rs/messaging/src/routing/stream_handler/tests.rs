@@ -1252,6 +1252,7 @@ fn generate_reject_response_queue_full() {
                 RejectCode::SysTransient,
                 err.to_string(),
             )),
+            deadline: msg.deadline,
         }
         .into(),
     );
@@ -1291,6 +1292,7 @@ fn generate_reject_response_canister_not_found() {
                 RejectCode::DestinationInvalid,
                 err.to_string(),
             )),
+            deadline: msg.deadline,
         }
         .into(),
     );
@@ -138,6 +138,10 @@ impl CanisterApi for CanisterApiImpl {
                 compute_allocation: None,
                 memory_allocation: None,
                 freezing_threshold: None,
+                log_visibility: None,
+                wasm_memory_limit: None,
+                wasm_memory_threshold: None,
+                reserved_cycles_limit: None,
             },
         };
 
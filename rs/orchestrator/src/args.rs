@@ -43,6 +43,12 @@ pub struct OrchestratorArgs {
     #[clap(long)]
     pub(crate) enable_provisional_registration: bool,
 
+    /// If set, the node signing key and the TLS key are rotated whenever the
+    /// TLS certificate registered for this node is older than this many
+    /// seconds.
+    #[clap(long)]
+    pub(crate) node_key_rotation_period_secs: Option<u64>,
+
     /// The path to the version file.
     #[clap(long, parse(from_os_str))]
     pub(crate) version_file: PathBuf,
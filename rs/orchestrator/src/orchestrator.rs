@@ -37,6 +37,7 @@ pub struct Orchestrator {
     upgrade: Option<Upgrade>,
     firewall: Option<Firewall>,
     ssh_access_manager: Option<SshAccessManager>,
+    registration: Option<NodeRegistration>,
     // A flag used to communicate to async tasks, that their job is done.
     exit_signal: Arc<RwLock<bool>>,
     // The subnet id of the node.
@@ -135,6 +136,7 @@ impl Orchestrator {
             node_id,
             Arc::clone(&crypto) as Arc<dyn CryptoComponentForNonReplicaProcess>,
             registry_local_store.clone(),
+            args.node_key_rotation_period_secs.map(Duration::from_secs),
         );
 
         if args.enable_provisional_registration {
@@ -203,13 +205,14 @@ impl Orchestrator {
             upgrade,
             firewall,
             ssh_access_manager,
+            registration: Some(registration),
             exit_signal: Default::default(),
             subnet_id: Default::default(),
             task_handles: Default::default(),
         })
     }
 
-    /// Starts two asynchronous tasks:
+    /// Starts three asynchronous tasks:
     ///
     /// 1. One that constantly monitors for a new CUP pointing to a newer
     /// replica version and executes the upgrade to this version if such a
@@ -221,6 +224,10 @@ impl Orchestrator {
     /// new data center is added, orchestrator will generate a new firewall
     /// configuration allowing access from the IP range specified in the DC
     /// record.
+    ///
+    /// 3. Third task makes sure that all node keys are registered and rotates
+    /// the node signing key and the TLS key once they reach the configured
+    /// age.
     pub fn spawn_tasks(&mut self) {
         async fn upgrade_checks(
             maybe_subnet_id: Arc<RwLock<Option<SubnetId>>>,
@@ -265,6 +272,26 @@ impl Orchestrator {
             info!(log, "Shut down the ssh keys & firewall monitoring loop");
         }
 
+        async fn node_key_checks(
+            registration: NodeRegistration,
+            exit_signal: Arc<RwLock<bool>>,
+            log: ReplicaLogger,
+        ) {
+            while !*exit_signal.read().await {
+                // Only rotate once the previously rotated keys (if any) are
+                // registered, so that the node never has more than one
+                // pending rotation.
+                if registration
+                    .check_additional_key_registered_otherwise_register()
+                    .await
+                {
+                    registration.rotate_node_keys_if_due().await;
+                }
+                tokio::time::sleep(CHECK_INTERVAL_SECS).await;
+            }
+            info!(log, "Shut down the node key check loop");
+        }
+
         if let Some(upgrade) = self.upgrade.take() {
             info!(self.logger, "Spawning the upgrade loop");
             self.task_handles.push(tokio::spawn(upgrade_checks(
@@ -290,6 +317,15 @@ impl Orchestrator {
                     self.logger.clone(),
                 )));
         }
+
+        if let Some(registration) = self.registration.take() {
+            info!(self.logger, "Spawning the node key check loop");
+            self.task_handles.push(tokio::spawn(node_key_checks(
+                registration,
+                Arc::clone(&self.exit_signal),
+                self.logger.clone(),
+            )));
+        }
     }
 
     /// Print the replica's current node ID.
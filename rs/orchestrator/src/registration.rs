@@ -10,18 +10,25 @@ use ic_config::{
     Config,
 };
 use ic_crypto::CryptoComponentForNonReplicaProcess;
+use ic_crypto_tls_interfaces::TlsPublicKeyCert;
 use ic_interfaces::{
     crypto::PublicKeyRegistrationStatus,
     registry::{RegistryClient, ZERO_REGISTRY_VERSION},
 };
 use ic_logger::{info, warn, ReplicaLogger};
 use ic_nns_constants::REGISTRY_CANISTER_ID;
-use ic_protobuf::registry::crypto::v1::PublicKey;
+use ic_protobuf::registry::crypto::v1::{PublicKey, X509PublicKeyCert};
 use ic_registry_local_store::LocalStore;
 use ic_sys::utility_command::UtilityCommand;
-use ic_types::{messages::MessageId, NodeId};
+use ic_types::{
+    crypto::{CryptoError, KeyPurpose},
+    messages::MessageId,
+    NodeId, RegistryVersion,
+};
+use openssl::asn1::Asn1Time;
 use prost::Message;
 use rand::prelude::*;
+use registry_canister::mutations::do_rotate_node_keys_directly::RotateNodeKeysDirectlyPayload;
 use registry_canister::mutations::do_update_node_directly::UpdateNodeDirectlyPayload;
 use registry_canister::mutations::node_management::do_add_node::AddNodePayload;
 use std::net::IpAddr;
@@ -37,6 +44,9 @@ pub(crate) struct NodeRegistration {
     node_id: NodeId,
     key_handler: Arc<dyn CryptoComponentForNonReplicaProcess>,
     local_store: Arc<dyn LocalStore>,
+    // If set, the node keys are rotated once the registered TLS certificate
+    // is older than this.
+    node_key_rotation_period: Option<Duration>,
 }
 
 impl NodeRegistration {
@@ -47,6 +57,7 @@ impl NodeRegistration {
         node_id: NodeId,
         key_handler: Arc<dyn CryptoComponentForNonReplicaProcess>,
         local_store: Arc<dyn LocalStore>,
+        node_key_rotation_period: Option<Duration>,
     ) -> Self {
         Self {
             log,
@@ -55,6 +66,7 @@ impl NodeRegistration {
             node_id,
             key_handler,
             local_store,
+            node_key_rotation_period,
         }
     }
 
@@ -191,6 +203,13 @@ impl NodeRegistration {
             Ok(PublicKeyRegistrationStatus::IDkgDealingEncPubkeyNeedsRegistration(key)) => {
                 self.try_to_register_additional_key(key).await
             }
+            Ok(PublicKeyRegistrationStatus::RotatedKeysNeedRegistration {
+                node_signing_pk,
+                tls_certificate,
+            }) => {
+                self.try_to_register_rotated_keys(node_signing_pk, tls_certificate)
+                    .await
+            }
             Ok(PublicKeyRegistrationStatus::AllKeysRegistered) => {
                 return true; // key is properly registered, we are all good
             }
@@ -207,8 +226,9 @@ impl NodeRegistration {
             None => return,
         };
         let node_id = self.node_id;
+        let registry_version = self.registry_client.get_latest_version();
 
-        let node_pub_key = if let Some(pk) = self.key_handler.node_public_keys().node_signing_pk {
+        let node_pub_key = if let Some(pk) = self.registered_node_signing_pk(registry_version) {
             pk
         } else {
             warn!(self.log, "Missing node signing key.");
@@ -216,7 +236,6 @@ impl NodeRegistration {
         };
 
         let key_handler = self.key_handler.clone();
-        let registry_version = self.registry_client.get_latest_version();
         let sign_cmd = move |msg: &MessageId| {
             tokio::task::block_in_place(|| {
                 key_handler
@@ -234,6 +253,7 @@ impl NodeRegistration {
         let agent = Agent::new(nns_url.clone(), sender);
         let update_node_payload = UpdateNodeDirectlyPayload {
             idkg_dealing_encryption_pk: Some(protobuf_to_vec(idkg_pk)),
+            node_id: Some(node_id),
         };
 
         if let Err(e) = agent
@@ -253,6 +273,153 @@ impl NodeRegistration {
         }
     }
 
+    /// Rotates the node keys if a rotation period is configured and the TLS
+    /// certificate registered for this node is older than that period.
+    ///
+    /// The TLS certificate is rotated together with the node signing key, so
+    /// its `notBefore` date is the age of both keys.
+    pub async fn rotate_node_keys_if_due(&self) {
+        let rotation_period = match self.node_key_rotation_period {
+            Some(period) => period,
+            None => return,
+        };
+        let registry_version = self.registry_client.get_latest_version();
+        let tls_certificate = match self.registered_tls_certificate(registry_version) {
+            Some(cert) => cert,
+            None => {
+                warn!(self.log, "Missing registered TLS certificate.");
+                return;
+            }
+        };
+        match tls_certificate_age(&tls_certificate, SystemTime::now()) {
+            Ok(age) if age >= rotation_period => {
+                info!(
+                    self.log,
+                    "Node keys are {:?} old, rotating them after {:?}.", age, rotation_period
+                );
+                self.rotate_node_keys().await
+            }
+            Ok(_) => {}
+            Err(e) => warn!(self.log, "Failed to determine the age of node keys: {}", e),
+        }
+    }
+
+    /// Rotates the node signing key and the TLS key of this node and tries to
+    /// register the new keys.
+    ///
+    /// The node keeps using its previous keys for all registry versions
+    /// before the one that contains the new keys. If the registration fails,
+    /// it is retried by `check_additional_key_registered_otherwise_register`.
+    pub async fn rotate_node_keys(&self) {
+        let rotated_keys = tokio::task::block_in_place(|| {
+            let node_signing_pk = self.key_handler.rotate_node_signing_key()?;
+            let tls_certificate = self.key_handler.rotate_tls_key()?;
+            Ok::<_, CryptoError>((node_signing_pk, tls_certificate))
+        });
+        match rotated_keys {
+            Ok((node_signing_pk, tls_certificate)) => {
+                info!(self.log, "Rotated node signing key and TLS key.");
+                self.try_to_register_rotated_keys(Some(node_signing_pk), Some(tls_certificate))
+                    .await
+            }
+            Err(e) => warn!(self.log, "Failed to rotate node keys: {:?}", e),
+        }
+    }
+
+    async fn try_to_register_rotated_keys(
+        &self,
+        node_signing_pk: Option<PublicKey>,
+        tls_certificate: Option<X509PublicKeyCert>,
+    ) {
+        let nns_url = match self.get_random_nns_url().await {
+            Some(url) => url,
+            None => return,
+        };
+        let node_id = self.node_id;
+        let registry_version = self.registry_client.get_latest_version();
+
+        // The request must be signed with the node signing key that is
+        // currently registered, which is the key `sign_basic` uses for
+        // `registry_version`, not the rotated one in the public key store.
+        let registered_node_signing_pk = match self.registered_node_signing_pk(registry_version) {
+            Some(pk) => pk,
+            None => {
+                warn!(self.log, "Missing registered node signing key.");
+                return;
+            }
+        };
+
+        let key_handler = self.key_handler.clone();
+        let sign_cmd = move |msg: &MessageId| {
+            tokio::task::block_in_place(|| {
+                key_handler
+                    .sign_basic(msg, node_id, registry_version)
+                    .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
+                    .map(|value| value.get().0)
+            })
+        };
+
+        let sender = Sender::Node {
+            pub_key: registered_node_signing_pk.key_value,
+            sign: Arc::new(sign_cmd),
+        };
+
+        let agent = Agent::new(nns_url.clone(), sender);
+        let rotate_node_keys_payload = RotateNodeKeysDirectlyPayload {
+            node_id,
+            node_signing_pk: node_signing_pk.map(protobuf_to_vec),
+            tls_certificate: tls_certificate.map(protobuf_to_vec),
+        };
+
+        if let Err(e) = agent
+            .execute_update(
+                &REGISTRY_CANISTER_ID,
+                "rotate_node_keys_directly",
+                Encode!(&rotate_node_keys_payload)
+                    .expect("Could not encode payload for rotate_node_keys_directly-call."),
+                generate_nonce(),
+            )
+            .await
+        {
+            warn!(
+                self.log,
+                "Error when sending register rotated keys request: {:?}", e
+            );
+        }
+    }
+
+    fn registered_tls_certificate(
+        &self,
+        registry_version: RegistryVersion,
+    ) -> Option<X509PublicKeyCert> {
+        use ic_registry_client_helpers::crypto::CryptoRegistry;
+        match self
+            .registry_client
+            .get_tls_certificate(self.node_id, registry_version)
+        {
+            Ok(cert) => cert,
+            Err(e) => {
+                warn!(self.log, "Registry error: {:?}", e);
+                None
+            }
+        }
+    }
+
+    fn registered_node_signing_pk(&self, registry_version: RegistryVersion) -> Option<PublicKey> {
+        use ic_registry_client_helpers::crypto::CryptoRegistry;
+        match self.registry_client.get_crypto_key_for_node(
+            self.node_id,
+            KeyPurpose::NodeSigning,
+            registry_version,
+        ) {
+            Ok(pk) => pk,
+            Err(e) => {
+                warn!(self.log, "Registry error: {:?}", e);
+                None
+            }
+        }
+    }
+
     async fn get_random_nns_url(&self) -> Option<Url> {
         match self.collect_shuffled_nns_urls().await {
             Ok(mut urls) => urls.pop(),
@@ -417,6 +584,26 @@ fn get_endpoint(log: &ReplicaLogger, ip_addr: String, port: u16) -> Orchestrator
 
 /// Create a nonce to be included with the ingress message sent to the node
 /// handler.
+/// Returns how long ago the given certificate became valid, or zero if it
+/// only becomes valid after `now`.
+fn tls_certificate_age(cert: &X509PublicKeyCert, now: SystemTime) -> Result<Duration, String> {
+    let cert = TlsPublicKeyCert::new_from_der(cert.certificate_der.clone())
+        .map_err(|e| format!("Malformed TLS certificate: {:?}", e))?;
+    let now_secs = now
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_err(|e| format!("Invalid current time: {}", e))?
+        .as_secs();
+    let now =
+        Asn1Time::from_unix(now_secs as i64).map_err(|e| format!("Invalid current time: {}", e))?;
+    let diff = cert
+        .as_x509()
+        .not_before()
+        .diff(&now)
+        .map_err(|e| format!("Invalid notBefore date: {}", e))?;
+    let age_secs = diff.days as i64 * 24 * 60 * 60 + diff.secs as i64;
+    Ok(Duration::from_secs(age_secs.max(0) as u64))
+}
+
 fn generate_nonce() -> Vec<u8> {
     SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -436,6 +623,7 @@ fn protobuf_to_vec<M: Message>(entry: M) -> Vec<u8> {
 mod tests {
     use super::*;
     use ic_config::transport::TransportFlowConfig;
+    use ic_crypto_test_utils::tls::x509_certificates::CertWithPrivateKey;
     use ic_test_utilities::with_test_replica_logger;
 
     #[test]
@@ -503,4 +691,30 @@ mod tests {
 
         assert_eq!(utility_command.execute().unwrap(), expected);
     }
+
+    fn tls_certificate_valid_from(not_before_unix: i64) -> X509PublicKeyCert {
+        let cert = CertWithPrivateKey::builder()
+            .not_before_unix(not_before_unix)
+            .build_ed25519();
+        X509PublicKeyCert {
+            certificate_der: cert.cert_der(),
+        }
+    }
+
+    #[test]
+    fn tls_certificate_age_is_time_since_not_before() {
+        let not_before = 1_600_000_000;
+        let cert = tls_certificate_valid_from(not_before);
+        let age = Duration::from_secs(3 * 24 * 60 * 60 + 42);
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(not_before as u64) + age;
+        assert_eq!(tls_certificate_age(&cert, now), Ok(age));
+    }
+
+    #[test]
+    fn tls_certificate_age_is_zero_before_not_before() {
+        let not_before = 1_600_000_000;
+        let cert = tls_certificate_valid_from(not_before);
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(not_before as u64 - 10);
+        assert_eq!(tls_certificate_age(&cert, now), Ok(Duration::from_secs(0)));
+    }
 }
@@ -32,6 +32,15 @@ pub struct EcdsaKeyId {
     #[prost(string, tag = "2")]
     pub name: ::prost::alloc::string::String,
 }
+#[derive(
+    serde::Serialize, serde::Deserialize, candid::CandidType, Eq, Clone, PartialEq, ::prost::Message,
+)]
+pub struct SchnorrKeyId {
+    #[prost(enumeration = "SchnorrAlgorithm", tag = "1")]
+    pub algorithm: i32,
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+}
 /// An algorithm ID. This is used to specify the signature algorithm associated with a public key.
 #[derive(
     serde::Serialize,
@@ -65,6 +74,9 @@ pub enum AlgorithmId {
     RsaSha256 = 14,
     ThresholdEcdsaSecp256k1 = 15,
     MegaSecp256k1 = 16,
+    ThresholdEcdsaSecp256r1 = 17,
+    ThresholdSchnorrBip340 = 18,
+    ThresholdEd25519 = 19,
 }
 /// Types of curves that can be used for ECDSA signatures.
 #[derive(
@@ -85,4 +97,26 @@ pub enum AlgorithmId {
 pub enum EcdsaCurve {
     Unspecified = 0,
     Secp256k1 = 1,
+    Secp256r1 = 2,
+}
+/// Types of algorithms that can be used for Schnorr signatures.
+#[derive(
+    serde::Serialize,
+    serde::Deserialize,
+    candid::CandidType,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    ::prost::Enumeration,
+)]
+#[repr(i32)]
+pub enum SchnorrAlgorithm {
+    Unspecified = 0,
+    Bip340secp256k1 = 1,
+    Ed25519 = 2,
 }
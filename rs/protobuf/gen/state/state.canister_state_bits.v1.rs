@@ -26,9 +26,11 @@ pub mod call_context {
         pub canister_id: ::core::option::Option<super::super::super::super::types::v1::CanisterId>,
         #[prost(uint64, tag = "2")]
         pub callback_id: u64,
+        #[prost(uint32, tag = "3")]
+        pub deadline_seconds: u32,
     }
     #[derive(Clone, PartialEq, ::prost::Message)]
-    pub struct Heartbeat {}
+    pub struct SystemTask {}
     #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum CallOrigin {
         #[prost(message, tag = "1")]
@@ -40,7 +42,7 @@ pub mod call_context {
         #[prost(message, tag = "4")]
         CanisterQuery(CanisterUpdateOrQuery),
         #[prost(message, tag = "7")]
-        Heartbeat(Heartbeat),
+        SystemTask(SystemTask),
     }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -73,6 +75,8 @@ pub struct Callback {
     pub originator: ::core::option::Option<super::super::super::types::v1::CanisterId>,
     #[prost(message, optional, tag = "7")]
     pub respondent: ::core::option::Option<super::super::super::types::v1::CanisterId>,
+    #[prost(uint32, tag = "8")]
+    pub deadline_seconds: u32,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CallbackEntry {
@@ -119,7 +123,7 @@ pub mod global {
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct WasmMethod {
-    #[prost(oneof = "wasm_method::WasmMethod", tags = "1, 2, 3")]
+    #[prost(oneof = "wasm_method::WasmMethod", tags = "1, 2, 3, 4")]
     pub wasm_method: ::core::option::Option<wasm_method::WasmMethod>,
 }
 /// Nested message and enum types in `WasmMethod`.
@@ -135,6 +139,8 @@ pub mod wasm_method {
         CanisterInspectMessage = 5,
         CanisterHeartbeat = 6,
         Empty = 7,
+        CanisterGlobalTimer = 8,
+        CanisterOnLowWasmMemory = 9,
     }
     #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum WasmMethod {
@@ -144,6 +150,8 @@ pub mod wasm_method {
         Query(::prost::alloc::string::String),
         #[prost(enumeration = "SystemMethod", tag = "3")]
         System(i32),
+        #[prost(string, tag = "4")]
+        CompositeQuery(::prost::alloc::string::String),
     }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -222,6 +230,53 @@ pub struct CanisterStatusStopping {
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CanisterStatusStopped {}
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExecutionTask {
+    #[prost(oneof = "execution_task::Task", tags = "1, 2")]
+    pub task: ::core::option::Option<execution_task::Task>,
+}
+/// Nested message and enum types in `ExecutionTask`.
+pub mod execution_task {
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct AbortedExecution {
+        #[prost(oneof = "aborted_execution::Message", tags = "1, 2, 3")]
+        pub message: ::core::option::Option<aborted_execution::Message>,
+    }
+    /// Nested message and enum types in `AbortedExecution`.
+    pub mod aborted_execution {
+        #[derive(Clone, PartialEq, ::prost::Oneof)]
+        pub enum Message {
+            #[prost(message, tag = "1")]
+            Request(super::super::super::super::queues::v1::Request),
+            #[prost(message, tag = "2")]
+            Response(super::super::super::super::queues::v1::Response),
+            #[prost(message, tag = "3")]
+            Ingress(super::super::super::super::ingress::v1::Ingress),
+        }
+    }
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct AbortedInstallCode {
+        #[prost(oneof = "aborted_install_code::Message", tags = "1, 2")]
+        pub message: ::core::option::Option<aborted_install_code::Message>,
+    }
+    /// Nested message and enum types in `AbortedInstallCode`.
+    pub mod aborted_install_code {
+        #[derive(Clone, PartialEq, ::prost::Oneof)]
+        pub enum Message {
+            #[prost(message, tag = "1")]
+            Request(super::super::super::super::queues::v1::Request),
+            #[prost(message, tag = "2")]
+            Ingress(super::super::super::super::ingress::v1::Ingress),
+        }
+    }
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Task {
+        #[prost(message, tag = "1")]
+        AbortedExecution(AbortedExecution),
+        #[prost(message, tag = "2")]
+        AbortedInstallCode(AbortedInstallCode),
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CanisterStateBits {
     #[prost(uint64, tag = "2")]
     pub last_full_execution_round: u64,
@@ -266,6 +321,44 @@ pub struct CanisterStateBits {
     /// tracked for the purposes of rate limiting the install_code messages.
     #[prost(uint64, tag = "29")]
     pub install_code_debit: u64,
+    /// The time in nanoseconds since the Unix epoch at which the canister's
+    /// global timer fires, or 0 if the timer is not set.
+    #[prost(uint64, tag = "30")]
+    pub global_timer_nanos: u64,
+    /// The canister's log, oldest record first.
+    #[prost(message, repeated, tag = "31")]
+    pub canister_log_records: ::prost::alloc::vec::Vec<CanisterLogRecord>,
+    /// The index assigned to the next record added to the canister's log.
+    #[prost(uint64, tag = "32")]
+    pub next_canister_log_record_idx: u64,
+    #[prost(enumeration = "LogVisibility", tag = "33")]
+    pub log_visibility: i32,
+    /// The most recent changes to the canister, e.g. code deployments and
+    /// controller changes.
+    #[prost(message, optional, tag = "34")]
+    pub canister_history: ::core::option::Option<CanisterHistory>,
+    /// The maximum size of the canister's Wasm memory in bytes, if limited.
+    #[prost(uint64, optional, tag = "35")]
+    pub wasm_memory_limit: ::core::option::Option<u64>,
+    /// The canister's `canister_on_low_wasm_memory` hook is triggered when less
+    /// than this many bytes are left below `wasm_memory_limit`.
+    #[prost(uint64, tag = "36")]
+    pub wasm_memory_threshold: u64,
+    #[prost(enumeration = "OnLowWasmMemoryHookStatus", tag = "37")]
+    pub on_low_wasm_memory_hook_status: i32,
+    /// Cycles reserved for the storage the canister allocated on a subnet with
+    /// high memory usage.
+    #[prost(message, optional, tag = "38")]
+    pub reserved_balance: ::core::option::Option<super::super::queues::v1::Cycles>,
+    /// The maximum number of cycles that can be reserved, if limited.
+    #[prost(message, optional, tag = "39")]
+    pub reserved_balance_limit: ::core::option::Option<super::super::queues::v1::Cycles>,
+    /// Incremented on every code installation and settings change.
+    #[prost(uint64, tag = "40")]
+    pub canister_version: u64,
+    /// Executions aborted before the checkpoint that are retried after it.
+    #[prost(message, repeated, tag = "41")]
+    pub task_queue: ::prost::alloc::vec::Vec<ExecutionTask>,
     #[prost(oneof = "canister_state_bits::CanisterStatus", tags = "11, 12, 13")]
     pub canister_status: ::core::option::Option<canister_state_bits::CanisterStatus>,
 }
@@ -281,6 +374,100 @@ pub mod canister_state_bits {
         Stopped(super::CanisterStatusStopped),
     }
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterLogRecord {
+    #[prost(uint64, tag = "1")]
+    pub idx: u64,
+    #[prost(uint64, tag = "2")]
+    pub timestamp_nanos: u64,
+    #[prost(bytes = "vec", tag = "3")]
+    pub content: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterChangeFromUser {
+    #[prost(message, optional, tag = "1")]
+    pub user_id: ::core::option::Option<super::super::super::types::v1::PrincipalId>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterChangeFromCanister {
+    #[prost(message, optional, tag = "1")]
+    pub canister_id: ::core::option::Option<super::super::super::types::v1::PrincipalId>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterCreation {
+    #[prost(message, repeated, tag = "1")]
+    pub controllers: ::prost::alloc::vec::Vec<super::super::super::types::v1::PrincipalId>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterCodeUninstall {}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterCodeDeployment {
+    #[prost(enumeration = "CanisterInstallMode", tag = "1")]
+    pub mode: i32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub module_hash: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterControllersChange {
+    #[prost(message, repeated, tag = "1")]
+    pub controllers: ::prost::alloc::vec::Vec<super::super::super::types::v1::PrincipalId>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterChange {
+    /// The batch time in nanoseconds since the Unix epoch at which the change
+    /// was made.
+    #[prost(uint64, tag = "1")]
+    pub timestamp_nanos: u64,
+    #[prost(oneof = "canister_change::ChangeOrigin", tags = "2, 3")]
+    pub change_origin: ::core::option::Option<canister_change::ChangeOrigin>,
+    #[prost(oneof = "canister_change::ChangeDetails", tags = "4, 5, 6, 7")]
+    pub change_details: ::core::option::Option<canister_change::ChangeDetails>,
+}
+/// Nested message and enum types in `CanisterChange`.
+pub mod canister_change {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum ChangeOrigin {
+        #[prost(message, tag = "2")]
+        CanisterChangeFromUser(super::CanisterChangeFromUser),
+        #[prost(message, tag = "3")]
+        CanisterChangeFromCanister(super::CanisterChangeFromCanister),
+    }
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum ChangeDetails {
+        #[prost(message, tag = "4")]
+        CanisterCreation(super::CanisterCreation),
+        #[prost(message, tag = "5")]
+        CanisterCodeUninstall(super::CanisterCodeUninstall),
+        #[prost(message, tag = "6")]
+        CanisterCodeDeployment(super::CanisterCodeDeployment),
+        #[prost(message, tag = "7")]
+        CanisterControllersChange(super::CanisterControllersChange),
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterHistory {
+    /// The most recent changes, oldest first.
+    #[prost(message, repeated, tag = "1")]
+    pub changes: ::prost::alloc::vec::Vec<CanisterChange>,
+    /// The total number of changes ever made to the canister, including the
+    /// ones that are no longer kept.
+    #[prost(uint64, tag = "2")]
+    pub total_num_changes: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterSnapshotBits {
+    /// The time in nanoseconds since the Unix epoch at which the snapshot was
+    /// taken.
+    #[prost(uint64, tag = "1")]
+    pub taken_at_timestamp_nanos: u64,
+    #[prost(bytes = "vec", tag = "2")]
+    pub certified_data: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "3")]
+    pub execution_state_bits: ::core::option::Option<ExecutionStateBits>,
+    /// The size of the snapshot's stable memory in Wasm pages.
+    #[prost(uint64, tag = "4")]
+    pub stable_memory_size: u64,
+}
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
 pub enum CustomSectionType {
@@ -288,3 +475,26 @@ pub enum CustomSectionType {
     Public = 1,
     Private = 2,
 }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum LogVisibility {
+    Unspecified = 0,
+    Controllers = 1,
+    Public = 2,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum OnLowWasmMemoryHookStatus {
+    Unspecified = 0,
+    ConditionNotSatisfied = 1,
+    Ready = 2,
+    Executed = 3,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum CanisterInstallMode {
+    Unspecified = 0,
+    Install = 1,
+    Reinstall = 2,
+    Upgrade = 3,
+}
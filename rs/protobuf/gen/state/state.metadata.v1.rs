@@ -212,6 +212,8 @@ pub struct SystemMetadata {
     pub time_of_last_allocation_charge_nanos: ::core::option::Option<TimeOfLastAllocationCharge>,
     #[prost(message, optional, tag = "15")]
     pub subnet_metrics: ::core::option::Option<SubnetMetrics>,
+    #[prost(uint64, tag = "16")]
+    pub next_snapshot_id: u64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct StableMemory {
@@ -205,6 +205,14 @@ fn build_registry_proto(def: &Path, out: &Path) {
         ".registry.crypto.v1.EcdsaKeyId",
         "#[derive(candid::CandidType, Eq)]",
     );
+    config.type_attribute(
+        ".registry.crypto.v1.SchnorrAlgorithm",
+        "#[derive(candid::CandidType)]",
+    );
+    config.type_attribute(
+        ".registry.crypto.v1.SchnorrKeyId",
+        "#[derive(candid::CandidType, Eq)]",
+    );
     config.type_attribute(
         ".registry.node_operator",
         "#[derive(candid::CandidType, serde::Serialize, candid::Deserialize, Eq, Hash)]",
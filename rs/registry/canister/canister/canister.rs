@@ -40,6 +40,7 @@ use registry_canister::{
         do_delete_subnet::DeleteSubnetPayload,
         do_recover_subnet::RecoverSubnetPayload,
         do_remove_nodes_from_subnet::RemoveNodesFromSubnetPayload,
+        do_rotate_node_keys_directly::RotateNodeKeysDirectlyPayload,
         do_set_firewall_config::SetFirewallConfigPayload,
         do_update_node_directly::UpdateNodeDirectlyPayload,
         do_update_node_operator_config::UpdateNodeOperatorConfigPayload,
@@ -790,6 +791,24 @@ fn update_node_directly_(payload: UpdateNodeDirectlyPayload) -> Result<(), Strin
     result
 }
 
+#[export_name = "canister_update rotate_node_keys_directly"]
+fn rotate_node_keys_directly() {
+    // This method can be called by anyone
+    println!(
+        "{}call: rotate_node_keys_directly from: {}",
+        LOG_PREFIX,
+        dfn_core::api::caller()
+    );
+    over_may_reject(candid_one, rotate_node_keys_directly_);
+}
+
+#[candid_method(update, rename = "rotate_node_keys_directly")]
+fn rotate_node_keys_directly_(payload: RotateNodeKeysDirectlyPayload) -> Result<(), String> {
+    let result = registry_mut().do_rotate_node_keys_directly(payload);
+    recertify_registry();
+    result
+}
+
 #[export_name = "canister_update remove_node_directly"]
 fn remove_node_directly() {
     // This method can be called by anyone
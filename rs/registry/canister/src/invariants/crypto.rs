@@ -40,8 +40,13 @@ type AllTlsCertificates = BTreeMap<NodeId, X509PublicKeyCert>;
 //     - TLS certificate
 //     - interactive DKG encryption public key
 //  * All public keys and TLS certificates have a corresponding node
-//  * every node's id (node_id) is correctly derived from its node signing
-//    public key
+//  * every node's id (node_id) is bound to its keys. The node signing public
+//    key is not required to derive the node_id, since this no longer holds
+//    for nodes that rotated their node signing key (see
+//    `do_rotate_node_keys_directly`); the node_id is checked against the one
+//    derived from the node signing key when the node is added (see
+//    `do_add_node`) and against the TLS certificate and the DKG dealing
+//    encryption key here.
 //  * all the public keys and all the TLS certificates belonging to the all the
 //    nodes are unique
 //  * At most 1 subnet can be an ECDSA signing subnet for a given key_id (for now)
@@ -122,13 +127,15 @@ fn check_node_keys(
         tls_certificate: certs.remove(node_id),
         idkg_dealing_encryption_pk,
     };
-    let vnpk = ValidNodePublicKeys::try_from(npk, *node_id).map_err(|e| InvariantCheckError {
-        msg: format!(
-            "crypto key validation for node {} failed with {}",
-            node_id, e
-        ),
-        source: None,
-    })?;
+    let vnpk = ValidNodePublicKeys::try_from_with_rotated_node_signing_key(npk, *node_id).map_err(
+        |e| InvariantCheckError {
+            msg: format!(
+                "crypto key validation for node {} failed with {}",
+                node_id, e
+            ),
+            source: None,
+        },
+    )?;
     Ok(vnpk)
 }
 
@@ -683,25 +690,46 @@ mod tests {
         insert_dummy_node(&node_id_2, &mut snapshot);
         insert_node_crypto_keys(&node_id_1, &node_pks_1, &mut snapshot);
 
+        // A node signing key that does not derive the node ID is a rotated
+        // key, but the TLS certificate must be issued for the node ID.
         let (node_pks_3, _node_id_3) = valid_node_keys_and_node_id();
-        let inconsistent_signing_key_node_pks = NodePublicKeys {
+        let inconsistent_tls_cert_node_pks = NodePublicKeys {
             version: node_pks_2.version,
-            node_signing_pk: node_pks_3.node_signing_pk,
+            node_signing_pk: node_pks_2.node_signing_pk,
             committee_signing_pk: node_pks_2.committee_signing_pk,
             dkg_dealing_encryption_pk: node_pks_2.dkg_dealing_encryption_pk,
             idkg_dealing_encryption_pk: node_pks_2.idkg_dealing_encryption_pk,
-            tls_certificate: node_pks_2.tls_certificate,
+            tls_certificate: node_pks_3.tls_certificate,
         };
-        insert_node_crypto_keys(
-            &node_id_2,
-            &inconsistent_signing_key_node_pks,
-            &mut snapshot,
-        );
+        insert_node_crypto_keys(&node_id_2, &inconsistent_tls_cert_node_pks, &mut snapshot);
         let result = check_node_crypto_keys_invariants(&snapshot);
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(err.to_string().contains(&node_id_2.to_string()));
-        assert!(err.to_string().contains("invalid node signing key"));
+        assert!(err.to_string().contains("invalid TLS certificate"));
+    }
+
+    #[test]
+    fn node_crypto_keys_with_rotated_node_signing_key_are_valid() {
+        let (node_pks_1, node_id_1) = valid_node_keys_and_node_id();
+        let (node_pks_2, _node_id_2) = valid_node_keys_and_node_id();
+        let rotated_node_pks = NodePublicKeys {
+            node_signing_pk: node_pks_2.node_signing_pk,
+            ..node_pks_1
+        };
+
+        let mut snapshot = RegistrySnapshot::new();
+        insert_dummy_node(&node_id_1, &mut snapshot);
+        insert_node_crypto_keys(&node_id_1, &rotated_node_pks, &mut snapshot);
+        let mut pks = get_all_nodes_public_keys(&snapshot);
+        let mut certs = get_all_tls_certs(&snapshot);
+
+        let valid_node_pks = check_node_keys(&node_id_1, &mut pks, &mut certs).unwrap();
+        assert_eq!(valid_node_pks.node_id(), node_id_1);
+        assert_eq!(
+            Some(valid_node_pks.node_signing_key()),
+            rotated_node_pks.node_signing_pk.as_ref()
+        );
     }
 
     #[test]
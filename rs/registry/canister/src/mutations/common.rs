@@ -1,11 +1,14 @@
 use std::convert::TryFrom;
 
-use ic_base_types::{PrincipalId, SubnetId};
+use ic_base_types::{NodeId, PrincipalId, SubnetId};
+use ic_crypto_utils_basic_sig::conversions as crypto_basicsig_conversions;
 use ic_protobuf::registry::{
-    replica_version::v1::BlessedReplicaVersions, subnet::v1::SubnetListRecord,
+    crypto::v1::PublicKey, replica_version::v1::BlessedReplicaVersions,
+    subnet::v1::SubnetListRecord,
 };
-use ic_registry_keys::make_blessed_replica_version_key;
+use ic_registry_keys::{make_blessed_replica_version_key, make_crypto_node_key};
 use ic_registry_transport::pb::v1::RegistryValue;
+use ic_types::crypto::KeyPurpose;
 use prost::Message;
 
 use crate::registry::Registry;
@@ -29,6 +32,37 @@ pub fn get_subnet_ids_from_subnet_list(subnet_list: SubnetListRecord) -> Vec<Sub
         .collect()
 }
 
+/// Checks that the caller of a call made directly by a node holds the node
+/// signing key that is currently registered for the node.
+///
+/// Such calls are signed with the node signing key, so the caller is the
+/// self-authenticating principal of this key. It is the node ID until the node
+/// rotates its node signing key for the first time. If no node signing key is
+/// registered for the node, the caller must be the node ID.
+pub(crate) fn check_caller_holds_node_signing_key(
+    registry: &Registry,
+    node_id: NodeId,
+    caller: PrincipalId,
+) -> Result<(), String> {
+    let node_signing_pk_key = make_crypto_node_key(node_id, KeyPurpose::NodeSigning);
+    let signer = match registry.get(node_signing_pk_key.as_bytes(), registry.latest_version()) {
+        Some(registry_value) => {
+            let node_signing_pk = PublicKey::decode(registry_value.value.as_slice())
+                .map_err(|e| format!("Failed to decode the node signing key: {:?}", e))?;
+            crypto_basicsig_conversions::derive_node_id(&node_signing_pk)
+                .map_err(|e| format!("Invalid node signing key of node {}: {:?}", node_id, e))?
+        }
+        None => node_id,
+    };
+    if NodeId::from(caller) != signer {
+        return Err(format!(
+            "The caller {} does not hold the node signing key of node {}",
+            caller, node_id
+        ));
+    }
+    Ok(())
+}
+
 fn blessed_versions_to_string(blessed: &BlessedReplicaVersions) -> String {
     format!("[{}]", blessed.blessed_version_ids.join(", "))
 }
@@ -0,0 +1,138 @@
+use crate::{
+    common::LOG_PREFIX,
+    mutations::common::{check_caller_holds_node_signing_key, encode_or_panic},
+    registry::Registry,
+};
+
+use prost::Message;
+
+use candid::{CandidType, Deserialize};
+use ic_base_types::NodeId;
+use ic_crypto_node_key_validation::{validate_tls_certificate, ValidNodeSigningPublicKey};
+use ic_protobuf::registry::crypto::v1::{PublicKey, X509PublicKeyCert};
+use ic_registry_keys::{make_crypto_node_key, make_crypto_tls_cert_key, make_node_record_key};
+use ic_registry_transport::update;
+use ic_types::crypto::KeyPurpose;
+
+impl Registry {
+    /// Replaces the node signing public key and/or the TLS certificate of an
+    /// existing node with rotated ones.
+    ///
+    /// This method is called directly by the node itself. The call must be
+    /// signed with the node signing key that is currently registered for the
+    /// node, i.e., the caller must be the self-authenticating principal of this
+    /// key. For a node that never rotated its node signing key, this principal
+    /// is the node ID.
+    ///
+    /// The node keeps using the previous keys for all registry versions
+    /// before the one created by this mutation.
+    pub fn do_rotate_node_keys_directly(
+        &mut self,
+        payload: RotateNodeKeysDirectlyPayload,
+    ) -> Result<(), String> {
+        println!("{}do_rotate_node_keys_directly: {:?}", LOG_PREFIX, payload);
+
+        // 1. Sanity check payload is not empty
+        if payload.node_signing_pk.is_none() && payload.tls_certificate.is_none() {
+            return Err(String::from(
+                "neither node_signing_pk nor tls_certificate is present",
+            ));
+        }
+        if matches!(&payload.node_signing_pk, Some(pk) if pk.is_empty()) {
+            return Err(String::from("node_signing_pk is empty"));
+        }
+        if matches!(&payload.tls_certificate, Some(cert) if cert.is_empty()) {
+            return Err(String::from("tls_certificate is empty"));
+        }
+
+        // 2. Check keys are not malformed
+        let node_signing_pk = payload
+            .node_signing_pk
+            .as_ref()
+            .map(|pk| PublicKey::decode(&pk[..]))
+            .transpose()
+            .map_err(|e| format!("node_signing_pk is not in the expected format: {:?}", e))?;
+        let tls_certificate = payload
+            .tls_certificate
+            .as_ref()
+            .map(|cert| X509PublicKeyCert::decode(&cert[..]))
+            .transpose()
+            .map_err(|e| format!("tls_certificate is not in the expected format: {:?}", e))?;
+
+        // 3. Check that the node exists
+        let node_id = payload.node_id;
+        let node_key = make_node_record_key(node_id);
+        self
+            .get(node_key.as_bytes(), self.latest_version())
+            .ok_or_else(|| format!(
+            "{}do_rotate_node_keys_directly: Node Id {:} not found in the registry, aborting node key rotation.",
+            LOG_PREFIX, node_id))?;
+
+        // 4. Check that the caller holds the currently registered node signing key
+        let node_signing_pk_key = make_crypto_node_key(node_id, KeyPurpose::NodeSigning);
+        let current_node_signing_pk = self
+            .get(node_signing_pk_key.as_bytes(), self.latest_version())
+            .map(|registry_value| PublicKey::decode(registry_value.value.as_slice()))
+            .transpose()
+            .map_err(|e| format!("Failed to decode the node signing key: {:?}", e))?
+            .ok_or_else(|| format!("Node signing key of node {} not found", node_id))?;
+        check_caller_holds_node_signing_key(self, node_id, dfn_core::api::caller())?;
+
+        let mut mutations = vec![];
+
+        // 5. Validate the rotated node signing key
+        if let Some(node_signing_pk) = node_signing_pk {
+            if node_signing_pk == current_node_signing_pk {
+                return Err(format!(
+                    "Node signing key was already set for this node {:?}",
+                    node_signing_pk_key
+                ));
+            }
+            let valid_node_signing_pk = ValidNodeSigningPublicKey::try_from(node_signing_pk)
+                .map_err(|e| format!("{}", e))?;
+            mutations.push(update(
+                node_signing_pk_key.as_bytes(),
+                encode_or_panic(valid_node_signing_pk.get()),
+            ));
+        }
+
+        // 6. Validate the rotated TLS certificate
+        if let Some(tls_certificate) = tls_certificate {
+            let tls_certificate_key = make_crypto_tls_cert_key(node_id);
+            let current_tls_certificate = self
+                .get(tls_certificate_key.as_bytes(), self.latest_version())
+                .and_then(|registry_value| {
+                    X509PublicKeyCert::decode(registry_value.value.as_slice()).ok()
+                });
+            if current_tls_certificate.as_ref() == Some(&tls_certificate) {
+                return Err(format!(
+                    "TLS certificate was already set for this node {:?}",
+                    tls_certificate_key
+                ));
+            }
+            validate_tls_certificate(Some(&tls_certificate), node_id)
+                .map_err(|e| format!("{}", e))?;
+            mutations.push(update(
+                tls_certificate_key.as_bytes(),
+                encode_or_panic(&tls_certificate),
+            ));
+        }
+
+        // Check invariants before applying mutations
+        self.maybe_apply_mutation_internal(mutations);
+
+        Ok(())
+    }
+}
+
+/// The payload of a request to rotate the node signing key and/or the TLS
+/// certificate of an existing node.
+///
+/// The keys are protobuf encoded (`PublicKey` and `X509PublicKeyCert`
+/// respectively).
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RotateNodeKeysDirectlyPayload {
+    pub node_id: NodeId,
+    pub node_signing_pk: Option<Vec<u8>>,
+    pub tls_certificate: Option<Vec<u8>>,
+}
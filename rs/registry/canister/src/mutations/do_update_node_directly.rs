@@ -1,4 +1,8 @@
-use crate::{common::LOG_PREFIX, mutations::common::encode_or_panic, registry::Registry};
+use crate::{
+    common::LOG_PREFIX,
+    mutations::common::{check_caller_holds_node_signing_key, encode_or_panic},
+    registry::Registry,
+};
 
 use prost::Message;
 
@@ -14,6 +18,9 @@ impl Registry {
     /// Updates an existing node's config in the registry.
     ///
     /// This method is called directly by the node itself that needs to update its node record.
+    /// The call must be signed with the node signing key that is currently
+    /// registered for the node. A node that rotated its node signing key is
+    /// no longer identified by the caller and has to set `node_id`.
     pub fn do_update_node_directly(
         &mut self,
         payload: UpdateNodeDirectlyPayload,
@@ -45,7 +52,7 @@ impl Registry {
 
         // 3. Check that caller is a node with a node_id that exists
         let caller = dfn_core::api::caller();
-        let node_id = NodeId::from(caller);
+        let node_id = payload.node_id.unwrap_or_else(|| NodeId::from(caller));
 
         let node_key = make_node_record_key(node_id);
         self
@@ -53,6 +60,7 @@ impl Registry {
             .ok_or_else(|| format!(
             "{}do_update_node_directly: Node Id {:} not found in the registry, aborting node update.",
             LOG_PREFIX, node_id))?;
+        check_caller_holds_node_signing_key(self, node_id, caller)?;
 
         // 4. Disallow updating if a key has already been set
         let idkg_de_pk_key = make_crypto_node_key(node_id, KeyPurpose::IDkgMEGaEncryption);
@@ -90,4 +98,7 @@ impl Registry {
 #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct UpdateNodeDirectlyPayload {
     pub idkg_dealing_encryption_pk: Option<Vec<u8>>,
+    /// The node to update. If not set, the caller is the node ID, which only
+    /// holds until the node rotates its node signing key.
+    pub node_id: Option<NodeId>,
 }
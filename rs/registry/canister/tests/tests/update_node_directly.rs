@@ -15,7 +15,10 @@ use ic_nns_test_utils::{
         get_value_or_panic, invariant_compliant_mutation_as_atomic_req, prepare_add_node_payload,
     },
 };
-use ic_protobuf::registry::{crypto::v1::PublicKey, node::v1::NodeRecord};
+use ic_protobuf::registry::{
+    crypto::v1::{AlgorithmId as AlgorithmIdProto, PublicKey},
+    node::v1::NodeRecord,
+};
 use ic_registry_keys::{make_crypto_node_key, make_node_record_key};
 use ic_registry_transport::pb::v1::{
     registry_mutation, RegistryAtomicMutateRequest, RegistryMutation,
@@ -61,14 +64,17 @@ fn node_is_updated_on_receiving_the_request() {
 
         let bad_payload = UpdateNodeDirectlyPayload {
             idkg_dealing_encryption_pk: Some(encode_or_panic(&bad_idkg_dealing_encryption_pk)),
+            node_id: None,
         };
 
         let good_payload = UpdateNodeDirectlyPayload {
             idkg_dealing_encryption_pk: Some(encode_or_panic(&good_idkg_dealing_encryption_pk)),
+            node_id: None,
         };
 
         let empty_payload = UpdateNodeDirectlyPayload {
             idkg_dealing_encryption_pk: Some(vec![]),
+            node_id: None,
         };
 
         // Issue a request with an unauthorized sender, which should fail.
@@ -146,6 +152,87 @@ fn node_is_updated_on_receiving_the_request() {
     });
 }
 
+#[test]
+fn node_with_rotated_node_signing_key_is_updated_on_receiving_the_request() {
+    local_test_on_nns_subnet(|runtime| async move {
+        let (add_node_payload, _, _) = prepare_add_node_payload();
+        let node_id = NodeId::from(*TEST_USER2_PRINCIPAL);
+        // The node rotated its node signing key to the key of TEST_USER1, so
+        // its calls are no longer made by the node ID.
+        let rotated_node_signing_pk = PublicKey {
+            version: 0,
+            algorithm: AlgorithmIdProto::Ed25519 as i32,
+            key_value: TEST_USER1_KEYPAIR.public.to_bytes().to_vec(),
+            proof_data: None,
+        };
+
+        let registry = set_up_registry_canister(
+            &runtime,
+            RegistryCanisterInitPayloadBuilder::new()
+                .push_init_mutate_request(invariant_compliant_mutation_as_atomic_req())
+                .push_init_mutate_request(init_mutation_for_node_with_id(
+                    node_id,
+                    &add_node_payload,
+                ))
+                .push_init_mutate_request(RegistryAtomicMutateRequest {
+                    mutations: vec![RegistryMutation {
+                        mutation_type: registry_mutation::Type::Insert as i32,
+                        key: make_crypto_node_key(node_id, KeyPurpose::NodeSigning)
+                            .as_bytes()
+                            .to_vec(),
+                        value: encode_or_panic(&rotated_node_signing_pk),
+                    }],
+                    preconditions: vec![],
+                })
+                .build(),
+        )
+        .await;
+
+        let (config, _temp_dir) = CryptoConfig::new_in_temp_dir();
+        let (pks, _node_id) = get_node_keys_or_generate_if_missing(&config, None);
+        let idkg_dealing_encryption_pk = pks
+            .idkg_dealing_encryption_pk
+            .expect("Missing idkg_dealing_encryption_pk");
+        let payload = UpdateNodeDirectlyPayload {
+            idkg_dealing_encryption_pk: Some(encode_or_panic(&idkg_dealing_encryption_pk)),
+            node_id: Some(node_id),
+        };
+
+        // The previous node signing key is no longer accepted.
+        let response: Result<(), String> = registry
+            .update_from_sender(
+                "update_node_directly",
+                candid,
+                (payload.clone(),),
+                &Sender::from_keypair(&TEST_USER2_KEYPAIR),
+            )
+            .await;
+        assert!(
+            matches!(response, Err(message) if message.contains("does not hold the node signing key"))
+        );
+        assert_no_idkg_mega_encryption_entry(&registry, node_id).await;
+
+        // A request signed with the rotated node signing key succeeds.
+        let response: Result<(), String> = registry
+            .update_from_sender(
+                "update_node_directly",
+                candid,
+                (payload,),
+                &Sender::from_keypair(&TEST_USER1_KEYPAIR),
+            )
+            .await;
+        assert!(response.is_ok());
+        let pk_record = get_value_or_panic::<PublicKey>(
+            &registry,
+            make_crypto_node_key(node_id, KeyPurpose::IDkgMEGaEncryption).as_bytes(),
+        )
+        .await;
+        assert_eq!(pk_record, idkg_dealing_encryption_pk);
+
+        Ok(())
+    });
+}
+
 async fn assert_no_idkg_mega_encryption_entry(registry: &Canister<'_>, node_id: NodeId) {
     // The pk record has not been updated
     let pk_record = get_value::<PublicKey>(
@@ -679,6 +679,7 @@ fn can_get_canister_information() {
                 None,
                 2592000,
                 0u128,
+                0,
             )
         );
 
@@ -730,6 +731,7 @@ fn can_get_canister_information() {
                     None,
                     259200,
                     0u128,
+                    0,
                 ),
                 CanisterStatusResultV2::decode(&res).unwrap(),
                 2 * BALANCE_EPSILON,
@@ -0,0 +1,257 @@
+#[cfg(test)]
+mod tests;
+
+use crate::{
+    canister_state::execution_state::WasmMetadata, num_bytes_try_from, CanisterState,
+    ExportedFunctions, Global, Memory, PageMap,
+};
+use ic_types::{CanisterId, NumBytes, PrincipalId, Time};
+use ic_wasm_types::CanisterModule;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::Arc;
+
+/// Uniquely identifies a canister snapshot on the subnet.
+///
+/// The id consists of the id of the canister the snapshot was taken of and a
+/// subnet-wide counter, so that ids are never reused even after the snapshot
+/// has been deleted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SnapshotId {
+    canister_id: CanisterId,
+    local_id: u64,
+}
+
+impl SnapshotId {
+    pub fn new(canister_id: CanisterId, local_id: u64) -> Self {
+        Self {
+            canister_id,
+            local_id,
+        }
+    }
+
+    /// Returns the id of the canister the snapshot belongs to.
+    pub fn canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    pub fn local_id(&self) -> u64 {
+        self.local_id
+    }
+
+    /// Returns the binary representation of the id exposed to users: the
+    /// big-endian `local_id` followed by the bytes of the canister id.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.local_id.to_be_bytes().to_vec();
+        bytes.extend_from_slice(self.canister_id.get_ref().as_slice());
+        bytes
+    }
+}
+
+impl TryFrom<&[u8]> for SnapshotId {
+    type Error = String;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        const LOCAL_ID_LENGTH: usize = std::mem::size_of::<u64>();
+        if bytes.len() <= LOCAL_ID_LENGTH {
+            return Err(format!(
+                "Snapshot id must be longer than {} bytes, got {}",
+                LOCAL_ID_LENGTH,
+                bytes.len()
+            ));
+        }
+        let (local_id, canister_id) = bytes.split_at(LOCAL_ID_LENGTH);
+        let mut local_id_bytes = [0; LOCAL_ID_LENGTH];
+        local_id_bytes.copy_from_slice(local_id);
+        let canister_id = PrincipalId::try_from(canister_id)
+            .map_err(|err| format!("Invalid canister id in snapshot id: {}", err))
+            .and_then(|id| CanisterId::new(id).map_err(|err| err.to_string()))?;
+        Ok(Self::new(canister_id, u64::from_be_bytes(local_id_bytes)))
+    }
+}
+
+impl fmt::Display for SnapshotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.canister_id, self.local_id)
+    }
+}
+
+/// A copy of the parts of a canister's state that are needed to restore it
+/// later: the Wasm module, the Wasm and stable memories, the exported globals
+/// and the certified data.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CanisterSnapshot {
+    /// The canister the snapshot was taken of.
+    pub canister_id: CanisterId,
+
+    /// The batch time at which the snapshot was taken.
+    pub taken_at_timestamp: Time,
+
+    /// The Wasm module installed at the time the snapshot was taken.
+    pub binary: CanisterModule,
+
+    pub wasm_memory: Memory,
+
+    pub stable_memory: Memory,
+
+    pub exported_globals: Vec<Global>,
+
+    /// Exports and metadata of `binary`, kept to avoid re-parsing the module
+    /// when the snapshot is loaded.
+    pub exports: ExportedFunctions,
+
+    pub metadata: WasmMetadata,
+
+    pub certified_data: Vec<u8>,
+}
+
+impl CanisterSnapshot {
+    /// Takes a snapshot of the given canister. Returns `None` if the canister
+    /// has no Wasm module installed.
+    ///
+    /// The memories of the snapshot share the canister's checkpoint files and
+    /// page deltas rather than copying all pages. At the next checkpoint the
+    /// snapshot's files are created from the canister's checkpoint files, on
+    /// top of which the page deltas are persisted.
+    pub fn from_canister(canister: &CanisterState, time: Time) -> Option<Self> {
+        let execution_state = canister.execution_state.as_ref()?;
+        Some(Self {
+            canister_id: canister.canister_id(),
+            taken_at_timestamp: time,
+            binary: execution_state.wasm_binary.binary.clone(),
+            wasm_memory: share_memory(&execution_state.wasm_memory),
+            stable_memory: share_memory(&execution_state.stable_memory),
+            exported_globals: execution_state.exported_globals.clone(),
+            exports: execution_state.exports.clone(),
+            metadata: execution_state.metadata.clone(),
+            certified_data: canister.system_state.certified_data.clone(),
+        })
+    }
+
+    /// Returns a copy of the Wasm memory of this snapshot that can be
+    /// installed into a canister.
+    pub fn restore_wasm_memory(&self) -> Memory {
+        copy_memory(&self.wasm_memory)
+    }
+
+    /// Returns a copy of the stable memory of this snapshot that can be
+    /// installed into a canister.
+    pub fn restore_stable_memory(&self) -> Memory {
+        copy_memory(&self.stable_memory)
+    }
+
+    /// Returns the amount of memory the snapshot occupies. This is the amount
+    /// the canister is charged for while the snapshot exists.
+    pub fn size(&self) -> NumBytes {
+        // We use 8 bytes per global, the same as for the execution state.
+        let globals_size_bytes = 8 * self.exported_globals.len() as u64;
+        num_bytes_try_from(self.wasm_memory.size)
+            .expect("could not convert from wasm memory number of pages to bytes")
+            + num_bytes_try_from(self.stable_memory.size)
+                .expect("could not convert from stable memory number of pages to bytes")
+            + NumBytes::from(globals_size_bytes)
+            + NumBytes::from(self.binary.len() as u64)
+            + NumBytes::from(self.certified_data.len() as u64)
+    }
+}
+
+/// Returns a `Memory` with the same pages as `memory`. The round delta is
+/// dropped from the shared `PageMap`, so that it is not flushed on its own to
+/// the snapshot's files, which don't exist before the next checkpoint.
+fn share_memory(memory: &Memory) -> Memory {
+    let mut page_map = memory.page_map.clone();
+    page_map.strip_round_delta();
+    Memory::new(page_map, memory.size)
+}
+
+/// Copies all pages of `memory` into the page delta of a new `PageMap`.
+fn copy_memory(memory: &Memory) -> Memory {
+    let pages: Vec<_> = memory.page_map.host_pages_iter().collect();
+    let mut page_map = PageMap::new();
+    page_map.update(&pages);
+    Memory::new(page_map, memory.size)
+}
+
+/// All canister snapshots on the subnet, indexed by snapshot id.
+///
+/// Snapshots are ordered by canister id first, so that the snapshots of a
+/// single canister can be looked up efficiently.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CanisterSnapshots {
+    snapshots: BTreeMap<SnapshotId, Arc<CanisterSnapshot>>,
+}
+
+impl CanisterSnapshots {
+    pub fn new(snapshots: BTreeMap<SnapshotId, Arc<CanisterSnapshot>>) -> Self {
+        Self { snapshots }
+    }
+
+    /// Adds a new snapshot, replacing any existing snapshot with the same id.
+    pub fn push(&mut self, snapshot_id: SnapshotId, snapshot: Arc<CanisterSnapshot>) {
+        self.snapshots.insert(snapshot_id, snapshot);
+    }
+
+    pub fn get(&self, snapshot_id: &SnapshotId) -> Option<&Arc<CanisterSnapshot>> {
+        self.snapshots.get(snapshot_id)
+    }
+
+    /// Returns a mutable reference to the given snapshot, cloning it first if
+    /// it is shared with another state.
+    pub fn get_mut(&mut self, snapshot_id: &SnapshotId) -> Option<&mut CanisterSnapshot> {
+        self.snapshots.get_mut(snapshot_id).map(Arc::make_mut)
+    }
+
+    pub fn remove(&mut self, snapshot_id: &SnapshotId) -> Option<Arc<CanisterSnapshot>> {
+        self.snapshots.remove(snapshot_id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&SnapshotId, &Arc<CanisterSnapshot>)> {
+        self.snapshots.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Returns the snapshots of the given canister ordered by snapshot id.
+    pub fn list_snapshots(
+        &self,
+        canister_id: CanisterId,
+    ) -> impl Iterator<Item = (&SnapshotId, &Arc<CanisterSnapshot>)> {
+        self.snapshots
+            .range(SnapshotId::new(canister_id, 0)..=SnapshotId::new(canister_id, u64::MAX))
+    }
+
+    /// Returns the number of snapshots of the given canister.
+    pub fn count_snapshots(&self, canister_id: CanisterId) -> usize {
+        self.list_snapshots(canister_id).count()
+    }
+
+    /// Returns the total size of all snapshots of the given canister.
+    pub fn memory_taken(&self, canister_id: CanisterId) -> NumBytes {
+        self.list_snapshots(canister_id)
+            .map(|(_, snapshot)| snapshot.size())
+            .sum()
+    }
+
+    /// Returns the total size of all snapshots on the subnet.
+    pub fn total_memory_taken(&self) -> NumBytes {
+        self.snapshots
+            .values()
+            .map(|snapshot| snapshot.size())
+            .sum()
+    }
+
+    /// Removes all snapshots of the given canister and returns their ids.
+    pub fn remove_snapshots(&mut self, canister_id: CanisterId) -> Vec<SnapshotId> {
+        let snapshot_ids: Vec<SnapshotId> = self
+            .list_snapshots(canister_id)
+            .map(|(snapshot_id, _)| *snapshot_id)
+            .collect();
+        for snapshot_id in snapshot_ids.iter() {
+            self.snapshots.remove(snapshot_id);
+        }
+        snapshot_ids
+    }
+}
@@ -0,0 +1,149 @@
+use super::*;
+use crate::canister_state::execution_state::{WasmBinary, WasmMetadata};
+use crate::{ExecutionState, NumWasmPages, PageIndex, SchedulerState, SystemState};
+use ic_base_types::NumSeconds;
+use ic_test_utilities::mock_time;
+use ic_test_utilities::types::ids::user_test_id;
+use ic_types::Cycles;
+
+const CANISTER_ID: CanisterId = CanisterId::from_u64(42);
+const OTHER_CANISTER_ID: CanisterId = CanisterId::from_u64(13);
+
+fn canister_with_memory(canister_id: CanisterId, page: u8) -> CanisterState {
+    let system_state = SystemState::new_running(
+        canister_id,
+        user_test_id(24).get(),
+        Cycles::new(1 << 36),
+        NumSeconds::from(100_000),
+    );
+    let mut wasm_memory = Memory::new(PageMap::new(), NumWasmPages::from(1));
+    wasm_memory
+        .page_map
+        .update(&[(PageIndex::new(0), &[page; crate::page_map::PAGE_SIZE])]);
+    let execution_state = ExecutionState::new(
+        Default::default(),
+        WasmBinary::new(CanisterModule::new(vec![1, 2, 3])),
+        ExportedFunctions::new(Default::default()),
+        wasm_memory,
+        Memory::default(),
+        vec![Global::I64(14)],
+        WasmMetadata::default(),
+    );
+    let mut canister = CanisterState::new(
+        system_state,
+        Some(execution_state),
+        SchedulerState::default(),
+    );
+    canister.system_state.certified_data = vec![page; 4];
+    canister
+}
+
+fn snapshot_of(canister_id: CanisterId) -> Arc<CanisterSnapshot> {
+    Arc::new(
+        CanisterSnapshot::from_canister(&canister_with_memory(canister_id, 1), mock_time())
+            .unwrap(),
+    )
+}
+
+#[test]
+fn snapshot_id_bytes_roundtrip() {
+    let snapshot_id = SnapshotId::new(CANISTER_ID, 17);
+    let bytes = snapshot_id.to_bytes();
+    assert_eq!(SnapshotId::try_from(bytes.as_slice()), Ok(snapshot_id));
+}
+
+#[test]
+fn snapshot_id_from_invalid_bytes_fails() {
+    assert!(SnapshotId::try_from(&[0u8; 8][..]).is_err());
+    assert!(SnapshotId::try_from(&[][..]).is_err());
+}
+
+#[test]
+fn snapshot_of_canister_without_module_is_none() {
+    let mut canister = canister_with_memory(CANISTER_ID, 1);
+    canister.execution_state = None;
+    assert_eq!(
+        CanisterSnapshot::from_canister(&canister, mock_time()),
+        None
+    );
+}
+
+#[test]
+fn snapshot_is_independent_of_canister() {
+    let mut canister = canister_with_memory(CANISTER_ID, 1);
+    let snapshot = CanisterSnapshot::from_canister(&canister, mock_time()).unwrap();
+
+    // Modifying the canister does not affect the snapshot.
+    let execution_state = canister.execution_state.as_mut().unwrap();
+    execution_state
+        .wasm_memory
+        .page_map
+        .update(&[(PageIndex::new(0), &[2; crate::page_map::PAGE_SIZE])]);
+    canister.system_state.certified_data = vec![2; 4];
+
+    assert_eq!(
+        snapshot.wasm_memory.page_map.get_page(PageIndex::new(0))[0],
+        1
+    );
+    assert_eq!(snapshot.certified_data, vec![1; 4]);
+    assert_eq!(snapshot.exported_globals, vec![Global::I64(14)]);
+
+    let restored = snapshot.restore_wasm_memory();
+    assert_eq!(restored.size, NumWasmPages::from(1));
+    assert_eq!(restored.page_map.get_page(PageIndex::new(0))[0], 1);
+}
+
+#[test]
+fn snapshot_size_includes_memories_module_and_certified_data() {
+    let snapshot = snapshot_of(CANISTER_ID);
+    // One Wasm page, one global, a 3 byte module and 4 bytes of certified data.
+    assert_eq!(snapshot.size(), NumBytes::from(65536 + 8 + 3 + 4));
+}
+
+#[test]
+fn snapshots_are_listed_per_canister() {
+    let mut snapshots = CanisterSnapshots::default();
+    snapshots.push(SnapshotId::new(CANISTER_ID, 0), snapshot_of(CANISTER_ID));
+    snapshots.push(
+        SnapshotId::new(OTHER_CANISTER_ID, 1),
+        snapshot_of(OTHER_CANISTER_ID),
+    );
+    snapshots.push(SnapshotId::new(CANISTER_ID, 2), snapshot_of(CANISTER_ID));
+
+    let listed: Vec<SnapshotId> = snapshots
+        .list_snapshots(CANISTER_ID)
+        .map(|(id, _)| *id)
+        .collect();
+    assert_eq!(
+        listed,
+        vec![
+            SnapshotId::new(CANISTER_ID, 0),
+            SnapshotId::new(CANISTER_ID, 2)
+        ]
+    );
+    assert_eq!(snapshots.count_snapshots(OTHER_CANISTER_ID), 1);
+    assert_eq!(
+        snapshots.memory_taken(CANISTER_ID),
+        snapshot_of(CANISTER_ID).size() * 2
+    );
+}
+
+#[test]
+fn remove_snapshots_only_removes_snapshots_of_canister() {
+    let mut snapshots = CanisterSnapshots::default();
+    snapshots.push(SnapshotId::new(CANISTER_ID, 0), snapshot_of(CANISTER_ID));
+    snapshots.push(
+        SnapshotId::new(OTHER_CANISTER_ID, 1),
+        snapshot_of(OTHER_CANISTER_ID),
+    );
+
+    assert_eq!(
+        snapshots.remove_snapshots(CANISTER_ID),
+        vec![SnapshotId::new(CANISTER_ID, 0)]
+    );
+    assert_eq!(snapshots.count_snapshots(CANISTER_ID), 0);
+    assert_eq!(snapshots.memory_taken(CANISTER_ID), NumBytes::from(0));
+    assert!(snapshots
+        .get(&SnapshotId::new(OTHER_CANISTER_ID, 1))
+        .is_some());
+}
@@ -185,17 +185,44 @@ impl CanisterState {
 
     /// Returns the first task from the task queue.  
     pub fn pop_task(&mut self) -> Option<ExecutionTask> {
-        self.execution_state
-            .as_mut()
-            .and_then(|es| es.task_queue.pop_front())
+        self.system_state.task_queue.pop_front()
     }
 
     /// Returns true if the canister has a task to execute.  
     pub fn has_task(&self) -> bool {
-        self.execution_state
-            .as_ref()
-            .map(|es| !es.task_queue.is_empty())
-            .unwrap_or(false)
+        !self.system_state.task_queue.is_empty()
+    }
+
+    /// Returns true if the canister has a paused `install_code` execution.
+    /// Such a task is always at the front of the task queue.
+    pub fn has_paused_install_code(&self) -> bool {
+        matches!(
+            self.system_state.task_queue.front(),
+            Some(ExecutionTask::PausedInstallCode(_))
+        )
+    }
+
+    /// Returns true if the canister has an aborted `install_code` execution.
+    /// Such a task is always at the front of the task queue.
+    pub fn has_aborted_install_code(&self) -> bool {
+        matches!(
+            self.system_state.task_queue.front(),
+            Some(ExecutionTask::AbortedInstallCode(_))
+        )
+    }
+
+    /// Returns true if the canister has a paused or aborted execution of a
+    /// message or of `install_code`.
+    pub fn has_paused_or_aborted_execution(&self) -> bool {
+        self.system_state.task_queue.iter().any(|task| match task {
+            ExecutionTask::PausedExecution(_)
+            | ExecutionTask::AbortedExecution(_)
+            | ExecutionTask::PausedInstallCode(_)
+            | ExecutionTask::AbortedInstallCode(_) => true,
+            ExecutionTask::Heartbeat
+            | ExecutionTask::GlobalTimer
+            | ExecutionTask::OnLowWasmMemory => false,
+        })
     }
 
     /// Returns true if the canister has a task or an input message to execute.  
@@ -343,6 +370,37 @@ impl CanisterState {
             + message_memory_usage
     }
 
+    /// The size of the canister's Wasm memory, i.e., its heap.
+    pub fn wasm_memory_usage(&self) -> NumBytes {
+        self.execution_state
+            .as_ref()
+            .map_or(NumBytes::from(0), |es| {
+                num_bytes_try_from(es.wasm_memory.size)
+                    .expect("could not convert from wasm memory number of pages to bytes")
+            })
+    }
+
+    /// Returns `true` if a Wasm memory limit and threshold are set and less
+    /// than `wasm_memory_threshold` bytes are left below the limit.
+    pub fn is_low_wasm_memory_hook_condition_satisfied(&self) -> bool {
+        let threshold = self.system_state.wasm_memory_threshold;
+        match self.system_state.wasm_memory_limit {
+            Some(limit) if threshold.get() > 0 => {
+                limit.get().saturating_sub(self.wasm_memory_usage().get()) < threshold.get()
+            }
+            _ => false,
+        }
+    }
+
+    /// Updates the status of the `canister_on_low_wasm_memory` hook based on
+    /// the canister's current Wasm memory usage.
+    pub fn update_on_low_wasm_memory_hook_status(&mut self) {
+        let is_hook_condition_satisfied = self.is_low_wasm_memory_hook_condition_satisfied();
+        self.system_state
+            .on_low_wasm_memory_hook_status
+            .update(is_hook_condition_satisfied);
+    }
+
     /// Hack to get the dashboard templating working.
     pub fn memory_usage_ref(&self, own_subnet_type: &SubnetType) -> NumBytes {
         self.memory_usage(*own_subnet_type)
@@ -385,6 +443,26 @@ impl CanisterState {
         }
     }
 
+    /// Returns true if the canister exports the `canister_global_timer` system
+    /// method.
+    pub fn exports_global_timer_method(&self) -> bool {
+        match &self.execution_state {
+            Some(execution_state) => execution_state
+                .exports_method(&WasmMethod::System(SystemMethod::CanisterGlobalTimer)),
+            None => false,
+        }
+    }
+
+    /// Returns true if the canister exports the `canister_on_low_wasm_memory`
+    /// system method.
+    pub fn exports_on_low_wasm_memory_method(&self) -> bool {
+        match &self.execution_state {
+            Some(execution_state) => execution_state
+                .exports_method(&WasmMethod::System(SystemMethod::CanisterOnLowWasmMemory)),
+            None => false,
+        }
+    }
+
     /// Returns true if the canister contains an exported query method with the
     /// name provided, false otherwise.
     pub fn exports_query_method(&self, method_name: String) -> bool {
@@ -396,6 +474,17 @@ impl CanisterState {
         }
     }
 
+    /// Returns true if the canister contains an exported composite query
+    /// method with the name provided, false otherwise.
+    pub fn exports_composite_query_method(&self, method_name: String) -> bool {
+        match &self.execution_state {
+            Some(execution_state) => {
+                execution_state.exports_method(&WasmMethod::CompositeQuery(method_name))
+            }
+            None => false,
+        }
+    }
+
     /// Returns the number of global variables in the Wasm module.
     pub fn num_wasm_globals(&self) -> usize {
         match &self.execution_state {
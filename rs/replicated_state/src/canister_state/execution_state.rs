@@ -1,6 +1,6 @@
 use super::SessionNonce;
 use crate::{canister_state::WASM_PAGE_SIZE_IN_BYTES, num_bytes_try_from, NumWasmPages, PageMap};
-use ic_interfaces::messages::CanisterInputMessage;
+use ic_interfaces::messages::{CanisterInputMessage, RequestOrIngress};
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
     state::canister_state_bits::v1 as pb,
@@ -10,11 +10,11 @@ use ic_types::{methods::WasmMethod, ExecutionRound, NumBytes};
 use ic_wasm_types::CanisterModule;
 use maplit::btreemap;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::BTreeMap;
 use std::hash::{Hash, Hasher};
 use std::{
     collections::BTreeSet,
-    convert::{From, TryFrom},
+    convert::{From, TryFrom, TryInto},
     iter::FromIterator,
     path::PathBuf,
     sync::{Arc, Mutex},
@@ -325,15 +325,21 @@ impl SandboxMemoryHandle {
 pub struct PausedExecutionId(pub u64);
 
 /// Represents a task that needs to be executed before processing canister
-/// inputs. The following tasks will be added in the future:
-/// - PausedInstallCode(..)
-/// - AbortedInstallCode(CanisterInputMessage)
-#[derive(Clone, Debug)]
+/// inputs.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ExecutionTask {
     // A heartbeat task exists only within an execution round. It is never
     // serialized.
     Heartbeat,
 
+    // A global timer task exists only within an execution round. It is never
+    // serialized.
+    GlobalTimer,
+
+    // An `on_low_wasm_memory` task exists only within an execution round. It
+    // is never serialized.
+    OnLowWasmMemory,
+
     // A paused execution task exists only within an epoch (between
     // checkpoints). It is never serialized and turns into `AbortedExecution`
     // before the checkpoint.
@@ -341,7 +347,121 @@ pub enum ExecutionTask {
 
     // Any paused execution that doesn't finish until the next checkpoint
     // becomes an aborted execution that should be retried after the checkpoint.
+    // It is serialized in the checkpoint.
     AbortedExecution(CanisterInputMessage),
+
+    // A paused `install_code` execution. Like `PausedExecution`, it exists
+    // only within an epoch and turns into `AbortedInstallCode` before the
+    // checkpoint. While it exists, the canister does not execute anything
+    // else.
+    PausedInstallCode(PausedExecutionId),
+
+    // A paused `install_code` execution that didn't finish until the next
+    // checkpoint. It is serialized in the checkpoint and restarted from
+    // scratch after it.
+    AbortedInstallCode(RequestOrIngress),
+}
+
+impl ExecutionTask {
+    /// Returns true if the task is serialized in the checkpoint. All other
+    /// tasks exist only within an execution round or an epoch.
+    pub fn is_persistent(&self) -> bool {
+        match self {
+            ExecutionTask::AbortedExecution(_) | ExecutionTask::AbortedInstallCode(_) => true,
+            ExecutionTask::Heartbeat
+            | ExecutionTask::GlobalTimer
+            | ExecutionTask::OnLowWasmMemory
+            | ExecutionTask::PausedExecution(_)
+            | ExecutionTask::PausedInstallCode(_) => false,
+        }
+    }
+}
+
+impl From<&ExecutionTask> for pb::ExecutionTask {
+    fn from(item: &ExecutionTask) -> Self {
+        use pb::execution_task::{aborted_execution, aborted_install_code, Task};
+        let task = match item {
+            ExecutionTask::Heartbeat
+            | ExecutionTask::GlobalTimer
+            | ExecutionTask::OnLowWasmMemory
+            | ExecutionTask::PausedExecution(_)
+            | ExecutionTask::PausedInstallCode(_) => {
+                panic!("Attempt to serialize ephemeral task: {:?}.", item);
+            }
+            ExecutionTask::AbortedExecution(message) => {
+                let message = match message {
+                    CanisterInputMessage::Request(request) => {
+                        aborted_execution::Message::Request(request.as_ref().into())
+                    }
+                    CanisterInputMessage::Response(response) => {
+                        aborted_execution::Message::Response(response.as_ref().into())
+                    }
+                    CanisterInputMessage::Ingress(ingress) => {
+                        aborted_execution::Message::Ingress(ingress.as_ref().into())
+                    }
+                };
+                Task::AbortedExecution(pb::execution_task::AbortedExecution {
+                    message: Some(message),
+                })
+            }
+            ExecutionTask::AbortedInstallCode(message) => {
+                let message = match message {
+                    RequestOrIngress::Request(request) => {
+                        aborted_install_code::Message::Request(request.as_ref().into())
+                    }
+                    RequestOrIngress::Ingress(ingress) => {
+                        aborted_install_code::Message::Ingress(ingress.as_ref().into())
+                    }
+                };
+                Task::AbortedInstallCode(pb::execution_task::AbortedInstallCode {
+                    message: Some(message),
+                })
+            }
+        };
+        Self { task: Some(task) }
+    }
+}
+
+impl TryFrom<pb::ExecutionTask> for ExecutionTask {
+    type Error = ProxyDecodeError;
+    fn try_from(value: pb::ExecutionTask) -> Result<Self, Self::Error> {
+        use pb::execution_task::{aborted_execution, aborted_install_code, Task};
+        let task = match value
+            .task
+            .ok_or(ProxyDecodeError::MissingField("ExecutionTask::task"))?
+        {
+            Task::AbortedExecution(aborted) => {
+                let message = match aborted.message.ok_or(ProxyDecodeError::MissingField(
+                    "ExecutionTask::AbortedExecution::message",
+                ))? {
+                    aborted_execution::Message::Request(request) => {
+                        CanisterInputMessage::Request(Arc::new(request.try_into()?))
+                    }
+                    aborted_execution::Message::Response(response) => {
+                        CanisterInputMessage::Response(Arc::new(response.try_into()?))
+                    }
+                    aborted_execution::Message::Ingress(ingress) => {
+                        CanisterInputMessage::Ingress(Arc::new(ingress.try_into()?))
+                    }
+                };
+                ExecutionTask::AbortedExecution(message)
+            }
+            Task::AbortedInstallCode(aborted) => {
+                let message = match aborted.message.ok_or(ProxyDecodeError::MissingField(
+                    "ExecutionTask::AbortedInstallCode::message",
+                ))? {
+                    aborted_install_code::Message::Request(request) => {
+                        RequestOrIngress::Request(Arc::new(request.try_into()?))
+                    }
+                    aborted_install_code::Message::Ingress(ingress) => {
+                        RequestOrIngress::Ingress(Arc::new(ingress.try_into()?))
+                    }
+                };
+                ExecutionTask::AbortedInstallCode(message)
+            }
+        };
+        Ok(task)
+    }
 }
 
 /// The part of the canister state that can be accessed during execution
@@ -400,10 +520,6 @@ pub struct ExecutionState {
     /// Round number at which canister executed
     /// update type operation.
     pub last_executed_round: ExecutionRound,
-
-    /// Tasks to execute before processing input messages.
-    /// Currently the task queue is empty outside of execution rounds.
-    pub task_queue: VecDeque<ExecutionTask>,
 }
 
 // We have to implement it by hand as embedder_cache can not be compared for
@@ -447,7 +563,6 @@ impl ExecutionState {
             exported_globals,
             metadata: wasm_metadata,
             last_executed_round: ExecutionRound::from(0),
-            task_queue: Default::default(),
         }
     }
 
@@ -10,13 +10,15 @@ use ic_protobuf::{
     types::v1 as pb_types,
 };
 use ic_types::{
-    messages::{Ingress, Request, RequestOrResponse, Response, MAX_RESPONSE_COUNT_BYTES},
+    messages::{
+        CallbackId, Ingress, Request, RequestOrResponse, Response, MAX_RESPONSE_COUNT_BYTES,
+    },
     xnet::{QueueId, SessionId},
     CanisterId, CountBytes, Cycles, QueueIndex,
 };
 use queue::{IngressQueue, InputQueue, OutputQueue};
 use std::{
-    collections::{BTreeMap, VecDeque},
+    collections::{BTreeMap, BTreeSet, VecDeque},
     convert::{From, TryFrom},
     ops::{AddAssign, SubAssign},
     sync::Arc,
@@ -68,6 +70,13 @@ pub struct CanisterQueues {
 
     /// Round-robin across ingress and cross-net input queues for pop_input().
     next_input_queue: NextInputQueue,
+
+    /// Callback IDs of all responses enqueued in input queues. Used to avoid
+    /// enqueuing a second response (e.g. a late response to a best-effort call
+    /// that was already timed out) for the same callback.
+    ///
+    /// Not persisted, recomputed from the input queues when deserializing.
+    callbacks_with_enqueued_response: BTreeSet<CallbackId>,
 }
 
 /// Circular iterator that consumes output queue messages: loops over output
@@ -312,6 +321,10 @@ impl CanisterQueues {
         };
         let iq_stats_delta = InputQueuesStats::stats_delta(QueueOp::Push, &msg);
         let mu_stats_delta = MemoryUsageStats::stats_delta(QueueOp::Push, &msg);
+        let response_callback = match &msg {
+            RequestOrResponse::Request(_) => None,
+            RequestOrResponse::Response(response) => Some(response.originator_reply_callback),
+        };
 
         input_queue.push(index, msg)?;
 
@@ -325,6 +338,9 @@ impl CanisterQueues {
             }
         }
 
+        if let Some(callback_id) = response_callback {
+            self.callbacks_with_enqueued_response.insert(callback_id);
+        }
         self.input_queues_stats += iq_stats_delta;
         self.memory_usage_stats += mu_stats_delta;
         debug_assert!(self.stats_ok());
@@ -332,6 +348,12 @@ impl CanisterQueues {
         Ok(())
     }
 
+    /// Returns `true` if a response for the given callback is enqueued in one
+    /// of the input queues.
+    pub fn has_enqueued_response(&self, callback_id: &CallbackId) -> bool {
+        self.callbacks_with_enqueued_response.contains(callback_id)
+    }
+
     /// Pops the next canister-to-canister message from `input_queues`.
     ///
     /// Note: We pop senders from the head of `input_schedule` and insert them
@@ -352,6 +374,10 @@ impl CanisterQueues {
                 input_schedule.push_back(sender);
             }
 
+            if let RequestOrResponse::Response(response) = &msg {
+                self.callbacks_with_enqueued_response
+                    .remove(&response.originator_reply_callback);
+            }
             self.input_queues_stats -= InputQueuesStats::stats_delta(QueueOp::Pop, &msg);
             self.memory_usage_stats -= MemoryUsageStats::stats_delta(QueueOp::Pop, &msg);
             debug_assert!(self.stats_ok());
@@ -653,9 +679,30 @@ impl CanisterQueues {
             Self::calculate_memory_usage_stats(&self.canister_queues),
             self.memory_usage_stats
         );
+        debug_assert_eq!(
+            Self::calculate_callbacks_with_enqueued_response(&self.canister_queues),
+            self.callbacks_with_enqueued_response
+        );
         true
     }
 
+    /// Computes the set of callbacks with an enqueued response from scratch.
+    /// Used when deserializing and in `debug_assert!()` checks.
+    ///
+    /// Time complexity: O(num_messages).
+    fn calculate_callbacks_with_enqueued_response(
+        canister_queues: &BTreeMap<CanisterId, (InputQueue, OutputQueue)>,
+    ) -> BTreeSet<CallbackId> {
+        canister_queues
+            .values()
+            .flat_map(|(iq, _)| iq.iter())
+            .filter_map(|msg| match msg {
+                RequestOrResponse::Request(_) => None,
+                RequestOrResponse::Response(response) => Some(response.originator_reply_callback),
+            })
+            .collect()
+    }
+
     /// Computes input queues stats from scratch. Used when deserializing and
     /// in `debug_assert!()` checks.
     ///
@@ -800,6 +847,8 @@ impl TryFrom<pb_queues::CanisterQueues> for CanisterQueues {
         let input_queues_stats = Self::calculate_input_queues_stats(&canister_queues);
         let memory_usage_stats = Self::calculate_memory_usage_stats(&canister_queues);
         let output_queues_stats = Self::calculate_output_queues_stats(&canister_queues);
+        let callbacks_with_enqueued_response =
+            Self::calculate_callbacks_with_enqueued_response(&canister_queues);
 
         let next_input_queue =
             match ProtoNextInputQueue::from_i32(item.next_input_queue).unwrap_or_default() {
@@ -835,6 +884,7 @@ impl TryFrom<pb_queues::CanisterQueues> for CanisterQueues {
             next_input_queue,
             local_subnet_input_schedule,
             remote_subnet_input_schedule,
+            callbacks_with_enqueued_response,
         })
     }
 }
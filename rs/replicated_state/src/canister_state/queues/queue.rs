@@ -279,6 +279,11 @@ impl InputQueue {
     pub(super) fn calculate_stat_sum(&self, stat: fn(&RequestOrResponse) -> usize) -> usize {
         self.queue.calculate_stat_sum(stat)
     }
+
+    /// Returns an iterator over the enqueued messages, front to back.
+    pub(super) fn iter(&self) -> impl Iterator<Item = &RequestOrResponse> {
+        self.queue.queue.iter()
+    }
 }
 
 impl From<&InputQueue> for pb_queues::InputOutputQueue {
@@ -1,11 +1,17 @@
 mod call_context_manager;
+mod canister_history;
+mod canister_log;
 
-use super::queues::can_push;
 pub use super::queues::memory_required_to_push_request;
+use super::queues::{can_push, QUEUE_INDEX_NONE};
 pub use crate::canister_state::queues::CanisterOutputQueuesIterator;
-use crate::{CanisterQueues, InputQueueType, StateError};
+use crate::{CanisterQueues, ExecutionTask, InputQueueType, StateError};
 pub use call_context_manager::{CallContext, CallContextAction, CallContextManager, CallOrigin};
+pub use canister_history::{CanisterHistory, MAX_CANISTER_HISTORY_CHANGES};
+pub use canister_log::{CanisterLog, MAX_CANISTER_LOG_BUFFER_SIZE};
 use ic_base_types::NumSeconds;
+use ic_error_types::RejectCode;
+use ic_ic00_types::{CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, LogVisibility};
 use ic_interfaces::messages::CanisterInputMessage;
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
@@ -13,19 +19,22 @@ use ic_protobuf::{
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_types::{
-    messages::{Ingress, Request, RequestOrResponse, Response, StopCanisterContext},
+    messages::{
+        Ingress, Payload, RejectContext, Request, RequestOrResponse, Response, StopCanisterContext,
+    },
     nominal_cycles::NominalCycles,
+    time::{CoarseTime, NO_DEADLINE},
     CanisterId, Cycles, MemoryAllocation, NumBytes, PrincipalId, QueueIndex, Time,
 };
 use lazy_static::lazy_static;
 use maplit::btreeset;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
+use std::{collections::BTreeSet, sync::Arc};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, VecDeque},
     convert::{TryFrom, TryInto},
 };
-use std::{collections::BTreeSet, sync::Arc};
 
 lazy_static! {
     static ref DEFAULT_PRINCIPAL_MULTIPLE_CONTROLLERS: PrincipalId =
@@ -47,6 +56,156 @@ pub struct CanisterMetrics {
     pub consumed_cycles_since_replica_started: NominalCycles,
 }
 
+/// The global timer of a canister.
+///
+/// The timer is set by the canister using `ic0.global_timer_set` and the
+/// `canister_global_timer` method is invoked once the time of the subnet is at
+/// or past the deadline. The timer is deactivated before the method is invoked,
+/// so it fires at most once for every deadline set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CanisterTimer {
+    /// The timer is not set.
+    Inactive,
+    /// The timer fires once the time of the subnet reaches the deadline.
+    Active(Time),
+}
+
+impl CanisterTimer {
+    /// Converts the timer into nanoseconds since the Unix epoch, where `0`
+    /// stands for an inactive timer. This matches the encoding used by
+    /// `ic0.global_timer_set`.
+    pub fn to_nanos_since_unix_epoch(&self) -> u64 {
+        match self {
+            CanisterTimer::Inactive => 0,
+            CanisterTimer::Active(time) => time.as_nanos_since_unix_epoch(),
+        }
+    }
+
+    /// Converts nanoseconds since the Unix epoch into a timer, where `0`
+    /// stands for an inactive timer.
+    pub fn from_nanos_since_unix_epoch(nanos: u64) -> Self {
+        match nanos {
+            0 => CanisterTimer::Inactive,
+            nanos => CanisterTimer::Active(Time::from_nanos_since_unix_epoch(nanos)),
+        }
+    }
+
+    /// Returns `true` if the timer is active and `now` is at or past its
+    /// deadline.
+    pub fn has_reached_deadline(&self, now: Time) -> bool {
+        match self {
+            CanisterTimer::Inactive => false,
+            CanisterTimer::Active(deadline) => *deadline <= now,
+        }
+    }
+}
+
+impl Default for CanisterTimer {
+    fn default() -> Self {
+        CanisterTimer::Inactive
+    }
+}
+
+/// Tracks whether the canister's `canister_on_low_wasm_memory` hook is due.
+///
+/// The hook is triggered at most once every time the free Wasm memory drops
+/// below `wasm_memory_threshold`: the status only returns to
+/// `ConditionNotSatisfied` once enough memory is available again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OnLowWasmMemoryHookStatus {
+    /// There is enough free Wasm memory, the hook is not due.
+    ConditionNotSatisfied,
+    /// The free Wasm memory is below the threshold and the hook has not been
+    /// executed yet.
+    Ready,
+    /// The hook has been executed and the free Wasm memory is still below the
+    /// threshold.
+    Executed,
+}
+
+impl OnLowWasmMemoryHookStatus {
+    /// Updates the status given whether the free Wasm memory is currently
+    /// below the threshold.
+    pub fn update(&mut self, is_hook_condition_satisfied: bool) {
+        *self = match (*self, is_hook_condition_satisfied) {
+            (_, false) => OnLowWasmMemoryHookStatus::ConditionNotSatisfied,
+            (OnLowWasmMemoryHookStatus::ConditionNotSatisfied, true) => {
+                OnLowWasmMemoryHookStatus::Ready
+            }
+            (status, true) => status,
+        };
+    }
+
+    pub fn is_ready(&self) -> bool {
+        *self == OnLowWasmMemoryHookStatus::Ready
+    }
+}
+
+impl Default for OnLowWasmMemoryHookStatus {
+    fn default() -> Self {
+        OnLowWasmMemoryHookStatus::ConditionNotSatisfied
+    }
+}
+
+impl From<OnLowWasmMemoryHookStatus> for pb::OnLowWasmMemoryHookStatus {
+    fn from(item: OnLowWasmMemoryHookStatus) -> Self {
+        match item {
+            OnLowWasmMemoryHookStatus::ConditionNotSatisfied => {
+                pb::OnLowWasmMemoryHookStatus::ConditionNotSatisfied
+            }
+            OnLowWasmMemoryHookStatus::Ready => pb::OnLowWasmMemoryHookStatus::Ready,
+            OnLowWasmMemoryHookStatus::Executed => pb::OnLowWasmMemoryHookStatus::Executed,
+        }
+    }
+}
+
+impl From<pb::OnLowWasmMemoryHookStatus> for OnLowWasmMemoryHookStatus {
+    fn from(item: pb::OnLowWasmMemoryHookStatus) -> Self {
+        match item {
+            // Checkpoints written before the hook was introduced don't
+            // specify the status, so they fall back to the default.
+            pb::OnLowWasmMemoryHookStatus::Unspecified
+            | pb::OnLowWasmMemoryHookStatus::ConditionNotSatisfied => {
+                OnLowWasmMemoryHookStatus::ConditionNotSatisfied
+            }
+            pb::OnLowWasmMemoryHookStatus::Ready => OnLowWasmMemoryHookStatus::Ready,
+            pb::OnLowWasmMemoryHookStatus::Executed => OnLowWasmMemoryHookStatus::Executed,
+        }
+    }
+}
+
+/// Errors returned by `SystemState::reserve_cycles()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReservationError {
+    /// The main balance holds less cycles than requested.
+    InsufficientCycles {
+        requested: Cycles,
+        available: Cycles,
+    },
+    /// The reserved balance would exceed its limit.
+    ReservedLimitExceed { requested: Cycles, limit: Cycles },
+}
+
+impl std::fmt::Display for ReservationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReservationError::InsufficientCycles {
+                requested,
+                available,
+            } => write!(
+                f,
+                "Cannot reserve {} cycles: only {} cycles are available",
+                requested, available
+            ),
+            ReservationError::ReservedLimitExceed { requested, limit } => write!(
+                f,
+                "The reserved cycles {} would exceed the reserved cycles limit {}",
+                requested, limit
+            ),
+        }
+    }
+}
+
 /// State that is controlled and owned by the system (IC).
 ///
 /// Contains structs needed for running and maintaining the canister on the IC.
@@ -78,6 +237,46 @@ pub struct SystemState {
     pub certified_data: Vec<u8>,
     pub canister_metrics: CanisterMetrics,
 
+    /// The canister's global timer, set through `ic0.global_timer_set`.
+    pub global_timer: CanisterTimer,
+
+    /// The messages the canister printed via `ic0.debug_print` and its trap
+    /// messages, readable through `fetch_canister_logs`.
+    pub canister_log: CanisterLog,
+
+    /// Determines who can read `canister_log`.
+    pub log_visibility: LogVisibility,
+
+    /// The maximum size of the canister's Wasm memory. `memory.grow` fails
+    /// with an error if it would exceed the limit. `None` means unlimited.
+    pub wasm_memory_limit: Option<NumBytes>,
+
+    /// The canister's `canister_on_low_wasm_memory` hook is triggered once
+    /// less than this many bytes are left below `wasm_memory_limit`.
+    pub wasm_memory_threshold: NumBytes,
+
+    /// Whether the `canister_on_low_wasm_memory` hook is due.
+    pub on_low_wasm_memory_hook_status: OnLowWasmMemoryHookStatus,
+
+    /// The version of the canister, readable through `ic0.canister_version`.
+    /// It starts at 0 and is incremented every time the canister's code is
+    /// installed, upgraded, reinstalled or uninstalled and every time its
+    /// settings are updated.
+    pub canister_version: u64,
+
+    /// The most recent changes to the canister, readable through
+    /// `canister_info` and certified in the canonical state.
+    canister_history: CanisterHistory,
+
+    /// Tasks to execute before processing input messages. The task queue is
+    /// not persisted in checkpoints: all tasks in it are either recreated in
+    /// each round or live only between checkpoints.
+    ///
+    /// It is part of the system state rather than the execution state because
+    /// an `install_code` execution can be paused on a canister that doesn't
+    /// have an execution state yet.
+    pub task_queue: VecDeque<ExecutionTask>,
+
     /// Should only be modified through `CyclesAccountManager`.
     ///
     /// A canister's state has an associated cycles balance, and may `send` a
@@ -92,6 +291,18 @@ pub struct SystemState {
     ///     2. executing the operation and return `cycles_spent`
     ///     3. reimburse the canister with `cycles_reserved` - `cycles_spent`
     cycles_balance: Cycles,
+
+    /// Cycles moved out of `cycles_balance` when the canister allocated new
+    /// storage on a subnet with high memory usage. They cannot be withdrawn
+    /// and are used to pay for the canister's storage before `cycles_balance`.
+    ///
+    /// Should only be modified through `CyclesAccountManager` or
+    /// `reserve_cycles()`.
+    reserved_balance: Cycles,
+
+    /// The upper bound of `reserved_balance`. Allocating storage that would
+    /// need a reservation beyond it fails. `None` means unlimited.
+    reserved_balance_limit: Option<Cycles>,
 }
 
 /// A wrapper around the different canister statuses.
@@ -236,11 +447,22 @@ impl SystemState {
             controllers: btreeset! {controller},
             queues: CanisterQueues::default(),
             cycles_balance: initial_cycles,
+            reserved_balance: Cycles::zero(),
+            reserved_balance_limit: None,
             memory_allocation: MemoryAllocation::BestEffort,
             freeze_threshold,
             status,
             certified_data: Default::default(),
             canister_metrics: CanisterMetrics::default(),
+            global_timer: CanisterTimer::Inactive,
+            canister_log: CanisterLog::default(),
+            log_visibility: LogVisibility::default(),
+            wasm_memory_limit: None,
+            wasm_memory_threshold: NumBytes::new(0),
+            on_low_wasm_memory_hook_status: OnLowWasmMemoryHookStatus::default(),
+            canister_version: 0,
+            canister_history: CanisterHistory::default(),
+            task_queue: Default::default(),
         }
     }
 
@@ -271,6 +493,17 @@ impl SystemState {
         certified_data: Vec<u8>,
         canister_metrics: CanisterMetrics,
         cycles_balance: Cycles,
+        reserved_balance: Cycles,
+        reserved_balance_limit: Option<Cycles>,
+        global_timer: CanisterTimer,
+        canister_log: CanisterLog,
+        log_visibility: LogVisibility,
+        wasm_memory_limit: Option<NumBytes>,
+        wasm_memory_threshold: NumBytes,
+        on_low_wasm_memory_hook_status: OnLowWasmMemoryHookStatus,
+        canister_version: u64,
+        canister_history: CanisterHistory,
+        task_queue: VecDeque<ExecutionTask>,
     ) -> Self {
         Self {
             controllers,
@@ -282,6 +515,17 @@ impl SystemState {
             certified_data,
             canister_metrics,
             cycles_balance,
+            reserved_balance,
+            reserved_balance_limit,
+            global_timer,
+            canister_log,
+            log_visibility,
+            wasm_memory_limit,
+            wasm_memory_threshold,
+            on_low_wasm_memory_hook_status,
+            canister_version,
+            canister_history,
+            task_queue,
         }
     }
 
@@ -289,6 +533,26 @@ impl SystemState {
         self.canister_id
     }
 
+    /// Returns the most recent changes to the canister.
+    pub fn canister_history(&self) -> &CanisterHistory {
+        &self.canister_history
+    }
+
+    /// Records a change to the canister made at the given time.
+    pub fn add_canister_change(
+        &mut self,
+        time: Time,
+        origin: CanisterChangeOrigin,
+        details: CanisterChangeDetails,
+    ) {
+        self.canister_history
+            .add_canister_change(CanisterChange::new(
+                time.as_nanos_since_unix_epoch(),
+                origin,
+                details,
+            ));
+    }
+
     /// Returns a mutable reference to the balance of the canister.
     pub fn balance_mut(&mut self) -> &mut Cycles {
         &mut self.cycles_balance
@@ -299,6 +563,56 @@ impl SystemState {
         self.cycles_balance
     }
 
+    /// Returns the amount of cycles that the reserved balance holds.
+    pub fn reserved_balance(&self) -> Cycles {
+        self.reserved_balance
+    }
+
+    /// Returns the upper bound of the reserved balance.
+    pub fn reserved_balance_limit(&self) -> Option<Cycles> {
+        self.reserved_balance_limit
+    }
+
+    /// Sets the upper bound of the reserved balance. The caller must ensure
+    /// that the limit is not below the current reserved balance.
+    pub fn set_reserved_balance_limit(&mut self, limit: Option<Cycles>) {
+        debug_assert!(limit.map_or(true, |limit| limit >= self.reserved_balance));
+        self.reserved_balance_limit = limit;
+    }
+
+    /// Moves `amount` cycles from the main balance to the reserved balance.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error and leaves the balances unchanged if the main balance
+    /// holds less than `amount` cycles or if the reserved balance would exceed
+    /// its limit.
+    pub fn reserve_cycles(&mut self, amount: Cycles) -> Result<(), ReservationError> {
+        if amount > self.cycles_balance {
+            return Err(ReservationError::InsufficientCycles {
+                requested: amount,
+                available: self.cycles_balance,
+            });
+        }
+        if let Some(limit) = self.reserved_balance_limit {
+            let requested = self.reserved_balance + amount;
+            if requested > limit {
+                return Err(ReservationError::ReservedLimitExceed { requested, limit });
+            }
+        }
+        self.cycles_balance -= amount;
+        self.reserved_balance += amount;
+        Ok(())
+    }
+
+    /// Removes up to `amount` cycles from the reserved balance and returns the
+    /// number of removed cycles.
+    pub fn remove_cycles_from_reserved_balance(&mut self, amount: Cycles) -> Cycles {
+        let removed = std::cmp::min(amount, self.reserved_balance);
+        self.reserved_balance -= removed;
+        removed
+    }
+
     /// This method is used for maintaining the backwards compatibility.
     /// Returns:
     /// - controller ID as-is, if there is only one controller.
@@ -481,6 +795,22 @@ impl SystemState {
                     call_context_manager
                         .validate_response(response)
                         .map_err(|err| (err, msg.clone()))?;
+                    if self
+                        .queues
+                        .has_enqueued_response(&response.originator_reply_callback)
+                    {
+                        // E.g. a late response to a best-effort call that was
+                        // already rejected because it timed out.
+                        return Err((
+                            StateError::NonMatchingResponse {
+                                err_str: "response already enqueued".to_string(),
+                                originator: response.originator,
+                                callback_id: response.originator_reply_callback,
+                                respondent: response.respondent,
+                            },
+                            msg,
+                        ));
+                    }
                 }
                 push_input(
                     &mut self.queues,
@@ -500,6 +830,72 @@ impl SystemState {
         self.queues.push_ingress(msg)
     }
 
+    /// Rejects all best-effort calls whose deadline is before `current_time`
+    /// and that have not yet received a response, by enqueuing a
+    /// `SYS_UNKNOWN` reject response into the slot reserved for the response.
+    /// Returns the number of callbacks that timed out.
+    ///
+    /// The cycles attached to a timed out call are lost: the reject response
+    /// carries no refund and a late response from the callee (including its
+    /// refund) is dropped.
+    pub fn time_out_callbacks(&mut self, current_time: CoarseTime) -> usize {
+        let call_context_manager = match &self.status {
+            CanisterStatus::Running {
+                call_context_manager,
+            }
+            | CanisterStatus::Stopping {
+                call_context_manager,
+                ..
+            } => call_context_manager,
+            CanisterStatus::Stopped => return 0,
+        };
+
+        let timed_out_responses: Vec<_> = call_context_manager
+            .callbacks()
+            .iter()
+            .filter(|(callback_id, callback)| {
+                callback.deadline != NO_DEADLINE
+                    && callback.deadline < current_time
+                    && !self.queues.has_enqueued_response(callback_id)
+            })
+            .map(|(callback_id, callback)| {
+                Response {
+                    originator: self.canister_id,
+                    // Best-effort callbacks always have a respondent, see
+                    // `Callback::new()`.
+                    respondent: callback
+                        .respondent
+                        .expect("Best-effort callback without respondent"),
+                    originator_reply_callback: *callback_id,
+                    refund: Cycles::zero(),
+                    response_payload: Payload::Reject(RejectContext {
+                        code: RejectCode::SysUnknown,
+                        message: "Call timed out.".to_string(),
+                    }),
+                    deadline: callback.deadline,
+                }
+            })
+            .collect();
+
+        let mut timed_out_count = 0;
+        for response in timed_out_responses {
+            // Cannot fail as long as there is a reserved slot for the response,
+            // which is the case for every callback without an enqueued response.
+            if self
+                .queues
+                .push_input(
+                    QUEUE_INDEX_NONE,
+                    RequestOrResponse::Response(Arc::new(response)),
+                    InputQueueType::LocalSubnet,
+                )
+                .is_ok()
+            {
+                timed_out_count += 1;
+            }
+        }
+        timed_out_count
+    }
+
     /// For each output queue, invokes `f` on every message until `f` returns
     /// `Err`; then moves on to the next output queue.
     ///
@@ -525,6 +921,17 @@ impl SystemState {
         &self.queues
     }
 
+    /// Takes the canister queues out of the system state, leaving empty
+    /// queues in their place.
+    pub fn take_queues(&mut self) -> CanisterQueues {
+        std::mem::take(&mut self.queues)
+    }
+
+    /// Replaces the canister queues with the given ones.
+    pub fn put_queues(&mut self, queues: CanisterQueues) {
+        self.queues = queues;
+    }
+
     /// Returns a boolean whether the system state is ready to be `Stopped`.
     /// Only relevant for a `Stopping` system state.
     pub fn ready_to_stop(&self) -> bool {
@@ -7,6 +7,7 @@ use ic_protobuf::proxy::{try_from_option_field, ProxyDecodeError};
 use ic_protobuf::state::canister_state_bits::v1 as pb;
 use ic_protobuf::types::v1 as pb_types;
 use ic_types::messages::Response;
+use ic_types::time::{CoarseTime, NO_DEADLINE};
 use ic_types::Time;
 use ic_types::{
     ingress::WasmResult,
@@ -215,10 +216,14 @@ pub struct CallContextManager {
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CallOrigin {
     Ingress(UserId, MessageId),
-    CanisterUpdate(CanisterId, CallbackId),
+    /// A request from another canister. The deadline of the request is carried
+    /// over to the response, so that a late response to a best-effort call can
+    /// be recognized and dropped.
+    CanisterUpdate(CanisterId, CallbackId, CoarseTime),
     Query(UserId),
     CanisterQuery(CanisterId, CallbackId),
-    Heartbeat,
+    /// A system task such as `canister_heartbeat` or `canister_global_timer`.
+    SystemTask,
 }
 
 impl From<&CallOrigin> for pb::call_context::CallOrigin {
@@ -228,10 +233,11 @@ impl From<&CallOrigin> for pb::call_context::CallOrigin {
                 user_id: Some(user_id_into_protobuf(*user_id)),
                 message_id: message_id.as_bytes().to_vec(),
             }),
-            CallOrigin::CanisterUpdate(canister_id, callback_id) => {
+            CallOrigin::CanisterUpdate(canister_id, callback_id, deadline) => {
                 Self::CanisterUpdate(pb::call_context::CanisterUpdateOrQuery {
                     canister_id: Some(pb_types::CanisterId::from(*canister_id)),
                     callback_id: callback_id.get(),
+                    deadline_seconds: deadline.as_secs_since_unix_epoch(),
                 })
             }
             CallOrigin::Query(user_id) => Self::Query(user_id_into_protobuf(*user_id)),
@@ -239,9 +245,10 @@ impl From<&CallOrigin> for pb::call_context::CallOrigin {
                 Self::CanisterQuery(pb::call_context::CanisterUpdateOrQuery {
                     canister_id: Some(pb_types::CanisterId::from(*canister_id)),
                     callback_id: callback_id.get(),
+                    deadline_seconds: NO_DEADLINE.as_secs_since_unix_epoch(),
                 })
             }
-            CallOrigin::Heartbeat => Self::Heartbeat(pb::call_context::Heartbeat {}),
+            CallOrigin::SystemTask => Self::SystemTask(pb::call_context::SystemTask {}),
         }
     }
 }
@@ -264,10 +271,12 @@ impl TryFrom<pb::call_context::CallOrigin> for CallOrigin {
                 pb::call_context::CanisterUpdateOrQuery {
                     canister_id,
                     callback_id,
+                    deadline_seconds,
                 },
             ) => Self::CanisterUpdate(
                 try_from_option_field(canister_id, "CallOrigin::CanisterUpdate::canister_id")?,
                 callback_id.into(),
+                CoarseTime::from_secs_since_unix_epoch(deadline_seconds),
             ),
             pb::call_context::CallOrigin::Query(user_id) => {
                 Self::Query(user_id_try_from_protobuf(user_id)?)
@@ -276,12 +285,13 @@ impl TryFrom<pb::call_context::CallOrigin> for CallOrigin {
                 pb::call_context::CanisterUpdateOrQuery {
                     canister_id,
                     callback_id,
+                    ..
                 },
             ) => Self::CanisterQuery(
                 try_from_option_field(canister_id, "CallOrigin::CanisterQuery::canister_id")?,
                 callback_id.into(),
             ),
-            pb::call_context::CallOrigin::Heartbeat { .. } => Self::Heartbeat,
+            pb::call_context::CallOrigin::SystemTask { .. } => Self::SystemTask,
         };
         Ok(call_origin)
     }
@@ -563,9 +573,11 @@ impl CallContextManager {
 impl From<&RequestOrIngress> for CallOrigin {
     fn from(msg: &RequestOrIngress) -> Self {
         match msg {
-            RequestOrIngress::Request(request) => {
-                CallOrigin::CanisterUpdate(request.sender, request.sender_reply_callback)
-            }
+            RequestOrIngress::Request(request) => CallOrigin::CanisterUpdate(
+                request.sender,
+                request.sender_reply_callback,
+                request.deadline,
+            ),
             RequestOrIngress::Ingress(ingress) => {
                 CallOrigin::Ingress(ingress.source, ingress.message_id.clone())
             }
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod tests;
+
+use ic_ic00_types::CanisterChange;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// The maximum number of changes kept in a canister's history. Once the
+/// history is full, the oldest changes are evicted to make room for new ones.
+pub const MAX_CANISTER_HISTORY_CHANGES: usize = 20;
+
+/// The most recent changes made to a canister: its creation, code
+/// deployments, code uninstallations and controller changes.
+///
+/// The changes are kept behind an `Arc`, as they rarely change but the
+/// canister state is cloned every round.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CanisterHistory {
+    changes: Arc<VecDeque<CanisterChange>>,
+    total_num_changes: u64,
+}
+
+impl CanisterHistory {
+    /// Creates a history from the given changes (oldest first) and the total
+    /// number of changes ever made to the canister, including evicted ones.
+    pub fn new(changes: Vec<CanisterChange>, total_num_changes: u64) -> Self {
+        debug_assert!(changes.len() as u64 <= total_num_changes);
+        Self {
+            changes: Arc::new(changes.into()),
+            total_num_changes,
+        }
+    }
+
+    /// Records a new change, evicting the oldest one if the history is full.
+    pub fn add_canister_change(&mut self, change: CanisterChange) {
+        let changes = Arc::make_mut(&mut self.changes);
+        if changes.len() >= MAX_CANISTER_HISTORY_CHANGES {
+            changes.pop_front();
+        }
+        changes.push_back(change);
+        self.total_num_changes += 1;
+    }
+
+    /// Returns the kept changes, oldest first.
+    pub fn changes(&self) -> &VecDeque<CanisterChange> {
+        &self.changes
+    }
+
+    /// Returns the (at most) `num_requested_changes` most recent changes,
+    /// oldest first.
+    pub fn get_changes(
+        &self,
+        num_requested_changes: usize,
+    ) -> impl Iterator<Item = &CanisterChange> {
+        let num_skipped = self.changes.len().saturating_sub(num_requested_changes);
+        self.changes.iter().skip(num_skipped)
+    }
+
+    /// Returns the total number of changes ever made to the canister.
+    pub fn total_num_changes(&self) -> u64 {
+        self.total_num_changes
+    }
+
+    /// Returns the index of the oldest kept change among all changes ever
+    /// made to the canister.
+    pub fn first_change_index(&self) -> u64 {
+        self.total_num_changes - self.changes.len() as u64
+    }
+}
@@ -0,0 +1,53 @@
+use super::*;
+use ic_ic00_types::{CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode};
+use ic_test_utilities::types::ids::user_test_id;
+
+fn code_deployment(timestamp_nanos: u64) -> CanisterChange {
+    CanisterChange::new(
+        timestamp_nanos,
+        CanisterChangeOrigin::from_user(user_test_id(1).get()),
+        CanisterChangeDetails::code_deployment(CanisterInstallMode::Upgrade, [0; 32]),
+    )
+}
+
+#[test]
+fn changes_are_recorded_in_order() {
+    let mut history = CanisterHistory::default();
+    history.add_canister_change(code_deployment(1));
+    history.add_canister_change(code_deployment(2));
+    assert_eq!(
+        history.changes().iter().cloned().collect::<Vec<_>>(),
+        vec![code_deployment(1), code_deployment(2)]
+    );
+    assert_eq!(history.total_num_changes(), 2);
+    assert_eq!(history.first_change_index(), 0);
+}
+
+#[test]
+fn oldest_changes_are_evicted_when_history_is_full() {
+    let mut history = CanisterHistory::default();
+    for i in 0..MAX_CANISTER_HISTORY_CHANGES as u64 + 5 {
+        history.add_canister_change(code_deployment(i));
+    }
+    assert_eq!(history.changes().len(), MAX_CANISTER_HISTORY_CHANGES);
+    assert_eq!(history.changes()[0], code_deployment(5));
+    assert_eq!(
+        history.total_num_changes(),
+        MAX_CANISTER_HISTORY_CHANGES as u64 + 5
+    );
+    assert_eq!(history.first_change_index(), 5);
+}
+
+#[test]
+fn get_changes_returns_most_recent_changes() {
+    let mut history = CanisterHistory::default();
+    for i in 0..3 {
+        history.add_canister_change(code_deployment(i));
+    }
+    assert_eq!(
+        history.get_changes(2).cloned().collect::<Vec<_>>(),
+        vec![code_deployment(1), code_deployment(2)]
+    );
+    assert_eq!(history.get_changes(0).count(), 0);
+    assert_eq!(history.get_changes(10).count(), 3);
+}
@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod tests;
+
+use ic_ic00_types::CanisterLogRecord;
+use ic_types::Time;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// The maximum total size of the records kept in a canister's log. Once the
+/// log is full, the oldest records are evicted to make room for new ones.
+pub const MAX_CANISTER_LOG_BUFFER_SIZE: usize = 4 * 1024;
+
+/// The space a record takes up in the log on top of its content: its index
+/// and timestamp.
+const RECORD_OVERHEAD: usize = 2 * std::mem::size_of::<u64>();
+
+/// Returns the space the given record takes up in the log.
+fn record_size(record: &CanisterLogRecord) -> usize {
+    RECORD_OVERHEAD + record.content.len()
+}
+
+/// A bounded ring buffer of the messages a canister printed via
+/// `ic0.debug_print` and of its trap messages.
+///
+/// Every record gets a unique, monotonically increasing index, so that
+/// readers can tell whether records were evicted between two reads.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanisterLog {
+    next_idx: u64,
+    records: VecDeque<CanisterLogRecord>,
+    used_space: usize,
+}
+
+impl CanisterLog {
+    pub fn new(next_idx: u64, records: Vec<CanisterLogRecord>) -> Self {
+        let used_space = records.iter().map(record_size).sum();
+        Self {
+            next_idx,
+            records: records.into(),
+            used_space,
+        }
+    }
+
+    /// Returns the index that will be assigned to the next record.
+    pub fn next_idx(&self) -> u64 {
+        self.next_idx
+    }
+
+    /// Returns the records of the log, oldest first.
+    pub fn records(&self) -> &VecDeque<CanisterLogRecord> {
+        &self.records
+    }
+
+    /// Returns the space taken up by the records of the log in bytes.
+    pub fn used_space(&self) -> usize {
+        self.used_space
+    }
+
+    /// Appends a new record with the given content, evicting the oldest
+    /// records if the log is full. Content that doesn't fit into an empty log
+    /// is truncated.
+    pub fn add_record(&mut self, time: Time, mut content: Vec<u8>) {
+        content.truncate(MAX_CANISTER_LOG_BUFFER_SIZE - RECORD_OVERHEAD);
+        let record = CanisterLogRecord {
+            idx: self.next_idx,
+            timestamp_nanos: time.as_nanos_since_unix_epoch(),
+            content,
+        };
+        self.next_idx += 1;
+        self.used_space += record_size(&record);
+        self.records.push_back(record);
+        while self.used_space > MAX_CANISTER_LOG_BUFFER_SIZE {
+            let evicted = self
+                .records
+                .pop_front()
+                .expect("The log cannot exceed its capacity while being empty");
+            self.used_space -= record_size(&evicted);
+        }
+    }
+
+    /// Removes all records from the log. Indices are not reused.
+    pub fn clear(&mut self) {
+        self.records.clear();
+        self.used_space = 0;
+    }
+}
@@ -0,0 +1,76 @@
+use super::*;
+
+fn time(nanos: u64) -> Time {
+    Time::from_nanos_since_unix_epoch(nanos)
+}
+
+#[test]
+fn records_get_increasing_indices() {
+    let mut log = CanisterLog::default();
+    log.add_record(time(1), b"a".to_vec());
+    log.add_record(time(2), b"b".to_vec());
+    assert_eq!(
+        log.records().iter().cloned().collect::<Vec<_>>(),
+        vec![
+            CanisterLogRecord {
+                idx: 0,
+                timestamp_nanos: 1,
+                content: b"a".to_vec(),
+            },
+            CanisterLogRecord {
+                idx: 1,
+                timestamp_nanos: 2,
+                content: b"b".to_vec(),
+            },
+        ]
+    );
+    assert_eq!(log.next_idx(), 2);
+    assert_eq!(log.used_space(), 2 * (RECORD_OVERHEAD + 1));
+}
+
+#[test]
+fn oldest_records_are_evicted_when_log_is_full() {
+    let mut log = CanisterLog::default();
+    let content_size = MAX_CANISTER_LOG_BUFFER_SIZE / 4 - RECORD_OVERHEAD;
+    for i in 0..5 {
+        log.add_record(time(i), vec![i as u8; content_size]);
+    }
+    let indices: Vec<u64> = log.records().iter().map(|record| record.idx).collect();
+    assert_eq!(indices, vec![1, 2, 3, 4]);
+    assert_eq!(log.used_space(), MAX_CANISTER_LOG_BUFFER_SIZE);
+}
+
+#[test]
+fn large_records_are_truncated() {
+    let mut log = CanisterLog::default();
+    log.add_record(time(0), b"small".to_vec());
+    log.add_record(time(1), vec![1; 2 * MAX_CANISTER_LOG_BUFFER_SIZE]);
+    assert_eq!(log.records().len(), 1);
+    assert_eq!(log.records()[0].idx, 1);
+    assert_eq!(
+        log.records()[0].content.len(),
+        MAX_CANISTER_LOG_BUFFER_SIZE - RECORD_OVERHEAD
+    );
+}
+
+#[test]
+fn clear_keeps_next_index() {
+    let mut log = CanisterLog::default();
+    log.add_record(time(0), b"a".to_vec());
+    log.clear();
+    assert!(log.records().is_empty());
+    assert_eq!(log.used_space(), 0);
+    log.add_record(time(1), b"b".to_vec());
+    assert_eq!(log.records()[0].idx, 1);
+}
+
+#[test]
+fn new_computes_used_space() {
+    let mut original = CanisterLog::default();
+    original.add_record(time(0), b"hello".to_vec());
+    let restored = CanisterLog::new(
+        original.next_idx(),
+        original.records().iter().cloned().collect(),
+    );
+    assert_eq!(restored, original);
+}
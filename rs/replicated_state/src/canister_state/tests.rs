@@ -2,17 +2,20 @@ use super::*;
 use crate::canister_state::execution_state::WasmMetadata;
 use crate::CallOrigin;
 use ic_base_types::NumSeconds;
+use ic_error_types::RejectCode;
+use ic_interfaces::messages::CanisterInputMessage;
 use ic_test_utilities::mock_time;
 use ic_test_utilities::types::{
     ids::user_test_id,
     messages::{RequestBuilder, ResponseBuilder},
 };
+use ic_types::{messages::MAX_RESPONSE_COUNT_BYTES, CountBytes, Cycles};
 use ic_types::{
-    messages::CallbackId,
+    messages::{CallbackId, Payload},
     methods::{Callback, WasmClosure},
+    time::{CoarseTime, NO_DEADLINE},
     Time,
 };
-use ic_types::{messages::MAX_RESPONSE_COUNT_BYTES, CountBytes, Cycles};
 use ic_wasm_types::CanisterModule;
 
 const INITIAL_CYCLES: Cycles = Cycles::new(1 << 36);
@@ -63,7 +66,7 @@ fn canister_state_push_input_response_no_reservation() {
             .call_context_manager_mut()
             .unwrap()
             .new_call_context(
-                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1)),
+                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1), NO_DEADLINE),
                 Cycles::zero(),
                 Time::from_nanos_since_unix_epoch(0),
             );
@@ -79,6 +82,7 @@ fn canister_state_push_input_response_no_reservation() {
                 WasmClosure::new(0, 2),
                 WasmClosure::new(0, 2),
                 None,
+                NO_DEADLINE,
             ));
 
         let response: RequestOrResponse = ResponseBuilder::default()
@@ -123,7 +127,7 @@ fn canister_state_push_input_response_success() {
             .call_context_manager_mut()
             .unwrap()
             .new_call_context(
-                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1)),
+                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1), NO_DEADLINE),
                 Cycles::zero(),
                 Time::from_nanos_since_unix_epoch(0),
             );
@@ -139,6 +143,7 @@ fn canister_state_push_input_response_success() {
                 WasmClosure::new(0, 2),
                 WasmClosure::new(0, 2),
                 None,
+                NO_DEADLINE,
             ));
 
         canister_state
@@ -159,6 +164,99 @@ fn canister_state_push_input_response_success() {
     })
 }
 
+#[test]
+fn canister_state_time_out_callbacks() {
+    canister_state_test(|mut canister_state| {
+        let deadline = CoarseTime::from_secs_since_unix_epoch(10);
+
+        // Make an input queue reservation.
+        canister_state
+            .push_output_request(
+                RequestBuilder::default()
+                    .sender(CANISTER_ID)
+                    .receiver(OTHER_CANISTER_ID)
+                    .deadline(deadline)
+                    .build()
+                    .into(),
+                mock_time(),
+            )
+            .unwrap();
+        canister_state.output_into_iter().count();
+
+        let call_context_id = canister_state
+            .system_state
+            .call_context_manager_mut()
+            .unwrap()
+            .new_call_context(
+                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1), NO_DEADLINE),
+                Cycles::zero(),
+                Time::from_nanos_since_unix_epoch(0),
+            );
+        let callback_id = canister_state
+            .system_state
+            .call_context_manager_mut()
+            .unwrap()
+            .register_callback(Callback::new(
+                call_context_id,
+                Some(CANISTER_ID),
+                Some(OTHER_CANISTER_ID),
+                Cycles::zero(),
+                WasmClosure::new(0, 2),
+                WasmClosure::new(0, 2),
+                None,
+                deadline,
+            ));
+
+        // Deadline not yet expired.
+        assert_eq!(0, canister_state.system_state.time_out_callbacks(deadline));
+
+        // Deadline expired: the callback is timed out exactly once.
+        let current_time = CoarseTime::from_secs_since_unix_epoch(11);
+        assert_eq!(
+            1,
+            canister_state.system_state.time_out_callbacks(current_time)
+        );
+        assert_eq!(
+            0,
+            canister_state.system_state.time_out_callbacks(current_time)
+        );
+
+        // A late response is not inducted.
+        let response: RequestOrResponse = ResponseBuilder::default()
+            .respondent(OTHER_CANISTER_ID)
+            .originator(CANISTER_ID)
+            .originator_reply_callback(callback_id)
+            .deadline(deadline)
+            .build()
+            .into();
+        assert!(matches!(
+            canister_state.push_input(
+                QueueIndex::from(0),
+                response,
+                MAX_CANISTER_MEMORY_SIZE,
+                &mut SUBNET_AVAILABLE_MEMORY.clone(),
+                SubnetType::Application,
+                InputQueueType::RemoteSubnet,
+            ),
+            Err((StateError::NonMatchingResponse { .. }, _))
+        ));
+
+        // The only input is the synthetic reject response.
+        match canister_state.system_state.pop_input() {
+            Some(CanisterInputMessage::Response(response)) => {
+                assert_eq!(callback_id, response.originator_reply_callback);
+                assert_eq!(deadline, response.deadline);
+                assert!(matches!(
+                    &response.response_payload,
+                    Payload::Reject(context) if context.code == RejectCode::SysUnknown
+                ));
+            }
+            msg => panic!("Expected a timeout reject response, got {:?}", msg),
+        }
+        assert_eq!(None, canister_state.system_state.pop_input());
+    })
+}
+
 #[test]
 #[should_panic(expected = "Expected `RequestOrResponse` to be targeted to canister ID")]
 fn canister_state_push_input_request_mismatched_receiver() {
@@ -456,7 +554,7 @@ fn canister_state_push_input_response_memory_limit_test_impl(
             .call_context_manager_mut()
             .unwrap()
             .new_call_context(
-                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1)),
+                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1), NO_DEADLINE),
                 Cycles::zero(),
                 Time::from_nanos_since_unix_epoch(0),
             );
@@ -472,6 +570,7 @@ fn canister_state_push_input_response_memory_limit_test_impl(
                 WasmClosure::new(0, 2),
                 WasmClosure::new(0, 2),
                 None,
+                NO_DEADLINE,
             ));
 
         let response: RequestOrResponse = ResponseBuilder::default()
@@ -1,4 +1,5 @@
 pub mod bitcoin_state;
+pub mod canister_snapshots;
 pub mod canister_state;
 pub mod metadata_state;
 pub mod page_map;
@@ -10,12 +11,15 @@ pub mod testing {
     pub use super::replicated_state::testing::ReplicatedStateTesting;
 }
 pub use bitcoin_state::{BitcoinState, BitcoinStateError};
+pub use canister_snapshots::{CanisterSnapshot, CanisterSnapshots, SnapshotId};
 pub use canister_state::{
     execution_state::{ExecutionTask, Memory},
     num_bytes_try_from,
     system_state::{
         memory_required_to_push_request, CallContext, CallContextAction, CallContextManager,
-        CallOrigin, CanisterMetrics, CanisterStatus, SystemState,
+        CallOrigin, CanisterHistory, CanisterLog, CanisterMetrics, CanisterStatus, CanisterTimer,
+        OnLowWasmMemoryHookStatus, ReservationError, SystemState, MAX_CANISTER_HISTORY_CHANGES,
+        MAX_CANISTER_LOG_BUFFER_SIZE,
     },
     CanisterQueues, CanisterState, EmbedderCache, ExecutionState, ExportedFunctions, Global,
     NumWasmPages, SchedulerState,
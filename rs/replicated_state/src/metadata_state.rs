@@ -60,6 +60,9 @@ pub struct SystemMetadata {
     /// Used for canister creation.
     pub generated_id_counter: u64,
 
+    /// A counter used for generating new canister snapshot ids.
+    pub next_snapshot_id: u64,
+
     /// The hash of the previous partial canonical state.
     /// The initial state doesn't have any previous state.
     pub prev_state_hash: Option<CryptoHashOfPartialState>,
@@ -432,6 +435,7 @@ impl From<&SystemMetadata> for pb_metadata::SystemMetadata {
                     .as_nanos_since_unix_epoch(),
             }),
             subnet_metrics: Some((&item.subnet_metrics).into()),
+            next_snapshot_id: item.next_snapshot_id,
         }
     }
 }
@@ -461,6 +465,7 @@ impl TryFrom<pb_metadata::SystemMetadata> for SystemMetadata {
             own_subnet_type: SubnetType::default(),
             own_subnet_features: item.own_subnet_features.unwrap_or_default().into(),
             generated_id_counter: item.generated_id_counter,
+            next_snapshot_id: item.next_snapshot_id,
             prev_state_hash: item.prev_state_hash.map(|b| CryptoHash(b).into()),
             batch_time: Time::from_nanos_since_unix_epoch(item.batch_time_nanos),
             ingress_history: try_from_option_field(
@@ -509,6 +514,7 @@ impl SystemMetadata {
             ingress_history: Default::default(),
             streams: Default::default(),
             generated_id_counter: Default::default(),
+            next_snapshot_id: 0,
             batch_time: UNIX_EPOCH,
             network_topology: Default::default(),
             subnet_call_context_manager: Default::default(),
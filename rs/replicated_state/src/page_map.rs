@@ -709,6 +709,10 @@ impl Buffer {
         self.dirty_pages.iter().map(|(i, p)| (*i, p))
     }
 
+    pub fn num_dirty_pages(&self) -> usize {
+        self.dirty_pages.len()
+    }
+
     pub fn into_page_map(&self) -> PageMap {
         let mut page_map = self.page_map.clone();
         page_map.update(&self.dirty_pages().collect::<Vec<_>>());
@@ -4,6 +4,7 @@ use super::{
 };
 use crate::{
     bitcoin_state::{BitcoinState, BitcoinStateError},
+    canister_snapshots::CanisterSnapshots,
     canister_state::system_state::{push_input, CanisterOutputQueuesIterator},
     metadata_state::StreamMap,
     CanisterQueues,
@@ -22,6 +23,7 @@ use ic_types::{
     messages::{
         is_subnet_message, CallbackId, MessageId, RequestOrResponse, Response, SignedIngressContent,
     },
+    time::CoarseTime,
     xnet::QueueId,
     CanisterId, MemoryAllocation, NumBytes, QueueIndex, SubnetId, Time,
 };
@@ -332,6 +334,9 @@ pub struct ReplicatedState {
     pub root: PathBuf,
 
     bitcoin: BitcoinState,
+
+    /// Snapshots of canisters taken via `take_canister_snapshot`.
+    pub canister_snapshots: CanisterSnapshots,
 }
 
 // We use custom impl of PartialEq because state root is not part of identity.
@@ -343,12 +348,14 @@ impl PartialEq for ReplicatedState {
             &self.metadata,
             &self.subnet_queues,
             &self.consensus_queue,
+            &self.canister_snapshots,
         ) == (
             &rhs.bitcoin,
             &rhs.canister_states,
             &rhs.metadata,
             &rhs.subnet_queues,
             &rhs.consensus_queue,
+            &rhs.canister_snapshots,
         )
     }
 }
@@ -367,6 +374,7 @@ impl ReplicatedState {
             subnet_queues: CanisterQueues::default(),
             consensus_queue: Vec::new(),
             bitcoin: BitcoinState::default(),
+            canister_snapshots: CanisterSnapshots::default(),
         }
     }
 
@@ -376,6 +384,7 @@ impl ReplicatedState {
         subnet_queues: CanisterQueues,
         consensus_queue: Vec<Response>,
         bitcoin: BitcoinState,
+        canister_snapshots: CanisterSnapshots,
         root: PathBuf,
     ) -> Self {
         let mut res = Self {
@@ -385,6 +394,7 @@ impl ReplicatedState {
             consensus_queue,
             root,
             bitcoin,
+            canister_snapshots,
         };
         res.update_stream_responses_size_bytes();
         res
@@ -544,8 +554,8 @@ impl ReplicatedState {
     ///
     /// This accounts for the canister memory reservation, where specified; and
     /// the actual canister memory usage, where no explicit memory reservation
-    /// has been made. Canister message memory usage is included for application
-    /// subnets only.
+    /// has been made. Canister snapshots are always included. Canister message
+    /// memory usage is included for application subnets only.
     pub fn total_memory_taken(&self) -> NumBytes {
         self.total_memory_taken_impl(self.metadata.own_subnet_type != SubnetType::System)
     }
@@ -570,6 +580,7 @@ impl ReplicatedState {
                 MemoryAllocation::BestEffort => canister.memory_usage_impl(with_messages),
             })
             .sum();
+        memory_taken += self.canister_snapshots.total_memory_taken();
         if with_messages {
             memory_taken += (self.subnet_queues.memory_usage() as u64).into();
         }
@@ -728,6 +739,17 @@ impl ReplicatedState {
         self.metadata.time()
     }
 
+    /// Rejects all best-effort calls of all canisters whose deadline has
+    /// passed, see `SystemState::time_out_callbacks()`. Returns the number of
+    /// callbacks that timed out.
+    pub fn time_out_callbacks(&mut self) -> usize {
+        let current_time = CoarseTime::floor(self.time());
+        self.canister_states
+            .values_mut()
+            .map(|canister| canister.system_state.time_out_callbacks(current_time))
+            .sum()
+    }
+
     /// Returns an immutable reference to `self.subnet_queues`.
     pub fn subnet_queues(&self) -> &CanisterQueues {
         &self.subnet_queues
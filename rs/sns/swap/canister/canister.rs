@@ -480,6 +480,7 @@ mod tests {
             None,
             0,
             0,
+            0,
         )
     }
 
@@ -1,6 +1,7 @@
 mod allocator;
 mod iter;
 mod node;
+mod overflow;
 use crate::{
     read_struct,
     types::{Address, Bytes, NULL},
@@ -8,17 +9,28 @@ use crate::{
 };
 use allocator::Allocator;
 pub use iter::Iter;
-use iter::{Cursor, Index};
-use node::{Entry, Node, NodeType, B};
+use node::{can_overflow, Entry, Node, NodeType, B};
 use std::marker::PhantomData;
-
-const LAYOUT_VERSION: u8 = 1;
+use std::ops::{Bound, RangeBounds};
+
+// The layout of maps where keys and values are bounded by the max key/value sizes.
+const LAYOUT_VERSION_1: u8 = 1;
+// The layout of maps where keys and values larger than the max key/value sizes are stored in
+// overflow pages. The header and the nodes of version 1 are valid in version 2, so a map is
+// migrated from version 1 to version 2 by only updating the version in its header.
+const LAYOUT_VERSION_2: u8 = 2;
 const MAGIC: &[u8; 3] = b"BTR";
 
 /// A "stable" map based on a B-tree.
 ///
 /// The implementation is based on the algorithm outlined in "Introduction to Algorithms"
 /// by Cormen et al.
+///
+/// Every node reserves `max_key_size` bytes for each key and `max_value_size` bytes for each
+/// value. Maps created with [`StableBTreeMap::new`] reject keys and values that are larger than
+/// these sizes. Maps created with [`StableBTreeMap::new_with_overflow`] store such keys and
+/// values in overflow pages instead, which allows keeping the max sizes small when large
+/// keys or values are rare.
 pub struct StableBTreeMap<M: Memory, K: Storable, V: Storable> {
     // The address of the root node. If a root node doesn't exist, the address
     // is set to NULL.
@@ -36,6 +48,9 @@ pub struct StableBTreeMap<M: Memory, K: Storable, V: Storable> {
     // The number of elements in the map.
     length: u64,
 
+    // The layout version of the map, which determines whether overflow pages are used.
+    version: u8,
+
     memory: M,
 
     // A marker to communicate to the Rust compiler that we own these types.
@@ -95,6 +110,51 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
     ///
     /// See [`Allocator`] for more details on its own memory layout.
     pub fn new(memory: M, max_key_size: u32, max_value_size: u32) -> Self {
+        Self::new_with_version(memory, max_key_size, max_value_size, LAYOUT_VERSION_1)
+    }
+
+    /// Initializes a `StableBTreeMap` that stores large keys and values in overflow pages.
+    ///
+    /// If the memory provided already contains a `StableBTreeMap`, then that map is loaded
+    /// and, if needed, migrated to the layout that supports overflow pages. Otherwise, a new
+    /// `StableBTreeMap` instance is created.
+    pub fn init_with_overflow(memory: M, max_key_size: u32, max_value_size: u32) -> Self {
+        if memory.size() == 0 {
+            // Memory is empty. Create a new map.
+            return StableBTreeMap::new_with_overflow(memory, max_key_size, max_value_size);
+        }
+
+        // Check if the magic in the memory corresponds to a StableBTreeMap.
+        let mut dst = vec![0; 3];
+        memory.read(0, &mut dst);
+        if dst != MAGIC {
+            // No StableBTreeMap found. Create a new instance.
+            StableBTreeMap::new_with_overflow(memory, max_key_size, max_value_size)
+        } else {
+            // The memory already contains a StableBTreeMap. Load and migrate it.
+            let mut btree = StableBTreeMap::load(memory);
+            btree.migrate_to_v2();
+            btree
+        }
+    }
+
+    /// Creates a new instance of a `StableBTreeMap` that stores large keys and values in
+    /// overflow pages.
+    ///
+    /// Keys (resp. values) of at most `max_key_size` (resp. `max_value_size`) bytes are stored
+    /// directly in the nodes of the B-tree. Larger keys and values are stored in overflow pages,
+    /// which are allocated from the same memory as the nodes. A key or a value can only spill
+    /// into overflow pages if its max size is at least 8 bytes.
+    ///
+    /// NOTE: the overflow pages of a node are rewritten every time the node is modified, so
+    /// overflowing keys and values are considerably more expensive to update than inline ones.
+    ///
+    /// See [`StableBTreeMap::new`] for the assumptions on the `memory`.
+    pub fn new_with_overflow(memory: M, max_key_size: u32, max_value_size: u32) -> Self {
+        Self::new_with_version(memory, max_key_size, max_value_size, LAYOUT_VERSION_2)
+    }
+
+    fn new_with_version(memory: M, max_key_size: u32, max_value_size: u32, version: u8) -> Self {
         // Because we assume that we have exclusive access to the memory,
         // we can store the `BTreeHeader` at address zero, and the allocator is
         // stored directly after the `BTreeHeader`.
@@ -111,6 +171,7 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
             max_key_size,
             max_value_size,
             length: 0,
+            version,
             _phantom: PhantomData,
         };
 
@@ -123,7 +184,10 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
         // Read the header from memory.
         let header: BTreeHeader = read_struct(Address::from(0), &memory);
         assert_eq!(&header.magic, MAGIC, "Bad magic.");
-        assert_eq!(header.version, LAYOUT_VERSION, "Unsupported version.");
+        assert!(
+            header.version == LAYOUT_VERSION_1 || header.version == LAYOUT_VERSION_2,
+            "Unsupported version."
+        );
 
         let allocator_addr = Address::from(0) + BTreeHeader::size();
         Self {
@@ -133,33 +197,48 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
             max_key_size: header.max_key_size,
             max_value_size: header.max_value_size,
             length: header.length,
+            version: header.version,
             _phantom: PhantomData,
         }
     }
 
+    // Migrates the map to the layout that supports overflow pages.
+    //
+    // Nodes of version 1 are valid nodes of version 2, so only the header needs to be updated.
+    // Nodes are then upgraded to version 2 lazily, whenever they are saved.
+    fn migrate_to_v2(&mut self) {
+        if self.version == LAYOUT_VERSION_1 {
+            self.version = LAYOUT_VERSION_2;
+            self.save();
+        }
+    }
+
     /// Inserts a key-value pair into the map.
     ///
     /// The previous value of the key, if present, is returned.
     ///
     /// The size of the key/value must be <= the max key/value sizes configured
-    /// for the map. Otherwise, an `InsertError` is returned.
+    /// for the map, unless the map stores large keys/values in overflow pages.
+    /// Otherwise, an `InsertError` is returned.
     pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, InsertError> {
         let key = key.to_bytes();
         let value = value.to_bytes();
 
         // Verify the size of the key.
-        if key.len() > self.max_key_size as usize {
+        let max_key_size = self.max_size(self.max_key_size);
+        if key.len() > max_key_size {
             return Err(InsertError::KeyTooLarge {
                 given: key.len(),
-                max: self.max_key_size as usize,
+                max: max_key_size,
             });
         }
 
         // Verify the size of the value.
-        if value.len() > self.max_value_size as usize {
+        let max_value_size = self.max_size(self.max_value_size);
+        if value.len() > max_value_size {
             return Err(InsertError::ValueTooLarge {
                 given: value.len(),
-                max: self.max_value_size as usize,
+                max: max_value_size,
             });
         }
 
@@ -180,7 +259,7 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
             if let Ok(idx) = root.get_key_idx(&key) {
                 // The key exists. Overwrite it and return the previous value.
                 let (_, previous_value) = root.swap_entry(idx, (key, value));
-                root.save(&self.memory);
+                self.save_node(&root);
                 return Ok(Some(V::from_bytes(previous_value)));
             }
 
@@ -212,6 +291,16 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
         Ok(self.insert_nonfull(root, key, value).map(V::from_bytes))
     }
 
+    // Returns the max size of a key or a value whose slot has the given size.
+    fn max_size(&self, slot_size: u32) -> usize {
+        if self.version == LAYOUT_VERSION_2 && can_overflow(slot_size) {
+            // The size of a key or a value is stored as a u32.
+            u32::MAX as usize
+        } else {
+            slot_size as usize
+        }
+    }
+
     // Inserts an entry into a node that is *not full*.
     fn insert_nonfull(&mut self, mut node: Node, key: Vec<u8>, value: Vec<u8>) -> Option<Vec<u8>> {
         // We're guaranteed by the caller that the provided node is not full.
@@ -224,7 +313,7 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
                 // Overwrite it and return the previous value.
                 let (_, previous_value) = node.swap_entry(idx, (key, value));
 
-                node.save(&self.memory);
+                self.save_node(&node);
                 Some(previous_value)
             }
             Err(idx) => {
@@ -235,7 +324,7 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
                         // The node is a non-full leaf.
                         // Insert the entry at the proper location.
                         node.entries.insert(idx, (key, value));
-                        node.save(&self.memory);
+                        self.save_node(&node);
 
                         // Update the length.
                         self.length += 1;
@@ -254,7 +343,7 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
                             if let Ok(idx) = child.get_key_idx(&key) {
                                 // The key exists. Overwrite it and return the previous value.
                                 let (_, previous_value) = child.swap_entry(idx, (key, value));
-                                child.save(&self.memory);
+                                self.save_node(&child);
                                 return Some(previous_value);
                             }
 
@@ -323,9 +412,9 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
         node.entries
             .insert(full_child_idx, (median_key, median_value));
 
-        sibling.save(&self.memory);
-        full_child.save(&self.memory);
-        node.save(&self.memory);
+        self.save_node(&sibling);
+        self.save_node(&full_child);
+        self.save_node(node);
     }
 
     /// Returns the value associated with the given key if it exists.
@@ -413,10 +502,10 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
                             );
 
                             // Deallocate the empty node.
-                            self.allocator.deallocate(node.address);
+                            self.deallocate_node(node.address);
                             self.root_addr = NULL;
                         } else {
-                            node.save(&self.memory);
+                            self.save_node(&node);
                         }
 
                         self.save();
@@ -462,7 +551,7 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
                             let (_, old_value) = node.swap_entry(idx, predecessor);
 
                             // Save the parent node.
-                            node.save(&self.memory);
+                            self.save_node(&node);
                             return Some(old_value);
                         }
 
@@ -498,7 +587,7 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
                             let (_, old_value) = node.swap_entry(idx, successor);
 
                             // Save the parent node.
-                            node.save(&self.memory);
+                            self.save_node(&node);
                             return Some(old_value);
                         }
 
@@ -539,12 +628,12 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
                             self.root_addr = new_child.address;
 
                             // Deallocate the root node.
-                            self.allocator.deallocate(node.address);
+                            self.deallocate_node(node.address);
                             self.save();
                         }
 
-                        node.save(&self.memory);
-                        new_child.save(&self.memory);
+                        self.save_node(&node);
+                        self.save_node(&new_child);
 
                         // Recursively delete the key.
                         self.remove_helper(new_child.address, key)
@@ -619,9 +708,9 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
                                     assert_eq!(child.node_type, NodeType::Leaf);
                                 }
 
-                                left_sibling.save(&self.memory);
-                                child.save(&self.memory);
-                                node.save(&self.memory);
+                                self.save_node(left_sibling);
+                                self.save_node(&child);
+                                self.save_node(&node);
                                 return self.remove_helper(child.address, key);
                             }
                         }
@@ -670,9 +759,9 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
                                     }
                                 }
 
-                                right_sibling.save(&self.memory);
-                                child.save(&self.memory);
-                                node.save(&self.memory);
+                                self.save_node(right_sibling);
+                                self.save_node(&child);
+                                self.save_node(&node);
                                 return self.remove_helper(child.address, key);
                             }
                         }
@@ -688,7 +777,7 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
                             node.children.remove(idx);
 
                             if node.entries.is_empty() {
-                                self.allocator.deallocate(node.address);
+                                self.deallocate_node(node.address);
 
                                 if node.address == self.root_addr {
                                     // Update the root.
@@ -696,7 +785,7 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
                                     self.save();
                                 }
                             } else {
-                                node.save(&self.memory);
+                                self.save_node(&node);
                             }
 
                             return self.remove_helper(left_sibling_address, key);
@@ -712,7 +801,7 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
                             node.children.remove(idx);
 
                             if node.entries.is_empty() {
-                                self.allocator.deallocate(node.address);
+                                self.deallocate_node(node.address);
 
                                 if node.address == self.root_addr {
                                     // Update the root.
@@ -720,7 +809,7 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
                                     self.save();
                                 }
                             } else {
-                                node.save(&self.memory);
+                                self.save_node(&node);
                             }
 
                             return self.remove_helper(right_sibling_address, key);
@@ -738,71 +827,62 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
         Iter::new(self)
     }
 
+    /// Returns an iterator over the entries in the map whose keys are within the given range.
+    ///
+    /// NOTE: keys are compared using their byte representation (see [`Storable`]), which is
+    /// the order in which the map stores and iterates over them.
+    pub fn range<R: RangeBounds<K>>(&self, key_range: R) -> Iter<M, K, V> {
+        Iter::new_in_range(
+            self,
+            encode_bound(key_range.start_bound()),
+            encode_bound(key_range.end_bound()),
+        )
+    }
+
     /// Returns an iterator over the entries in the map where keys begin with the given `prefix`.
     /// If the optional `offset` is set, the iterator returned will start from the entry that
     /// contains this `offset` (while still iterating over all remaining entries that begin
     /// with the given `prefix`).
-    pub fn range(&self, prefix: Vec<u8>, offset: Option<Vec<u8>>) -> Iter<M, K, V> {
-        if self.root_addr == NULL {
-            // Map is empty.
-            return Iter::null(self);
+    pub fn range_with_prefix(&self, prefix: Vec<u8>, offset: Option<Vec<u8>>) -> Iter<M, K, V> {
+        // The keys starting with `prefix` are exactly the keys that are >= `prefix` and
+        // < the smallest key that is greater than all the keys starting with `prefix`.
+        let end = match prefix_upper_bound(&prefix) {
+            Some(upper_bound) => Bound::Excluded(upper_bound),
+            None => Bound::Unbounded,
+        };
+
+        let mut start = prefix;
+        if let Some(offset) = offset {
+            start.extend_from_slice(&offset);
         }
 
-        let mut node = self.load_node(self.root_addr);
-        let mut cursors = vec![];
-        loop {
-            // Look for the prefix in the node.
-            let mut pivot = prefix.clone();
-            if let Some(offset) = &offset {
-                pivot.extend_from_slice(offset);
-            }
-            match node.entries.binary_search_by(|e| e.0.cmp(&pivot)) {
-                Ok(idx) | Err(idx) => {
-                    // If `prefix` is a key in the node, then `idx` would return its
-                    // location. Otherwise, `idx` is the location of the next key in
-                    // lexicographical order.
-
-                    // Load the next child of the node to visit if it exists.
-                    // This is done first to avoid cloning the node.
-                    let child = match node.node_type {
-                        NodeType::Internal => {
-                            // Note that loading a child node cannot fail since
-                            // len(children) = len(entries) + 1
-                            Some(self.load_node(node.children[idx]))
-                        }
-                        NodeType::Leaf => None,
-                    };
-
-                    // If the prefix is found in the node, then add a cursor starting from its index.
-                    if idx < node.entries.len() && node.entries[idx].0.starts_with(&prefix) {
-                        cursors.push(Cursor::Node {
-                            node,
-                            next: Index::Entry(idx),
-                        });
-                    }
+        Iter::new_in_range(self, Bound::Included(start), end)
+    }
 
-                    match child {
-                        None => {
-                            // Leaf node. Return an iterator with the found cursors.
-                            match offset {
-                                Some(offset) => {
-                                    return Iter::new_with_prefix_and_offset(
-                                        self, prefix, offset, cursors,
-                                    );
-                                }
-                                None => {
-                                    return Iter::new_with_prefix(self, prefix, cursors);
-                                }
-                            }
-                        }
-                        Some(child) => {
-                            // Iterate over the child node.
-                            node = child;
-                        }
-                    }
-                }
-            }
-        }
+    /// Returns the first key-value pair in the map, i.e. the one with the smallest key,
+    /// or `None` if the map is empty.
+    pub fn first_key_value(&self) -> Option<(K, V)> {
+        self.iter().next()
+    }
+
+    /// Returns the last key-value pair in the map, i.e. the one with the largest key,
+    /// or `None` if the map is empty.
+    pub fn last_key_value(&self) -> Option<(K, V)> {
+        self.iter().next_back()
+    }
+
+    /// Removes and returns the first key-value pair in the map, or `None` if the map is empty.
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let (key, _) = self.first_key_value()?;
+        let value = self.remove(&key)?;
+        Some((key, value))
+    }
+
+    /// Removes and returns the last key-value pair in the map, or `None` if the map is empty.
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let (key, _) = self.last_key_value()?;
+        let value = self.remove(&key)?;
+        Some((key, value))
     }
 
     // Merges one node (`source`) into another (`into`), along with a median entry.
@@ -841,9 +921,9 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
         // Move the children (if any exist).
         lower.children.append(&mut higher.children);
 
-        lower.save(&self.memory);
+        self.save_node(&lower);
 
-        self.allocator.deallocate(source_address);
+        self.deallocate_node(source_address);
         lower
     }
 
@@ -867,11 +947,35 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
         )
     }
 
+    // Saves the node to memory, using overflow pages if the map supports them.
+    fn save_node(&mut self, node: &Node) {
+        if self.version == LAYOUT_VERSION_2 {
+            node.save_with_overflow(&self.memory, &mut self.allocator);
+        } else {
+            node.save(&self.memory);
+        }
+    }
+
+    // Deallocates the node at the given address, along with its overflow pages if any.
+    fn deallocate_node(&mut self, address: Address) {
+        if self.version == LAYOUT_VERSION_2 {
+            Node::deallocate_with_overflow(
+                address,
+                &self.memory,
+                &mut self.allocator,
+                self.max_key_size,
+                self.max_value_size,
+            );
+        } else {
+            self.allocator.deallocate(address);
+        }
+    }
+
     // Saves the map to memory.
     fn save(&self) {
         let header = BTreeHeader {
             magic: *MAGIC,
-            version: LAYOUT_VERSION,
+            version: self.version,
             root_addr: self.root_addr,
             max_key_size: self.max_key_size,
             max_value_size: self.max_value_size,
@@ -883,6 +987,28 @@ impl<M: Memory + Clone, K: Storable, V: Storable> StableBTreeMap<M, K, V> {
     }
 }
 
+// Converts a bound on keys into a bound on their byte representation.
+fn encode_bound<K: Storable>(bound: Bound<&K>) -> Bound<Vec<u8>> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.to_bytes().to_vec()),
+        Bound::Excluded(key) => Bound::Excluded(key.to_bytes().to_vec()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+// Returns the smallest byte string that is greater than all the byte strings starting with
+// `prefix`, or `None` if there is no such string (i.e. all the bytes of `prefix` are 0xFF).
+fn prefix_upper_bound(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut upper_bound = prefix.to_vec();
+    while let Some(last) = upper_bound.pop() {
+        if last < u8::MAX {
+            upper_bound.push(last + 1);
+            return Some(upper_bound);
+        }
+    }
+    None
+}
+
 /// An error returned when inserting entries into the map.
 #[derive(Debug, PartialEq)]
 pub enum InsertError {
@@ -1739,9 +1865,14 @@ mod test {
         let btree = StableBTreeMap::<_, Vec<u8>, Vec<u8>>::new(mem, 5, 5);
 
         // Test prefixes that don't exist in the map.
-        assert_eq!(btree.range(vec![0], None).collect::<Vec<_>>(), vec![]);
         assert_eq!(
-            btree.range(vec![1, 2, 3, 4], None).collect::<Vec<_>>(),
+            btree.range_with_prefix(vec![0], None).collect::<Vec<_>>(),
+            vec![]
+        );
+        assert_eq!(
+            btree
+                .range_with_prefix(vec![1, 2, 3, 4], None)
+                .collect::<Vec<_>>(),
             vec![]
         );
     }
@@ -1755,7 +1886,10 @@ mod test {
         btree.insert(vec![0], vec![]).unwrap();
 
         // Test a prefix that's larger than the value in the leaf node. Should be empty.
-        assert_eq!(btree.range(vec![1], None).collect::<Vec<_>>(), vec![]);
+        assert_eq!(
+            btree.range_with_prefix(vec![1], None).collect::<Vec<_>>(),
+            vec![]
+        );
     }
 
     // Tests the case where the prefix is larger than all the entries in an internal node.
@@ -1775,7 +1909,7 @@ mod test {
 
         // Test a prefix that's larger than the value in the internal node.
         assert_eq!(
-            btree.range(vec![7], None).collect::<Vec<_>>(),
+            btree.range_with_prefix(vec![7], None).collect::<Vec<_>>(),
             vec![(vec![7], vec![])]
         );
     }
@@ -1810,7 +1944,7 @@ mod test {
 
         // Tests a prefix that's smaller than the value in the internal node.
         assert_eq!(
-            btree.range(vec![0], None).collect::<Vec<_>>(),
+            btree.range_with_prefix(vec![0], None).collect::<Vec<_>>(),
             vec![
                 (vec![0, 1], vec![]),
                 (vec![0, 2], vec![]),
@@ -1821,7 +1955,7 @@ mod test {
 
         // Tests a prefix that crosses several nodes.
         assert_eq!(
-            btree.range(vec![1], None).collect::<Vec<_>>(),
+            btree.range_with_prefix(vec![1], None).collect::<Vec<_>>(),
             vec![
                 (vec![1, 1], vec![]),
                 (vec![1, 2], vec![]),
@@ -1832,7 +1966,7 @@ mod test {
 
         // Tests a prefix that's larger than the value in the internal node.
         assert_eq!(
-            btree.range(vec![2], None).collect::<Vec<_>>(),
+            btree.range_with_prefix(vec![2], None).collect::<Vec<_>>(),
             vec![
                 (vec![2, 1], vec![]),
                 (vec![2, 2], vec![]),
@@ -1920,11 +2054,16 @@ mod test {
         );
 
         // Tests a prefix that doesn't exist, but is in the middle of the root node.
-        assert_eq!(btree.range(vec![1, 5], None).collect::<Vec<_>>(), vec![]);
+        assert_eq!(
+            btree
+                .range_with_prefix(vec![1, 5], None)
+                .collect::<Vec<_>>(),
+            vec![]
+        );
 
         // Tests a prefix that crosses several nodes.
         assert_eq!(
-            btree.range(vec![1], None).collect::<Vec<_>>(),
+            btree.range_with_prefix(vec![1], None).collect::<Vec<_>>(),
             vec![
                 (vec![1, 2], vec![]),
                 (vec![1, 4], vec![]),
@@ -1937,7 +2076,7 @@ mod test {
         // Tests a prefix that starts from a leaf node, then iterates through the root and right
         // sibling.
         assert_eq!(
-            btree.range(vec![2], None).collect::<Vec<_>>(),
+            btree.range_with_prefix(vec![2], None).collect::<Vec<_>>(),
             vec![
                 (vec![2, 1], vec![]),
                 (vec![2, 2], vec![]),
@@ -1979,7 +2118,7 @@ mod test {
         // Getting the range with a prefix should return all 1000 elements with that prefix.
         for prefix in 0..=1 {
             let mut i: u32 = 0;
-            for (key, _) in btree.range(vec![prefix], None) {
+            for (key, _) in btree.range_with_prefix(vec![prefix], None) {
                 assert_eq!(
                     key,
                     vec![vec![prefix], i.to_be_bytes().to_vec()]
@@ -2023,7 +2162,9 @@ mod test {
 
         // Tests a offset that's smaller than the value in the internal node.
         assert_eq!(
-            btree.range(vec![0], Some(vec![0])).collect::<Vec<_>>(),
+            btree
+                .range_with_prefix(vec![0], Some(vec![0]))
+                .collect::<Vec<_>>(),
             vec![
                 (vec![0, 1], vec![]),
                 (vec![0, 2], vec![]),
@@ -2034,13 +2175,17 @@ mod test {
 
         // Tests a offset that has a value somewhere in the range of values of an internal node.
         assert_eq!(
-            btree.range(vec![1], Some(vec![3])).collect::<Vec<_>>(),
+            btree
+                .range_with_prefix(vec![1], Some(vec![3]))
+                .collect::<Vec<_>>(),
             vec![(vec![1, 3], vec![]), (vec![1, 4], vec![]),]
         );
 
         // Tests a offset that's larger than the value in the internal node.
         assert_eq!(
-            btree.range(vec![2], Some(vec![5])).collect::<Vec<_>>(),
+            btree
+                .range_with_prefix(vec![2], Some(vec![5]))
+                .collect::<Vec<_>>(),
             vec![],
         );
     }
@@ -2124,7 +2269,9 @@ mod test {
 
         // Tests a offset that crosses several nodes.
         assert_eq!(
-            btree.range(vec![1], Some(vec![4])).collect::<Vec<_>>(),
+            btree
+                .range_with_prefix(vec![1], Some(vec![4]))
+                .collect::<Vec<_>>(),
             vec![
                 (vec![1, 4], vec![]),
                 (vec![1, 6], vec![]),
@@ -2136,7 +2283,9 @@ mod test {
         // Tests a offset that starts from a leaf node, then iterates through the root and right
         // sibling.
         assert_eq!(
-            btree.range(vec![2], Some(vec![2])).collect::<Vec<_>>(),
+            btree
+                .range_with_prefix(vec![2], Some(vec![2]))
+                .collect::<Vec<_>>(),
             vec![
                 (vec![2, 2], vec![]),
                 (vec![2, 3], vec![]),
@@ -2149,4 +2298,299 @@ mod test {
             ]
         );
     }
+
+    // A helper method to create a value of the given size whose contents depend on `seed`.
+    fn large_value(seed: u64, size: usize) -> Vec<u8> {
+        (0..size).map(|i| (seed as usize + i) as u8).collect()
+    }
+
+    #[test]
+    fn overflow_insert_get_remove() {
+        let mem = make_memory();
+        let mut btree = StableBTreeMap::new_with_overflow(mem.clone(), 8, 8);
+
+        // Insert values of various sizes, most of which don't fit into a node.
+        for i in 0..500u64 {
+            let size = ((i * 37) % 3000) as usize;
+            assert_eq!(btree.insert(i, large_value(i, size)), Ok(None));
+        }
+
+        for i in 0..500u64 {
+            let size = ((i * 37) % 3000) as usize;
+            assert_eq!(btree.get(&i), Some(large_value(i, size)));
+        }
+
+        // Reload the map and remove the entries.
+        let mut btree: StableBTreeMap<_, u64, Vec<u8>> = StableBTreeMap::load(mem);
+        assert_eq!(btree.len(), 500);
+        for i in 0..500u64 {
+            let size = ((i * 37) % 3000) as usize;
+            assert_eq!(btree.remove(&i), Some(large_value(i, size)));
+            assert_eq!(btree.get(&i), None);
+        }
+
+        // All the nodes and overflow pages have been deallocated.
+        assert!(btree.is_empty());
+        assert_eq!(btree.allocator.num_allocated_chunks(), 0);
+    }
+
+    #[test]
+    fn overflow_overwrite_value() {
+        let mem = make_memory();
+        let mut btree = StableBTreeMap::new_with_overflow(mem, 8, 8);
+
+        for i in 0..100u64 {
+            btree.insert(i, vec![]).unwrap();
+        }
+        let num_chunks = btree.allocator.num_allocated_chunks();
+
+        // Replace small values with large ones, and then with small ones again.
+        for i in 0..100u64 {
+            assert_eq!(btree.insert(i, large_value(i, 1000)), Ok(Some(vec![])));
+        }
+        for i in 0..100u64 {
+            assert_eq!(
+                btree.insert(i, vec![i as u8]),
+                Ok(Some(large_value(i, 1000)))
+            );
+        }
+
+        // Overflow pages of the replaced values have been deallocated.
+        assert_eq!(btree.allocator.num_allocated_chunks(), num_chunks);
+        for i in 0..100u64 {
+            assert_eq!(btree.get(&i), Some(vec![i as u8]));
+        }
+    }
+
+    #[test]
+    fn overflow_keys() {
+        let mem = make_memory();
+        let mut btree = StableBTreeMap::new_with_overflow(mem, 8, 8);
+
+        // Keys share a long prefix, so their order is determined by the last bytes.
+        let key = |i: u8| {
+            let mut key = vec![0; 500];
+            key.push(i);
+            key
+        };
+
+        for i in (0..50u8).rev() {
+            assert_eq!(btree.insert(key(i), vec![i]), Ok(None));
+        }
+
+        for i in 0..50u8 {
+            assert_eq!(btree.get(&key(i)), Some(vec![i]));
+        }
+
+        assert_eq!(
+            btree.iter().collect::<Vec<_>>(),
+            (0..50u8).map(|i| (key(i), vec![i])).collect::<Vec<_>>()
+        );
+        assert_eq!(btree.range_with_prefix(vec![0; 500], None).count(), 50);
+
+        for i in 0..50u8 {
+            assert_eq!(btree.remove(&key(i)), Some(vec![i]));
+        }
+        assert_eq!(btree.allocator.num_allocated_chunks(), 0);
+    }
+
+    #[test]
+    fn overflow_requires_slots_to_fit_an_address() {
+        let mem = make_memory();
+        let mut btree = StableBTreeMap::new_with_overflow(mem, 4, 8);
+
+        assert_eq!(
+            btree.insert(vec![1, 2, 3, 4, 5], vec![]),
+            Err(InsertError::KeyTooLarge { given: 5, max: 4 })
+        );
+        assert_eq!(btree.insert(vec![1, 2, 3, 4], vec![0; 100]), Ok(None));
+    }
+
+    #[test]
+    fn bounded_map_rejects_large_values() {
+        let mem = make_memory();
+        let mut btree = StableBTreeMap::new(mem, 8, 8);
+
+        assert_eq!(
+            btree.insert(1u64, vec![0; 9]),
+            Err(InsertError::ValueTooLarge { given: 9, max: 8 })
+        );
+    }
+
+    #[test]
+    fn migrate_to_overflow() {
+        let mem = make_memory();
+        let mut btree = StableBTreeMap::new(mem.clone(), 8, 8);
+        for i in 0..100u64 {
+            btree.insert(i, vec![i as u8; 8]).unwrap();
+        }
+
+        // Loading a map with bounded values doesn't migrate it.
+        let mut btree: StableBTreeMap<_, u64, Vec<u8>> = StableBTreeMap::init(mem.clone(), 8, 8);
+        assert_eq!(btree.version, LAYOUT_VERSION_1);
+        assert!(btree.insert(0, vec![0; 9]).is_err());
+
+        // The existing data is preserved after the migration.
+        let mut btree: StableBTreeMap<_, u64, Vec<u8>> =
+            StableBTreeMap::init_with_overflow(mem.clone(), 8, 8);
+        assert_eq!(btree.version, LAYOUT_VERSION_2);
+        assert_eq!(btree.len(), 100);
+        for i in 0..100u64 {
+            assert_eq!(btree.get(&i), Some(vec![i as u8; 8]));
+        }
+
+        // Large values can be inserted after the migration.
+        for i in 50..150u64 {
+            btree.insert(i, large_value(i, 2000)).unwrap();
+        }
+
+        // The migration is persisted.
+        let mut btree: StableBTreeMap<_, u64, Vec<u8>> = StableBTreeMap::init(mem, 8, 8);
+        assert_eq!(btree.version, LAYOUT_VERSION_2);
+        for i in 0..150u64 {
+            let expected = if i < 50 {
+                vec![i as u8; 8]
+            } else {
+                large_value(i, 2000)
+            };
+            assert_eq!(btree.remove(&i), Some(expected));
+        }
+        assert_eq!(btree.allocator.num_allocated_chunks(), 0);
+    }
+
+    #[test]
+    fn range_bounds() {
+        let mem = make_memory();
+        let mut btree = StableBTreeMap::new(mem, 1, 1);
+
+        // Insert the even numbers in [0, 100).
+        for i in (0..100u8).step_by(2) {
+            btree.insert(vec![i], vec![i]).unwrap();
+        }
+
+        let keys = |iter: Iter<_, Vec<u8>, Vec<u8>>| iter.map(|(k, _)| k[0]).collect::<Vec<_>>();
+
+        assert_eq!(
+            keys(btree.range(vec![10]..vec![20])),
+            vec![10, 12, 14, 16, 18]
+        );
+        assert_eq!(keys(btree.range(vec![11]..vec![20])), vec![12, 14, 16, 18]);
+        assert_eq!(
+            keys(btree.range(vec![10]..=vec![20])),
+            vec![10, 12, 14, 16, 18, 20]
+        );
+        assert_eq!(
+            keys(btree.range((Bound::Excluded(vec![10]), Bound::Included(vec![20])))),
+            vec![12, 14, 16, 18, 20]
+        );
+        assert_eq!(keys(btree.range(..vec![6])), vec![0, 2, 4]);
+        assert_eq!(keys(btree.range(vec![94]..)), vec![94, 96, 98]);
+        assert_eq!(keys(btree.range(..)).len(), 50);
+        assert_eq!(keys(btree.range(vec![20]..vec![20])), Vec::<u8>::new());
+        assert_eq!(keys(btree.range(vec![21]..vec![22])), Vec::<u8>::new());
+        assert_eq!(keys(btree.range(vec![200]..)), Vec::<u8>::new());
+
+        // Ranges can also be iterated on in reverse.
+        assert_eq!(
+            keys(btree.range(vec![9]..=vec![20]))
+                .into_iter()
+                .rev()
+                .collect::<Vec<_>>(),
+            btree
+                .range(vec![9]..=vec![20])
+                .rev()
+                .map(|(k, _)| k[0])
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            btree
+                .range((Bound::Excluded(vec![10]), Bound::Excluded(vec![20])))
+                .rev()
+                .map(|(k, _)| k[0])
+                .collect::<Vec<_>>(),
+            vec![18, 16, 14, 12]
+        );
+        assert_eq!(
+            btree
+                .range(..vec![7])
+                .rev()
+                .map(|(k, _)| k[0])
+                .collect::<Vec<_>>(),
+            vec![6, 4, 2, 0]
+        );
+    }
+
+    #[test]
+    fn range_matches_std_btreemap() {
+        let mem = make_memory();
+        let mut btree = StableBTreeMap::new(mem, 2, 0);
+        let mut std_btree = std::collections::BTreeMap::new();
+
+        for i in 0..300u16 {
+            let key = ((i * 7) % 300).to_be_bytes().to_vec();
+            btree.insert(key.clone(), vec![]).unwrap();
+            std_btree.insert(key, ());
+        }
+
+        for (start, end) in [(0u16, 300u16), (13, 14), (13, 13), (17, 250), (299, 300)] {
+            let start = start.to_be_bytes().to_vec();
+            let end = end.to_be_bytes().to_vec();
+
+            let expected: Vec<_> = std_btree
+                .range(start.clone()..end.clone())
+                .map(|(k, _)| k.clone())
+                .collect();
+            let actual: Vec<_> = btree
+                .range(start.clone()..end.clone())
+                .map(|(k, _)| k)
+                .collect();
+            assert_eq!(actual, expected);
+
+            let expected: Vec<_> = std_btree
+                .range(start.clone()..=end.clone())
+                .rev()
+                .map(|(k, _)| k.clone())
+                .collect();
+            let actual: Vec<_> = btree.range(start..=end).rev().map(|(k, _)| k).collect();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn first_and_last_key_value() {
+        let mem = make_memory();
+        let mut btree = StableBTreeMap::new(mem, 1, 1);
+
+        assert_eq!(btree.first_key_value(), None);
+        assert_eq!(btree.last_key_value(), None);
+
+        for i in (0..100).rev() {
+            btree.insert(vec![i], vec![i + 1]).unwrap();
+        }
+
+        assert_eq!(btree.first_key_value(), Some((vec![0], vec![1])));
+        assert_eq!(btree.last_key_value(), Some((vec![99], vec![100])));
+    }
+
+    #[test]
+    fn pop_first_and_last() {
+        let mem = make_memory();
+        let mut btree = StableBTreeMap::new(mem, 1, 1);
+
+        assert_eq!(btree.pop_first(), None);
+        assert_eq!(btree.pop_last(), None);
+
+        for i in 0..100 {
+            btree.insert(vec![i], vec![i + 1]).unwrap();
+        }
+
+        for i in 0..50 {
+            assert_eq!(btree.pop_first(), Some((vec![i], vec![i + 1])));
+            assert_eq!(btree.pop_last(), Some((vec![99 - i], vec![100 - i])));
+        }
+
+        assert!(btree.is_empty());
+        assert_eq!(btree.pop_first(), None);
+        assert_eq!(btree.allocator.num_allocated_chunks(), 0);
+    }
 }
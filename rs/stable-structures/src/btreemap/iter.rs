@@ -3,6 +3,7 @@ use super::{
     StableBTreeMap,
 };
 use crate::{types::NULL, Address, Memory, Storable};
+use std::ops::Bound;
 
 /// An indicator of the current position in the map.
 pub(crate) enum Cursor {
@@ -17,68 +18,182 @@ pub(crate) enum Index {
 }
 
 /// An iterator over the entries of a [`StableBTreeMap`].
+///
+/// The iterator can be consumed from both ends. Keys are compared using their byte
+/// representation, which is the order in which the map stores them.
 #[must_use = "iterators are lazy and do nothing unless consumed"]
 pub struct Iter<'a, M: Memory, K: Storable, V: Storable> {
     // A reference to the map being iterated on.
     map: &'a StableBTreeMap<M, K, V>,
 
-    // A stack of cursors indicating the current position in the tree.
-    cursors: Vec<Cursor>,
+    // Flags indicating whether the cursors have been initialized.
+    forward_cursors_initialized: bool,
+    backward_cursors_initialized: bool,
 
-    // An optional prefix that the keys of all the entries returned must have.
-    // Iteration stops as soon as it runs into a key that doesn't have this prefix.
-    prefix: Option<Vec<u8>>,
+    // Stacks of cursors indicating the current iteration positions in the tree,
+    // when iterating forward and backward respectively.
+    forward_cursors: Vec<Cursor>,
+    backward_cursors: Vec<Cursor>,
 
-    // An optional offset to begin iterating from in the keys with the same prefix.
-    // Used only in the case that prefix is also set.
-    offset: Option<Vec<u8>>,
+    // The range of keys that remain to be iterated on. The bounds are updated as entries
+    // are returned from either end, so that both ends never return the same entry.
+    start: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
 }
 
-impl<'a, M: Memory, K: Storable, V: Storable> Iter<'a, M, K, V> {
+impl<'a, M: Memory + Clone, K: Storable, V: Storable> Iter<'a, M, K, V> {
+    /// Returns an iterator over all the entries of the map.
     pub(crate) fn new(map: &'a StableBTreeMap<M, K, V>) -> Self {
+        Self::new_in_range(map, Bound::Unbounded, Bound::Unbounded)
+    }
+
+    /// Returns an iterator over the entries of the map whose keys are within the given bounds.
+    pub(crate) fn new_in_range(
+        map: &'a StableBTreeMap<M, K, V>,
+        start: Bound<Vec<u8>>,
+        end: Bound<Vec<u8>>,
+    ) -> Self {
         Self {
             map,
-            // Initialize the cursors with the address of the root of the map.
-            cursors: vec![Cursor::Address(map.root_addr)],
-            prefix: None,
-            offset: None,
+            forward_cursors_initialized: false,
+            backward_cursors_initialized: false,
+            forward_cursors: vec![],
+            backward_cursors: vec![],
+            start,
+            end,
         }
     }
 
-    /// Returns an empty iterator.
-    pub(crate) fn null(map: &'a StableBTreeMap<M, K, V>) -> Self {
-        Self {
-            map,
-            cursors: vec![],
-            prefix: None,
-            offset: None,
+    // Initializes the forward cursors to point to the first entry within the range.
+    fn initialize_forward_cursors(&mut self) {
+        debug_assert!(self.forward_cursors.is_empty());
+        self.forward_cursors_initialized = true;
+
+        if self.map.root_addr == NULL {
+            // Map is empty.
+            return;
+        }
+
+        let mut node = self.map.load_node(self.map.root_addr);
+        loop {
+            // Find the index of the first entry in the node that could be within the range.
+            let idx = match &self.start {
+                Bound::Unbounded => 0,
+                Bound::Included(key) | Bound::Excluded(key) => {
+                    match node.entries.binary_search_by(|e| e.0.cmp(key)) {
+                        Ok(idx) => {
+                            if let Bound::Included(_) = self.start {
+                                // The key is in the node. It's the first entry to return.
+                                self.forward_cursors.push(Cursor::Node {
+                                    node,
+                                    next: Index::Entry(idx),
+                                });
+                            } else {
+                                // The key is excluded. Iteration starts right after it, which
+                                // (for internal nodes) is in the child to the right of the key.
+                                self.forward_cursors.push(Cursor::Node {
+                                    next: match node.node_type {
+                                        NodeType::Internal => Index::Child(idx + 1),
+                                        NodeType::Leaf => Index::Entry(idx + 1),
+                                    },
+                                    node,
+                                });
+                            }
+                            return;
+                        }
+                        Err(idx) => idx,
+                    }
+                }
+            };
+
+            // All the entries that are >= `idx` are within the start bound. Iterate on
+            // them after all the relevant entries of the child at `idx` (if any).
+            let child = match node.node_type {
+                // Note that loading a child node cannot fail since
+                // len(children) = len(entries) + 1
+                NodeType::Internal => Some(self.map.load_node(node.children[idx])),
+                NodeType::Leaf => None,
+            };
+
+            self.forward_cursors.push(Cursor::Node {
+                node,
+                next: Index::Entry(idx),
+            });
+
+            match child {
+                None => return,
+                Some(child) => node = child,
+            }
         }
     }
 
-    pub(crate) fn new_with_prefix(
-        map: &'a StableBTreeMap<M, K, V>,
-        prefix: Vec<u8>,
-        cursors: Vec<Cursor>,
-    ) -> Self {
-        Self {
-            map,
-            cursors,
-            prefix: Some(prefix),
-            offset: None,
+    // Initializes the backward cursors to point to the last entry within the range.
+    fn initialize_backward_cursors(&mut self) {
+        debug_assert!(self.backward_cursors.is_empty());
+        self.backward_cursors_initialized = true;
+
+        if self.map.root_addr == NULL {
+            // Map is empty.
+            return;
+        }
+
+        let mut node = self.map.load_node(self.map.root_addr);
+        loop {
+            // Find the number of entries in the node that could be within the range.
+            let idx = match &self.end {
+                Bound::Unbounded => node.entries.len(),
+                Bound::Included(key) | Bound::Excluded(key) => {
+                    match node.entries.binary_search_by(|e| e.0.cmp(key)) {
+                        Ok(idx) => {
+                            if let Bound::Included(_) = self.end {
+                                // The key is in the node. It's the first entry to return.
+                                self.backward_cursors.push(Cursor::Node {
+                                    node,
+                                    next: Index::Entry(idx),
+                                });
+                            } else {
+                                match node.node_type {
+                                    // The key is excluded. Iteration starts right before it,
+                                    // which is in the child to the left of the key.
+                                    NodeType::Internal => {
+                                        self.backward_cursors.push(Cursor::Node {
+                                            node,
+                                            next: Index::Child(idx),
+                                        })
+                                    }
+                                    NodeType::Leaf => self.push_backward_entry(node, idx),
+                                }
+                            }
+                            return;
+                        }
+                        Err(idx) => idx,
+                    }
+                }
+            };
+
+            // All the entries that are < `idx` are within the end bound. Iterate on
+            // them after all the relevant entries of the child at `idx` (if any).
+            let child = match node.node_type {
+                NodeType::Internal => Some(self.map.load_node(node.children[idx])),
+                NodeType::Leaf => None,
+            };
+
+            self.push_backward_entry(node, idx);
+
+            match child {
+                None => return,
+                Some(child) => node = child,
+            }
         }
     }
 
-    pub(crate) fn new_with_prefix_and_offset(
-        map: &'a StableBTreeMap<M, K, V>,
-        prefix: Vec<u8>,
-        offset: Vec<u8>,
-        cursors: Vec<Cursor>,
-    ) -> Self {
-        Self {
-            map,
-            cursors,
-            prefix: Some(prefix),
-            offset: Some(offset),
+    // Adds a backward cursor to the entry that precedes the entry at `idx`, if any.
+    fn push_backward_entry(&mut self, node: Node, idx: usize) {
+        if idx > 0 {
+            self.backward_cursors.push(Cursor::Node {
+                node,
+                next: Index::Entry(idx - 1),
+            });
         }
     }
 }
@@ -87,12 +202,16 @@ impl<M: Memory + Clone, K: Storable, V: Storable> Iterator for Iter<'_, M, K, V>
     type Item = (K, V);
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.cursors.pop() {
+        if !self.forward_cursors_initialized {
+            self.initialize_forward_cursors();
+        }
+
+        match self.forward_cursors.pop() {
             Some(Cursor::Address(address)) => {
                 if address != NULL {
                     // Load the node at the given address, and add it to the cursors.
                     let node = self.map.load_node(address);
-                    self.cursors.push(Cursor::Node {
+                    self.forward_cursors.push(Cursor::Node {
                         next: match node.node_type {
                             // Iterate on internal nodes starting from the first child.
                             NodeType::Internal => Index::Child(0),
@@ -116,13 +235,13 @@ impl<M: Memory + Clone, K: Storable, V: Storable> Iterator for Iter<'_, M, K, V>
 
                 // After iterating on the child, iterate on the next _entry_ in this node.
                 // The entry immediately after the child has the same index as the child's.
-                self.cursors.push(Cursor::Node {
+                self.forward_cursors.push(Cursor::Node {
                     node,
                     next: Index::Entry(child_idx),
                 });
 
                 // Add the child to the top of the cursors to be iterated on first.
-                self.cursors.push(Cursor::Address(child_address));
+                self.forward_cursors.push(Cursor::Address(child_address));
 
                 self.next()
             }
@@ -141,7 +260,7 @@ impl<M: Memory + Clone, K: Storable, V: Storable> Iterator for Iter<'_, M, K, V>
                 let entry = node.swap_entry(entry_idx, (vec![], vec![]));
 
                 // Add to the cursors the next element to be traversed.
-                self.cursors.push(Cursor::Node {
+                self.forward_cursors.push(Cursor::Node {
                     next: match node.node_type {
                         // If this is an internal node, add the next child to the cursors.
                         NodeType::Internal => Index::Child(entry_idx + 1),
@@ -151,23 +270,110 @@ impl<M: Memory + Clone, K: Storable, V: Storable> Iterator for Iter<'_, M, K, V>
                     node,
                 });
 
-                // If there's a prefix, verify that the key has that given prefix.
-                // Otherwise iteration is stopped.
-                if let Some(prefix) = &self.prefix {
-                    if !entry.0.starts_with(prefix) {
-                        // Clear all cursors to avoid needless work in subsequent calls.
-                        self.cursors = vec![];
-                        return None;
-                    } else if let Some(offset) = &self.offset {
-                        let mut prefix_with_offset = prefix.clone();
-                        prefix_with_offset.extend_from_slice(offset);
-                        // Clear all cursors to avoid needless work in subsequent calls.
-                        if entry.0 < prefix_with_offset {
-                            self.cursors = vec![];
-                            return None;
+                // Verify that the key is within the range. Otherwise iteration is stopped.
+                let within_range = match &self.end {
+                    Bound::Unbounded => true,
+                    Bound::Included(end) => entry.0 <= *end,
+                    Bound::Excluded(end) => entry.0 < *end,
+                };
+                if !within_range {
+                    // Clear all cursors to avoid needless work in subsequent calls.
+                    self.forward_cursors = vec![];
+                    return None;
+                }
+
+                // Entries up to and including this one are no longer in the range.
+                self.start = Bound::Excluded(entry.0.clone());
+
+                Some((K::from_bytes(entry.0), V::from_bytes(entry.1)))
+            }
+            None => {
+                // The cursors are empty. Iteration is complete.
+                None
+            }
+        }
+    }
+}
+
+impl<M: Memory + Clone, K: Storable, V: Storable> DoubleEndedIterator for Iter<'_, M, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if !self.backward_cursors_initialized {
+            self.initialize_backward_cursors();
+        }
+
+        match self.backward_cursors.pop() {
+            Some(Cursor::Address(address)) => {
+                if address != NULL {
+                    // Load the node at the given address, and add it to the cursors.
+                    let node = self.map.load_node(address);
+                    match node.node_type {
+                        // Iterate on internal nodes starting from the last child.
+                        NodeType::Internal => self.backward_cursors.push(Cursor::Node {
+                            next: Index::Child(node.children.len() - 1),
+                            node,
+                        }),
+                        // Iterate on leaf nodes starting from the last entry.
+                        NodeType::Leaf => {
+                            let num_entries = node.entries.len();
+                            self.push_backward_entry(node, num_entries);
                         }
                     }
                 }
+                self.next_back()
+            }
+
+            Some(Cursor::Node {
+                node,
+                next: Index::Child(child_idx),
+            }) => {
+                let child_address = *node
+                    .children
+                    .get(child_idx)
+                    .expect("Iterating over children went out of bounds.");
+
+                // After iterating on the child, iterate on the previous _entry_ in this node.
+                // The entry immediately before the child has the index of the child minus one.
+                self.push_backward_entry(node, child_idx);
+
+                // Add the child to the top of the cursors to be iterated on first.
+                self.backward_cursors.push(Cursor::Address(child_address));
+
+                self.next_back()
+            }
+
+            Some(Cursor::Node {
+                mut node,
+                next: Index::Entry(entry_idx),
+            }) => {
+                // Take the entry from the node. It's swapped with an empty element to
+                // avoid cloning.
+                let entry = node.swap_entry(entry_idx, (vec![], vec![]));
+
+                // Add to the cursors the previous element to be traversed.
+                match node.node_type {
+                    // If this is an internal node, add the previous child to the cursors.
+                    NodeType::Internal => self.backward_cursors.push(Cursor::Node {
+                        node,
+                        next: Index::Child(entry_idx),
+                    }),
+                    // If this is a leaf node, add the previous entry to the cursors.
+                    NodeType::Leaf => self.push_backward_entry(node, entry_idx),
+                }
+
+                // Verify that the key is within the range. Otherwise iteration is stopped.
+                let within_range = match &self.start {
+                    Bound::Unbounded => true,
+                    Bound::Included(start) => entry.0 >= *start,
+                    Bound::Excluded(start) => entry.0 > *start,
+                };
+                if !within_range {
+                    // Clear all cursors to avoid needless work in subsequent calls.
+                    self.backward_cursors = vec![];
+                    return None;
+                }
+
+                // Entries from this one onwards are no longer in the range.
+                self.end = Bound::Excluded(entry.0.clone());
 
                 Some((K::from_bytes(entry.0), V::from_bytes(entry.1)))
             }
@@ -229,4 +435,51 @@ mod test {
 
         assert_eq!(i, 100);
     }
+
+    #[test]
+    fn iterate_reverse() {
+        let mem = make_memory();
+        let mut btree = StableBTreeMap::new(mem, 1, 1);
+
+        for i in 0..100 {
+            btree.insert(vec![i], vec![i + 1]).unwrap();
+        }
+
+        // Iteration should be in descending order.
+        let mut i = 100;
+        for (key, value) in btree.iter().rev() {
+            i -= 1;
+            assert_eq!(key, vec![i]);
+            assert_eq!(value, vec![i + 1]);
+        }
+
+        assert_eq!(i, 0);
+    }
+
+    #[test]
+    fn iterate_from_both_ends() {
+        let mem = make_memory();
+        let mut btree = StableBTreeMap::new(mem, 1, 1);
+
+        for i in 0..100 {
+            btree.insert(vec![i], vec![]).unwrap();
+        }
+
+        // Alternate between both ends. Every entry is returned exactly once.
+        let mut iter = btree.iter();
+        let mut front = vec![];
+        let mut back = vec![];
+        while let Some((key, _)) = iter.next() {
+            front.push(key[0]);
+            match iter.next_back() {
+                Some((key, _)) => back.push(key[0]),
+                None => break,
+            }
+        }
+
+        assert_eq!(front, (0..50).collect::<Vec<_>>());
+        assert_eq!(back, (50..100).rev().collect::<Vec<_>>());
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+    }
 }
@@ -1,7 +1,11 @@
+use super::{
+    allocator::Allocator,
+    overflow::{free_overflow, read_overflow, write_overflow},
+};
 use crate::{
     read_struct, read_u32, read_u64,
     types::{Address, Bytes},
-    write, write_struct, write_u32, Memory,
+    write, write_struct, write_u32, write_u64, Memory,
 };
 
 /// The minimum degree to use in the btree.
@@ -9,12 +13,17 @@ use crate::{
 pub const B: u64 = 6;
 /// The maximum number of entries per node.
 pub const CAPACITY: u64 = 2 * B - 1;
-const LAYOUT_VERSION: u8 = 1;
+// The layout of nodes in which every key and value is stored inline.
+const LAYOUT_VERSION_1: u8 = 1;
+// The layout of nodes in which keys and values can spill into overflow pages.
+const LAYOUT_VERSION_2: u8 = 2;
 const MAGIC: &[u8; 3] = b"BTN";
 const LEAF_NODE_TYPE: u8 = 0;
 const INTERNAL_NODE_TYPE: u8 = 1;
 // The size of u32 in bytes.
 const U32_SIZE: Bytes = Bytes::new(4);
+// The size of the address of an overflow page stored in a slot, in bytes.
+const OVERFLOW_ADDRESS_SIZE: u32 = 8;
 
 // Entries in the node are key-value pairs and both are blobs.
 pub type Entry = (Vec<u8>, Vec<u8>);
@@ -38,6 +47,14 @@ pub enum NodeType {
 ///     - value (`max_value_size` bytes)
 ///
 /// Each node can contain up to `CAPACITY + 1` children, each child is 8 bytes.
+///
+/// # Overflow
+///
+/// In nodes with layout version 2, a key (resp. value) whose size is larger than
+/// `max_key_size` (resp. `max_value_size`) is stored in overflow pages (see
+/// [`super::overflow`]), and its slot stores the 8-byte address of the first overflow
+/// page instead. Such nodes are saved with [`Node::save_with_overflow`]. Nodes with
+/// layout version 1 never contain overflow pages, so they are also valid version 2 nodes.
 #[derive(Debug, PartialEq)]
 pub struct Node {
     pub address: Address,
@@ -59,7 +76,13 @@ impl Node {
         // Load the header.
         let header: NodeHeader = read_struct(address, memory);
         assert_eq!(&header.magic, MAGIC, "Bad magic.");
-        assert_eq!(header.version, LAYOUT_VERSION, "Unsupported version.");
+        assert!(
+            header.version == LAYOUT_VERSION_1 || header.version == LAYOUT_VERSION_2,
+            "Unsupported version."
+        );
+
+        // Overflow pages are allocated by the same allocator as nodes, so they have the same size.
+        let page_size = Self::size(max_key_size, max_value_size);
 
         // Load the entries.
         let mut entries = vec![];
@@ -70,8 +93,7 @@ impl Node {
             offset += U32_SIZE;
 
             // Read the key.
-            let mut key = vec![0; key_size as usize];
-            memory.read((address + offset).get(), &mut key);
+            let key = read_blob(memory, address + offset, key_size, max_key_size, page_size);
             offset += Bytes::from(max_key_size as u64);
 
             // Read the value's size.
@@ -79,8 +101,13 @@ impl Node {
             offset += U32_SIZE;
 
             // Read the value.
-            let mut value = vec![0; value_size as usize];
-            memory.read((address + offset).get(), &mut value);
+            let value = read_blob(
+                memory,
+                address + offset,
+                value_size,
+                max_value_size,
+                page_size,
+            );
             offset += Bytes::from(max_value_size as u64);
 
             entries.push((key, value));
@@ -114,7 +141,77 @@ impl Node {
     }
 
     /// Saves the node to memory.
+    ///
+    /// PRECONDITION: all keys and values fit into their slots.
     pub fn save<M: Memory>(&self, memory: &M) {
+        self.save_impl(memory, None)
+    }
+
+    /// Saves the node to memory, storing keys and values that don't fit into their slots
+    /// in overflow pages.
+    ///
+    /// The overflow pages of the node previously stored at the same address are deallocated.
+    pub fn save_with_overflow<M: Memory>(&self, memory: &M, allocator: &mut Allocator<M>) {
+        Self::free_overflow_pages(
+            self.address,
+            memory,
+            allocator,
+            self.max_key_size,
+            self.max_value_size,
+        );
+        self.save_impl(memory, Some(allocator))
+    }
+
+    /// Deallocates the node at the given address along with its overflow pages.
+    pub fn deallocate_with_overflow<M: Memory>(
+        address: Address,
+        memory: &M,
+        allocator: &mut Allocator<M>,
+        max_key_size: u32,
+        max_value_size: u32,
+    ) {
+        Self::free_overflow_pages(address, memory, allocator, max_key_size, max_value_size);
+
+        // Clear the magic so that the overflow pages aren't freed again if the
+        // chunk is reused for another node.
+        write(memory, address.get(), &[0; 3]);
+
+        allocator.deallocate(address);
+    }
+
+    // Deallocates the overflow pages of the node stored in memory at the given address.
+    //
+    // The node is read directly from memory, as opposed to relying on an in-memory copy, so that
+    // the pages that are freed are exactly the ones that are referenced by the stored node.
+    fn free_overflow_pages<M: Memory>(
+        address: Address,
+        memory: &M,
+        allocator: &mut Allocator<M>,
+        max_key_size: u32,
+        max_value_size: u32,
+    ) {
+        let header: NodeHeader = read_struct(address, memory);
+        if &header.magic != MAGIC || header.version != LAYOUT_VERSION_2 {
+            // No node with overflow pages is stored at this address.
+            return;
+        }
+
+        let mut offset = NodeHeader::size();
+        for _ in 0..header.num_entries {
+            for max_size in [max_key_size, max_value_size] {
+                let size = read_u32(memory, address + offset);
+                offset += U32_SIZE;
+
+                if size > max_size {
+                    let overflow_address = Address::from(read_u64(memory, address + offset));
+                    free_overflow(overflow_address, memory, allocator);
+                }
+                offset += Bytes::from(max_size);
+            }
+        }
+    }
+
+    fn save_impl<M: Memory>(&self, memory: &M, mut allocator: Option<&mut Allocator<M>>) {
         match self.node_type {
             NodeType::Leaf => {
                 assert!(self.children.is_empty());
@@ -132,7 +229,11 @@ impl Node {
 
         let header = NodeHeader {
             magic: *MAGIC,
-            version: LAYOUT_VERSION,
+            // Nodes saved without an allocator cannot contain overflow pages.
+            version: match allocator {
+                Some(_) => LAYOUT_VERSION_2,
+                None => LAYOUT_VERSION_1,
+            },
             node_type: match self.node_type {
                 NodeType::Leaf => LEAF_NODE_TYPE,
                 NodeType::Internal => INTERNAL_NODE_TYPE,
@@ -151,7 +252,13 @@ impl Node {
             offset += U32_SIZE;
 
             // Write the key.
-            write(memory, (self.address + offset).get(), key);
+            write_blob(
+                memory,
+                allocator.as_deref_mut(),
+                self.address + offset,
+                key,
+                self.max_key_size,
+            );
             offset += Bytes::from(self.max_key_size);
 
             // Write the size of the value.
@@ -159,7 +266,13 @@ impl Node {
             offset += U32_SIZE;
 
             // Write the value.
-            write(memory, (self.address + offset).get(), value);
+            write_blob(
+                memory,
+                allocator.as_deref_mut(),
+                self.address + offset,
+                value,
+                self.max_value_size,
+            );
             offset += Bytes::from(self.max_value_size);
         }
 
@@ -255,6 +368,51 @@ impl Node {
     }
 }
 
+/// Returns true if a key or a value whose slot has the given size can be stored
+/// in overflow pages, i.e. if the slot can hold the address of an overflow page.
+pub fn can_overflow(max_size: u32) -> bool {
+    max_size >= OVERFLOW_ADDRESS_SIZE
+}
+
+// Reads a key or a value of the given size from a slot of `max_size` bytes at `address`.
+fn read_blob<M: Memory>(
+    memory: &M,
+    address: Address,
+    size: u32,
+    max_size: u32,
+    page_size: Bytes,
+) -> Vec<u8> {
+    if size > max_size {
+        // The blob didn't fit into the slot, which stores the address of its overflow pages.
+        let overflow_address = Address::from(read_u64(memory, address));
+        return read_overflow(overflow_address, size as usize, page_size, memory);
+    }
+
+    let mut blob = vec![0; size as usize];
+    memory.read(address.get(), &mut blob);
+    blob
+}
+
+// Writes a key or a value into a slot of `max_size` bytes at `address`,
+// spilling into overflow pages if it doesn't fit.
+fn write_blob<M: Memory>(
+    memory: &M,
+    allocator: Option<&mut Allocator<M>>,
+    address: Address,
+    blob: &[u8],
+    max_size: u32,
+) {
+    if blob.len() <= max_size as usize {
+        write(memory, address.get(), blob);
+        return;
+    }
+
+    let allocator = allocator.expect("A blob exceeds the size of its slot.");
+    assert!(can_overflow(max_size));
+    let overflow_address = write_overflow(blob, memory, allocator);
+    write_u64(memory, address, overflow_address.get());
+}
+
 // A transient data structure for reading/writing metadata into/from stable memory.
 #[repr(packed)]
 struct NodeHeader {
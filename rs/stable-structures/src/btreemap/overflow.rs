@@ -0,0 +1,154 @@
+//! Overflow pages for keys and values that don't fit into a node's slot.
+//!
+//! A blob that is too large to be stored inline in a node is stored in a linked list of
+//! overflow pages, and the node's slot stores the address of the first page instead.
+//!
+//! Overflow pages are allocated from the B-tree's [`Allocator`], so each page has the same
+//! size as a node. Every page has the following layout:
+//!
+//! ```text
+//! ---------------------------------------- <- Page address
+//! Magic "BTO"             ↕ 3 bytes
+//! ----------------------------------------
+//! Layout version          ↕ 1 byte
+//! ----------------------------------------
+//! Alignment               ↕ 4 bytes
+//! ----------------------------------------
+//! Next page address       ↕ 8 bytes (NULL for the last page)
+//! ----------------------------------------
+//! Data                    ↕ (page size - 16) bytes
+//! ----------------------------------------
+//! ```
+use super::allocator::Allocator;
+use crate::{
+    read_struct,
+    types::{Address, Bytes, NULL},
+    write, write_struct, Memory,
+};
+
+const LAYOUT_VERSION: u8 = 1;
+const MAGIC: &[u8; 3] = b"BTO"; // btree overflow
+
+#[repr(packed)]
+struct OverflowPageHeader {
+    magic: [u8; 3],
+    version: u8,
+    // Empty space to memory-align the following fields.
+    _alignment: [u8; 4],
+    next: Address,
+}
+
+impl OverflowPageHeader {
+    fn size() -> Bytes {
+        Bytes::from(core::mem::size_of::<Self>() as u64)
+    }
+
+    fn load<M: Memory>(address: Address, memory: &M) -> Self {
+        let header: OverflowPageHeader = read_struct(address, memory);
+        assert_eq!(&header.magic, MAGIC, "Bad magic.");
+        assert_eq!(header.version, LAYOUT_VERSION, "Unsupported version.");
+        header
+    }
+}
+
+// The number of data bytes that fit into an overflow page of the given size.
+fn page_capacity(page_size: Bytes) -> usize {
+    let page_size: usize = page_size.into();
+    let header_size: usize = OverflowPageHeader::size().into();
+    assert!(
+        page_size > header_size,
+        "Overflow pages must be larger than their header."
+    );
+    page_size - header_size
+}
+
+/// Writes the given bytes into newly allocated overflow pages and returns the
+/// address of the first page.
+///
+/// PRECONDITION: `bytes` is not empty.
+pub fn write_overflow<M: Memory>(
+    bytes: &[u8],
+    memory: &M,
+    allocator: &mut Allocator<M>,
+) -> Address {
+    assert!(!bytes.is_empty());
+
+    let chunks: Vec<&[u8]> = bytes
+        .chunks(page_capacity(allocator.allocation_size()))
+        .collect();
+    let pages: Vec<Address> = chunks.iter().map(|_| allocator.allocate()).collect();
+
+    for (i, (page, chunk)) in pages.iter().zip(chunks.iter()).enumerate() {
+        let header = OverflowPageHeader {
+            magic: *MAGIC,
+            version: LAYOUT_VERSION,
+            _alignment: [0; 4],
+            next: pages.get(i + 1).copied().unwrap_or(NULL),
+        };
+        write_struct(&header, *page, memory);
+        write(memory, (*page + OverflowPageHeader::size()).get(), chunk);
+    }
+
+    pages[0]
+}
+
+/// Reads `len` bytes stored in the overflow pages starting at `address`.
+pub fn read_overflow<M: Memory>(
+    address: Address,
+    len: usize,
+    page_size: Bytes,
+    memory: &M,
+) -> Vec<u8> {
+    let capacity = page_capacity(page_size);
+    let mut bytes = vec![0; len];
+    let mut page = address;
+
+    for chunk in bytes.chunks_mut(capacity) {
+        assert!(page != NULL, "Overflow pages ended prematurely.");
+        let header = OverflowPageHeader::load(page, memory);
+        memory.read((page + OverflowPageHeader::size()).get(), chunk);
+        page = header.next;
+    }
+
+    bytes
+}
+
+/// Deallocates all the overflow pages starting at `address`.
+pub fn free_overflow<M: Memory>(address: Address, memory: &M, allocator: &mut Allocator<M>) {
+    let mut page = address;
+    while page != NULL {
+        let header = OverflowPageHeader::load(page, memory);
+        allocator.deallocate(page);
+        page = header.next;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn make_memory() -> Rc<RefCell<Vec<u8>>> {
+        Rc::new(RefCell::new(Vec::new()))
+    }
+
+    #[test]
+    fn write_read_free() {
+        let mem = make_memory();
+        let page_size = Bytes::from(32u64);
+        let mut allocator = Allocator::new(mem.clone(), Address::from(0), page_size);
+
+        // Every page holds 16 bytes of data.
+        for (len, num_pages) in [(1, 1), (15, 1), (16, 1), (17, 2), (100, 7)] {
+            let bytes: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let address = write_overflow(&bytes, &mem, &mut allocator);
+
+            assert_eq!(allocator.num_allocated_chunks(), num_pages);
+            assert_eq!(read_overflow(address, len, page_size, &mem), bytes);
+
+            free_overflow(address, &mem, &mut allocator);
+            assert_eq!(allocator.num_allocated_chunks(), 0);
+        }
+    }
+}
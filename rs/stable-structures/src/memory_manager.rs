@@ -0,0 +1,735 @@
+//! A module for simulating multiple memories within a single memory.
+//!
+//! The typical way for a canister to have multiple stable structures is by dividing the memory
+//! into distinct ranges (e.g. using [`RestrictedMemory`](crate::RestrictedMemory)) and dedicating
+//! each range to a stable structure. This approach has two problems:
+//!
+//! 1. The developer needs to put in advance an upper bound on the memory of each stable structure.
+//! 2. It wastes the canister's memory allocation. For example, if a canister creates two stable
+//!    structures A and B, and gives each one of them a 1GiB region of memory, then writing to B
+//!    will require growing the memory to more than 1GiB, even if A is empty.
+//!
+//! The [`MemoryManager`] solves both of these problems. It simulates having multiple memories,
+//! each being able to grow without bound. The underlying memory is divided into equally-sized
+//! "buckets", and buckets are handed out to the virtual memories on demand as they grow.
+//!
+//! Example usage:
+//!
+//! ```
+//! use stable_structures::memory_manager::{MemoryId, MemoryManager};
+//! use stable_structures::{DefaultMemoryImpl, Memory};
+//!
+//! let mem_mgr = MemoryManager::init(DefaultMemoryImpl::default());
+//!
+//! // Create different memories, each with a unique ID.
+//! let memory_0 = mem_mgr.get(MemoryId::new(0));
+//! let memory_1 = mem_mgr.get(MemoryId::new(1));
+//!
+//! // Each memory can be used independently.
+//! memory_0.grow(1);
+//! memory_0.write(0, &[1, 2, 3]);
+//!
+//! memory_1.grow(1);
+//! memory_1.write(0, &[4, 5, 6]);
+//!
+//! let mut bytes = vec![0; 3];
+//! memory_0.read(0, &mut bytes);
+//! assert_eq!(bytes, vec![1, 2, 3]);
+//!
+//! memory_1.read(0, &mut bytes);
+//! assert_eq!(bytes, vec![4, 5, 6]);
+//! ```
+//!
+//! # V1 layout
+//!
+//! ```text
+//! -------------------------------------------------- <- Address 0
+//! Magic "MGR"                           ↕ 3 bytes
+//! --------------------------------------------------
+//! Layout version                        ↕ 1 byte
+//! --------------------------------------------------
+//! Number of allocated buckets           ↕ 2 bytes
+//! --------------------------------------------------
+//! Bucket size (in pages) = N            ↕ 2 bytes
+//! --------------------------------------------------
+//! Reserved space                        ↕ 32 bytes
+//! --------------------------------------------------
+//! Size of memory 0 (in pages)           ↕ 8 bytes
+//! --------------------------------------------------
+//! Size of memory 1 (in pages)           ↕ 8 bytes
+//! --------------------------------------------------
+//! ...
+//! --------------------------------------------------
+//! Size of memory 254 (in pages)         ↕ 8 bytes
+//! -------------------------------------------------- <- Bucket allocations
+//! Owner of bucket 0                     ↕ 1 byte
+//! --------------------------------------------------
+//! ...
+//! --------------------------------------------------
+//! Owner of bucket MAX_NUM_BUCKETS - 1   ↕ 1 byte
+//! --------------------------------------------------
+//! Unallocated space
+//! -------------------------------------------------- <- Buckets (page 1)
+//! Bucket 0                              ↕ N pages
+//! --------------------------------------------------
+//! Bucket 1                              ↕ N pages
+//! --------------------------------------------------
+//! ...
+//! ```
+use crate::{
+    read_struct,
+    types::{Address, Bytes},
+    write, write_struct, Memory, WASM_PAGE_SIZE,
+};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+const MAGIC: &[u8; 3] = b"MGR"; // short for "memory manager"
+const LAYOUT_VERSION: u8 = 1;
+
+// The maximum number of memories that can be created.
+const MAX_NUM_MEMORIES: u8 = 255;
+
+// The maximum number of buckets the memory manager can handle.
+// With a bucket size of 128 pages this can support up to 256GiB of memory.
+const MAX_NUM_BUCKETS: u64 = 32768;
+
+const BUCKET_SIZE_IN_PAGES: u64 = 128;
+
+// A value used internally to indicate that a bucket is unallocated.
+const UNALLOCATED_BUCKET_MARKER: u8 = MAX_NUM_MEMORIES;
+
+// The offset where buckets are in memory.
+const BUCKETS_OFFSET_IN_PAGES: u64 = 1;
+const BUCKETS_OFFSET_IN_BYTES: u64 = BUCKETS_OFFSET_IN_PAGES * WASM_PAGE_SIZE;
+
+// Reserved bytes in the header for future extensions.
+const HEADER_RESERVED_BYTES: usize = 32;
+
+/// A memory manager simulates multiple memories within a single memory.
+///
+/// The memory manager can return up to 255 unique instances of [`VirtualMemory`], and each can be
+/// used independently and can grow up to the bounds of the underlying memory.
+///
+/// The memory manager divides the memory into "buckets" of 128 pages. Each [`VirtualMemory`] is
+/// internally represented as a list of buckets. Buckets of different memories can be interleaved,
+/// but the virtual memory interface gives the illusion of a continuous address space.
+///
+/// Because a [`VirtualMemory`] is a list of buckets, this implies that internally it grows one
+/// bucket at a time (as opposed to one page at a time).
+///
+/// The first page of the memory is reserved for the memory manager's own state, so the
+/// underlying memory must not be shared with any other data structure.
+pub struct MemoryManager<M: Memory> {
+    inner: Rc<RefCell<MemoryManagerInner<M>>>,
+}
+
+impl<M: Memory> MemoryManager<M> {
+    /// Initializes a `MemoryManager` with the given memory.
+    ///
+    /// If the memory already contains a `MemoryManager`, then that manager is loaded.
+    /// Otherwise, a new `MemoryManager` is created.
+    pub fn init(memory: M) -> Self {
+        Self::init_with_bucket_size(memory, BUCKET_SIZE_IN_PAGES as u16)
+    }
+
+    /// Initializes a `MemoryManager` with the given memory and bucket size in pages.
+    ///
+    /// NOTE: the bucket size is only used when a new manager is created. If the memory already
+    /// contains a `MemoryManager`, the bucket size stored in its header is used instead.
+    pub fn init_with_bucket_size(memory: M, bucket_size_in_pages: u16) -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(MemoryManagerInner::init(
+                memory,
+                bucket_size_in_pages,
+            ))),
+        }
+    }
+
+    /// Returns the memory associated with the given ID.
+    pub fn get(&self, id: MemoryId) -> VirtualMemory<M> {
+        VirtualMemory {
+            id,
+            memory_manager: self.inner.clone(),
+        }
+    }
+}
+
+#[repr(packed)]
+struct Header {
+    magic: [u8; 3],
+    version: u8,
+    // The number of buckets allocated by the memory manager.
+    num_allocated_buckets: u16,
+    // The size of a bucket in Wasm pages.
+    bucket_size_in_pages: u16,
+    // Additional space reserved to add new fields without breaking backward-compatibility.
+    _reserved: [u8; HEADER_RESERVED_BYTES],
+    // The size of each individual memory that can be created by the memory manager.
+    memory_sizes_in_pages: [u64; MAX_NUM_MEMORIES as usize],
+}
+
+impl Header {
+    fn size() -> Bytes {
+        Bytes::from(core::mem::size_of::<Self>() as u64)
+    }
+}
+
+/// A memory simulated by a [`MemoryManager`].
+///
+/// Virtual memories are cheap to clone: all the clones refer to the same memory.
+pub struct VirtualMemory<M: Memory> {
+    id: MemoryId,
+    memory_manager: Rc<RefCell<MemoryManagerInner<M>>>,
+}
+
+impl<M: Memory> Clone for VirtualMemory<M> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            memory_manager: self.memory_manager.clone(),
+        }
+    }
+}
+
+impl<M: Memory> Memory for VirtualMemory<M> {
+    fn size(&self) -> u64 {
+        self.memory_manager.borrow().memory_size(self.id)
+    }
+
+    fn grow(&self, pages: u64) -> i64 {
+        self.memory_manager.borrow_mut().grow(self.id, pages)
+    }
+
+    fn read(&self, offset: u64, dst: &mut [u8]) {
+        self.memory_manager.borrow().read(self.id, offset, dst)
+    }
+
+    fn write(&self, offset: u64, src: &[u8]) {
+        self.memory_manager.borrow().write(self.id, offset, src)
+    }
+}
+
+struct MemoryManagerInner<M: Memory> {
+    memory: M,
+
+    // The number of buckets that have been allocated.
+    allocated_buckets: u16,
+
+    bucket_size_in_pages: u16,
+
+    // An array storing the size (in pages) of each of the managed memories.
+    memory_sizes_in_pages: [u64; MAX_NUM_MEMORIES as usize],
+
+    // A map mapping each managed memory to the buckets it owns, in order.
+    memory_buckets: BTreeMap<MemoryId, Vec<BucketId>>,
+}
+
+impl<M: Memory> MemoryManagerInner<M> {
+    fn init(memory: M, bucket_size_in_pages: u16) -> Self {
+        if memory.size() == 0 {
+            // Memory is empty. Create a new map.
+            return Self::new(memory, bucket_size_in_pages);
+        }
+
+        // Check if the magic in the memory corresponds to this object.
+        let mut dst = vec![0; 3];
+        memory.read(0, &mut dst);
+        if dst != MAGIC {
+            // No memory manager found. Create a new instance.
+            Self::new(memory, bucket_size_in_pages)
+        } else {
+            // The memory already contains a memory manager. Load it.
+            Self::load(memory)
+        }
+    }
+
+    fn new(memory: M, bucket_size_in_pages: u16) -> Self {
+        assert!(bucket_size_in_pages > 0, "Bucket size must be positive.");
+
+        let mem_mgr = Self {
+            memory,
+            allocated_buckets: 0,
+            memory_sizes_in_pages: [0; MAX_NUM_MEMORIES as usize],
+            memory_buckets: BTreeMap::new(),
+            bucket_size_in_pages,
+        };
+
+        mem_mgr.save_header();
+
+        // Mark all the buckets as unallocated.
+        write(
+            &mem_mgr.memory,
+            bucket_allocations_address(BucketId(0)).get(),
+            &[UNALLOCATED_BUCKET_MARKER; MAX_NUM_BUCKETS as usize],
+        );
+
+        mem_mgr
+    }
+
+    fn load(memory: M) -> Self {
+        // Read the header from memory.
+        let header: Header = read_struct(Address::from(0), &memory);
+        assert_eq!(&header.magic, MAGIC, "Bad magic.");
+        assert_eq!(header.version, LAYOUT_VERSION, "Unsupported version.");
+
+        let mut buckets = vec![0; MAX_NUM_BUCKETS as usize];
+        memory.read(bucket_allocations_address(BucketId(0)).get(), &mut buckets);
+
+        // Buckets are allocated in increasing order, so the buckets of each memory
+        // are recovered in the order in which they were allocated.
+        let mut memory_buckets: BTreeMap<MemoryId, Vec<BucketId>> = BTreeMap::new();
+        for (bucket_idx, owner) in buckets.into_iter().enumerate() {
+            if owner != UNALLOCATED_BUCKET_MARKER {
+                memory_buckets
+                    .entry(MemoryId(owner))
+                    .or_default()
+                    .push(BucketId(bucket_idx as u16));
+            }
+        }
+
+        Self {
+            memory,
+            allocated_buckets: header.num_allocated_buckets,
+            bucket_size_in_pages: header.bucket_size_in_pages,
+            memory_sizes_in_pages: header.memory_sizes_in_pages,
+            memory_buckets,
+        }
+    }
+
+    fn save_header(&self) {
+        let header = Header {
+            magic: *MAGIC,
+            version: LAYOUT_VERSION,
+            num_allocated_buckets: self.allocated_buckets,
+            bucket_size_in_pages: self.bucket_size_in_pages,
+            _reserved: [0; HEADER_RESERVED_BYTES],
+            memory_sizes_in_pages: self.memory_sizes_in_pages,
+        };
+
+        write_struct(&header, Address::from(0), &self.memory);
+    }
+
+    // Returns the size of a memory (in pages).
+    fn memory_size(&self, id: MemoryId) -> u64 {
+        self.memory_sizes_in_pages[id.0 as usize]
+    }
+
+    // Grows the memory with the given id by the given number of pages.
+    //
+    // Returns the previous size of the memory (in pages) on success, -1 otherwise.
+    fn grow(&mut self, id: MemoryId, pages: u64) -> i64 {
+        let old_size = self.memory_size(id);
+        let new_size = match old_size.checked_add(pages) {
+            Some(new_size) => new_size,
+            None => return -1,
+        };
+
+        // Compute how many additional buckets are needed.
+        let current_buckets = self.num_buckets_needed(old_size);
+        let required_buckets = self.num_buckets_needed(new_size);
+        let new_buckets_needed = required_buckets - current_buckets;
+
+        if new_buckets_needed + self.allocated_buckets as u64 > MAX_NUM_BUCKETS {
+            // Exceeded the memory that can be managed.
+            return -1;
+        }
+
+        // Grow the underlying memory if necessary, before any changes are made to
+        // the state of the manager, so that a failure leaves the state intact.
+        let pages_needed = BUCKETS_OFFSET_IN_PAGES
+            + self.bucket_size_in_pages as u64
+                * (self.allocated_buckets as u64 + new_buckets_needed);
+        let current_pages = self.memory.size();
+        if pages_needed > current_pages && self.memory.grow(pages_needed - current_pages) == -1 {
+            return -1;
+        }
+
+        // Allocate the new buckets.
+        for _ in 0..new_buckets_needed {
+            let new_bucket_id = BucketId(self.allocated_buckets);
+
+            self.memory_buckets
+                .entry(id)
+                .or_default()
+                .push(new_bucket_id);
+
+            // Record in the memory that this bucket belongs to the memory with the given `id`.
+            write(
+                &self.memory,
+                bucket_allocations_address(new_bucket_id).get(),
+                &[id.0],
+            );
+
+            self.allocated_buckets += 1;
+        }
+
+        // Update the memory with the new size.
+        self.memory_sizes_in_pages[id.0 as usize] = new_size;
+
+        // Update the header and return the old size.
+        self.save_header();
+        old_size as i64
+    }
+
+    fn write(&self, id: MemoryId, offset: u64, src: &[u8]) {
+        self.check_bounds(id, offset, src.len(), "write");
+
+        self.for_each_segment(id, offset, src.len(), |address, start, len| {
+            self.memory.write(address.get(), &src[start..start + len]);
+        });
+    }
+
+    fn read(&self, id: MemoryId, offset: u64, dst: &mut [u8]) {
+        self.check_bounds(id, offset, dst.len(), "read");
+
+        let len = dst.len();
+        self.for_each_segment(id, offset, len, |address, start, len| {
+            self.memory
+                .read(address.get(), &mut dst[start..start + len]);
+        });
+    }
+
+    // Panics if the range `[offset, offset + len)` is outside of the memory with the given `id`.
+    fn check_bounds(&self, id: MemoryId, offset: u64, len: usize, op: &str) {
+        let end = offset
+            .checked_add(len as u64)
+            .unwrap_or_else(|| panic!("{:?}: {} out of bounds", id, op));
+
+        if end > self.memory_size(id) * WASM_PAGE_SIZE {
+            panic!("{:?}: {} out of bounds", id, op);
+        }
+    }
+
+    // Splits the range `[offset, offset + len)` of the memory with the given `id` into
+    // segments that are contiguous in the underlying memory, and calls `f` for each of them
+    // with the segment's address in the underlying memory, its offset within the range,
+    // and its length.
+    //
+    // PRECONDITION: the range is within the bounds of the memory.
+    fn for_each_segment(
+        &self,
+        id: MemoryId,
+        offset: u64,
+        len: usize,
+        mut f: impl FnMut(Address, usize, usize),
+    ) {
+        if len == 0 {
+            return;
+        }
+
+        let buckets = self
+            .memory_buckets
+            .get(&id)
+            .expect("a non-empty memory must have buckets");
+        let bucket_size_in_bytes = self.bucket_size_in_bytes();
+
+        let mut processed = 0;
+        while processed < len {
+            let virtual_offset = offset + processed as u64;
+            let bucket = buckets[(virtual_offset / bucket_size_in_bytes) as usize];
+            let offset_in_bucket = virtual_offset % bucket_size_in_bytes;
+
+            let segment_len = std::cmp::min(
+                (len - processed) as u64,
+                bucket_size_in_bytes - offset_in_bucket,
+            ) as usize;
+
+            f(
+                self.bucket_address(bucket) + Bytes::from(offset_in_bucket),
+                processed,
+                segment_len,
+            );
+
+            processed += segment_len;
+        }
+    }
+
+    fn bucket_size_in_bytes(&self) -> u64 {
+        self.bucket_size_in_pages as u64 * WASM_PAGE_SIZE
+    }
+
+    // Returns the number of buckets needed to accommodate the given number of pages.
+    fn num_buckets_needed(&self, num_pages: u64) -> u64 {
+        let bucket_size_in_pages = self.bucket_size_in_pages as u64;
+        match num_pages % bucket_size_in_pages {
+            0 => num_pages / bucket_size_in_pages,
+            _ => num_pages / bucket_size_in_pages + 1,
+        }
+    }
+
+    // Returns the address of the bucket in the underlying memory.
+    fn bucket_address(&self, id: BucketId) -> Address {
+        Address::from(BUCKETS_OFFSET_IN_BYTES)
+            + Bytes::from(self.bucket_size_in_bytes()) * id.0 as u64
+    }
+}
+
+// Returns the address where the owner of the given bucket is recorded.
+fn bucket_allocations_address(id: BucketId) -> Address {
+    Address::from(0) + Header::size() + Bytes::from(id.0)
+}
+
+/// The ID of a [`VirtualMemory`] returned by the [`MemoryManager`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MemoryId(u8);
+
+impl MemoryId {
+    pub const fn new(id: u8) -> Self {
+        // Any ID can be used except the special value that's used internally to
+        // mark a bucket as unallocated.
+        assert!(id != UNALLOCATED_BUCKET_MARKER);
+
+        Self(id)
+    }
+}
+
+// Referring to a bucket.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct BucketId(u16);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::StableBTreeMap;
+
+    fn make_memory() -> Rc<RefCell<Vec<u8>>> {
+        Rc::new(RefCell::new(Vec::new()))
+    }
+
+    #[test]
+    fn can_get_memory() {
+        let mem_mgr = MemoryManager::init(make_memory());
+        let memory = mem_mgr.get(MemoryId(0));
+        assert_eq!(memory.size(), 0);
+    }
+
+    #[test]
+    fn can_allocate_and_use_memory() {
+        let mem = make_memory();
+        let mem_mgr = MemoryManager::init(mem.clone());
+        let memory = mem_mgr.get(MemoryId(0));
+        assert_eq!(memory.grow(1), 0);
+        assert_eq!(memory.size(), 1);
+
+        memory.write(0, &[1, 2, 3]);
+
+        let mut bytes = vec![0; 3];
+        memory.read(0, &mut bytes);
+        assert_eq!(bytes, vec![1, 2, 3]);
+
+        // The underlying memory holds the manager's page and a single bucket.
+        assert_eq!(mem.size(), BUCKETS_OFFSET_IN_PAGES + BUCKET_SIZE_IN_PAGES);
+        assert_eq!(
+            mem_mgr.inner.borrow().memory_buckets.get(&MemoryId(0)),
+            Some(&vec![BucketId(0)])
+        );
+    }
+
+    #[test]
+    fn can_allocate_and_use_multiple_memories() {
+        let mem = make_memory();
+        let mem_mgr = MemoryManager::init(mem.clone());
+        let memory_0 = mem_mgr.get(MemoryId(0));
+        let memory_1 = mem_mgr.get(MemoryId(1));
+
+        assert_eq!(memory_0.grow(1), 0);
+        assert_eq!(memory_1.grow(1), 0);
+
+        assert_eq!(memory_0.size(), 1);
+        assert_eq!(memory_1.size(), 1);
+
+        memory_0.write(0, &[1, 2, 3]);
+        memory_0.write(0, &[1, 2, 3]);
+        memory_1.write(0, &[4, 5, 6]);
+
+        let mut bytes = vec![0; 3];
+        memory_0.read(0, &mut bytes);
+        assert_eq!(bytes, vec![1, 2, 3]);
+
+        let mut bytes = vec![0; 3];
+        memory_1.read(0, &mut bytes);
+        assert_eq!(bytes, vec![4, 5, 6]);
+
+        // + 1 is for the header.
+        assert_eq!(mem.size(), 2 * BUCKET_SIZE_IN_PAGES + 1);
+    }
+
+    #[test]
+    fn can_be_reinitialized_from_memory() {
+        let mem = make_memory();
+        let mem_mgr = MemoryManager::init(mem.clone());
+        let memory_0 = mem_mgr.get(MemoryId(0));
+        let memory_1 = mem_mgr.get(MemoryId(1));
+
+        assert_eq!(memory_0.grow(1), 0);
+        assert_eq!(memory_1.grow(1), 0);
+
+        memory_0.write(0, &[1, 2, 3]);
+        memory_1.write(0, &[4, 5, 6]);
+
+        let mem_mgr = MemoryManager::init(mem);
+        let memory_0 = mem_mgr.get(MemoryId(0));
+        let memory_1 = mem_mgr.get(MemoryId(1));
+
+        assert_eq!(memory_0.size(), 1);
+        assert_eq!(memory_1.size(), 1);
+
+        let mut bytes = vec![0; 3];
+        memory_0.read(0, &mut bytes);
+        assert_eq!(bytes, vec![1, 2, 3]);
+
+        memory_1.read(0, &mut bytes);
+        assert_eq!(bytes, vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn growing_same_memory_multiple_times_doesnt_increase_underlying_allocation() {
+        let mem = make_memory();
+        let mem_mgr = MemoryManager::init(mem.clone());
+        let memory_0 = mem_mgr.get(MemoryId(0));
+
+        // Grow the memory by 1 page. This should increase the underlying allocation
+        // by `BUCKET_SIZE_IN_PAGES` pages.
+        assert_eq!(memory_0.grow(1), 0);
+        assert_eq!(mem.size(), 1 + BUCKET_SIZE_IN_PAGES);
+
+        // Grow the memory again. This should NOT increase the underlying allocation.
+        assert_eq!(memory_0.grow(1), 1);
+        assert_eq!(memory_0.size(), 2);
+        assert_eq!(mem.size(), 1 + BUCKET_SIZE_IN_PAGES);
+
+        // Grow the memory up to the BUCKET_SIZE_IN_PAGES.
+        // This should NOT increase the underlying allocation.
+        assert_eq!(memory_0.grow(BUCKET_SIZE_IN_PAGES - 2), 2);
+        assert_eq!(memory_0.size(), BUCKET_SIZE_IN_PAGES);
+        assert_eq!(mem.size(), 1 + BUCKET_SIZE_IN_PAGES);
+
+        // Grow the memory by one more page.
+        // This should increase the underlying allocation.
+        assert_eq!(memory_0.grow(1), BUCKET_SIZE_IN_PAGES as i64);
+        assert_eq!(memory_0.size(), BUCKET_SIZE_IN_PAGES + 1);
+        assert_eq!(mem.size(), 1 + 2 * BUCKET_SIZE_IN_PAGES);
+    }
+
+    #[test]
+    fn reads_and_writes_span_interleaved_buckets() {
+        let mem = make_memory();
+        let mem_mgr = MemoryManager::init_with_bucket_size(mem.clone(), 1);
+        let memory_0 = mem_mgr.get(MemoryId(0));
+        let memory_1 = mem_mgr.get(MemoryId(1));
+
+        // Interleave the buckets of the two memories.
+        for _ in 0..3 {
+            memory_0.grow(1);
+            memory_1.grow(1);
+        }
+        assert_eq!(
+            mem_mgr.inner.borrow().memory_buckets.get(&MemoryId(0)),
+            Some(&vec![BucketId(0), BucketId(2), BucketId(4)])
+        );
+
+        // Write data that crosses bucket boundaries in both memories.
+        let data_0: Vec<u8> = (0..3 * WASM_PAGE_SIZE).map(|i| (i % 251) as u8).collect();
+        let data_1: Vec<u8> = (0..3 * WASM_PAGE_SIZE).map(|i| (i % 241) as u8).collect();
+        memory_0.write(0, &data_0);
+        memory_1.write(0, &data_1);
+
+        let mut bytes = vec![0; data_0.len()];
+        memory_0.read(0, &mut bytes);
+        assert_eq!(bytes, data_0);
+        memory_1.read(0, &mut bytes);
+        assert_eq!(bytes, data_1);
+
+        // Unaligned read across a bucket boundary.
+        let mut bytes = vec![0; 10];
+        memory_0.read(WASM_PAGE_SIZE - 5, &mut bytes);
+        assert_eq!(
+            bytes,
+            data_0[(WASM_PAGE_SIZE - 5) as usize..(WASM_PAGE_SIZE + 5) as usize]
+        );
+
+        // The bucket size is persisted.
+        let mem_mgr = MemoryManager::init(mem);
+        let memory_0 = mem_mgr.get(MemoryId(0));
+        let mut bytes = vec![0; data_0.len()];
+        memory_0.read(0, &mut bytes);
+        assert_eq!(bytes, data_0);
+    }
+
+    #[test]
+    fn grow_fails_when_buckets_are_exhausted() {
+        let mem = make_memory();
+        let mem_mgr = MemoryManager::init_with_bucket_size(mem.clone(), 1);
+        let memory_0 = mem_mgr.get(MemoryId(0));
+
+        assert_eq!(memory_0.grow(1), 0);
+        assert_eq!(memory_0.grow(MAX_NUM_BUCKETS), -1);
+
+        // A failed grow leaves both the virtual and the underlying memory untouched.
+        assert_eq!(memory_0.size(), 1);
+        assert_eq!(mem.size(), 2);
+        assert_eq!(mem_mgr.inner.borrow().allocated_buckets, 1);
+    }
+
+    #[test]
+    fn grow_fails_when_underlying_memory_cannot_grow() {
+        let mem = crate::RestrictedMemory::new(make_memory(), 0..3);
+        let mem_mgr = MemoryManager::init_with_bucket_size(mem, 1);
+        let memory_0 = mem_mgr.get(MemoryId(0));
+
+        assert_eq!(memory_0.grow(2), 0);
+        assert_eq!(memory_0.grow(1), -1);
+        assert_eq!(memory_0.size(), 2);
+        assert_eq!(mem_mgr.inner.borrow().allocated_buckets, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "MemoryId(0): write out of bounds")]
+    fn write_out_of_bounds() {
+        let mem_mgr = MemoryManager::init(make_memory());
+        let memory_0 = mem_mgr.get(MemoryId(0));
+        memory_0.grow(1);
+        memory_0.write(WASM_PAGE_SIZE - 1, &[1, 2]);
+    }
+
+    #[test]
+    #[should_panic(expected = "MemoryId(1): read out of bounds")]
+    fn read_out_of_bounds() {
+        let mem_mgr = MemoryManager::init(make_memory());
+        let memory_1 = mem_mgr.get(MemoryId(1));
+        memory_1.read(0, &mut [0]);
+    }
+
+    #[test]
+    fn multiple_btreemaps_share_one_memory() {
+        let mem = make_memory();
+        let mem_mgr = MemoryManager::init_with_bucket_size(mem.clone(), 1);
+
+        let mut btree_0: StableBTreeMap<_, u64, u64> =
+            StableBTreeMap::init(mem_mgr.get(MemoryId(0)), 8, 8);
+        let mut btree_1: StableBTreeMap<_, u64, Vec<u8>> =
+            StableBTreeMap::init(mem_mgr.get(MemoryId(1)), 8, 100);
+
+        // Both maps grow well beyond a single bucket.
+        for i in 0..1000u64 {
+            btree_0.insert(i, i * 2).unwrap();
+            btree_1.insert(i, vec![i as u8; 100]).unwrap();
+        }
+
+        // Reload both maps from the underlying memory.
+        let mem_mgr = MemoryManager::init(mem);
+        let btree_0: StableBTreeMap<_, u64, u64> =
+            StableBTreeMap::init(mem_mgr.get(MemoryId(0)), 8, 8);
+        let btree_1: StableBTreeMap<_, u64, Vec<u8>> =
+            StableBTreeMap::init(mem_mgr.get(MemoryId(1)), 8, 100);
+
+        assert_eq!(btree_0.len(), 1000);
+        assert_eq!(btree_1.len(), 1000);
+        for i in 0..1000u64 {
+            assert_eq!(btree_0.get(&i), Some(i * 2));
+            assert_eq!(btree_1.get(&i), Some(vec![i as u8; 100]));
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! This module implements a priority queue in stable memory.
+//!
+//! The heap is stored as a [`StableVec`] in the usual array layout of a binary
+//! heap: the children of the element at index `i` are stored at indices `2i + 1`
+//! and `2i + 2`. The memory layout is the same as the one of [`StableVec`],
+//! except for the magic, which is "SMH" (short for "stable min heap").
+use crate::vec::{InsertError, Iter, StableVec};
+use crate::{Memory, Storable};
+
+#[cfg(test)]
+mod tests;
+
+/// The magic number: Stable Min Heap.
+const MAGIC: &[u8; 3] = b"SMH";
+
+/// A binary min-heap of elements stored in stable memory.
+///
+/// The smallest element according to [`Ord`] is always at the top of the heap.
+/// Elements are subject to the same size restrictions as in [`StableVec`].
+pub struct StableMinHeap<T: Storable + Ord, M: Memory> {
+    data: StableVec<T, M>,
+}
+
+impl<T: Storable + Ord, M: Memory> StableMinHeap<T, M> {
+    /// Initializes a `StableMinHeap`.
+    ///
+    /// If the memory provided already contains a `StableMinHeap`, then that
+    /// heap is loaded. Otherwise, a new `StableMinHeap` instance is created.
+    pub fn init(memory: M, max_element_size: u32) -> Self {
+        Self {
+            data: StableVec::init_with_magic(memory, max_element_size, MAGIC),
+        }
+    }
+
+    /// Creates a new empty `StableMinHeap`, overwriting the previous contents of the memory.
+    pub fn new(memory: M, max_element_size: u32) -> Self {
+        Self {
+            data: StableVec::new_with_magic(memory, max_element_size, MAGIC),
+        }
+    }
+
+    /// Loads the heap from memory.
+    pub fn load(memory: M) -> Self {
+        Self {
+            data: StableVec::load_with_magic(memory, MAGIC),
+        }
+    }
+
+    /// Returns the number of elements in the heap.
+    pub fn len(&self) -> u64 {
+        self.data.len()
+    }
+
+    /// Returns `true` if the heap contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns a reference to the memory used by the heap.
+    pub fn get_memory(&self) -> &M {
+        self.data.get_memory()
+    }
+
+    /// Forgets the heap and returns the underlying memory.
+    pub fn forget(self) -> M {
+        self.data.forget()
+    }
+
+    /// Pushes an element onto the heap.
+    ///
+    /// If the element is too large or the memory cannot grow to fit it, the heap is left
+    /// unchanged.
+    ///
+    /// Complexity: O(log(self.len())).
+    pub fn push(&mut self, item: &T) -> Result<(), InsertError> {
+        self.data.push(item)?;
+        self.bubble_up(self.data.len() - 1, item);
+        Ok(())
+    }
+
+    /// Removes the smallest element from the heap and returns it,
+    /// or `None` if the heap is empty.
+    ///
+    /// Complexity: O(log(self.len())).
+    pub fn pop(&mut self) -> Option<T> {
+        let n = self.data.len();
+        match n {
+            0 => None,
+            1 => self.data.pop(),
+            _ => {
+                let smallest = self.data.get(0).unwrap();
+                let last = self.data.pop().unwrap();
+                self.set(0, &last);
+                self.bubble_down(0, n - 1, &last);
+                Some(smallest)
+            }
+        }
+    }
+
+    /// Returns the smallest element in the heap, or `None` if the heap is empty.
+    pub fn peek(&self) -> Option<T> {
+        self.data.get(0)
+    }
+
+    /// Returns an iterator visiting all the elements in the heap, in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, T, M> {
+        self.data.iter()
+    }
+
+    // Moves the `item` located at index `i` up until the heap property is restored.
+    fn bubble_up(&mut self, mut i: u64, item: &T) {
+        while i > 0 {
+            let parent_idx = (i - 1) / 2;
+            let parent = self.data.get(parent_idx).unwrap();
+            if *item >= parent {
+                break;
+            }
+
+            self.set(i, &parent);
+            self.set(parent_idx, item);
+            i = parent_idx;
+        }
+    }
+
+    // Moves the `item` located at index `i` down until the heap property is restored,
+    // considering only the first `len` elements.
+    fn bubble_down(&mut self, mut i: u64, len: u64, item: &T) {
+        loop {
+            let left_idx = i * 2 + 1;
+            if left_idx >= len {
+                break;
+            }
+
+            // Find the smallest of the children.
+            let left = self.data.get(left_idx).unwrap();
+            let right_idx = left_idx + 1;
+            let (smallest_idx, smallest) = match self.data.get(right_idx) {
+                Some(right) if right_idx < len && right < left => (right_idx, right),
+                _ => (left_idx, left),
+            };
+
+            if *item <= smallest {
+                break;
+            }
+
+            self.set(i, &smallest);
+            self.set(smallest_idx, item);
+            i = smallest_idx;
+        }
+    }
+
+    // Overwrites the element at the given index with an element that's already in the heap.
+    fn set(&mut self, index: u64, item: &T) {
+        // Elements already in the heap are known to fit into a slot and the memory for
+        // all existing slots has already been allocated, so this cannot fail.
+        self.data
+            .set(index, item)
+            .expect("BUG: failed to move an element within the heap");
+    }
+}
+
+impl<T: Storable + Ord + std::fmt::Debug, M: Memory> std::fmt::Debug for StableMinHeap<T, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.data.fmt(f)
+    }
+}
@@ -0,0 +1,104 @@
+use crate::min_heap::StableMinHeap;
+use crate::vec::{InsertError, StableVec};
+use crate::vec_mem::VectorMemory;
+
+#[test]
+fn test_heap_push_pop_in_order() {
+    let mut heap = StableMinHeap::new(VectorMemory::default(), 8);
+
+    // Push the elements in a scrambled order.
+    for i in 0..100u64 {
+        heap.push(&((i * 37) % 100)).unwrap();
+        assert_eq!(heap.len(), i + 1);
+    }
+
+    assert_eq!(heap.peek(), Some(0));
+
+    for i in 0..100u64 {
+        assert_eq!(heap.pop(), Some(i));
+    }
+
+    assert_eq!(heap.pop(), None);
+    assert_eq!(heap.peek(), None);
+    assert!(heap.is_empty());
+}
+
+#[test]
+fn test_heap_duplicates() {
+    let mut heap = StableMinHeap::new(VectorMemory::default(), 8);
+
+    for i in [3u64, 1, 3, 2, 1, 2] {
+        heap.push(&i).unwrap();
+    }
+
+    let mut popped = vec![];
+    while let Some(i) = heap.pop() {
+        popped.push(i);
+    }
+    assert_eq!(popped, vec![1, 1, 2, 2, 3, 3]);
+}
+
+#[test]
+fn test_heap_interleaved_push_pop() {
+    let mut heap = StableMinHeap::new(VectorMemory::default(), 8);
+
+    heap.push(&5u64).unwrap();
+    heap.push(&2u64).unwrap();
+    assert_eq!(heap.pop(), Some(2));
+    heap.push(&7u64).unwrap();
+    heap.push(&1u64).unwrap();
+    assert_eq!(heap.pop(), Some(1));
+    assert_eq!(heap.pop(), Some(5));
+    heap.push(&3u64).unwrap();
+    assert_eq!(heap.pop(), Some(3));
+    assert_eq!(heap.pop(), Some(7));
+    assert_eq!(heap.pop(), None);
+}
+
+#[test]
+fn test_heap_persistence() {
+    let mut heap = StableMinHeap::new(VectorMemory::default(), 8);
+    for i in [4u64, 2, 8, 6] {
+        heap.push(&i).unwrap();
+    }
+
+    let mut heap: StableMinHeap<u64, _> = StableMinHeap::init(heap.forget(), 8);
+    assert_eq!(heap.len(), 4);
+
+    let mut items: Vec<u64> = heap.iter().collect();
+    items.sort_unstable();
+    assert_eq!(items, vec![2, 4, 6, 8]);
+
+    assert_eq!(heap.pop(), Some(2));
+    assert_eq!(heap.pop(), Some(4));
+}
+
+#[test]
+fn test_heap_value_too_large() {
+    let mut heap = StableMinHeap::new(VectorMemory::default(), 2);
+
+    heap.push(&b"b".to_vec()).unwrap();
+    assert_eq!(
+        heap.push(&b"abc".to_vec()),
+        Err(InsertError::ValueTooLarge { given: 3, max: 2 })
+    );
+    assert_eq!(heap.len(), 1);
+    assert_eq!(heap.peek(), Some(b"b".to_vec()));
+}
+
+#[test]
+fn test_heap_and_vec_are_distinguished() {
+    let mut vec = StableVec::new(VectorMemory::default(), 8);
+    vec.push(&1u64).unwrap();
+
+    // A heap initialized on top of a vector doesn't reuse its contents.
+    let heap: StableMinHeap<u64, _> = StableMinHeap::init(vec.forget(), 8);
+    assert!(heap.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Bad magic.")]
+fn test_heap_load_vec() {
+    let vec: StableVec<u64, _> = StableVec::new(VectorMemory::default(), 8);
+    let _: StableMinHeap<u64, _> = StableMinHeap::load(vec.forget());
+}
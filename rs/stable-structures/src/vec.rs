@@ -0,0 +1,391 @@
+//! This module implements a growable array in stable memory.
+//!
+//! All the elements of a [`StableVec`] occupy slots of the same size, which is determined by
+//! the maximum element size given at creation time. This allows constant-time access and in-place
+//! updates of any element.
+//!
+//! # V1 layout
+//!
+//! ```text
+//! ---------------------------------------- <- Address 0
+//! Magic                   ↕ 3 bytes
+//! ----------------------------------------
+//! Layout version          ↕ 1 byte
+//! ----------------------------------------
+//! Max element size = S    ↕ 4 bytes
+//! ----------------------------------------
+//! Number of elements = L  ↕ 8 bytes
+//! ----------------------------------------
+//! Reserved space          ↕ 16 bytes
+//! ---------------------------------------- <- Address 32
+//! Size of element 0       ↕ 4 bytes
+//! ----------------------------------------
+//! Element 0 bytes         ↕ S bytes
+//! ----------------------------------------
+//! ...
+//! ----------------------------------------
+//! Size of element L-1     ↕ 4 bytes
+//! ----------------------------------------
+//! Element L-1 bytes       ↕ S bytes
+//! ----------------------------------------
+//! Unallocated space
+//! ```
+use crate::{
+    read_struct, read_u32, safe_write,
+    types::{Address, Bytes},
+    write_struct, GrowFailed, Memory, Storable,
+};
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+#[cfg(test)]
+mod tests;
+
+/// The magic number: Stable VeC.
+const MAGIC: &[u8; 3] = b"SVC";
+
+/// The current version of the layout.
+const LAYOUT_VERSION: u8 = 1;
+
+// The size of u32 in bytes.
+const U32_SIZE: u64 = 4;
+
+#[repr(packed)]
+struct VecHeader {
+    magic: [u8; 3],
+    version: u8,
+    max_element_size: u32,
+    len: u64,
+    // Additional space reserved to add new fields without breaking backward-compatibility.
+    _buffer: [u8; 16],
+}
+
+impl VecHeader {
+    fn size() -> Bytes {
+        Bytes::from(core::mem::size_of::<Self>() as u64)
+    }
+}
+
+/// An error returned when inserting elements into a [`StableVec`] or
+/// a [`StableMinHeap`](crate::StableMinHeap).
+#[derive(Debug, PartialEq, Eq)]
+pub enum InsertError {
+    /// The encoded element is larger than the max element size of the structure.
+    ValueTooLarge { given: usize, max: usize },
+    /// The underlying memory could not grow to accommodate the element.
+    GrowFailed { current_size: u64, delta: u64 },
+}
+
+impl From<GrowFailed> for InsertError {
+    fn from(
+        GrowFailed {
+            current_size,
+            delta,
+        }: GrowFailed,
+    ) -> Self {
+        Self::GrowFailed {
+            current_size,
+            delta,
+        }
+    }
+}
+
+impl std::fmt::Display for InsertError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ValueTooLarge { given, max } => {
+                write!(
+                    f,
+                    "InsertError::ValueTooLarge Expected value to be <= {} bytes but received value with {} bytes.",
+                    max, given
+                )
+            }
+            Self::GrowFailed {
+                current_size,
+                delta,
+            } => {
+                write!(
+                    f,
+                    "InsertError::GrowFailed Failed to grow memory from {} pages by {} pages.",
+                    current_size, delta
+                )
+            }
+        }
+    }
+}
+
+/// A growable array of elements stored in stable memory.
+///
+/// Elements are encoded using [`Storable`] and each one of them must fit into `max_element_size`
+/// bytes. Every element takes `max_element_size + 4` bytes of memory regardless of its actual
+/// size, so the structure works best for elements of a fixed or nearly-fixed size.
+pub struct StableVec<T: Storable, M: Memory> {
+    memory: M,
+
+    // The maximum size an element can have.
+    max_element_size: u32,
+
+    // The number of elements in the vector.
+    len: u64,
+
+    // The magic identifying the structure stored in the memory.
+    magic: [u8; 3],
+
+    // A marker to communicate to the Rust compiler that we own these types.
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Storable, M: Memory> StableVec<T, M> {
+    /// Initializes a `StableVec`.
+    ///
+    /// If the memory provided already contains a `StableVec`, then that
+    /// vector is loaded. Otherwise, a new `StableVec` instance is created.
+    pub fn init(memory: M, max_element_size: u32) -> Self {
+        Self::init_with_magic(memory, max_element_size, MAGIC)
+    }
+
+    /// Creates a new empty `StableVec`, overwriting the previous contents of the memory.
+    ///
+    /// The given `memory` is assumed to be exclusively reserved for this data
+    /// structure and that it starts at address zero.
+    pub fn new(memory: M, max_element_size: u32) -> Self {
+        Self::new_with_magic(memory, max_element_size, MAGIC)
+    }
+
+    /// Loads the vector from memory.
+    pub fn load(memory: M) -> Self {
+        Self::load_with_magic(memory, MAGIC)
+    }
+
+    pub(crate) fn init_with_magic(memory: M, max_element_size: u32, magic: &[u8; 3]) -> Self {
+        if memory.size() == 0 {
+            // Memory is empty. Create a new vector.
+            return Self::new_with_magic(memory, max_element_size, magic);
+        }
+
+        // Check if the magic in the memory corresponds to the expected structure.
+        let mut dst = vec![0; 3];
+        memory.read(0, &mut dst);
+        if dst != magic {
+            // No structure found. Create a new instance.
+            Self::new_with_magic(memory, max_element_size, magic)
+        } else {
+            // The memory already contains the structure. Load it.
+            Self::load_with_magic(memory, magic)
+        }
+    }
+
+    pub(crate) fn new_with_magic(memory: M, max_element_size: u32, magic: &[u8; 3]) -> Self {
+        let vec = Self {
+            memory,
+            max_element_size,
+            len: 0,
+            magic: *magic,
+            _phantom: PhantomData,
+        };
+
+        vec.save();
+        vec
+    }
+
+    pub(crate) fn load_with_magic(memory: M, magic: &[u8; 3]) -> Self {
+        // Read the header from memory.
+        let header: VecHeader = read_struct(Address::from(0), &memory);
+        assert_eq!(&header.magic, magic, "Bad magic.");
+        assert_eq!(header.version, LAYOUT_VERSION, "Unsupported version.");
+
+        Self {
+            memory,
+            max_element_size: header.max_element_size,
+            len: header.len,
+            magic: *magic,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the number of elements in the vector.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the vector contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the maximum size of an element in bytes.
+    pub fn max_element_size(&self) -> u32 {
+        self.max_element_size
+    }
+
+    /// Returns a reference to the memory used by the vector.
+    pub fn get_memory(&self) -> &M {
+        &self.memory
+    }
+
+    /// Forgets the vector and returns the underlying memory.
+    pub fn forget(self) -> M {
+        self.memory
+    }
+
+    /// Returns the element at the given index, or `None` if the index is out of bounds.
+    pub fn get(&self, index: u64) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+
+        Some(T::from_bytes(self.read_element(index)))
+    }
+
+    /// Replaces the element at the given index.
+    ///
+    /// If the new element is too large, the vector is left unchanged.
+    ///
+    /// PRECONDITION: index < self.len()
+    pub fn set(&mut self, index: u64, item: &T) -> Result<(), InsertError> {
+        assert!(
+            index < self.len,
+            "Index out of bounds: the len is {} but the index is {}.",
+            self.len,
+            index
+        );
+
+        self.write_element(index, item)
+    }
+
+    /// Appends an element to the end of the vector.
+    ///
+    /// If the element is too large or the memory cannot grow to fit it, the vector is left
+    /// unchanged.
+    pub fn push(&mut self, item: &T) -> Result<(), InsertError> {
+        self.write_element(self.len, item)?;
+
+        self.len += 1;
+        self.save();
+        Ok(())
+    }
+
+    /// Removes the last element from the vector and returns it, or `None` if it is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let value = self.read_element(self.len - 1);
+
+        self.len -= 1;
+        self.save();
+        Some(T::from_bytes(value))
+    }
+
+    /// Returns an iterator over the elements of the vector.
+    pub fn iter(&self) -> Iter<'_, T, M> {
+        Iter {
+            vec: self,
+            pos: 0,
+            end: self.len,
+        }
+    }
+
+    /// Returns the bytes of the element at the given index.
+    ///
+    /// PRECONDITION: index < self.len
+    fn read_element(&self, index: u64) -> Vec<u8> {
+        let address = self.slot_address(index);
+        let size = read_u32(&self.memory, address);
+        let mut bytes = vec![0; size as usize];
+        self.memory
+            .read((address + Bytes::from(U32_SIZE)).get(), &mut bytes);
+        bytes
+    }
+
+    /// Encodes the element and writes it into the slot with the given index,
+    /// growing the memory if needed.
+    fn write_element(&self, index: u64, item: &T) -> Result<(), InsertError> {
+        let encoded = item.to_bytes();
+        let bytes: &[u8] = encoded.borrow();
+
+        if bytes.len() > self.max_element_size as usize {
+            return Err(InsertError::ValueTooLarge {
+                given: bytes.len(),
+                max: self.max_element_size as usize,
+            });
+        }
+
+        // The size and the contents of the element are written at once, so that a failure to
+        // grow the memory doesn't leave the slot in an inconsistent state.
+        let mut slot = Vec::with_capacity(U32_SIZE as usize + bytes.len());
+        slot.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        slot.extend_from_slice(bytes);
+
+        safe_write(&self.memory, self.slot_address(index).get(), &slot)?;
+        Ok(())
+    }
+
+    // Returns the address of the slot with the given index.
+    fn slot_address(&self, index: u64) -> Address {
+        let slot_size = Bytes::from(U32_SIZE + self.max_element_size as u64);
+        Address::from(0) + VecHeader::size() + slot_size * index
+    }
+
+    // Saves the header to memory.
+    fn save(&self) {
+        let header = VecHeader {
+            magic: self.magic,
+            version: LAYOUT_VERSION,
+            max_element_size: self.max_element_size,
+            len: self.len,
+            _buffer: [0; 16],
+        };
+
+        write_struct(&header, Address::from(0), &self.memory);
+    }
+}
+
+impl<T: Storable + std::fmt::Debug, M: Memory> std::fmt::Debug for StableVec<T, M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the elements of a [`StableVec`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct Iter<'a, T: Storable, M: Memory> {
+    vec: &'a StableVec<T, M>,
+    // The index of the next element to return from the front.
+    pos: u64,
+    // The index past the last element to return from the back.
+    end: u64,
+}
+
+impl<T: Storable, M: Memory> Iterator for Iter<'_, T, M> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.pos >= self.end {
+            return None;
+        }
+
+        let item = self.vec.get(self.pos);
+        self.pos += 1;
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end.saturating_sub(self.pos) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Storable, M: Memory> DoubleEndedIterator for Iter<'_, T, M> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.pos >= self.end {
+            return None;
+        }
+
+        self.end -= 1;
+        self.vec.get(self.end)
+    }
+}
+
+impl<T: Storable, M: Memory> ExactSizeIterator for Iter<'_, T, M> {}
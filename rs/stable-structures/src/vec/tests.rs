@@ -0,0 +1,149 @@
+use crate::vec::{InsertError, StableVec};
+use crate::vec_mem::VectorMemory;
+use crate::{Memory, RestrictedMemory, WASM_PAGE_SIZE};
+
+#[test]
+fn test_vec_new_is_empty() {
+    let vec: StableVec<u64, _> = StableVec::new(VectorMemory::default(), 8);
+
+    assert_eq!(vec.len(), 0);
+    assert!(vec.is_empty());
+    assert_eq!(vec.max_element_size(), 8);
+    assert_eq!(vec.get(0), None);
+    assert_eq!(vec.iter().next(), None);
+}
+
+#[test]
+fn test_vec_push_pop() {
+    let mut vec = StableVec::new(VectorMemory::default(), 8);
+
+    for i in 0..100u64 {
+        vec.push(&i).unwrap();
+        assert_eq!(vec.len(), i + 1);
+        assert_eq!(vec.get(i), Some(i));
+    }
+
+    for i in (0..100u64).rev() {
+        assert_eq!(vec.pop(), Some(i));
+        assert_eq!(vec.len(), i);
+        assert_eq!(vec.get(i), None);
+    }
+
+    assert_eq!(vec.pop(), None);
+    assert!(vec.is_empty());
+}
+
+#[test]
+fn test_vec_set() {
+    let mut vec = StableVec::new(VectorMemory::default(), 10);
+
+    vec.push(&b"foo".to_vec()).unwrap();
+    vec.push(&b"bar".to_vec()).unwrap();
+    vec.set(0, &b"a longer".to_vec()).unwrap();
+    vec.set(1, &vec![]).unwrap();
+
+    assert_eq!(vec.get(0), Some(b"a longer".to_vec()));
+    assert_eq!(vec.get(1), Some(vec![]));
+    assert_eq!(vec.len(), 2);
+}
+
+#[test]
+#[should_panic(expected = "Index out of bounds")]
+fn test_vec_set_out_of_bounds() {
+    let mut vec = StableVec::new(VectorMemory::default(), 8);
+    vec.push(&1u64).unwrap();
+    vec.set(1, &2u64).unwrap();
+}
+
+#[test]
+fn test_vec_value_too_large() {
+    let mut vec = StableVec::new(VectorMemory::default(), 3);
+
+    assert_eq!(
+        vec.push(&b"abcd".to_vec()),
+        Err(InsertError::ValueTooLarge { given: 4, max: 3 })
+    );
+    assert!(vec.is_empty());
+
+    vec.push(&b"abc".to_vec()).unwrap();
+    assert_eq!(
+        vec.set(0, &b"abcde".to_vec()),
+        Err(InsertError::ValueTooLarge { given: 5, max: 3 })
+    );
+    assert_eq!(vec.get(0), Some(b"abc".to_vec()));
+}
+
+#[test]
+fn test_vec_persistence() {
+    let mut vec = StableVec::new(VectorMemory::default(), 8);
+    for i in 0..10u64 {
+        vec.push(&i).unwrap();
+    }
+
+    // `init` loads the existing vector and ignores the given max element size.
+    let vec: StableVec<u64, _> = StableVec::init(vec.forget(), 100);
+    assert_eq!(vec.len(), 10);
+    assert_eq!(vec.max_element_size(), 8);
+    assert_eq!(vec.iter().collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+
+    // `new` overwrites the existing vector.
+    let vec: StableVec<u64, _> = StableVec::new(vec.forget(), 8);
+    assert!(vec.is_empty());
+}
+
+#[test]
+fn test_vec_init_with_different_magic() {
+    let mem = VectorMemory::default();
+    assert_eq!(mem.grow(1), 0);
+    mem.write(0, b"WAS");
+
+    let vec: StableVec<u64, _> = StableVec::init(mem, 8);
+    assert!(vec.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Unsupported version.")]
+fn test_vec_load_bad_version() {
+    let mem = VectorMemory::default();
+    assert_eq!(mem.grow(1), 0);
+    mem.write(0, b"SVC\x02");
+
+    let _: StableVec<u64, _> = StableVec::init(mem, 8);
+}
+
+#[test]
+fn test_vec_iter() {
+    let mut vec = StableVec::new(VectorMemory::default(), 8);
+    for i in 0..5u64 {
+        vec.push(&i).unwrap();
+    }
+
+    let mut iter = vec.iter();
+    assert_eq!(iter.len(), 5);
+    assert_eq!(iter.next(), Some(0));
+    assert_eq!(iter.next_back(), Some(4));
+    assert_eq!(iter.len(), 3);
+    assert_eq!(iter.collect::<Vec<_>>(), vec![1, 2, 3]);
+
+    assert_eq!(vec.iter().rev().collect::<Vec<_>>(), vec![4, 3, 2, 1, 0]);
+}
+
+#[test]
+fn test_vec_out_of_memory() {
+    let mut vec = StableVec::new(RestrictedMemory::new(VectorMemory::default(), 0..1), 1000);
+
+    // The header takes 32 bytes and every slot takes 1004 bytes.
+    let fits = (WASM_PAGE_SIZE - 32) / 1004;
+    for _ in 0..fits {
+        vec.push(&vec![1; 1000]).unwrap();
+    }
+
+    assert_eq!(
+        vec.push(&vec![1; 1000]),
+        Err(InsertError::GrowFailed {
+            current_size: 1,
+            delta: 1
+        })
+    );
+    assert_eq!(vec.len(), fits);
+}
@@ -3,6 +3,7 @@ use crate::error::LayoutError;
 
 use bitcoin::{hashes::Hash, Network, OutPoint, Script, TxOut, Txid};
 use ic_base_types::{NumBytes, NumSeconds};
+use ic_ic00_types::{CanisterChange, LogVisibility};
 use ic_logger::ReplicaLogger;
 use ic_protobuf::{
     bitcoin::v1 as pb_bitcoin,
@@ -14,12 +15,13 @@ use ic_protobuf::{
 };
 use ic_replicated_state::{
     bitcoin_state, canister_state::execution_state::WasmMetadata, CallContextManager,
-    CanisterStatus, ExportedFunctions, Global, NumWasmPages,
+    CanisterHistory, CanisterLog, CanisterStatus, ExecutionTask, ExportedFunctions, Global,
+    NumWasmPages, OnLowWasmMemoryHookStatus, SnapshotId,
 };
 use ic_sys::mmap::ScopedMmap;
 use ic_types::{
     nominal_cycles::NominalCycles, AccumulatedPriority, CanisterId, ComputeAllocation, Cycles,
-    ExecutionRound, Height, MemoryAllocation, NumInstructions, PrincipalId,
+    ExecutionRound, Height, MemoryAllocation, NumInstructions, PrincipalId, Time,
 };
 use ic_wasm_types::{CanisterModule, WasmHash};
 use std::convert::{From, TryFrom, TryInto};
@@ -178,6 +180,8 @@ pub struct CanisterStateBits {
     pub memory_allocation: MemoryAllocation,
     pub freeze_threshold: NumSeconds,
     pub cycles_balance: Cycles,
+    pub reserved_balance: Cycles,
+    pub reserved_balance_limit: Option<Cycles>,
     pub status: CanisterStatus,
     pub scheduled_as_first: u64,
     pub skipped_round_due_to_no_messages: u64,
@@ -188,6 +192,25 @@ pub struct CanisterStateBits {
     pub stable_memory_size: NumWasmPages,
     pub heap_delta_debit: NumBytes,
     pub install_code_debit: NumInstructions,
+    pub global_timer_nanos: u64,
+    pub canister_log: CanisterLog,
+    pub log_visibility: LogVisibility,
+    pub wasm_memory_limit: Option<NumBytes>,
+    pub wasm_memory_threshold: NumBytes,
+    pub on_low_wasm_memory_hook_status: OnLowWasmMemoryHookStatus,
+    pub canister_version: u64,
+    pub canister_history: CanisterHistory,
+    pub task_queue: Vec<ExecutionTask>,
+}
+
+/// This struct contains bits of a `CanisterSnapshot` that are not already
+/// covered somewhere else and are too small to be serialized separately.
+#[derive(Debug)]
+pub struct CanisterSnapshotBits {
+    pub taken_at_timestamp: Time,
+    pub certified_data: Vec<u8>,
+    pub execution_state_bits: ExecutionStateBits,
+    pub stable_memory_size: NumWasmPages,
 }
 
 /// This struct contains bits of the `BitcoinState` that are not already
@@ -235,12 +258,18 @@ impl Default for BitcoinStateBits {
 /// |   |       └── utxos_small.bin
 /// |   |       └── utxos_medium.bin
 /// |   |       └── address_outpoints.bin
-/// │   └── canister_states
-/// │       └── <hex(canister_id)>
-/// │           ├── queues.pbuf
+/// │   ├── canister_states
+/// │   │   └── <hex(canister_id)>
+/// │   │       ├── queues.pbuf
+/// │   │       ├── vmemory_0.bin
+/// │   │       ├── canister.pbuf
+/// │   │       ├── stable_memory.(pbuf|bin)
+/// │   │       └── software.wasm
+/// │   └── snapshots
+/// │       └── <hex(snapshot_id)>
+/// │           ├── snapshot.pbuf
 /// │           ├── vmemory_0.bin
-/// │           ├── canister.pbuf
-/// │           ├── stable_memory.(pbuf|bin)
+/// │           ├── stable_memory.bin
 /// │           └── software.wasm
 /// │
 /// ├── [checkpoints] {owned and varies by checkpoint manager}
@@ -253,12 +282,18 @@ impl Default for BitcoinStateBits {
 /// |      |       └── utxos_small.bin
 /// |      |       └── utxos_medium.bin
 /// |      |       └── address_outpoints.bin
-/// │      └── canister_states
-/// │          └── <hex(canister_id)>
-/// │              ├── queues.pbuf
+/// │      ├── canister_states
+/// │      │   └── <hex(canister_id)>
+/// │      │       ├── queues.pbuf
+/// │      │       ├── vmemory_0.bin
+/// │      │       ├── canister.pbuf
+/// │      │       ├── stable_memory.(pbuf|bin)
+/// │      │       └── software.wasm
+/// │      └── snapshots
+/// │          └── <hex(snapshot_id)>
+/// │              ├── snapshot.pbuf
 /// │              ├── vmemory_0.bin
-/// │              ├── canister.pbuf
-/// │              ├── stable_memory.(pbuf|bin)
+/// │              ├── stable_memory.bin
 /// │              └── software.wasm
 /// │
 /// └── tmp
@@ -734,6 +769,32 @@ impl<Permissions: AccessPolicy> CheckpointLayout<Permissions> {
         )
     }
 
+    pub fn snapshot_ids(&self) -> Result<Vec<SnapshotId>, LayoutError> {
+        let snapshots_dir = self.root.join("snapshots");
+        Permissions::check_dir(&snapshots_dir)?;
+        collect_subdirs(snapshots_dir.as_path(), |p| {
+            let blob = hex::decode(p).unwrap_or_else(|err| {
+                panic!(
+                    "Failed to convert directory name {} into a snapshot id: {}",
+                    p, err
+                )
+            });
+
+            SnapshotId::try_from(&blob[..]).expect("failed to parse snapshot id")
+        })
+    }
+
+    pub fn snapshot(
+        &self,
+        snapshot_id: &SnapshotId,
+    ) -> Result<SnapshotLayout<Permissions>, LayoutError> {
+        SnapshotLayout::new(
+            self.root
+                .join("snapshots")
+                .join(hex::encode(snapshot_id.to_bytes())),
+        )
+    }
+
     pub fn bitcoin(&self) -> Result<BitcoinStateLayout<Permissions>, LayoutError> {
         // TODO(EXC-1113): Rename this path to "bitcoin", as it stores data for either network.
         BitcoinStateLayout::new(self.root.join("bitcoin").join("testnet"))
@@ -810,6 +871,60 @@ impl<Permissions: AccessPolicy> CanisterLayout<Permissions> {
     }
 }
 
+pub struct SnapshotLayout<Permissions: AccessPolicy> {
+    snapshot_root: PathBuf,
+    permissions_tag: PhantomData<Permissions>,
+}
+
+impl<Permissions: AccessPolicy> SnapshotLayout<Permissions> {
+    pub fn new(snapshot_root: PathBuf) -> Result<Self, LayoutError> {
+        Permissions::check_dir(&snapshot_root)?;
+        Ok(Self {
+            snapshot_root,
+            permissions_tag: PhantomData,
+        })
+    }
+
+    pub fn raw_path(&self) -> PathBuf {
+        self.snapshot_root.clone()
+    }
+
+    pub fn snapshot(
+        &self,
+    ) -> ProtoFileWith<pb_canister_state_bits::CanisterSnapshotBits, Permissions> {
+        self.snapshot_root.join("snapshot.pbuf").into()
+    }
+
+    pub fn wasm(&self) -> WasmFile<Permissions> {
+        self.snapshot_root.join("software.wasm").into()
+    }
+
+    pub fn vmemory_0(&self) -> PathBuf {
+        self.snapshot_root.join("vmemory_0.bin")
+    }
+
+    pub fn stable_memory_blob(&self) -> PathBuf {
+        self.snapshot_root.join("stable_memory.bin")
+    }
+
+    pub fn tombstone(&self) -> PathBuf {
+        self.snapshot_root.join("tombstone")
+    }
+
+    /// Marks this snapshot as deleted by creating a 'tombstone' file in the
+    /// snapshot directory.  Such directories will be excluded when a
+    /// checkpoint is created.
+    pub fn mark_deleted(&self) -> Result<(), LayoutError> {
+        let path = self.tombstone();
+        let _ = std::fs::File::create(&path).map_err(|err| LayoutError::IoError {
+            path,
+            message: "Failed to create a file".to_string(),
+            io_err: err,
+        })?;
+        Ok(())
+    }
+}
+
 pub struct BitcoinStateLayout<Permissions: AccessPolicy> {
     bitcoin_root: PathBuf,
     permissions_tag: PhantomData<Permissions>,
@@ -1059,6 +1174,8 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
             memory_allocation: item.memory_allocation.bytes().get(),
             freeze_threshold: item.freeze_threshold.get(),
             cycles_balance: Some(item.cycles_balance.into()),
+            reserved_balance: Some(item.reserved_balance.into()),
+            reserved_balance_limit: item.reserved_balance_limit.map(|limit| limit.into()),
             canister_status: Some((&item.status).into()),
             scheduled_as_first: item.scheduled_as_first,
             skipped_round_due_to_no_messages: item.skipped_round_due_to_no_messages,
@@ -1071,6 +1188,33 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
             stable_memory_size64: item.stable_memory_size.get() as u64,
             heap_delta_debit: item.heap_delta_debit.get(),
             install_code_debit: item.install_code_debit.get(),
+            global_timer_nanos: item.global_timer_nanos,
+            canister_log_records: item
+                .canister_log
+                .records()
+                .iter()
+                .map(|record| record.into())
+                .collect(),
+            next_canister_log_record_idx: item.canister_log.next_idx(),
+            log_visibility: pb_canister_state_bits::LogVisibility::from(item.log_visibility).into(),
+            wasm_memory_limit: item.wasm_memory_limit.map(|limit| limit.get()),
+            wasm_memory_threshold: item.wasm_memory_threshold.get(),
+            on_low_wasm_memory_hook_status:
+                pb_canister_state_bits::OnLowWasmMemoryHookStatus::from(
+                    item.on_low_wasm_memory_hook_status,
+                )
+                .into(),
+            canister_version: item.canister_version,
+            canister_history: Some(pb_canister_state_bits::CanisterHistory {
+                changes: item
+                    .canister_history
+                    .changes()
+                    .iter()
+                    .map(|change| change.into())
+                    .collect(),
+                total_num_changes: item.canister_history.total_num_changes(),
+            }),
+            task_queue: item.task_queue.iter().map(|task| task.into()).collect(),
         }
     }
 }
@@ -1104,6 +1248,20 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
         let cycles_balance =
             try_from_option_field(value.cycles_balance, "CanisterStateBits::cycles_balance")?;
 
+        // Checkpoints written before the canister history was introduced
+        // don't have one, so they start with an empty history.
+        let canister_history = match value.canister_history {
+            Some(history) => CanisterHistory::new(
+                history
+                    .changes
+                    .into_iter()
+                    .map(CanisterChange::try_from)
+                    .collect::<Result<_, _>>()?,
+                history.total_num_changes,
+            ),
+            None => CanisterHistory::default(),
+        };
+
         Ok(Self {
             controllers,
             last_full_execution_round: value.last_full_execution_round.into(),
@@ -1123,6 +1281,13 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
                 })?,
             freeze_threshold: NumSeconds::from(value.freeze_threshold),
             cycles_balance,
+            // Checkpoints written before cycles could be reserved have no
+            // reserved balance.
+            reserved_balance: value
+                .reserved_balance
+                .map(Cycles::from)
+                .unwrap_or_else(Cycles::zero),
+            reserved_balance_limit: value.reserved_balance_limit.map(Cycles::from),
             status: try_from_option_field(
                 value.canister_status,
                 "CanisterStateBits::canister_status",
@@ -1136,6 +1301,33 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
             stable_memory_size: NumWasmPages::from(value.stable_memory_size64 as usize),
             heap_delta_debit: NumBytes::from(value.heap_delta_debit),
             install_code_debit: NumInstructions::from(value.install_code_debit),
+            global_timer_nanos: value.global_timer_nanos,
+            canister_log: CanisterLog::new(
+                value.next_canister_log_record_idx,
+                value
+                    .canister_log_records
+                    .into_iter()
+                    .map(|record| record.into())
+                    .collect(),
+            ),
+            log_visibility: pb_canister_state_bits::LogVisibility::from_i32(value.log_visibility)
+                .unwrap_or_default()
+                .into(),
+            wasm_memory_limit: value.wasm_memory_limit.map(NumBytes::from),
+            wasm_memory_threshold: NumBytes::from(value.wasm_memory_threshold),
+            on_low_wasm_memory_hook_status:
+                pb_canister_state_bits::OnLowWasmMemoryHookStatus::from_i32(
+                    value.on_low_wasm_memory_hook_status,
+                )
+                .unwrap_or_default()
+                .into(),
+            canister_version: value.canister_version,
+            canister_history,
+            task_queue: value
+                .task_queue
+                .into_iter()
+                .map(ExecutionTask::try_from)
+                .collect::<Result<_, _>>()?,
         })
     }
 }
@@ -1193,6 +1385,34 @@ impl TryFrom<pb_canister_state_bits::ExecutionStateBits> for ExecutionStateBits
     }
 }
 
+impl From<CanisterSnapshotBits> for pb_canister_state_bits::CanisterSnapshotBits {
+    fn from(item: CanisterSnapshotBits) -> Self {
+        Self {
+            taken_at_timestamp_nanos: item.taken_at_timestamp.as_nanos_since_unix_epoch(),
+            certified_data: item.certified_data,
+            execution_state_bits: Some((&item.execution_state_bits).into()),
+            stable_memory_size: item.stable_memory_size.get() as u64,
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::CanisterSnapshotBits> for CanisterSnapshotBits {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb_canister_state_bits::CanisterSnapshotBits) -> Result<Self, Self::Error> {
+        let execution_state_bits: ExecutionStateBits = try_from_option_field(
+            value.execution_state_bits,
+            "CanisterSnapshotBits::execution_state_bits",
+        )?;
+        Ok(Self {
+            taken_at_timestamp: Time::from_nanos_since_unix_epoch(value.taken_at_timestamp_nanos),
+            certified_data: value.certified_data,
+            execution_state_bits,
+            stable_memory_size: NumWasmPages::from(value.stable_memory_size as usize),
+        })
+    }
+}
+
 impl From<&BitcoinStateBits> for pb_bitcoin::BitcoinStateBits {
     fn from(item: &BitcoinStateBits) -> Self {
         pb_bitcoin::BitcoinStateBits {
@@ -1311,6 +1531,8 @@ mod test {
             memory_allocation: MemoryAllocation::default(),
             freeze_threshold: NumSeconds::from(0),
             cycles_balance: Cycles::zero(),
+            reserved_balance: Cycles::zero(),
+            reserved_balance_limit: None,
             status: CanisterStatus::Stopped,
             scheduled_as_first: 0,
             skipped_round_due_to_no_messages: 0,
@@ -1321,6 +1543,15 @@ mod test {
             stable_memory_size: NumWasmPages::from(0),
             heap_delta_debit: NumBytes::from(0),
             install_code_debit: NumInstructions::from(0),
+            global_timer_nanos: 0,
+            canister_log: Default::default(),
+            log_visibility: Default::default(),
+            wasm_memory_limit: None,
+            wasm_memory_threshold: NumBytes::from(0),
+            on_low_wasm_memory_hook_status: Default::default(),
+            canister_version: 0,
+            canister_history: Default::default(),
+            task_queue: vec![],
         };
 
         let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
@@ -1347,6 +1578,8 @@ mod test {
             memory_allocation: MemoryAllocation::default(),
             freeze_threshold: NumSeconds::from(0),
             cycles_balance: Cycles::zero(),
+            reserved_balance: Cycles::zero(),
+            reserved_balance_limit: None,
             status: CanisterStatus::Stopped,
             scheduled_as_first: 0,
             skipped_round_due_to_no_messages: 0,
@@ -1357,6 +1590,15 @@ mod test {
             stable_memory_size: NumWasmPages::from(0),
             heap_delta_debit: NumBytes::from(0),
             install_code_debit: NumInstructions::from(0),
+            global_timer_nanos: 0,
+            canister_log: Default::default(),
+            log_visibility: Default::default(),
+            wasm_memory_limit: None,
+            wasm_memory_threshold: NumBytes::from(0),
+            on_low_wasm_memory_hook_status: Default::default(),
+            canister_version: 0,
+            canister_history: Default::default(),
+            task_queue: vec![],
         };
 
         let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
@@ -1,3 +1,4 @@
+use ic_config::flag_status::FlagStatus;
 use ic_config::subnet_config::{SubnetConfig, SubnetConfigs};
 use ic_crypto_internal_seed::Seed;
 use ic_crypto_internal_threshold_sig_bls12381::api::{
@@ -8,11 +9,12 @@ use ic_crypto_internal_types::sign::threshold_sig::public_key::CspThresholdSigPu
 use ic_crypto_tree_hash::{flatmap, Label, LabeledTree, LabeledTree::SubTree};
 use ic_cycles_account_manager::CyclesAccountManager;
 pub use ic_error_types::{ErrorCode, UserError};
-use ic_execution_environment::ExecutionServices;
+use ic_execution_environment::{ExecutionServices, InstructionProfiles, MessageInstructionProfile};
 use ic_ic00_types::{self as ic00, CanisterIdRecord, InstallCodeArgs, Method, Payload};
 pub use ic_ic00_types::{CanisterInstallMode, CanisterSettingsArgs};
 use ic_interfaces::{
     certification::{Verifier, VerifierError},
+    certified_stream_store::{CertifiedStreamStore, EncodeStreamError},
     crypto::Signable,
     execution_environment::{IngressHistoryReader, QueryHandler},
     messaging::MessageRouting,
@@ -25,27 +27,33 @@ use ic_messaging::MessageRoutingImpl;
 use ic_metrics::MetricsRegistry;
 use ic_protobuf::registry::{
     provisional_whitelist::v1::ProvisionalWhitelist as PbProvisionalWhitelist,
-    routing_table::v1::RoutingTable as PbRoutingTable,
+    routing_table::v1::{
+        CanisterMigrations as PbCanisterMigrations, RoutingTable as PbRoutingTable,
+    },
+    subnet::v1::SubnetListRecord,
 };
 use ic_protobuf::types::v1::PrincipalId as PrincipalIdIdProto;
 use ic_protobuf::types::v1::SubnetId as SubnetIdProto;
 use ic_registry_client_fake::FakeRegistryClient;
 use ic_registry_client_helpers::subnet::SubnetListRegistry;
 use ic_registry_keys::{
-    make_provisional_whitelist_record_key, make_routing_table_record_key, ROOT_SUBNET_ID_KEY,
+    make_canister_migrations_record_key, make_provisional_whitelist_record_key,
+    make_routing_table_record_key, make_subnet_list_record_key, ROOT_SUBNET_ID_KEY,
 };
 use ic_registry_proto_data_provider::ProtoRegistryDataProvider;
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_routing_table::{
-    routing_table_insert_subnet, CanisterIdRange, CanisterIdRanges, RoutingTable,
+    routing_table_insert_subnet, CanisterIdRange, CanisterIdRanges, CanisterMigrations,
+    RoutingTable,
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::page_map::Buffer;
 use ic_replicated_state::{
     canister_state::{NumWasmPages, WASM_PAGE_SIZE_IN_BYTES},
-    Memory, PageMap, ReplicatedState,
+    CanisterState, Memory, PageMap, ReplicatedState,
 };
-use ic_state_manager::StateManagerImpl;
+use ic_state_layout::{CheckpointLayout, ReadOnly, RwPolicy};
+use ic_state_manager::{canister_migration, StateManagerImpl};
 use ic_test_utilities_metrics::fetch_histogram_stats;
 use ic_test_utilities_registry::{
     add_subnet_record, insert_initial_dkg_transcript, SubnetRecordBuilder,
@@ -57,7 +65,7 @@ use ic_types::crypto::{CombinedThresholdSig, CombinedThresholdSigOf, Signed};
 use ic_types::messages::Certificate;
 use ic_types::signature::ThresholdSignature;
 use ic_types::{
-    batch::{Batch, BatchPayload, IngressPayload},
+    batch::{Batch, BatchPayload, IngressPayload, XNetPayload},
     consensus::certification::Certification,
     messages::{
         Blob, HttpCallContent, HttpCanisterUpdate, HttpRequestEnvelope, SignedIngress, UserQuery,
@@ -97,6 +105,14 @@ impl Verifier for FakeVerifier {
 
 const GENESIS: Time = Time::from_nanos_since_unix_epoch(1_620_328_630_000_000_000);
 
+/// The type of the subnets of all state machines.
+const SUBNET_TYPE: SubnetType = SubnetType::System;
+
+/// The maximum number of rounds that [StateMachine::migrate_canister] executes
+/// on each subnet while waiting for the canister to stop or for the messages
+/// in flight to be delivered.
+const MAX_MIGRATION_ROUNDS: usize = 100;
+
 /// Constructs the initial version of the registry containing a subnet with the
 /// specified SUBNET_ID, with the node with the specified NODE_ID assigned to
 /// it.
@@ -104,9 +120,20 @@ fn make_single_node_registry(
     subnet_id: SubnetId,
     subnet_type: SubnetType,
     node_id: NodeId,
+) -> (Arc<ProtoRegistryDataProvider>, Arc<FakeRegistryClient>) {
+    make_multi_subnet_registry(&[(subnet_id, node_id)], subnet_type)
+}
+
+/// Constructs the initial version of the registry containing the specified
+/// single-node subnets, with a canister range assigned to each of them. The
+/// first subnet is the root subnet.
+fn make_multi_subnet_registry(
+    subnets: &[(SubnetId, NodeId)],
+    subnet_type: SubnetType,
 ) -> (Arc<ProtoRegistryDataProvider>, Arc<FakeRegistryClient>) {
     let registry_version = RegistryVersion::from(1);
     let data_provider = Arc::new(ProtoRegistryDataProvider::new());
+    let (subnet_id, _) = subnets[0];
 
     let root_subnet_id_proto = SubnetIdProto {
         principal_id: Some(PrincipalIdIdProto {
@@ -122,7 +149,9 @@ fn make_single_node_registry(
         .unwrap();
 
     let mut routing_table = RoutingTable::new();
-    routing_table_insert_subnet(&mut routing_table, subnet_id).unwrap();
+    for (subnet_id, _) in subnets {
+        routing_table_insert_subnet(&mut routing_table, *subnet_id).unwrap();
+    }
     let pb_routing_table = PbRoutingTable::from(routing_table);
     data_provider
         .add(
@@ -141,18 +170,42 @@ fn make_single_node_registry(
         .unwrap();
 
     // Set subnetwork list(needed for filling network_topology.nns_subnet_id)
-    let record = SubnetRecordBuilder::from(&[node_id])
-        .with_subnet_type(subnet_type)
-        .build();
+    for (subnet_id, node_id) in subnets {
+        let record = SubnetRecordBuilder::from(&[*node_id])
+            .with_subnet_type(subnet_type)
+            .build();
 
-    insert_initial_dkg_transcript(registry_version.get(), subnet_id, &record, &data_provider);
-    add_subnet_record(&data_provider, registry_version.get(), subnet_id, record);
+        insert_initial_dkg_transcript(registry_version.get(), *subnet_id, &record, &data_provider);
+        add_subnet_record(&data_provider, registry_version.get(), *subnet_id, record);
+    }
+    // `add_subnet_record` replaces the subnet list with the last subnet.
+    data_provider
+        .add(
+            &make_subnet_list_record_key(),
+            registry_version,
+            Some(SubnetListRecord {
+                subnets: subnets
+                    .iter()
+                    .map(|(subnet_id, _)| subnet_id.get().into_vec())
+                    .collect(),
+            }),
+        )
+        .unwrap();
 
     let registry_client = Arc::new(FakeRegistryClient::new(Arc::clone(&data_provider) as _));
     registry_client.update_to_latest_version();
     (data_provider, registry_client)
 }
 
+/// Converts a range of canister IDs into `CanisterIdRanges`.
+fn canister_id_ranges(canister_range: std::ops::RangeInclusive<CanisterId>) -> CanisterIdRanges {
+    CanisterIdRanges::try_from(vec![CanisterIdRange {
+        start: *canister_range.start(),
+        end: *canister_range.end(),
+    }])
+    .unwrap()
+}
+
 /// Convert an object into CBOR binary.
 fn into_cbor<R: Serialize>(r: &R) -> Vec<u8> {
     let mut ser = serde_cbor::Serializer::new(Vec::new());
@@ -174,6 +227,8 @@ pub struct StateMachine {
     metrics_registry: MetricsRegistry,
     ingress_history_reader: Box<dyn IngressHistoryReader>,
     query_handler: Arc<dyn QueryHandler<State = ReplicatedState>>,
+    instruction_profiling: FlagStatus,
+    instruction_profiles: InstructionProfiles,
     _runtime: Runtime,
     state_dir: TempDir,
     checkpoints_enabled: std::cell::Cell<bool>,
@@ -200,31 +255,89 @@ impl StateMachine {
     /// Constructs a new environment that uses a temporary directory for storing
     /// states.
     pub fn new() -> Self {
-        Self::setup_from_dir(
-            TempDir::new().expect("failed to create a temporary directory"),
-            0,
-            GENESIS,
-            None,
-        )
+        Self::new_single_subnet(None, FlagStatus::Disabled)
     }
 
     /// Constructs a new environment with the specified configuration.
     pub fn new_with_config(config: SubnetConfig) -> Self {
+        Self::new_single_subnet(Some(config), FlagStatus::Disabled)
+    }
+
+    /// Constructs a new environment that records how many instructions each
+    /// Wasm function of the executed canister messages uses.
+    /// See [StateMachine::instruction_profiles].
+    pub fn new_with_instruction_profiling() -> Self {
+        Self::new_single_subnet(None, FlagStatus::Enabled)
+    }
+
+    /// Constructs environments for `count` subnets that share a registry, so
+    /// that canisters on different subnets can call each other. Each subnet is
+    /// assigned its own canister range.
+    ///
+    /// Messages between subnets are only delivered by
+    /// [StateMachine::execute_xnet_from].
+    pub fn new_subnets(count: usize) -> Vec<Self> {
+        let subnets: Vec<_> = (1..=count as u64)
+            .map(|i| {
+                (
+                    SubnetId::from(PrincipalId::new_subnet_test_id(i)),
+                    NodeId::from(PrincipalId::new_node_test_id(i)),
+                )
+            })
+            .collect();
+        let (registry_data_provider, registry_client) =
+            make_multi_subnet_registry(&subnets, SUBNET_TYPE);
+        subnets
+            .into_iter()
+            .map(|(subnet_id, _)| {
+                Self::setup_from_dir(
+                    TempDir::new().expect("failed to create a temporary directory"),
+                    0,
+                    GENESIS,
+                    None,
+                    FlagStatus::Disabled,
+                    subnet_id,
+                    (
+                        Arc::clone(&registry_data_provider),
+                        Arc::clone(&registry_client),
+                    ),
+                )
+            })
+            .collect()
+    }
+
+    /// Constructs a new environment for a single subnet that uses a temporary
+    /// directory for storing states.
+    fn new_single_subnet(
+        subnet_config: Option<SubnetConfig>,
+        instruction_profiling: FlagStatus,
+    ) -> Self {
+        let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+        let node_id = NodeId::from(PrincipalId::new_node_test_id(1));
         Self::setup_from_dir(
             TempDir::new().expect("failed to create a temporary directory"),
             0,
             GENESIS,
-            Some(config),
+            subnet_config,
+            instruction_profiling,
+            subnet_id,
+            make_single_node_registry(subnet_id, SUBNET_TYPE, node_id),
         )
     }
 
-    /// Constructs and initializes a new state machine that uses the specified
-    /// directory for storing states.
+    /// Constructs and initializes a new state machine for the specified subnet
+    /// that uses the specified directory for storing states.
     fn setup_from_dir(
         state_dir: TempDir,
         nonce: u64,
         time: Time,
         subnet_config: Option<SubnetConfig>,
+        instruction_profiling: FlagStatus,
+        subnet_id: SubnetId,
+        (registry_data_provider, registry_client): (
+            Arc<ProtoRegistryDataProvider>,
+            Arc<FakeRegistryClient>,
+        ),
     ) -> Self {
         use slog::Drain;
 
@@ -240,21 +353,17 @@ impl StateMachine {
         let logger = slog::Logger::root(drain, slog::o!());
         let replica_logger: ReplicaLogger = logger.into();
 
-        let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
-        let node_id = NodeId::from(PrincipalId::new_node_test_id(1));
         let metrics_registry = MetricsRegistry::new();
-        let subnet_type = SubnetType::System;
+        let subnet_type = SUBNET_TYPE;
         let subnet_config = match subnet_config {
             Some(subnet_config) => subnet_config,
             None => SubnetConfigs::default().own_subnet_config(subnet_type),
         };
 
-        let (registry_data_provider, registry_client) =
-            make_single_node_registry(subnet_id, subnet_type, node_id);
-
         let sm_config = ic_config::state_manager::Config::new(state_dir.path().to_path_buf());
         let hypervisor_config = ic_config::execution_environment::Config {
-            canister_sandboxing_flag: ic_config::flag_status::FlagStatus::Disabled,
+            canister_sandboxing_flag: FlagStatus::Disabled,
+            instruction_profiling,
             ..Default::default()
         };
 
@@ -335,6 +444,8 @@ impl StateMachine {
             message_routing,
             metrics_registry,
             query_handler: execution_services.sync_query_handler,
+            instruction_profiling,
+            instruction_profiles: execution_services.instruction_profiles,
             _runtime: runtime,
             state_dir,
             // Note: state machine tests are commonly used for testing
@@ -347,7 +458,15 @@ impl StateMachine {
 
     /// Emulates a node restart, including checkpoint recovery.
     pub fn restart_node(self) -> Self {
-        Self::setup_from_dir(self.state_dir, self.nonce.get(), self.time.get(), None)
+        Self::setup_from_dir(
+            self.state_dir,
+            self.nonce.get(),
+            self.time.get(),
+            None,
+            self.instruction_profiling,
+            self.subnet_id,
+            (self.registry_data_provider, self.registry_client),
+        )
     }
 
     /// Same as [restart_node], but the subnet will have the specified `config`
@@ -358,6 +477,9 @@ impl StateMachine {
             self.nonce.get(),
             self.time.get(),
             Some(config),
+            self.instruction_profiling,
+            self.subnet_id,
+            (self.registry_data_provider, self.registry_client),
         )
     }
 
@@ -371,16 +493,76 @@ impl StateMachine {
         self.checkpoints_enabled.set(enabled)
     }
 
+    /// Returns the instruction profiles of the messages executed since the
+    /// last call of this function and clears them.
+    ///
+    /// Profiles are only recorded if the state machine was constructed with
+    /// [StateMachine::new_with_instruction_profiling].
+    pub fn instruction_profiles(&self) -> Vec<MessageInstructionProfile> {
+        self.instruction_profiles.take()
+    }
+
+    /// Writes the instruction profiles of all messages executed so far to the
+    /// given file in the folded stacks format, which can be rendered as a
+    /// flame graph by `inferno-flamegraph` or `flamegraph.pl`.
+    pub fn write_instruction_profiles(&self, path: &Path) -> std::io::Result<()> {
+        let mut file = std::fs::File::create(path)?;
+        self.instruction_profiles.write_folded(&mut file)
+    }
+
     /// Creates a new batch containing a single ingress message and sends it for
     /// processing to the replicated state machine.
     fn send_signed_ingress(&self, msg: SignedIngress) {
-        self.execute_block_with_ingress_payload(IngressPayload::from(vec![msg]))
+        self.execute_block_with_payload(BatchPayload {
+            ingress: IngressPayload::from(vec![msg]),
+            ..BatchPayload::default()
+        })
     }
 
     /// Triggers a single round of execution without any new inputs.  The state
     /// machine will invoke hearbeats and make progress on pending async calls.
     pub fn tick(&self) {
-        self.execute_block_with_ingress_payload(IngressPayload::default())
+        self.execute_block_with_payload(BatchPayload::default())
+    }
+
+    /// Triggers a single round of execution that inducts the stream from the
+    /// subnet of `remote` to the subnet of this state machine: the messages
+    /// this subnet has not inducted yet and the signals for the messages this
+    /// subnet sent to `remote`. The round is executed even if `remote` has no
+    /// stream to this subnet.
+    ///
+    /// Both state machines must have been constructed by
+    /// [StateMachine::new_subnets].
+    pub fn execute_xnet_from(&self, remote: &StateMachine) {
+        remote.certify_latest_state();
+        let begin = self
+            .state_manager
+            .get_latest_state()
+            .take()
+            .get_stream(&remote.subnet_id)
+            .map(|stream| stream.signals_end());
+
+        let mut xnet = XNetPayload::default();
+        match remote.state_manager.encode_certified_stream_slice(
+            self.subnet_id,
+            begin,
+            begin,
+            None,
+            None,
+        ) {
+            Ok(slice) => {
+                xnet.stream_slices.insert(remote.subnet_id, slice);
+            }
+            Err(EncodeStreamError::NoStreamForSubnet(_)) => {}
+            Err(err) => panic!(
+                "Failed to encode the stream from {} to {}: {}",
+                remote.subnet_id, self.subnet_id, err
+            ),
+        }
+        self.execute_block_with_payload(BatchPayload {
+            xnet,
+            ..BatchPayload::default()
+        })
     }
 
     /// Makes the state machine tick until there are no more messages in the system.
@@ -413,7 +595,7 @@ impl StateMachine {
         }
     }
 
-    fn execute_block_with_ingress_payload(&self, ingress: IngressPayload) {
+    fn execute_block_with_payload(&self, payload: BatchPayload) {
         let batch_number = self.message_routing.expected_batch_height();
 
         let mut seed = [0u8; 32];
@@ -423,10 +605,7 @@ impl StateMachine {
         let batch = Batch {
             batch_number,
             requires_full_state_hash: self.checkpoints_enabled.get(),
-            payload: BatchPayload {
-                ingress,
-                ..BatchPayload::default()
-            },
+            payload,
             randomness: Randomness::from(seed),
             ecdsa_subnet_public_keys: BTreeMap::new(),
             registry_version: self.registry_client.get_latest_version(),
@@ -657,13 +836,8 @@ impl StateMachine {
         wasm: Vec<u8>,
         payload: Vec<u8>,
     ) -> Result<(), UserError> {
-        let state = self.state_manager.get_latest_state().take();
-        let sender = state
-            .canister_state(&canister_id)
-            .and_then(|s| s.controllers().iter().next().cloned())
-            .unwrap_or_else(PrincipalId::new_anonymous);
         self.execute_ingress_as(
-            sender,
+            self.controller_or_anonymous(canister_id),
             ic00::IC_00,
             Method::InstallCode,
             InstallCodeArgs::new(mode, canister_id, wasm, payload, None, None, None).encode(),
@@ -671,6 +845,16 @@ impl StateMachine {
         .map(|_| ())
     }
 
+    /// Returns a controller of the specified canister or the anonymous
+    /// principal if the canister does not exist or has no controllers.
+    fn controller_or_anonymous(&self, canister_id: CanisterId) -> PrincipalId {
+        let state = self.state_manager.get_latest_state().take();
+        state
+            .canister_state(&canister_id)
+            .and_then(|s| s.controllers().iter().next().cloned())
+            .unwrap_or_else(PrincipalId::new_anonymous)
+    }
+
     /// Compiles specified WAT to Wasm and installs it for the canister using
     /// the specified ID in the provided install mode.
     fn install_wat_in_mode(
@@ -808,12 +992,7 @@ impl StateMachine {
         method: impl ToString,
         method_payload: Vec<u8>,
     ) -> Result<WasmResult, UserError> {
-        if self.state_manager.latest_state_height() > self.state_manager.latest_certified_height() {
-            let state_hashes = self.state_manager.list_state_hashes_to_certify();
-            let (height, hash) = state_hashes.last().unwrap();
-            self.state_manager
-                .deliver_state_certification(self.certify_hash(height, hash));
-        }
+        self.certify_latest_state();
 
         let path = SubTree(flatmap! {
             Label::from("canister") => SubTree(
@@ -844,6 +1023,16 @@ impl StateMachine {
         )
     }
 
+    /// Certifies the latest state, unless it is certified already.
+    fn certify_latest_state(&self) {
+        if self.state_manager.latest_state_height() > self.state_manager.latest_certified_height() {
+            let state_hashes = self.state_manager.list_state_hashes_to_certify();
+            let (height, hash) = state_hashes.last().unwrap();
+            self.state_manager
+                .deliver_state_certification(self.certify_hash(height, hash));
+        }
+    }
+
     fn certify_hash(&self, height: &Height, hash: &CryptoHashOfPartialState) -> Certification {
         let signature_bytes = Some(
             sign_message(
@@ -965,6 +1154,15 @@ impl StateMachine {
         )
     }
 
+    /// Starts the canister with the specified ID.
+    pub fn start_canister(&self, canister_id: CanisterId) -> Result<WasmResult, UserError> {
+        self.execute_ingress(
+            CanisterId::ic_00(),
+            "start_canister",
+            (CanisterIdRecord::from(canister_id)).encode(),
+        )
+    }
+
     /// Updates the routing table so that a range of canisters is assigned to
     /// the specified destination subnet.
     pub fn reroute_canister_range(
@@ -984,14 +1182,7 @@ impl StateMachine {
             .expect("missing routing table");
 
         routing_table
-            .assign_ranges(
-                CanisterIdRanges::try_from(vec![CanisterIdRange {
-                    start: *canister_range.start(),
-                    end: *canister_range.end(),
-                }])
-                .unwrap(),
-                destination,
-            )
+            .assign_ranges(canister_id_ranges(canister_range), destination)
             .expect("ranges are not well formed");
 
         self.registry_data_provider
@@ -1006,6 +1197,101 @@ impl StateMachine {
         assert_eq!(next_version, self.registry_client.get_latest_version());
     }
 
+    /// Records in the registry that a range of canisters is being migrated
+    /// from the `source` to the `destination` subnet, like the
+    /// `prepare_canister_migration` registry mutation does.
+    pub fn prepare_canister_migration(
+        &self,
+        canister_range: std::ops::RangeInclusive<CanisterId>,
+        source: SubnetId,
+        destination: SubnetId,
+    ) {
+        self.update_canister_migrations(|canister_migrations| {
+            canister_migrations
+                .insert_ranges(canister_id_ranges(canister_range), source, destination)
+                .expect("failed to insert canister migration")
+        });
+    }
+
+    /// Removes the migration of a range of canisters from the `source` to the
+    /// `destination` subnet from the registry, like the
+    /// `complete_canister_migration` registry mutation does.
+    pub fn complete_canister_migration(
+        &self,
+        canister_range: std::ops::RangeInclusive<CanisterId>,
+        source: SubnetId,
+        destination: SubnetId,
+    ) {
+        self.update_canister_migrations(|canister_migrations| {
+            canister_migrations
+                .remove_ranges(
+                    canister_id_ranges(canister_range),
+                    vec![source, destination],
+                )
+                .expect("failed to remove canister migration")
+        });
+    }
+
+    /// Applies `f` to the canister migrations in the latest registry version
+    /// and stores the result in a new registry version.
+    fn update_canister_migrations(&self, f: impl FnOnce(&mut CanisterMigrations)) {
+        use ic_registry_client_helpers::routing_table::RoutingTableRegistry;
+
+        let last_version = self.registry_client.get_latest_version();
+        let next_version = last_version.increment();
+
+        let mut canister_migrations = self
+            .registry_client
+            .get_canister_migrations(last_version)
+            .expect("malformed canister migrations")
+            .unwrap_or_default();
+        f(&mut canister_migrations);
+
+        self.registry_data_provider
+            .add(
+                &make_canister_migrations_record_key(),
+                next_version,
+                Some(PbCanisterMigrations::from(canister_migrations)),
+            )
+            .unwrap();
+        self.registry_client.update_to_latest_version();
+
+        assert_eq!(next_version, self.registry_client.get_latest_version());
+    }
+
+    /// Migrates the canister with the specified ID from the subnet of this
+    /// state machine to the subnet of `destination`, following the steps of
+    /// [canister_migration::migrate_canister]. The canister keeps its ID and
+    /// remains stopped on the destination subnet.
+    ///
+    /// Both state machines must have been constructed by
+    /// [StateMachine::new_subnets].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the migration fails, e.g. if the canister does
+    /// not stop or the streams are not drained within a reasonable number of
+    /// rounds.
+    pub fn migrate_canister(&self, canister_id: CanisterId, destination: &StateMachine) {
+        let export_dir = TempDir::new().expect("failed to create a temporary directory");
+        let env = CanisterMigrationSubnets {
+            source: self,
+            destination,
+        };
+        canister_migration::migrate_canister(
+            &env,
+            canister_id,
+            export_dir.path(),
+            MAX_MIGRATION_ROUNDS,
+        )
+        .unwrap_or_else(|e| panic!("Failed to migrate canister {}: {}", canister_id, e));
+    }
+
+    /// Returns the ID of the subnet of this state machine.
+    pub fn subnet_id(&self) -> SubnetId {
+        self.subnet_id
+    }
+
     /// Return the subnet_ids from the internal RegistryClient
     pub fn get_subnet_ids(&self) -> Vec<SubnetId> {
         self.registry_client
@@ -1105,3 +1391,96 @@ impl StateMachine {
         balance
     }
 }
+
+/// The source and the destination subnet of a canister migration between two
+/// state machines that share a registry.
+struct CanisterMigrationSubnets<'a> {
+    source: &'a StateMachine,
+    destination: &'a StateMachine,
+}
+
+impl canister_migration::CanisterMigrationEnvironment for CanisterMigrationSubnets<'_> {
+    fn source_subnet(&self) -> SubnetId {
+        self.source.subnet_id
+    }
+
+    fn destination_subnet(&self) -> SubnetId {
+        self.destination.subnet_id
+    }
+
+    fn prepare_canister_migration(&self, canister_id: CanisterId) {
+        self.source.prepare_canister_migration(
+            canister_id..=canister_id,
+            self.source.subnet_id,
+            self.destination.subnet_id,
+        );
+    }
+
+    fn reroute_canister(&self, canister_id: CanisterId) {
+        self.source
+            .reroute_canister_range(canister_id..=canister_id, self.destination.subnet_id);
+    }
+
+    fn complete_canister_migration(&self, canister_id: CanisterId) {
+        self.source.complete_canister_migration(
+            canister_id..=canister_id,
+            self.source.subnet_id,
+            self.destination.subnet_id,
+        );
+    }
+
+    fn stop_canister(&self, canister_id: CanisterId) {
+        self.source.send_ingress(
+            self.source.controller_or_anonymous(canister_id),
+            CanisterId::ic_00(),
+            "stop_canister",
+            (CanisterIdRecord::from(canister_id)).encode(),
+        );
+    }
+
+    fn execute_xnet_round(&self) {
+        self.destination.execute_xnet_from(self.source);
+        self.source.execute_xnet_from(self.destination);
+    }
+
+    fn execute_source_round(&self) {
+        self.source.tick();
+    }
+
+    fn checkpoint_source(
+        &self,
+    ) -> Result<CheckpointLayout<ReadOnly>, canister_migration::CanisterMigrationError> {
+        let checkpoints_enabled = self.source.checkpoints_enabled.replace(true);
+        self.source.tick();
+        self.source.checkpoints_enabled.set(checkpoints_enabled);
+        let state_manager = &self.source.state_manager;
+        Ok(state_manager
+            .state_layout()
+            .checkpoint(state_manager.latest_state_height())?)
+    }
+
+    fn source_state(&self) -> Arc<ReplicatedState> {
+        self.source.state_manager.get_latest_state().take()
+    }
+
+    fn destination_state(&self) -> Arc<ReplicatedState> {
+        self.destination.state_manager.get_latest_state().take()
+    }
+
+    fn destination_tip(
+        &self,
+    ) -> Result<CheckpointLayout<RwPolicy>, canister_migration::CanisterMigrationError> {
+        Ok(self
+            .destination
+            .state_manager
+            .state_layout()
+            .tip(ic_types::Height::new(0))?)
+    }
+
+    fn put_destination_canister(&self, canister_state: CanisterState) {
+        let state_manager = &self.destination.state_manager;
+        let (h, mut state) = state_manager.take_tip();
+        state.put_canister_state(canister_state);
+        state_manager.commit_and_certify(state, h.increment(), CertificationScope::Full);
+    }
+}
@@ -0,0 +1,155 @@
+use ic_state_machine_tests::{
+    CanisterId, IngressState, IngressStatus, MessageId, PrincipalId, StateMachine, WasmResult,
+};
+use std::convert::TryInto;
+
+// A canister that keeps a counter in the first four bytes of its memory.
+// `inc` increments the counter and `read` returns its value.
+const COUNTER_WAT: &str = r#"
+    (module
+        (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (func (export "canister_update inc")
+            (i32.store (i32.const 0) (i32.add (i32.load (i32.const 0)) (i32.const 1)))
+            (call $msg_reply))
+        (func (export "canister_query read")
+            (call $msg_reply_data_append (i32.const 0) (i32.const 4))
+            (call $msg_reply))
+        (memory 1)
+    )"#;
+
+// A canister that calls `inc` on the canister passed as argument to
+// `call_inc` and replies with "ok" if the call succeeded and with "rejected"
+// otherwise.
+const PROXY_WAT: &str = r#"
+    (module
+        (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+        (import "ic0" "msg_arg_data_copy" (func $msg_arg_data_copy (param i32 i32 i32)))
+        (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (import "ic0" "call_new"
+            (func $call_new (param i32 i32 i32 i32 i32 i32 i32 i32)))
+        (import "ic0" "call_perform" (func $call_perform (result i32)))
+        (func (export "canister_update call_inc")
+            (call $msg_arg_data_copy (i32.const 100) (i32.const 0) (call $msg_arg_data_size))
+            (call $call_new
+                (i32.const 100) (call $msg_arg_data_size)  ;; callee canister id
+                (i32.const 10) (i32.const 3)               ;; refers to "inc" on the heap
+                (i32.const 0) (i32.const 0)                ;; on_reply closure
+                (i32.const 1) (i32.const 0))               ;; on_reject closure
+            (drop (call $call_perform)))
+        (func $on_reply (param $env i32)
+            (call $msg_reply_data_append (i32.const 0) (i32.const 2))
+            (call $msg_reply))
+        (func $on_reject (param $env i32)
+            (call $msg_reply_data_append (i32.const 2) (i32.const 8))
+            (call $msg_reply))
+        (table funcref (elem $on_reply $on_reject))
+        (memory 1)
+        (data (i32.const 0) "okrejected")
+        (data (i32.const 10) "inc")
+    )"#;
+
+fn send_call_inc(env: &StateMachine, proxy: CanisterId, counter: CanisterId) -> MessageId {
+    env.send_ingress(
+        PrincipalId::new_anonymous(),
+        proxy,
+        "call_inc",
+        counter.get().to_vec(),
+    )
+}
+
+fn wasm_result(env: &StateMachine, msg_id: &MessageId) -> Option<WasmResult> {
+    match env.ingress_status(msg_id) {
+        IngressStatus::Known {
+            state: IngressState::Completed(result),
+            ..
+        } => Some(result),
+        IngressStatus::Known {
+            state: IngressState::Failed(error),
+            ..
+        } => panic!("Ingress message {} failed: {}", msg_id, error),
+        _ => None,
+    }
+}
+
+fn read_counter(env: &StateMachine, counter: CanisterId) -> u32 {
+    match env.query(counter, "read", vec![]).unwrap() {
+        WasmResult::Reply(bytes) => u32::from_le_bytes(bytes.try_into().unwrap()),
+        WasmResult::Reject(reason) => panic!("read rejected: {}", reason),
+    }
+}
+
+/// Exchanges the streams between `env_a` and `env_b` until the ingress
+/// message `msg_id` sent to `env_b` completes.
+fn await_xnet_result(env_a: &StateMachine, env_b: &StateMachine, msg_id: &MessageId) -> WasmResult {
+    for _ in 0..100 {
+        if let Some(result) = wasm_result(env_b, msg_id) {
+            return result;
+        }
+        env_a.execute_xnet_from(env_b);
+        env_b.execute_xnet_from(env_a);
+    }
+    panic!("Ingress message {} did not complete", msg_id)
+}
+
+#[test]
+fn canister_can_be_migrated_between_subnets() {
+    let mut subnets = StateMachine::new_subnets(2);
+    let env_b = subnets.pop().unwrap();
+    let env_a = subnets.pop().unwrap();
+
+    let counter = env_a.install_canister_wat(COUNTER_WAT, vec![], None);
+    let proxy = env_b.install_canister_wat(PROXY_WAT, vec![], None);
+
+    // A call across subnets goes through the streams.
+    let msg_id = send_call_inc(&env_b, proxy, counter);
+    assert_eq!(
+        await_xnet_result(&env_a, &env_b, &msg_id),
+        WasmResult::Reply(b"ok".to_vec())
+    );
+    assert_eq!(read_counter(&env_a, counter), 1);
+
+    // A call that is still in flight when the counter is stopped for the
+    // migration is rejected.
+    let msg_id = send_call_inc(&env_b, proxy, counter);
+    env_b.tick();
+    env_a.stop_canister(counter).unwrap();
+    env_a.migrate_canister(counter, &env_b);
+    assert_eq!(
+        await_xnet_result(&env_a, &env_b, &msg_id),
+        WasmResult::Reply(b"rejected".to_vec())
+    );
+
+    assert!(!env_a.canister_exists(counter));
+    assert!(env_b.canister_exists(counter));
+
+    // The migrated canister keeps its state and becomes a local callee of the
+    // proxy once it is started on its new subnet.
+    env_b.start_canister(counter).unwrap();
+    let msg_id = send_call_inc(&env_b, proxy, counter);
+    env_b.tick();
+    assert_eq!(
+        wasm_result(&env_b, &msg_id),
+        Some(WasmResult::Reply(b"ok".to_vec()))
+    );
+    assert_eq!(read_counter(&env_b, counter), 2);
+}
+
+#[test]
+fn migrated_canister_survives_node_restart() {
+    let mut subnets = StateMachine::new_subnets(2);
+    let env_b = subnets.pop().unwrap();
+    let env_a = subnets.pop().unwrap();
+
+    let counter = env_a.install_canister_wat(COUNTER_WAT, vec![], None);
+    env_a
+        .execute_ingress(counter, "inc", vec![])
+        .expect("failed to increment the counter");
+    env_a.migrate_canister(counter, &env_b);
+
+    let env_b = env_b.restart_node();
+    assert!(env_b.canister_exists(counter));
+    env_b.start_canister(counter).unwrap();
+    assert_eq!(read_counter(&env_b, counter), 1);
+}
@@ -269,6 +269,10 @@ fn test_canister_out_of_cycles() {
             compute_allocation: Some(candid::Nat::from(1)),
             memory_allocation: None,
             freezing_threshold: None,
+            log_visibility: None,
+            wasm_memory_limit: None,
+            wasm_memory_threshold: None,
+            reserved_cycles_limit: None,
         }),
     );
 
@@ -431,6 +435,29 @@ fn test_state_machine_consumes_instructions() {
     );
 }
 
+#[test]
+fn test_instruction_profiling() {
+    let env = StateMachine::new_with_instruction_profiling();
+
+    let canister_id = env.install_canister_wat(TEST_CANISTER, vec![], None);
+    // Discard the profiles of the canister installation.
+    env.instruction_profiles();
+    env.execute_ingress(canister_id, "inc", vec![]).unwrap();
+
+    let profiles = env.instruction_profiles();
+    assert_eq!(profiles.len(), 1);
+    let profile = &profiles[0];
+    assert_eq!(profile.canister_id, canister_id);
+    assert_eq!(profile.function, "canister_update inc");
+    // `$inc` is the first function after the six imports.
+    assert!(profile.profile.stacks[&vec![6]] > 0);
+    let expected_prefix = format!("{}:canister_update inc;func[6] ", canister_id);
+    assert!(profile
+        .to_folded()
+        .lines()
+        .any(|line| line.starts_with(&expected_prefix)));
+}
+
 #[test]
 fn test_set_stable_memory() {
     let env = StateMachine::new();
@@ -0,0 +1,595 @@
+//! Moves the state of a stopped canister between the checkpoints of two
+//! subnets.
+//!
+//! A canister is migrated by exporting its files from a checkpoint of the
+//! source subnet and importing them into a checkpoint (or the tip) of the
+//! destination subnet. The canister keeps its ID. Only stopped canisters
+//! without any messages in their queues can be migrated: such canisters have
+//! no open call contexts and therefore no outstanding requests or responses
+//! that would need to follow them. Canister snapshots are stored outside of
+//! the canister directory and are not migrated, so canisters that have
+//! snapshots cannot be migrated either.
+//!
+//! [`migrate_canister`] performs all steps of a migration in order: it
+//! records the migration in the registry, so that the `StreamHandler` of
+//! either subnet rejects or reroutes the messages in flight, moves the
+//! canister, waits until the streams between the two subnets are drained of
+//! its messages and finally removes the migration from the registry.
+use crate::{checkpoint::load_canister_state, CheckpointError};
+use ic_base_types::{CanisterId, SubnetId};
+use ic_replicated_state::{CanisterState, CanisterStatus, ReplicatedState};
+use ic_state_layout::{
+    error::LayoutError, CanisterLayout, CheckpointLayout, ReadOnly, ReadPolicy, RwPolicy,
+};
+use ic_utils::fs::copy_file_sparse;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Errors returned when exporting or importing a canister.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CanisterMigrationError {
+    /// Reading or writing the canister files failed.
+    Checkpoint(CheckpointError),
+    /// The canister to export does not exist in the checkpoint.
+    CanisterNotFound(CanisterId),
+    /// The canister to import already exists in the checkpoint.
+    CanisterAlreadyExists(CanisterId),
+    /// The canister is not stopped.
+    CanisterNotStopped(CanisterId),
+    /// The canister has messages in its input or output queues.
+    CanisterHasMessages(CanisterId),
+    /// The canister has snapshots, which are not migrated.
+    CanisterHasSnapshots(CanisterId),
+    /// The source subnet still hosts the canister after it was rerouted.
+    CanisterNotRemoved(CanisterId),
+    /// The streams between the two subnets still contain messages from or to
+    /// the canister.
+    MessagesInFlight(CanisterId),
+}
+
+impl std::error::Error for CanisterMigrationError {}
+
+impl std::fmt::Display for CanisterMigrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CanisterMigrationError::Checkpoint(err) => write!(f, "{}", err),
+            CanisterMigrationError::CanisterNotFound(canister_id) => {
+                write!(f, "canister {} not found", canister_id)
+            }
+            CanisterMigrationError::CanisterAlreadyExists(canister_id) => {
+                write!(f, "canister {} already exists", canister_id)
+            }
+            CanisterMigrationError::CanisterNotStopped(canister_id) => {
+                write!(f, "canister {} is not stopped", canister_id)
+            }
+            CanisterMigrationError::CanisterHasMessages(canister_id) => {
+                write!(f, "canister {} has messages in its queues", canister_id)
+            }
+            CanisterMigrationError::CanisterHasSnapshots(canister_id) => {
+                write!(f, "canister {} has snapshots", canister_id)
+            }
+            CanisterMigrationError::CanisterNotRemoved(canister_id) => {
+                write!(
+                    f,
+                    "canister {} was not removed from the source subnet",
+                    canister_id
+                )
+            }
+            CanisterMigrationError::MessagesInFlight(canister_id) => {
+                write!(
+                    f,
+                    "messages of canister {} are still in flight",
+                    canister_id
+                )
+            }
+        }
+    }
+}
+
+impl From<CheckpointError> for CanisterMigrationError {
+    fn from(err: CheckpointError) -> Self {
+        CanisterMigrationError::Checkpoint(err)
+    }
+}
+
+impl From<LayoutError> for CanisterMigrationError {
+    fn from(err: LayoutError) -> Self {
+        CanisterMigrationError::Checkpoint(err.into())
+    }
+}
+
+/// The registry updates and the rounds on the source and the destination
+/// subnet that a canister migration consists of. [`migrate_canister`]
+/// performs them in the order that keeps the messages of the canister
+/// routable throughout the migration.
+pub trait CanisterMigrationEnvironment {
+    /// Returns the ID of the subnet the canister is migrated from.
+    fn source_subnet(&self) -> SubnetId;
+
+    /// Returns the ID of the subnet the canister is migrated to.
+    fn destination_subnet(&self) -> SubnetId;
+
+    /// Records the migration of the canister in the registry, like the
+    /// `prepare_canister_migration` registry mutation does.
+    fn prepare_canister_migration(&self, canister_id: CanisterId);
+
+    /// Assigns the canister to the destination subnet in the routing table,
+    /// like the `reroute_canister_ranges` registry mutation does.
+    fn reroute_canister(&self, canister_id: CanisterId);
+
+    /// Removes the migration of the canister from the registry, like the
+    /// `complete_canister_migration` registry mutation does.
+    fn complete_canister_migration(&self, canister_id: CanisterId);
+
+    /// Requests the source subnet to stop the canister.
+    fn stop_canister(&self, canister_id: CanisterId);
+
+    /// Executes a round on both subnets, inducting the streams between them.
+    fn execute_xnet_round(&self);
+
+    /// Executes a round on the source subnet.
+    fn execute_source_round(&self);
+
+    /// Executes a round on the source subnet that ends with a checkpoint and
+    /// returns the layout of that checkpoint.
+    fn checkpoint_source(&self) -> Result<CheckpointLayout<ReadOnly>, CanisterMigrationError>;
+
+    /// Returns the latest state of the source subnet.
+    fn source_state(&self) -> Arc<ReplicatedState>;
+
+    /// Returns the latest state of the destination subnet.
+    fn destination_state(&self) -> Arc<ReplicatedState>;
+
+    /// Returns the layout of the tip of the destination subnet.
+    fn destination_tip(&self) -> Result<CheckpointLayout<RwPolicy>, CanisterMigrationError>;
+
+    /// Adds the imported canister to the state of the destination subnet.
+    fn put_destination_canister(&self, canister_state: CanisterState);
+}
+
+/// Migrates the canister with the specified ID from the source to the
+/// destination subnet of `env`, using `export_dir` to hold the exported
+/// canister files. The canister keeps its ID and remains stopped on the
+/// destination subnet.
+///
+/// The migration consists of the following steps:
+///  1. The migration is recorded in the registry, so that the stream handlers
+///     of both subnets accept messages for and from the canister on either
+///     subnet.
+///  2. The canister is stopped, delivering the messages in flight between the
+///     two subnets until it has no open call contexts.
+///  3. The canister is exported from a checkpoint of the source subnet.
+///  4. The canister is rerouted to the destination subnet, which makes the
+///     source subnet drop the stopped canister.
+///  5. The exported canister is imported on the destination subnet.
+///  6. The streams between the two subnets are drained of messages from and
+///     to the canister: the stream handlers reject requests and reroute
+///     responses that arrive at the source subnet.
+///  7. The migration is removed from the registry.
+///
+/// Fails if the canister does not exist or has snapshots, if it does not stop
+/// or its messages are not drained within `max_rounds` rounds, or if any of
+/// the steps fails.
+pub fn migrate_canister<E: CanisterMigrationEnvironment>(
+    env: &E,
+    canister_id: CanisterId,
+    export_dir: &Path,
+    max_rounds: usize,
+) -> Result<(), CanisterMigrationError> {
+    let source_subnet = env.source_subnet();
+    let destination_subnet = env.destination_subnet();
+
+    let source_state = env.source_state();
+    if source_state.canister_state(&canister_id).is_none() {
+        return Err(CanisterMigrationError::CanisterNotFound(canister_id));
+    }
+    if source_state.canister_snapshots.count_snapshots(canister_id) > 0 {
+        return Err(CanisterMigrationError::CanisterHasSnapshots(canister_id));
+    }
+
+    env.prepare_canister_migration(canister_id);
+
+    env.stop_canister(canister_id);
+    let stopped = execute_xnet_rounds_until(env, max_rounds, || {
+        env.source_state()
+            .canister_state(&canister_id)
+            .map_or(false, |canister| {
+                matches!(canister.system_state.status, CanisterStatus::Stopped)
+            })
+    });
+    if !stopped {
+        return Err(CanisterMigrationError::CanisterNotStopped(canister_id));
+    }
+
+    export_canister(&env.checkpoint_source()?, &canister_id, export_dir)?;
+
+    env.reroute_canister(canister_id);
+    env.execute_source_round();
+    if env.source_state().canister_state(&canister_id).is_some() {
+        return Err(CanisterMigrationError::CanisterNotRemoved(canister_id));
+    }
+
+    let canister_state = import_canister(&env.destination_tip()?, &canister_id, export_dir)?;
+    env.put_destination_canister(canister_state);
+
+    let drained = execute_xnet_rounds_until(env, max_rounds, || {
+        !has_messages_in_flight(&env.source_state(), canister_id, destination_subnet)
+            && !has_messages_in_flight(&env.destination_state(), canister_id, source_subnet)
+    });
+    if !drained {
+        return Err(CanisterMigrationError::MessagesInFlight(canister_id));
+    }
+
+    env.complete_canister_migration(canister_id);
+    Ok(())
+}
+
+/// Executes rounds on both subnets of `env` until `condition` holds. Returns
+/// false if it does not hold after `max_rounds` rounds.
+fn execute_xnet_rounds_until<E: CanisterMigrationEnvironment>(
+    env: &E,
+    max_rounds: usize,
+    condition: impl Fn() -> bool,
+) -> bool {
+    for _round in 0..max_rounds {
+        if condition() {
+            return true;
+        }
+        env.execute_xnet_round();
+    }
+    condition()
+}
+
+/// Returns true if the stream from the subnet with the specified `state` to
+/// `remote_subnet` contains messages from or to the specified canister.
+pub fn has_messages_in_flight(
+    state: &ReplicatedState,
+    canister_id: CanisterId,
+    remote_subnet: SubnetId,
+) -> bool {
+    state.get_stream(&remote_subnet).map_or(false, |stream| {
+        stream
+            .messages()
+            .iter()
+            .any(|(_, msg)| msg.sender() == canister_id || msg.receiver() == canister_id)
+    })
+}
+
+/// Copies the files of the canister with the specified ID from the checkpoint
+/// at `checkpoint_layout` into the directory `dst`, which is created if it
+/// does not exist.
+///
+/// Fails if the canister is not stopped, has messages in its queues or has
+/// snapshots.
+pub fn export_canister<P: ReadPolicy>(
+    checkpoint_layout: &CheckpointLayout<P>,
+    canister_id: &CanisterId,
+    dst: &Path,
+) -> Result<(), CanisterMigrationError> {
+    if !checkpoint_layout.canister_ids()?.contains(canister_id) {
+        return Err(CanisterMigrationError::CanisterNotFound(*canister_id));
+    }
+    let canister_layout = checkpoint_layout.canister(canister_id)?;
+    let (canister_state, _) =
+        load_canister_state(&canister_layout, canister_id, checkpoint_layout.height())?;
+    validate_migratable(&canister_state)?;
+    if checkpoint_layout
+        .snapshot_ids()?
+        .iter()
+        .any(|snapshot_id| snapshot_id.canister_id() == *canister_id)
+    {
+        return Err(CanisterMigrationError::CanisterHasSnapshots(*canister_id));
+    }
+
+    let dst_layout = CanisterLayout::<RwPolicy>::new(dst.to_path_buf())?;
+    copy_canister_files(&canister_layout.raw_path(), &dst_layout.raw_path())
+}
+
+/// Copies the files of a canister exported by [`export_canister`] from the
+/// directory `src` into the checkpoint at `checkpoint_layout`, which is
+/// usually the tip or a scratchpad. Returns the imported canister state.
+///
+/// Fails if the checkpoint already contains the canister or if the exported
+/// canister is not stopped or has messages in its queues.
+pub fn import_canister(
+    checkpoint_layout: &CheckpointLayout<RwPolicy>,
+    canister_id: &CanisterId,
+    src: &Path,
+) -> Result<CanisterState, CanisterMigrationError> {
+    if checkpoint_layout.canister_ids()?.contains(canister_id)
+        && !checkpoint_layout.canister(canister_id)?.is_marked_deleted()
+    {
+        return Err(CanisterMigrationError::CanisterAlreadyExists(*canister_id));
+    }
+    let src_layout = CanisterLayout::<ReadOnly>::new(src.to_path_buf())?;
+    let (canister_state, _) =
+        load_canister_state(&src_layout, canister_id, checkpoint_layout.height())?;
+    validate_migratable(&canister_state)?;
+
+    let canister_layout = checkpoint_layout.canister(canister_id)?;
+    if canister_layout.is_marked_deleted() {
+        std::fs::remove_dir_all(canister_layout.raw_path())
+            .map_err(|err| io_error(&canister_layout.raw_path(), "failed to remove", err))?;
+    }
+    let canister_layout = checkpoint_layout.canister(canister_id)?;
+    copy_canister_files(&src_layout.raw_path(), &canister_layout.raw_path())?;
+
+    // Load the state again from its new location so that the page maps are
+    // backed by the imported files.
+    let (canister_state, _) =
+        load_canister_state(&canister_layout, canister_id, checkpoint_layout.height())?;
+    Ok(canister_state)
+}
+
+/// Checks that the canister can be moved to another subnet without moving
+/// any messages along with it.
+fn validate_migratable(canister_state: &CanisterState) -> Result<(), CanisterMigrationError> {
+    let canister_id = canister_state.canister_id();
+    if !matches!(canister_state.system_state.status, CanisterStatus::Stopped) {
+        return Err(CanisterMigrationError::CanisterNotStopped(canister_id));
+    }
+    if canister_state.has_input() || canister_state.has_output() {
+        return Err(CanisterMigrationError::CanisterHasMessages(canister_id));
+    }
+    Ok(())
+}
+
+/// Copies all files of the canister directory `src` into `dst` and makes the
+/// copies writable.
+fn copy_canister_files(src: &Path, dst: &Path) -> Result<(), CanisterMigrationError> {
+    let entries = src
+        .read_dir()
+        .map_err(|err| io_error(src, "failed to read directory", err))?;
+    for entry in entries {
+        let entry = entry.map_err(|err| io_error(src, "failed to read directory entry", err))?;
+        let src_file = entry.path();
+        let dst_file = dst.join(entry.file_name());
+        if !src_file.is_file() {
+            return Err(CanisterMigrationError::Checkpoint(
+                CheckpointError::CorruptedLayout {
+                    path: src_file,
+                    message: "canister directories must only contain files".to_string(),
+                },
+            ));
+        }
+        copy_file_sparse(&src_file, &dst_file)
+            .map_err(|err| io_error(&dst_file, "failed to copy file", err))?;
+        let mut permissions = dst_file
+            .metadata()
+            .map_err(|err| io_error(&dst_file, "failed to get file metadata", err))?
+            .permissions();
+        permissions.set_readonly(false);
+        std::fs::set_permissions(&dst_file, permissions)
+            .map_err(|err| io_error(&dst_file, "failed to set file permissions", err))?;
+    }
+    Ok(())
+}
+
+fn io_error(path: &Path, message: &str, err: std::io::Error) -> CanisterMigrationError {
+    CanisterMigrationError::Checkpoint(CheckpointError::IoError {
+        path: path.to_path_buf(),
+        message: message.to_string(),
+        io_err: err.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{checkpoint::make_checkpoint, CheckpointMetrics, NUMBER_OF_CHECKPOINT_THREADS};
+    use ic_base_types::NumSeconds;
+    use ic_registry_subnet_type::SubnetType;
+    use ic_replicated_state::{
+        canister_state::execution_state::WasmMetadata, CanisterSnapshot, ExportedFunctions, Memory,
+        SnapshotId,
+    };
+    use ic_state_layout::StateLayout;
+    use ic_test_utilities::{
+        state::new_canister_state,
+        types::ids::{canister_test_id, subnet_test_id, user_test_id},
+        with_test_replica_logger,
+    };
+    use ic_types::{Cycles, Height, Time};
+    use ic_wasm_types::CanisterModule;
+    use std::collections::BTreeSet;
+    use tempfile::Builder;
+
+    const INITIAL_CYCLES: Cycles = Cycles::new(1 << 36);
+    const HEIGHT: Height = Height::new(42);
+
+    /// Returns a state containing a single canister with the specified status.
+    fn state_with_canister(canister_id: CanisterId, status: CanisterStatus) -> ReplicatedState {
+        let mut state = ReplicatedState::new_rooted_at(
+            subnet_test_id(1),
+            SubnetType::Application,
+            "NOT_USED".into(),
+        );
+        let mut canister_state = new_canister_state(
+            canister_id,
+            user_test_id(24).get(),
+            INITIAL_CYCLES,
+            NumSeconds::from(100_000),
+        );
+        canister_state.system_state.status = status;
+        state.put_canister_state(canister_state);
+        state
+    }
+
+    /// Creates a checkpoint of `state` at `HEIGHT` under `root`.
+    fn make_checkpoint_of(
+        log: &ic_logger::ReplicaLogger,
+        root: &Path,
+        state: &ReplicatedState,
+    ) -> StateLayout {
+        let layout = StateLayout::new(log.clone(), root.to_path_buf());
+        let metrics_registry = ic_metrics::MetricsRegistry::new();
+        make_checkpoint(
+            state,
+            HEIGHT,
+            &layout,
+            log,
+            &CheckpointMetrics::new(&metrics_registry),
+            &mut scoped_threadpool::Pool::new(NUMBER_OF_CHECKPOINT_THREADS),
+        )
+        .unwrap();
+        layout
+    }
+
+    /// Creates a checkpoint at `HEIGHT` under `root` containing a single
+    /// canister with the specified status.
+    fn make_checkpoint_with_canister(
+        log: &ic_logger::ReplicaLogger,
+        root: &Path,
+        canister_id: CanisterId,
+        status: CanisterStatus,
+    ) -> StateLayout {
+        make_checkpoint_of(log, root, &state_with_canister(canister_id, status))
+    }
+
+    #[test]
+    fn can_export_and_import_stopped_canister() {
+        with_test_replica_logger(|log| {
+            let src_root = Builder::new().prefix("test").tempdir().unwrap();
+            let dst_root = Builder::new().prefix("test").tempdir().unwrap();
+            let export_dir = Builder::new().prefix("test").tempdir().unwrap();
+            let canister_id = canister_test_id(10);
+
+            let src_layout = make_checkpoint_with_canister(
+                &log,
+                src_root.path(),
+                canister_id,
+                CanisterStatus::Stopped,
+            );
+            export_canister(
+                &src_layout.checkpoint(HEIGHT).unwrap(),
+                &canister_id,
+                export_dir.path(),
+            )
+            .unwrap();
+
+            let dst_layout = StateLayout::new(log, dst_root.path().to_path_buf());
+            let tip = dst_layout.tip(Height::new(0)).unwrap();
+            let canister_state = import_canister(&tip, &canister_id, export_dir.path()).unwrap();
+
+            assert_eq!(canister_state.canister_id(), canister_id);
+            assert_eq!(canister_state.system_state.balance(), INITIAL_CYCLES);
+            assert!(matches!(
+                canister_state.system_state.status,
+                CanisterStatus::Stopped
+            ));
+            assert_eq!(tip.canister_ids().unwrap(), vec![canister_id]);
+        });
+    }
+
+    #[test]
+    fn cannot_export_running_canister() {
+        with_test_replica_logger(|log| {
+            let root = Builder::new().prefix("test").tempdir().unwrap();
+            let export_dir = Builder::new().prefix("test").tempdir().unwrap();
+            let canister_id = canister_test_id(10);
+
+            let layout = make_checkpoint_with_canister(
+                &log,
+                root.path(),
+                canister_id,
+                CanisterStatus::new_running(),
+            );
+            assert_eq!(
+                export_canister(
+                    &layout.checkpoint(HEIGHT).unwrap(),
+                    &canister_id,
+                    export_dir.path(),
+                ),
+                Err(CanisterMigrationError::CanisterNotStopped(canister_id))
+            );
+        });
+    }
+
+    #[test]
+    fn cannot_export_canister_with_snapshots() {
+        with_test_replica_logger(|log| {
+            let root = Builder::new().prefix("test").tempdir().unwrap();
+            let export_dir = Builder::new().prefix("test").tempdir().unwrap();
+            let canister_id = canister_test_id(10);
+
+            let mut state = state_with_canister(canister_id, CanisterStatus::Stopped);
+            let snapshot = CanisterSnapshot {
+                canister_id,
+                taken_at_timestamp: Time::from_nanos_since_unix_epoch(1_000_000_000),
+                binary: CanisterModule::new(vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]),
+                wasm_memory: Memory::default(),
+                stable_memory: Memory::default(),
+                exported_globals: vec![],
+                exports: ExportedFunctions::new(BTreeSet::new()),
+                metadata: WasmMetadata::default(),
+                certified_data: vec![],
+            };
+            state
+                .canister_snapshots
+                .push(SnapshotId::new(canister_id, 0), Arc::new(snapshot));
+            let layout = make_checkpoint_of(&log, root.path(), &state);
+
+            assert_eq!(
+                export_canister(
+                    &layout.checkpoint(HEIGHT).unwrap(),
+                    &canister_id,
+                    export_dir.path(),
+                ),
+                Err(CanisterMigrationError::CanisterHasSnapshots(canister_id))
+            );
+        });
+    }
+
+    #[test]
+    fn cannot_export_missing_canister() {
+        with_test_replica_logger(|log| {
+            let root = Builder::new().prefix("test").tempdir().unwrap();
+            let export_dir = Builder::new().prefix("test").tempdir().unwrap();
+
+            let layout = make_checkpoint_with_canister(
+                &log,
+                root.path(),
+                canister_test_id(10),
+                CanisterStatus::Stopped,
+            );
+            assert_eq!(
+                export_canister(
+                    &layout.checkpoint(HEIGHT).unwrap(),
+                    &canister_test_id(11),
+                    export_dir.path(),
+                ),
+                Err(CanisterMigrationError::CanisterNotFound(canister_test_id(
+                    11
+                )))
+            );
+        });
+    }
+
+    #[test]
+    fn cannot_import_existing_canister() {
+        with_test_replica_logger(|log| {
+            let root = Builder::new().prefix("test").tempdir().unwrap();
+            let export_dir = Builder::new().prefix("test").tempdir().unwrap();
+            let canister_id = canister_test_id(10);
+
+            let layout = make_checkpoint_with_canister(
+                &log,
+                root.path(),
+                canister_id,
+                CanisterStatus::Stopped,
+            );
+            export_canister(
+                &layout.checkpoint(HEIGHT).unwrap(),
+                &canister_id,
+                export_dir.path(),
+            )
+            .unwrap();
+
+            let scratchpad = layout.checkpoint_to_scratchpad(HEIGHT).unwrap();
+            assert_eq!(
+                import_canister(&scratchpad, &canister_id, export_dir.path()).map(|_| ()),
+                Err(CanisterMigrationError::CanisterAlreadyExists(canister_id))
+            );
+        });
+    }
+}
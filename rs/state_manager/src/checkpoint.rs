@@ -10,14 +10,14 @@ use ic_replicated_state::{
     bitcoin_state::{BitcoinState, UtxoSet},
     canister_state::execution_state::WasmBinary,
     page_map::PageMap,
-    CanisterMetrics, CanisterState, ExecutionState, NumWasmPages, ReplicatedState, SchedulerState,
-    SystemState,
+    CanisterMetrics, CanisterSnapshot, CanisterSnapshots, CanisterState, CanisterTimer,
+    ExecutionState, NumWasmPages, ReplicatedState, SchedulerState, SnapshotId, SystemState,
 };
 use ic_state_layout::{
-    BitcoinStateBits, BitcoinStateLayout, CanisterLayout, CanisterStateBits, CheckpointLayout,
-    ExecutionStateBits, ReadPolicy, RwPolicy, StateLayout,
+    BitcoinStateBits, BitcoinStateLayout, CanisterLayout, CanisterSnapshotBits, CanisterStateBits,
+    CheckpointLayout, ExecutionStateBits, ReadPolicy, RwPolicy, StateLayout,
 };
-use ic_types::Height;
+use ic_types::{ExecutionRound, Height};
 use ic_utils::fs::defrag_file_partially;
 use ic_utils::thread::parallel_map;
 use rand::prelude::SliceRandom;
@@ -25,6 +25,7 @@ use rand::{Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
 use std::collections::BTreeMap;
 use std::os::unix::prelude::MetadataExt;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{
     convert::{From, TryFrom},
@@ -58,7 +59,7 @@ pub fn make_checkpoint(
             .make_checkpoint_step_duration
             .with_label_values(&["serialize_to_tip"])
             .start_timer();
-        serialize_to_tip(log, state, &tip, thread_pool)?;
+        serialize_to_tip(log, state, layout, &tip, thread_pool)?;
     }
 
     {
@@ -96,6 +97,7 @@ pub fn make_checkpoint(
 fn serialize_to_tip(
     log: &ReplicaLogger,
     state: &ReplicatedState,
+    layout: &StateLayout,
     tip: &CheckpointLayout<RwPolicy>,
     thread_pool: &mut scoped_threadpool::Pool,
 ) -> Result<(), CheckpointError> {
@@ -113,6 +115,18 @@ fn serialize_to_tip(
         result?;
     }
 
+    let results = parallel_map(
+        thread_pool,
+        state.canister_snapshots.iter(),
+        |(snapshot_id, snapshot)| {
+            serialize_snapshot_to_tip(log, snapshot_id, snapshot, layout, tip)
+        },
+    );
+
+    for result in results.into_iter() {
+        result?;
+    }
+
     serialize_bitcoin_state_to_tip(state.bitcoin(), &tip.bitcoin()?)?;
 
     Ok(())
@@ -190,6 +204,8 @@ fn serialize_canister_to_tip(
                 memory_allocation: canister_state.system_state.memory_allocation,
                 freeze_threshold: canister_state.system_state.freeze_threshold,
                 cycles_balance: canister_state.system_state.balance(),
+                reserved_balance: canister_state.system_state.reserved_balance(),
+                reserved_balance_limit: canister_state.system_state.reserved_balance_limit(),
                 execution_state_bits,
                 status: canister_state.system_state.status.clone(),
                 scheduled_as_first: canister_state
@@ -217,12 +233,139 @@ fn serialize_canister_to_tip(
                     .unwrap_or_else(|| NumWasmPages::from(0)),
                 heap_delta_debit: canister_state.scheduler_state.heap_delta_debit,
                 install_code_debit: canister_state.scheduler_state.install_code_debit,
+                global_timer_nanos: canister_state
+                    .system_state
+                    .global_timer
+                    .to_nanos_since_unix_epoch(),
+                canister_log: canister_state.system_state.canister_log.clone(),
+                log_visibility: canister_state.system_state.log_visibility,
+                wasm_memory_limit: canister_state.system_state.wasm_memory_limit,
+                wasm_memory_threshold: canister_state.system_state.wasm_memory_threshold,
+                on_low_wasm_memory_hook_status: canister_state
+                    .system_state
+                    .on_low_wasm_memory_hook_status,
+                canister_version: canister_state.system_state.canister_version,
+                canister_history: canister_state.system_state.canister_history().clone(),
+                // Paused executions are aborted before the checkpoint and
+                // tasks of system methods are enqueued again in each round,
+                // so only the aborted executions need to be persisted.
+                task_queue: canister_state
+                    .system_state
+                    .task_queue
+                    .iter()
+                    .filter(|task| task.is_persistent())
+                    .cloned()
+                    .collect(),
             }
             .into(),
         )
         .map_err(CheckpointError::from)
 }
 
+fn serialize_snapshot_to_tip(
+    log: &ReplicaLogger,
+    snapshot_id: &SnapshotId,
+    snapshot: &CanisterSnapshot,
+    layout: &StateLayout,
+    tip: &CheckpointLayout<RwPolicy>,
+) -> Result<(), CheckpointError> {
+    let snapshot_layout = tip.snapshot(snapshot_id)?;
+
+    match snapshot.binary.file() {
+        Some(path) => {
+            let wasm = snapshot_layout.wasm();
+            if !wasm.raw_path().exists() {
+                ic_state_layout::utils::do_copy(log, path, wasm.raw_path()).map_err(|io_err| {
+                    CheckpointError::IoError {
+                        path: path.to_path_buf(),
+                        message: "failed to copy Wasm file".to_string(),
+                        io_err: io_err.to_string(),
+                    }
+                })?;
+            }
+        }
+        None => {
+            // The snapshot was taken in this checkpoint interval. Persist its
+            // wasm binary.
+            snapshot_layout.wasm().serialize(&snapshot.binary)?;
+        }
+    }
+    // The memories of a snapshot taken in this checkpoint interval share the
+    // canister's files in the last checkpoint. Copy those files before
+    // persisting the page deltas on top of them.
+    if !snapshot_layout.vmemory_0().exists() {
+        if let Some(height) = snapshot.wasm_memory.page_map.base_height {
+            let canister_layout = layout.checkpoint(height)?.canister(&snapshot.canister_id)?;
+            copy_base_file(
+                log,
+                &canister_layout.vmemory_0(),
+                &snapshot_layout.vmemory_0(),
+            )?;
+        }
+    }
+    if !snapshot_layout.stable_memory_blob().exists() {
+        if let Some(height) = snapshot.stable_memory.page_map.base_height {
+            let canister_layout = layout.checkpoint(height)?.canister(&snapshot.canister_id)?;
+            copy_base_file(
+                log,
+                &canister_layout.stable_memory_blob(),
+                &snapshot_layout.stable_memory_blob(),
+            )?;
+        }
+    }
+    snapshot
+        .wasm_memory
+        .page_map
+        .persist_and_sync_delta(&snapshot_layout.vmemory_0())?;
+    snapshot
+        .stable_memory
+        .page_map
+        .persist_and_sync_delta(&snapshot_layout.stable_memory_blob())?;
+
+    snapshot_layout
+        .snapshot()
+        .serialize(
+            CanisterSnapshotBits {
+                taken_at_timestamp: snapshot.taken_at_timestamp,
+                certified_data: snapshot.certified_data.clone(),
+                execution_state_bits: ExecutionStateBits {
+                    exported_globals: snapshot.exported_globals.clone(),
+                    heap_size: snapshot.wasm_memory.size,
+                    exports: snapshot.exports.clone(),
+                    last_executed_round: ExecutionRound::from(0),
+                    metadata: snapshot.metadata.clone(),
+                    binary_hash: Some(snapshot.binary.module_hash().into()),
+                },
+                stable_memory_size: snapshot.stable_memory.size,
+            }
+            .into(),
+        )
+        .map_err(CheckpointError::from)
+}
+
+/// Copies a read-only page map file of the last checkpoint to `dst` and makes
+/// the copy writable, unless the file was never created because the memory
+/// has no pages in the checkpoint.
+fn copy_base_file(log: &ReplicaLogger, src: &Path, dst: &Path) -> Result<(), CheckpointError> {
+    if !src.exists() {
+        return Ok(());
+    }
+    let io_error = |message: &str, io_err: std::io::Error| CheckpointError::IoError {
+        path: dst.to_path_buf(),
+        message: message.to_string(),
+        io_err: io_err.to_string(),
+    };
+    ic_state_layout::utils::do_copy(log, src, dst)
+        .map_err(|io_err| io_error("failed to copy page map file", io_err))?;
+    let mut permissions = dst
+        .metadata()
+        .map_err(|io_err| io_error("failed to get file metadata", io_err))?
+        .permissions();
+    permissions.set_readonly(false);
+    std::fs::set_permissions(dst, permissions)
+        .map_err(|io_err| io_error("failed to set file permissions", io_err))
+}
+
 fn serialize_bitcoin_state_to_tip(
     state: &BitcoinState,
     layout: &BitcoinStateLayout<RwPolicy>,
@@ -421,6 +564,20 @@ pub fn load_checkpoint<P: ReadPolicy + Send + Sync>(
         load_bitcoin_state(checkpoint_layout)?
     };
 
+    let canister_snapshots = {
+        let _timer = metrics
+            .load_checkpoint_step_duration
+            .with_label_values(&["canister_snapshots"])
+            .start_timer();
+
+        let mut canister_snapshots = BTreeMap::new();
+        for snapshot_id in checkpoint_layout.snapshot_ids()? {
+            let snapshot = load_snapshot_from_checkpoint(checkpoint_layout, &snapshot_id)?;
+            canister_snapshots.insert(snapshot_id, Arc::new(snapshot));
+        }
+        CanisterSnapshots::new(canister_snapshots)
+    };
+
     let state = ReplicatedState::new_from_checkpoint(
         canister_states,
         metadata,
@@ -428,6 +585,7 @@ pub fn load_checkpoint<P: ReadPolicy + Send + Sync>(
         // Consensus queue needs to be empty at the end of every round.
         Vec::new(),
         bitcoin,
+        canister_snapshots,
         checkpoint_layout.raw_path().into(),
     );
 
@@ -511,7 +669,6 @@ pub fn load_canister_state<P: ReadPolicy>(
                 exports: execution_state_bits.exports,
                 metadata: execution_state_bits.metadata,
                 last_executed_round: execution_state_bits.last_executed_round,
-                task_queue: Default::default(),
             })
         }
         None => None,
@@ -546,6 +703,17 @@ pub fn load_canister_state<P: ReadPolicy>(
         canister_state_bits.certified_data,
         canister_metrics,
         canister_state_bits.cycles_balance,
+        canister_state_bits.reserved_balance,
+        canister_state_bits.reserved_balance_limit,
+        CanisterTimer::from_nanos_since_unix_epoch(canister_state_bits.global_timer_nanos),
+        canister_state_bits.canister_log,
+        canister_state_bits.log_visibility,
+        canister_state_bits.wasm_memory_limit,
+        canister_state_bits.wasm_memory_threshold,
+        canister_state_bits.on_low_wasm_memory_hook_status,
+        canister_state_bits.canister_version,
+        canister_state_bits.canister_history,
+        canister_state_bits.task_queue.into_iter().collect(),
     );
 
     let canister_state = CanisterState {
@@ -577,6 +745,42 @@ fn load_canister_state_from_checkpoint<P: ReadPolicy>(
     load_canister_state::<P>(&canister_layout, canister_id, checkpoint_layout.height())
 }
 
+fn load_snapshot_from_checkpoint<P: ReadPolicy>(
+    checkpoint_layout: &CheckpointLayout<P>,
+    snapshot_id: &SnapshotId,
+) -> Result<CanisterSnapshot, CheckpointError> {
+    let snapshot_layout = checkpoint_layout.snapshot(snapshot_id)?;
+    let height = checkpoint_layout.height();
+
+    let snapshot_bits = CanisterSnapshotBits::try_from(snapshot_layout.snapshot().deserialize()?)
+        .map_err(|err| CheckpointError::ProtoError {
+        path: snapshot_layout.raw_path(),
+        field: format!("snapshots[{}]::canister_snapshot_bits", snapshot_id),
+        proto_err: err.to_string(),
+    })?;
+    let execution_state_bits = snapshot_bits.execution_state_bits;
+
+    Ok(CanisterSnapshot {
+        canister_id: snapshot_id.canister_id(),
+        taken_at_timestamp: snapshot_bits.taken_at_timestamp,
+        binary: snapshot_layout
+            .wasm()
+            .deserialize(execution_state_bits.binary_hash)?,
+        wasm_memory: Memory::new(
+            PageMap::open(&snapshot_layout.vmemory_0(), Some(height))?,
+            execution_state_bits.heap_size,
+        ),
+        stable_memory: Memory::new(
+            PageMap::open(&snapshot_layout.stable_memory_blob(), Some(height))?,
+            snapshot_bits.stable_memory_size,
+        ),
+        exported_globals: execution_state_bits.exported_globals,
+        exports: execution_state_bits.exports,
+        metadata: execution_state_bits.metadata,
+        certified_data: snapshot_bits.certified_data,
+    })
+}
+
 fn load_bitcoin_state<P: ReadPolicy>(
     checkpoint_layout: &CheckpointLayout<P>,
 ) -> Result<BitcoinState, CheckpointError> {
@@ -631,24 +835,29 @@ mod tests {
     use super::*;
     use crate::NUMBER_OF_CHECKPOINT_THREADS;
     use ic_base_types::NumSeconds;
-    use ic_ic00_types::CanisterStatusType;
+    use ic_ic00_types::{
+        CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode, CanisterStatusType,
+        LogVisibility,
+    };
+    use ic_interfaces::messages::{CanisterInputMessage, RequestOrIngress};
     use ic_registry_subnet_type::SubnetType;
     use ic_replicated_state::{
         canister_state::execution_state::WasmBinary, canister_state::execution_state::WasmMetadata,
         page_map, testing::ReplicatedStateTesting, CallContextManager, CanisterStatus,
-        ExecutionState, ExportedFunctions, NumWasmPages, PageIndex,
+        ExecutionState, ExecutionTask, ExportedFunctions, Global, NumWasmPages,
+        OnLowWasmMemoryHookStatus, PageIndex,
     };
     use ic_sys::PAGE_SIZE;
     use ic_test_utilities::{
         state::{canister_ids, new_canister_state},
         types::{
             ids::{canister_test_id, message_test_id, subnet_test_id, user_test_id},
-            messages::IngressBuilder,
+            messages::{IngressBuilder, RequestBuilder},
         },
         with_test_replica_logger,
     };
     use ic_types::messages::StopCanisterContext;
-    use ic_types::{CanisterId, Cycles, ExecutionRound, Height};
+    use ic_types::{CanisterId, Cycles, ExecutionRound, Height, NumBytes, Time};
     use ic_wasm_types::CanisterModule;
     use std::collections::BTreeSet;
     use tempfile::Builder;
@@ -836,7 +1045,6 @@ mod tests {
                 exports: ExportedFunctions::new(BTreeSet::new()),
                 metadata: WasmMetadata::default(),
                 last_executed_round: ExecutionRound::from(0),
-                task_queue: Default::default(),
             };
             canister_state.execution_state = Some(execution_state);
 
@@ -1155,6 +1363,551 @@ mod tests {
         });
     }
 
+    #[test]
+    fn can_recover_a_canister_global_timer() {
+        with_test_replica_logger(|log| {
+            let tmp = Builder::new().prefix("test").tempdir().unwrap();
+            let root = tmp.path().to_path_buf();
+            let layout = StateLayout::new(log.clone(), root);
+
+            const HEIGHT: Height = Height::new(42);
+            let canister_id: CanisterId = canister_test_id(10);
+            let controller = user_test_id(24).get();
+
+            let mut canister_state = CanisterState {
+                system_state: SystemState::new_running(
+                    canister_id,
+                    controller,
+                    INITIAL_CYCLES,
+                    NumSeconds::from(100_000),
+                ),
+                execution_state: None,
+                scheduler_state: Default::default(),
+            };
+            let global_timer =
+                CanisterTimer::Active(Time::from_nanos_since_unix_epoch(1_000_000_000));
+            canister_state.system_state.global_timer = global_timer;
+
+            let own_subnet_type = SubnetType::Application;
+            let mut state = ReplicatedState::new_rooted_at(
+                subnet_test_id(1),
+                own_subnet_type,
+                "NOT_USED".into(),
+            );
+            state.put_canister_state(canister_state);
+            let _state = make_checkpoint_and_get_state(&log, &state, HEIGHT, &layout);
+
+            let recovered_state = load_checkpoint(
+                &layout.checkpoint(HEIGHT).unwrap(),
+                own_subnet_type,
+                &checkpoint_metrics(),
+                Some(&mut thread_pool()),
+            )
+            .unwrap();
+
+            let canister = recovered_state.canister_state(&canister_id).unwrap();
+            assert_eq!(canister.system_state.global_timer, global_timer);
+        });
+    }
+
+    #[test]
+    fn can_recover_a_canister_log() {
+        with_test_replica_logger(|log| {
+            let tmp = Builder::new().prefix("test").tempdir().unwrap();
+            let root = tmp.path().to_path_buf();
+            let layout = StateLayout::new(log.clone(), root);
+
+            const HEIGHT: Height = Height::new(42);
+            let canister_id: CanisterId = canister_test_id(10);
+            let controller = user_test_id(24).get();
+
+            let mut canister_state = CanisterState {
+                system_state: SystemState::new_running(
+                    canister_id,
+                    controller,
+                    INITIAL_CYCLES,
+                    NumSeconds::from(100_000),
+                ),
+                execution_state: None,
+                scheduler_state: Default::default(),
+            };
+            let time = Time::from_nanos_since_unix_epoch(1_000_000_000);
+            canister_state
+                .system_state
+                .canister_log
+                .add_record(time, b"first".to_vec());
+            canister_state.system_state.canister_log.clear();
+            canister_state
+                .system_state
+                .canister_log
+                .add_record(time, b"second".to_vec());
+            canister_state.system_state.log_visibility = LogVisibility::Public;
+
+            let own_subnet_type = SubnetType::Application;
+            let mut state = ReplicatedState::new_rooted_at(
+                subnet_test_id(1),
+                own_subnet_type,
+                "NOT_USED".into(),
+            );
+            state.put_canister_state(canister_state);
+            let _state = make_checkpoint_and_get_state(&log, &state, HEIGHT, &layout);
+
+            let recovered_state = load_checkpoint(
+                &layout.checkpoint(HEIGHT).unwrap(),
+                own_subnet_type,
+                &checkpoint_metrics(),
+                Some(&mut thread_pool()),
+            )
+            .unwrap();
+
+            let canister = recovered_state.canister_state(&canister_id).unwrap();
+            let canister_log = &canister.system_state.canister_log;
+            assert_eq!(canister_log.next_idx(), 2);
+            assert_eq!(canister_log.records().len(), 1);
+            assert_eq!(canister_log.records()[0].idx, 1);
+            assert_eq!(canister_log.records()[0].content, b"second".to_vec());
+            assert_eq!(canister.system_state.log_visibility, LogVisibility::Public);
+        });
+    }
+
+    #[test]
+    fn can_recover_wasm_memory_limit_and_threshold() {
+        with_test_replica_logger(|log| {
+            let tmp = Builder::new().prefix("test").tempdir().unwrap();
+            let root = tmp.path().to_path_buf();
+            let layout = StateLayout::new(log.clone(), root);
+
+            const HEIGHT: Height = Height::new(42);
+            let canister_id: CanisterId = canister_test_id(10);
+            let controller = user_test_id(24).get();
+
+            let mut canister_state = CanisterState {
+                system_state: SystemState::new_running(
+                    canister_id,
+                    controller,
+                    INITIAL_CYCLES,
+                    NumSeconds::from(100_000),
+                ),
+                execution_state: None,
+                scheduler_state: Default::default(),
+            };
+            canister_state.system_state.wasm_memory_limit = Some(NumBytes::from(1 << 30));
+            canister_state.system_state.wasm_memory_threshold = NumBytes::from(1 << 20);
+            canister_state.system_state.on_low_wasm_memory_hook_status =
+                OnLowWasmMemoryHookStatus::Executed;
+
+            let own_subnet_type = SubnetType::Application;
+            let mut state = ReplicatedState::new_rooted_at(
+                subnet_test_id(1),
+                own_subnet_type,
+                "NOT_USED".into(),
+            );
+            state.put_canister_state(canister_state);
+            let _state = make_checkpoint_and_get_state(&log, &state, HEIGHT, &layout);
+
+            let recovered_state = load_checkpoint(
+                &layout.checkpoint(HEIGHT).unwrap(),
+                own_subnet_type,
+                &checkpoint_metrics(),
+                Some(&mut thread_pool()),
+            )
+            .unwrap();
+
+            let system_state = &recovered_state
+                .canister_state(&canister_id)
+                .unwrap()
+                .system_state;
+            assert_eq!(
+                system_state.wasm_memory_limit,
+                Some(NumBytes::from(1 << 30))
+            );
+            assert_eq!(system_state.wasm_memory_threshold, NumBytes::from(1 << 20));
+            assert_eq!(
+                system_state.on_low_wasm_memory_hook_status,
+                OnLowWasmMemoryHookStatus::Executed
+            );
+        });
+    }
+
+    #[test]
+    fn can_recover_reserved_balance_and_limit() {
+        with_test_replica_logger(|log| {
+            let tmp = Builder::new().prefix("test").tempdir().unwrap();
+            let root = tmp.path().to_path_buf();
+            let layout = StateLayout::new(log.clone(), root);
+
+            const HEIGHT: Height = Height::new(42);
+            let canister_id: CanisterId = canister_test_id(10);
+            let controller = user_test_id(24).get();
+
+            let mut canister_state = CanisterState {
+                system_state: SystemState::new_running(
+                    canister_id,
+                    controller,
+                    INITIAL_CYCLES,
+                    NumSeconds::from(100_000),
+                ),
+                execution_state: None,
+                scheduler_state: Default::default(),
+            };
+            canister_state
+                .system_state
+                .set_reserved_balance_limit(Some(Cycles::new(5_000)));
+            canister_state
+                .system_state
+                .reserve_cycles(Cycles::new(1_000))
+                .unwrap();
+
+            let own_subnet_type = SubnetType::Application;
+            let mut state = ReplicatedState::new_rooted_at(
+                subnet_test_id(1),
+                own_subnet_type,
+                "NOT_USED".into(),
+            );
+            state.put_canister_state(canister_state);
+            let _state = make_checkpoint_and_get_state(&log, &state, HEIGHT, &layout);
+
+            let recovered_state = load_checkpoint(
+                &layout.checkpoint(HEIGHT).unwrap(),
+                own_subnet_type,
+                &checkpoint_metrics(),
+                Some(&mut thread_pool()),
+            )
+            .unwrap();
+
+            let system_state = &recovered_state
+                .canister_state(&canister_id)
+                .unwrap()
+                .system_state;
+            assert_eq!(system_state.reserved_balance(), Cycles::new(1_000));
+            assert_eq!(
+                system_state.reserved_balance_limit(),
+                Some(Cycles::new(5_000))
+            );
+            assert_eq!(system_state.balance(), INITIAL_CYCLES - Cycles::new(1_000));
+        });
+    }
+
+    #[test]
+    fn can_recover_canister_version() {
+        with_test_replica_logger(|log| {
+            let tmp = Builder::new().prefix("test").tempdir().unwrap();
+            let root = tmp.path().to_path_buf();
+            let layout = StateLayout::new(log.clone(), root);
+
+            const HEIGHT: Height = Height::new(42);
+            let canister_id: CanisterId = canister_test_id(10);
+            let controller = user_test_id(24).get();
+
+            let mut canister_state = CanisterState {
+                system_state: SystemState::new_running(
+                    canister_id,
+                    controller,
+                    INITIAL_CYCLES,
+                    NumSeconds::from(100_000),
+                ),
+                execution_state: None,
+                scheduler_state: Default::default(),
+            };
+            canister_state.system_state.canister_version = 7;
+
+            let own_subnet_type = SubnetType::Application;
+            let mut state = ReplicatedState::new_rooted_at(
+                subnet_test_id(1),
+                own_subnet_type,
+                "NOT_USED".into(),
+            );
+            state.put_canister_state(canister_state);
+            let _state = make_checkpoint_and_get_state(&log, &state, HEIGHT, &layout);
+
+            let recovered_state = load_checkpoint(
+                &layout.checkpoint(HEIGHT).unwrap(),
+                own_subnet_type,
+                &checkpoint_metrics(),
+                Some(&mut thread_pool()),
+            )
+            .unwrap();
+
+            assert_eq!(
+                recovered_state
+                    .canister_state(&canister_id)
+                    .unwrap()
+                    .system_state
+                    .canister_version,
+                7
+            );
+        });
+    }
+
+    #[test]
+    fn can_recover_aborted_executions() {
+        with_test_replica_logger(|log| {
+            let tmp = Builder::new().prefix("test").tempdir().unwrap();
+            let root = tmp.path().to_path_buf();
+            let layout = StateLayout::new(log.clone(), root);
+
+            const HEIGHT: Height = Height::new(42);
+            let canister_id: CanisterId = canister_test_id(10);
+            let controller = user_test_id(24).get();
+
+            let mut canister_state = CanisterState {
+                system_state: SystemState::new_running(
+                    canister_id,
+                    controller,
+                    INITIAL_CYCLES,
+                    NumSeconds::from(100_000),
+                ),
+                execution_state: None,
+                scheduler_state: Default::default(),
+            };
+            let aborted_execution = ExecutionTask::AbortedExecution(CanisterInputMessage::Ingress(
+                Arc::new(IngressBuilder::new().receiver(canister_id).build()),
+            ));
+            let aborted_install_code =
+                ExecutionTask::AbortedInstallCode(RequestOrIngress::Request(Arc::new(
+                    RequestBuilder::new().receiver(canister_id).build(),
+                )));
+            canister_state.system_state.task_queue = vec![
+                aborted_execution.clone(),
+                ExecutionTask::Heartbeat,
+                aborted_install_code.clone(),
+            ]
+            .into();
+
+            let own_subnet_type = SubnetType::Application;
+            let mut state = ReplicatedState::new_rooted_at(
+                subnet_test_id(1),
+                own_subnet_type,
+                "NOT_USED".into(),
+            );
+            state.put_canister_state(canister_state);
+            let _state = make_checkpoint_and_get_state(&log, &state, HEIGHT, &layout);
+
+            let recovered_state = load_checkpoint(
+                &layout.checkpoint(HEIGHT).unwrap(),
+                own_subnet_type,
+                &checkpoint_metrics(),
+                Some(&mut thread_pool()),
+            )
+            .unwrap();
+
+            // The heartbeat task is not persisted.
+            assert_eq!(
+                recovered_state
+                    .canister_state(&canister_id)
+                    .unwrap()
+                    .system_state
+                    .task_queue,
+                vec![aborted_execution, aborted_install_code]
+            );
+        });
+    }
+
+    #[test]
+    fn can_recover_a_canister_history() {
+        with_test_replica_logger(|log| {
+            let tmp = Builder::new().prefix("test").tempdir().unwrap();
+            let root = tmp.path().to_path_buf();
+            let layout = StateLayout::new(log.clone(), root);
+
+            const HEIGHT: Height = Height::new(42);
+            let canister_id: CanisterId = canister_test_id(10);
+            let controller = user_test_id(24).get();
+
+            let mut canister_state = CanisterState {
+                system_state: SystemState::new_running(
+                    canister_id,
+                    controller,
+                    INITIAL_CYCLES,
+                    NumSeconds::from(100_000),
+                ),
+                execution_state: None,
+                scheduler_state: Default::default(),
+            };
+            let time = Time::from_nanos_since_unix_epoch(1_000_000_000);
+            canister_state.system_state.add_canister_change(
+                time,
+                CanisterChangeOrigin::from_user(controller),
+                CanisterChangeDetails::canister_creation(vec![controller]),
+            );
+            canister_state.system_state.add_canister_change(
+                time,
+                CanisterChangeOrigin::from_canister(canister_test_id(1).get()),
+                CanisterChangeDetails::code_deployment(CanisterInstallMode::Install, [1; 32]),
+            );
+            canister_state.system_state.add_canister_change(
+                time,
+                CanisterChangeOrigin::from_user(controller),
+                CanisterChangeDetails::code_uninstall(),
+            );
+            let canister_history = canister_state.system_state.canister_history().clone();
+
+            let own_subnet_type = SubnetType::Application;
+            let mut state = ReplicatedState::new_rooted_at(
+                subnet_test_id(1),
+                own_subnet_type,
+                "NOT_USED".into(),
+            );
+            state.put_canister_state(canister_state);
+            let _state = make_checkpoint_and_get_state(&log, &state, HEIGHT, &layout);
+
+            let recovered_state = load_checkpoint(
+                &layout.checkpoint(HEIGHT).unwrap(),
+                own_subnet_type,
+                &checkpoint_metrics(),
+                Some(&mut thread_pool()),
+            )
+            .unwrap();
+
+            let canister = recovered_state.canister_state(&canister_id).unwrap();
+            assert_eq!(canister.system_state.canister_history(), &canister_history);
+            assert_eq!(
+                canister.system_state.canister_history().total_num_changes(),
+                3
+            );
+        });
+    }
+
+    #[test]
+    fn can_recover_a_canister_snapshot() {
+        with_test_replica_logger(|log| {
+            let tmp = Builder::new().prefix("test").tempdir().unwrap();
+            let root = tmp.path().to_path_buf();
+            let layout = StateLayout::new(log.clone(), root);
+
+            const HEIGHT: Height = Height::new(42);
+            let canister_id: CanisterId = canister_test_id(10);
+            let snapshot_id = SnapshotId::new(canister_id, 3);
+
+            let snapshot = CanisterSnapshot {
+                canister_id,
+                taken_at_timestamp: Time::from_nanos_since_unix_epoch(1_000_000_000),
+                binary: empty_wasm(),
+                wasm_memory: one_page_of(1),
+                stable_memory: one_page_of(2),
+                exported_globals: vec![Global::I64(7)],
+                exports: ExportedFunctions::new(BTreeSet::new()),
+                metadata: WasmMetadata::default(),
+                certified_data: vec![1, 2, 3],
+            };
+
+            let own_subnet_type = SubnetType::Application;
+            let mut state = ReplicatedState::new_rooted_at(
+                subnet_test_id(1),
+                own_subnet_type,
+                "NOT_USED".into(),
+            );
+            state.metadata.next_snapshot_id = 4;
+            state
+                .canister_snapshots
+                .push(snapshot_id, Arc::new(snapshot.clone()));
+            let _state = make_checkpoint_and_get_state(&log, &state, HEIGHT, &layout);
+
+            let recovered_state = load_checkpoint(
+                &layout.checkpoint(HEIGHT).unwrap(),
+                own_subnet_type,
+                &checkpoint_metrics(),
+                Some(&mut thread_pool()),
+            )
+            .unwrap();
+
+            assert_eq!(recovered_state.metadata.next_snapshot_id, 4);
+            let recovered = recovered_state
+                .canister_snapshots
+                .get(&snapshot_id)
+                .unwrap();
+            assert_eq!(recovered.canister_id, canister_id);
+            assert_eq!(recovered.taken_at_timestamp, snapshot.taken_at_timestamp);
+            assert_eq!(recovered.binary.as_slice(), snapshot.binary.as_slice());
+            assert_eq!(recovered.wasm_memory, snapshot.wasm_memory);
+            assert_eq!(recovered.stable_memory, snapshot.stable_memory);
+            assert_eq!(recovered.exported_globals, snapshot.exported_globals);
+            assert_eq!(recovered.certified_data, snapshot.certified_data);
+        });
+    }
+
+    #[test]
+    fn can_recover_a_snapshot_sharing_checkpointed_memory() {
+        with_test_replica_logger(|log| {
+            let tmp = Builder::new().prefix("test").tempdir().unwrap();
+            let root = tmp.path().to_path_buf();
+            let layout = StateLayout::new(log.clone(), root.clone());
+
+            const HEIGHT: Height = Height::new(42);
+            const NEXT_HEIGHT: Height = Height::new(84);
+            let canister_id: CanisterId = canister_test_id(10);
+            let snapshot_id = SnapshotId::new(canister_id, 0);
+
+            let mut canister_state = new_canister_state(
+                canister_id,
+                user_test_id(24).get(),
+                INITIAL_CYCLES,
+                NumSeconds::from(100_000),
+            );
+            canister_state.execution_state = Some(ExecutionState {
+                canister_root: root.clone(),
+                session_nonce: None,
+                wasm_binary: WasmBinary::new(empty_wasm()),
+                wasm_memory: one_page_of(1),
+                stable_memory: one_page_of(2),
+                exported_globals: vec![],
+                exports: ExportedFunctions::new(BTreeSet::new()),
+                metadata: WasmMetadata::default(),
+                last_executed_round: ExecutionRound::from(0),
+            });
+
+            let own_subnet_type = SubnetType::Application;
+            let mut state =
+                ReplicatedState::new_rooted_at(subnet_test_id(1), own_subnet_type, root);
+            state.put_canister_state(canister_state);
+            let mut state = make_checkpoint_and_get_state(&log, &state, HEIGHT, &layout);
+
+            // The snapshot shares the checkpointed page 0 and the page delta
+            // with page 1.
+            let execution_state = state
+                .canister_state_mut(&canister_id)
+                .unwrap()
+                .execution_state
+                .as_mut()
+                .unwrap();
+            execution_state
+                .wasm_memory
+                .page_map
+                .update(&[(PageIndex::new(1), &[3; PAGE_SIZE])]);
+            execution_state.wasm_memory.size = NumWasmPages::new(2);
+            let canister = state.take_canister_state(&canister_id).unwrap();
+            let snapshot = CanisterSnapshot::from_canister(
+                &canister,
+                Time::from_nanos_since_unix_epoch(1_000_000_000),
+            )
+            .unwrap();
+            assert_eq!(snapshot.wasm_memory.page_map.base_height, Some(HEIGHT));
+            state
+                .canister_snapshots
+                .push(snapshot_id, Arc::new(snapshot));
+            let _state = make_checkpoint_and_get_state(&log, &state, NEXT_HEIGHT, &layout);
+
+            let recovered_state = load_checkpoint(
+                &layout.checkpoint(NEXT_HEIGHT).unwrap(),
+                own_subnet_type,
+                &checkpoint_metrics(),
+                Some(&mut thread_pool()),
+            )
+            .unwrap();
+            let recovered = recovered_state
+                .canister_snapshots
+                .get(&snapshot_id)
+                .unwrap();
+            let wasm_memory = &recovered.wasm_memory.page_map;
+            assert_eq!(wasm_memory.get_page(PageIndex::new(0)), &[1; PAGE_SIZE]);
+            assert_eq!(wasm_memory.get_page(PageIndex::new(1)), &[3; PAGE_SIZE]);
+            assert_eq!(
+                recovered.stable_memory.page_map.get_page(PageIndex::new(0)),
+                &[2; PAGE_SIZE]
+            );
+        });
+    }
+
     #[test]
     fn can_recover_subnet_queues() {
         with_test_replica_logger(|log| {
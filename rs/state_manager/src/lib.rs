@@ -1,3 +1,4 @@
+pub mod canister_migration;
 // Needs to be `pub` so that the benchmarking code in `state_manager/benches`
 // can access it.
 pub mod checkpoint;
@@ -33,7 +34,7 @@ use ic_protobuf::{messaging::xnet::v1, state::v1 as pb};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
     canister_state::execution_state::SandboxMemory, page_map::PersistenceError, PageIndex, PageMap,
-    ReplicatedState,
+    ReplicatedState, SnapshotId,
 };
 use ic_state_layout::{error::LayoutError, AccessPolicy, CheckpointLayout, StateLayout};
 use ic_types::{
@@ -802,6 +803,8 @@ pub enum PageMapType {
     WasmMemory(CanisterId),
     StableMemory(CanisterId),
     Bitcoin(BitcoinPageMap),
+    SnapshotWasmMemory(SnapshotId),
+    SnapshotStableMemory(SnapshotId),
 }
 
 /// PageMaps used in the Bitcoin state.
@@ -827,6 +830,11 @@ impl PageMapType {
         result.push(Self::Bitcoin(BitcoinPageMap::UtxosMedium));
         result.push(Self::Bitcoin(BitcoinPageMap::AddressOutpoints));
 
+        for (id, _snapshot) in state.canister_snapshots.iter() {
+            result.push(Self::SnapshotWasmMemory(id.to_owned()));
+            result.push(Self::SnapshotStableMemory(id.to_owned()));
+        }
+
         result
     }
 
@@ -845,6 +853,8 @@ impl PageMapType {
             PageMapType::Bitcoin(BitcoinPageMap::AddressOutpoints) => {
                 Ok(layout.bitcoin()?.address_outpoints())
             }
+            PageMapType::SnapshotWasmMemory(id) => Ok(layout.snapshot(id)?.vmemory_0()),
+            PageMapType::SnapshotStableMemory(id) => Ok(layout.snapshot(id)?.stable_memory_blob()),
         }
     }
 
@@ -870,6 +880,14 @@ impl PageMapType {
             PageMapType::Bitcoin(BitcoinPageMap::AddressOutpoints) => {
                 Some(&state.bitcoin().utxo_set.address_outpoints)
             }
+            PageMapType::SnapshotWasmMemory(id) => state
+                .canister_snapshots
+                .get(id)
+                .map(|snapshot| &snapshot.wasm_memory.page_map),
+            PageMapType::SnapshotStableMemory(id) => state
+                .canister_snapshots
+                .get(id)
+                .map(|snapshot| &snapshot.stable_memory.page_map),
         }
     }
 
@@ -895,6 +913,14 @@ impl PageMapType {
             PageMapType::Bitcoin(BitcoinPageMap::AddressOutpoints) => {
                 Some(&mut state.bitcoin_mut().utxo_set.address_outpoints)
             }
+            PageMapType::SnapshotWasmMemory(id) => state
+                .canister_snapshots
+                .get_mut(id)
+                .map(|snapshot| &mut snapshot.wasm_memory.page_map),
+            PageMapType::SnapshotStableMemory(id) => state
+                .canister_snapshots
+                .get_mut(id)
+                .map(|snapshot| &mut snapshot.stable_memory.page_map),
         }
     }
 }
@@ -925,6 +951,14 @@ pub fn get_dirty_pages(
         .filter_map(|entry| {
             let page_map = entry.get(state)?;
             let height = page_map.base_height?;
+            // The memories of a snapshot taken since the last checkpoint are
+            // based on the canister's files, not on the snapshot's own files.
+            match &entry {
+                PageMapType::SnapshotWasmMemory(id) | PageMapType::SnapshotStableMemory(id) => {
+                    previous_snapshot?.state.canister_snapshots.get(id)?;
+                }
+                _ => {}
+            }
             Some(DirtyPageMap {
                 height,
                 file_type: FileType::PageMap(entry),
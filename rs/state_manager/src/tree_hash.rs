@@ -198,7 +198,6 @@ mod tests {
                 exports: ExportedFunctions::new(BTreeSet::new()),
                 metadata,
                 last_executed_round: ExecutionRound::from(0),
-                task_queue: Default::default(),
             };
             canister_state.execution_state = Some(execution_state);
 
@@ -278,6 +277,8 @@ mod tests {
             "48BCE58420569AB8C30A8E0653FC597AF1B8C072118BF6E0A75D3BEC2059FFFB",
             "901C2CDB77697DBD3F024F2521FED2D92F1309A6073C8C50C52A348F76418D3F",
             "901C2CDB77697DBD3F024F2521FED2D92F1309A6073C8C50C52A348F76418D3F",
+            "A89FD7EE9D0385583EED44CCA7F995E474958B9617CD02905103D7374F14E3D4",
+            "A89FD7EE9D0385583EED44CCA7F995E474958B9617CD02905103D7374F14E3D4",
         ];
         for certification_version in CertificationVersion::iter() {
             assert_partial_state_hash_matches(
@@ -0,0 +1,30 @@
+//! Exports the state of a stopped canister from a checkpoint.
+
+use ic_state_layout::CompleteCheckpointLayout;
+use ic_state_manager::canister_migration::export_canister;
+use ic_types::{CanisterId, Height};
+use std::path::PathBuf;
+
+/// Copies the files of the canister with the specified ID from the checkpoint
+/// at `path` into the `output` directory.
+pub fn do_export(path: PathBuf, canister_id: CanisterId, output: PathBuf) -> Result<(), String> {
+    let cp_layout = CompleteCheckpointLayout::new(path.clone(), Height::new(0))
+        .map_err(|e| format!("failed to create checkpoint layout: {}", e))?;
+
+    export_canister(&cp_layout, &canister_id, &output).map_err(|e| {
+        format!(
+            "failed to export canister {} from {}: {}",
+            canister_id,
+            path.display(),
+            e
+        )
+    })?;
+
+    println!(
+        "Successfully exported canister {} to {}",
+        canister_id,
+        output.display()
+    );
+
+    Ok(())
+}
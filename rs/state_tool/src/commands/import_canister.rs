@@ -0,0 +1,65 @@
+//! Imports the state of a canister exported from another subnet.
+
+use crate::commands::utils;
+use ic_state_manager::canister_migration::import_canister;
+use ic_types::{CanisterId, Height};
+use std::path::PathBuf;
+
+/// Creates a checkpoint at `height` that consists of the latest checkpoint in
+/// the replica state directory and the canister exported to `input`.
+///
+/// Function is not crash-safe. Caller is responsible to follow guidelines
+/// regarding crash-safe I/O.
+pub fn do_import(
+    input: PathBuf,
+    canister_id: CanisterId,
+    config_path: PathBuf,
+    height: u64,
+) -> Result<(), String> {
+    let state_layout = utils::locate_state_root(config_path)?;
+    let height = Height::new(height);
+
+    let latest_height = state_layout
+        .checkpoint_heights()
+        .map_err(|e| format!("Failed to list checkpoints: {}", e))?
+        .last()
+        .copied()
+        .ok_or_else(|| {
+            format!(
+                "No checkpoints found in state root {}",
+                state_layout.raw_path().display()
+            )
+        })?;
+    if height <= latest_height {
+        return Err(format!(
+            "Height {} must be greater than the latest checkpoint height {}",
+            height, latest_height
+        ));
+    }
+
+    let cp_layout = state_layout
+        .checkpoint_to_scratchpad(latest_height)
+        .map_err(|e| format!("Failed to copy checkpoint {}: {}", latest_height, e))?;
+
+    import_canister(&cp_layout, &canister_id, &input).map_err(|e| {
+        format!(
+            "Failed to import canister {} from {}: {}",
+            canister_id,
+            input.display(),
+            e
+        )
+    })?;
+
+    state_layout
+        .scratchpad_to_checkpoint(cp_layout, height)
+        .map_err(|e| e.to_string())?;
+
+    println!(
+        "Successfully created checkpoint {} with canister {} in state root {}",
+        height,
+        canister_id,
+        state_layout.raw_path().display()
+    );
+
+    Ok(())
+}
@@ -2,9 +2,10 @@
 //!
 //! A command-line tool to manage Internet Computer replicated states (decode
 //! persisted state files, diff checkpoints, compute partial state hashes and
-//! checkpoint manifests, import state trees).
+//! checkpoint manifests, import state trees, export and import canisters).
 
 use clap::Parser;
+use ic_types::CanisterId;
 use std::path::PathBuf;
 
 mod commands;
@@ -41,6 +42,42 @@ enum Opt {
         height: u64,
     },
 
+    /// Exports a stopped canister from a checkpoint.
+    #[clap(name = "export-canister")]
+    ExportCanister {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+
+        /// The ID of the canister to export.
+        #[clap(long = "canister")]
+        canister_id: CanisterId,
+
+        /// Path to the directory to export the canister to.
+        #[clap(long = "output")]
+        output: PathBuf,
+    },
+
+    /// Imports a canister exported from another subnet into a new checkpoint.
+    #[clap(name = "import-canister")]
+    ImportCanister {
+        /// Path to the exported canister.
+        #[clap(long = "input")]
+        input: PathBuf,
+
+        /// The ID of the canister to import.
+        #[clap(long = "canister")]
+        canister_id: CanisterId,
+
+        /// Path to the replica configuration (ic.json).
+        #[clap(long = "config")]
+        config: PathBuf,
+
+        /// The height to label the new checkpoint with.
+        #[clap(long = "height", short = 'h')]
+        height: u64,
+    },
+
     /// Computes manifest of a checkpoint.
     #[clap(name = "manifest")]
     Manifest {
@@ -76,6 +113,17 @@ fn main() {
             config,
             height,
         } => commands::import_state::do_import(state, config, height),
+        Opt::ExportCanister {
+            path,
+            canister_id,
+            output,
+        } => commands::export_canister::do_export(path, canister_id, output),
+        Opt::ImportCanister {
+            input,
+            canister_id,
+            config,
+            height,
+        } => commands::import_canister::do_import(input, canister_id, config, height),
         Opt::Manifest { path } => commands::manifest::do_compute_manifest(path),
         Opt::ListStates { config } => commands::list::do_list(config),
         Opt::Decode { file } => commands::decode::do_decode(file),
@@ -6,6 +6,7 @@ mod stable_memory;
 pub mod system_api_empty;
 
 use ic_config::flag_status::FlagStatus;
+use ic_cycles_account_manager::ResourceSaturation;
 use ic_error_types::RejectCode;
 use ic_interfaces::execution_environment::{
     AvailableMemory, ExecutionComplexity, ExecutionMode,
@@ -16,12 +17,16 @@ use ic_interfaces::execution_environment::{
 };
 use ic_logger::{error, ReplicaLogger};
 use ic_registry_subnet_type::SubnetType;
-use ic_replicated_state::{memory_required_to_push_request, Memory, NumWasmPages, PageIndex};
+use ic_replicated_state::{
+    canister_state::WASM_PAGE_SIZE_IN_BYTES, memory_required_to_push_request, CanisterTimer,
+    Memory, NumWasmPages, PageIndex, MAX_CANISTER_LOG_BUFFER_SIZE,
+};
 use ic_sys::PageBytes;
 use ic_types::{
     ingress::WasmResult,
     messages::{CallContextId, RejectContext, Request, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES},
-    methods::{Callback, WasmClosure},
+    methods::{Callback, SystemMethod, WasmClosure},
+    time::NO_DEADLINE,
     CanisterId, ComputeAllocation, Cycles, NumBytes, NumInstructions, PrincipalId, SubnetId, Time,
 };
 use ic_utils::deterministic_operations::deterministic_copy_from_slice;
@@ -29,6 +34,7 @@ use request_in_prep::{into_request, RequestInPrep};
 use sandbox_safe_system_state::{CanisterStatusView, SandboxSafeSystemState, SystemStateChanges};
 use serde::{Deserialize, Serialize};
 use stable_memory::StableMemory;
+pub use stable_memory::{stable_memory_dirty_page_limit_exceeded, MappedStableMemory};
 use std::{
     convert::{From, TryFrom},
     sync::Arc,
@@ -146,6 +152,9 @@ pub struct ExecutionParameters {
     pub compute_allocation: ComputeAllocation,
     pub subnet_type: SubnetType,
     pub execution_mode: ExecutionMode,
+    /// The subnet memory usage at the start of the execution. Allocating
+    /// storage above the threshold reserves cycles.
+    pub subnet_memory_saturation: ResourceSaturation,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -299,8 +308,11 @@ pub enum ApiType {
         message_accepted: bool,
     },
 
-    // For executing the `canister_heartbeat` method
-    Heartbeat {
+    /// For executing system tasks such as the `canister_heartbeat` and the
+    /// `canister_global_timer` methods.
+    SystemTask {
+        /// The system method that is being executed.
+        system_task: SystemMethod,
         time: Time,
         call_context_id: CallContextId,
         /// Optional outgoing request under construction. If `None` no outgoing
@@ -332,8 +344,13 @@ impl ApiType {
         }
     }
 
-    pub fn heartbeat(time: Time, call_context_id: CallContextId) -> Self {
-        Self::Heartbeat {
+    pub fn system_task(
+        system_task: SystemMethod,
+        time: Time,
+        call_context_id: CallContextId,
+    ) -> Self {
+        Self::SystemTask {
+            system_task,
             time,
             call_context_id,
             outgoing_request: None,
@@ -487,7 +504,7 @@ impl ApiType {
             | ApiType::ReplyCallback { .. }
             | ApiType::RejectCallback { .. }
             | ApiType::PreUpgrade { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. } => ModificationTracking::Track,
         }
     }
@@ -498,7 +515,12 @@ impl ApiType {
         match self {
             ApiType::Start { .. } => "start",
             ApiType::Init { .. } => "init",
-            ApiType::Heartbeat { .. } => "heartbeat",
+            ApiType::SystemTask { system_task, .. } => match system_task {
+                SystemMethod::CanisterHeartbeat => "heartbeat",
+                SystemMethod::CanisterGlobalTimer => "global timer",
+                SystemMethod::CanisterOnLowWasmMemory => "on low wasm memory",
+                _ => "system task",
+            },
             ApiType::Update { .. } => "update",
             ApiType::ReplicatedQuery { .. } => "replicated query",
             ApiType::NonReplicatedQuery { .. } => "non replicated query",
@@ -742,6 +764,17 @@ impl SystemApiImpl {
         }
     }
 
+    /// Appends a record of the error that terminated the execution to the
+    /// canister log. Traps raised by `ic0.trap` are not recorded again,
+    /// because the system call records its message itself.
+    pub fn append_trap_to_canister_log(&mut self, err: &HypervisorError) {
+        if let CalledTrap(_) = err {
+            return;
+        }
+        self.sandbox_safe_system_state
+            .append_canister_log(format!("[TRAP]: {}", err).into_bytes());
+    }
+
     /// Gets the result of execution, assuming there is no error from
     /// running the canister. Returns any cycles used for an outgoing request
     /// that doesn't get sent and returns allocated memory to the subnet if the
@@ -753,7 +786,7 @@ impl SystemApiImpl {
         match &mut self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::PreUpgrade { .. }
@@ -790,7 +823,7 @@ impl SystemApiImpl {
             | ApiType::Init { .. }
             | ApiType::PreUpgrade { .. }
             | ApiType::Cleanup { .. }
-            | ApiType::Heartbeat { .. } => Ok(None),
+            | ApiType::SystemTask { .. } => Ok(None),
             ApiType::InspectMessage {
                 message_accepted, ..
             } => {
@@ -837,6 +870,22 @@ impl SystemApiImpl {
         self.memory_usage.allocated_message_memory
     }
 
+    /// Reserves cycles for `allocated_pages` Wasm pages that were just
+    /// allocated in `memory_usage`, if the subnet memory usage is above the
+    /// threshold. Failing to reserve traps, which discards the allocation.
+    fn reserve_storage_cycles(&mut self, allocated_pages: usize) -> HypervisorResult<()> {
+        let allocated_bytes =
+            ic_replicated_state::num_bytes_try_from(NumWasmPages::from(allocated_pages))
+                .expect("could not convert wasm pages to bytes");
+        // The subnet memory usage right before this allocation.
+        let subnet_memory_saturation = self
+            .execution_parameters
+            .subnet_memory_saturation
+            .add((self.memory_usage.total_allocated_memory - allocated_bytes).get());
+        self.sandbox_safe_system_state
+            .reserve_storage_cycles(allocated_bytes, &subnet_memory_saturation)
+    }
+
     fn error_for(&self, method_name: &str) -> HypervisorError {
         HypervisorError::ContractViolation(format!(
             "\"{}\" cannot be executed in {} mode",
@@ -847,7 +896,7 @@ impl SystemApiImpl {
     fn get_msg_caller_id(&self, method_name: &str) -> Result<PrincipalId, HypervisorError> {
         match &self.api_type {
             ApiType::Start { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplyCallback { .. }
             | ApiType::RejectCallback { .. } => Err(self.error_for(method_name)),
@@ -865,7 +914,7 @@ impl SystemApiImpl {
             ApiType::Start { .. }
             | ApiType::Init { .. }
             | ApiType::PreUpgrade { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::InspectMessage { .. } => None,
             ApiType::Update {
@@ -905,7 +954,7 @@ impl SystemApiImpl {
         match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::NonReplicatedQuery { .. }
@@ -921,7 +970,7 @@ impl SystemApiImpl {
         match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::NonReplicatedQuery { .. }
@@ -949,7 +998,7 @@ impl SystemApiImpl {
             ApiType::Update {
                 outgoing_request, ..
             }
-            | ApiType::Heartbeat {
+            | ApiType::SystemTask {
                 outgoing_request, ..
             }
             | ApiType::ReplyCallback {
@@ -980,7 +1029,7 @@ impl SystemApiImpl {
         match &self.api_type {
             ApiType::Start {} => Err(self.error_for(method_name)),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -999,7 +1048,7 @@ impl SystemApiImpl {
         match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::PreUpgrade { .. }
@@ -1023,7 +1072,7 @@ impl SystemApiImpl {
         match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::NonReplicatedQuery { .. }
@@ -1055,7 +1104,7 @@ impl SystemApiImpl {
             } => Ok(Cycles::new(0)),
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::PreUpgrade { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -1087,6 +1136,19 @@ impl SystemApiImpl {
         self.stable_memory.stable_memory_size
     }
 
+    /// Makes `ic0.stable*` calls access the given mapped stable memory
+    /// directly instead of copying through the stable memory `PageMap`.
+    pub fn set_mapped_stable_memory(&mut self, mapped_stable_memory: Box<dyn MappedStableMemory>) {
+        self.stable_memory
+            .set_mapped_stable_memory(mapped_stable_memory);
+    }
+
+    /// Makes `ic0.stable*` writes trap once more than `limit` pages of the
+    /// stable memory have been modified.
+    pub fn set_stable_memory_dirty_page_limit(&mut self, limit: u64) {
+        self.stable_memory.set_dirty_page_limit(limit);
+    }
+
     /// Wrapper around `self.sandbox_safe_system_state.push_output_request()` that
     /// tries to allocate memory for the `Request` before pushing it.
     ///
@@ -1238,7 +1300,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Cleanup { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::RejectCallback { .. }
             | ApiType::PreUpgrade { .. } => Err(self.error_for("ic0_msg_arg_data_size")),
             ApiType::Init {
@@ -1273,7 +1335,7 @@ impl SystemApi for SystemApiImpl {
     ) -> HypervisorResult<()> {
         let result = match &self.api_type {
             ApiType::Start { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::RejectCallback { .. }
             | ApiType::PreUpgrade { .. } => Err(self.error_for("ic0_msg_arg_data_copy")),
@@ -1327,7 +1389,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::Cleanup { .. }
             | ApiType::Update { .. }
             | ApiType::ReplyCallback { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::NonReplicatedQuery { .. }
             | ApiType::Init { .. } => Err(self.error_for("ic0_msg_method_name_size")),
@@ -1351,7 +1413,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::PreUpgrade { .. }
             | ApiType::Update { .. }
             | ApiType::ReplyCallback { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::NonReplicatedQuery { .. }
             | ApiType::Init { .. } => Err(self.error_for("ic0_msg_method_name_copy")),
@@ -1388,7 +1450,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::Cleanup { .. }
             | ApiType::Update { .. }
             | ApiType::ReplyCallback { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::ReplicatedQuery { .. }
             | ApiType::NonReplicatedQuery { .. }
             | ApiType::Init { .. } => Err(self.error_for("ic0_accept_message")),
@@ -1560,7 +1622,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start { .. } => Err(self.error_for("ic0_canister_self_size")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::Update { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -1589,7 +1651,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start { .. } => Err(self.error_for("ic0_canister_self_copy")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Cleanup { .. }
             | ApiType::Update { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -1623,7 +1685,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_controller_size")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -1649,7 +1711,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_controller_copy")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -1706,7 +1768,7 @@ impl SystemApi for SystemApiImpl {
                     },
                 ..
             }
-            | ApiType::Heartbeat {
+            | ApiType::SystemTask {
                 call_context_id, ..
             }
             | ApiType::ReplyCallback {
@@ -1752,6 +1814,7 @@ impl SystemApi for SystemApiImpl {
                             on_reply,
                             on_reject,
                             None,
+                            NO_DEADLINE,
                         ))?;
 
                 let msg = Request {
@@ -1761,6 +1824,7 @@ impl SystemApi for SystemApiImpl {
                     method_payload: payload,
                     sender_reply_callback: callback_id,
                     payment: Cycles::zero(),
+                    deadline: NO_DEADLINE,
                 };
                 self.push_output_request(msg)
             }
@@ -1818,7 +1882,7 @@ impl SystemApi for SystemApiImpl {
                     },
                 ..
             }
-            | ApiType::Heartbeat {
+            | ApiType::SystemTask {
                 outgoing_request, ..
             }
             | ApiType::ReplyCallback {
@@ -1887,7 +1951,7 @@ impl SystemApi for SystemApiImpl {
                     },
                 ..
             }
-            | ApiType::Heartbeat {
+            | ApiType::SystemTask {
                 outgoing_request, ..
             }
             | ApiType::ReplyCallback {
@@ -1934,7 +1998,7 @@ impl SystemApi for SystemApiImpl {
                     },
                 ..
             }
-            | ApiType::Heartbeat {
+            | ApiType::SystemTask {
                 outgoing_request, ..
             }
             | ApiType::ReplyCallback {
@@ -1953,6 +2017,55 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
+    fn ic0_call_with_best_effort_response(&mut self, timeout_seconds: u32) -> HypervisorResult<()> {
+        let result = match &mut self.api_type {
+            ApiType::Start { .. }
+            | ApiType::Init { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery {
+                query_kind: NonReplicatedQueryKind::Pure,
+                ..
+            }
+            | ApiType::Cleanup { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => {
+                Err(self.error_for("ic0_call_with_best_effort_response"))
+            }
+            ApiType::Update {
+                outgoing_request, ..
+            }
+            | ApiType::NonReplicatedQuery {
+                query_kind:
+                    NonReplicatedQueryKind::Stateful {
+                        outgoing_request, ..
+                    },
+                ..
+            }
+            | ApiType::SystemTask {
+                outgoing_request, ..
+            }
+            | ApiType::ReplyCallback {
+                outgoing_request, ..
+            }
+            | ApiType::RejectCallback {
+                outgoing_request, ..
+            } => match outgoing_request {
+                None => Err(HypervisorError::ContractViolation(
+                    "ic0.call_with_best_effort_response called when no call is under construction."
+                        .to_string(),
+                )),
+                Some(request) => request.set_timeout(timeout_seconds),
+            },
+        };
+        trace_syscall!(
+            self,
+            ic0_call_with_best_effort_response,
+            result,
+            timeout_seconds
+        );
+        result
+    }
+
     fn ic0_call_cycles_add(&mut self, amount: u64) -> HypervisorResult<()> {
         let result = self.ic0_call_cycles_add_helper("ic0_call_cycles_add", Cycles::from(amount));
         trace_syscall!(self, ic0_call_cycles_add, result, amount);
@@ -1987,21 +2100,25 @@ impl SystemApi for SystemApiImpl {
             | ApiType::PreUpgrade { .. }
             | ApiType::InspectMessage { .. } => Err(self.error_for("ic0_call_perform")),
             ApiType::Update {
+                time,
                 call_context_id,
                 outgoing_request,
                 ..
             }
-            | ApiType::Heartbeat {
+            | ApiType::SystemTask {
+                time,
                 call_context_id,
                 outgoing_request,
                 ..
             }
             | ApiType::ReplyCallback {
+                time,
                 call_context_id,
                 outgoing_request,
                 ..
             }
             | ApiType::RejectCallback {
+                time,
                 call_context_id,
                 outgoing_request,
                 ..
@@ -2015,6 +2132,7 @@ impl SystemApi for SystemApiImpl {
                 let req = into_request(
                     req_in_prep,
                     *call_context_id,
+                    *time,
                     &mut self.sandbox_safe_system_state,
                     &self.log,
                 )?;
@@ -2027,6 +2145,7 @@ impl SystemApi for SystemApiImpl {
                         call_context_id,
                         outgoing_request,
                     },
+                time,
                 ..
             } => {
                 let req_in_prep = outgoing_request.take().ok_or_else(|| {
@@ -2038,6 +2157,7 @@ impl SystemApi for SystemApiImpl {
                 let req = into_request(
                     req_in_prep,
                     *call_context_id,
+                    *time,
                     &mut self.sandbox_safe_system_state,
                     &self.log,
                 )?;
@@ -2053,7 +2173,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable_size")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2071,7 +2191,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable_grow")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2087,7 +2207,7 @@ impl SystemApi for SystemApiImpl {
                             Err(_) | Ok(-1) => self
                                 .memory_usage
                                 .deallocate_pages(additional_pages as usize),
-                            _ => {}
+                            _ => self.reserve_storage_cycles(additional_pages as usize)?,
                         }
                         res
                     }
@@ -2109,7 +2229,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable_read")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2143,7 +2263,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable_write")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2171,7 +2291,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable64_size")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2189,7 +2309,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable64_grow")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2205,7 +2325,7 @@ impl SystemApi for SystemApiImpl {
                             Err(_) | Ok(-1) => self
                                 .memory_usage
                                 .deallocate_pages(additional_pages as usize),
-                            _ => {}
+                            _ => self.reserve_storage_cycles(additional_pages as usize)?,
                         }
                         res
                     }
@@ -2227,7 +2347,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable64_read")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2261,7 +2381,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start {} => Err(self.error_for("ic0_stable64_write")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::Cleanup { .. }
             | ApiType::ReplicatedQuery { .. }
@@ -2289,7 +2409,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start { .. } => Err(self.error_for("ic0_time")),
             ApiType::Init { time, .. }
-            | ApiType::Heartbeat { time, .. }
+            | ApiType::SystemTask { time, .. }
             | ApiType::Update { time, .. }
             | ApiType::Cleanup { time, .. }
             | ApiType::NonReplicatedQuery { time, .. }
@@ -2342,8 +2462,18 @@ impl SystemApi for SystemApiImpl {
             if native_memory_grow_res == -1 {
                 return Ok(-1);
             }
+            if let Some(limit) = self.sandbox_safe_system_state.wasm_memory_limit() {
+                let new_size_in_pages = native_memory_grow_res as u64 + additional_pages as u64;
+                let bytes = NumBytes::from(new_size_in_pages * WASM_PAGE_SIZE_IN_BYTES as u64);
+                if bytes > limit {
+                    return Err(HypervisorError::WasmMemoryLimitExceeded { bytes, limit });
+                }
+            }
             match self.memory_usage.allocate_pages(additional_pages as usize) {
-                Ok(()) => Ok(native_memory_grow_res),
+                Ok(()) => {
+                    self.reserve_storage_cycles(additional_pages as usize)?;
+                    Ok(native_memory_grow_res)
+                }
                 Err(_err) => Err(HypervisorError::OutOfMemory),
             }
         };
@@ -2488,7 +2618,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::PreUpgrade { .. }
             | ApiType::InspectMessage { .. }
             | ApiType::Update { .. }
-            | ApiType::Heartbeat { .. } => Ok(0),
+            | ApiType::SystemTask { .. } => Ok(0),
             ApiType::ReplicatedQuery {
                 data_certificate, ..
             }
@@ -2507,7 +2637,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::ReplyCallback { .. }
             | ApiType::RejectCallback { .. }
@@ -2538,7 +2668,7 @@ impl SystemApi for SystemApiImpl {
         let result = match &self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::ReplyCallback { .. }
             | ApiType::RejectCallback { .. }
@@ -2607,7 +2737,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::NonReplicatedQuery { .. }
             | ApiType::InspectMessage { .. } => Err(self.error_for("ic0_certified_data_set")),
             ApiType::Init { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::ReplyCallback { .. }
             | ApiType::RejectCallback { .. }
@@ -2658,7 +2788,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::NonReplicatedQuery { .. }
             | ApiType::Init { .. }
             | ApiType::Cleanup { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::Update { .. }
             | ApiType::ReplyCallback { .. }
             | ApiType::RejectCallback { .. }
@@ -2673,6 +2803,71 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
+    fn ic0_is_controller(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<u32> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_is_controller")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Update { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::InspectMessage { .. } => {
+                let id_bytes = valid_subslice("ic0.is_controller", src, size, heap)?;
+                let principal_id =
+                    PrincipalId::try_from(id_bytes).map_err(HypervisorError::InvalidPrincipalId)?;
+                Ok(self
+                    .sandbox_safe_system_state
+                    .controllers
+                    .contains(&principal_id) as u32)
+            }
+        };
+        trace_syscall!(self, ic0_is_controller, result, src, size);
+        result
+    }
+
+    fn ic0_canister_version(&self) -> HypervisorResult<u64> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_canister_version")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Update { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::InspectMessage { .. } => Ok(self.sandbox_safe_system_state.canister_version),
+        };
+        trace_syscall!(self, ic0_canister_version, result);
+        result
+    }
+
+    fn ic0_in_replicated_execution(&self) -> HypervisorResult<i32> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_in_replicated_execution")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Update { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::InspectMessage { .. } => match self.execution_parameters.execution_mode {
+                ExecutionMode::Replicated => Ok(1),
+                ExecutionMode::NonReplicated => Ok(0),
+            },
+        };
+        trace_syscall!(self, ic0_in_replicated_execution, result);
+        result
+    }
+
     fn ic0_mint_cycles(&mut self, amount: u64) -> HypervisorResult<u64> {
         let result = match self.api_type {
             ApiType::Start { .. }
@@ -2683,7 +2878,7 @@ impl SystemApi for SystemApiImpl {
             | ApiType::NonReplicatedQuery { .. }
             | ApiType::InspectMessage { .. } => Err(self.error_for("ic0_mint_cycles")),
             ApiType::Update { .. }
-            | ApiType::Heartbeat { .. }
+            | ApiType::SystemTask { .. }
             | ApiType::ReplyCallback { .. }
             | ApiType::RejectCallback { .. } => {
                 self.sandbox_safe_system_state
@@ -2695,6 +2890,31 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
+    fn ic0_global_timer_set(&mut self, time: Time) -> HypervisorResult<Time> {
+        let result = match self.api_type {
+            ApiType::Start { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::InspectMessage { .. } => Err(self.error_for("ic0_global_timer_set")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Update { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. } => {
+                let previous_timer = self.sandbox_safe_system_state.set_global_timer(
+                    CanisterTimer::from_nanos_since_unix_epoch(time.as_nanos_since_unix_epoch()),
+                );
+                Ok(Time::from_nanos_since_unix_epoch(
+                    previous_timer.to_nanos_since_unix_epoch(),
+                ))
+            }
+        };
+        trace_syscall!(self, ic0_global_timer_set, result, time);
+        result
+    }
+
     fn ic0_debug_print(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
         const MAX_DEBUG_MESSAGE_SIZE: u32 = 32 * 1024;
         let size = size.min(MAX_DEBUG_MESSAGE_SIZE);
@@ -2715,6 +2935,17 @@ impl SystemApi for SystemApiImpl {
         Ok(())
     }
 
+    fn save_log_message(&mut self, src: u32, size: u32, heap: &[u8]) {
+        let size = size.min(MAX_CANISTER_LOG_BUFFER_SIZE as u32);
+        let content = match valid_subslice("save_log_message", src, size, heap) {
+            Ok(bytes) => bytes.to_vec(),
+            // Same as for `ic0.debug_print`, an invalid memory range is not an
+            // error here.
+            Err(_) => b"(log message out of memory bounds)".to_vec(),
+        };
+        self.sandbox_safe_system_state.append_canister_log(content);
+    }
+
     fn ic0_trap(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
         const MAX_ERROR_MESSAGE_SIZE: u32 = 16 * 1024;
         let size = size.min(MAX_ERROR_MESSAGE_SIZE);
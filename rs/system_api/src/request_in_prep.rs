@@ -2,9 +2,10 @@ use crate::{sandbox_safe_system_state::SandboxSafeSystemState, valid_subslice};
 use ic_interfaces::execution_environment::{HypervisorError, HypervisorResult};
 use ic_logger::ReplicaLogger;
 use ic_types::{
-    messages::{CallContextId, Request},
+    messages::{CallContextId, Request, MAX_CALL_TIMEOUT_SECONDS},
     methods::{Callback, WasmClosure},
-    CanisterId, Cycles, NumBytes, PrincipalId,
+    time::NO_DEADLINE,
+    CanisterId, CoarseTime, Cycles, NumBytes, PrincipalId, Time,
 };
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
@@ -37,6 +38,9 @@ pub struct RequestInPrep {
     cycles: Cycles,
     method_name: String,
     method_payload: Vec<u8>,
+    /// The timeout requested via `ic0.call_with_best_effort_response`, if
+    /// any. Makes this a best-effort call.
+    timeout_seconds: Option<u32>,
     /// The maximum size of a message that will go to a canister on another
     /// subnet.
     max_size_remote_subnet: NumBytes,
@@ -94,6 +98,7 @@ impl RequestInPrep {
             cycles: Cycles::zero(),
             method_name,
             method_payload: Vec::new(),
+            timeout_seconds: None,
             max_size_remote_subnet,
             multiplier_max_size_local_subnet,
         })
@@ -111,6 +116,21 @@ impl RequestInPrep {
         }
     }
 
+    /// Makes this a best-effort call that times out after `timeout_seconds`,
+    /// clamped to `1..=MAX_CALL_TIMEOUT_SECONDS` (so that a best-effort call
+    /// always has a non-zero deadline).
+    pub(crate) fn set_timeout(&mut self, timeout_seconds: u32) -> HypervisorResult<()> {
+        if self.timeout_seconds.is_some() {
+            Err(HypervisorError::ContractViolation(
+                "ic0.call_with_best_effort_response can be called at most once between `ic0.call_new` and `ic0.call_perform`"
+                    .to_string(),
+            ))
+        } else {
+            self.timeout_seconds = Some(timeout_seconds.clamp(1, MAX_CALL_TIMEOUT_SECONDS));
+            Ok(())
+        }
+    }
+
     pub(crate) fn take_cycles(self) -> Cycles {
         self.cycles
     }
@@ -153,10 +173,12 @@ pub(crate) fn into_request(
         cycles,
         method_name,
         method_payload,
+        timeout_seconds,
         max_size_remote_subnet,
         multiplier_max_size_local_subnet,
     }: RequestInPrep,
     call_context_id: CallContextId,
+    time: Time,
     sandbox_safe_system_state: &mut SandboxSafeSystemState,
     _logger: &ReplicaLogger,
 ) -> HypervisorResult<Request> {
@@ -174,6 +196,13 @@ pub(crate) fn into_request(
         }
     }
 
+    // A best-effort call times out `timeout_seconds` after the current round's
+    // time, rounded down to whole seconds.
+    let deadline = match timeout_seconds {
+        Some(timeout_seconds) => CoarseTime::floor(time).saturating_add_secs(timeout_seconds),
+        None => NO_DEADLINE,
+    };
+
     let callback_id = sandbox_safe_system_state.register_callback(Callback::new(
         call_context_id,
         Some(sender),
@@ -182,6 +211,7 @@ pub(crate) fn into_request(
         on_reply,
         on_reject,
         on_cleanup,
+        deadline,
     ))?;
 
     let req = Request {
@@ -191,6 +221,7 @@ pub(crate) fn into_request(
         method_payload,
         sender_reply_callback: callback_id,
         payment: cycles,
+        deadline,
     };
     // We cannot call `Request::payload_size_bytes()` before constructing the
     // request, so ensure our separate calculation matches the actual size.
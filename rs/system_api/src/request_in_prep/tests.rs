@@ -77,3 +77,42 @@ fn payloads_larger_than_intra_limit_rejected() {
         .extend_method_payload(0, 100, &heap)
         .unwrap_err();
 }
+
+fn request_in_prep_for_test() -> RequestInPrep {
+    let heap = vec![0; 1024];
+    let callback = WasmClosure::new(0, 0);
+    RequestInPrep::new(
+        CanisterId::from(1),
+        0,
+        1,
+        0,
+        1,
+        &heap,
+        callback.clone(),
+        callback,
+        NumBytes::from(10),
+        1,
+    )
+    .unwrap()
+}
+
+#[test]
+fn timeout_can_be_set_at_most_once() {
+    let mut req_in_prep = request_in_prep_for_test();
+    req_in_prep.set_timeout(10).unwrap();
+    req_in_prep.set_timeout(10).unwrap_err();
+    assert_eq!(Some(10), req_in_prep.timeout_seconds);
+}
+
+#[test]
+fn timeout_is_clamped() {
+    let mut req_in_prep = request_in_prep_for_test();
+    req_in_prep
+        .set_timeout(MAX_CALL_TIMEOUT_SECONDS + 1)
+        .unwrap();
+    assert_eq!(Some(MAX_CALL_TIMEOUT_SECONDS), req_in_prep.timeout_seconds);
+
+    let mut req_in_prep = request_in_prep_for_test();
+    req_in_prep.set_timeout(0).unwrap();
+    assert_eq!(Some(1), req_in_prep.timeout_seconds);
+}
@@ -3,9 +3,10 @@ use std::str::FromStr;
 use candid::Decode;
 use ic_base_types::{CanisterId, SubnetId};
 use ic_ic00_types::{
-    CanisterIdRecord, ComputeInitialEcdsaDealingsArgs, ECDSAPublicKeyArgs, EcdsaKeyId,
-    InstallCodeArgs, Method as Ic00Method, Payload, ProvisionalTopUpCanisterArgs,
-    SetControllerArgs, SignWithECDSAArgs, UpdateSettingsArgs,
+    CanisterIdRecord, CanisterInfoRequest, CanisterSnapshotArgs, ComputeInitialEcdsaDealingsArgs,
+    ECDSAPublicKeyArgs, EcdsaKeyId, InstallCodeArgs, Method as Ic00Method, Payload,
+    ProvisionalTopUpCanisterArgs, SetControllerArgs, SignWithECDSAArgs, TakeCanisterSnapshotArgs,
+    UpdateSettingsArgs,
 };
 use ic_replicated_state::NetworkTopology;
 
@@ -81,7 +82,9 @@ pub(super) fn resolve_destination(
         | Ok(Ic00Method::StopCanister)
         | Ok(Ic00Method::DeleteCanister)
         | Ok(Ic00Method::UninstallCode)
-        | Ok(Ic00Method::DepositCycles) => {
+        | Ok(Ic00Method::DepositCycles)
+        | Ok(Ic00Method::ListCanisterSnapshots)
+        | Ok(Ic00Method::FetchCanisterLogs) => {
             let args = Decode!(payload, CanisterIdRecord)?;
             let canister_id = args.get_canister_id();
             network_topology
@@ -91,6 +94,39 @@ pub(super) fn resolve_destination(
                     ResolveDestinationError::SubnetNotFound(canister_id, method.unwrap())
                 })
         }
+        Ok(Ic00Method::CanisterInfo) => {
+            let args = Decode!(payload, CanisterInfoRequest)?;
+            let canister_id = args.canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .ok_or({
+                    ResolveDestinationError::SubnetNotFound(canister_id, Ic00Method::CanisterInfo)
+                })
+        }
+        Ok(Ic00Method::TakeCanisterSnapshot) => {
+            let args = Decode!(payload, TakeCanisterSnapshotArgs)?;
+            let canister_id = args.get_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .ok_or({
+                    ResolveDestinationError::SubnetNotFound(
+                        canister_id,
+                        Ic00Method::TakeCanisterSnapshot,
+                    )
+                })
+        }
+        Ok(Ic00Method::LoadCanisterSnapshot) | Ok(Ic00Method::DeleteCanisterSnapshot) => {
+            let args = Decode!(payload, CanisterSnapshotArgs)?;
+            let canister_id = args.get_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .ok_or_else(|| {
+                    ResolveDestinationError::SubnetNotFound(canister_id, method.unwrap())
+                })
+        }
         Ok(Ic00Method::ProvisionalTopUpCanister) => {
             let args = ProvisionalTopUpCanisterArgs::decode(payload)?;
             let canister_id = args.get_canister_id();
@@ -1,14 +1,18 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
 use ic_base_types::{CanisterId, NumBytes, NumSeconds, PrincipalId, SubnetId};
-use ic_cycles_account_manager::{CyclesAccountManager, CyclesAccountManagerError};
+use ic_cycles_account_manager::{
+    CyclesAccountManager, CyclesAccountManagerError, ResourceSaturation,
+};
 use ic_ic00_types::IC_00;
 use ic_interfaces::execution_environment::{HypervisorError, HypervisorResult};
 use ic_logger::{info, ReplicaLogger};
 use ic_nns_constants::CYCLES_MINTING_CANISTER_ID;
+use ic_registry_routing_table::CanisterIdRanges;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    canister_state::DEFAULT_QUEUE_CAPACITY, CanisterStatus, NetworkTopology, SystemState,
+    canister_state::DEFAULT_QUEUE_CAPACITY, CanisterStatus, CanisterTimer, NetworkTopology,
+    SystemState, MAX_CANISTER_LOG_BUFFER_SIZE,
 };
 use ic_types::{
     messages::{CallContextId, CallbackId, Request},
@@ -48,9 +52,17 @@ pub enum CallbackUpdate {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemStateChanges {
     pub(super) new_certified_data: Option<Vec<u8>>,
+    pub(super) new_global_timer: Option<CanisterTimer>,
     pub(super) callback_updates: Vec<CallbackUpdate>,
+    /// The contents of the records to append to the canister's log, oldest
+    /// first.
+    canister_log_records: VecDeque<Vec<u8>>,
+    canister_log_size: usize,
     cycles_balance_change: CyclesBalanceChange,
     cycles_consumed: Cycles,
+    /// Cycles moved from the main balance to the reserved balance. They are
+    /// already subtracted in `cycles_balance_change`.
+    reserved_cycles: Cycles,
     call_context_balance_taken: BTreeMap<CallContextId, Cycles>,
     request_slots_used: BTreeMap<CanisterId, usize>,
     requests: Vec<Request>,
@@ -60,9 +72,13 @@ impl Default for SystemStateChanges {
     fn default() -> Self {
         Self {
             new_certified_data: None,
+            new_global_timer: None,
             callback_updates: vec![],
+            canister_log_records: VecDeque::new(),
+            canister_log_size: 0,
             cycles_balance_change: CyclesBalanceChange::zero(),
             cycles_consumed: Cycles::zero(),
+            reserved_cycles: Cycles::zero(),
             call_context_balance_taken: BTreeMap::new(),
             request_slots_used: BTreeMap::new(),
             requests: vec![],
@@ -74,7 +90,8 @@ impl SystemStateChanges {
     /// Checks that no cycles were created during the execution of this message
     /// (unless the canister is the cycles minting canister).
     fn cycle_change_is_valid(&self, is_cmc_canister: bool) -> bool {
-        let mut universal_cycle_change = self.cycles_balance_change;
+        let mut universal_cycle_change =
+            self.cycles_balance_change + CyclesBalanceChange::added(self.reserved_cycles);
         for call_context_balance_taken in self.call_context_balance_taken.values() {
             universal_cycle_change =
                 universal_cycle_change + CyclesBalanceChange::removed(*call_context_balance_taken);
@@ -108,8 +125,11 @@ impl SystemStateChanges {
     ) {
         // Verify total cycle change is not positive and update cycles balance.
         assert!(self.cycle_change_is_valid(system_state.canister_id == CYCLES_MINTING_CANISTER_ID));
-        self.cycles_balance_change
+        (self.cycles_balance_change + CyclesBalanceChange::added(self.reserved_cycles))
             .apply_ref(system_state.balance_mut());
+        system_state
+            .reserve_cycles(self.reserved_cycles)
+            .expect("Canister reserved more cycles than available or allowed");
 
         // Observe consumed cycles.
         system_state
@@ -188,6 +208,26 @@ impl SystemStateChanges {
             assert!(certified_data.len() <= CERTIFIED_DATA_MAX_LENGTH as usize);
             system_state.certified_data = certified_data.clone();
         }
+
+        // Update the global timer if it was set.
+        if let Some(new_global_timer) = self.new_global_timer {
+            system_state.global_timer = new_global_timer;
+        }
+
+        // Append the logged records to the canister's log.
+        for content in self.canister_log_records {
+            system_state.canister_log.add_record(time, content);
+        }
+    }
+
+    /// Appends the records logged during execution to the canister's log.
+    ///
+    /// Unlike the other changes, log records are kept even if the execution
+    /// failed, so this is called on its own for failed executions.
+    pub fn apply_canister_log_changes(self, time: Time, system_state: &mut SystemState) {
+        for content in self.canister_log_records {
+            system_state.canister_log.add_record(time, content);
+        }
     }
 }
 
@@ -201,11 +241,16 @@ pub struct SandboxSafeSystemState {
     pub system_state_changes: SystemStateChanges,
     pub(super) canister_id: CanisterId,
     pub(super) controller: PrincipalId,
+    pub(super) controllers: BTreeSet<PrincipalId>,
     pub(super) status: CanisterStatusView,
+    pub(super) canister_version: u64,
     pub(super) subnet_type: SubnetType,
+    global_timer: CanisterTimer,
     freeze_threshold: NumSeconds,
     memory_allocation: MemoryAllocation,
     initial_cycles_balance: Cycles,
+    initial_reserved_balance: Cycles,
+    reserved_balance_limit: Option<Cycles>,
     call_context_balances: BTreeMap<CallContextId, Cycles>,
     cycles_account_manager: CyclesAccountManager,
     // None indicates that we are in a context where the canister cannot
@@ -215,6 +260,10 @@ pub struct SandboxSafeSystemState {
     available_request_slots: BTreeMap<CanisterId, usize>,
     ic00_available_request_slots: usize,
     ic00_aliases: BTreeSet<CanisterId>,
+    // The canister ranges of this canister's subnet. Best-effort calls are
+    // limited to these canisters, see `push_output_request()`.
+    own_subnet_canister_ranges: CanisterIdRanges,
+    wasm_memory_limit: Option<NumBytes>,
 }
 
 impl SandboxSafeSystemState {
@@ -224,32 +273,46 @@ impl SandboxSafeSystemState {
     pub fn new_internal(
         canister_id: CanisterId,
         controller: PrincipalId,
+        controllers: BTreeSet<PrincipalId>,
         status: CanisterStatusView,
+        canister_version: u64,
         freeze_threshold: NumSeconds,
         memory_allocation: MemoryAllocation,
         initial_cycles_balance: Cycles,
+        initial_reserved_balance: Cycles,
+        reserved_balance_limit: Option<Cycles>,
         call_context_balances: BTreeMap<CallContextId, Cycles>,
         cycles_account_manager: CyclesAccountManager,
         next_callback_id: Option<u64>,
         available_request_slots: BTreeMap<CanisterId, usize>,
         ic00_available_request_slots: usize,
         ic00_aliases: BTreeSet<CanisterId>,
+        own_subnet_canister_ranges: CanisterIdRanges,
+        global_timer: CanisterTimer,
+        wasm_memory_limit: Option<NumBytes>,
     ) -> Self {
         Self {
             canister_id,
             controller,
+            controllers,
             status,
+            canister_version,
             subnet_type: cycles_account_manager.subnet_type(),
+            global_timer,
             freeze_threshold,
             memory_allocation,
             system_state_changes: SystemStateChanges::default(),
             initial_cycles_balance,
+            initial_reserved_balance,
+            reserved_balance_limit,
             call_context_balances,
             cycles_account_manager,
             next_callback_id,
             available_request_slots,
             ic00_available_request_slots,
             ic00_aliases,
+            own_subnet_canister_ranges,
+            wasm_memory_limit,
         }
     }
 
@@ -287,13 +350,23 @@ impl SandboxSafeSystemState {
             .min()
             .unwrap_or(DEFAULT_QUEUE_CAPACITY);
 
+        let own_subnet_canister_ranges = network_topology
+            .routing_table
+            .route(system_state.canister_id.get())
+            .map(|subnet_id| network_topology.routing_table.ranges(subnet_id))
+            .unwrap_or_default();
+
         Self::new_internal(
             system_state.canister_id,
             *system_state.controller(),
+            system_state.controllers.clone(),
             CanisterStatusView::from_full_status(&system_state.status),
+            system_state.canister_version,
             system_state.freeze_threshold,
             system_state.memory_allocation,
             system_state.balance(),
+            system_state.reserved_balance(),
+            system_state.reserved_balance_limit(),
             call_context_balances,
             cycles_account_manager,
             system_state
@@ -302,6 +375,9 @@ impl SandboxSafeSystemState {
             available_request_slots,
             ic00_available_request_slots,
             ic00_aliases,
+            own_subnet_canister_ranges,
+            system_state.global_timer,
+            system_state.wasm_memory_limit,
         )
     }
 
@@ -317,6 +393,31 @@ impl SandboxSafeSystemState {
         std::mem::take(&mut self.system_state_changes)
     }
 
+    /// Records a message to be appended to the canister's log. Records that
+    /// would be evicted from the log right away are dropped here already.
+    pub(super) fn append_canister_log(&mut self, content: Vec<u8>) {
+        let changes = &mut self.system_state_changes;
+        changes.canister_log_size += content.len();
+        changes.canister_log_records.push_back(content);
+        while changes.canister_log_size > MAX_CANISTER_LOG_BUFFER_SIZE
+            && changes.canister_log_records.len() > 1
+        {
+            let evicted = changes.canister_log_records.pop_front().unwrap();
+            changes.canister_log_size -= evicted.len();
+        }
+    }
+
+    /// Returns the maximum size of the canister's Wasm memory, if limited.
+    pub(super) fn wasm_memory_limit(&self) -> Option<NumBytes> {
+        self.wasm_memory_limit
+    }
+
+    /// Sets the global timer and returns the previous value.
+    pub(super) fn set_global_timer(&mut self, timer: CanisterTimer) -> CanisterTimer {
+        self.system_state_changes.new_global_timer = Some(timer);
+        std::mem::replace(&mut self.global_timer, timer)
+    }
+
     /// Only public for use in tests.
     #[doc(hidden)]
     pub fn register_callback(&mut self, callback: Callback) -> HypervisorResult<CallbackId> {
@@ -346,6 +447,48 @@ impl SandboxSafeSystemState {
         cycles_change.apply(self.initial_cycles_balance)
     }
 
+    pub(super) fn reserved_balance(&self) -> Cycles {
+        self.initial_reserved_balance + self.system_state_changes.reserved_cycles
+    }
+
+    /// Moves the cycles needed to pay for `allocated_bytes` of new storage
+    /// from the main balance to the reserved balance, see
+    /// `CyclesAccountManager::storage_reservation_cycles()`.
+    pub(super) fn reserve_storage_cycles(
+        &mut self,
+        allocated_bytes: NumBytes,
+        subnet_memory_saturation: &ResourceSaturation,
+    ) -> HypervisorResult<()> {
+        let cycles_to_reserve = self
+            .cycles_account_manager
+            .storage_reservation_cycles(allocated_bytes, subnet_memory_saturation);
+        if cycles_to_reserve.is_zero() {
+            return Ok(());
+        }
+
+        let cycles_balance = self.cycles_balance();
+        if cycles_to_reserve > cycles_balance {
+            return Err(HypervisorError::InsufficientCyclesInMemoryGrow {
+                bytes: allocated_bytes,
+                available: cycles_balance,
+                required: cycles_to_reserve,
+            });
+        }
+        if let Some(limit) = self.reserved_balance_limit {
+            let requested = self.reserved_balance() + cycles_to_reserve;
+            if requested > limit {
+                return Err(HypervisorError::ReservedCyclesLimitExceededInMemoryGrow {
+                    bytes: allocated_bytes,
+                    requested,
+                    limit,
+                });
+            }
+        }
+        self.update_balance_change(cycles_balance - cycles_to_reserve);
+        self.system_state_changes.reserved_cycles += cycles_to_reserve;
+        Ok(())
+    }
+
     pub(super) fn msg_cycles_available(&self, call_context_id: CallContextId) -> Cycles {
         let initial_available = *self
             .call_context_balances
@@ -467,6 +610,14 @@ impl SandboxSafeSystemState {
         compute_allocation: ComputeAllocation,
         msg: Request,
     ) -> Result<(), Request> {
+        // Deadlines are only part of the certified stream encoding as of
+        // certification version 11, so a best-effort request or response
+        // that crosses subnets would lose its deadline. Until then,
+        // best-effort calls are limited to canisters on the same subnet.
+        if msg.is_best_effort() && !self.is_on_own_subnet(msg.receiver) {
+            return Err(msg);
+        }
+
         let mut new_balance = self.cycles_balance();
         if self
             .cycles_account_manager
@@ -510,4 +661,13 @@ impl SandboxSafeSystemState {
         self.update_balance_change_consuming(new_balance);
         Ok(())
     }
+
+    /// Returns `true` if `canister_id` is hosted by this canister's subnet.
+    /// Calls to the management canister are never considered local, since
+    /// they may be routed to another subnet.
+    fn is_on_own_subnet(&self, canister_id: CanisterId) -> bool {
+        self.own_subnet_canister_ranges
+            .iter()
+            .any(|range| range.start <= canister_id && canister_id <= range.end)
+    }
 }
@@ -5,12 +5,46 @@ use ic_interfaces::execution_environment::{
     TrapCode::{HeapOutOfBounds, StableMemoryOutOfBounds, StableMemoryTooBigFor32Bit},
 };
 use ic_replicated_state::{canister_state::WASM_PAGE_SIZE_IN_BYTES, page_map, NumWasmPages};
+use ic_sys::PAGE_SIZE;
 use ic_types::MAX_STABLE_MEMORY_IN_BYTES;
+use ic_utils::deterministic_operations::deterministic_copy_from_slice;
 
 const MAX_64_BIT_STABLE_MEMORY_IN_PAGES: usize =
     (MAX_STABLE_MEMORY_IN_BYTES / WASM_PAGE_SIZE_IN_BYTES as u64) as usize;
 const MAX_32_BIT_STABLE_MEMORY_IN_PAGES: usize = 64 * 1024; // 4GiB
 
+/// Gives direct access to a stable memory that the embedder has mapped into
+/// the address space of the process. The embedder tracks the accessed and
+/// modified pages of the mapping in the same way as it does for the Wasm
+/// memory, so the accessed bytes do not need to be copied through the
+/// `PageMap`.
+pub trait MappedStableMemory: Send {
+    /// Returns the mapping. It covers the maximum size of the stable memory,
+    /// so the caller is responsible for checking the bounds against the
+    /// current size.
+    fn as_slice(&self) -> &[u8];
+
+    /// Same as `as_slice()`, but returns a mutable slice.
+    fn as_mut_slice(&mut self) -> &mut [u8];
+
+    /// Informs the embedder that the stable memory has grown to the given
+    /// size.
+    fn grow_to(&mut self, size: NumWasmPages);
+
+    /// Returns the number of pages of the mapping that the embedder has seen
+    /// modified so far.
+    fn num_dirty_pages(&self) -> usize;
+}
+
+/// Returns the error for an execution that modified more than `limit` pages
+/// of the stable memory.
+pub fn stable_memory_dirty_page_limit_exceeded(limit: u64) -> HypervisorError {
+    HypervisorError::MemoryAccessLimitExceeded(format!(
+        "Exceeded the limit for the number of modified pages in the stable memory in a single execution: limit {} KB.",
+        limit * (PAGE_SIZE as u64) / 1024,
+    ))
+}
+
 /// Essentially the same as a `page_map::Memory`, but we use a `Buffer` instead
 /// of a `PageMap`.
 pub struct StableMemory {
@@ -23,6 +57,12 @@ pub struct StableMemory {
     pub stable_memory_buffer: page_map::Buffer,
     /// The size of the canister's stable memory.
     pub stable_memory_size: NumWasmPages,
+    /// If present, then all reads and writes go to the mapped stable memory
+    /// and `stable_memory_buffer` remains unchanged.
+    mapped_stable_memory: Option<Box<dyn MappedStableMemory>>,
+    /// If present, then writes trap as soon as more pages than the limit have
+    /// been modified.
+    dirty_page_limit: Option<u64>,
 }
 
 impl StableMemory {
@@ -30,6 +70,68 @@ impl StableMemory {
         Self {
             stable_memory_buffer: page_map::Buffer::new(stable_memory.page_map),
             stable_memory_size: stable_memory.size,
+            mapped_stable_memory: None,
+            dirty_page_limit: None,
+        }
+    }
+
+    /// Limits the number of pages that can be modified by the execution.
+    pub(super) fn set_dirty_page_limit(&mut self, limit: u64) {
+        self.dirty_page_limit = Some(limit);
+    }
+
+    /// Returns an error if the execution has modified more pages than the
+    /// limit. For the mapped stable memory the pages are counted by the memory
+    /// tracker of the embedder when the write faults on them.
+    fn check_dirty_page_limit(&self) -> HypervisorResult<()> {
+        let limit = match self.dirty_page_limit {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        let dirty_pages = match &self.mapped_stable_memory {
+            Some(mapped) => mapped.num_dirty_pages(),
+            None => self.stable_memory_buffer.num_dirty_pages(),
+        };
+        if dirty_pages as u64 > limit {
+            return Err(stable_memory_dirty_page_limit_exceeded(limit));
+        }
+        Ok(())
+    }
+
+    /// Switches to the given mapped stable memory for all subsequent reads
+    /// and writes.
+    pub(super) fn set_mapped_stable_memory(&mut self, mut mapped: Box<dyn MappedStableMemory>) {
+        mapped.grow_to(self.stable_memory_size);
+        self.mapped_stable_memory = Some(mapped);
+    }
+
+    /// Copies bytes starting at the given offset of the stable memory into
+    /// `dst`. The caller must ensure that the range is within bounds.
+    fn read(&self, dst: &mut [u8], offset: usize) {
+        match &self.mapped_stable_memory {
+            Some(mapped) => {
+                deterministic_copy_from_slice(dst, &mapped.as_slice()[offset..offset + dst.len()])
+            }
+            None => self.stable_memory_buffer.read(dst, offset),
+        }
+    }
+
+    /// Copies `src` into the stable memory starting at the given offset. The
+    /// caller must ensure that the range is within bounds.
+    fn write(&mut self, src: &[u8], offset: usize) {
+        match &mut self.mapped_stable_memory {
+            Some(mapped) => deterministic_copy_from_slice(
+                &mut mapped.as_mut_slice()[offset..offset + src.len()],
+                src,
+            ),
+            None => self.stable_memory_buffer.write(src, offset),
+        }
+    }
+
+    fn set_size(&mut self, size: NumWasmPages) {
+        self.stable_memory_size = size;
+        if let Some(mapped) = &mut self.mapped_stable_memory {
+            mapped.grow_to(size);
         }
     }
 
@@ -53,7 +155,7 @@ impl StableMemory {
             return Ok(-1);
         }
 
-        self.stable_memory_size = NumWasmPages::from(initial_page_count + additional_pages);
+        self.set_size(NumWasmPages::from(initial_page_count + additional_pages));
 
         Ok(initial_page_count
             .try_into()
@@ -77,8 +179,7 @@ impl StableMemory {
         if dst + size > heap.len() {
             return Err(HypervisorError::Trapped(HeapOutOfBounds));
         }
-        self.stable_memory_buffer
-            .read(&mut heap[dst..dst + size], offset);
+        self.read(&mut heap[dst..dst + size], offset);
         Ok(())
     }
 
@@ -100,9 +201,8 @@ impl StableMemory {
             return Err(HypervisorError::Trapped(HeapOutOfBounds));
         }
 
-        self.stable_memory_buffer
-            .write(&heap[src..src + size], offset);
-        Ok(())
+        self.write(&heap[src..src + size], offset);
+        self.check_dirty_page_limit()
     }
 
     /// Determines size of stable memory in Web assembly pages.
@@ -119,8 +219,9 @@ impl StableMemory {
             return Ok(-1);
         }
 
-        self.stable_memory_size =
-            NumWasmPages::from(initial_page_count as usize + additional_pages as usize);
+        self.set_size(NumWasmPages::from(
+            initial_page_count as usize + additional_pages as usize,
+        ));
 
         Ok(initial_page_count as i64)
     }
@@ -153,8 +254,7 @@ impl StableMemory {
         if overflow || heap_end > heap.len() {
             return Err(HypervisorError::Trapped(HeapOutOfBounds));
         }
-        self.stable_memory_buffer
-            .read(&mut heap[dst..heap_end], offset);
+        self.read(&mut heap[dst..heap_end], offset);
         Ok(())
     }
 
@@ -187,8 +287,7 @@ impl StableMemory {
             return Err(HypervisorError::Trapped(HeapOutOfBounds));
         }
 
-        self.stable_memory_buffer
-            .write(&heap[src..heap_end], offset);
-        Ok(())
+        self.write(&heap[src..heap_end], offset);
+        self.check_dirty_page_limit()
     }
 }
@@ -2,7 +2,7 @@ use std::{convert::TryFrom, sync::Arc};
 
 use ic_base_types::{CanisterId, NumBytes, SubnetId};
 use ic_config::flag_status::FlagStatus;
-use ic_cycles_account_manager::CyclesAccountManager;
+use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_interfaces::execution_environment::{AvailableMemory, ExecutionMode};
 use ic_logger::replica_logger::no_op_logger;
 use ic_nns_constants::CYCLES_MINTING_CANISTER_ID;
@@ -20,6 +20,8 @@ use ic_test_utilities::{
 use ic_test_utilities::{state::SystemStateBuilder, types::ids::canister_test_id};
 use ic_types::{
     messages::{CallContextId, CallbackId, RejectContext},
+    methods::SystemMethod,
+    time::NO_DEADLINE,
     ComputeAllocation, Cycles, NumInstructions, Time,
 };
 use maplit::btreemap;
@@ -37,6 +39,7 @@ pub fn execution_parameters() -> ExecutionParameters {
         compute_allocation: ComputeAllocation::default(),
         subnet_type: SubnetType::Application,
         execution_mode: ExecutionMode::Replicated,
+        subnet_memory_saturation: ResourceSaturation::default(),
     }
 }
 
@@ -79,7 +82,19 @@ impl ApiTypeBuilder {
     }
 
     pub fn build_heartbeat_api() -> ApiType {
-        ApiType::heartbeat(mock_time(), CallContextId::from(1))
+        ApiType::system_task(
+            SystemMethod::CanisterHeartbeat,
+            mock_time(),
+            CallContextId::from(1),
+        )
+    }
+
+    pub fn build_global_timer_api() -> ApiType {
+        ApiType::system_task(
+            SystemMethod::CanisterGlobalTimer,
+            mock_time(),
+            CallContextId::from(1),
+        )
     }
 
     pub fn build_reply_api(incoming_cycles: Cycles) -> ApiType {
@@ -131,7 +146,7 @@ pub fn get_system_state() -> SystemState {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), NO_DEADLINE),
             Cycles::new(50),
             Time::from_nanos_since_unix_epoch(0),
         );
@@ -2,6 +2,7 @@ use ic_base_types::{NumBytes, NumSeconds};
 use ic_interfaces::execution_environment::SystemApi;
 use ic_logger::replica_logger::no_op_logger;
 use ic_nns_constants::CYCLES_MINTING_CANISTER_ID;
+use ic_registry_routing_table::{CanisterIdRange, RoutingTable};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{NetworkTopology, SystemState};
 use ic_system_api::sandbox_safe_system_state::SandboxSafeSystemState;
@@ -15,10 +16,13 @@ use ic_test_utilities::{
     },
 };
 use ic_types::{
-    messages::MAX_INTER_CANISTER_PAYLOAD_IN_BYTES, ComputeAllocation, Cycles, NumInstructions,
+    messages::MAX_INTER_CANISTER_PAYLOAD_IN_BYTES, time::NO_DEADLINE, CoarseTime,
+    ComputeAllocation, Cycles, NumInstructions,
 };
+use maplit::btreemap;
 use prometheus::IntCounter;
-use std::convert::From;
+use std::convert::{From, TryFrom};
+use std::sync::Arc;
 
 mod common;
 use common::*;
@@ -109,6 +113,73 @@ fn push_output_request_fails_not_enough_cycles_for_response() {
     );
 }
 
+#[test]
+fn push_output_request_limits_best_effort_calls_to_own_subnet() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_max_num_instructions(MAX_NUM_INSTRUCTIONS)
+        .build();
+
+    let system_state = SystemState::new_running(
+        canister_test_id(0),
+        user_test_id(1).get(),
+        INITIAL_CYCLES,
+        NumSeconds::from(100_000),
+    );
+
+    let routing_table = RoutingTable::try_from(btreemap! {
+        CanisterIdRange { start: canister_test_id(0), end: canister_test_id(9) } => subnet_test_id(1),
+        CanisterIdRange { start: canister_test_id(10), end: canister_test_id(19) } => subnet_test_id(2),
+    })
+    .unwrap();
+    let network_topology = NetworkTopology {
+        routing_table: Arc::new(routing_table),
+        ..Default::default()
+    };
+
+    let mut sandbox_safe_system_state =
+        SandboxSafeSystemState::new(&system_state, cycles_account_manager, &network_topology);
+
+    let deadline = CoarseTime::from_secs_since_unix_epoch(10);
+    let request = |receiver, deadline| {
+        RequestBuilder::default()
+            .sender(canister_test_id(0))
+            .receiver(receiver)
+            .deadline(deadline)
+            .build()
+    };
+
+    // Best-effort call to a canister on the same subnet.
+    assert_eq!(
+        sandbox_safe_system_state.push_output_request(
+            NumBytes::from(0),
+            ComputeAllocation::default(),
+            request(canister_test_id(1), deadline),
+        ),
+        Ok(())
+    );
+
+    // Best-effort call to a canister on another subnet.
+    let remote_request = request(canister_test_id(10), deadline);
+    assert_eq!(
+        sandbox_safe_system_state.push_output_request(
+            NumBytes::from(0),
+            ComputeAllocation::default(),
+            remote_request.clone(),
+        ),
+        Err(remote_request)
+    );
+
+    // Guaranteed response call to a canister on another subnet.
+    assert_eq!(
+        sandbox_safe_system_state.push_output_request(
+            NumBytes::from(0),
+            ComputeAllocation::default(),
+            request(canister_test_id(10), NO_DEADLINE),
+        ),
+        Ok(())
+    );
+}
+
 #[test]
 fn push_output_request_succeeds_with_enough_cycles() {
     let cycles_account_manager = CyclesAccountManagerBuilder::new()
@@ -7,8 +7,8 @@ use ic_interfaces::execution_environment::{
 use ic_logger::replica_logger::no_op_logger;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    testing::CanisterQueuesTesting, CallOrigin, Memory, NetworkTopology, NumWasmPages, PageMap,
-    SystemState,
+    testing::CanisterQueuesTesting, CallOrigin, CanisterTimer, Memory, NetworkTopology,
+    NumWasmPages, PageMap, SystemState,
 };
 use ic_system_api::{
     sandbox_safe_system_state::SandboxSafeSystemState, ApiType, DefaultOutOfInstructionsHandler,
@@ -26,6 +26,7 @@ use ic_test_utilities::{
 use ic_types::{
     messages::{CallContextId, CallbackId, RejectContext, MAX_RESPONSE_COUNT_BYTES},
     methods::{Callback, WasmClosure},
+    time::NO_DEADLINE,
     CountBytes, Cycles, NumBytes, NumInstructions, Time,
 };
 use std::{
@@ -94,6 +95,7 @@ fn test_canister_init_support() {
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(10));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -122,7 +124,11 @@ fn test_canister_init_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_mint_cycles(0));
+    assert_api_supported(api.ic0_global_timer_set(mock_time()));
 }
 
 #[test]
@@ -157,6 +163,7 @@ fn test_canister_update_support() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(10));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -185,7 +192,11 @@ fn test_canister_update_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_mint_cycles(0));
+    assert_api_supported(api.ic0_global_timer_set(mock_time()));
 }
 
 #[test]
@@ -220,6 +231,7 @@ fn test_canister_replicated_query_support() {
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(10));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -248,7 +260,11 @@ fn test_canister_replicated_query_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_not_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_mint_cycles(0));
+    assert_api_not_supported(api.ic0_global_timer_set(mock_time()));
 }
 
 #[test]
@@ -283,6 +299,7 @@ fn test_canister_pure_query_support() {
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(10));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -311,7 +328,11 @@ fn test_canister_pure_query_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_not_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_mint_cycles(0));
+    assert_api_not_supported(api.ic0_global_timer_set(mock_time()));
 }
 
 #[test]
@@ -356,6 +377,7 @@ fn test_canister_stateful_query_support() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(10));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -384,7 +406,11 @@ fn test_canister_stateful_query_support() {
     assert_api_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_not_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_mint_cycles(0));
+    assert_api_not_supported(api.ic0_global_timer_set(mock_time()));
 }
 
 #[test]
@@ -419,6 +445,7 @@ fn test_reply_api_support_on_nns() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(10));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -447,7 +474,11 @@ fn test_reply_api_support_on_nns() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_mint_cycles(0));
+    assert_api_supported(api.ic0_global_timer_set(mock_time()));
 }
 
 #[test]
@@ -482,6 +513,7 @@ fn test_reply_api_support_non_nns() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(10));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -510,7 +542,11 @@ fn test_reply_api_support_non_nns() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_mint_cycles(0));
+    assert_api_supported(api.ic0_global_timer_set(mock_time()));
 }
 
 #[test]
@@ -548,6 +584,7 @@ fn test_reject_api_support_on_nns() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(10));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -576,7 +613,11 @@ fn test_reject_api_support_on_nns() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_mint_cycles(0));
+    assert_api_supported(api.ic0_global_timer_set(mock_time()));
 }
 
 #[test]
@@ -614,6 +655,7 @@ fn test_reject_api_support_non_nns() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(10));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -642,7 +684,11 @@ fn test_reject_api_support_non_nns() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_mint_cycles(0));
+    assert_api_supported(api.ic0_global_timer_set(mock_time()));
 }
 
 #[test]
@@ -677,6 +723,7 @@ fn test_pre_upgrade_support() {
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(10));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -705,7 +752,11 @@ fn test_pre_upgrade_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_mint_cycles(0));
+    assert_api_supported(api.ic0_global_timer_set(mock_time()));
 }
 
 #[test]
@@ -740,6 +791,7 @@ fn test_start_support() {
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(10));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -768,7 +820,11 @@ fn test_start_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_not_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_not_supported(api.ic0_canister_status());
+    assert_api_not_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_not_supported(api.ic0_canister_version());
+    assert_api_not_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_mint_cycles(0));
+    assert_api_not_supported(api.ic0_global_timer_set(mock_time()));
 }
 
 #[test]
@@ -803,6 +859,7 @@ fn test_cleanup_support() {
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(10));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -831,7 +888,11 @@ fn test_cleanup_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_not_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_mint_cycles(0));
+    assert_api_supported(api.ic0_global_timer_set(mock_time()));
 }
 
 #[test]
@@ -871,6 +932,7 @@ fn test_inspect_message_support() {
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(10));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -899,7 +961,11 @@ fn test_inspect_message_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_not_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_mint_cycles(0));
+    assert_api_not_supported(api.ic0_global_timer_set(mock_time()));
 }
 
 #[test]
@@ -935,6 +1001,7 @@ fn test_canister_heartbeat_support() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(10));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -963,7 +1030,11 @@ fn test_canister_heartbeat_support() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_mint_cycles(0));
+    assert_api_supported(api.ic0_global_timer_set(mock_time()));
 }
 
 #[test]
@@ -998,6 +1069,7 @@ fn test_canister_heartbeat_support_nns() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(10));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -1026,7 +1098,11 @@ fn test_canister_heartbeat_support_nns() {
     assert_api_not_supported(api.ic0_data_certificate_copy(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_certified_data_set(0, 0, &[]));
     assert_api_supported(api.ic0_canister_status());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_mint_cycles(0));
+    assert_api_supported(api.ic0_global_timer_set(mock_time()));
 }
 
 #[test]
@@ -1176,7 +1252,7 @@ fn test_canister_balance() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), NO_DEADLINE),
             Cycles::new(50),
             Time::from_nanos_since_unix_epoch(0),
         );
@@ -1204,7 +1280,7 @@ fn test_canister_cycle_balance() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), NO_DEADLINE),
             Cycles::new(50),
             Time::from_nanos_since_unix_epoch(0),
         );
@@ -1238,7 +1314,7 @@ fn test_msg_cycles_available_traps() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), NO_DEADLINE),
             available_cycles,
             Time::from_nanos_since_unix_epoch(0),
         );
@@ -1316,6 +1392,81 @@ fn certified_data_set() {
     assert_eq!(system_state.certified_data, vec![10; 32])
 }
 
+#[test]
+fn global_timer_set() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let mut system_state = SystemStateBuilder::default().build();
+    let mut api = get_system_api(
+        ApiTypeBuilder::build_global_timer_api(),
+        &system_state,
+        cycles_account_manager,
+    );
+    let deadline = Time::from_nanos_since_unix_epoch(1_000);
+
+    // The timer is not set initially.
+    assert_eq!(
+        api.ic0_global_timer_set(deadline),
+        Ok(Time::from_nanos_since_unix_epoch(0))
+    );
+    // Setting the timer returns the previous deadline.
+    assert_eq!(
+        api.ic0_global_timer_set(Time::from_nanos_since_unix_epoch(2_000)),
+        Ok(deadline)
+    );
+    assert_eq!(
+        api.ic0_global_timer_set(deadline),
+        Ok(Time::from_nanos_since_unix_epoch(2_000))
+    );
+
+    let system_state_changes = api.into_system_state_changes();
+    system_state_changes.apply_changes(
+        mock_time(),
+        &mut system_state,
+        &default_network_topology(),
+        subnet_test_id(1),
+        &no_op_logger(),
+    );
+    assert_eq!(system_state.global_timer, CanisterTimer::Active(deadline));
+}
+
+#[test]
+fn save_log_message_appends_to_canister_log() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let mut system_state = SystemStateBuilder::default().build();
+    let mut api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &system_state,
+        cycles_account_manager,
+    );
+    let heap = b"hello world".to_vec();
+
+    api.save_log_message(0, 5, &heap);
+    // An out of bounds message is replaced by an error message.
+    api.save_log_message(5, 100, &heap);
+
+    let system_state_changes = api.into_system_state_changes();
+    system_state_changes.apply_changes(
+        mock_time(),
+        &mut system_state,
+        &default_network_topology(),
+        subnet_test_id(1),
+        &no_op_logger(),
+    );
+    let contents: Vec<Vec<u8>> = system_state
+        .canister_log
+        .records()
+        .iter()
+        .map(|record| record.content.clone())
+        .collect();
+    assert_eq!(
+        contents,
+        vec![
+            b"hello".to_vec(),
+            b"(log message out of memory bounds)".to_vec()
+        ]
+    );
+}
+
 #[test]
 fn data_certificate_copy() {
     let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
@@ -1387,6 +1538,48 @@ fn canister_status() {
     assert_eq!(api.ic0_canister_status(), Ok(3));
 }
 
+#[test]
+fn is_controller() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let system_state = SystemStateBuilder::default()
+        .with_controller(user_test_id(24))
+        .build();
+    let api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &system_state,
+        cycles_account_manager,
+    );
+
+    let controller = user_test_id(24).get().to_vec();
+    assert_eq!(
+        api.ic0_is_controller(0, controller.len() as u32, &controller),
+        Ok(1)
+    );
+    let other = user_test_id(25).get().to_vec();
+    assert_eq!(api.ic0_is_controller(0, other.len() as u32, &other), Ok(0));
+
+    // Bytes that are too long to be a principal trap.
+    let heap = vec![0; 30];
+    assert!(api.ic0_is_controller(0, 30, &heap).is_err());
+    // Out of bounds reads trap.
+    assert!(api
+        .ic0_is_controller(1, controller.len() as u32, &controller)
+        .is_err());
+}
+
+#[test]
+fn canister_version() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let mut system_state = SystemStateBuilder::default().build();
+    system_state.canister_version = 42;
+    let api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &system_state,
+        cycles_account_manager,
+    );
+    assert_eq!(api.ic0_canister_version(), Ok(42));
+}
+
 /// msg_cycles_accept() can accept all cycles in call context
 #[test]
 fn msg_cycles_accept_all_cycles_in_call_context() {
@@ -1397,7 +1590,7 @@ fn msg_cycles_accept_all_cycles_in_call_context() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), NO_DEADLINE),
             Cycles::from(amount),
             Time::from_nanos_since_unix_epoch(0),
         );
@@ -1420,7 +1613,7 @@ fn msg_cycles_accept_all_cycles_in_call_context_when_more_asked() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), NO_DEADLINE),
             Cycles::new(40),
             Time::from_nanos_since_unix_epoch(0),
         );
@@ -1450,7 +1643,7 @@ fn call_perform_not_enough_cycles_resets_state() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), NO_DEADLINE),
             Cycles::new(40),
             Time::from_nanos_since_unix_epoch(0),
         );
@@ -1602,6 +1795,7 @@ fn take_execution_result_properly_frees_memory() {
             WasmClosure::new(0, 0),
             WasmClosure::new(0, 0),
             None,
+            NO_DEADLINE,
         ))
         .unwrap();
     let mut api = SystemApiImpl::new(
@@ -1659,6 +1853,7 @@ fn push_output_request_respects_memory_limits() {
                 WasmClosure::new(0, 0),
                 WasmClosure::new(0, 0),
                 None,
+                NO_DEADLINE,
             ))
             .unwrap();
         let mut api = SystemApiImpl::new(
@@ -1751,6 +1946,7 @@ fn push_output_request_oversized_request_memory_limits() {
             WasmClosure::new(0, 0),
             WasmClosure::new(0, 0),
             None,
+            NO_DEADLINE,
         ))
         .unwrap();
     let mut api = SystemApiImpl::new(
@@ -21,6 +21,7 @@ use ic_interfaces::crypto::{
 use ic_interfaces::crypto::{MultiSigVerifier, MultiSigner, Signable};
 use ic_interfaces::registry::RegistryClient;
 use ic_protobuf::crypto::v1::NodePublicKeys;
+use ic_protobuf::registry::crypto::v1::{PublicKey as PublicKeyProto, X509PublicKeyCert};
 use ic_registry_client::client::RegistryClientImpl;
 use ic_registry_client_fake::FakeRegistryClient;
 use ic_registry_proto_data_provider::ProtoRegistryDataProvider;
@@ -542,6 +543,14 @@ impl KeyManager for CryptoReturningOk {
     fn node_public_keys(&self) -> NodePublicKeys {
         unimplemented!()
     }
+
+    fn rotate_node_signing_key(&self) -> CryptoResult<PublicKeyProto> {
+        unimplemented!()
+    }
+
+    fn rotate_tls_key(&self) -> CryptoResult<X509PublicKeyCert> {
+        unimplemented!()
+    }
 }
 
 impl IDkgProtocol for CryptoReturningOk {
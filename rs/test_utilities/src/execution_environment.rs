@@ -18,9 +18,9 @@ use ic_execution_environment::{
     Hypervisor, IngressHistoryWriterImpl, InternalHttpQueryHandler, RoundInstructions, RoundLimits,
 };
 use ic_ic00_types::{
-    CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgs, CanisterStatusType, EcdsaKeyId,
-    EmptyBlob, InstallCodeArgs, Method, Payload, ProvisionalCreateCanisterWithCyclesArgs,
-    UpdateSettingsArgs,
+    CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgs, CanisterSnapshotArgs,
+    CanisterStatusType, EcdsaKeyId, EmptyBlob, InstallCodeArgs, LogVisibility, Method, Payload,
+    ProvisionalCreateCanisterWithCyclesArgs, TakeCanisterSnapshotArgs, UpdateSettingsArgs,
 };
 use ic_interfaces::execution_environment::{
     IngressHistoryWriter, QueryHandler, RegistryExecutionSettings,
@@ -48,6 +48,7 @@ use ic_types::Time;
 use ic_types::{
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{AnonymousQuery, CallbackId, MessageId, RequestOrResponse, Response, UserQuery},
+    methods::SystemMethod,
     CanisterId, Cycles, NumInstructions, UserId,
 };
 use ic_types_test_utils::ids::{subnet_test_id, user_test_id};
@@ -378,6 +379,108 @@ impl ExecutionTest {
         self.subnet_message(Method::UpdateSettings, payload)
     }
 
+    /// Takes a snapshot of the given canister, optionally replacing an
+    /// existing snapshot.
+    pub fn take_canister_snapshot(
+        &mut self,
+        canister_id: CanisterId,
+        replace_snapshot: Option<Vec<u8>>,
+    ) -> Result<WasmResult, UserError> {
+        let payload = TakeCanisterSnapshotArgs::new(canister_id, replace_snapshot).encode();
+        self.subnet_message(Method::TakeCanisterSnapshot, payload)
+    }
+
+    /// Loads the given snapshot into the given canister.
+    pub fn load_canister_snapshot(
+        &mut self,
+        canister_id: CanisterId,
+        snapshot_id: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        let payload = CanisterSnapshotArgs::new(canister_id, snapshot_id).encode();
+        self.subnet_message(Method::LoadCanisterSnapshot, payload)
+    }
+
+    /// Lists the snapshots of the given canister.
+    pub fn list_canister_snapshots(
+        &mut self,
+        canister_id: CanisterId,
+    ) -> Result<WasmResult, UserError> {
+        let payload = CanisterIdRecord::from(canister_id).encode();
+        self.subnet_message(Method::ListCanisterSnapshots, payload)
+    }
+
+    /// Deletes the given snapshot of the given canister.
+    pub fn delete_canister_snapshot(
+        &mut self,
+        canister_id: CanisterId,
+        snapshot_id: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        let payload = CanisterSnapshotArgs::new(canister_id, snapshot_id).encode();
+        self.subnet_message(Method::DeleteCanisterSnapshot, payload)
+    }
+
+    /// Updates the log visibility of the given canister.
+    pub fn update_log_visibility(
+        &mut self,
+        canister_id: CanisterId,
+        log_visibility: LogVisibility,
+    ) -> Result<WasmResult, UserError> {
+        let payload = UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgs {
+                log_visibility: Some(log_visibility),
+                ..Default::default()
+            },
+        }
+        .encode();
+        self.subnet_message(Method::UpdateSettings, payload)
+    }
+
+    /// Updates the Wasm memory limit and threshold of the given canister.
+    pub fn update_wasm_memory_limit(
+        &mut self,
+        canister_id: CanisterId,
+        wasm_memory_limit: NumBytes,
+        wasm_memory_threshold: NumBytes,
+    ) -> Result<WasmResult, UserError> {
+        let payload = UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgs {
+                wasm_memory_limit: Some(candid::Nat::from(wasm_memory_limit.get())),
+                wasm_memory_threshold: Some(candid::Nat::from(wasm_memory_threshold.get())),
+                ..Default::default()
+            },
+        }
+        .encode();
+        self.subnet_message(Method::UpdateSettings, payload)
+    }
+
+    /// Updates the reserved cycles limit of the given canister.
+    pub fn update_reserved_cycles_limit(
+        &mut self,
+        canister_id: CanisterId,
+        reserved_cycles_limit: Cycles,
+    ) -> Result<WasmResult, UserError> {
+        let payload = UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgs {
+                reserved_cycles_limit: Some(candid::Nat::from(reserved_cycles_limit.get())),
+                ..Default::default()
+            },
+        }
+        .encode();
+        self.subnet_message(Method::UpdateSettings, payload)
+    }
+
+    /// Fetches the log of the given canister.
+    pub fn fetch_canister_logs(
+        &mut self,
+        canister_id: CanisterId,
+    ) -> Result<WasmResult, UserError> {
+        let payload = CanisterIdRecord::from(canister_id).encode();
+        self.subnet_message(Method::FetchCanisterLogs, payload)
+    }
+
     /// Installs the given Wasm binary in the given canister.
     pub fn install_canister(
         &mut self,
@@ -563,6 +666,27 @@ impl ExecutionTest {
 
     /// Executes the heartbeat method of the given canister.
     pub fn heartbeat(&mut self, canister_id: CanisterId) -> Result<(), CanisterHeartbeatError> {
+        self.system_task(canister_id, SystemMethod::CanisterHeartbeat)
+    }
+
+    /// Executes the global timer method of the given canister.
+    pub fn global_timer(&mut self, canister_id: CanisterId) -> Result<(), CanisterHeartbeatError> {
+        self.system_task(canister_id, SystemMethod::CanisterGlobalTimer)
+    }
+
+    /// Executes the `canister_on_low_wasm_memory` method of the given canister.
+    pub fn on_low_wasm_memory(
+        &mut self,
+        canister_id: CanisterId,
+    ) -> Result<(), CanisterHeartbeatError> {
+        self.system_task(canister_id, SystemMethod::CanisterOnLowWasmMemory)
+    }
+
+    fn system_task(
+        &mut self,
+        canister_id: CanisterId,
+        system_task: SystemMethod,
+    ) -> Result<(), CanisterHeartbeatError> {
         let mut state = self.state.take().unwrap();
         let canister = state.take_canister_state(&canister_id).unwrap();
         let network_topology = Arc::new(state.metadata.network_topology.clone());
@@ -571,8 +695,9 @@ impl ExecutionTest {
             subnet_available_memory: self.subnet_available_memory.get().into(),
         };
         let instructions_before = round_limits.instructions;
-        let (canister, result) = self.exec_env.execute_canister_heartbeat(
+        let (canister, result) = self.exec_env.execute_canister_system_task(
             canister,
+            system_task,
             self.instruction_limits.clone(),
             network_topology,
             self.time,
@@ -711,7 +836,28 @@ impl ExecutionTest {
         message_id
     }
 
+    /// Sends an `install_code` message to the IC management canister and
+    /// executes only its first slice. The remaining slices can be executed
+    /// using `execute_slice()` of the target canister.
+    pub fn dts_install_code(&mut self, args: InstallCodeArgs) -> MessageId {
+        let mut state = self.state.take().unwrap();
+        let message_id = self.next_message_id();
+        let message = IngressBuilder::new()
+            .message_id(message_id.clone())
+            .source(self.user_id)
+            .receiver(CanisterId::ic_00())
+            .method_name(Method::InstallCode)
+            .method_payload(args.encode())
+            .build();
+        state.subnet_queues_mut().push_ingress(message);
+        let message = state.pop_subnet_input().unwrap();
+        self.state = Some(state);
+        self.execute_subnet_message_slice(message);
+        message_id
+    }
+
     // Executes a single subnet message from the subnet input queue.
+    // A paused `install_code` message is executed until completion.
     // Return a progress flag indicating if the message was executed or not.
     fn execute_subnet_message(&mut self) -> bool {
         let mut state = self.state.take().unwrap();
@@ -722,6 +868,20 @@ impl ExecutionTest {
                 return false;
             }
         };
+        self.state = Some(state);
+        let maybe_canister_id = get_canister_id_if_install_code(message.clone());
+        self.execute_subnet_message_slice(message);
+        if let Some(canister_id) = maybe_canister_id {
+            while self.has_long_install_code(canister_id) {
+                self.execute_slice(canister_id);
+            }
+        }
+        true
+    }
+
+    // Executes the first slice of the given subnet message.
+    fn execute_subnet_message_slice(&mut self, message: CanisterInputMessage) {
+        let state = self.state.take().unwrap();
         let maybe_canister_id = get_canister_id_if_install_code(message.clone());
         let mut round_limits = RoundLimits {
             instructions: RoundInstructions::from(i64::MAX),
@@ -749,7 +909,43 @@ impl ExecutionTest {
                 instructions_executed,
             );
         }
-        true
+    }
+
+    // Returns true if the given canister has a paused or aborted
+    // `install_code` execution.
+    fn has_long_install_code(&self, canister_id: CanisterId) -> bool {
+        self.state()
+            .canister_state(&canister_id)
+            .map_or(false, |canister| {
+                canister.has_paused_install_code() || canister.has_aborted_install_code()
+            })
+    }
+
+    // Executes a slice of a paused `install_code` message or starts executing
+    // an aborted one.
+    fn execute_install_code_slice(&mut self, canister_id: CanisterId) {
+        let state = self.state.take().unwrap();
+        let mut round_limits = RoundLimits {
+            instructions: RoundInstructions::from(i64::MAX),
+            subnet_available_memory: self.subnet_available_memory.get().into(),
+        };
+        let instructions_before = round_limits.instructions;
+        let new_state = self.exec_env.resume_install_code(
+            state,
+            &canister_id,
+            self.install_code_instruction_limits.clone(),
+            &mut round_limits,
+        );
+        let instructions_executed =
+            as_num_instructions(instructions_before - round_limits.instructions);
+        self.subnet_available_memory
+            .set(round_limits.subnet_available_memory.get());
+        self.state = Some(new_state);
+        self.update_execution_stats(
+            canister_id,
+            self.install_code_instruction_limits.message(),
+            instructions_executed,
+        );
     }
 
     /// Inducts and executes all pending messages.
@@ -780,7 +976,10 @@ impl ExecutionTest {
         for canister_id in canister_ids {
             let network_topology = Arc::new(state.metadata.network_topology.clone());
             let mut canister = canisters.remove(&canister_id).unwrap();
-            while canister.is_active() {
+            while canister.is_active()
+                && !canister.has_paused_install_code()
+                && !canister.has_aborted_install_code()
+            {
                 let instructions_before = round_limits.instructions;
                 let result = execute_canister(
                     &self.exec_env,
@@ -826,6 +1025,10 @@ impl ExecutionTest {
 
     /// Executes a slice of the given canister.
     pub fn execute_slice(&mut self, canister_id: CanisterId) {
+        if self.has_long_install_code(canister_id) {
+            self.execute_install_code_slice(canister_id);
+            return;
+        }
         let mut state = self.state.take().unwrap();
         let mut canisters = state.take_canister_states();
         let network_topology = Arc::new(state.metadata.network_topology.clone());
@@ -1036,10 +1239,13 @@ pub struct ExecutionTestBuilder {
     initial_canister_cycles: Cycles,
     subnet_total_memory: i64,
     subnet_message_memory: i64,
+    subnet_memory_threshold: NumBytes,
     registry_settings: RegistryExecutionSettings,
     manual_execution: bool,
     rate_limiting_of_instructions: bool,
     deterministic_time_slicing: bool,
+    wasm_native_stable_memory: bool,
+    stable_memory_dirty_page_limit: u64,
     allocatable_compute_capacity_in_percent: usize,
     subnet_features: String,
 }
@@ -1068,10 +1274,15 @@ impl Default for ExecutionTestBuilder {
             initial_canister_cycles: INITIAL_CANISTER_CYCLES,
             subnet_total_memory,
             subnet_message_memory: subnet_total_memory,
+            subnet_memory_threshold: ic_config::execution_environment::Config::default()
+                .subnet_memory_threshold,
             registry_settings: test_registry_settings(),
             manual_execution: false,
             rate_limiting_of_instructions: false,
             deterministic_time_slicing: false,
+            wasm_native_stable_memory: false,
+            stable_memory_dirty_page_limit: ic_config::execution_environment::Config::default()
+                .stable_memory_dirty_page_limit,
             allocatable_compute_capacity_in_percent: 100,
             subnet_features: String::default(),
         }
@@ -1167,6 +1378,16 @@ impl ExecutionTestBuilder {
         }
     }
 
+    /// Sets the subnet memory usage above which allocating storage reserves
+    /// cycles. The subnet memory usage is the default subnet memory capacity
+    /// minus the subnet total memory.
+    pub fn with_subnet_memory_threshold(self, subnet_memory_threshold: u64) -> Self {
+        Self {
+            subnet_memory_threshold: NumBytes::from(subnet_memory_threshold),
+            ..self
+        }
+    }
+
     pub fn with_subnet_message_memory(self, subnet_message_memory: i64) -> Self {
         Self {
             subnet_message_memory,
@@ -1212,6 +1433,20 @@ impl ExecutionTestBuilder {
         }
     }
 
+    pub fn with_wasm_native_stable_memory(self) -> Self {
+        Self {
+            wasm_native_stable_memory: true,
+            ..self
+        }
+    }
+
+    pub fn with_stable_memory_dirty_page_limit(self, stable_memory_dirty_page_limit: u64) -> Self {
+        Self {
+            stable_memory_dirty_page_limit,
+            ..self
+        }
+    }
+
     pub fn with_allocatable_compute_capacity_in_percent(
         self,
         allocatable_compute_capacity_in_percent: usize,
@@ -1324,10 +1559,18 @@ impl ExecutionTestBuilder {
         } else {
             FlagStatus::Disabled
         };
+        let wasm_native_stable_memory = if self.wasm_native_stable_memory {
+            FlagStatus::Enabled
+        } else {
+            FlagStatus::Disabled
+        };
         let config = Config {
             rate_limiting_of_instructions,
             deterministic_time_slicing,
+            wasm_native_stable_memory,
+            stable_memory_dirty_page_limit: self.stable_memory_dirty_page_limit,
             allocatable_compute_capacity_in_percent: self.allocatable_compute_capacity_in_percent,
+            subnet_memory_threshold: self.subnet_memory_threshold,
             ..Config::default()
         };
         let hypervisor = Hypervisor::new(
@@ -1381,7 +1624,7 @@ impl ExecutionTestBuilder {
                 self.slice_instruction_limit,
             ),
             install_code_instruction_limits: InstructionLimits::new(
-                FlagStatus::Disabled, // TODO(RUN-221): Enable once implemented.
+                deterministic_time_slicing,
                 self.install_code_instruction_limit,
                 self.slice_instruction_limit,
             ),
@@ -29,6 +29,7 @@ use ic_types::messages::CallbackId;
 use ic_types::methods::{Callback, WasmClosure};
 use ic_types::{
     messages::{Ingress, Request, RequestOrResponse},
+    time::NO_DEADLINE,
     xnet::{StreamHeader, StreamIndex, StreamIndexedQueue},
     CanisterId, ComputeAllocation, Cycles, ExecutionRound, MemoryAllocation, NumBytes, PrincipalId,
     SubnetId, Time,
@@ -475,7 +476,6 @@ pub fn initial_execution_state() -> ExecutionState {
         exports: ExportedFunctions::new(BTreeSet::new()),
         metadata: wasm_metadata,
         last_executed_round: ExecutionRound::from(0),
-        task_queue: Default::default(),
     }
 }
 
@@ -676,7 +676,7 @@ pub fn register_callback(
         .call_context_manager_mut()
         .unwrap();
     let call_context_id = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(originator, callback_id),
+        CallOrigin::CanisterUpdate(originator, callback_id, NO_DEADLINE),
         Cycles::zero(),
         Time::from_nanos_since_unix_epoch(0),
     );
@@ -689,6 +689,7 @@ pub fn register_callback(
         WasmClosure::new(0, 2),
         WasmClosure::new(0, 2),
         None,
+        NO_DEADLINE,
     ));
 }
 
@@ -2,6 +2,7 @@ use std::convert::TryFrom;
 use std::sync::Arc;
 
 use ic_config::flag_status::FlagStatus;
+use ic_cycles_account_manager::ResourceSaturation;
 use ic_embedders::{wasm_utils::compile, wasmtime_embedder::WasmtimeInstance, WasmtimeEmbedder};
 use ic_interfaces::execution_environment::{AvailableMemory, ExecutionMode, SystemApi};
 use ic_logger::replica_logger::no_op_logger;
@@ -110,6 +111,7 @@ impl WasmtimeInstanceBuilder {
                 compute_allocation: ComputeAllocation::default(),
                 subnet_type: self.subnet_type,
                 execution_mode: ExecutionMode::Replicated,
+                subnet_memory_saturation: ResourceSaturation::default(),
             },
             AvailableMemory::new(i64::MAX / 2, i64::MAX / 2),
             Memory::default(),
@@ -124,6 +126,7 @@ impl WasmtimeInstanceBuilder {
                 &self.globals,
                 ic_replicated_state::NumWasmPages::from(0),
                 PageMap::default(),
+                None,
                 ModificationTracking::Track,
                 api,
             )
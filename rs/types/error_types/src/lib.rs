@@ -22,6 +22,9 @@ pub enum RejectCode {
     DestinationInvalid = 3,
     CanisterReject = 4,
     CanisterError = 5,
+    /// The outcome of a best-effort call is unknown, e.g. because its deadline
+    /// expired before a response was received.
+    SysUnknown = 6,
 }
 
 impl ToString for RejectCode {
@@ -38,6 +41,7 @@ impl RejectCode {
             RejectCode::DestinationInvalid => "DESTINATION_INVALID",
             RejectCode::CanisterReject => "CANISTER_REJECT",
             RejectCode::CanisterError => "CANISTER_ERROR",
+            RejectCode::SysUnknown => "SYS_UNKNOWN",
         }
     }
 }
@@ -51,6 +55,7 @@ impl TryFrom<u64> for RejectCode {
             3 => Ok(RejectCode::DestinationInvalid),
             4 => Ok(RejectCode::CanisterReject),
             5 => Ok(RejectCode::CanisterError),
+            6 => Ok(RejectCode::SysUnknown),
             _ => Err(TryFromError::ValueOutOfRange(code)),
         }
     }
@@ -70,6 +75,7 @@ impl From<ErrorCode> for RejectCode {
             CanisterWasmModuleNotFound => DestinationInvalid,
             CanisterAlreadyInstalled => DestinationInvalid,
             CanisterEmpty => DestinationInvalid,
+            CanisterSnapshotNotFound => DestinationInvalid,
             CanisterNonEmpty => CanisterError,
             CanisterOutOfCycles => CanisterError,
             CanisterTrapped => CanisterError,
@@ -97,6 +103,14 @@ impl From<ErrorCode> for RejectCode {
             CanisterWasmEngineError => CanisterError,
             CanisterInstructionLimitExceeded => CanisterError,
             CanisterInstallCodeRateLimited => SysTransient,
+            CanisterSnapshotLimitExceeded => CanisterError,
+            QueryCallGraphTooDeep => CanisterError,
+            QueryCallGraphTotalInstructionLimitExceeded => CanisterError,
+            CompositeQueryCalledInReplicatedMode => CanisterError,
+            CanisterMemoryAccessLimitExceeded => CanisterError,
+            CanisterWasmMemoryLimitExceeded => CanisterError,
+            InsufficientCyclesInMemoryGrow => CanisterError,
+            ReservedCyclesLimitExceededInMemoryGrow => CanisterError,
         }
     }
 }
@@ -118,6 +132,7 @@ pub enum ErrorCode {
     CanisterAlreadyInstalled = 303,
     CanisterWasmModuleNotFound = 304,
     CanisterEmpty = 305,
+    CanisterSnapshotNotFound = 306,
     InsufficientTransferFunds = 401,
     InsufficientMemoryAllocation = 402,
     InsufficientCyclesForCreateCanister = 403,
@@ -145,6 +160,14 @@ pub enum ErrorCode {
     CanisterWasmEngineError = 521,
     CanisterInstructionLimitExceeded = 522,
     CanisterInstallCodeRateLimited = 523,
+    CanisterSnapshotLimitExceeded = 524,
+    QueryCallGraphTooDeep = 525,
+    QueryCallGraphTotalInstructionLimitExceeded = 526,
+    CompositeQueryCalledInReplicatedMode = 527,
+    CanisterMemoryAccessLimitExceeded = 528,
+    CanisterWasmMemoryLimitExceeded = 529,
+    InsufficientCyclesInMemoryGrow = 530,
+    ReservedCyclesLimitExceededInMemoryGrow = 531,
 }
 
 impl TryFrom<u64> for ErrorCode {
@@ -160,6 +183,7 @@ impl TryFrom<u64> for ErrorCode {
             303 => Ok(ErrorCode::CanisterAlreadyInstalled),
             304 => Ok(ErrorCode::CanisterWasmModuleNotFound),
             305 => Ok(ErrorCode::CanisterEmpty),
+            306 => Ok(ErrorCode::CanisterSnapshotNotFound),
             401 => Ok(ErrorCode::InsufficientTransferFunds),
             402 => Ok(ErrorCode::InsufficientMemoryAllocation),
             403 => Ok(ErrorCode::InsufficientCyclesForCreateCanister),
@@ -187,6 +211,14 @@ impl TryFrom<u64> for ErrorCode {
             521 => Ok(ErrorCode::CanisterWasmEngineError),
             522 => Ok(ErrorCode::CanisterInstructionLimitExceeded),
             523 => Ok(ErrorCode::CanisterInstallCodeRateLimited),
+            524 => Ok(ErrorCode::CanisterSnapshotLimitExceeded),
+            525 => Ok(ErrorCode::QueryCallGraphTooDeep),
+            526 => Ok(ErrorCode::QueryCallGraphTotalInstructionLimitExceeded),
+            527 => Ok(ErrorCode::CompositeQueryCalledInReplicatedMode),
+            528 => Ok(ErrorCode::CanisterMemoryAccessLimitExceeded),
+            529 => Ok(ErrorCode::CanisterWasmMemoryLimitExceeded),
+            530 => Ok(ErrorCode::InsufficientCyclesInMemoryGrow),
+            531 => Ok(ErrorCode::ReservedCyclesLimitExceededInMemoryGrow),
             _ => Err(TryFromError::ValueOutOfRange(err)),
         }
     }
@@ -7,7 +7,11 @@ use ic_base_types::{CanisterId, NodeId, NumBytes, PrincipalId, RegistryVersion,
 use ic_error_types::{ErrorCode, UserError};
 use ic_protobuf::registry::crypto::v1::PublicKey;
 use ic_protobuf::registry::subnet::v1::{InitialIDkgDealings, InitialNiDkgTranscriptRecord};
-use ic_protobuf::{proxy::ProxyDecodeError, registry::crypto::v1 as pb_registry_crypto};
+use ic_protobuf::state::canister_state_bits::v1 as pb_canister_state_bits;
+use ic_protobuf::{
+    proxy::{try_from_option_field, ProxyDecodeError},
+    registry::crypto::v1 as pb_registry_crypto,
+};
 use num_traits::cast::ToPrimitive;
 use serde::Serialize;
 use std::{collections::BTreeSet, convert::TryFrom, fmt, slice::Iter, str::FromStr};
@@ -16,6 +20,8 @@ use strum_macros::{Display, EnumIter, EnumString};
 /// The id of the management canister.
 pub const IC_00: CanisterId = CanisterId::ic_00();
 pub const MAX_CONTROLLERS: usize = 10;
+/// The maximum number of snapshots a canister can have at any point in time.
+pub const MAX_CANISTER_SNAPSHOTS: usize = 1;
 pub use http::{CanisterHttpRequestArgs, CanisterHttpResponsePayload, HttpHeader, HttpMethod};
 pub use provisional::{ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs};
 
@@ -40,6 +46,18 @@ pub enum Method {
     UpdateSettings,
     ComputeInitialEcdsaDealings,
 
+    // Canister snapshots.
+    TakeCanisterSnapshot,
+    LoadCanisterSnapshot,
+    ListCanisterSnapshots,
+    DeleteCanisterSnapshot,
+
+    // Canister logs.
+    FetchCanisterLogs,
+
+    // Canister history.
+    CanisterInfo,
+
     // Bitcoin Interface.
     BitcoinGetBalance,
     BitcoinGetUtxos,
@@ -196,6 +214,7 @@ impl Payload<'_> for CanisterStatusResult {}
 ///     memory_size: nat;
 ///     cycles: nat;
 ///     idle_cycles_burned_per_day: nat;
+///     reserved_cycles: nat;
 /// })`
 #[derive(CandidType, Debug, Deserialize, Eq, PartialEq)]
 pub struct CanisterStatusResultV2 {
@@ -209,6 +228,7 @@ pub struct CanisterStatusResultV2 {
     balance: Vec<(Vec<u8>, candid::Nat)>,
     freezing_threshold: candid::Nat,
     idle_cycles_burned_per_day: candid::Nat,
+    reserved_cycles: candid::Nat,
 }
 
 impl CanisterStatusResultV2 {
@@ -224,6 +244,7 @@ impl CanisterStatusResultV2 {
         memory_allocation: Option<u64>,
         freezing_threshold: u64,
         idle_cycles_burned_per_day: u128,
+        reserved_cycles: u128,
     ) -> Self {
         Self {
             status,
@@ -243,6 +264,7 @@ impl CanisterStatusResultV2 {
             ),
             freezing_threshold: candid::Nat::from(freezing_threshold),
             idle_cycles_burned_per_day: candid::Nat::from(idle_cycles_burned_per_day),
+            reserved_cycles: candid::Nat::from(reserved_cycles),
         }
     }
 
@@ -277,6 +299,10 @@ impl CanisterStatusResultV2 {
     pub fn idle_cycles_burned_per_day(&self) -> u128 {
         self.idle_cycles_burned_per_day.0.to_u128().unwrap()
     }
+
+    pub fn reserved_cycles(&self) -> u128 {
+        self.reserved_cycles.0.to_u128().unwrap()
+    }
 }
 
 /// Indicates whether the canister is running, stopping, or stopped.
@@ -369,6 +395,42 @@ impl From<CanisterInstallMode> for String {
     }
 }
 
+impl From<CanisterInstallMode> for pb_canister_state_bits::CanisterInstallMode {
+    fn from(item: CanisterInstallMode) -> Self {
+        match item {
+            CanisterInstallMode::Install => pb_canister_state_bits::CanisterInstallMode::Install,
+            CanisterInstallMode::Reinstall => {
+                pb_canister_state_bits::CanisterInstallMode::Reinstall
+            }
+            CanisterInstallMode::Upgrade => pb_canister_state_bits::CanisterInstallMode::Upgrade,
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::CanisterInstallMode> for CanisterInstallMode {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_canister_state_bits::CanisterInstallMode) -> Result<Self, Self::Error> {
+        match item {
+            pb_canister_state_bits::CanisterInstallMode::Install => {
+                Ok(CanisterInstallMode::Install)
+            }
+            pb_canister_state_bits::CanisterInstallMode::Reinstall => {
+                Ok(CanisterInstallMode::Reinstall)
+            }
+            pb_canister_state_bits::CanisterInstallMode::Upgrade => {
+                Ok(CanisterInstallMode::Upgrade)
+            }
+            pb_canister_state_bits::CanisterInstallMode::Unspecified => {
+                Err(ProxyDecodeError::ValueOutOfRange {
+                    typ: "CanisterInstallMode",
+                    err: format!("Unable to convert {:?} to a CanisterInstallMode", item),
+                })
+            }
+        }
+    }
+}
+
 impl Payload<'_> for CanisterStatusResultV2 {}
 
 /// Struct used for encoding/decoding
@@ -498,6 +560,11 @@ impl Payload<'_> for UpdateSettingsArgs {}
 ///     controllers: opt vec principal;
 ///     compute_allocation: opt nat;
 ///     memory_allocation: opt nat;
+///     freezing_threshold: opt nat;
+///     log_visibility: opt log_visibility;
+///     wasm_memory_limit: opt nat;
+///     wasm_memory_threshold: opt nat;
+///     reserved_cycles_limit: opt nat;
 /// })`
 #[derive(Default, Clone, CandidType, Deserialize, Debug)]
 pub struct CanisterSettingsArgs {
@@ -506,6 +573,10 @@ pub struct CanisterSettingsArgs {
     pub compute_allocation: Option<candid::Nat>,
     pub memory_allocation: Option<candid::Nat>,
     pub freezing_threshold: Option<candid::Nat>,
+    pub log_visibility: Option<LogVisibility>,
+    pub wasm_memory_limit: Option<candid::Nat>,
+    pub wasm_memory_threshold: Option<candid::Nat>,
+    pub reserved_cycles_limit: Option<candid::Nat>,
 }
 
 impl Payload<'_> for CanisterSettingsArgs {}
@@ -524,6 +595,48 @@ impl CanisterSettingsArgs {
             compute_allocation: compute_allocation.map(candid::Nat::from),
             memory_allocation: memory_allocation.map(candid::Nat::from),
             freezing_threshold: freezing_threshold.map(candid::Nat::from),
+            log_visibility: None,
+            wasm_memory_limit: None,
+            wasm_memory_threshold: None,
+            reserved_cycles_limit: None,
+        }
+    }
+}
+
+/// Determines who can read the logs of a canister through
+/// `fetch_canister_logs`:
+/// `variant { controllers; public }`
+#[derive(Clone, Copy, CandidType, Deserialize, Debug, Eq, PartialEq, Serialize)]
+pub enum LogVisibility {
+    #[serde(rename = "controllers")]
+    Controllers,
+    #[serde(rename = "public")]
+    Public,
+}
+
+impl Default for LogVisibility {
+    fn default() -> Self {
+        LogVisibility::Controllers
+    }
+}
+
+impl From<LogVisibility> for pb_canister_state_bits::LogVisibility {
+    fn from(item: LogVisibility) -> Self {
+        match item {
+            LogVisibility::Controllers => pb_canister_state_bits::LogVisibility::Controllers,
+            LogVisibility::Public => pb_canister_state_bits::LogVisibility::Public,
+        }
+    }
+}
+
+impl From<pb_canister_state_bits::LogVisibility> for LogVisibility {
+    fn from(item: pb_canister_state_bits::LogVisibility) -> Self {
+        match item {
+            // Checkpoints written before log visibility was introduced don't
+            // specify it, so they fall back to the default.
+            pb_canister_state_bits::LogVisibility::Unspecified
+            | pb_canister_state_bits::LogVisibility::Controllers => LogVisibility::Controllers,
+            pb_canister_state_bits::LogVisibility::Public => LogVisibility::Public,
         }
     }
 }
@@ -588,6 +701,446 @@ impl SetControllerArgs {
 
 impl Payload<'_> for SetControllerArgs {}
 
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+///     replace_snapshot : opt blob;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct TakeCanisterSnapshotArgs {
+    canister_id: PrincipalId,
+    replace_snapshot: Option<Vec<u8>>,
+}
+
+impl TakeCanisterSnapshotArgs {
+    pub fn new(canister_id: CanisterId, replace_snapshot: Option<Vec<u8>>) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            replace_snapshot,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn replace_snapshot(&self) -> Option<&[u8]> {
+        self.replace_snapshot.as_deref()
+    }
+}
+
+impl Payload<'_> for TakeCanisterSnapshotArgs {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+///     snapshot_id : blob;
+/// })`
+///
+/// Used by both `load_canister_snapshot` and `delete_canister_snapshot`.
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct CanisterSnapshotArgs {
+    canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    snapshot_id: Vec<u8>,
+}
+
+impl CanisterSnapshotArgs {
+    pub fn new(canister_id: CanisterId, snapshot_id: Vec<u8>) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            snapshot_id,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn snapshot_id(&self) -> &[u8] {
+        &self.snapshot_id
+    }
+}
+
+impl Payload<'_> for CanisterSnapshotArgs {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     id : blob;
+///     taken_at_timestamp : nat64;
+///     total_size : nat64;
+/// })`
+///
+/// Returned by `take_canister_snapshot` and, as a vector, by
+/// `list_canister_snapshots`.
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct CanisterSnapshotResponse {
+    #[serde(with = "serde_bytes")]
+    pub id: Vec<u8>,
+    pub taken_at_timestamp: u64,
+    pub total_size: u64,
+}
+
+impl CanisterSnapshotResponse {
+    pub fn new(id: Vec<u8>, taken_at_timestamp: u64, total_size: NumBytes) -> Self {
+        Self {
+            id,
+            taken_at_timestamp,
+            total_size: total_size.get(),
+        }
+    }
+}
+
+impl Payload<'_> for CanisterSnapshotResponse {}
+
+/// Struct used for encoding/decoding the result of `list_canister_snapshots`:
+/// `(vec record { id : blob; taken_at_timestamp : nat64; total_size : nat64 })`.
+#[derive(Clone, CandidType, Deserialize, Debug, Default, Eq, PartialEq)]
+pub struct ListCanisterSnapshotsResponse(pub Vec<CanisterSnapshotResponse>);
+
+impl Payload<'_> for ListCanisterSnapshotsResponse {}
+
+/// A single record of a canister's log:
+/// `(record {
+///     idx : nat64;
+///     timestamp_nanos : nat64;
+///     content : blob;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct CanisterLogRecord {
+    pub idx: u64,
+    pub timestamp_nanos: u64,
+    #[serde(with = "serde_bytes")]
+    pub content: Vec<u8>,
+}
+
+impl Payload<'_> for CanisterLogRecord {}
+
+impl From<&CanisterLogRecord> for pb_canister_state_bits::CanisterLogRecord {
+    fn from(item: &CanisterLogRecord) -> Self {
+        Self {
+            idx: item.idx,
+            timestamp_nanos: item.timestamp_nanos,
+            content: item.content.clone(),
+        }
+    }
+}
+
+impl From<pb_canister_state_bits::CanisterLogRecord> for CanisterLogRecord {
+    fn from(item: pb_canister_state_bits::CanisterLogRecord) -> Self {
+        Self {
+            idx: item.idx,
+            timestamp_nanos: item.timestamp_nanos,
+            content: item.content,
+        }
+    }
+}
+
+/// Struct used for encoding/decoding the result of `fetch_canister_logs`:
+/// `(record {
+///     canister_log_records : vec canister_log_record;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, Default, Eq, PartialEq)]
+pub struct FetchCanisterLogsResponse {
+    pub canister_log_records: Vec<CanisterLogRecord>,
+}
+
+impl Payload<'_> for FetchCanisterLogsResponse {}
+
+/// `(record { user_id : principal })`
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq, Serialize)]
+pub struct CanisterChangeFromUserRecord {
+    pub user_id: PrincipalId,
+}
+
+/// `(record { canister_id : principal })`
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq, Serialize)]
+pub struct CanisterChangeFromCanisterRecord {
+    pub canister_id: PrincipalId,
+}
+
+/// The principal that made a change to a canister:
+/// `(variant {
+///     from_user : record { user_id : principal };
+///     from_canister : record { canister_id : principal };
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq, Serialize)]
+pub enum CanisterChangeOrigin {
+    #[serde(rename = "from_user")]
+    FromUser(CanisterChangeFromUserRecord),
+    #[serde(rename = "from_canister")]
+    FromCanister(CanisterChangeFromCanisterRecord),
+}
+
+impl CanisterChangeOrigin {
+    pub fn from_user(user_id: PrincipalId) -> Self {
+        Self::FromUser(CanisterChangeFromUserRecord { user_id })
+    }
+
+    pub fn from_canister(canister_id: PrincipalId) -> Self {
+        Self::FromCanister(CanisterChangeFromCanisterRecord { canister_id })
+    }
+
+    /// Returns the principal that made the change.
+    pub fn origin(&self) -> PrincipalId {
+        match self {
+            Self::FromUser(record) => record.user_id,
+            Self::FromCanister(record) => record.canister_id,
+        }
+    }
+}
+
+/// `(record { controllers : vec principal })`
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq, Serialize)]
+pub struct CanisterCreationRecord {
+    pub controllers: Vec<PrincipalId>,
+}
+
+/// `(record {
+///     mode : variant { install; reinstall; upgrade };
+///     module_hash : blob;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq, Serialize)]
+pub struct CanisterCodeDeploymentRecord {
+    pub mode: CanisterInstallMode,
+    #[serde(with = "serde_bytes")]
+    pub module_hash: Vec<u8>,
+}
+
+/// `(record { controllers : vec principal })`
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq, Serialize)]
+pub struct CanisterControllersChangeRecord {
+    pub controllers: Vec<PrincipalId>,
+}
+
+/// What changed about a canister:
+/// `(variant {
+///     creation : record { controllers : vec principal };
+///     code_uninstall;
+///     code_deployment : record {
+///         mode : variant { install; reinstall; upgrade };
+///         module_hash : blob;
+///     };
+///     controllers_change : record { controllers : vec principal };
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq, Serialize)]
+pub enum CanisterChangeDetails {
+    #[serde(rename = "creation")]
+    CanisterCreation(CanisterCreationRecord),
+    #[serde(rename = "code_uninstall")]
+    CanisterCodeUninstall,
+    #[serde(rename = "code_deployment")]
+    CanisterCodeDeployment(CanisterCodeDeploymentRecord),
+    #[serde(rename = "controllers_change")]
+    CanisterControllersChange(CanisterControllersChangeRecord),
+}
+
+impl CanisterChangeDetails {
+    pub fn canister_creation(controllers: Vec<PrincipalId>) -> Self {
+        Self::CanisterCreation(CanisterCreationRecord { controllers })
+    }
+
+    pub fn code_uninstall() -> Self {
+        Self::CanisterCodeUninstall
+    }
+
+    pub fn code_deployment(mode: CanisterInstallMode, module_hash: [u8; 32]) -> Self {
+        Self::CanisterCodeDeployment(CanisterCodeDeploymentRecord {
+            mode,
+            module_hash: module_hash.to_vec(),
+        })
+    }
+
+    pub fn controllers_change(controllers: Vec<PrincipalId>) -> Self {
+        Self::CanisterControllersChange(CanisterControllersChangeRecord { controllers })
+    }
+}
+
+/// A single entry of a canister's history:
+/// `(record {
+///     timestamp_nanos : nat64;
+///     origin : change_origin;
+///     details : change_details;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq, Serialize)]
+pub struct CanisterChange {
+    pub timestamp_nanos: u64,
+    pub origin: CanisterChangeOrigin,
+    pub details: CanisterChangeDetails,
+}
+
+impl CanisterChange {
+    pub fn new(
+        timestamp_nanos: u64,
+        origin: CanisterChangeOrigin,
+        details: CanisterChangeDetails,
+    ) -> Self {
+        Self {
+            timestamp_nanos,
+            origin,
+            details,
+        }
+    }
+}
+
+impl From<&CanisterChange> for pb_canister_state_bits::CanisterChange {
+    fn from(item: &CanisterChange) -> Self {
+        use pb_canister_state_bits::canister_change::{ChangeDetails, ChangeOrigin};
+
+        let change_origin = match &item.origin {
+            CanisterChangeOrigin::FromUser(record) => ChangeOrigin::CanisterChangeFromUser(
+                pb_canister_state_bits::CanisterChangeFromUser {
+                    user_id: Some(record.user_id.into()),
+                },
+            ),
+            CanisterChangeOrigin::FromCanister(record) => ChangeOrigin::CanisterChangeFromCanister(
+                pb_canister_state_bits::CanisterChangeFromCanister {
+                    canister_id: Some(record.canister_id.into()),
+                },
+            ),
+        };
+        let change_details = match &item.details {
+            CanisterChangeDetails::CanisterCreation(record) => {
+                ChangeDetails::CanisterCreation(pb_canister_state_bits::CanisterCreation {
+                    controllers: record.controllers.iter().map(|c| (*c).into()).collect(),
+                })
+            }
+            CanisterChangeDetails::CanisterCodeUninstall => ChangeDetails::CanisterCodeUninstall(
+                pb_canister_state_bits::CanisterCodeUninstall {},
+            ),
+            CanisterChangeDetails::CanisterCodeDeployment(record) => {
+                ChangeDetails::CanisterCodeDeployment(
+                    pb_canister_state_bits::CanisterCodeDeployment {
+                        mode: pb_canister_state_bits::CanisterInstallMode::from(record.mode).into(),
+                        module_hash: record.module_hash.clone(),
+                    },
+                )
+            }
+            CanisterChangeDetails::CanisterControllersChange(record) => {
+                ChangeDetails::CanisterControllersChange(
+                    pb_canister_state_bits::CanisterControllersChange {
+                        controllers: record.controllers.iter().map(|c| (*c).into()).collect(),
+                    },
+                )
+            }
+        };
+        Self {
+            timestamp_nanos: item.timestamp_nanos,
+            change_origin: Some(change_origin),
+            change_details: Some(change_details),
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::CanisterChange> for CanisterChange {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_canister_state_bits::CanisterChange) -> Result<Self, Self::Error> {
+        use pb_canister_state_bits::canister_change::{ChangeDetails, ChangeOrigin};
+
+        let origin = match item.change_origin.ok_or(ProxyDecodeError::MissingField(
+            "CanisterChange::change_origin",
+        ))? {
+            ChangeOrigin::CanisterChangeFromUser(record) => CanisterChangeOrigin::from_user(
+                try_from_option_field(record.user_id, "CanisterChangeFromUser::user_id")?,
+            ),
+            ChangeOrigin::CanisterChangeFromCanister(record) => {
+                CanisterChangeOrigin::from_canister(try_from_option_field(
+                    record.canister_id,
+                    "CanisterChangeFromCanister::canister_id",
+                )?)
+            }
+        };
+        let details = match item.change_details.ok_or(ProxyDecodeError::MissingField(
+            "CanisterChange::change_details",
+        ))? {
+            ChangeDetails::CanisterCreation(record) => CanisterChangeDetails::canister_creation(
+                record
+                    .controllers
+                    .into_iter()
+                    .map(PrincipalId::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            ChangeDetails::CanisterCodeUninstall(_) => CanisterChangeDetails::code_uninstall(),
+            ChangeDetails::CanisterCodeDeployment(record) => {
+                let mode = pb_canister_state_bits::CanisterInstallMode::from_i32(record.mode)
+                    .ok_or(ProxyDecodeError::ValueOutOfRange {
+                        typ: "CanisterInstallMode",
+                        err: format!("Unable to convert {} to a CanisterInstallMode", record.mode),
+                    })?;
+                CanisterChangeDetails::CanisterCodeDeployment(CanisterCodeDeploymentRecord {
+                    mode: CanisterInstallMode::try_from(mode)?,
+                    module_hash: record.module_hash,
+                })
+            }
+            ChangeDetails::CanisterControllersChange(record) => {
+                CanisterChangeDetails::controllers_change(
+                    record
+                        .controllers
+                        .into_iter()
+                        .map(PrincipalId::try_from)
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+        };
+        Ok(Self {
+            timestamp_nanos: item.timestamp_nanos,
+            origin,
+            details,
+        })
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+///     num_requested_changes : opt nat64;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug)]
+pub struct CanisterInfoRequest {
+    canister_id: PrincipalId,
+    num_requested_changes: Option<u64>,
+}
+
+impl CanisterInfoRequest {
+    pub fn new(canister_id: CanisterId, num_requested_changes: Option<u64>) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            num_requested_changes,
+        }
+    }
+
+    pub fn canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn num_requested_changes(&self) -> Option<u64> {
+        self.num_requested_changes
+    }
+}
+
+impl Payload<'_> for CanisterInfoRequest {}
+
+/// Struct used for encoding/decoding the result of `canister_info`:
+/// `(record {
+///     total_num_changes : nat64;
+///     recent_changes : vec change;
+///     module_hash : opt blob;
+///     controllers : vec principal;
+/// })`
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct CanisterInfoResponse {
+    pub total_num_changes: u64,
+    pub recent_changes: Vec<CanisterChange>,
+    pub module_hash: Option<Vec<u8>>,
+    pub controllers: Vec<PrincipalId>,
+}
+
+impl Payload<'_> for CanisterInfoResponse {}
+
 /// Struct used for encoding/decoding
 /// `(record {
 ///     node_ids : vec principal;
@@ -691,7 +1244,7 @@ impl SetupInitialDKGResponse {
 
 /// Types of curves that can be used for ECDSA signing.
 /// ```text
-/// (variant { secp256k1; })
+/// (variant { secp256k1; secp256r1; })
 /// ```
 #[derive(
     CandidType, Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize, Hash,
@@ -699,6 +1252,8 @@ impl SetupInitialDKGResponse {
 pub enum EcdsaCurve {
     #[serde(rename = "secp256k1")]
     Secp256k1,
+    #[serde(rename = "secp256r1")]
+    Secp256r1,
 }
 
 impl TryFrom<pb_registry_crypto::EcdsaCurve> for EcdsaCurve {
@@ -707,6 +1262,7 @@ impl TryFrom<pb_registry_crypto::EcdsaCurve> for EcdsaCurve {
     fn try_from(item: pb_registry_crypto::EcdsaCurve) -> Result<Self, Self::Error> {
         match item {
             pb_registry_crypto::EcdsaCurve::Secp256k1 => Ok(EcdsaCurve::Secp256k1),
+            pb_registry_crypto::EcdsaCurve::Secp256r1 => Ok(EcdsaCurve::Secp256r1),
             pb_registry_crypto::EcdsaCurve::Unspecified => Err(ProxyDecodeError::ValueOutOfRange {
                 typ: "EcdsaCurve",
                 err: format!("Unable to convert {:?} to an EcdsaCurve", item),
@@ -719,6 +1275,7 @@ impl From<EcdsaCurve> for pb_registry_crypto::EcdsaCurve {
     fn from(item: EcdsaCurve) -> Self {
         match item {
             EcdsaCurve::Secp256k1 => pb_registry_crypto::EcdsaCurve::Secp256k1,
+            EcdsaCurve::Secp256r1 => pb_registry_crypto::EcdsaCurve::Secp256r1,
         }
     }
 }
@@ -735,6 +1292,7 @@ impl FromStr for EcdsaCurve {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "Secp256k1" => Ok(Self::Secp256k1),
+            "Secp256r1" => Ok(Self::Secp256r1),
             _ => Err(format!("{} is not a recognized ECDSA curve", s)),
         }
     }
@@ -742,12 +1300,9 @@ impl FromStr for EcdsaCurve {
 
 #[test]
 fn ecdsa_curve_round_trip() {
-    assert_eq!(
-        format!("{}", EcdsaCurve::Secp256k1)
-            .parse::<EcdsaCurve>()
-            .unwrap(),
-        EcdsaCurve::Secp256k1
-    );
+    for curve in [EcdsaCurve::Secp256k1, EcdsaCurve::Secp256r1] {
+        assert_eq!(format!("{}", curve).parse::<EcdsaCurve>().unwrap(), curve);
+    }
 }
 
 /// Unique identifier for a key that can be used for ECDSA signatures. The name
@@ -877,6 +1432,152 @@ pub struct ECDSAPublicKeyResponse {
 
 impl Payload<'_> for ECDSAPublicKeyResponse {}
 
+/// Types of algorithms that can be used for Schnorr signing.
+/// ```text
+/// (variant { bip340secp256k1; ed25519; })
+/// ```
+#[derive(
+    CandidType, Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize, Hash,
+)]
+pub enum SchnorrAlgorithm {
+    #[serde(rename = "bip340secp256k1")]
+    Bip340Secp256k1,
+    #[serde(rename = "ed25519")]
+    Ed25519,
+}
+
+impl TryFrom<pb_registry_crypto::SchnorrAlgorithm> for SchnorrAlgorithm {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_registry_crypto::SchnorrAlgorithm) -> Result<Self, Self::Error> {
+        match item {
+            pb_registry_crypto::SchnorrAlgorithm::Bip340secp256k1 => {
+                Ok(SchnorrAlgorithm::Bip340Secp256k1)
+            }
+            pb_registry_crypto::SchnorrAlgorithm::Ed25519 => Ok(SchnorrAlgorithm::Ed25519),
+            pb_registry_crypto::SchnorrAlgorithm::Unspecified => {
+                Err(ProxyDecodeError::ValueOutOfRange {
+                    typ: "SchnorrAlgorithm",
+                    err: format!("Unable to convert {:?} to a SchnorrAlgorithm", item),
+                })
+            }
+        }
+    }
+}
+
+impl From<SchnorrAlgorithm> for pb_registry_crypto::SchnorrAlgorithm {
+    fn from(item: SchnorrAlgorithm) -> Self {
+        match item {
+            SchnorrAlgorithm::Bip340Secp256k1 => {
+                pb_registry_crypto::SchnorrAlgorithm::Bip340secp256k1
+            }
+            SchnorrAlgorithm::Ed25519 => pb_registry_crypto::SchnorrAlgorithm::Ed25519,
+        }
+    }
+}
+
+impl std::fmt::Display for SchnorrAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for SchnorrAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Bip340Secp256k1" => Ok(Self::Bip340Secp256k1),
+            "Ed25519" => Ok(Self::Ed25519),
+            _ => Err(format!("{} is not a recognized Schnorr algorithm", s)),
+        }
+    }
+}
+
+#[test]
+fn schnorr_algorithm_round_trip() {
+    for algorithm in [SchnorrAlgorithm::Bip340Secp256k1, SchnorrAlgorithm::Ed25519] {
+        assert_eq!(
+            format!("{}", algorithm)
+                .parse::<SchnorrAlgorithm>()
+                .unwrap(),
+            algorithm
+        );
+    }
+}
+
+/// Unique identifier for a key that can be used for Schnorr signatures. The
+/// name is just a identifier, but it may be used to convey some information
+/// about the key (e.g. that the key is meant to be used for testing purposes).
+/// ```text
+/// (record { algorithm: schnorr_algorithm; name: text})
+/// ```
+#[derive(
+    CandidType, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize, Hash,
+)]
+pub struct SchnorrKeyId {
+    pub algorithm: SchnorrAlgorithm,
+    pub name: String,
+}
+
+impl TryFrom<pb_registry_crypto::SchnorrKeyId> for SchnorrKeyId {
+    type Error = ProxyDecodeError;
+    fn try_from(item: pb_registry_crypto::SchnorrKeyId) -> Result<Self, Self::Error> {
+        Ok(Self {
+            algorithm: SchnorrAlgorithm::try_from(
+                pb_registry_crypto::SchnorrAlgorithm::from_i32(item.algorithm).ok_or(
+                    ProxyDecodeError::ValueOutOfRange {
+                        typ: "SchnorrKeyId",
+                        err: format!("Unable to convert {} to a SchnorrAlgorithm", item.algorithm),
+                    },
+                )?,
+            )?,
+            name: item.name,
+        })
+    }
+}
+
+impl From<&SchnorrKeyId> for pb_registry_crypto::SchnorrKeyId {
+    fn from(item: &SchnorrKeyId) -> Self {
+        Self {
+            algorithm: pb_registry_crypto::SchnorrAlgorithm::from(item.algorithm) as i32,
+            name: item.name.clone(),
+        }
+    }
+}
+
+impl std::fmt::Display for SchnorrKeyId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.name)
+    }
+}
+
+impl FromStr for SchnorrKeyId {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (algorithm, name) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Schnorr key id {} does not contain a ':'", s))?;
+        Ok(SchnorrKeyId {
+            algorithm: algorithm.parse::<SchnorrAlgorithm>()?,
+            name: name.to_string(),
+        })
+    }
+}
+
+#[test]
+fn schnorr_key_id_round_trip() {
+    for algorithm in [SchnorrAlgorithm::Bip340Secp256k1, SchnorrAlgorithm::Ed25519] {
+        for name in ["key_1", "", "other_key", "other key", "other:key"] {
+            let key = SchnorrKeyId {
+                algorithm,
+                name: name.to_string(),
+            };
+            assert_eq!(format!("{}", key).parse::<SchnorrKeyId>().unwrap(), key);
+        }
+    }
+}
+
 /// Argument of the compute_initial_ecdsa_dealings API.
 /// `(record {
 ///     key_id: ecdsa_key_id;
@@ -156,6 +156,9 @@ pub enum AlgorithmId {
     RsaSha256 = 14,
     ThresholdEcdsaSecp256k1 = 15,
     MegaSecp256k1 = 16,
+    ThresholdEcdsaSecp256r1 = 17,
+    ThresholdSchnorrBip340 = 18,
+    ThresholdEd25519 = 19,
 }
 
 impl From<CspThresholdSigPublicKey> for AlgorithmId {
@@ -206,6 +209,9 @@ impl From<i32> for AlgorithmId {
             14 => AlgorithmId::RsaSha256,
             15 => AlgorithmId::ThresholdEcdsaSecp256k1,
             16 => AlgorithmId::MegaSecp256k1,
+            17 => AlgorithmId::ThresholdEcdsaSecp256r1,
+            18 => AlgorithmId::ThresholdSchnorrBip340,
+            19 => AlgorithmId::ThresholdEd25519,
             _ => AlgorithmId::Placeholder,
         }
     }
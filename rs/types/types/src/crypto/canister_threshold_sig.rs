@@ -244,6 +244,9 @@ pub struct ThresholdEcdsaSigInputs {
 // The byte length of an hashed message for ECDSA signatures over the curve secp256k1.
 pub const ECDSA_SECP256K1_HASH_BYTE_LENGTH: usize = 32;
 
+// The byte length of an hashed message for ECDSA signatures over the curve secp256r1.
+pub const ECDSA_SECP256R1_HASH_BYTE_LENGTH: usize = 32;
+
 impl ThresholdEcdsaSigInputs {
     /// Creates the inputs to the threshold ECDSA signing protocol.
     ///
@@ -337,6 +340,12 @@ impl ThresholdEcdsaSigInputs {
                 }
                 Ok(())
             }
+            AlgorithmId::ThresholdEcdsaSecp256r1 => {
+                if hashed_message.len() != ECDSA_SECP256R1_HASH_BYTE_LENGTH {
+                    return Err(error::ThresholdEcdsaSigInputsCreationError::InvalidHashLength);
+                }
+                Ok(())
+            }
             _ => Err(error::ThresholdEcdsaSigInputsCreationError::UnsupportedAlgorithm),
         }
     }
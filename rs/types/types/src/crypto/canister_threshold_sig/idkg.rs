@@ -297,8 +297,8 @@ impl IDkgTranscriptParams {
     ///   and `ReceiversEmpty`)
     /// * |dealers| >= self.collection_threshold + faults_tolerated(|dealers|)
     ///   (error: `UnsatisfiedCollectionThreshold`)
-    /// * algorithm_id is of type `ThresholdEcdsaSecp256k1` (error:
-    ///   `UnsupportedAlgorithmId`)
+    /// * algorithm_id is `ThresholdEcdsaSecp256k1`, `ThresholdEcdsaSecp256r1`,
+    ///   `ThresholdSchnorrBip340` or `ThresholdEd25519` (error: `UnsupportedAlgorithmId`)
     /// * If `operation_type` is:
     ///   - ReshareOfMasked(t):
     ///     - t is of type Masked(_)
@@ -467,7 +467,10 @@ impl IDkgTranscriptParams {
 
     fn ensure_algorithm_id_supported(&self) -> Result<(), IDkgParamsValidationError> {
         match self.algorithm_id {
-            AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(()),
+            AlgorithmId::ThresholdEcdsaSecp256k1
+            | AlgorithmId::ThresholdEcdsaSecp256r1
+            | AlgorithmId::ThresholdSchnorrBip340
+            | AlgorithmId::ThresholdEd25519 => Ok(()),
             _ => Err(IDkgParamsValidationError::UnsupportedAlgorithmId {
                 algorithm_id: self.algorithm_id,
             }),
@@ -78,7 +78,7 @@ pub mod time;
 pub mod xnet;
 
 pub use crate::replica_version::ReplicaVersion;
-pub use crate::time::Time;
+pub use crate::time::{CoarseTime, Time};
 pub use funds::*;
 pub use ic_base_types::{
     subnet_id_into_protobuf, subnet_id_try_from_protobuf, CanisterId, CanisterIdBlobParseError,
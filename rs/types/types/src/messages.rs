@@ -65,6 +65,10 @@ pub const MAX_XNET_PAYLOAD_IN_BYTES: NumBytes =
 pub const MAX_RESPONSE_COUNT_BYTES: usize =
     size_of::<RequestOrResponse>() + MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64 as usize;
 
+/// The maximum timeout of a best-effort call, in seconds. Larger timeouts
+/// requested via `ic0.call_with_best_effort_response` are silently capped.
+pub const MAX_CALL_TIMEOUT_SECONDS: u32 = 300;
+
 /// An end user's signature.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct UserSignature {
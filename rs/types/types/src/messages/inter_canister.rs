@@ -1,4 +1,7 @@
-use crate::{ingress::WasmResult, CanisterId, CountBytes, Cycles, Funds, NumBytes};
+use crate::{
+    ingress::WasmResult, time::NO_DEADLINE, CanisterId, CoarseTime, CountBytes, Cycles, Funds,
+    NumBytes,
+};
 use ic_error_types::{RejectCode, TryFromError, UserError};
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
@@ -33,6 +36,11 @@ pub struct Request {
     pub method_name: String,
     #[serde(with = "serde_bytes")]
     pub method_payload: Vec<u8>,
+    /// If non-zero, this is a best-effort call: the sender's callback times
+    /// out with a `SysUnknown` reject once `deadline` has passed and the
+    /// request or its response may be dropped under load.
+    #[serde(default)]
+    pub deadline: CoarseTime,
 }
 
 impl Request {
@@ -57,6 +65,12 @@ impl Request {
         let bytes = self.method_name.len() + self.method_payload.len();
         NumBytes::from(bytes as u64)
     }
+
+    /// Returns `true` if this is the request of a best-effort call (i.e. it
+    /// has a deadline).
+    pub fn is_best_effort(&self) -> bool {
+        self.deadline != NO_DEADLINE
+    }
 }
 
 impl std::fmt::Debug for Request {
@@ -80,9 +94,10 @@ impl std::fmt::Debug for Request {
         }
         write!(
             f,
-            "method_payload: [{}] }}",
+            "method_payload: [{}], ",
             truncate_and_format(&self.method_payload, 1024)
         )?;
+        write!(f, "deadline: {:?} }}", self.deadline)?;
         Ok(())
     }
 }
@@ -200,6 +215,9 @@ pub struct Response {
     pub originator_reply_callback: CallbackId,
     pub refund: Cycles,
     pub response_payload: Payload,
+    /// The deadline of the `Request` this is a response to.
+    #[serde(default)]
+    pub deadline: CoarseTime,
 }
 
 impl Response {
@@ -311,6 +329,7 @@ impl From<&Request> for pb_queues::Request {
             method_name: req.method_name.clone(),
             method_payload: req.method_payload.clone(),
             cycles_payment: Some((req.payment).into()),
+            deadline_seconds: req.deadline.as_secs_since_unix_epoch(),
         }
     }
 }
@@ -334,6 +353,7 @@ impl TryFrom<pb_queues::Request> for Request {
             payment,
             method_name: req.method_name,
             method_payload: req.method_payload,
+            deadline: CoarseTime::from_secs_since_unix_epoch(req.deadline_seconds),
         })
     }
 }
@@ -376,6 +396,7 @@ impl From<&Response> for pb_queues::Response {
             refund: Some((&Funds::new(rep.refund)).into()),
             response_payload: Some(p),
             cycles_refund: Some((rep.refund).into()),
+            deadline_seconds: rep.deadline.as_secs_since_unix_epoch(),
         }
     }
 }
@@ -406,6 +427,7 @@ impl TryFrom<pb_queues::Response> for Response {
             originator_reply_callback: rep.originator_reply_callback.into(),
             refund,
             response_payload,
+            deadline: CoarseTime::from_secs_since_unix_epoch(rep.deadline_seconds),
         })
     }
 }
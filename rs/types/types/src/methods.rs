@@ -1,7 +1,7 @@
 //! This module contains a collection of types and structs that define the
 //! various types of methods in the IC.
 
-use crate::{messages::CallContextId, Cycles};
+use crate::{messages::CallContextId, time::NO_DEADLINE, CoarseTime, Cycles};
 use ic_base_types::CanisterId;
 use ic_protobuf::proxy::{try_from_option_field, ProxyDecodeError};
 use ic_protobuf::state::{canister_state_bits::v1 as pb, queues::v1::Cycles as PbCycles};
@@ -26,6 +26,13 @@ pub enum WasmMethod {
     /// execution.
     Query(String),
 
+    /// An exported composite query method along with its name.
+    ///
+    /// Like queries, composite queries do not persist their modifications.
+    /// Unlike queries, they can call other query and composite query methods,
+    /// but only in non-replicated mode.
+    CompositeQuery(String),
+
     /// An exported system method. Unlike query or update method, there
     /// are a few fixed system methods as defined in `SystemMethod`.
     System(SystemMethod),
@@ -36,6 +43,7 @@ impl WasmMethod {
         match self {
             Self::Update(name) => name.to_string(),
             Self::Query(name) => name.to_string(),
+            Self::CompositeQuery(name) => name.to_string(),
             Self::System(system_method) => system_method.to_string(),
         }
     }
@@ -46,6 +54,7 @@ impl fmt::Display for WasmMethod {
         match self {
             Self::Update(name) => write!(f, "canister_update {}", name),
             Self::Query(name) => write!(f, "canister_query {}", name),
+            Self::CompositeQuery(name) => write!(f, "canister_composite_query {}", name),
             Self::System(system_method) => system_method.fmt(f),
         }
     }
@@ -63,6 +72,10 @@ impl TryFrom<String> for WasmMethod {
             // Take the part after the first space
             let parts: Vec<&str> = name.splitn(2, ' ').collect();
             Ok(WasmMethod::Query(parts[1].to_string()))
+        } else if name.starts_with("canister_composite_query ") {
+            // Take the part after the first space
+            let parts: Vec<&str> = name.splitn(2, ' ').collect();
+            Ok(WasmMethod::CompositeQuery(parts[1].to_string()))
         } else {
             match SystemMethod::try_from(name.as_ref()) {
                 Ok(system_method) => Ok(WasmMethod::System(system_method)),
@@ -83,6 +96,9 @@ impl From<&WasmMethod> for pb::WasmMethod {
             WasmMethod::Query(value) => Self {
                 wasm_method: Some(PbWasmMethod::Query(value.clone())),
             },
+            WasmMethod::CompositeQuery(value) => Self {
+                wasm_method: Some(PbWasmMethod::CompositeQuery(value.clone())),
+            },
             WasmMethod::System(value) => Self {
                 wasm_method: Some(PbWasmMethod::System(match value {
                     SystemMethod::CanisterStart => PbSystemMethod::CanisterStart,
@@ -91,6 +107,10 @@ impl From<&WasmMethod> for pb::WasmMethod {
                     SystemMethod::CanisterPostUpgrade => PbSystemMethod::CanisterPostUpgrade,
                     SystemMethod::CanisterInspectMessage => PbSystemMethod::CanisterInspectMessage,
                     SystemMethod::CanisterHeartbeat => PbSystemMethod::CanisterHeartbeat,
+                    SystemMethod::CanisterGlobalTimer => PbSystemMethod::CanisterGlobalTimer,
+                    SystemMethod::CanisterOnLowWasmMemory => {
+                        PbSystemMethod::CanisterOnLowWasmMemory
+                    }
                     SystemMethod::Empty => PbSystemMethod::Empty,
                 } as i32)),
             },
@@ -107,6 +127,7 @@ impl TryFrom<pb::WasmMethod> for WasmMethod {
         match try_from_option_field(method.wasm_method, "WasmMethod::wasm_method")? {
             PbWasmMethod::Update(update) => Ok(Self::Update(update)),
             PbWasmMethod::Query(query) => Ok(Self::Query(query)),
+            PbWasmMethod::CompositeQuery(query) => Ok(Self::CompositeQuery(query)),
             PbWasmMethod::System(system) => {
                 let method =
                     PbSystemMethod::from_i32(system).unwrap_or(PbSystemMethod::Unspecified);
@@ -124,6 +145,10 @@ impl TryFrom<pb::WasmMethod> for WasmMethod {
                     PbSystemMethod::CanisterPostUpgrade => SystemMethod::CanisterPostUpgrade,
                     PbSystemMethod::CanisterInspectMessage => SystemMethod::CanisterInspectMessage,
                     PbSystemMethod::CanisterHeartbeat => SystemMethod::CanisterHeartbeat,
+                    PbSystemMethod::CanisterGlobalTimer => SystemMethod::CanisterGlobalTimer,
+                    PbSystemMethod::CanisterOnLowWasmMemory => {
+                        SystemMethod::CanisterOnLowWasmMemory
+                    }
                     PbSystemMethod::Empty => SystemMethod::Empty,
                 }))
             }
@@ -147,6 +172,11 @@ pub enum SystemMethod {
     CanisterInspectMessage,
     /// A system method that is run at regular intervals for cron support.
     CanisterHeartbeat,
+    /// A system method that is run once the canister's global timer expires.
+    CanisterGlobalTimer,
+    /// A system method that is run once the canister's free Wasm memory drops
+    /// below its `wasm_memory_threshold`.
+    CanisterOnLowWasmMemory,
     /// This is introduced as temporary scaffolding to aid in construction of
     /// the initial ExecutionState. This isn't used to execute any actual wasm
     /// but as a way to get to the wasm embedder from execution. Eventually, we
@@ -166,6 +196,8 @@ impl TryFrom<&str> for SystemMethod {
             "canister_start" => Ok(SystemMethod::CanisterStart),
             "canister_inspect_message" => Ok(SystemMethod::CanisterInspectMessage),
             "canister_heartbeat" => Ok(SystemMethod::CanisterHeartbeat),
+            "canister_global_timer" => Ok(SystemMethod::CanisterGlobalTimer),
+            "canister_on_low_wasm_memory" => Ok(SystemMethod::CanisterOnLowWasmMemory),
             "empty" => Ok(SystemMethod::Empty),
             _ => Err(format!("Cannot convert {} to SystemMethod.", value)),
         }
@@ -181,6 +213,8 @@ impl fmt::Display for SystemMethod {
             Self::CanisterStart => write!(f, "canister_start"),
             Self::CanisterInspectMessage => write!(f, "canister_inspect_message"),
             Self::CanisterHeartbeat => write!(f, "canister_heartbeat"),
+            Self::CanisterGlobalTimer => write!(f, "canister_global_timer"),
+            Self::CanisterOnLowWasmMemory => write!(f, "canister_on_low_wasm_memory"),
             Self::Empty => write!(f, "empty"),
         }
     }
@@ -210,7 +244,8 @@ pub struct Callback {
     // Currently optional to ensure backwards compatibility.
     /// The request's sender id.
     pub originator: Option<CanisterId>,
-    /// The id of the principal that the request was addressed to.
+    /// The id of the principal that the request was addressed to. Always
+    /// set for best-effort calls, since timing them out requires it.
     pub respondent: Option<CanisterId>,
     /// The number of cycles that were sent in the original request.
     pub cycles_sent: Cycles,
@@ -221,6 +256,11 @@ pub struct Callback {
     /// An optional closure to be executed if the execution of `on_reply` or
     /// `on_reject` traps.
     pub on_cleanup: Option<WasmClosure>,
+    /// The deadline of a best-effort call, `NO_DEADLINE` otherwise. Once it
+    /// has passed, the callback is executed with a `SysUnknown` reject unless
+    /// a response was already enqueued.
+    #[serde(default)]
+    pub deadline: CoarseTime,
 }
 
 impl Callback {
@@ -232,7 +272,12 @@ impl Callback {
         on_reply: WasmClosure,
         on_reject: WasmClosure,
         on_cleanup: Option<WasmClosure>,
+        deadline: CoarseTime,
     ) -> Self {
+        assert!(
+            deadline == NO_DEADLINE || respondent.is_some(),
+            "Best-effort callback without respondent"
+        );
         Self {
             call_context_id,
             originator,
@@ -241,6 +286,7 @@ impl Callback {
             on_reply,
             on_reject,
             on_cleanup,
+            deadline,
         }
     }
 }
@@ -270,6 +316,7 @@ impl From<&Callback> for pb::Callback {
                 func_idx: on_cleanup.func_idx,
                 env: on_cleanup.env,
             }),
+            deadline_seconds: item.deadline.as_secs_since_unix_epoch(),
         }
     }
 }
@@ -284,11 +331,16 @@ impl TryFrom<pb::Callback> for Callback {
             try_from_option_field(value.on_reject, "Callback::on_reject")?;
         let cycles_sent: PbCycles =
             try_from_option_field(value.cycles_sent, "Callback::cycles_sent")?;
+        let respondent = try_from_option_field(value.respondent, "Callback::respondent").ok();
+        let deadline = CoarseTime::from_secs_since_unix_epoch(value.deadline_seconds);
+        if deadline != NO_DEADLINE && respondent.is_none() {
+            return Err(ProxyDecodeError::MissingField("Callback::respondent"));
+        }
 
         Ok(Self {
             call_context_id: CallContextId::from(value.call_context_id),
             originator: try_from_option_field(value.originator, "Callback::originator").ok(),
-            respondent: try_from_option_field(value.respondent, "Callback::respondent").ok(),
+            respondent,
             cycles_sent: Cycles::from(cycles_sent),
             on_reply: WasmClosure {
                 func_idx: on_reply.func_idx,
@@ -302,6 +354,7 @@ impl TryFrom<pb::Callback> for Callback {
                 func_idx: on_cleanup.func_idx,
                 env: on_cleanup.env,
             }),
+            deadline,
         })
     }
 }
@@ -332,9 +385,12 @@ impl FuncRef {
             | Self::Method(WasmMethod::System(SystemMethod::CanisterPreUpgrade))
             | Self::Method(WasmMethod::System(SystemMethod::CanisterPostUpgrade))
             | Self::Method(WasmMethod::System(SystemMethod::CanisterHeartbeat))
+            | Self::Method(WasmMethod::System(SystemMethod::CanisterGlobalTimer))
+            | Self::Method(WasmMethod::System(SystemMethod::CanisterOnLowWasmMemory))
             | Self::UpdateClosure(_) => true,
             Self::QueryClosure(_)
             | Self::Method(WasmMethod::Query(_))
+            | Self::Method(WasmMethod::CompositeQuery(_))
             | Self::Method(WasmMethod::System(SystemMethod::Empty))
             | Self::Method(WasmMethod::System(SystemMethod::CanisterInspectMessage)) => false,
         }
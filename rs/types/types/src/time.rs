@@ -95,6 +95,55 @@ impl fmt::Display for Time {
     }
 }
 
+/// Time since UNIX_EPOCH, in whole seconds.
+///
+/// Used where a coarse granularity suffices and a compact encoding matters,
+/// e.g. for the deadlines of best-effort inter-canister messages.
+#[derive(
+    Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Serialize, Deserialize,
+)]
+#[cfg_attr(test, derive(Arbitrary))]
+pub struct CoarseTime(u32);
+
+/// The deadline of messages and callbacks that never time out.
+pub const NO_DEADLINE: CoarseTime = CoarseTime(0);
+
+impl CoarseTime {
+    pub const fn from_secs_since_unix_epoch(secs: u32) -> Self {
+        CoarseTime(secs)
+    }
+
+    /// Number of whole seconds since UNIX EPOCH.
+    pub fn as_secs_since_unix_epoch(self) -> u32 {
+        self.0
+    }
+
+    /// Returns the latest `CoarseTime` that is not after `time`, saturating at
+    /// `u32::MAX` seconds.
+    pub fn floor(time: Time) -> Self {
+        let secs = Duration::from_nanos(time.as_nanos_since_unix_epoch()).as_secs();
+        CoarseTime(secs.min(u32::MAX as u64) as u32)
+    }
+
+    /// Returns this `CoarseTime` plus `secs` seconds, saturating at `u32::MAX`
+    /// seconds.
+    pub fn saturating_add_secs(self, secs: u32) -> Self {
+        CoarseTime(self.0.saturating_add(secs))
+    }
+}
+
+impl From<CoarseTime> for Time {
+    fn from(val: CoarseTime) -> Self {
+        UNIX_EPOCH + Duration::from_secs(val.0 as u64)
+    }
+}
+
+impl fmt::Display for CoarseTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Time::from(*self))
+    }
+}
+
 /// Returns the current time.
 ///
 /// WARNING: this function should not be used in any deterministic part of the
@@ -2,6 +2,7 @@ use crate::ids::{canister_test_id, node_test_id, subnet_test_id, user_test_id};
 use ic_types::{
     crypto::{AlgorithmId, KeyId, KeyPurpose, UserPublicKey},
     messages::{CallbackId, Payload, RejectContext, Request, RequestOrResponse, Response},
+    time::NO_DEADLINE,
     time::UNIX_EPOCH,
     xnet::StreamIndex,
     CanisterId, Cycles, Height, IDkgId, NodeId, RegistryVersion, SubnetId, Time, UserId,
@@ -129,6 +130,7 @@ prop_compose! {
             payment: Cycles::from(cycles_payment),
             method_name,
             method_payload,
+            deadline: NO_DEADLINE,
         }
     }
 }
@@ -162,7 +164,8 @@ prop_compose! {
             respondent,
             originator_reply_callback: CallbackId::from(callback),
             refund: Cycles::from(cycles_refund),
-            response_payload
+            response_payload,
+            deadline: NO_DEADLINE,
         }
     }
 }
@@ -12,6 +12,9 @@ mod ic0 {
         pub fn canister_self_copy(dst: u32, offset: u32, size: u32) -> ();
         pub fn canister_self_size() -> u32;
         pub fn canister_status() -> u32;
+        pub fn canister_version() -> u64;
+        pub fn is_controller(src: u32, size: u32) -> u32;
+        pub fn in_replicated_execution() -> u32;
         pub fn debug_print(offset: u32, size: u32) -> ();
         pub fn msg_arg_data_copy(dst: u32, offset: u32, size: u32) -> ();
         pub fn msg_arg_data_size() -> u32;
@@ -174,6 +177,20 @@ pub fn status() -> u32 {
     unsafe { ic0::canister_status() }
 }
 
+pub fn canister_version() -> u64 {
+    unsafe { ic0::canister_version() }
+}
+
+/// Returns 1 if the given principal is a controller of the canister and 0
+/// otherwise.
+pub fn is_controller(principal: &[u8]) -> u32 {
+    unsafe { ic0::is_controller(principal.as_ptr() as u32, principal.len() as u32) }
+}
+
+pub fn in_replicated_execution() -> u32 {
+    unsafe { ic0::in_replicated_execution() }
+}
+
 /// Returns the size of the reject message in bytes.
 pub fn msg_reject_msg_size() -> u32 {
     unsafe { ic0::msg_reject_msg_size() }
@@ -361,6 +361,18 @@ fn eval(ops: Ops) {
                 stack.push_blob(api::msg_reject_msg_copy(offset, size));
             }
 
+            // is_controller
+            62 => {
+                let principal = stack.pop_blob();
+                stack.push_int(api::is_controller(&principal));
+            }
+
+            // canister_version
+            63 => stack.push_int64(api::canister_version()),
+
+            // in_replicated_execution
+            64 => stack.push_int(api::in_replicated_execution()),
+
             _ => api::trap_with(&format!("unknown op {}", op)),
         }
     }
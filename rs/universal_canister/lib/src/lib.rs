@@ -16,7 +16,7 @@ use hex_literal::hex;
 /// `rs/universal_canister`.
 pub const UNIVERSAL_CANISTER_WASM: &[u8] = include_bytes!("universal_canister.wasm");
 pub const UNIVERSAL_CANISTER_WASM_SHA256: [u8; 32] =
-    hex!("c54cce351c28b6334bfe6e88066128fc51cd374306be6bbbbf58849c3c70e2b5");
+    hex!("ad653ce4c4c4e0d06e38fa8401d5d481e21f2a102cd31a572d26be03d25af66a");
 
 /// Operands used in encoding UC payloads.
 enum Ops {
@@ -65,6 +65,9 @@ enum Ops {
     MsgCallerCopy = 59,
     MsgRejectMsgSize = 60,
     MsgRejectMsgCopy = 61,
+    IsController = 62,
+    CanisterVersion = 63,
+    InReplicatedExecution = 64,
 }
 
 /// A succinct shortcut for creating a `PayloadBuilder`, which is used to encode
@@ -439,6 +442,27 @@ impl PayloadBuilder {
         self
     }
 
+    /// Pushes 1 onto the stack if the given principal is a controller of the
+    /// canister and 0 otherwise.
+    pub fn is_controller(mut self, principal: &[u8]) -> Self {
+        self = self.push_bytes(principal);
+        self.0.push(Ops::IsController as u8);
+        self
+    }
+
+    /// Pushes the canister version onto the stack.
+    pub fn canister_version(mut self) -> Self {
+        self.0.push(Ops::CanisterVersion as u8);
+        self
+    }
+
+    /// Pushes 1 onto the stack if the message is executed in replicated mode
+    /// and 0 otherwise.
+    pub fn in_replicated_execution(mut self) -> Self {
+        self.0.push(Ops::InReplicatedExecution as u8);
+        self
+    }
+
     pub fn build(self) -> Vec<u8> {
         self.0
     }